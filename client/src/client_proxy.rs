@@ -929,11 +929,22 @@ impl ClientProxy {
         receiver: &AccountAddress,
         num_coins: u64,
         is_blocking: bool,
+    ) -> Result<()> {
+        let program = transaction_builder::encode_mint_script(&receiver, num_coins);
+        self.execute_script_with_local_faucet_account(program, is_blocking)
+    }
+
+    /// Submits the given script from the local faucet account, which is the association
+    /// account, e.g. to administer the validator set. If is_blocking = true, waits for the
+    /// transaction to be committed.
+    pub fn execute_script_with_local_faucet_account(
+        &mut self,
+        program: Script,
+        is_blocking: bool,
     ) -> Result<()> {
         ensure!(self.faucet_account.is_some(), "No faucet account loaded");
         let sender = self.faucet_account.as_ref().unwrap();
         let sender_address = sender.address;
-        let program = transaction_builder::encode_mint_script(&receiver, num_coins);
         let req = self.create_submit_transaction_req(
             TransactionPayload::Script(program),
             sender,
@@ -0,0 +1,52 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! A view of the internal state of consensus, served by the debug interface as JSON.
+
+use serde::{Deserialize, Serialize};
+
+/// A block of the in-memory block tree of consensus.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct BlockView {
+    /// The id of the block.
+    pub id: String,
+    /// The id of the parent block.
+    pub parent_id: String,
+    /// The round of the block.
+    pub round: u64,
+    /// The author of the block (None for the genesis and NIL blocks).
+    pub author: Option<String>,
+    /// True in case the block is certified by a QC.
+    pub certified: bool,
+}
+
+/// The last vote sent by the node.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct VoteView {
+    /// The id of the voted block.
+    pub block_id: String,
+    /// The round of the voted block.
+    pub round: u64,
+}
+
+/// The state of consensus at the time of the request.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ConsensusStateView {
+    /// The current epoch.
+    pub epoch: u64,
+    /// The current round of the pacemaker.
+    pub current_round: u64,
+    /// The deadline of the current round in milliseconds since the Unix epoch, None if no round
+    /// has started yet.
+    pub round_deadline_ms: Option<u64>,
+    /// The primary proposer of the current round.
+    pub proposer: Option<String>,
+    /// The round of the highest quorum certificate.
+    pub hqc_round: u64,
+    /// The root of the block tree, i.e., the latest committed block.
+    pub root: BlockView,
+    /// The blocks of the tree above the root, ordered by round.
+    pub pending_blocks: Vec<BlockView>,
+    /// The last vote sent by the node since it started.
+    pub last_vote: Option<VoteView>,
+}
@@ -0,0 +1,66 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! The health of the subsystems of a node, served by the debug interface as JSON.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// A subsystem of the node whose health is checked.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub enum Subsystem {
+    Mempool,
+    Consensus,
+    StateSync,
+    Storage,
+}
+
+impl fmt::Display for Subsystem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Subsystem::Mempool => "mempool",
+            Subsystem::Consensus => "consensus",
+            Subsystem::StateSync => "state sync",
+            Subsystem::Storage => "storage",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// The outcome of the health check of a subsystem.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct SubsystemHealth {
+    pub subsystem: Subsystem,
+    pub healthy: bool,
+    /// What was checked, e.g. the committed round and the time since it last advanced.
+    pub detail: String,
+}
+
+impl fmt::Display for SubsystemHealth {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} is {}: {}",
+            self.subsystem,
+            if self.healthy { "healthy" } else { "unhealthy" },
+            self.detail
+        )
+    }
+}
+
+/// The health of the subsystems running on the node at the time of the request.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct HealthReport {
+    pub subsystems: Vec<SubsystemHealth>,
+}
+
+impl HealthReport {
+    /// The subsystems that failed their health check.
+    pub fn unhealthy(&self) -> Vec<SubsystemHealth> {
+        self.subsystems
+            .iter()
+            .filter(|health| !health.healthy)
+            .cloned()
+            .collect()
+    }
+}
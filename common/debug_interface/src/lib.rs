@@ -1,17 +1,29 @@
 // Copyright (c) The Libra Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::proto::{
-    node_debug_interface::GetNodeDetailsRequest,
-    node_debug_interface_grpc::NodeDebugInterfaceClient,
+use crate::{
+    consensus_state::ConsensusStateView,
+    health::HealthReport,
+    proto::{
+        node_debug_interface::{
+            GetConsensusStateRequest, GetConsensusStateResponse, GetHealthRequest,
+            GetHealthResponse, GetLatestCommitCertificateRequest,
+            GetLatestCommitCertificateResponse, GetNodeDetailsRequest, GetNodeDetailsResponse,
+        },
+        node_debug_interface_grpc::NodeDebugInterfaceClient,
+    },
 };
 use failure::prelude::*;
-use grpcio::{ChannelBuilder, EnvBuilder};
-use std::{collections::HashMap, sync::Arc};
+use futures::{future, Future};
+use grpcio::{CallOption, ChannelBuilder, EnvBuilder, Environment};
+use lazy_static::lazy_static;
+use std::{collections::HashMap, sync::Arc, time::Duration};
 
 // Generated
 pub mod proto;
 
+pub mod consensus_state;
+pub mod health;
 pub mod node_debug_helpers;
 pub mod node_debug_service;
 #[macro_use]
@@ -47,17 +59,160 @@ impl NodeDebugClient {
             .get_node_details(&GetNodeDetailsRequest::new())
             .context("Unable to query Node metrics")?;
 
-        response
-            .stats
-            .into_iter()
-            .map(|(k, v)| match v.parse::<i64>() {
-                Ok(v) => Ok((k, v)),
-                Err(_) => Err(format_err!(
-                    "Failed to parse stat value to i64 {}: {}",
-                    &k,
-                    &v
-                )),
-            })
-            .collect()
+        parse_node_metrics(response)
     }
+
+    /// The serialized `types.LedgerInfoWithSignatures` proto of the latest block committed by
+    /// the node, if any.
+    pub fn get_latest_commit_certificate(&self) -> Result<Option<Vec<u8>>> {
+        let response = self
+            .client
+            .get_latest_commit_certificate(&GetLatestCommitCertificateRequest::new())
+            .context("Unable to query the latest commit certificate")?;
+        Ok(parse_commit_certificate(response))
+    }
+
+    /// The internal state of consensus, None if consensus doesn't run on the node.
+    pub fn get_consensus_state(&self) -> Result<Option<ConsensusStateView>> {
+        let response = self
+            .client
+            .get_consensus_state(&GetConsensusStateRequest::new())
+            .context("Unable to query the consensus state")?;
+        parse_consensus_state(response)
+    }
+
+    /// The health of the subsystems of the node, None if the node doesn't report it.
+    pub fn get_health(&self) -> Result<Option<HealthReport>> {
+        let response = self
+            .client
+            .get_health(&GetHealthRequest::new())
+            .context("Unable to query the health of the node")?;
+        parse_health(response)
+    }
+}
+
+lazy_static! {
+    // The gRPC environment (completion queue threads) shared by all the async clients.
+    static ref ASYNC_CLIENT_ENV: Arc<Environment> =
+        Arc::new(EnvBuilder::new().name_prefix("grpc-debug-async-").build());
+}
+
+/// Asynchronous version of `NodeDebugClient`: the requests return futures instead of blocking
+/// the calling thread, such that many nodes can be polled concurrently from a single thread.
+/// All the clients share the same gRPC environment and every client reuses a single channel
+/// (connection) to its node for all its requests.
+#[derive(Clone)]
+pub struct AsyncNodeDebugClient {
+    client: NodeDebugInterfaceClient,
+    timeout: Duration,
+}
+
+impl AsyncNodeDebugClient {
+    /// Every request fails if it is not completed within the given timeout.
+    pub fn new<A: AsRef<str>>(address: A, port: u16, timeout: Duration) -> Self {
+        let ch = ChannelBuilder::new(Arc::clone(&ASYNC_CLIENT_ENV))
+            .connect(&format!("{}:{}", address.as_ref(), port));
+        Self {
+            client: NodeDebugInterfaceClient::new(ch),
+            timeout,
+        }
+    }
+
+    pub fn get_node_metric<S: AsRef<str>>(
+        &self,
+        metric: S,
+    ) -> impl Future<Item = Option<i64>, Error = Error> {
+        let metric = metric.as_ref().to_string();
+        self.get_node_metrics()
+            .map(move |metrics| metrics.get(&metric).cloned())
+    }
+
+    pub fn get_node_metrics(&self) -> impl Future<Item = HashMap<String, i64>, Error = Error> {
+        let call_option = CallOption::default().timeout(self.timeout);
+        future::result(
+            self.client
+                .get_node_details_async_opt(&GetNodeDetailsRequest::new(), call_option),
+        )
+        .flatten()
+        .map_err(|e| format_err!("Unable to query Node metrics: {:?}", e))
+        .and_then(parse_node_metrics)
+    }
+
+    pub fn get_latest_commit_certificate(
+        &self,
+    ) -> impl Future<Item = Option<Vec<u8>>, Error = Error> {
+        let call_option = CallOption::default().timeout(self.timeout);
+        future::result(self.client.get_latest_commit_certificate_async_opt(
+            &GetLatestCommitCertificateRequest::new(),
+            call_option,
+        ))
+        .flatten()
+        .map_err(|e| format_err!("Unable to query the latest commit certificate: {:?}", e))
+        .map(parse_commit_certificate)
+    }
+
+    pub fn get_consensus_state(
+        &self,
+    ) -> impl Future<Item = Option<ConsensusStateView>, Error = Error> {
+        let call_option = CallOption::default().timeout(self.timeout);
+        future::result(
+            self.client
+                .get_consensus_state_async_opt(&GetConsensusStateRequest::new(), call_option),
+        )
+        .flatten()
+        .map_err(|e| format_err!("Unable to query the consensus state: {:?}", e))
+        .and_then(parse_consensus_state)
+    }
+
+    pub fn get_health(&self) -> impl Future<Item = Option<HealthReport>, Error = Error> {
+        let call_option = CallOption::default().timeout(self.timeout);
+        future::result(self.client.get_health_async_opt(&GetHealthRequest::new(), call_option))
+            .flatten()
+            .map_err(|e| format_err!("Unable to query the health of the node: {:?}", e))
+            .and_then(parse_health)
+    }
+}
+
+fn parse_commit_certificate(mut response: GetLatestCommitCertificateResponse) -> Option<Vec<u8>> {
+    let bytes = response.take_ledger_info_with_signatures();
+    if bytes.is_empty() {
+        None
+    } else {
+        Some(bytes)
+    }
+}
+
+fn parse_consensus_state(
+    response: GetConsensusStateResponse,
+) -> Result<Option<ConsensusStateView>> {
+    if response.json.is_empty() {
+        return Ok(None);
+    }
+    let state = serde_json::from_str(&response.json)
+        .with_context(|_| format!("Failed to parse the consensus state {}", response.json))?;
+    Ok(Some(state))
+}
+
+fn parse_health(response: GetHealthResponse) -> Result<Option<HealthReport>> {
+    if response.json.is_empty() {
+        return Ok(None);
+    }
+    let report = serde_json::from_str(&response.json)
+        .with_context(|_| format!("Failed to parse the health report {}", response.json))?;
+    Ok(Some(report))
+}
+
+fn parse_node_metrics(response: GetNodeDetailsResponse) -> Result<HashMap<String, i64>> {
+    response
+        .stats
+        .into_iter()
+        .map(|(k, v)| match v.parse::<i64>() {
+            Ok(v) => Ok((k, v)),
+            Err(_) => Err(format_err!(
+                "Failed to parse stat value to i64 {}: {}",
+                &k,
+                &v
+            )),
+        })
+        .collect()
 }
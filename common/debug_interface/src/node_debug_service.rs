@@ -4,11 +4,15 @@
 //! Debug interface to access information in a specific node.
 
 use crate::{
+    consensus_state::ConsensusStateView,
+    health::{HealthReport, SubsystemHealth},
     json_log,
     proto::{
         node_debug_interface::{
-            Event, GetEventsRequest, GetEventsResponse, GetNodeDetailsRequest,
-            GetNodeDetailsResponse,
+            Event, GetConsensusStateRequest, GetConsensusStateResponse, GetEventsRequest,
+            GetEventsResponse, GetHealthRequest, GetHealthResponse,
+            GetLatestCommitCertificateRequest, GetLatestCommitCertificateResponse,
+            GetNodeDetailsRequest, GetNodeDetailsResponse,
         },
         node_debug_interface_grpc::NodeDebugInterface,
     },
@@ -16,14 +20,52 @@ use crate::{
 use futures::Future;
 use logger::prelude::*;
 use metrics::counters::COUNTER_ADMISSION_CONTROL_CANNOT_SEND_REPLY;
+use std::sync::{Arc, RwLock};
 
+/// Provides the commit certificate of the latest block committed by the node (e.g.,
+/// implemented by consensus).
+pub trait CommitCertificateProvider: Send + Sync {
+    /// The serialized `types.LedgerInfoWithSignatures` proto, None if no block has been
+    /// committed yet.
+    fn latest_commit_certificate(&self) -> Option<Vec<u8>>;
+}
+
+/// Provides a view of the internal state of consensus.
+pub trait ConsensusStateProvider: Send + Sync {
+    fn consensus_state(&self) -> ConsensusStateView;
+}
+
+/// Checks the health of a subsystem of the node, on every health request.
+pub trait SubsystemHealthCheck: Send + Sync {
+    fn check(&self) -> SubsystemHealth;
+}
+
+/// The clones of the service share the same providers, such that the providers can be set after
+/// the service has been registered with the server.
 #[derive(Clone, Default)]
-pub struct NodeDebugService {}
+pub struct NodeDebugService {
+    commit_certificate_provider: Arc<RwLock<Option<Arc<dyn CommitCertificateProvider>>>>,
+    consensus_state_provider: Arc<RwLock<Option<Arc<dyn ConsensusStateProvider>>>>,
+    health_checks: Arc<RwLock<Vec<Arc<dyn SubsystemHealthCheck>>>>,
+}
 
 impl NodeDebugService {
     pub fn new() -> Self {
         Default::default()
     }
+
+    pub fn set_commit_certificate_provider(&self, provider: Arc<dyn CommitCertificateProvider>) {
+        *self.commit_certificate_provider.write().unwrap() = Some(provider);
+    }
+
+    pub fn set_consensus_state_provider(&self, provider: Arc<dyn ConsensusStateProvider>) {
+        *self.consensus_state_provider.write().unwrap() = Some(provider);
+    }
+
+    /// Adds the health check of a subsystem to the health report, in the order they're added.
+    pub fn add_health_check(&self, check: Arc<dyn SubsystemHealthCheck>) {
+        self.health_checks.write().unwrap().push(check);
+    }
 }
 
 impl NodeDebugInterface for NodeDebugService {
@@ -57,6 +99,53 @@ impl NodeDebugInterface for NodeDebugService {
         }
         ctx.spawn(sink.success(response).map_err(default_reply_error_logger))
     }
+
+    fn get_latest_commit_certificate(
+        &mut self,
+        ctx: ::grpcio::RpcContext<'_>,
+        _req: GetLatestCommitCertificateRequest,
+        sink: ::grpcio::UnarySink<GetLatestCommitCertificateResponse>,
+    ) {
+        let mut response = GetLatestCommitCertificateResponse::new();
+        let provider = self.commit_certificate_provider.read().unwrap().clone();
+        if let Some(bytes) = provider.and_then(|p| p.latest_commit_certificate()) {
+            response.set_ledger_info_with_signatures(bytes);
+        }
+        ctx.spawn(sink.success(response).map_err(default_reply_error_logger))
+    }
+
+    fn get_consensus_state(
+        &mut self,
+        ctx: ::grpcio::RpcContext<'_>,
+        _req: GetConsensusStateRequest,
+        sink: ::grpcio::UnarySink<GetConsensusStateResponse>,
+    ) {
+        let mut response = GetConsensusStateResponse::new();
+        let provider = self.consensus_state_provider.read().unwrap().clone();
+        if let Some(provider) = provider {
+            let json = serde_json::to_string(&provider.consensus_state())
+                .expect("Failed to serialize the consensus state to json");
+            response.set_json(json);
+        }
+        ctx.spawn(sink.success(response).map_err(default_reply_error_logger))
+    }
+
+    fn get_health(
+        &mut self,
+        ctx: ::grpcio::RpcContext<'_>,
+        _req: GetHealthRequest,
+        sink: ::grpcio::UnarySink<GetHealthResponse>,
+    ) {
+        let checks = self.health_checks.read().unwrap().clone();
+        let report = HealthReport {
+            subsystems: checks.iter().map(|check| check.check()).collect(),
+        };
+        let mut response = GetHealthResponse::new();
+        response.set_json(
+            serde_json::to_string(&report).expect("Failed to serialize the health report to json"),
+        );
+        ctx.spawn(sink.success(response).map_err(default_reply_error_logger))
+    }
 }
 
 fn default_reply_error_logger<T: ::std::fmt::Debug>(e: T) {
@@ -0,0 +1,130 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Provides a slog drain writing every record as a JSON object on its own line, such that the
+//! logs can be indexed and queried by tools (e.g. the swarm log collector).
+
+use crate::{collector_serializer::CollectorSerializer, kv_categorizer::KVCategorizer};
+use chrono;
+use serde::Serialize;
+use slog::{Drain, OwnedKVList, Record, KV};
+use std::{
+    collections::BTreeMap,
+    io::{self, Write},
+    sync::Mutex,
+};
+use thread_id;
+
+/// A line of a JSON-formatted log.
+#[derive(Serialize)]
+struct JsonLine {
+    /// Microseconds since the Unix epoch
+    timestamp_us: i64,
+    /// The same time in RFC 3339 format
+    time: String,
+    level: &'static str,
+    module: &'static str,
+    file: &'static str,
+    line: u32,
+    thread: usize,
+    message: String,
+    /// The KV values of the record and of its logger
+    fields: BTreeMap<&'static str, String>,
+}
+
+/// A slog `Drain` for JSON-formatted logs.
+pub struct JsonFormat<W: io::Write, C: KVCategorizer> {
+    writer: Mutex<W>,
+    categorizer: C,
+}
+
+impl<W: io::Write, C: KVCategorizer> JsonFormat<W, C> {
+    /// Create a JSON-formatted `Drain` writing to the provided writer, the KV values are named
+    /// by the provided `Categorizer`
+    pub fn new(writer: W, categorizer: C) -> JsonFormat<W, C> {
+        JsonFormat {
+            writer: Mutex::new(writer),
+            categorizer,
+        }
+    }
+}
+
+impl<W: io::Write, C: KVCategorizer> Drain for JsonFormat<W, C> {
+    type Ok = ();
+    type Err = io::Error;
+
+    fn log(&self, record: &Record<'_>, values: &OwnedKVList) -> io::Result<Self::Ok> {
+        let mut serializer = CollectorSerializer::new(&self.categorizer);
+        values.serialize(record, &mut serializer)?;
+        record.kv().serialize(record, &mut serializer)?;
+        let fields = serializer
+            .into_inner()
+            .into_iter()
+            .map(|(k, v)| (self.categorizer.name(k), v))
+            .collect();
+
+        let now = chrono::Utc::now();
+        let line = JsonLine {
+            timestamp_us: now.timestamp() * 1_000_000 + i64::from(now.timestamp_subsec_micros()),
+            time: now.to_rfc3339(),
+            level: record.level().as_str(),
+            module: record.module(),
+            file: record.file(),
+            line: record.line(),
+            thread: thread_id::get(),
+            message: format!("{}", record.msg()),
+            fields,
+        };
+        let mut writer = self.writer.lock().unwrap();
+        serde_json::to_writer(&mut *writer, &line)?;
+        writeln!(writer)?;
+        writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::JsonFormat;
+    use crate::kv_categorizer::InlineCategorizer;
+    use serde_json::Value;
+    use slog::{info, o, Drain, Logger};
+    use std::{
+        io,
+        sync::{Arc, Mutex},
+    };
+
+    #[derive(Clone)]
+    struct TestBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl io::Write for TestBuffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_json_line() {
+        let buffer = TestBuffer(Arc::new(Mutex::new(vec![])));
+        let drain = JsonFormat::new(buffer.clone(), InlineCategorizer).fuse();
+        let log = Logger::root(drain, o!("mode" => "test"));
+
+        let line = line!() + 1;
+        info!(log, "Test log {}", 1; "tau" => 6.28);
+
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        assert_eq!(output.lines().count(), 1);
+        let json: Value = serde_json::from_str(output.trim_end()).unwrap();
+        assert_eq!(json["level"], "INFO");
+        assert_eq!(json["message"], "Test log 1");
+        assert_eq!(json["module"], module_path!());
+        assert_eq!(json["file"], file!());
+        assert_eq!(json["line"], line);
+        assert_eq!(json["fields"]["mode"], "test");
+        assert_eq!(json["fields"]["tau"], "6.28");
+        assert!(json["timestamp_us"].as_i64().unwrap() > 0);
+    }
+}
@@ -26,6 +26,7 @@ mod collector_serializer;
 mod glog_format;
 mod http_local_slog_drain;
 mod http_log_client;
+mod json_format;
 mod kv_categorizer;
 mod security;
 mod simple_logger;
@@ -37,6 +38,7 @@ use crate::{
 use arc_swap::ArcSwap;
 use failure::prelude::*;
 use glog_format::GlogFormat;
+use json_format::JsonFormat;
 use lazy_static::lazy_static;
 use slog::{o, Discard, Drain, FilterLevel, Logger, Never};
 pub use slog::{slog_crit, slog_debug, slog_error, slog_info, slog_trace, slog_warn};
@@ -67,8 +69,22 @@ pub fn set_default_global_logger(async_drain: bool, chan_size: Option<usize>) ->
     set_global_logger(logger)
 }
 
+/// The environment variable selecting the format of the default logger: the logs are written as
+/// one JSON object per line if it is set to `json`, in glog format otherwise.
+pub const LOG_FORMAT_ENV_VAR: &str = "LIBRA_LOG_FORMAT";
+
 /// Creates a root logger with default settings.
 fn create_default_root_logger(async_drain: bool, chan_size: Option<usize>) -> Logger {
+    if ::std::env::var(LOG_FORMAT_ENV_VAR)
+        .ok()
+        .as_ref()
+        .map(String::as_str)
+        == Some("json")
+    {
+        let drain = JsonFormat::new(::std::io::stderr(), ErrorCategorizer).fuse();
+        let logger = create_env_logger(drain);
+        return get_logger(async_drain, chan_size, logger);
+    }
     let drain = GlogFormat::new(PlainDecorator::new(::std::io::stderr()), ErrorCategorizer).fuse();
     let logger = create_env_logger(drain);
     get_logger(async_drain, chan_size, logger)
@@ -39,6 +39,9 @@ pub enum SecurityEvent {
     /// Consensus received an invalid sync info message
     InvalidSyncInfoMsg,
 
+    /// Consensus received two conflicting messages signed by the same author for the same round
+    ConsensusEquivocation,
+
     /// A block being committed or executed is invalid
     InvalidBlock,
 
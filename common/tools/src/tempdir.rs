@@ -36,6 +36,13 @@ impl TempPath {
         &self.0
     }
 
+    /// Consume the wrapper without deleting the underlying path, which is returned.
+    pub fn persist(self) -> PathBuf {
+        let path = self.0.clone();
+        std::mem::forget(self);
+        path
+    }
+
     pub fn create_as_file(&self) -> io::Result<()> {
         let mut builder = fs::OpenOptions::new();
         builder.write(true).create_new(true);
@@ -0,0 +1,177 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! A cache of the genesis of the swarms, reused across launches.
+//!
+//! Encoding the genesis transaction runs the VM and dominates the time spent building the
+//! configs of a swarm. A cached genesis is keyed by the number of validators, the template config,
+//! the faucet public key and the binary building it (the genesis changes with the code). The
+//! validator keys are generated from a seed derived from the key, such that they match the cached
+//! genesis. The cache also holds a faucet keypair to launch with, since a new faucet key is a new
+//! genesis.
+//!
+//! No waypoint is built in this tree, the genesis blob and the keys are all there is to cache.
+
+use crypto::{ed25519::*, test_utils::KeyPair, HashValue};
+use failure::prelude::*;
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+/// The number of cached geneses kept, the oldest ones are evicted first.
+const MAX_CACHED_GENESES: usize = 32;
+const GENESIS_SUFFIX: &str = ".genesis.blob";
+const FAUCET_KEY_FILE: &str = "faucet_keys";
+
+/// The key of a cached genesis.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct GenesisCacheKey(HashValue);
+
+impl GenesisCacheKey {
+    pub fn new(
+        num_nodes: usize,
+        template: &[u8],
+        faucet_public_key: &Ed25519PublicKey,
+        key_seed: Option<[u8; 32]>,
+    ) -> Self {
+        let mut buffer = vec![];
+        buffer.extend_from_slice(&(num_nodes as u64).to_le_bytes());
+        buffer.extend_from_slice(HashValue::from_sha3_256(template).as_ref());
+        buffer.extend_from_slice(&faucet_public_key.to_bytes());
+        // An explicit seed is part of the key, the seed is derived from the key otherwise.
+        if let Some(seed) = key_seed {
+            buffer.extend_from_slice(&seed);
+        }
+        buffer.extend_from_slice(&binary_stamp());
+        GenesisCacheKey(HashValue::from_sha3_256(&buffer))
+    }
+
+    /// The seed the validator keys of the cached genesis are generated from.
+    pub fn key_seed(&self) -> [u8; 32] {
+        *self.0.as_ref()
+    }
+
+    fn file_name(&self) -> String {
+        format!("{}{}", hex::encode(self.0.to_vec()), GENESIS_SUFFIX)
+    }
+}
+
+// The size and modification time of the running binary, a rebuild invalidates the cache.
+fn binary_stamp() -> Vec<u8> {
+    let mut stamp = vec![];
+    if let Some(metadata) = env::current_exe()
+        .ok()
+        .and_then(|exe| fs::metadata(exe).ok())
+    {
+        stamp.extend_from_slice(&metadata.len().to_le_bytes());
+        if let Some(modified) = metadata
+            .modified()
+            .ok()
+            .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        {
+            stamp.extend_from_slice(&modified.as_nanos().to_le_bytes());
+        }
+    }
+    stamp
+}
+
+/// A directory of cached geneses, safe to share between concurrent launches.
+#[derive(Clone, Debug)]
+pub struct GenesisCache {
+    dir: PathBuf,
+}
+
+impl GenesisCache {
+    pub fn new<P: AsRef<Path>>(dir: P) -> Self {
+        Self {
+            dir: dir.as_ref().to_path_buf(),
+        }
+    }
+
+    /// The cache shared by the swarms of the machine, in the temporary directory.
+    pub fn default_dir() -> PathBuf {
+        env::temp_dir().join("libra_genesis_cache")
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Copies the cached genesis of the given key to the given path, returns false if none is
+    /// cached.
+    pub fn load(&self, key: &GenesisCacheKey, genesis_path: &Path) -> Result<bool> {
+        let cached = self.dir.join(key.file_name());
+        if !cached.is_file() {
+            return Ok(false);
+        }
+        fs::copy(&cached, genesis_path)
+            .with_context(|_| format!("Failed to copy the cached genesis {:?}", cached))?;
+        Ok(true)
+    }
+
+    /// Caches the genesis at the given path under the given key.
+    pub fn store(&self, key: &GenesisCacheKey, genesis_path: &Path) -> Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        // The genesis is renamed into place, a concurrent launch never reads a partial one.
+        let cached = self.dir.join(key.file_name());
+        let partial = cached.with_extension(format!("partial.{}", std::process::id()));
+        fs::copy(genesis_path, &partial)?;
+        fs::rename(&partial, &cached)?;
+        self.evict()
+    }
+
+    // Removes the oldest geneses beyond the max number of cached geneses.
+    fn evict(&self) -> Result<()> {
+        let mut geneses = vec![];
+        for entry in fs::read_dir(&self.dir)? {
+            let entry = entry?;
+            if entry
+                .file_name()
+                .to_string_lossy()
+                .ends_with(GENESIS_SUFFIX)
+            {
+                geneses.push((entry.metadata()?.modified()?, entry.path()));
+            }
+        }
+        if geneses.len() > MAX_CACHED_GENESES {
+            geneses.sort();
+            for (_, path) in &geneses[..geneses.len() - MAX_CACHED_GENESES] {
+                // Another launch may have evicted it already.
+                let _ = fs::remove_file(path);
+            }
+        }
+        Ok(())
+    }
+
+    /// The faucet keypair of the cache and the file holding it, generated on first use.
+    pub fn faucet_keypair(
+        &self,
+    ) -> Result<(KeyPair<Ed25519PrivateKey, Ed25519PublicKey>, PathBuf)> {
+        let path = self.dir.join(FAUCET_KEY_FILE);
+        if let Ok(keypair) = generate_keypair::load_key_from_file(&path) {
+            return Ok((keypair, path));
+        }
+        fs::create_dir_all(&self.dir)?;
+        let partial = path.with_extension(format!("partial.{}", std::process::id()));
+        generate_keypair::create_faucet_key_file(
+            partial
+                .to_str()
+                .ok_or_else(|| format_err!("Invalid cache dir {:?}", self.dir))?,
+        );
+        // The link fails if the file exists: the first of concurrent launches wins, all of them
+        // use the key it wrote.
+        let _ = fs::hard_link(&partial, &path);
+        fs::remove_file(&partial)?;
+        Ok((generate_keypair::load_key_from_file(&path)?, path))
+    }
+
+    /// Removes all the cached geneses and the faucet keypair.
+    pub fn clear(&self) -> Result<()> {
+        if self.dir.exists() {
+            fs::remove_dir_all(&self.dir)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,258 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Building the genesis in stages across multiple operators.
+//!
+//! 1. Every operator generates its validator keys locally and publishes a
+//!    `ValidatorContribution` holding the public keys only.
+//! 2. A coordinator collects the contributions in a `GenesisCeremony`, assembles the peers
+//!    configs and signs the genesis transaction with the association key.
+//! 3. Every operator rebuilds the ceremony from the published contributions and verifies the
+//!    genesis blob before starting its node.
+//!
+//! No party ever has access to the private keys of the other validators.
+
+use config::trusted_peers::{
+    deserialize_key, serialize_key, ConsensusPeerInfo, ConsensusPeersConfig, NetworkPeerInfo,
+    NetworkPeerPrivateKeys, NetworkPeersConfig,
+};
+use crypto::{
+    ed25519::{compat, *},
+    test_utils::KeyPair,
+    x25519::{self, X25519StaticPublicKey},
+};
+use failure::prelude::*;
+use proto_conv::{FromProtoBytes, IntoProtoBytes};
+use rand::rngs::StdRng;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fs::File,
+    io::prelude::*,
+    path::Path,
+    str::FromStr,
+};
+use types::{
+    account_address::AccountAddress, transaction::SignedTransaction, validator_set::ValidatorSet,
+    validator_verifier::DEFAULT_VOTING_POWER,
+};
+use vm_genesis::{encode_genesis_raw_transaction, encode_genesis_transaction_with_validator};
+
+#[cfg(test)]
+#[path = "genesis_ceremony_test.rs"]
+mod genesis_ceremony_test;
+
+/// The public part of the config of a validator, contributed by its operator to the genesis.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ValidatorContribution {
+    pub peer_id: String,
+    #[serde(serialize_with = "serialize_key")]
+    #[serde(deserialize_with = "deserialize_key")]
+    pub consensus_pubkey: Ed25519PublicKey,
+    #[serde(serialize_with = "serialize_key")]
+    #[serde(deserialize_with = "deserialize_key")]
+    pub network_signing_pubkey: Ed25519PublicKey,
+    #[serde(serialize_with = "serialize_key")]
+    #[serde(deserialize_with = "deserialize_key")]
+    pub network_identity_pubkey: X25519StaticPublicKey,
+}
+
+/// The private keys an operator generates for its validator, they never leave the operator.
+pub struct ValidatorPrivateKeys {
+    pub consensus_private_key: Ed25519PrivateKey,
+    pub network_private_keys: NetworkPeerPrivateKeys,
+}
+
+impl ValidatorContribution {
+    /// Generates the keys of a new validator. The peer id is derived from the consensus public
+    /// key, as for the validators generated by `ConfigHelpers`.
+    pub fn generate(rng: &mut StdRng) -> (Self, ValidatorPrivateKeys) {
+        let (consensus_private_key, consensus_pubkey) = compat::generate_keypair(&mut *rng);
+        let (network_signing_private_key, network_signing_pubkey) =
+            compat::generate_keypair(&mut *rng);
+        let (network_identity_private_key, network_identity_pubkey) =
+            x25519::compat::generate_keypair(&mut *rng);
+        let contribution = Self {
+            peer_id: AccountAddress::from_public_key(&consensus_pubkey).to_string(),
+            consensus_pubkey,
+            network_signing_pubkey,
+            network_identity_pubkey,
+        };
+        let private_keys = ValidatorPrivateKeys {
+            consensus_private_key,
+            network_private_keys: NetworkPeerPrivateKeys {
+                network_signing_private_key,
+                network_identity_private_key,
+            },
+        };
+        (contribution, private_keys)
+    }
+
+    fn verify(&self) -> Result<()> {
+        let peer_id = AccountAddress::from_str(&self.peer_id)
+            .map_err(|e| format_err!("Invalid peer id {}: {}", self.peer_id, e))?;
+        ensure!(
+            peer_id == AccountAddress::from_public_key(&self.consensus_pubkey),
+            "Peer id {} doesn't match the consensus public key",
+            self.peer_id
+        );
+        Ok(())
+    }
+}
+
+/// Collects the contributions of all the operators and assembles (or verifies) the genesis.
+#[derive(Default)]
+pub struct GenesisCeremony {
+    // Ordered by peer id, such that every party builds the same genesis.
+    contributions: BTreeMap<String, ValidatorContribution>,
+    // The peer ids of the validators expected to contribute, None if any validator can.
+    participants: Option<BTreeSet<String>>,
+}
+
+impl GenesisCeremony {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A ceremony among the given validators only: the genesis is neither assembled nor verified
+    /// until every one of them has contributed.
+    pub fn with_participants<I: IntoIterator<Item = String>>(participants: I) -> Self {
+        Self {
+            contributions: BTreeMap::new(),
+            participants: Some(participants.into_iter().collect()),
+        }
+    }
+
+    /// Adds the contribution of an operator, every validator can contribute only once.
+    pub fn add_contribution(&mut self, contribution: ValidatorContribution) -> Result<()> {
+        contribution.verify()?;
+        if let Some(participants) = &self.participants {
+            ensure!(
+                participants.contains(&contribution.peer_id),
+                "Validator {} is not a participant of the ceremony",
+                contribution.peer_id
+            );
+        }
+        ensure!(
+            !self.contributions.contains_key(&contribution.peer_id),
+            "Duplicate contribution for validator {}",
+            contribution.peer_id
+        );
+        self.contributions
+            .insert(contribution.peer_id.clone(), contribution);
+        Ok(())
+    }
+
+    pub fn contributions(&self) -> impl Iterator<Item = &ValidatorContribution> {
+        self.contributions.values()
+    }
+
+    pub fn consensus_peers_config(&self) -> ConsensusPeersConfig {
+        let peers: HashMap<_, _> = self
+            .contributions()
+            .map(|c| {
+                (
+                    c.peer_id.clone(),
+                    ConsensusPeerInfo {
+                        consensus_pubkey: c.consensus_pubkey.clone(),
+                        voting_power: DEFAULT_VOTING_POWER,
+                    },
+                )
+            })
+            .collect();
+        ConsensusPeersConfig { peers }
+    }
+
+    pub fn network_peers_config(&self) -> NetworkPeersConfig {
+        let peers: HashMap<_, _> = self
+            .contributions()
+            .map(|c| {
+                (
+                    c.peer_id.clone(),
+                    NetworkPeerInfo {
+                        network_signing_pubkey: c.network_signing_pubkey.clone(),
+                        network_identity_pubkey: c.network_identity_pubkey.clone(),
+                    },
+                )
+            })
+            .collect();
+        NetworkPeersConfig { peers }
+    }
+
+    fn validator_set(&self) -> ValidatorSet {
+        self.consensus_peers_config()
+            .get_validator_set(&self.network_peers_config())
+    }
+
+    // Fails unless every participant has contributed.
+    fn ensure_complete(&self) -> Result<()> {
+        ensure!(
+            !self.contributions.is_empty(),
+            "Can't assemble a genesis without validators"
+        );
+        if let Some(participants) = &self.participants {
+            let missing: Vec<_> = participants
+                .iter()
+                .filter(|peer_id| !self.contributions.contains_key(*peer_id))
+                .collect();
+            ensure!(
+                missing.is_empty(),
+                "Missing the contributions of the validators {:?}",
+                missing
+            );
+        }
+        Ok(())
+    }
+
+    /// Assembles the genesis blob signed by the association (faucet) key. Only the coordinator
+    /// holds this key.
+    pub fn assemble(
+        &self,
+        faucet_account_keypair: &KeyPair<Ed25519PrivateKey, Ed25519PublicKey>,
+    ) -> Result<Vec<u8>> {
+        self.ensure_complete()?;
+        encode_genesis_transaction_with_validator(
+            &faucet_account_keypair.private_key,
+            faucet_account_keypair.public_key.clone(),
+            self.validator_set(),
+        )
+        .into_proto_bytes()
+    }
+
+    /// Verifies that the genesis blob assembled by the coordinator is signed by the association
+    /// key and contains exactly the validators of this ceremony, including the one of the
+    /// verifying operator.
+    pub fn verify(
+        &self,
+        genesis_blob: &[u8],
+        faucet_public_key: &Ed25519PublicKey,
+        own_contribution: &ValidatorContribution,
+    ) -> Result<()> {
+        self.ensure_complete()?;
+        ensure!(
+            self.contributions
+                .get(&own_contribution.peer_id)
+                .map_or(false, |c| c == own_contribution),
+            "Validator {} is not part of the genesis",
+            own_contribution.peer_id
+        );
+        let transaction = SignedTransaction::from_proto_bytes(genesis_blob)?.check_signature()?;
+        ensure!(
+            transaction.public_key() == *faucet_public_key,
+            "Genesis is not signed by the association key"
+        );
+        ensure!(
+            transaction.into_raw_transaction()
+                == encode_genesis_raw_transaction(faucet_public_key, self.validator_set()),
+            "Genesis doesn't match the contributed validator set"
+        );
+        Ok(())
+    }
+}
+
+/// Writes the genesis blob to the given location.
+pub fn save_genesis_blob<P: AsRef<Path>>(path: P, genesis_blob: &[u8]) -> Result<()> {
+    let mut file = File::create(path)?;
+    file.write_all(genesis_blob)?;
+    Ok(())
+}
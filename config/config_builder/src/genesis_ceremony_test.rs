@@ -0,0 +1,154 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::genesis_ceremony::{GenesisCeremony, ValidatorContribution};
+use crypto::{
+    ed25519::{compat, *},
+    test_utils::KeyPair,
+};
+use rand::{rngs::StdRng, SeedableRng};
+
+fn contributions(rng: &mut StdRng, num_validators: usize) -> Vec<ValidatorContribution> {
+    (0..num_validators)
+        .map(|_| ValidatorContribution::generate(rng).0)
+        .collect()
+}
+
+fn faucet_keypair(rng: &mut StdRng) -> KeyPair<Ed25519PrivateKey, Ed25519PublicKey> {
+    let (private_key, _) = compat::generate_keypair(&mut *rng);
+    KeyPair::from(private_key)
+}
+
+fn ceremony_of(contributions: &[ValidatorContribution]) -> GenesisCeremony {
+    let mut ceremony =
+        GenesisCeremony::with_participants(contributions.iter().map(|c| c.peer_id.clone()));
+    for contribution in contributions {
+        ceremony.add_contribution(contribution.clone()).unwrap();
+    }
+    ceremony
+}
+
+#[test]
+fn test_assemble_and_verify() {
+    let mut rng = StdRng::from_seed([0u8; 32]);
+    let contributions = contributions(&mut rng, 3);
+    let faucet = faucet_keypair(&mut rng);
+    let ceremony = ceremony_of(&contributions);
+    let genesis_blob = ceremony.assemble(&faucet).unwrap();
+    for contribution in &contributions {
+        ceremony
+            .verify(&genesis_blob, &faucet.public_key, contribution)
+            .unwrap();
+    }
+}
+
+#[test]
+fn test_duplicate_contribution() {
+    let mut rng = StdRng::from_seed([1u8; 32]);
+    let contributions = contributions(&mut rng, 2);
+    let mut ceremony = GenesisCeremony::new();
+    ceremony.add_contribution(contributions[0].clone()).unwrap();
+    assert!(ceremony.add_contribution(contributions[0].clone()).is_err());
+
+    // The second contribution of a validator can't replace its keys either.
+    let mut replaced = contributions[0].clone();
+    replaced.network_signing_pubkey = contributions[1].network_signing_pubkey.clone();
+    assert!(ceremony.add_contribution(replaced).is_err());
+    assert_eq!(
+        ceremony.contributions().collect::<Vec<_>>(),
+        vec![&contributions[0]]
+    );
+}
+
+#[test]
+fn test_contribution_of_another_peer_id() {
+    let mut rng = StdRng::from_seed([2u8; 32]);
+    let contributions = contributions(&mut rng, 2);
+    let mut ceremony = GenesisCeremony::new();
+    // The peer id is derived from the consensus key, it can't be claimed with another key.
+    let mut forged = contributions[0].clone();
+    forged.peer_id = contributions[1].peer_id.clone();
+    assert!(ceremony.add_contribution(forged).is_err());
+    assert_eq!(ceremony.contributions().count(), 0);
+}
+
+#[test]
+fn test_contribution_of_non_participant() {
+    let mut rng = StdRng::from_seed([3u8; 32]);
+    let contributions = contributions(&mut rng, 3);
+    let mut ceremony =
+        GenesisCeremony::with_participants(contributions[..2].iter().map(|c| c.peer_id.clone()));
+    assert!(ceremony.add_contribution(contributions[2].clone()).is_err());
+}
+
+#[test]
+fn test_genesis_with_bad_signature() {
+    let mut rng = StdRng::from_seed([4u8; 32]);
+    let contributions = contributions(&mut rng, 2);
+    let faucet = faucet_keypair(&mut rng);
+    let other_key = faucet_keypair(&mut rng);
+    let ceremony = ceremony_of(&contributions);
+
+    // A genesis signed by another key than the association key.
+    let genesis_blob = ceremony.assemble(&other_key).unwrap();
+    assert!(ceremony
+        .verify(&genesis_blob, &faucet.public_key, &contributions[0])
+        .is_err());
+
+    // A genesis whose signature, which ends the blob, was tampered with.
+    let mut genesis_blob = ceremony.assemble(&faucet).unwrap();
+    let last = genesis_blob.len() - 1;
+    genesis_blob[last] ^= 0xff;
+    assert!(ceremony
+        .verify(&genesis_blob, &faucet.public_key, &contributions[0])
+        .is_err());
+}
+
+#[test]
+fn test_genesis_of_other_validators() {
+    let mut rng = StdRng::from_seed([5u8; 32]);
+    let contributions = contributions(&mut rng, 3);
+    let faucet = faucet_keypair(&mut rng);
+    // The coordinator left a validator out of the genesis.
+    let genesis_blob = ceremony_of(&contributions[..2]).assemble(&faucet).unwrap();
+    let ceremony = ceremony_of(&contributions);
+    for contribution in &contributions {
+        assert!(ceremony
+            .verify(&genesis_blob, &faucet.public_key, contribution)
+            .is_err());
+    }
+}
+
+#[test]
+fn test_finalize_before_all_contributions() {
+    let mut rng = StdRng::from_seed([6u8; 32]);
+    let contributions = contributions(&mut rng, 3);
+    let faucet = faucet_keypair(&mut rng);
+    let mut ceremony =
+        GenesisCeremony::with_participants(contributions.iter().map(|c| c.peer_id.clone()));
+    assert!(ceremony.assemble(&faucet).is_err());
+    for contribution in &contributions[..2] {
+        ceremony.add_contribution(contribution.clone()).unwrap();
+    }
+    assert!(ceremony.assemble(&faucet).is_err());
+
+    // A genesis assembled from all the contributions can't be verified by an operator who
+    // misses some of them.
+    let genesis_blob = ceremony_of(&contributions).assemble(&faucet).unwrap();
+    assert!(ceremony
+        .verify(&genesis_blob, &faucet.public_key, &contributions[0])
+        .is_err());
+
+    ceremony.add_contribution(contributions[2].clone()).unwrap();
+    let genesis_blob = ceremony.assemble(&faucet).unwrap();
+    ceremony
+        .verify(&genesis_blob, &faucet.public_key, &contributions[0])
+        .unwrap();
+}
+
+#[test]
+fn test_empty_ceremony() {
+    let mut rng = StdRng::from_seed([7u8; 32]);
+    let faucet = faucet_keypair(&mut rng);
+    assert!(GenesisCeremony::new().assemble(&faucet).is_err());
+}
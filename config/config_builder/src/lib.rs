@@ -1,5 +1,7 @@
 // Copyright (c) The Libra Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
+pub mod genesis_cache;
+pub mod genesis_ceremony;
 pub mod swarm_config;
 pub mod util;
@@ -2,7 +2,11 @@
 // SPDX-License-Identifier: Apache-2.0
 
 //! Convenience structs and functions for generating configuration for a swarm of libra nodes
-use crate::util::gen_genesis_transaction;
+use crate::{
+    genesis_cache::{GenesisCache, GenesisCacheKey},
+    genesis_ceremony::{save_genesis_blob, GenesisCeremony, ValidatorContribution},
+    util::gen_genesis_transaction,
+};
 use config::{
     config::{
         BaseConfig, ConsensusConfig, NetworkConfig, NodeConfig, NodeConfigHelpers,
@@ -12,11 +16,20 @@ use config::{
     seed_peers::{SeedPeersConfig, SeedPeersConfigHelpers},
     trusted_peers::{
         ConfigHelpers, ConsensusPeersConfig, NetworkPeerPrivateKeys, NetworkPeersConfig,
+        UpstreamPeersConfig,
     },
 };
 use crypto::{ed25519::*, test_utils::KeyPair};
 use failure::prelude::*;
-use std::path::{Path, PathBuf};
+use rand::{rngs::StdRng, SeedableRng};
+use std::{
+    collections::HashMap,
+    fs, iter,
+    path::{Path, PathBuf},
+};
+
+/// The config of a node is saved as `<alias><suffix>` in the output dir.
+const NODE_CONFIG_SUFFIX: &str = ".node.config.toml";
 
 pub struct SwarmConfig {
     pub configs: Vec<(PathBuf, NodeConfig)>,
@@ -30,27 +43,58 @@ impl SwarmConfig {
     pub fn new(
         mut template: NodeConfig,
         num_nodes: usize,
+        num_full_nodes_per_validator: usize,
         role: RoleType,
         faucet_key: KeyPair<Ed25519PrivateKey, Ed25519PublicKey>,
         prune_seed_peers_for_discovery: bool,
         is_ipv4: bool,
         key_seed: Option<[u8; 32]>,
+        genesis_ceremony: bool,
+        genesis_cache: Option<(&GenesisCache, GenesisCacheKey)>,
         output_dir: &Path,
     ) -> Result<Self> {
         // Generate trusted peer configs + their private keys.
         template.base.data_dir_path = output_dir.into();
 
-        // Setup consensus keys and peers config file.
         let consensus_peers_file = template.consensus.consensus_peers_file.clone();
-        let (mut consensus_private_keys, consensus_peers_config) =
-            ConfigHelpers::get_test_consensus_config(num_nodes, key_seed);
-        consensus_peers_config.save_config(&output_dir.join(&consensus_peers_file));
-
-        // Setup network keys and file.
         let network_peers_file = template.networks.get(0).unwrap().network_peers_file.clone();
-        let (mut network_private_keys, network_peers_config) =
-            ConfigHelpers::get_test_network_peers_config(&consensus_peers_config, key_seed);
-        network_peers_config.save_config(&output_dir.join(&network_peers_file));
+        let genesis_path = output_dir.join(&template.execution.genesis_file_location);
+        let (
+            mut consensus_private_keys,
+            consensus_peers_config,
+            mut network_private_keys,
+            mut network_peers_config,
+        ) = if genesis_ceremony {
+            Self::run_genesis_ceremony(num_nodes, &faucet_key, key_seed, &genesis_path)?
+        } else {
+            let (consensus_private_keys, consensus_peers_config) =
+                ConfigHelpers::get_test_consensus_config(num_nodes, key_seed);
+            let (network_private_keys, network_peers_config) =
+                ConfigHelpers::get_test_network_peers_config(&consensus_peers_config, key_seed);
+            let cached = match &genesis_cache {
+                Some((cache, key)) => cache.load(key, &genesis_path)?,
+                None => false,
+            };
+            if !cached {
+                gen_genesis_transaction(
+                    &genesis_path,
+                    &faucet_key,
+                    &consensus_peers_config,
+                    &network_peers_config,
+                )?;
+                if let Some((cache, key)) = &genesis_cache {
+                    cache.store(key, &genesis_path)?;
+                }
+            }
+            (
+                consensus_private_keys,
+                consensus_peers_config,
+                network_private_keys,
+                network_peers_config,
+            )
+        };
+        // Setup consensus peers config file.
+        consensus_peers_config.save_config(&output_dir.join(&consensus_peers_file));
 
         // Setup seed peers and file.
         let seed_peers_file = template.networks.get(0).unwrap().seed_peers_file.clone();
@@ -60,13 +104,6 @@ impl SwarmConfig {
             is_ipv4,
         );
 
-        gen_genesis_transaction(
-            &output_dir.join(&template.execution.genesis_file_location),
-            &faucet_key,
-            &consensus_peers_config,
-            &network_peers_config,
-        )?;
-
         let mut configs = Vec::new();
         // Generate configs for all nodes.
         for (node_id, addrs) in &seed_peers_config.seed_peers {
@@ -100,6 +137,18 @@ impl SwarmConfig {
                 .advertised_address = addrs[0].clone();
             configs.push(validator_config);
         }
+        let full_node_configs = Self::get_full_node_configs(
+            &template,
+            &configs,
+            num_full_nodes_per_validator,
+            &mut network_peers_config,
+            is_ipv4,
+            key_seed,
+            output_dir,
+        );
+        configs.extend(full_node_configs);
+        // The network peers include the full nodes, such that the validators accept them.
+        network_peers_config.save_config(&output_dir.join(&network_peers_file));
         if prune_seed_peers_for_discovery {
             seed_peers_config.seed_peers = seed_peers_config
                 .seed_peers
@@ -112,7 +161,7 @@ impl SwarmConfig {
         let configs = configs
             .into_iter()
             .map(|config| {
-                let file_name = format!("{}.node.config.toml", Self::get_alias(&config));
+                let file_name = format!("{}{}", Self::get_alias(&config), NODE_CONFIG_SUFFIX);
                 let config_file = output_dir.join(file_name);
                 (config_file, config)
             })
@@ -133,6 +182,141 @@ impl SwarmConfig {
         })
     }
 
+    /// Generates the validators keys and the genesis through a `GenesisCeremony`: each
+    /// validator contributes its public keys, the genesis is assembled with the faucet key and
+    /// verified by every validator.
+    fn run_genesis_ceremony(
+        num_nodes: usize,
+        faucet_key: &KeyPair<Ed25519PrivateKey, Ed25519PublicKey>,
+        key_seed: Option<[u8; 32]>,
+        genesis_path: &Path,
+    ) -> Result<(
+        HashMap<String, Ed25519PrivateKey>,
+        ConsensusPeersConfig,
+        HashMap<String, NetworkPeerPrivateKeys>,
+        NetworkPeersConfig,
+    )> {
+        let mut rng = StdRng::from_seed(key_seed.unwrap_or([0u8; 32]));
+        let mut contributions = vec![];
+        let mut consensus_private_keys = HashMap::new();
+        let mut network_private_keys = HashMap::new();
+        for _ in 0..num_nodes {
+            let (contribution, private_keys) = ValidatorContribution::generate(&mut rng);
+            consensus_private_keys.insert(
+                contribution.peer_id.clone(),
+                private_keys.consensus_private_key,
+            );
+            network_private_keys.insert(
+                contribution.peer_id.clone(),
+                private_keys.network_private_keys,
+            );
+            contributions.push(contribution);
+        }
+        let mut ceremony =
+            GenesisCeremony::with_participants(contributions.iter().map(|c| c.peer_id.clone()));
+        for contribution in &contributions {
+            ceremony.add_contribution(contribution.clone())?;
+        }
+        let genesis_blob = ceremony.assemble(faucet_key)?;
+        for contribution in &contributions {
+            ceremony.verify(&genesis_blob, &faucet_key.public_key, contribution)?;
+        }
+        save_genesis_blob(genesis_path, &genesis_blob)?;
+        Ok((
+            consensus_private_keys,
+            ceremony.consensus_peers_config(),
+            network_private_keys,
+            ceremony.network_peers_config(),
+        ))
+    }
+
+    /// Generates the configs of `num_full_nodes_per_validator` full nodes syncing from each of
+    /// the given validators, and adds the full nodes to the network peers. A full node only
+    /// knows the address of its upstream validator.
+    fn get_full_node_configs(
+        template: &NodeConfig,
+        validator_configs: &[NodeConfig],
+        num_full_nodes_per_validator: usize,
+        network_peers_config: &mut NetworkPeersConfig,
+        is_ipv4: bool,
+        key_seed: Option<[u8; 32]>,
+        output_dir: &Path,
+    ) -> Vec<NodeConfig> {
+        if num_full_nodes_per_validator == 0 {
+            return vec![];
+        }
+        // The keys of the full nodes are derived from the complement of the seed of the
+        // validators, such that their peer ids don't collide.
+        let mut seed = key_seed.unwrap_or([0u8; 32]);
+        seed.iter_mut().for_each(|byte| *byte = !*byte);
+        let (mut consensus_private_keys, consensus_peers_config) =
+            ConfigHelpers::get_test_consensus_config(
+                validator_configs.len() * num_full_nodes_per_validator,
+                Some(seed),
+            );
+        let (mut network_private_keys, full_node_peers_config) =
+            ConfigHelpers::get_test_network_peers_config(&consensus_peers_config, Some(seed));
+        let full_node_addrs = SeedPeersConfigHelpers::get_test_config_with_ipver(
+            &full_node_peers_config,
+            None,
+            is_ipv4,
+        );
+
+        let mut full_node_ids: Vec<_> = full_node_peers_config.peers.keys().cloned().collect();
+        full_node_ids.sort();
+        let mut upstream_networks: Vec<_> = validator_configs
+            .iter()
+            .map(|config| config.networks.get(0).unwrap())
+            .collect();
+        upstream_networks.sort_by_key(|network| network.peer_id.clone());
+        let upstream_networks = upstream_networks
+            .into_iter()
+            .flat_map(|network| iter::repeat(network).take(num_full_nodes_per_validator));
+
+        let mut configs = vec![];
+        for (node_id, upstream_network) in full_node_ids.iter().zip(upstream_networks) {
+            // The full nodes don't take part in consensus, their consensus keys are never used.
+            let consensus_private_key = consensus_private_keys.remove(node_id).unwrap();
+            let consensus_keypair = ConsensusKeyPair::load(Some(consensus_private_key));
+            let NetworkPeerPrivateKeys {
+                network_signing_private_key,
+                network_identity_private_key,
+            } = network_private_keys.remove(node_id).unwrap();
+            let network_keypairs =
+                NetworkKeyPairs::load(network_signing_private_key, network_identity_private_key);
+            let mut full_node_config = Self::get_config_by_role(
+                template,
+                RoleType::FullNode,
+                node_id,
+                &network_keypairs,
+                &consensus_keypair,
+                output_dir,
+                &template.storage.dir,
+            );
+            let network = full_node_config.networks.get_mut(0).unwrap();
+            let addr = full_node_addrs.seed_peers[node_id][0].clone();
+            network.listen_address = addr.clone();
+            network.advertised_address = addr;
+            network.seed_peers_file = format!("{}.seed_peers.config.toml", node_id).into();
+            let upstream_seed_peers = SeedPeersConfig {
+                seed_peers: iter::once((
+                    upstream_network.peer_id.clone(),
+                    vec![upstream_network.advertised_address.clone()],
+                ))
+                .collect(),
+            };
+            upstream_seed_peers.save_config(&output_dir.join(&network.seed_peers_file));
+            full_node_config.state_sync.upstream_peers = UpstreamPeersConfig {
+                upstream_peers: vec![upstream_network.peer_id.clone()],
+            };
+            configs.push(full_node_config);
+        }
+        network_peers_config
+            .peers
+            .extend(full_node_peers_config.peers);
+        configs
+    }
+
     fn get_config_by_role(
         template: &NodeConfig,
         role: RoleType,
@@ -179,10 +363,65 @@ impl SwarmConfig {
         };
         let consensus_config = ConsensusConfig {
             max_block_size: template.consensus.max_block_size,
+            max_block_bytes: template.consensus.max_block_bytes,
+            max_uncommitted_blocks: template.consensus.max_uncommitted_blocks,
             proposer_type: template.consensus.proposer_type.clone(),
             contiguous_rounds: template.consensus.contiguous_rounds,
             max_pruned_blocks_in_mem: template.consensus.max_pruned_blocks_in_mem,
+            max_pruned_payload_bytes_in_mem: template.consensus.max_pruned_payload_bytes_in_mem,
             pacemaker_initial_timeout_ms: template.consensus.pacemaker_initial_timeout_ms,
+            pacemaker_timeout_exponent_base: template.consensus.pacemaker_timeout_exponent_base,
+            pacemaker_max_exponent: template.consensus.pacemaker_max_exponent,
+            pacemaker_max_timeout_ms: template.consensus.pacemaker_max_timeout_ms,
+            pacemaker_adaptive_timeout_percentile: template
+                .consensus
+                .pacemaker_adaptive_timeout_percentile,
+            pacemaker_timeout_retransmission_interval_ms: template
+                .consensus
+                .pacemaker_timeout_retransmission_interval_ms,
+            pacemaker_timeout_retransmission_max_jitter_ms: template
+                .consensus
+                .pacemaker_timeout_retransmission_max_jitter_ms,
+            pacemaker_max_timeout_retransmissions: template
+                .consensus
+                .pacemaker_max_timeout_retransmissions,
+            commit_heartbeat_interval_ms: template.consensus.commit_heartbeat_interval_ms,
+            commit_heartbeat_quiet_period_ms: template.consensus.commit_heartbeat_quiet_period_ms,
+            block_retrieval_fanout: template.consensus.block_retrieval_fanout,
+            block_retrieval_peer_timeout_ms: template.consensus.block_retrieval_peer_timeout_ms,
+            block_retrieval_max_peer_timeout_ms: template
+                .consensus
+                .block_retrieval_max_peer_timeout_ms,
+            block_retrieval_max_retries_per_peer: template
+                .consensus
+                .block_retrieval_max_retries_per_peer,
+            block_retrieval_preferred_peers: template
+                .consensus
+                .block_retrieval_preferred_peers
+                .clone(),
+            inbound_msgs_per_sec_per_peer: template.consensus.inbound_msgs_per_sec_per_peer,
+            inbound_msgs_burst_per_peer: template.consensus.inbound_msgs_burst_per_peer,
+            inbound_queue_size: template.consensus.inbound_queue_size,
+            inbound_queue_size_overrides: template.consensus.inbound_queue_size_overrides.clone(),
+            signature_verification_threads: template.consensus.signature_verification_threads,
+            // Every node writes its consensus events to its own file in the output dir.
+            event_log_file: template
+                .consensus
+                .event_log_file
+                .as_ref()
+                .and_then(|file| file.file_name())
+                .map(|file_name| {
+                    output_dir.join(format!("{}.{}", node_id, file_name.to_string_lossy()))
+                }),
+            safety_rules_backend: template.consensus.safety_rules_backend.clone(),
+            safety_rules_socket: template.consensus.safety_rules_socket.clone(),
+            safety_rules_storage_file: template.consensus.safety_rules_storage_file.clone(),
+            commit_rule: template.consensus.commit_rule.clone(),
+            nil_blocks_enabled: template.consensus.nil_blocks_enabled,
+            leader_skipping_enabled: template.consensus.leader_skipping_enabled,
+            observer: template.consensus.observer,
+            observers: template.consensus.observers.clone(),
+            storage_backend: template.consensus.storage_backend.clone(),
             consensus_keypair: ConsensusKeyPair::default(),
             consensus_keypair_file: consensus_keys_file_name.into(),
             consensus_peers: template.consensus.consensus_peers.clone(),
@@ -205,11 +444,59 @@ impl SwarmConfig {
         };
         NodeConfigHelpers::randomize_config_ports(&mut config);
         let alias = Self::get_alias(&config);
-        config.storage.dir = dir.join(alias).join("db");
+        config.storage.dir = dir.join(&alias).join("db");
+        // The safety rules service of every node has its own socket and storage.
+        config.consensus.safety_rules_socket = dir.join(&alias).join("safety_rules.sock");
+        config.consensus.safety_rules_storage_file = dir.join(&alias).join("safety_rules.json");
         config.vm_config.publishing_options = VMPublishingOption::Open;
         config
     }
 
+    /// Loads the configs of a swarm from the output dir they were generated into, e.g. to attach
+    /// to the nodes of a running swarm. The validators come first, as in a generated config.
+    pub fn load(output_dir: &Path) -> Result<Self> {
+        let mut configs = vec![];
+        for entry in fs::read_dir(output_dir)? {
+            let path = entry?.path();
+            let is_node_config = path
+                .file_name()
+                .and_then(|file_name| file_name.to_str())
+                .map_or(false, |file_name| file_name.ends_with(NODE_CONFIG_SUFFIX));
+            if is_node_config {
+                let config = NodeConfig::load_config(&path);
+                configs.push((path, config));
+            }
+        }
+        configs.sort_by_key(|(path, config)| (!config.is_validator(), path.clone()));
+        // The full nodes have seed peers of their own.
+        let (seed_peers_file, network_peers_file, consensus_peers_file) = {
+            let (_, config) = configs
+                .first()
+                .ok_or_else(|| format_err!("No node config found in {:?}", output_dir))?;
+            let network = config.networks.get(0).unwrap();
+            (
+                output_dir.join(&network.seed_peers_file),
+                output_dir.join(&network.network_peers_file),
+                output_dir.join(&config.consensus.consensus_peers_file),
+            )
+        };
+        Ok(Self {
+            configs,
+            seed_peers: (
+                seed_peers_file.clone(),
+                SeedPeersConfig::load_config(&seed_peers_file),
+            ),
+            network_peers: (
+                network_peers_file.clone(),
+                NetworkPeersConfig::load_config(&network_peers_file),
+            ),
+            consensus_peers: (
+                consensus_peers_file.clone(),
+                ConsensusPeersConfig::load_config(&consensus_peers_file),
+            ),
+        })
+    }
+
     pub fn get_alias(config: &NodeConfig) -> String {
         let network = config.networks.get(0).unwrap();
         match (&network.role).into() {
@@ -224,11 +511,14 @@ impl SwarmConfig {
 
 pub struct SwarmConfigBuilder {
     num_nodes: usize,
+    num_full_nodes_per_validator: usize,
     template_path: PathBuf,
     output_dir: PathBuf,
     force_discovery: bool,
     is_ipv4: bool,
     key_seed: Option<[u8; 32]>,
+    genesis_ceremony: bool,
+    genesis_cache: Option<GenesisCache>,
     faucet_account_keypair_filepath: Option<PathBuf>,
     faucet_account_keypair: Option<KeyPair<Ed25519PrivateKey, Ed25519PublicKey>>,
     role: RoleType,
@@ -237,11 +527,14 @@ impl Default for SwarmConfigBuilder {
     fn default() -> Self {
         SwarmConfigBuilder {
             num_nodes: 1,
+            num_full_nodes_per_validator: 0,
             template_path: "config/data/configs/node.config.toml".into(),
             output_dir: "configs".into(),
             force_discovery: false,
             is_ipv4: false,
             key_seed: None,
+            genesis_ceremony: false,
+            genesis_cache: None,
             faucet_account_keypair_filepath: None,
             faucet_account_keypair: None,
             role: RoleType::Validator,
@@ -282,6 +575,15 @@ impl SwarmConfigBuilder {
         self
     }
 
+    /// Generates the configs of the given number of full nodes syncing from each validator.
+    pub fn with_num_full_nodes_per_validator(
+        &mut self,
+        num_full_nodes_per_validator: usize,
+    ) -> &mut Self {
+        self.num_full_nodes_per_validator = num_full_nodes_per_validator;
+        self
+    }
+
     pub fn with_role(&mut self, role: RoleType) -> &mut Self {
         self.role = role;
         self
@@ -307,6 +609,21 @@ impl SwarmConfigBuilder {
         self
     }
 
+    /// Generates the validator keys and the genesis through a multi-party `GenesisCeremony`
+    /// instead of generating them all in one place.
+    pub fn with_genesis_ceremony(&mut self) -> &mut Self {
+        self.genesis_ceremony = true;
+        self
+    }
+
+    /// Reuses the genesis cached for the same number of validators, template and faucet key,
+    /// or caches the generated one. Unless a key seed is given, the validator keys are derived
+    /// from the cache key. A genesis ceremony always runs in full.
+    pub fn with_genesis_cache(&mut self, cache: GenesisCache) -> &mut Self {
+        self.genesis_cache = Some(cache);
+        self
+    }
+
     pub fn build(&mut self) -> Result<SwarmConfig> {
         // verify required fields
         let faucet_key_path = self.faucet_account_keypair_filepath.clone();
@@ -329,6 +646,24 @@ impl SwarmConfigBuilder {
             );
         }
 
+        let genesis_cache = match &self.genesis_cache {
+            Some(cache) if !self.genesis_ceremony => {
+                let template = fs::read(&self.template_path)
+                    .with_context(|_| format!("Failed to read {:?}", self.template_path))?;
+                let key = GenesisCacheKey::new(
+                    self.num_nodes,
+                    &template,
+                    &faucet_key.public_key,
+                    self.key_seed,
+                );
+                Some((cache, key))
+            }
+            _ => None,
+        };
+        let key_seed = self
+            .key_seed
+            .or_else(|| genesis_cache.map(|(_, key)| key.key_seed()));
+
         // read template
         let mut template = NodeConfig::load_config(&self.template_path);
         // update everything in the template and then generate swarm config
@@ -376,11 +711,14 @@ impl SwarmConfigBuilder {
         SwarmConfig::new(
             template,
             self.num_nodes,
+            self.num_full_nodes_per_validator,
             self.role,
             faucet_key,
             self.force_discovery,
             self.is_ipv4,
-            self.key_seed,
+            key_seed,
+            self.genesis_ceremony,
+            genesis_cache,
             &self.output_dir,
         )
     }
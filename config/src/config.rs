@@ -2,7 +2,10 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    config::ConsensusProposerType::{FixedProposer, MultipleOrderedProposers, RotatingProposer},
+    config::ConsensusProposerType::{
+        FixedProposer, LeaderReputation, MultipleOrderedProposers, RotatingProposer,
+        WeightedRotatingProposer,
+    },
     keys::{ConsensusKeyPair, NetworkKeyPairs},
     seed_peers::{SeedPeersConfig, SeedPeersConfigHelpers},
     trusted_peers::{
@@ -428,10 +431,87 @@ impl NetworkConfig {
 #[serde(default)]
 pub struct ConsensusConfig {
     pub max_block_size: u64,
+    // Max size in bytes of the serialized payload of a block, alongside the max number of
+    // transactions of max_block_size.
+    pub max_block_bytes: Option<u64>,
+    // The leader proposes blocks without transactions while more than this number of ordered
+    // blocks are not committed yet, so that execution can catch up.
+    pub max_uncommitted_blocks: Option<usize>,
     pub proposer_type: String,
     pub contiguous_rounds: u32,
     pub max_pruned_blocks_in_mem: Option<u64>,
+    // If set, the committed blocks are kept in memory as long as their payloads add up to at most
+    // this number of bytes, instead of keeping max_pruned_blocks_in_mem blocks.
+    pub max_pruned_payload_bytes_in_mem: Option<u64>,
     pub pacemaker_initial_timeout_ms: Option<u64>,
+    // By how much the pacemaker timeout grows after every round without a commit.
+    pub pacemaker_timeout_exponent_base: Option<f64>,
+    // The timeout stops growing after this number of rounds without a commit.
+    pub pacemaker_max_exponent: Option<usize>,
+    // Upper bound of the pacemaker timeout, regardless of the exponent.
+    pub pacemaker_max_timeout_ms: Option<u64>,
+    // If set, the base timeout adapts to this percentile (in (0, 1]) of the recent round
+    // durations instead of being fixed to pacemaker_initial_timeout_ms.
+    pub pacemaker_adaptive_timeout_percentile: Option<f64>,
+    // Delay between the retransmissions of the timeout message of a round that didn't advance,
+    // a random jitter up to pacemaker_timeout_retransmission_max_jitter_ms is added to it.
+    pub pacemaker_timeout_retransmission_interval_ms: Option<u64>,
+    pub pacemaker_timeout_retransmission_max_jitter_ms: Option<u64>,
+    // Number of times the timeout message of a round is retransmitted, 0 disables them.
+    pub pacemaker_max_timeout_retransmissions: Option<usize>,
+    // Delay between the broadcasts of the highest commit certificate of the node, 0 disables
+    // them. They are only sent once no proposal was received for commit_heartbeat_quiet_period_ms.
+    pub commit_heartbeat_interval_ms: Option<u64>,
+    pub commit_heartbeat_quiet_period_ms: Option<u64>,
+    // Number of peers the missing blocks are requested from concurrently.
+    pub block_retrieval_fanout: Option<usize>,
+    // Timeout of the first block request to a peer, doubled on every following attempt.
+    pub block_retrieval_peer_timeout_ms: Option<u64>,
+    // Upper bound of the timeout of a block request to a peer.
+    pub block_retrieval_max_peer_timeout_ms: Option<u64>,
+    // Number of times a peer that failed to return the missing blocks is asked again.
+    pub block_retrieval_max_retries_per_peer: Option<u32>,
+    // Peer ids asked for the missing blocks before the other signers of the quorum certificate.
+    pub block_retrieval_preferred_peers: Vec<String>,
+    // Number of messages of every type a peer may send per second, 0 disables the rate limiting.
+    pub inbound_msgs_per_sec_per_peer: Option<u32>,
+    // Number of messages of every type a peer may send at once after being idle.
+    pub inbound_msgs_burst_per_peer: Option<u32>,
+    // Number of messages of every type waiting for the event processor, the messages received
+    // while the queue of their type is full are dropped.
+    pub inbound_queue_size: Option<usize>,
+    // Queue sizes of specific message types ("proposal", "vote", "timeout_msg", "sync_info",
+    // "sync_info_request" or "block_request"), overriding inbound_queue_size.
+    pub inbound_queue_size_overrides: HashMap<String, usize>,
+    // Number of threads verifying the signatures of the inbound messages, one per CPU if unset.
+    pub signature_verification_threads: Option<usize>,
+    // If set, consensus appends a structured JSON-lines log of its events to this file.
+    pub event_log_file: Option<PathBuf>,
+    // Where the safety rules run: "in_process", or "process" in which case consensus connects
+    // to the safety rules service listening on safety_rules_socket.
+    pub safety_rules_backend: String,
+    // Local socket of the safety rules service, used by the "process" backend.
+    pub safety_rules_socket: PathBuf,
+    // File the safety rules service persists the consensus state to.
+    pub safety_rules_storage_file: PathBuf,
+    // How many contiguous certified blocks commit a block: "three_chain", or "two_chain" which
+    // commits one round earlier.
+    pub commit_rule: String,
+    // If disabled, a validator that received no proposal doesn't vote for a NIL block when its
+    // round times out: the round only advances with a timeout certificate, and the next proposer
+    // extends the highest certified block with a block carrying transactions.
+    pub nil_blocks_enabled: bool,
+    // If enabled with the "multiple_ordered_proposers" proposer type, a primary proposer that
+    // failed to propose in its last rounds is demoted to a backup proposer for a couple of rounds.
+    pub leader_skipping_enabled: bool,
+    // If set, the node is an observer of consensus: it follows the proposals and the commits of
+    // the validators without ever voting, proposing or sending timeouts, e.g., on a full node.
+    pub observer: bool,
+    // Peer ids of the observers the validator sends its proposals and timeouts to.
+    pub observers: Vec<String>,
+    // Where the blocks, quorum certs and liveness data of consensus are persisted: "rocksdb" in
+    // the storage dir, or "in_memory" in which case they are lost when the node stops.
+    pub storage_backend: String,
     // consensus_keypair contains the node's consensus keypair.
     // it is filled later on from consensus_keypair_file.
     #[serde(skip)]
@@ -446,10 +526,42 @@ impl Default for ConsensusConfig {
     fn default() -> ConsensusConfig {
         ConsensusConfig {
             max_block_size: 100,
+            max_block_bytes: None,
+            max_uncommitted_blocks: None,
             proposer_type: "multiple_ordered_proposers".to_string(),
             contiguous_rounds: 2,
             max_pruned_blocks_in_mem: None,
+            max_pruned_payload_bytes_in_mem: None,
             pacemaker_initial_timeout_ms: None,
+            pacemaker_timeout_exponent_base: None,
+            pacemaker_max_exponent: None,
+            pacemaker_max_timeout_ms: None,
+            pacemaker_adaptive_timeout_percentile: None,
+            pacemaker_timeout_retransmission_interval_ms: None,
+            pacemaker_timeout_retransmission_max_jitter_ms: None,
+            pacemaker_max_timeout_retransmissions: None,
+            commit_heartbeat_interval_ms: None,
+            commit_heartbeat_quiet_period_ms: None,
+            block_retrieval_fanout: None,
+            block_retrieval_peer_timeout_ms: None,
+            block_retrieval_max_peer_timeout_ms: None,
+            block_retrieval_max_retries_per_peer: None,
+            block_retrieval_preferred_peers: vec![],
+            inbound_msgs_per_sec_per_peer: None,
+            inbound_msgs_burst_per_peer: None,
+            inbound_queue_size: None,
+            inbound_queue_size_overrides: HashMap::new(),
+            signature_verification_threads: None,
+            event_log_file: None,
+            safety_rules_backend: "in_process".to_string(),
+            safety_rules_socket: PathBuf::from("safety_rules.sock"),
+            safety_rules_storage_file: PathBuf::from("safety_rules.json"),
+            commit_rule: "three_chain".to_string(),
+            nil_blocks_enabled: true,
+            leader_skipping_enabled: false,
+            observer: false,
+            observers: vec![],
+            storage_backend: "rocksdb".to_string(),
             consensus_keypair: ConsensusKeyPair::default(),
             consensus_keypair_file: PathBuf::from("consensus_keypair.config.toml"),
             consensus_peers: ConsensusPeersConfig::default(),
@@ -466,6 +578,34 @@ pub enum ConsensusProposerType {
     RotatingProposer,
     // Multiple ordered proposers per round (primary, secondary, etc.)
     MultipleOrderedProposers,
+    // Proposers elected based on their recent activity in the committed blocks
+    LeaderReputation,
+    // Rotation of proposers in which every proposer is active proportionally to its voting power
+    WeightedRotatingProposer,
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum ConsensusStorageBackend {
+    // The ConsensusDB in the storage dir of the node
+    RocksDB,
+    // Memory only, for the tests
+    InMemory,
+}
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum SafetyRulesBackend {
+    // The safety rules run within consensus
+    InProcess,
+    // The safety rules run in a separate process, reachable over the given local socket
+    Process(PathBuf),
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum CommitRule {
+    // A block is committed by a chain of 3 certified blocks of contiguous rounds
+    ThreeChain,
+    // A block is committed by a chain of 2 certified blocks of contiguous rounds
+    TwoChain,
 }
 
 impl ConsensusConfig {
@@ -486,6 +626,8 @@ impl ConsensusConfig {
             "fixed_proposer" => FixedProposer,
             "rotating_proposer" => RotatingProposer,
             "multiple_ordered_proposers" => MultipleOrderedProposers,
+            "leader_reputation" => LeaderReputation,
+            "weighted_rotating_proposer" => WeightedRotatingProposer,
             &_ => unimplemented!("Invalid proposer type: {}", self.proposer_type),
         }
     }
@@ -498,14 +640,170 @@ impl ConsensusConfig {
         self.max_block_size
     }
 
+    pub fn max_block_bytes(&self) -> &Option<u64> {
+        &self.max_block_bytes
+    }
+
+    pub fn max_uncommitted_blocks(&self) -> &Option<usize> {
+        &self.max_uncommitted_blocks
+    }
+
     pub fn max_pruned_blocks_in_mem(&self) -> &Option<u64> {
         &self.max_pruned_blocks_in_mem
     }
 
+    pub fn max_pruned_payload_bytes_in_mem(&self) -> &Option<u64> {
+        &self.max_pruned_payload_bytes_in_mem
+    }
+
     pub fn pacemaker_initial_timeout_ms(&self) -> &Option<u64> {
         &self.pacemaker_initial_timeout_ms
     }
 
+    pub fn pacemaker_timeout_exponent_base(&self) -> &Option<f64> {
+        &self.pacemaker_timeout_exponent_base
+    }
+
+    pub fn pacemaker_max_exponent(&self) -> &Option<usize> {
+        &self.pacemaker_max_exponent
+    }
+
+    pub fn pacemaker_max_timeout_ms(&self) -> &Option<u64> {
+        &self.pacemaker_max_timeout_ms
+    }
+
+    pub fn pacemaker_adaptive_timeout_percentile(&self) -> &Option<f64> {
+        &self.pacemaker_adaptive_timeout_percentile
+    }
+
+    pub fn pacemaker_timeout_retransmission_interval_ms(&self) -> &Option<u64> {
+        &self.pacemaker_timeout_retransmission_interval_ms
+    }
+
+    pub fn pacemaker_timeout_retransmission_max_jitter_ms(&self) -> &Option<u64> {
+        &self.pacemaker_timeout_retransmission_max_jitter_ms
+    }
+
+    pub fn pacemaker_max_timeout_retransmissions(&self) -> &Option<usize> {
+        &self.pacemaker_max_timeout_retransmissions
+    }
+
+    pub fn commit_heartbeat_interval_ms(&self) -> &Option<u64> {
+        &self.commit_heartbeat_interval_ms
+    }
+
+    pub fn commit_heartbeat_quiet_period_ms(&self) -> &Option<u64> {
+        &self.commit_heartbeat_quiet_period_ms
+    }
+
+    pub fn block_retrieval_fanout(&self) -> &Option<usize> {
+        &self.block_retrieval_fanout
+    }
+
+    pub fn block_retrieval_peer_timeout_ms(&self) -> &Option<u64> {
+        &self.block_retrieval_peer_timeout_ms
+    }
+
+    pub fn block_retrieval_max_peer_timeout_ms(&self) -> &Option<u64> {
+        &self.block_retrieval_max_peer_timeout_ms
+    }
+
+    pub fn block_retrieval_max_retries_per_peer(&self) -> &Option<u32> {
+        &self.block_retrieval_max_retries_per_peer
+    }
+
+    pub fn get_block_retrieval_preferred_peers(&self) -> Vec<PeerId> {
+        self.block_retrieval_preferred_peers
+            .iter()
+            .map(|peer_id_str| {
+                PeerId::from_str(peer_id_str).unwrap_or_else(|_| {
+                    panic!(
+                        "Failed to deserialize block retrieval preferred peer id: {}",
+                        peer_id_str
+                    )
+                })
+            })
+            .collect()
+    }
+
+    pub fn inbound_msgs_per_sec_per_peer(&self) -> &Option<u32> {
+        &self.inbound_msgs_per_sec_per_peer
+    }
+
+    pub fn inbound_msgs_burst_per_peer(&self) -> &Option<u32> {
+        &self.inbound_msgs_burst_per_peer
+    }
+
+    pub fn inbound_queue_size(&self) -> &Option<usize> {
+        &self.inbound_queue_size
+    }
+
+    pub fn inbound_queue_size_overrides(&self) -> &HashMap<String, usize> {
+        &self.inbound_queue_size_overrides
+    }
+
+    pub fn signature_verification_threads(&self) -> &Option<usize> {
+        &self.signature_verification_threads
+    }
+
+    pub fn event_log_file(&self) -> &Option<PathBuf> {
+        &self.event_log_file
+    }
+
+    pub fn get_safety_rules_backend(&self) -> SafetyRulesBackend {
+        match self.safety_rules_backend.as_str() {
+            "in_process" => SafetyRulesBackend::InProcess,
+            "process" => SafetyRulesBackend::Process(self.safety_rules_socket.clone()),
+            &_ => unimplemented!(
+                "Invalid safety rules backend: {}",
+                self.safety_rules_backend
+            ),
+        }
+    }
+
+    pub fn safety_rules_storage_file(&self) -> &PathBuf {
+        &self.safety_rules_storage_file
+    }
+
+    pub fn get_commit_rule(&self) -> CommitRule {
+        match self.commit_rule.as_str() {
+            "three_chain" => CommitRule::ThreeChain,
+            "two_chain" => CommitRule::TwoChain,
+            &_ => unimplemented!("Invalid commit rule: {}", self.commit_rule),
+        }
+    }
+
+    pub fn nil_blocks_enabled(&self) -> bool {
+        self.nil_blocks_enabled
+    }
+
+    pub fn leader_skipping_enabled(&self) -> bool {
+        self.leader_skipping_enabled
+    }
+
+    pub fn observer(&self) -> bool {
+        self.observer
+    }
+
+    pub fn get_observers(&self) -> Vec<PeerId> {
+        self.observers
+            .iter()
+            .map(|peer_id_str| {
+                PeerId::from_str(peer_id_str).unwrap_or_else(|_| {
+                    panic!("Failed to deserialize observer peer id: {}", peer_id_str)
+                })
+            })
+            .collect()
+    }
+
+    pub fn get_storage_backend(&self) -> ConsensusStorageBackend {
+        match self.storage_backend.as_str() {
+            "rocksdb" => ConsensusStorageBackend::RocksDB,
+            "in_memory" => ConsensusStorageBackend::InMemory,
+            &_ => unimplemented!("Invalid storage backend: {}", self.storage_backend),
+        }
+    }
+
     pub fn get_consensus_peers(&self) -> HashMap<PeerId, Ed25519PublicKey> {
         self.consensus_peers
             .peers
@@ -523,6 +821,24 @@ impl ConsensusConfig {
             })
             .collect()
     }
+
+    pub fn get_consensus_voting_power(&self) -> HashMap<PeerId, u64> {
+        self.consensus_peers
+            .peers
+            .iter()
+            .map(|(peer_id_str, peer_info)| {
+                (
+                    PeerId::from_str(peer_id_str).unwrap_or_else(|_| {
+                        panic!(
+                            "Failed to deserialize PeerId: {} from consensus peers config: ",
+                            peer_id_str
+                        )
+                    }),
+                    peer_info.voting_power,
+                )
+            })
+            .collect()
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -650,7 +966,8 @@ impl NodeConfigHelpers {
         new_addr
     }
 
-    fn get_tcp_port(addr: &Multiaddr) -> Option<u16> {
+    /// Returns the Tcp port of the multiaddr, if present.
+    pub fn get_tcp_port(addr: &Multiaddr) -> Option<u16> {
         for p in addr.iter() {
             if let Protocol::Tcp(port) = p {
                 return Some(port);
@@ -15,7 +15,7 @@ use std::{
 };
 use types::{
     account_address::AccountAddress, validator_public_keys::ValidatorPublicKeys,
-    validator_set::ValidatorSet,
+    validator_set::ValidatorSet, validator_verifier::DEFAULT_VOTING_POWER,
 };
 
 #[cfg(test)]
@@ -52,6 +52,13 @@ pub struct ConsensusPeerInfo {
     #[serde(deserialize_with = "deserialize_key")]
     #[serde(rename = "c")]
     pub consensus_pubkey: Ed25519PublicKey,
+    #[serde(default = "default_voting_power")]
+    #[serde(rename = "v")]
+    pub voting_power: u64,
+}
+
+fn default_voting_power() -> u64 {
+    DEFAULT_VOTING_POWER
 }
 
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
@@ -129,6 +136,7 @@ impl ConfigHelpers {
                 peer_id.to_string(),
                 ConsensusPeerInfo {
                     consensus_pubkey: public0,
+                    voting_power: DEFAULT_VOTING_POWER,
                 },
             );
             // save the private keys in a different hashmap
@@ -0,0 +1,65 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Exports the consensus data of a (stopped) node to a snapshot file, or imports a snapshot into
+//! the storage of a node that never ran.
+
+use clap::{App, Arg};
+use consensus::snapshot::ConsensusSnapshot;
+use std::path::Path;
+use types::transaction::SignedTransaction;
+
+const STORAGE_DIR_ARG: &str = "storage-dir";
+const EXPORT_ARG: &str = "export";
+const IMPORT_ARG: &str = "import";
+
+fn main() {
+    let args = App::new("Consensus Snapshot")
+        .version("0.1.0")
+        .author("Libra Association <opensource@libra.org>")
+        .about("Tool to export and import the consensus data of a stopped node")
+        .arg(
+            Arg::with_name(STORAGE_DIR_ARG)
+                .short("s")
+                .long(STORAGE_DIR_ARG)
+                .takes_value(true)
+                .required(true)
+                .help("The storage directory of the node (storage.dir in the node config)"),
+        )
+        .arg(
+            Arg::with_name(EXPORT_ARG)
+                .short("e")
+                .long(EXPORT_ARG)
+                .takes_value(true)
+                .required_unless(IMPORT_ARG)
+                .conflicts_with(IMPORT_ARG)
+                .help("The snapshot file to export the consensus data to"),
+        )
+        .arg(
+            Arg::with_name(IMPORT_ARG)
+                .short("i")
+                .long(IMPORT_ARG)
+                .takes_value(true)
+                .help("The snapshot file to import into the storage of a fresh node"),
+        )
+        .get_matches();
+
+    let storage_dir = Path::new(args.value_of(STORAGE_DIR_ARG).unwrap());
+    if let Some(path) = args.value_of(EXPORT_ARG) {
+        let snapshot = ConsensusSnapshot::<Vec<SignedTransaction>>::from_storage_dir(storage_dir)
+            .unwrap_or_else(|e| panic!("Failed to export the consensus data: {}", e));
+        snapshot
+            .save(Path::new(path))
+            .unwrap_or_else(|e| panic!("Failed to save the snapshot: {}", e));
+        println!("Exported {} blocks to {}", snapshot.num_blocks(), path);
+    } else {
+        let path = args.value_of(IMPORT_ARG).unwrap();
+        let snapshot = ConsensusSnapshot::<Vec<SignedTransaction>>::load(Path::new(path))
+            .unwrap_or_else(|e| panic!("Failed to load the snapshot: {}", e));
+        let num_blocks = snapshot.num_blocks();
+        snapshot
+            .import_to_storage_dir(storage_dir)
+            .unwrap_or_else(|e| panic!("Failed to import the snapshot: {}", e));
+        println!("Imported {} blocks from {}", num_blocks, path);
+    }
+}
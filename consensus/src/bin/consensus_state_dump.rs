@@ -0,0 +1,47 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Prints the content of the consensus persistent storage of a (stopped) node.
+
+use clap::{App, Arg};
+use consensus::state_dump::ConsensusStateDump;
+use std::path::Path;
+use types::transaction::SignedTransaction;
+
+const STORAGE_DIR_ARG: &str = "storage-dir";
+const JSON_ARG: &str = "json";
+
+fn main() {
+    let args = App::new("Consensus State Dump")
+        .version("0.1.0")
+        .author("Libra Association <opensource@libra.org>")
+        .about("Tool to inspect the consensus persistent storage of a stopped node")
+        .arg(
+            Arg::with_name(STORAGE_DIR_ARG)
+                .short("s")
+                .long(STORAGE_DIR_ARG)
+                .takes_value(true)
+                .required(true)
+                .help("The storage directory of the node (storage.dir in the node config)"),
+        )
+        .arg(
+            Arg::with_name(JSON_ARG)
+                .short("j")
+                .long(JSON_ARG)
+                .help("Print the dump as JSON"),
+        )
+        .get_matches();
+
+    let storage_dir = Path::new(args.value_of(STORAGE_DIR_ARG).unwrap());
+    let dump = ConsensusStateDump::from_storage_dir::<Vec<SignedTransaction>>(storage_dir)
+        .unwrap_or_else(|e| panic!("Failed to dump the consensus state: {}", e));
+    if args.is_present(JSON_ARG) {
+        println!(
+            "{}",
+            dump.to_json()
+                .unwrap_or_else(|e| panic!("Failed to serialize the dump: {}", e))
+        );
+    } else {
+        print!("{}", dump);
+    }
+}
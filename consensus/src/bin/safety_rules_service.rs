@@ -0,0 +1,33 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Runs the safety rules of a validator whose node config selects the "process" safety rules
+//! backend, next to the node.
+
+use clap::{App, Arg};
+use config::config::{NodeConfig, PersistableConfig};
+use consensus::run_safety_rules_service;
+
+const CONFIG_ARG: &str = "config";
+
+fn main() {
+    let args = App::new("Safety Rules Service")
+        .version("0.1.0")
+        .author("Libra Association <opensource@libra.org>")
+        .about("Runs the consensus safety rules of a validator in a separate process")
+        .arg(
+            Arg::with_name(CONFIG_ARG)
+                .short("f")
+                .long(CONFIG_ARG)
+                .takes_value(true)
+                .required(true)
+                .help("The node config of the validator"),
+        )
+        .get_matches();
+
+    let _logger = logger::set_default_global_logger(false /* async */, None);
+    let config = NodeConfig::load_config(args.value_of(CONFIG_ARG).unwrap());
+    if let Err(e) = run_safety_rules_service(&config.consensus) {
+        panic!("Safety rules service failed: {}", e);
+    }
+}
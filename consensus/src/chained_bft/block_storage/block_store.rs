@@ -3,7 +3,10 @@
 
 use crate::{
     chained_bft::{
-        block_storage::{block_tree::BlockTree, BlockReader, VoteReceptionResult},
+        block_storage::{
+            block_tree::BlockTree, AsyncBlockReader, BlockReadFuture, BlockReader, PruningPolicy,
+            VoteReceptionResult,
+        },
         common::{Payload, Round},
         consensus_types::{
             block::{Block, ExecutedBlock},
@@ -15,17 +18,26 @@ use crate::{
     state_replication::StateComputer,
 };
 use crypto::HashValue;
+use debug_interface::node_debug_service::CommitCertificateProvider;
 use failure::ResultExt;
+use futures::future::{self, FutureExt};
 use logger::prelude::*;
 
 use crate::chained_bft::persistent_storage::RecoveryData;
 use executor::StateComputeResult;
 use mirai_annotations::checked_precondition;
+use proto_conv::IntoProto;
+use protobuf::Message;
 use std::{
     collections::{vec_deque::VecDeque, HashMap},
-    sync::{Arc, RwLock},
+    sync::{Arc, Mutex, RwLock},
+};
+use types::{
+    crypto_proxies::{
+        LedgerInfoWithSignatures, ValidatorSigner, ValidatorVerifier, SIGNATURE_SCHEME,
+    },
+    ledger_info::LedgerInfo,
 };
-use types::{crypto_proxies::ValidatorSigner, ledger_info::LedgerInfo};
 
 #[cfg(test)]
 #[path = "block_store_test.rs"]
@@ -58,12 +70,21 @@ pub enum NeedFetchResult {
 ///             ╰--------------> D3
 pub struct BlockStore<T> {
     inner: Arc<RwLock<BlockTree<T>>>,
-    validator_signer: ValidatorSigner,
+    validator_signer: Arc<ValidatorSigner>,
+    /// The signers of the latest rotation of the consensus key, if any: the previous signer, the
+    /// handover round and the new signer.
+    rotated_signers: RwLock<Option<(Arc<ValidatorSigner>, Round, Arc<ValidatorSigner>)>>,
     state_computer: Arc<dyn StateComputer<Payload = T>>,
     enforce_increasing_timestamps: bool,
     /// The persistent storage backing up the in-memory data structure, every write should go
     /// through this before in-memory tree.
     storage: Arc<dyn PersistentStorage<T>>,
+    /// The ledger info with signatures that committed the root, i.e., the latest commit
+    /// certificate of the node.
+    latest_commit_cert: RwLock<LedgerInfoWithSignatures>,
+    /// The blocks ordered by a commit certificate (hence pruned from the tree) that are not
+    /// committed by the state computer yet, oldest first.
+    uncommitted_ordered_blocks: Mutex<VecDeque<Arc<ExecutedBlock<T>>>>,
 }
 
 impl<T: Payload> BlockStore<T> {
@@ -73,25 +94,29 @@ impl<T: Payload> BlockStore<T> {
         validator_signer: ValidatorSigner,
         state_computer: Arc<dyn StateComputer<Payload = T>>,
         enforce_increasing_timestamps: bool,
-        max_pruned_blocks_in_mem: usize,
+        pruning_policy: PruningPolicy,
     ) -> Self {
         let (root, blocks, quorum_certs) = initial_data.take();
+        let latest_commit_cert = RwLock::new(root.2.ledger_info().clone());
         let inner = Arc::new(RwLock::new(
             Self::build_block_tree(
                 root,
                 blocks,
                 quorum_certs,
                 Arc::clone(&state_computer),
-                max_pruned_blocks_in_mem,
+                pruning_policy,
             )
             .await,
         ));
         BlockStore {
             inner,
-            validator_signer,
+            validator_signer: Arc::new(validator_signer),
+            rotated_signers: RwLock::new(None),
             state_computer,
             enforce_increasing_timestamps,
             storage,
+            latest_commit_cert,
+            uncommitted_ordered_blocks: Mutex::new(VecDeque::new()),
         }
     }
 
@@ -100,19 +125,14 @@ impl<T: Payload> BlockStore<T> {
         blocks: Vec<Block<T>>,
         quorum_certs: Vec<QuorumCert>,
         state_computer: Arc<dyn StateComputer<Payload = T>>,
-        max_pruned_blocks_in_mem: usize,
+        pruning_policy: PruningPolicy,
     ) -> BlockTree<T> {
         let (root_block, root_qc, root_li) = (root.0, root.1, root.2);
 
         // root_compute_res will not used anywhere so use default value to simplify code.
         let root_compute_res = StateComputeResult::default();
         let executed_root_block = ExecutedBlock::new(root_block, root_compute_res);
-        let mut tree = BlockTree::new(
-            executed_root_block,
-            root_qc,
-            root_li,
-            max_pruned_blocks_in_mem,
-        );
+        let mut tree = BlockTree::new(executed_root_block, root_qc, root_li, pruning_policy);
         let quorum_certs = quorum_certs
             .into_iter()
             .map(|qc| (qc.certified_block_id(), qc))
@@ -147,12 +167,13 @@ impl<T: Payload> BlockStore<T> {
         blocks: Vec<Block<T>>,
         quorum_certs: Vec<QuorumCert>,
     ) {
+        let commit_cert = root.2.ledger_info().clone();
         let tree = Self::build_block_tree(
             root,
             blocks,
             quorum_certs,
             Arc::clone(&self.state_computer),
-            self.inner.read().unwrap().max_pruned_blocks_in_mem(),
+            self.inner.read().unwrap().pruning_policy(),
         )
         .await;
         let to_remove = self.inner.read().unwrap().get_all_block_id();
@@ -161,12 +182,67 @@ impl<T: Payload> BlockStore<T> {
             error!("fail to delete block: {:?}", e);
         }
         *self.inner.write().unwrap() = tree;
+        *self.latest_commit_cert.write().unwrap() = commit_cert;
     }
 
+    /// The signer the block store was created with, see `signer_for_round` for the signer of
+    /// the messages once the consensus key is rotated.
     pub fn signer(&self) -> &ValidatorSigner {
         &self.validator_signer
     }
 
+    /// The signer of the messages of the given round: the messages of the handover round of the
+    /// latest key rotation and the following rounds are signed with the new key.
+    pub fn signer_for_round(&self, round: Round) -> Arc<ValidatorSigner> {
+        match &*self.rotated_signers.read().unwrap() {
+            Some((previous_signer, handover_round, new_signer)) => {
+                if round >= *handover_round {
+                    Arc::clone(new_signer)
+                } else {
+                    Arc::clone(previous_signer)
+                }
+            }
+            None => Arc::clone(&self.validator_signer),
+        }
+    }
+
+    /// The signer of the latest consensus key, i.e., the key of the node in the validator set.
+    pub fn latest_signer(&self) -> Arc<ValidatorSigner> {
+        match &*self.rotated_signers.read().unwrap() {
+            Some((_, _, new_signer)) => Arc::clone(new_signer),
+            None => Arc::clone(&self.validator_signer),
+        }
+    }
+
+    /// Signs the messages of the handover round and the following rounds with the new signer,
+    /// the messages of the previous rounds with the latest signer so far. The signers are
+    /// persisted before the rotation takes effect, such that a restarted node keeps signing with
+    /// the new signer.
+    pub fn rotate_signer(
+        &self,
+        new_signer: ValidatorSigner,
+        handover_round: Round,
+    ) -> failure::Result<()> {
+        let previous_signer = self.latest_signer();
+        self.storage
+            .save_rotated_signers(&previous_signer, handover_round, &new_signer)
+            .with_context(|e| format!("Failed to persist the rotated signers: {}", e))?;
+        self.restore_rotated_signers(previous_signer, handover_round, Arc::new(new_signer));
+        Ok(())
+    }
+
+    /// Restores the signers of the latest rotation of the consensus key persisted before a
+    /// restart.
+    pub fn restore_rotated_signers(
+        &self,
+        previous_signer: Arc<ValidatorSigner>,
+        handover_round: Round,
+        new_signer: Arc<ValidatorSigner>,
+    ) {
+        *self.rotated_signers.write().unwrap() =
+            Some((previous_signer, handover_round, new_signer));
+    }
+
     /// Execute and insert a block if it passes all validation tests.
     /// Returns the Arc to the block kept in the block store after persisting it to storage
     ///
@@ -220,7 +296,9 @@ impl<T: Payload> BlockStore<T> {
         // LedgerInfo doesn't carry the information about the round of the committed block. However,
         // the 3-chain safety rules specify that the round of the committed block must be
         // certified_block_round() - 2. In case root().round() is greater than that the committed
-        // block carried by LI is older than my current commit.
+        // block carried by LI is older than my current commit. With the 2-chain commit rule the
+        // committed block might be the one following the root, which is retrieved along with the
+        // certified block without a sync.
         !(self.block_exists(committed_block_id)
             || self.root().round() + 2 >= qc.certified_block_round())
     }
@@ -277,11 +355,70 @@ impl<T: Payload> BlockStore<T> {
     /// Different execution ids are treated as different blocks (e.g., if some proposal is
     /// executed in a non-deterministic fashion due to a bug, then the votes for execution result
     /// A and the votes for execution result B are aggregated separately).
-    pub fn insert_vote(&self, vote_msg: VoteMsg, min_votes_for_qc: usize) -> VoteReceptionResult {
-        self.inner
-            .write()
-            .unwrap()
-            .insert_vote(&vote_msg, min_votes_for_qc)
+    /// The signatures of the votes are verified in batch once a quorum of votes is aggregated.
+    /// If the signature scheme supports it, the signatures of a new QC are aggregated into a
+    /// single signature.
+    pub fn insert_vote(
+        &self,
+        vote_msg: VoteMsg,
+        validator: &ValidatorVerifier,
+    ) -> VoteReceptionResult {
+        let result = self.inner.write().unwrap().insert_vote(
+            &vote_msg,
+            validator.quorum_size(),
+            Some(validator),
+        );
+        Self::aggregate_new_qc_signatures(result, validator)
+    }
+
+    /// Same as `insert_vote` for a vote whose signature has already been verified, e.g., by the
+    /// signature verifier of the network: the signatures are not verified again once a quorum of
+    /// votes is aggregated.
+    pub fn insert_verified_vote(
+        &self,
+        vote_msg: VoteMsg,
+        validator: &ValidatorVerifier,
+    ) -> VoteReceptionResult {
+        let result =
+            self.inner
+                .write()
+                .unwrap()
+                .insert_vote(&vote_msg, validator.quorum_size(), None);
+        Self::aggregate_new_qc_signatures(result, validator)
+    }
+
+    /// Replaces the individual signatures of a new QC with their aggregation if the signature
+    /// scheme supports it. The QC keeps its individual signatures if the aggregation fails.
+    fn aggregate_new_qc_signatures(
+        result: VoteReceptionResult,
+        validator: &ValidatorVerifier,
+    ) -> VoteReceptionResult {
+        match result {
+            VoteReceptionResult::NewQuorumCertificate(qc)
+                if SIGNATURE_SCHEME.supports_aggregation() =>
+            {
+                let mut aggregated_qc = qc.as_ref().clone();
+                match aggregated_qc
+                    .aggregate_signatures(&validator.at_round(qc.certified_block_round()))
+                {
+                    Ok(()) => VoteReceptionResult::NewQuorumCertificate(Arc::new(aggregated_qc)),
+                    Err(e) => {
+                        error!(
+                            "Failed to aggregate the signatures of the QC for block {}: {:?}",
+                            qc.certified_block_id(),
+                            e
+                        );
+                        VoteReceptionResult::NewQuorumCertificate(qc)
+                    }
+                }
+            }
+            result => result,
+        }
+    }
+
+    /// The votes for the blocks of the given round that have not formed a QC yet.
+    pub fn pending_votes(&self, round: Round) -> Vec<VoteMsg> {
+        self.inner.read().unwrap().pending_votes(round)
     }
 
     /// Prune the tree up to next_root_id (keep next_root_id's block).  Any branches not part of
@@ -301,15 +438,7 @@ impl<T: Payload> BlockStore<T> {
             .read()
             .unwrap()
             .find_blocks_to_prune(next_root_id);
-        if let Err(e) = self
-            .storage
-            .prune_tree(id_to_remove.clone().into_iter().collect())
-        {
-            // it's fine to fail here, as long as the commit succeeds, the next restart will clean
-            // up dangling blocks, and we need to prune the tree to keep the root consistent with
-            // executor.
-            error!("fail to delete block: {:?}", e);
-        }
+        self.prune_storage(id_to_remove.clone());
         self.inner
             .write()
             .unwrap()
@@ -317,26 +446,89 @@ impl<T: Payload> BlockStore<T> {
         id_to_remove
     }
 
-    /// If block id information is found, returns the ledger info placeholder, otherwise, return
-    /// a placeholder with info of the genesis block.
-    pub fn ledger_info_placeholder(&self, id: Option<HashValue>) -> LedgerInfo {
+    /// Moves the root of the tree to the given block, ordered by a commit certificate, without
+    /// waiting for the state computer to commit it: the pruned blocks are kept in the persistent
+    /// storage until the commit is persisted (see `prune_storage`), such that a restart recovers
+    /// the blocks of the pending commits.
+    ///
+    /// Returns the newly ordered blocks, i.e., the path from the previous root to the new root,
+    /// and the ids of the blocks removed from the tree. The ordered blocks count as uncommitted
+    /// until `complete_commit` is called.
+    pub fn order_blocks(
+        &self,
+        next_root_id: HashValue,
+    ) -> (Vec<Arc<ExecutedBlock<T>>>, VecDeque<HashValue>) {
+        let ordered_blocks = self.path_from_root(next_root_id).unwrap_or_else(Vec::new);
+        let id_to_remove = self
+            .inner
+            .read()
+            .unwrap()
+            .find_blocks_to_prune(next_root_id);
+        self.inner
+            .write()
+            .unwrap()
+            .process_pruned_blocks(next_root_id, id_to_remove.clone());
+        // The blocks are committed in order, starting from the oldest one.
+        self.uncommitted_ordered_blocks
+            .lock()
+            .unwrap()
+            .extend(ordered_blocks.iter().rev().cloned());
+        (ordered_blocks, id_to_remove)
+    }
+
+    /// Removes the blocks pruned from the tree from the persistent storage.
+    pub fn prune_storage(&self, block_ids: VecDeque<HashValue>) {
+        if let Err(e) = self.storage.prune_tree(block_ids.into_iter().collect()) {
+            // it's fine to fail here, as long as the commit succeeds, the next restart will clean
+            // up dangling blocks, and we need to prune the tree to keep the root consistent with
+            // executor.
+            error!("fail to delete block: {:?}", e);
+        }
+    }
+
+    /// Records that the state computer is done with the commit of the given number of ordered
+    /// blocks.
+    pub fn complete_commit(&self, num_blocks: usize) {
+        let mut uncommitted_ordered_blocks = self.uncommitted_ordered_blocks.lock().unwrap();
+        for _ in 0..num_blocks {
+            uncommitted_ordered_blocks.pop_front();
+        }
+    }
+
+    /// Records the ledger info with signatures of a newly committed block.
+    pub fn set_latest_commit_cert(&self, commit_cert: LedgerInfoWithSignatures) {
+        *self.latest_commit_cert.write().unwrap() = commit_cert;
+    }
+
+    /// The ledger info with signatures of the latest block committed by the node, which is a
+    /// finality proof of the committed state.
+    pub fn latest_commit_cert(&self) -> LedgerInfoWithSignatures {
+        self.latest_commit_cert.read().unwrap().clone()
+    }
+
+    /// If block id information is found, returns the ledger info placeholder of the given epoch,
+    /// otherwise, return a placeholder of the given epoch with info of the genesis block.
+    /// The placeholder carries the validator set of the next epoch in case the execution of the
+    /// block reconfigures the validators.
+    pub fn ledger_info_placeholder(&self, id: Option<HashValue>, epoch: u64) -> LedgerInfo {
         let block_id = match id {
-            None => return Self::zero_ledger_info_placeholder(),
+            None => return Self::zero_ledger_info_placeholder(epoch),
             Some(id) => id,
         };
         let block = match self.get_block(block_id) {
             Some(b) => b,
             None => {
-                return Self::zero_ledger_info_placeholder();
+                return Self::zero_ledger_info_placeholder(epoch);
             }
         };
-        let (state_id, version) = match self.get_compute_result(block_id) {
+        let (state_id, version, next_validator_set) = match self.get_compute_result(block_id) {
             Some(compute_state) => (
                 compute_state.executed_state.state_id,
                 compute_state.executed_state.version,
+                compute_state.executed_state.validators.clone(),
             ),
             None => {
-                return Self::zero_ledger_info_placeholder();
+                return Self::zero_ledger_info_placeholder(epoch);
             }
         };
         LedgerInfo::new(
@@ -344,22 +536,22 @@ impl<T: Payload> BlockStore<T> {
             state_id,
             HashValue::zero(),
             block_id,
-            0, // TODO [Reconfiguration] use the real epoch number.
+            epoch,
             block.timestamp_usecs(),
-            None,
+            next_validator_set,
         )
     }
 
     /// Used in case we're using a ledger info just as a placeholder for signing the votes / QCs
     /// and there is no real block committed.
-    /// It's all pretty much zeroes.
-    fn zero_ledger_info_placeholder() -> LedgerInfo {
+    /// It's all pretty much zeroes but the epoch, which the votes are checked against.
+    fn zero_ledger_info_placeholder(epoch: u64) -> LedgerInfo {
         LedgerInfo::new(
             0,
             HashValue::zero(),
             HashValue::zero(),
             HashValue::zero(),
-            0,
+            epoch,
             0,
             None,
         )
@@ -441,7 +633,7 @@ impl<T: Payload> BlockReader for BlockStore<T> {
             round,
             timestamp_usecs,
             quorum_cert,
-            &self.validator_signer,
+            &self.signer_for_round(round),
         )
     }
 
@@ -456,6 +648,75 @@ impl<T: Payload> BlockReader for BlockStore<T> {
     fn highest_ledger_info(&self) -> Arc<QuorumCert> {
         self.inner.read().unwrap().highest_ledger_info()
     }
+
+    fn num_uncommitted_ordered_blocks(&self) -> usize {
+        self.uncommitted_ordered_blocks.lock().unwrap().len()
+    }
+
+    fn uncommitted_ordered_blocks(&self) -> Vec<Arc<ExecutedBlock<T>>> {
+        self.uncommitted_ordered_blocks
+            .lock()
+            .unwrap()
+            .iter()
+            .cloned()
+            .collect()
+    }
+
+    fn tree_blocks(&self) -> Vec<Arc<ExecutedBlock<T>>> {
+        self.inner.read().unwrap().tree_blocks()
+    }
+
+    fn pruned_blocks(&self) -> Vec<Arc<ExecutedBlock<T>>> {
+        self.inner.read().unwrap().pruned_blocks()
+    }
+
+    fn all_quorum_certs(&self) -> Vec<Arc<QuorumCert>> {
+        self.inner.read().unwrap().all_quorum_certs()
+    }
+
+    fn export_dot(&self) -> String {
+        self.inner.read().unwrap().export_dot()
+    }
+}
+
+impl<T: Payload> CommitCertificateProvider for BlockStore<T> {
+    fn latest_commit_certificate(&self) -> Option<Vec<u8>> {
+        self.latest_commit_cert()
+            .into_proto()
+            .write_to_bytes()
+            .map_err(|e| error!("Failed to serialize the latest commit certificate: {:?}", e))
+            .ok()
+    }
+}
+
+// All the blocks are currently kept in memory, the lookups complete right away.
+impl<T: Payload> AsyncBlockReader for BlockStore<T> {
+    type Payload = T;
+
+    fn block_exists_async(&self, block_id: HashValue) -> BlockReadFuture<'_, bool> {
+        future::ready(self.block_exists(block_id)).boxed()
+    }
+
+    fn get_block_async(
+        &self,
+        block_id: HashValue,
+    ) -> BlockReadFuture<'_, Option<Arc<ExecutedBlock<T>>>> {
+        future::ready(self.get_block(block_id)).boxed()
+    }
+
+    fn get_quorum_cert_for_block_async(
+        &self,
+        block_id: HashValue,
+    ) -> BlockReadFuture<'_, Option<Arc<QuorumCert>>> {
+        future::ready(self.get_quorum_cert_for_block(block_id)).boxed()
+    }
+
+    fn path_from_root_async(
+        &self,
+        block_id: HashValue,
+    ) -> BlockReadFuture<'_, Option<Vec<Arc<ExecutedBlock<T>>>>> {
+        future::ready(self.path_from_root(block_id)).boxed()
+    }
 }
 
 #[cfg(any(test, feature = "fuzzing"))]
@@ -476,10 +737,19 @@ impl<T: Payload> BlockStore<T> {
         self.inner.read().unwrap().pruned_blocks_in_mem()
     }
 
-    /// Helper to insert vote and qc
+    /// The size of the payloads of the pruned blocks that are still available in memory
+    pub(super) fn pruned_payload_bytes_in_mem(&self) -> u64 {
+        self.inner.read().unwrap().pruned_payload_bytes_in_mem()
+    }
+
+    /// Helper to insert vote and qc, the signatures of the votes are not verified.
     /// Can't be used in production, because production insertion potentially requires state sync
     pub fn insert_vote_and_qc(&self, vote_msg: VoteMsg, qc_size: usize) -> VoteReceptionResult {
-        let r = self.insert_vote(vote_msg, qc_size);
+        let r = self
+            .inner
+            .write()
+            .unwrap()
+            .insert_vote(&vote_msg, qc_size, None);
         if let VoteReceptionResult::NewQuorumCertificate(ref qc) = r {
             self.insert_single_quorum_cert(qc.as_ref().clone()).unwrap();
         }
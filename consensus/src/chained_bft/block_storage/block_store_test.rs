@@ -2,8 +2,11 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::chained_bft::{
-    block_storage::{BlockReader, BlockStore, NeedFetchResult, VoteReceptionResult},
-    common::Author,
+    block_storage::{
+        AsyncBlockReader, BlockReader, BlockStore, NeedFetchResult, PruningPolicy,
+        VoteReceptionResult,
+    },
+    common::{Author, Round},
     consensus_types::{
         block::{block_test, Block, ExecutedBlock},
         quorum_cert::QuorumCert,
@@ -11,7 +14,8 @@ use crate::chained_bft::{
         vote_msg::VoteMsg,
     },
     test_utils::{
-        build_empty_tree, build_empty_tree_with_custom_signing, placeholder_certificate_for_block,
+        build_empty_tree, build_empty_tree_with_custom_signing,
+        build_empty_tree_with_pruning_policy, placeholder_certificate_for_block,
         placeholder_ledger_info, TreeInserter,
     },
 };
@@ -20,7 +24,9 @@ use futures::executor::block_on;
 use proptest::prelude::*;
 use std::{cmp::min, collections::HashSet, sync::Arc};
 use types::{
-    account_address::AccountAddress, crypto_proxies::ValidatorSigner, ledger_info::LedgerInfo,
+    account_address::AccountAddress,
+    crypto_proxies::{ValidatorSigner, ValidatorVerifier},
+    ledger_info::LedgerInfo,
 };
 
 fn build_simple_tree() -> (
@@ -218,6 +224,220 @@ proptest! {
     }
 }
 
+/// An operation applied to the block store by `test_block_tree_invariants`. The indices pick
+/// the block among the candidates of the operation at the time it is applied.
+#[derive(Clone, Debug)]
+enum TreeOp {
+    /// Inserts a child of a block of the tree (along with the QC of the parent), the round of
+    /// the child is greater than all the rounds so far by the given gap.
+    InsertBlock(prop::sample::Index, Round),
+    /// Certifies a block of the tree that is not certified yet.
+    InsertQc(prop::sample::Index),
+    /// Orders a certified block on the path to the highest certified block and completes its
+    /// commit, as the event processor does.
+    Commit(prop::sample::Index),
+    /// Prunes the tree up to a block on the path to the highest certified block.
+    Prune(prop::sample::Index),
+}
+
+fn arb_tree_op() -> impl Strategy<Value = TreeOp> {
+    prop_oneof![
+        3 => (any::<prop::sample::Index>(), 1..3u64)
+            .prop_map(|(parent, round_gap)| TreeOp::InsertBlock(parent, round_gap)),
+        2 => any::<prop::sample::Index>().prop_map(TreeOp::InsertQc),
+        1 => any::<prop::sample::Index>().prop_map(TreeOp::Commit),
+        1 => any::<prop::sample::Index>().prop_map(TreeOp::Prune),
+    ]
+}
+
+fn arb_pruning_policy() -> impl Strategy<Value = PruningPolicy> {
+    prop_oneof![
+        (0..5usize).prop_map(PruningPolicy::MaxBlocks),
+        (0..64u64).prop_map(PruningPolicy::MaxPayloadBytes),
+    ]
+}
+
+/// Applies the operations of `test_block_tree_invariants` to a block store and checks the
+/// invariants of its tree against the history of the operations.
+struct TreeOpRunner {
+    block_store: Arc<BlockStore<Vec<usize>>>,
+    pruning_policy: PruningPolicy,
+    inserter: TreeInserter,
+    last_round: Round,
+    /// The chain of the blocks committed so far, from genesis to the root.
+    committed: Vec<Arc<ExecutedBlock<Vec<usize>>>>,
+    /// All the blocks inserted so far, including genesis.
+    inserted: Vec<Arc<ExecutedBlock<Vec<usize>>>>,
+}
+
+impl TreeOpRunner {
+    fn new(pruning_policy: PruningPolicy) -> Self {
+        let block_store =
+            build_empty_tree_with_pruning_policy(ValidatorSigner::random(None), pruning_policy);
+        let genesis = block_store.root();
+        Self {
+            inserter: TreeInserter::new(block_store.clone()),
+            block_store,
+            pruning_policy,
+            last_round: 0,
+            committed: vec![genesis.clone()],
+            inserted: vec![genesis],
+        }
+    }
+
+    // The blocks between the root and the highest certified block, the latter included: only
+    // these blocks can be committed by the 3-chain (or 2-chain) rule.
+    fn path_to_hqc(&self) -> Vec<Arc<ExecutedBlock<Vec<usize>>>> {
+        self.block_store
+            .path_from_root(self.block_store.highest_certified_block().id())
+            .expect("The highest certified block must be in the tree")
+    }
+
+    fn apply(&mut self, op: TreeOp) {
+        match op {
+            TreeOp::InsertBlock(parent, round_gap) => {
+                let tree_blocks = self.block_store.tree_blocks();
+                let parent = &tree_blocks[parent.index(tree_blocks.len())];
+                self.last_round += round_gap;
+                let block = if parent.round() == 0 {
+                    self.inserter.insert_block_with_qc(
+                        QuorumCert::certificate_for_genesis(),
+                        parent,
+                        self.last_round,
+                    )
+                } else {
+                    self.inserter.insert_block(parent, self.last_round)
+                };
+                self.inserted.push(block);
+            }
+            TreeOp::InsertQc(block) => {
+                let uncertified: Vec<_> = self
+                    .block_store
+                    .tree_blocks()
+                    .into_iter()
+                    .filter(|block| {
+                        self.block_store
+                            .get_quorum_cert_for_block(block.id())
+                            .is_none()
+                    })
+                    .collect();
+                if uncertified.is_empty() {
+                    return;
+                }
+                let block = &uncertified[block.index(uncertified.len())];
+                self.block_store
+                    .insert_single_quorum_cert(self.inserter.create_qc_for_block(block))
+                    .unwrap();
+            }
+            TreeOp::Commit(block) => {
+                let certified: Vec<_> = self
+                    .path_to_hqc()
+                    .into_iter()
+                    .filter(|block| {
+                        self.block_store
+                            .get_quorum_cert_for_block(block.id())
+                            .is_some()
+                    })
+                    .collect();
+                if certified.is_empty() {
+                    return;
+                }
+                let block = &certified[block.index(certified.len())];
+                let (ordered_blocks, pruned_block_ids) = self.block_store.order_blocks(block.id());
+                self.committed.extend(ordered_blocks.iter().rev().cloned());
+                self.block_store.prune_storage(pruned_block_ids);
+                self.block_store.complete_commit(ordered_blocks.len());
+            }
+            TreeOp::Prune(block) => {
+                let candidates = self.path_to_hqc();
+                if candidates.is_empty() {
+                    return;
+                }
+                let block = &candidates[block.index(candidates.len())];
+                let path = self.block_store.path_from_root(block.id()).unwrap();
+                self.block_store.prune_tree(block.id());
+                self.committed.extend(path.into_iter().rev());
+            }
+        }
+    }
+
+    fn check_invariants(&self, previous_root_round: Round) {
+        let root = self.block_store.root();
+        let tree_blocks = self.block_store.tree_blocks();
+        let pruned_blocks = self.block_store.pruned_blocks();
+
+        // Root monotonicity: the root only moves forward, to the last committed block.
+        assert!(root.round() >= previous_root_round);
+        assert_eq!(tree_blocks[0], root);
+        assert_eq!(self.committed.last(), Some(&root));
+        assert_eq!(self.block_store.num_uncommitted_ordered_blocks(), 0);
+
+        // Single committed chain: every committed block is the parent of the next one.
+        for pair in self.committed.windows(2) {
+            assert_eq!(pair[1].parent_id(), pair[0].id());
+            assert!(pair[1].round() > pair[0].round());
+        }
+
+        // The tree is made of the root and its descendants, every block comes after its parent.
+        let mut tree_ids = HashSet::new();
+        tree_ids.insert(root.id());
+        for block in &tree_blocks[1..] {
+            assert!(tree_ids.contains(&block.parent_id()));
+            assert!(block.round() > root.round());
+            assert!(tree_ids.insert(block.id()));
+            assert!(self.block_store.path_from_root(block.id()).is_some());
+        }
+        assert!(tree_ids.contains(&self.block_store.highest_certified_block().id()));
+
+        // Pruned blocks are unreachable: the ones kept in memory are not part of the tree
+        // anymore, the other ones are gone.
+        let pruned_ids: HashSet<_> = pruned_blocks.iter().map(|block| block.id()).collect();
+        assert_eq!(pruned_ids.len(), pruned_blocks.len());
+        for block in &self.inserted {
+            let in_tree = tree_ids.contains(&block.id());
+            let pruned = pruned_ids.contains(&block.id());
+            assert!(!(in_tree && pruned));
+            assert_eq!(self.block_store.block_exists(block.id()), in_tree || pruned);
+            if !in_tree {
+                assert_eq!(self.block_store.path_from_root(block.id()), None);
+            }
+        }
+        match self.pruning_policy {
+            PruningPolicy::MaxBlocks(max_pruned_blocks) => {
+                assert!(pruned_blocks.len() <= max_pruned_blocks);
+            }
+            PruningPolicy::MaxPayloadBytes(max_pruned_payload_bytes) => {
+                let pruned_payload_bytes: u64 = pruned_blocks
+                    .iter()
+                    .map(|block| block.block().payload_size())
+                    .sum();
+                assert!(pruned_payload_bytes <= max_pruned_payload_bytes);
+            }
+        }
+
+        // No orphaned QCs: every QC certifies a block kept in memory.
+        for qc in self.block_store.all_quorum_certs() {
+            let block_id = qc.certified_block_id();
+            assert!(tree_ids.contains(&block_id) || pruned_ids.contains(&block_id));
+        }
+    }
+}
+
+proptest! {
+    #[test]
+    fn test_block_tree_invariants(
+        pruning_policy in arb_pruning_policy(),
+        ops in proptest::collection::vec(arb_tree_op(), 1..50),
+    ) {
+        let mut runner = TreeOpRunner::new(pruning_policy);
+        for op in ops {
+            let previous_root_round = runner.block_store.root().round();
+            runner.apply(op);
+            runner.check_invariants(previous_root_round);
+        }
+    }
+}
+
 #[test]
 fn test_block_store_prune() {
     let (blocks, block_store) = build_simple_tree();
@@ -277,6 +497,42 @@ fn test_block_store_prune() {
     assert_eq!(block_store.child_links(), block_store.len() - 1);
 }
 
+// The line declaring the node of the given block in the DOT rendering of a tree.
+fn dot_node<'a>(dot: &'a str, block_id: HashValue) -> &'a str {
+    let node = format!("\"{:x}\" [", block_id);
+    dot.lines()
+        .find(|line| line.trim_start().starts_with(&node))
+        .expect("Block not rendered")
+}
+
+#[test]
+fn test_export_dot() {
+    let (blocks, block_store) = build_simple_tree();
+    let dot = block_store.export_dot();
+    assert!(dot.starts_with("digraph BlockTree {"));
+    // Every block but the root points to its parent.
+    assert_eq!(dot.matches(" -> ").count(), blocks.len() - 1);
+    assert!(dot.contains(&format!(
+        "\"{:x}\" -> \"{:x}\"",
+        blocks[2].id(),
+        blocks[1].id()
+    )));
+    assert!(dot_node(&dot, blocks[0].id()).contains("\\nroot"));
+    // B1 is certified by B2 and C1.
+    assert!(dot_node(&dot, blocks[4].id()).contains("\\nHQC"));
+    assert!(dot_node(&dot, blocks[4].id()).contains("bold"));
+    assert!(!dot_node(&dot, blocks[3].id()).contains("\\nQC"));
+
+    // Prune up to block A1: the pruned blocks kept in memory are still rendered.
+    block_store.prune_tree(blocks[1].id());
+    let dot = block_store.export_dot();
+    assert!(dot_node(&dot, blocks[1].id()).contains("\\nroot"));
+    for pruned in &[&blocks[0], &blocks[4], &blocks[5], &blocks[6]] {
+        assert!(dot_node(&dot, pruned.id()).contains("\\npruned"));
+    }
+    assert!(!dot_node(&dot, blocks[2].id()).contains("\\npruned"));
+}
+
 #[test]
 fn test_block_tree_gc() {
     // build a tree with 100 nodes, max_pruned_nodes_in_mem = 10
@@ -306,6 +562,48 @@ fn test_block_tree_gc() {
     }
 }
 
+#[test]
+fn test_block_tree_gc_payload_budget() {
+    // The pruned blocks are kept as long as their payloads fit in 10 bytes.
+    let max_pruned_payload_bytes = 10;
+    let block_store = build_empty_tree_with_pruning_policy(
+        ValidatorSigner::random(None),
+        PruningPolicy::MaxPayloadBytes(max_pruned_payload_bytes),
+    );
+    let genesis = block_store.root();
+    let mut inserter = TreeInserter::new(block_store.clone());
+    let mut cur_node =
+        inserter.insert_block_with_qc(QuorumCert::certificate_for_genesis(), &genesis, 1);
+    let mut added_blocks = vec![cur_node.clone()];
+    for round in 2..30 {
+        cur_node = inserter.insert_block(&cur_node, round);
+        added_blocks.push(cur_node.clone());
+    }
+
+    let mut pruned_blocks = vec![];
+    for block in &added_blocks {
+        pruned_blocks.push(block_store.root());
+        block_store.prune_tree(block.id());
+        // The most recently pruned blocks that fit in the budget are kept.
+        let mut expected_blocks = 0;
+        let mut expected_bytes = 0;
+        for pruned in pruned_blocks.iter().rev() {
+            let payload_size = pruned.block().payload_size();
+            if expected_bytes + payload_size > max_pruned_payload_bytes {
+                break;
+            }
+            expected_blocks += 1;
+            expected_bytes += payload_size;
+        }
+        assert_eq!(block_store.pruned_blocks_in_mem(), expected_blocks);
+        assert_eq!(block_store.pruned_payload_bytes_in_mem(), expected_bytes);
+        let oldest_kept = pruned_blocks.len() - expected_blocks;
+        for (i, pruned) in pruned_blocks.iter().enumerate() {
+            assert_eq!(block_store.block_exists(pruned.id()), i >= oldest_kept);
+        }
+    }
+}
+
 #[test]
 fn test_path_from_root() {
     let block_store = build_empty_tree();
@@ -327,6 +625,54 @@ fn test_path_from_root() {
     assert_eq!(block_store.path_from_root(genesis.id()), None);
 }
 
+#[test]
+fn test_order_blocks() {
+    let block_store = build_empty_tree();
+    let genesis = block_store.get_block(block_store.root().id()).unwrap();
+    let mut inserter = TreeInserter::new(block_store.clone());
+    let b1 = inserter.insert_block_with_qc(QuorumCert::certificate_for_genesis(), &genesis, 1);
+    let b2 = inserter.insert_block(&b1, 2);
+    let b3 = inserter.insert_block(&b2, 3);
+
+    // The root moves to the ordered block before the commit completes.
+    let (ordered_blocks, pruned_block_ids) = block_store.order_blocks(b2.id());
+    assert_eq!(ordered_blocks, vec![b2.clone(), b1.clone()]);
+    assert_eq!(pruned_block_ids.len(), 2);
+    assert_eq!(block_store.root().id(), b2.id());
+    assert_eq!(block_store.path_from_root(b3.id()), Some(vec![b3.clone()]));
+    assert_eq!(block_store.num_uncommitted_ordered_blocks(), 2);
+
+    block_store.prune_storage(pruned_block_ids);
+    block_store.complete_commit(ordered_blocks.len());
+    assert_eq!(block_store.num_uncommitted_ordered_blocks(), 0);
+}
+
+#[test]
+fn test_async_block_reader() {
+    let (blocks, block_store) = build_simple_tree();
+    let genesis_block_id = blocks[0].id();
+    let a3 = &blocks[3];
+    let missing_block_id = HashValue::random();
+    block_on(async move {
+        assert!(block_store.block_exists_async(a3.id()).await);
+        assert!(!block_store.block_exists_async(missing_block_id).await);
+        assert_eq!(block_store.get_block_async(a3.id()).await, Some(a3.clone()));
+        assert_eq!(block_store.get_block_async(missing_block_id).await, None);
+        assert_eq!(
+            block_store.get_quorum_cert_for_block_async(a3.id()).await,
+            block_store.get_quorum_cert_for_block(a3.id())
+        );
+        assert_eq!(
+            block_store.path_from_root_async(a3.id()).await,
+            block_store.path_from_root(a3.id())
+        );
+        assert_eq!(
+            block_store.path_from_root_async(genesis_block_id).await,
+            Some(vec![])
+        );
+    });
+}
+
 #[test]
 fn test_insert_vote() {
     // Set up enough different authors to support different votes for the same block.
@@ -409,6 +755,201 @@ fn test_insert_vote() {
     assert_eq!(block_qc.certified_block_id(), block.id());
 }
 
+#[test]
+fn test_pending_votes() {
+    let signers: Vec<_> = (0..3).map(|i| ValidatorSigner::random([i; 32])).collect();
+    let block_store = build_empty_tree_with_custom_signing(signers[0].clone());
+    let genesis = block_store.root();
+    let mut inserter = TreeInserter::new(block_store.clone());
+    let block = inserter.insert_block_with_qc(QuorumCert::certificate_for_genesis(), &genesis, 1);
+    let vote_data = VoteData::new(
+        block.id(),
+        block_store
+            .get_compute_result(block.id())
+            .unwrap()
+            .executed_state
+            .state_id,
+        block.round(),
+        block.quorum_cert().parent_block_id(),
+        block.quorum_cert().parent_block_round(),
+        block.quorum_cert().grandparent_block_id(),
+        block.quorum_cert().grandparent_block_round(),
+    );
+    let votes: Vec<_> = signers
+        .iter()
+        .map(|signer| {
+            VoteMsg::new(
+                vote_data.clone(),
+                signer.author(),
+                placeholder_ledger_info(),
+                signer,
+            )
+        })
+        .collect();
+
+    assert!(block_store.pending_votes(block.round()).is_empty());
+    for vote in votes.iter().take(2) {
+        block_store.insert_vote_and_qc(vote.clone(), 3);
+    }
+    let pending_votes = block_store.pending_votes(block.round());
+    assert_eq!(pending_votes.len(), 2);
+    assert!(pending_votes.contains(&votes[0]));
+    assert!(pending_votes.contains(&votes[1]));
+    assert!(block_store.pending_votes(block.round() + 1).is_empty());
+
+    // The votes are no longer pending once aggregated into a QC.
+    block_store.insert_vote_and_qc(votes[2].clone(), 3);
+    assert!(block_store.pending_votes(block.round()).is_empty());
+}
+
+#[test]
+fn test_insert_vote_batch_verification() {
+    let signers: Vec<_> = (0..3).map(|i| ValidatorSigner::random([i; 32])).collect();
+    let validator = ValidatorVerifier::new(
+        signers
+            .iter()
+            .map(|signer| (signer.author(), signer.public_key()))
+            .collect(),
+    );
+    let block_store = build_empty_tree_with_custom_signing(signers[0].clone());
+    let genesis = block_store.root();
+    let mut inserter = TreeInserter::new(block_store.clone());
+    let block = inserter.insert_block_with_qc(QuorumCert::certificate_for_genesis(), &genesis, 1);
+    let vote_data = VoteData::new(
+        block.id(),
+        block_store
+            .get_compute_result(block.id())
+            .unwrap()
+            .executed_state
+            .state_id,
+        block.round(),
+        block.quorum_cert().parent_block_id(),
+        block.quorum_cert().parent_block_round(),
+        block.quorum_cert().grandparent_block_id(),
+        block.quorum_cert().grandparent_block_round(),
+    );
+
+    for (i, signer) in signers.iter().enumerate().take(2) {
+        let vote_msg = VoteMsg::new(
+            vote_data.clone(),
+            signer.author(),
+            placeholder_ledger_info(),
+            signer,
+        );
+        assert_eq!(
+            block_store.insert_vote(vote_msg, &validator),
+            VoteReceptionResult::VoteAdded(i + 1)
+        );
+    }
+
+    // The last vote of the quorum is not signed by its author: the batched verification fails
+    // and the forged vote is dropped.
+    let forged_vote = VoteMsg::new(
+        vote_data.clone(),
+        signers[2].author(),
+        placeholder_ledger_info(),
+        &signers[0],
+    );
+    assert_eq!(
+        block_store.insert_vote(forged_vote, &validator),
+        VoteReceptionResult::InvalidVotesDropped(2)
+    );
+
+    // The author can still cast its valid vote.
+    let vote_msg = VoteMsg::new(
+        vote_data,
+        signers[2].author(),
+        placeholder_ledger_info(),
+        &signers[2],
+    );
+    match block_store.insert_vote(vote_msg, &validator) {
+        VoteReceptionResult::NewQuorumCertificate(qc) => {
+            assert_eq!(qc.certified_block_id(), block.id());
+            // The Ed25519 signatures of the voters are not aggregated.
+            assert!(qc.ledger_info().aggregated_signature().is_none());
+            assert_eq!(qc.ledger_info().signatures().len(), 3);
+            assert!(qc.verify(&validator).is_ok());
+        }
+        _ => {
+            panic!("QC not formed!");
+        }
+    }
+}
+
+#[test]
+fn test_forged_vote_does_not_evict_pending_vote() {
+    let signers: Vec<_> = (0..4).map(|i| ValidatorSigner::random([i; 32])).collect();
+    let validator = ValidatorVerifier::new(
+        signers
+            .iter()
+            .map(|signer| (signer.author(), signer.public_key()))
+            .collect(),
+    );
+    let block_store = build_empty_tree_with_custom_signing(signers[0].clone());
+    let genesis = block_store.root();
+    let mut inserter = TreeInserter::new(block_store.clone());
+    let block = inserter.insert_block_with_qc(QuorumCert::certificate_for_genesis(), &genesis, 1);
+    let vote_data = VoteData::new(
+        block.id(),
+        block_store
+            .get_compute_result(block.id())
+            .unwrap()
+            .executed_state
+            .state_id,
+        block.round(),
+        block.quorum_cert().parent_block_id(),
+        block.quorum_cert().parent_block_round(),
+        block.quorum_cert().grandparent_block_id(),
+        block.quorum_cert().grandparent_block_round(),
+    );
+    let made_up_vote_data = VoteData::new(
+        HashValue::random(),
+        HashValue::random(),
+        block.round(),
+        block.quorum_cert().parent_block_id(),
+        block.quorum_cert().parent_block_round(),
+        block.quorum_cert().grandparent_block_id(),
+        block.quorum_cert().grandparent_block_round(),
+    );
+    let vote = |vote_data: &VoteData, author: usize, signer: usize| {
+        VoteMsg::new(
+            vote_data.clone(),
+            signers[author].author(),
+            placeholder_ledger_info(),
+            &signers[signer],
+        )
+    };
+
+    assert_eq!(
+        block_store.insert_vote(vote(&vote_data, 0, 0), &validator),
+        VoteReceptionResult::VoteAdded(1)
+    );
+    // A forged vote for a made up block doesn't replace the pending vote of its author.
+    assert_eq!(
+        block_store.insert_vote(vote(&made_up_vote_data, 0, 3), &validator),
+        VoteReceptionResult::InvalidVotesDropped(0)
+    );
+    // A forged vote sent before the vote of its author doesn't get the valid vote rejected as a
+    // duplicate: the forged vote is replaced.
+    assert_eq!(
+        block_store.insert_vote(vote(&vote_data, 1, 3), &validator),
+        VoteReceptionResult::VoteAdded(2)
+    );
+    assert_eq!(
+        block_store.insert_vote(vote(&vote_data, 1, 1), &validator),
+        VoteReceptionResult::VoteAdded(2)
+    );
+    match block_store.insert_vote(vote(&vote_data, 2, 2), &validator) {
+        VoteReceptionResult::NewQuorumCertificate(qc) => {
+            assert_eq!(qc.certified_block_id(), block.id());
+            assert!(qc.verify(&validator).is_ok());
+        }
+        _ => {
+            panic!("QC not formed!");
+        }
+    }
+}
+
 #[test]
 /// Verify that votes are properly aggregated based on their LedgerInfo digest
 fn test_vote_aggregation() {
@@ -3,8 +3,8 @@
 
 use crate::{
     chained_bft::{
-        block_storage::VoteReceptionResult,
-        common::Author,
+        block_storage::{PruningPolicy, VoteReceptionResult},
+        common::{Author, Round},
         consensus_types::{
             block::ExecutedBlock, quorum_cert::QuorumCert, vote_data::VoteData, vote_msg::VoteMsg,
         },
@@ -13,7 +13,7 @@ use crate::{
     util::time_service::duration_since_epoch,
 };
 use canonical_serialization::CanonicalSerialize;
-use crypto::{hash::CryptoHash, HashValue};
+use crypto::{ed25519::Ed25519Signature, hash::CryptoHash, HashValue};
 use executor::StateComputeResult;
 use logger::prelude::*;
 use mirai_annotations::{checked_verify_eq, precondition};
@@ -24,7 +24,7 @@ use std::{
     sync::Arc,
     time::Duration,
 };
-use types::crypto_proxies::LedgerInfoWithSignatures;
+use types::crypto_proxies::{LedgerInfoWithSignatures, ValidatorVerifier};
 
 /// This structure is a wrapper of [`ExecutedBlock`](crate::consensus_types::block::ExecutedBlock)
 /// that adds `children` field to know the parent-child relationship between blocks.
@@ -71,10 +71,12 @@ where
 
 /// This structure maintains tuple of block_id and LedgerInfo for last voted block by an Author
 /// We only remember latest vote from Author. Digest is used to identify and prune pending vote from
-/// same Author.
+/// same Author. The vote itself is kept in order to be persisted while pending.
+#[derive(Clone)]
 struct BlockPendingVote {
     block_id: HashValue,
     digest: HashValue,
+    vote: VoteMsg,
 }
 
 /// This structure maintains a consistent block tree of parent and children links. Blocks contain
@@ -107,8 +109,13 @@ pub struct BlockTree<T> {
     id_to_quorum_cert: HashMap<HashValue, Arc<QuorumCert>>,
     /// To keep the IDs of the elements that have been pruned from the tree but not cleaned up yet.
     pruned_block_ids: VecDeque<HashValue>,
-    /// Num pruned blocks to keep in memory.
-    max_pruned_blocks_in_mem: usize,
+    /// The payload size of the pruned blocks, in the order of `pruned_block_ids` (only tracked by
+    /// the `MaxPayloadBytes` pruning policy).
+    pruned_payload_sizes: VecDeque<u64>,
+    /// The sum of `pruned_payload_sizes`.
+    pruned_payload_bytes: u64,
+    /// How many pruned blocks to keep in memory.
+    pruning_policy: PruningPolicy,
 }
 
 impl<T> BlockTree<T>
@@ -119,7 +126,7 @@ where
         root: ExecutedBlock<T>,
         root_quorum_cert: QuorumCert,
         root_ledger_info: QuorumCert,
-        max_pruned_blocks_in_mem: usize,
+        pruning_policy: PruningPolicy,
     ) -> Self {
         assert_eq!(
             root.id(),
@@ -142,7 +149,12 @@ where
             Arc::clone(&root_quorum_cert),
         );
 
-        let pruned_block_ids = VecDeque::with_capacity(max_pruned_blocks_in_mem);
+        let pruned_block_ids = match pruning_policy {
+            PruningPolicy::MaxBlocks(max_pruned_blocks_in_mem) => {
+                VecDeque::with_capacity(max_pruned_blocks_in_mem)
+            }
+            PruningPolicy::MaxPayloadBytes(_) => VecDeque::new(),
+        };
 
         BlockTree {
             id_to_block,
@@ -154,7 +166,9 @@ where
             author_to_last_voted_block_id: HashMap::new(),
             id_to_quorum_cert,
             pruned_block_ids,
-            max_pruned_blocks_in_mem,
+            pruned_payload_sizes: VecDeque::new(),
+            pruned_payload_bytes: 0,
+            pruning_policy,
         }
     }
 
@@ -272,6 +286,7 @@ where
                 if block.round() > self.highest_certified_block().round() {
                     self.highest_certified_block_id = block.id();
                     self.highest_quorum_cert = Arc::clone(&qc);
+                    counters::HIGHEST_QUORUM_CERT_ROUND.set(block.round() as i64);
                 }
             }
             None => bail!("Block {} not found", block_id),
@@ -304,48 +319,142 @@ where
     /// Check if vote is valid. If this is the first vote from Author, add it to map. If Author has
     /// already voted on same block then return DuplicateVote error. If Author has already voted
     /// on some other block, prune last vote and insert new one in map.
-    fn check_vote_valid(&mut self, vote_msg: &VoteMsg) -> Result<(), VoteReceptionResult> {
+    /// The signatures are not verified before a quorum is aggregated, except when the vote
+    /// conflicts with a pending vote of Author (a vote for a block that is not certified yet): a
+    /// vote has to be signed by Author to replace its pending vote or to be rejected as its
+    /// duplicate, and a pending vote with an invalid signature is dropped in favor of a properly
+    /// signed one. A forged vote thus never evicts the vote of an honest Author.
+    fn check_vote_valid(
+        &mut self,
+        vote_msg: &VoteMsg,
+        validator: Option<&ValidatorVerifier>,
+    ) -> Result<(), VoteReceptionResult> {
         let author = vote_msg.author();
         let block_id = vote_msg.vote_data().block_id();
         let digest = vote_msg.ledger_info().hash();
 
-        let last_voted_block = match self
-            .author_to_last_voted_block_id
-            .insert(author, BlockPendingVote { block_id, digest })
-        {
+        let last_voted_block = match self.author_to_last_voted_block_id.get(&author) {
             None => {
                 // First vote from Author, do nothing.
+                self.author_to_last_voted_block_id.insert(
+                    author,
+                    BlockPendingVote {
+                        block_id,
+                        digest,
+                        vote: vote_msg.clone(),
+                    },
+                );
                 return Ok(());
             }
-            Some(last_voted_block) => last_voted_block,
+            Some(last_voted_block) => last_voted_block.clone(),
         };
 
+        if let Some(validator) = validator {
+            if let Some(last_signature) = self.pending_signature(author, &last_voted_block) {
+                if vote_msg
+                    .signature()
+                    .verify(
+                        &validator.at_round(vote_msg.vote_data().block_round()),
+                        author,
+                        digest,
+                    )
+                    .is_err()
+                {
+                    warn!(
+                        "Dropping the vote of {} for block {}: invalid signature",
+                        author.short_str(),
+                        block_id
+                    );
+                    return Err(VoteReceptionResult::InvalidVotesDropped(
+                        self.num_votes(block_id, digest),
+                    ));
+                }
+                if validator
+                    .at_round(last_voted_block.vote.vote_data().block_round())
+                    .verify_signature(author, last_voted_block.digest, &last_signature)
+                    .is_err()
+                {
+                    warn!(
+                        "Dropping the vote of {} for block {}: invalid signature",
+                        author.short_str(),
+                        last_voted_block.block_id
+                    );
+                    self.remove_pending_vote(author, &last_voted_block);
+                    self.author_to_last_voted_block_id.insert(
+                        author,
+                        BlockPendingVote {
+                            block_id,
+                            digest,
+                            vote: vote_msg.clone(),
+                        },
+                    );
+                    return Ok(());
+                }
+            }
+        }
+
+        self.author_to_last_voted_block_id.insert(
+            author,
+            BlockPendingVote {
+                block_id,
+                digest,
+                vote: vote_msg.clone(),
+            },
+        );
         // Prune last pending vote from Author
         if block_id == last_voted_block.block_id {
             // Author has already voted for this block
             return Err(VoteReceptionResult::DuplicateVote);
         }
+        self.remove_pending_vote(author, &last_voted_block);
+        Ok(())
+    }
 
-        if let Some(block_pending_votes) = self.id_to_votes.get_mut(&last_voted_block.block_id) {
-            if let Some(li_digest_to_sig) = block_pending_votes.get_mut(&last_voted_block.digest) {
+    /// The signature of the given vote of the author, if the vote still counts towards a QC.
+    fn pending_signature(
+        &self,
+        author: Author,
+        pending_vote: &BlockPendingVote,
+    ) -> Option<Ed25519Signature> {
+        if self.id_to_quorum_cert.contains_key(&pending_vote.block_id) {
+            return None;
+        }
+        self.id_to_votes
+            .get(&pending_vote.block_id)
+            .and_then(|block_pending_votes| block_pending_votes.get(&pending_vote.digest))
+            .and_then(|li_with_sig| li_with_sig.signatures().get(&author))
+            .cloned()
+    }
+
+    /// The number of votes aggregated for the given (proposal, execution) pair.
+    fn num_votes(&self, block_id: HashValue, digest: HashValue) -> usize {
+        self.id_to_votes
+            .get(&block_id)
+            .and_then(|block_pending_votes| block_pending_votes.get(&digest))
+            .map_or(0, |li_with_sig| li_with_sig.signatures().len())
+    }
+
+    fn remove_pending_vote(&mut self, author: Author, pending_vote: &BlockPendingVote) {
+        if let Some(block_pending_votes) = self.id_to_votes.get_mut(&pending_vote.block_id) {
+            if let Some(li_digest_to_sig) = block_pending_votes.get_mut(&pending_vote.digest) {
                 // Removing signature from last voted block
                 li_digest_to_sig.remove_signature(author);
                 if li_digest_to_sig.signatures().is_empty() {
                     // Last vote/signature for block, remove digest entry
-                    block_pending_votes.remove(&last_voted_block.digest);
+                    block_pending_votes.remove(&pending_vote.digest);
                     if block_pending_votes.is_empty() {
-                        self.id_to_votes.remove(&last_voted_block.block_id);
+                        self.id_to_votes.remove(&pending_vote.block_id);
                     }
                 }
             }
         }
-        Ok(())
     }
 
     pub(super) fn insert_vote(
         &mut self,
         vote_msg: &VoteMsg,
         min_votes_for_qc: usize,
+        validator: Option<&ValidatorVerifier>,
     ) -> VoteReceptionResult {
         let author = vote_msg.author();
         let block_id = vote_msg.vote_data().block_id();
@@ -353,7 +462,7 @@ where
             return VoteReceptionResult::OldQuorumCertificate(Arc::clone(old_qc));
         }
 
-        if let Err(e) = self.check_vote_valid(vote_msg) {
+        if let Err(e) = self.check_vote_valid(vote_msg, validator) {
             return e;
         }
 
@@ -372,8 +481,36 @@ where
 
         vote_msg.signature().clone().add_to_li(author, li_with_sig);
 
-        let num_votes = li_with_sig.signatures().len();
+        let mut num_votes = li_with_sig.signatures().len();
         if num_votes >= min_votes_for_qc {
+            if let Some(validator) = validator {
+                let validator = validator.at_round(vote_msg.vote_data().block_round());
+                if let Err(e) = validator.batch_verify(digest, li_with_sig.signatures()) {
+                    let invalid_authors: Vec<Author> = li_with_sig
+                        .signatures()
+                        .iter()
+                        .filter(|(author, signature)| {
+                            validator
+                                .verify_signature(**author, digest, signature)
+                                .is_err()
+                        })
+                        .map(|(author, _)| *author)
+                        .collect();
+                    warn!(
+                        "Dropping the votes of {:?} for block {}: {:?}",
+                        invalid_authors, block_id, e
+                    );
+                    for invalid_author in invalid_authors {
+                        li_with_sig.remove_signature(invalid_author);
+                        // Let the author vote again with a valid signature.
+                        self.author_to_last_voted_block_id.remove(&invalid_author);
+                    }
+                    num_votes = li_with_sig.signatures().len();
+                    if num_votes < min_votes_for_qc {
+                        return VoteReceptionResult::InvalidVotesDropped(num_votes);
+                    }
+                }
+            }
             let quorum_cert = QuorumCert::new(
                 VoteData::new(
                     block_id,
@@ -399,6 +536,18 @@ where
         VoteReceptionResult::VoteAdded(num_votes)
     }
 
+    /// The votes for the blocks of the given round that have not been aggregated into a QC yet.
+    pub(super) fn pending_votes(&self, round: Round) -> Vec<VoteMsg> {
+        self.author_to_last_voted_block_id
+            .values()
+            .filter(|pending_vote| {
+                pending_vote.vote.vote_data().block_round() == round
+                    && !self.id_to_quorum_cert.contains_key(&pending_vote.block_id)
+            })
+            .map(|pending_vote| pending_vote.vote.clone())
+            .collect()
+    }
+
     /// Find the blocks to prune up to next_root_id (keep next_root_id's block). Any branches not
     /// part of the next_root_id's tree should be removed as well.
     ///
@@ -452,16 +601,45 @@ where
         self.root_id = root_id;
         counters::NUM_BLOCKS_IN_TREE.sub(newly_pruned_blocks.len() as i64);
         // The newly pruned blocks are pushed back to the deque pruned_block_ids.
-        // In case the overall number of the elements is greater than the predefined threshold,
-        // the oldest elements (in the front of the deque) are removed from the tree.
-        self.pruned_block_ids.append(&mut newly_pruned_blocks);
-        if self.pruned_block_ids.len() > self.max_pruned_blocks_in_mem {
-            let num_blocks_to_remove = self.pruned_block_ids.len() - self.max_pruned_blocks_in_mem;
-            for _ in 0..num_blocks_to_remove {
-                if let Some(id) = self.pruned_block_ids.pop_front() {
-                    self.remove_block(id);
+        // In case the overall number of the elements (or the size of their payloads) is greater
+        // than the predefined threshold, the oldest elements (in the front of the deque) are
+        // removed from the tree.
+        match self.pruning_policy {
+            PruningPolicy::MaxBlocks(max_pruned_blocks_in_mem) => {
+                self.pruned_block_ids.append(&mut newly_pruned_blocks);
+                if self.pruned_block_ids.len() > max_pruned_blocks_in_mem {
+                    let num_blocks_to_remove =
+                        self.pruned_block_ids.len() - max_pruned_blocks_in_mem;
+                    for _ in 0..num_blocks_to_remove {
+                        if let Some(id) = self.pruned_block_ids.pop_front() {
+                            self.remove_block(id);
+                        }
+                    }
                 }
             }
+            PruningPolicy::MaxPayloadBytes(max_pruned_payload_bytes) => {
+                for id in newly_pruned_blocks {
+                    let payload_size = self
+                        .get_block(&id)
+                        .map_or(0, |block| block.block().payload_size());
+                    self.pruned_block_ids.push_back(id);
+                    self.pruned_payload_sizes.push_back(payload_size);
+                    self.pruned_payload_bytes += payload_size;
+                }
+                while self.pruned_payload_bytes > max_pruned_payload_bytes {
+                    match (
+                        self.pruned_block_ids.pop_front(),
+                        self.pruned_payload_sizes.pop_front(),
+                    ) {
+                        (Some(id), Some(payload_size)) => {
+                            self.pruned_payload_bytes -= payload_size;
+                            self.remove_block(id);
+                        }
+                        _ => break,
+                    }
+                }
+                counters::PRUNED_PAYLOAD_BYTES_IN_MEM.set(self.pruned_payload_bytes as i64);
+            }
         }
     }
 
@@ -494,13 +672,108 @@ where
         Some(res)
     }
 
-    pub(super) fn max_pruned_blocks_in_mem(&self) -> usize {
-        self.max_pruned_blocks_in_mem
+    pub(super) fn pruning_policy(&self) -> PruningPolicy {
+        self.pruning_policy
     }
 
     pub(super) fn get_all_block_id(&self) -> Vec<HashValue> {
         self.id_to_block.keys().cloned().collect()
     }
+
+    /// Renders the tree in the DOT format of Graphviz, see `BlockReader::export_dot`.
+    pub(super) fn export_dot(&self) -> String {
+        let mut blocks: Vec<_> = self
+            .id_to_block
+            .values()
+            .map(LinkableBlock::executed_block)
+            .collect();
+        blocks.sort_by_key(|block| (block.round(), block.id()));
+        let pruned_block_ids: HashSet<_> = self.pruned_block_ids.iter().collect();
+        let mut dot = String::from("digraph BlockTree {\n    rankdir=LR;\n    node [shape=box];\n");
+        for block in &blocks {
+            let mut label = format!("{}\\nround {}", block.id().short_str(), block.round());
+            let mut styles = vec![];
+            let mut attributes = vec![];
+            if block.is_nil_block() {
+                label.push_str("\\nNIL");
+            }
+            if let Some(qc) = self.id_to_quorum_cert.get(&block.id()) {
+                label.push_str(&format!(
+                    "\\nQC ({} votes)",
+                    qc.ledger_info().signatures().len()
+                ));
+                styles.push("bold");
+            }
+            if block.id() == self.highest_certified_block_id {
+                label.push_str("\\nHQC");
+                attributes.push("color=blue");
+            }
+            if block.id() == self.root_id {
+                label.push_str("\\nroot");
+                styles.push("filled");
+                attributes.push("fillcolor=palegreen");
+            }
+            if pruned_block_ids.contains(&block.id()) {
+                label.push_str("\\npruned");
+                styles.push("dashed");
+                attributes.push("fontcolor=gray");
+            }
+            dot.push_str(&format!(
+                "    \"{:x}\" [label=\"{}\", style=\"{}\"{}];\n",
+                block.id(),
+                label,
+                styles.join(","),
+                attributes
+                    .iter()
+                    .map(|attribute| format!(", {}", attribute))
+                    .collect::<String>(),
+            ));
+        }
+        // The edges follow the parent links, i.e., the QC carried by every block.
+        for block in &blocks {
+            if block.id() != self.root_id && self.block_exists(&block.parent_id()) {
+                dot.push_str(&format!(
+                    "    \"{:x}\" -> \"{:x}\";\n",
+                    block.id(),
+                    block.parent_id()
+                ));
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Returns the root and its descendants in breadth-first order, see
+    /// `BlockReader::tree_blocks`.
+    pub(super) fn tree_blocks(&self) -> Vec<Arc<ExecutedBlock<T>>> {
+        let mut res = vec![];
+        let mut to_visit = VecDeque::new();
+        to_visit.push_back(self.linkable_root());
+        while let Some(block) = to_visit.pop_front() {
+            res.push(Arc::clone(block.executed_block()));
+            for child_id in block.children() {
+                to_visit.push_back(
+                    self.get_linkable_block(child_id)
+                        .expect("Child must exist in the tree"),
+                );
+            }
+        }
+        res
+    }
+
+    pub(super) fn pruned_blocks(&self) -> Vec<Arc<ExecutedBlock<T>>> {
+        self.pruned_block_ids
+            .iter()
+            .map(|block_id| {
+                self.get_block(block_id)
+                    .expect("Pruned block must be kept in memory")
+            })
+            .collect()
+    }
+
+    pub(super) fn all_quorum_certs(&self) -> Vec<Arc<QuorumCert>> {
+        self.id_to_quorum_cert.values().cloned().collect()
+    }
 }
 
 #[cfg(any(test, feature = "fuzzing"))]
@@ -535,4 +808,10 @@ where
     pub(super) fn pruned_blocks_in_mem(&self) -> usize {
         self.pruned_block_ids.len()
     }
+
+    /// The size of the payloads of the pruned blocks that are still available in memory, as
+    /// tracked by the `MaxPayloadBytes` pruning policy
+    pub(super) fn pruned_payload_bytes_in_mem(&self) -> u64 {
+        self.pruned_payload_bytes
+    }
 }
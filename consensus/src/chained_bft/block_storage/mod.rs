@@ -9,7 +9,8 @@ use crate::chained_bft::{
     },
 };
 use crypto::HashValue;
-use std::sync::Arc;
+use futures::Future;
+use std::{pin::Pin, sync::Arc};
 
 mod block_store;
 mod block_tree;
@@ -30,6 +31,22 @@ pub enum VoteReceptionResult {
     OldQuorumCertificate(Arc<QuorumCert>),
     /// This block has just been certified after adding the vote.
     NewQuorumCertificate(Arc<QuorumCert>),
+    /// The batched verification of the aggregated votes failed, or this vote conflicting with a
+    /// pending vote of its author has an invalid signature: the votes with invalid signatures
+    /// (possibly including this one) have been dropped. Return the number of remaining votes for
+    /// the given (proposal, execution) pair.
+    InvalidVotesDropped(usize),
+}
+
+/// How many of the blocks pruned from the tree are kept in memory, such that the other replicas
+/// can still retrieve them after they've been committed. The oldest pruned blocks are removed
+/// first.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PruningPolicy {
+    /// Keep up to this number of pruned blocks.
+    MaxBlocks(usize),
+    /// Keep the pruned blocks as long as their payloads add up to at most this number of bytes.
+    MaxPayloadBytes(u64),
 }
 
 pub trait BlockReader: Send + Sync {
@@ -86,4 +103,61 @@ pub trait BlockReader: Send + Sync {
 
     /// Return the quorum certificate that carries ledger info with the highest round
     fn highest_ledger_info(&self) -> Arc<QuorumCert>;
+
+    /// Return the number of blocks ordered by a commit certificate, which are not part of the
+    /// tree anymore, but are not committed by the state computer yet.
+    fn num_uncommitted_ordered_blocks(&self) -> usize;
+
+    /// Return the blocks ordered by a commit certificate that are not committed by the state
+    /// computer yet, oldest first: their transactions are in flight.
+    fn uncommitted_ordered_blocks(&self) -> Vec<Arc<ExecutedBlock<Self::Payload>>>;
+
+    /// Returns the blocks of the tree, i.e., the root and all its descendants, such that every
+    /// block comes after its parent (the root comes first).
+    fn tree_blocks(&self) -> Vec<Arc<ExecutedBlock<Self::Payload>>>;
+
+    /// Returns the blocks pruned from the tree that are still kept in memory (see
+    /// `PruningPolicy`), oldest first.
+    fn pruned_blocks(&self) -> Vec<Arc<ExecutedBlock<Self::Payload>>>;
+
+    /// Returns all the quorum certificates kept in memory, in no particular order.
+    fn all_quorum_certs(&self) -> Vec<Arc<QuorumCert>>;
+
+    /// Renders the block tree in the DOT format of Graphviz (e.g., `dot -Tsvg`): every block
+    /// points to its parent and is labeled with its round and its QC, if any. The root is
+    /// filled, the highest certified block is blue and the pruned blocks that are still kept in
+    /// memory are dashed.
+    fn export_dot(&self) -> String;
+}
+
+/// The future returned by the lookups of `AsyncBlockReader`.
+pub type BlockReadFuture<'a, R> = Pin<Box<dyn Future<Output = R> + Send + 'a>>;
+
+/// The async counterpart of the lookups of `BlockReader`. A lookup may have to wait for the
+/// block to be fetched or persisted (e.g., once the blocks are stored on disk), hence the callers
+/// running on the consensus runtime should prefer this interface, such that they don't block the
+/// runtime threads on storage.
+pub trait AsyncBlockReader: Send + Sync {
+    type Payload;
+
+    /// Check if a block with the block_id exist in the BlockTree.
+    fn block_exists_async(&self, block_id: HashValue) -> BlockReadFuture<'_, bool>;
+
+    /// Try to get a block with the block_id, return an Arc of it if found.
+    fn get_block_async(
+        &self,
+        block_id: HashValue,
+    ) -> BlockReadFuture<'_, Option<Arc<ExecutedBlock<Self::Payload>>>>;
+
+    fn get_quorum_cert_for_block_async(
+        &self,
+        block_id: HashValue,
+    ) -> BlockReadFuture<'_, Option<Arc<QuorumCert>>>;
+
+    /// Returns all the blocks between the root and the given block, see
+    /// `BlockReader::path_from_root`.
+    fn path_from_root_async(
+        &self,
+        block_id: HashValue,
+    ) -> BlockReadFuture<'_, Option<Vec<Arc<ExecutedBlock<Self::Payload>>>>>;
 }
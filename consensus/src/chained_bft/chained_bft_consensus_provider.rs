@@ -16,6 +16,7 @@ use crate::{
     txn_manager::MempoolProxy,
 };
 use config::config::{ConsensusProposerType::FixedProposer, NodeConfig};
+use debug_interface::node_debug_service::{CommitCertificateProvider, ConsensusStateProvider};
 use executor::Executor;
 use failure::prelude::*;
 use logger::prelude::*;
@@ -26,7 +27,7 @@ use std::{convert::TryFrom, sync::Arc};
 use tokio::runtime;
 use types::{
     account_address::AccountAddress,
-    crypto_proxies::{ValidatorSigner, ValidatorVerifier},
+    crypto_proxies::{LedgerInfoWithSignatures, ValidatorSigner, ValidatorVerifier},
     transaction::SignedTransaction,
 };
 use vm_runtime::MoveVM;
@@ -61,12 +62,16 @@ impl ChainedBftProvider {
 
         let initial_setup = Self::initialize_setup(node_config);
         let epoch_mgr = Arc::new(EpochManager::new(0, initial_setup.validator.clone()));
+        let config = ChainedBftSMRConfig::from_node_config(&node_config.consensus);
         let network = ConsensusNetworkImpl::new(
             initial_setup.author,
             network_sender.clone(),
             network_events,
             Arc::clone(&epoch_mgr),
-        );
+        )
+        .with_observers(node_config.consensus.get_observers())
+        .with_rate_limit_policy(config.inbound_rate_limit_policy.clone())
+        .with_signature_verification_threads(config.signature_verification_threads);
         let proposer = {
             let peers = epoch_mgr.validators().get_ordered_account_addresses();
             if node_config.consensus.get_proposer_type() == FixedProposer {
@@ -77,11 +82,10 @@ impl ChainedBftProvider {
         };
         debug!("[Consensus] My peer: {:?}", initial_setup.author);
         debug!("[Consensus] Chosen proposer: {:?}", proposer);
-        let config = ChainedBftSMRConfig::from_node_config(&node_config.consensus);
-        let (storage, initial_data) = StorageWriteProxy::start(node_config);
+        let (storage, initial_data) =
+            StorageWriteProxy::<Vec<SignedTransaction>>::start(node_config);
         info!(
-            "Starting up the consensus state machine with recovery data - {:?}, {}",
-            initial_data.state(),
+            "Starting up the consensus state machine with recovery data - {}",
             initial_data.highest_timeout_certificates()
         );
         let smr = ChainedBftSMR::new(
@@ -124,8 +128,14 @@ impl ChainedBftProvider {
         // Keeping the initial set of validators in a node config is embarrassing and we should
         // all feel bad about it.
         let peers_with_public_keys = node_config.consensus.get_consensus_peers();
-        let validator = ValidatorVerifier::new(peers_with_public_keys);
-        counters::EPOCH_NUM.set(0); // No reconfiguration yet, so it is always zero
+        let validator = ValidatorVerifier::new_with_voting_power(
+            peers_with_public_keys,
+            node_config.consensus.get_consensus_voting_power(),
+        )
+        .expect("Invalid voting power of the consensus peers");
+        // A restarted node starts from the initial epoch and validator set, then replays the
+        // persisted epoch changes, which update the counters.
+        counters::EPOCH_NUM.set(0);
         counters::CURRENT_EPOCH_NUM_VALIDATORS.set(validator.len() as i64);
         counters::CURRENT_EPOCH_QUORUM_SIZE.set(validator.quorum_size() as i64);
         debug!("[Consensus]: quorum_size = {:?}", validator.quorum_size());
@@ -160,4 +170,16 @@ impl ConsensusProvider for ChainedBftProvider {
         self.smr.stop();
         debug!("Consensus provider stopped.");
     }
+
+    fn latest_commit_certificate(&self) -> Option<LedgerInfoWithSignatures> {
+        self.smr.latest_commit_certificate()
+    }
+
+    fn commit_certificate_provider(&self) -> Option<Arc<dyn CommitCertificateProvider>> {
+        self.smr.commit_certificate_provider()
+    }
+
+    fn consensus_state_provider(&self) -> Option<Arc<dyn ConsensusStateProvider>> {
+        self.smr.consensus_state_provider()
+    }
 }
@@ -3,28 +3,46 @@
 
 use crate::{
     chained_bft::{
-        block_storage::BlockStore,
+        block_storage::{BlockReader, BlockStore, PruningPolicy},
+        commit_pipeline::{CommitPipeline, CommitSubscribers},
         common::{Payload, Round},
+        consensus_types::{key_rotation::KeyRotation, vote_msg::VoteMsg},
+        event_log::EventLogger,
         event_processor::EventProcessor,
+        evidence::{Evidence, EvidenceStore},
+        introspection::ConsensusIntrospector,
         liveness::{
+            commit_heartbeat::CommitHeartbeatPolicy,
+            leader_reputation::LeaderReputation,
             multi_proposer_election::MultiProposer,
-            pacemaker::{ExponentialTimeInterval, Pacemaker},
+            pacemaker::{
+                AdaptiveTimeInterval, ExponentialTimeInterval, Pacemaker, PacemakerTimeInterval,
+            },
             pacemaker_timeout_manager::HighestTimeoutCertificates,
             proposal_generator::ProposalGenerator,
             proposer_election::ProposerElection,
             rotating_proposer_election::RotatingProposer,
+            timeout_retransmission::TimeoutRetransmissionPolicy,
         },
         network::{ConsensusNetworkImpl, NetworkReceivers},
-        persistent_storage::{PersistentLivenessStorage, PersistentStorage, RecoveryData},
-        safety::safety_rules::SafetyRules,
+        network_rate_limit::{InboundMessageType, InboundRateLimitPolicy},
+        persistent_storage::{PersistentStorage, RecoveryData},
+        safety::{
+            remote_safety_rules::RemoteSafetyRules,
+            safety_rules::{ConsensusState, SafetyRules, TSafetyRules},
+        },
+        sync_manager::BlockRetrievalPolicy,
     },
     counters,
-    state_replication::{StateComputer, StateMachineReplication, TxnManager},
+    state_replication::{
+        NoopPayloadValidator, PayloadValidator, StateComputer, StateMachineReplication, TxnManager,
+    },
     util::time_service::{ClockTimeService, TimeService},
 };
 use channel;
 use failure::prelude::*;
 use futures::{
+    channel::mpsc,
     compat::Future01CompatExt,
     executor::block_on,
     future::{FutureExt, TryFutureExt},
@@ -32,38 +50,442 @@ use futures::{
     stream::StreamExt,
 };
 
-use crate::chained_bft::{common::Author, epoch_manager::EpochManager};
-use config::config::{ConsensusConfig, ConsensusProposerType};
+#[cfg(test)]
+use crate::chained_bft::block_storage::AsyncBlockReader;
+#[cfg(test)]
+use crate::chained_bft::event_hooks::{EventHook, EventHooks, ProcessedEvent};
+use crate::chained_bft::{
+    common::Author,
+    epoch_manager::{EpochChange, EpochManager},
+};
+use config::config::{CommitRule, ConsensusConfig, ConsensusProposerType, SafetyRulesBackend};
+use debug_interface::node_debug_service::{CommitCertificateProvider, ConsensusStateProvider};
 use logger::prelude::*;
-use std::{sync::Arc, time::Duration};
+use std::{path::PathBuf, sync::Arc, time::Duration};
 use tokio::runtime::{Runtime, TaskExecutor};
-use types::crypto_proxies::ValidatorSigner;
+use types::crypto_proxies::{LedgerInfoWithSignatures, ValidatorSigner};
+
+// The leader reputation looks at the last committed blocks, excluding the blocks of the last
+// rounds, which might not be committed by all the replicas yet (a block is committed 3 rounds
+// after it is proposed).
+const LEADER_REPUTATION_WINDOW_SIZE: usize = 10;
+const LEADER_REPUTATION_EXCLUDE_ROUNDS: Round = 4;
+const LEADER_REPUTATION_ACTIVE_WEIGHT: u64 = 100;
+const LEADER_REPUTATION_INACTIVE_WEIGHT: u64 = 1;
+
+// With the leader skipping, a primary proposer that failed in 2 consecutive rounds it led within
+// the last 100 committed rounds is a backup proposer for the next 30 rounds.
+const LEADER_SKIPPING_MAX_FAILED_ROUNDS: usize = 2;
+const LEADER_SKIPPING_COOL_DOWN_ROUNDS: Round = 30;
+const LEADER_SKIPPING_WINDOW_ROUNDS: Round = 100;
+const LEADER_SKIPPING_EXCLUDE_ROUNDS: Round = 4;
+
+// The adaptive timeout is 3 times the configured percentile of the last 20 successful rounds.
+const ADAPTIVE_TIMEOUT_MULTIPLIER: f64 = 3.0;
+const ADAPTIVE_TIMEOUT_MIN_BASE: Duration = Duration::from_millis(100);
+const ADAPTIVE_TIMEOUT_WINDOW_SIZE: usize = 20;
+// Number of commits buffered for a subscriber before it starts skipping commits.
+const COMMIT_SUBSCRIPTION_BUFFER_SIZE: usize = 100;
 
 /// Consensus configuration derived from ConsensusConfig
+#[derive(Clone)]
 pub struct ChainedBftSMRConfig {
-    /// Keep up to this number of committed blocks before cleaning them up from the block store.
-    pub max_pruned_blocks_in_mem: usize,
+    /// How many committed blocks to keep before cleaning them up from the block store, either a
+    /// number of blocks or a budget of payload bytes
+    pub pruning_policy: PruningPolicy,
     /// Initial timeout for pacemaker
     pub pacemaker_initial_timeout: Duration,
+    /// By how much the pacemaker timeout grows after every round without a commit
+    pub pacemaker_timeout_exponent_base: f64,
+    /// Number of rounds without a commit after which the pacemaker timeout stops growing
+    pub pacemaker_max_exponent: usize,
+    /// Optional upper bound of the pacemaker timeout
+    pub pacemaker_max_timeout: Option<Duration>,
+    /// If set, the initial timeout adapts to this percentile of the recent round durations
+    pub pacemaker_adaptive_timeout_percentile: Option<f64>,
+    /// How the timeout message of a round is retransmitted until the round advances
+    pub timeout_retransmission_policy: TimeoutRetransmissionPolicy,
+    /// How the highest commit certificate is broadcast while no proposals are received
+    pub commit_heartbeat_policy: CommitHeartbeatPolicy,
     /// Consensus proposer type
     pub proposer_type: ConsensusProposerType,
     /// Contiguous rounds for proposer
     pub contiguous_rounds: u32,
     /// Max block size (number of transactions) that consensus pulls from mempool
     pub max_block_size: u64,
+    /// Max size in bytes of the serialized payload of a block, larger proposals are rejected
+    pub max_block_bytes: u64,
+    /// The proposals carry no transactions while more ordered blocks are not committed yet
+    pub max_uncommitted_blocks: usize,
+    /// Optional file the structured consensus event log is appended to
+    pub event_log_file: Option<PathBuf>,
+    /// Policy of the retrieval of the missing blocks from the other validators
+    pub block_retrieval_policy: BlockRetrievalPolicy,
+    /// Limits of the messages received from every peer before they reach the event processor
+    pub inbound_rate_limit_policy: InboundRateLimitPolicy,
+    /// Number of threads verifying the signatures of the inbound messages, 0 for one per CPU
+    pub signature_verification_threads: usize,
+    /// Whether the safety rules run within consensus or in a separate process
+    pub safety_rules_backend: SafetyRulesBackend,
+    /// Whether blocks are committed by a 3-chain or a 2-chain of contiguous certified blocks
+    pub commit_rule: CommitRule,
+    /// Whether the validators vote for NIL blocks in the rounds that time out without proposal
+    pub nil_blocks_enabled: bool,
+    /// Whether the repeatedly failing primary proposers are temporarily demoted
+    pub leader_skipping_enabled: bool,
+    /// An observer follows the proposals and commits without voting, proposing or timing out
+    pub observer: bool,
 }
 
 impl ChainedBftSMRConfig {
     pub fn from_node_config(cfg: &ConsensusConfig) -> ChainedBftSMRConfig {
         let pacemaker_initial_timeout_ms = cfg.pacemaker_initial_timeout_ms().unwrap_or(1000);
+        let default_retrieval_policy = BlockRetrievalPolicy::default();
+        let block_retrieval_policy = BlockRetrievalPolicy {
+            fanout: cfg
+                .block_retrieval_fanout()
+                .unwrap_or(default_retrieval_policy.fanout),
+            initial_peer_timeout: cfg.block_retrieval_peer_timeout_ms().map_or(
+                default_retrieval_policy.initial_peer_timeout,
+                Duration::from_millis,
+            ),
+            max_peer_timeout: cfg.block_retrieval_max_peer_timeout_ms().map_or(
+                default_retrieval_policy.max_peer_timeout,
+                Duration::from_millis,
+            ),
+            max_retries_per_peer: cfg
+                .block_retrieval_max_retries_per_peer()
+                .unwrap_or(default_retrieval_policy.max_retries_per_peer),
+            preferred_peers: cfg.get_block_retrieval_preferred_peers(),
+        };
+        // The payload budget, if any, takes precedence over the number of pruned blocks.
+        let pruning_policy = match cfg.max_pruned_payload_bytes_in_mem() {
+            Some(max_pruned_payload_bytes) => {
+                PruningPolicy::MaxPayloadBytes(*max_pruned_payload_bytes)
+            }
+            None => {
+                PruningPolicy::MaxBlocks(cfg.max_pruned_blocks_in_mem().unwrap_or(10000) as usize)
+            }
+        };
+        let default_retransmission_policy = TimeoutRetransmissionPolicy::default();
+        let timeout_retransmission_policy = TimeoutRetransmissionPolicy {
+            interval: cfg.pacemaker_timeout_retransmission_interval_ms().map_or(
+                default_retransmission_policy.interval,
+                Duration::from_millis,
+            ),
+            max_jitter: cfg.pacemaker_timeout_retransmission_max_jitter_ms().map_or(
+                default_retransmission_policy.max_jitter,
+                Duration::from_millis,
+            ),
+            max_retransmissions: cfg
+                .pacemaker_max_timeout_retransmissions()
+                .unwrap_or(default_retransmission_policy.max_retransmissions),
+        };
+        let default_heartbeat_policy = CommitHeartbeatPolicy::default();
+        let commit_heartbeat_policy = CommitHeartbeatPolicy {
+            interval: cfg
+                .commit_heartbeat_interval_ms()
+                .map_or(default_heartbeat_policy.interval, Duration::from_millis),
+            quiet_period: cfg
+                .commit_heartbeat_quiet_period_ms()
+                .map_or(default_heartbeat_policy.quiet_period, Duration::from_millis),
+        };
+        let default_rate_limit_policy = InboundRateLimitPolicy::default();
+        let inbound_rate_limit_policy = InboundRateLimitPolicy {
+            msgs_per_sec: cfg
+                .inbound_msgs_per_sec_per_peer()
+                .unwrap_or(default_rate_limit_policy.msgs_per_sec),
+            burst: cfg
+                .inbound_msgs_burst_per_peer()
+                .unwrap_or(default_rate_limit_policy.burst),
+            queue_size: cfg
+                .inbound_queue_size()
+                .unwrap_or(default_rate_limit_policy.queue_size),
+            queue_size_overrides: cfg
+                .inbound_queue_size_overrides()
+                .iter()
+                .map(|(msg_type, queue_size)| {
+                    let msg_type = InboundMessageType::from_name(msg_type)
+                        .unwrap_or_else(|| panic!("Invalid inbound message type: {}", msg_type));
+                    (msg_type, *queue_size)
+                })
+                .collect(),
+        };
         ChainedBftSMRConfig {
-            max_pruned_blocks_in_mem: cfg.max_pruned_blocks_in_mem().unwrap_or(10000) as usize,
+            pruning_policy,
             pacemaker_initial_timeout: Duration::from_millis(pacemaker_initial_timeout_ms),
+            pacemaker_timeout_exponent_base: cfg.pacemaker_timeout_exponent_base().unwrap_or(1.5),
+            pacemaker_max_exponent: cfg.pacemaker_max_exponent().unwrap_or(6),
+            pacemaker_max_timeout: cfg.pacemaker_max_timeout_ms().map(Duration::from_millis),
+            pacemaker_adaptive_timeout_percentile: *cfg.pacemaker_adaptive_timeout_percentile(),
+            timeout_retransmission_policy,
+            commit_heartbeat_policy,
             proposer_type: cfg.get_proposer_type(),
             contiguous_rounds: cfg.contiguous_rounds(),
             max_block_size: cfg.max_block_size(),
+            max_block_bytes: cfg.max_block_bytes().unwrap_or(4 * 1024 * 1024),
+            max_uncommitted_blocks: cfg.max_uncommitted_blocks().unwrap_or(20),
+            event_log_file: cfg.event_log_file().clone(),
+            block_retrieval_policy,
+            inbound_rate_limit_policy,
+            signature_verification_threads: cfg.signature_verification_threads().unwrap_or(0),
+            safety_rules_backend: cfg.get_safety_rules_backend(),
+            commit_rule: cfg.get_commit_rule(),
+            nil_blocks_enabled: cfg.nil_blocks_enabled(),
+            leader_skipping_enabled: cfg.leader_skipping_enabled(),
+            observer: cfg.observer(),
+        }
+    }
+}
+
+// The proposers of the epoch with the given validators: the fixed proposer keeps proposing as long
+// as it remains a validator.
+fn epoch_proposers(
+    proposer_type: &ConsensusProposerType,
+    proposers: &[Author],
+    authors: Vec<Author>,
+) -> Vec<Author> {
+    match proposer_type {
+        ConsensusProposerType::FixedProposer => {
+            match proposers.iter().find(|p| authors.contains(*p)) {
+                Some(proposer) => vec![*proposer],
+                None => vec![authors[0]],
+            }
+        }
+        _ => authors,
+    }
+}
+
+/// Creates the event processor of every epoch: the event loop replaces its event processor with
+/// a new one whenever a reconfiguration is committed. All the epochs share the same block store,
+/// network and state computer.
+struct EventProcessorFactory<T> {
+    author: Author,
+    proposers: Vec<Author>,
+    config: ChainedBftSMRConfig,
+    block_store: Arc<BlockStore<T>>,
+    txn_manager: Arc<dyn TxnManager<Payload = T>>,
+    state_computer: Arc<dyn StateComputer<Payload = T>>,
+    commit_pipeline: CommitPipeline<T>,
+    network: ConsensusNetworkImpl,
+    storage: Arc<dyn PersistentStorage<T>>,
+    time_service: Arc<dyn TimeService>,
+    timeout_sender: channel::Sender<Round>,
+    timeout_retransmission_sender: channel::Sender<Round>,
+    commit_heartbeat_sender: channel::Sender<u64>,
+    epoch_mgr: Arc<EpochManager>,
+    evidence_store: Arc<EvidenceStore<T>>,
+    introspector: Arc<ConsensusIntrospector<T>>,
+    payload_validator: Arc<dyn PayloadValidator<Payload = T>>,
+}
+
+impl<T: Payload> EventProcessorFactory<T> {
+    fn create_pacemaker(
+        &self,
+        highest_timeout_certificate: HighestTimeoutCertificates,
+    ) -> Pacemaker {
+        // With the defaults, 1.5^6 ~= 11
+        // Timeout goes from initial_timeout to initial_timeout*11 in 6 steps
+        let mut time_interval = ExponentialTimeInterval::new(
+            self.config.pacemaker_initial_timeout,
+            self.config.pacemaker_timeout_exponent_base,
+            self.config.pacemaker_max_exponent,
+        );
+        if let Some(max_timeout) = self.config.pacemaker_max_timeout {
+            time_interval = time_interval.with_max_interval(max_timeout);
+        }
+        let time_interval: Box<dyn PacemakerTimeInterval> =
+            match self.config.pacemaker_adaptive_timeout_percentile {
+                Some(percentile) => Box::new(AdaptiveTimeInterval::new(
+                    time_interval,
+                    percentile,
+                    ADAPTIVE_TIMEOUT_MULTIPLIER,
+                    ADAPTIVE_TIMEOUT_MIN_BASE,
+                    ADAPTIVE_TIMEOUT_WINDOW_SIZE,
+                )),
+                None => Box::new(time_interval),
+            };
+        Pacemaker::new(
+            self.storage.persistent_liveness_storage(),
+            time_interval,
+            Arc::clone(&self.time_service),
+            self.timeout_sender.clone(),
+            highest_timeout_certificate,
+        )
+    }
+
+    /// Create a proposer election handler based on proposers
+    fn create_proposer_election(&self) -> Box<dyn ProposerElection<T> + Send + Sync> {
+        assert!(!self.proposers.is_empty());
+        match self.config.proposer_type {
+            ConsensusProposerType::MultipleOrderedProposers => {
+                let proposer_election = MultiProposer::new(self.proposers.clone(), 2);
+                if self.config.leader_skipping_enabled {
+                    Box::new(proposer_election.with_leader_skipping(
+                        Arc::clone(&self.block_store) as Arc<dyn BlockReader<Payload = T>>,
+                        LEADER_SKIPPING_MAX_FAILED_ROUNDS,
+                        LEADER_SKIPPING_COOL_DOWN_ROUNDS,
+                        LEADER_SKIPPING_WINDOW_ROUNDS,
+                        LEADER_SKIPPING_EXCLUDE_ROUNDS,
+                    ))
+                } else {
+                    Box::new(proposer_election)
+                }
+            }
+            ConsensusProposerType::WeightedRotatingProposer => {
+                let validators = self.epoch_mgr.validators();
+                let weighted_proposers = self
+                    .proposers
+                    .iter()
+                    .map(|author| {
+                        let voting_power = validators
+                            .get_voting_power(*author)
+                            .expect("The proposers must be validators");
+                        (*author, voting_power)
+                    })
+                    .collect();
+                Box::new(RotatingProposer::new_weighted(
+                    weighted_proposers,
+                    self.config.contiguous_rounds,
+                ))
+            }
+            ConsensusProposerType::LeaderReputation => Box::new(LeaderReputation::new(
+                self.proposers.clone(),
+                Arc::clone(&self.block_store) as Arc<dyn BlockReader<Payload = T>>,
+                LEADER_REPUTATION_WINDOW_SIZE,
+                LEADER_REPUTATION_EXCLUDE_ROUNDS,
+                LEADER_REPUTATION_ACTIVE_WEIGHT,
+                LEADER_REPUTATION_INACTIVE_WEIGHT,
+            )),
+            // We don't really have a fixed proposer!
+            _ => Box::new(RotatingProposer::new(
+                self.proposers.clone(),
+                self.config.contiguous_rounds,
+            )),
         }
     }
+
+    fn create_event_processor(
+        &self,
+        consensus_state: ConsensusState,
+        highest_timeout_certificates: HighestTimeoutCertificates,
+    ) -> EventProcessor<T> {
+        // txn manager is required both by proposal generator (to pull the proposers)
+        // and by event processor (to update their status).
+        let proposal_generator = ProposalGenerator::new(
+            Arc::clone(&self.block_store),
+            Arc::clone(&self.txn_manager),
+            Arc::clone(&self.time_service),
+            self.config.max_block_size,
+            self.config.max_block_bytes,
+            self.config.max_uncommitted_blocks,
+            true,
+        );
+        let event_logger = self.config.event_log_file.as_ref().and_then(|path| {
+            EventLogger::new(self.author, path)
+                .map_err(|e| error!("Consensus event log is disabled: {:?}", e))
+                .ok()
+        });
+        EventProcessor::new(
+            self.author,
+            Arc::clone(&self.block_store),
+            self.create_pacemaker(highest_timeout_certificates),
+            self.create_proposer_election(),
+            proposal_generator,
+            self.create_safety_rules(consensus_state),
+            Arc::clone(&self.state_computer),
+            self.commit_pipeline.clone(),
+            self.network.clone(),
+            Arc::clone(&self.storage),
+            Arc::clone(&self.time_service),
+            true,
+            Arc::clone(&self.epoch_mgr),
+            event_logger,
+            self.config.block_retrieval_policy.clone(),
+            Arc::clone(&self.evidence_store),
+        )
+        .with_commit_rule(self.config.commit_rule)
+        .with_nil_blocks_enabled(self.config.nil_blocks_enabled)
+        .with_observer(self.config.observer)
+        .with_introspector(Arc::clone(&self.introspector))
+        .with_timeout_retransmission(
+            self.config.timeout_retransmission_policy.clone(),
+            self.timeout_retransmission_sender.clone(),
+        )
+        .with_commit_heartbeat(
+            self.config.commit_heartbeat_policy.clone(),
+            self.commit_heartbeat_sender.clone(),
+        )
+        .with_payload_validator(Arc::clone(&self.payload_validator))
+    }
+
+    fn create_safety_rules(&self, consensus_state: ConsensusState) -> Box<dyn TSafetyRules<T>> {
+        match &self.config.safety_rules_backend {
+            SafetyRulesBackend::InProcess => Box::new(
+                SafetyRules::new_with_storage(
+                    consensus_state,
+                    self.storage.persistent_safety_storage(),
+                )
+                .with_commit_rule(self.config.commit_rule),
+            ),
+            SafetyRulesBackend::Process(socket_path) => {
+                let mut safety_rules = RemoteSafetyRules::connect(socket_path.clone())
+                    .expect("Unable to connect to the safety rules service");
+                // The service must not vote again at the rounds consensus has already voted at,
+                // e.g., when the safety rules of a validator are moved out of process.
+                TSafetyRules::<T>::increase_last_vote_round(
+                    &mut safety_rules,
+                    consensus_state.last_vote_round(),
+                )
+                .expect("Unable to initialize the safety rules service");
+                Box::new(safety_rules)
+            }
+        }
+    }
+
+    /// Switches to the validator set of the next epoch and creates the event processor of the
+    /// epoch. The rounds keep increasing across the epochs: the block tree and the consensus
+    /// state are carried over, while the timeout certificates of the previous epoch are dropped.
+    /// The epoch change is persisted, such that a restarted node resumes in the new epoch.
+    fn start_new_epoch(
+        &mut self,
+        epoch_change: EpochChange,
+        consensus_state: ConsensusState,
+    ) -> EventProcessor<T> {
+        let epoch = epoch_change.epoch();
+        let validators = self
+            .epoch_mgr
+            .validators()
+            .with_validator_set(epoch_change.validator_set());
+        let authors = validators.get_ordered_account_addresses();
+        // The first blocks of the new epoch extend the blocks certified by the previous epoch.
+        let hqc = self.block_store.highest_quorum_cert();
+        let mut previous_epoch_qcs = vec![
+            hqc.as_ref().clone(),
+            self.block_store.highest_ledger_info().as_ref().clone(),
+        ];
+        for block in self
+            .block_store
+            .path_from_root(hqc.certified_block_id())
+            .unwrap_or_else(Vec::new)
+        {
+            previous_epoch_qcs.push(block.quorum_cert().clone());
+        }
+        self.epoch_mgr
+            .start_new_epoch(epoch_change, validators, previous_epoch_qcs.iter());
+        if let Err(e) = self
+            .storage
+            .save_epoch_changes(self.epoch_mgr.epoch_changes())
+        {
+            error!("Failed to persist the change to epoch {}: {:?}", epoch, e);
+        }
+        self.proposers = epoch_proposers(&self.config.proposer_type, &self.proposers, authors);
+        info!(
+            "Consensus epoch {} started with proposers {:?}",
+            epoch, self.proposers
+        );
+        self.create_event_processor(consensus_state, HighestTimeoutCertificates::default())
+    }
 }
 
 /// ChainedBFTSMR is the one to generate the components (BlockStore, Proposer, etc.) and start the
@@ -75,11 +497,19 @@ pub struct ChainedBftSMR<T> {
     proposers: Vec<Author>,
     runtime: Option<Runtime>,
     block_store: Option<Arc<BlockStore<T>>>,
+    evidence_store: Option<Arc<EvidenceStore<T>>>,
+    introspector: Option<Arc<ConsensusIntrospector<T>>>,
     network: ConsensusNetworkImpl,
     config: ChainedBftSMRConfig,
     storage: Arc<dyn PersistentStorage<T>>,
     initial_data: Option<RecoveryData<T>>,
     epoch_mgr: Arc<EpochManager>,
+    // Overrides the clock of the runtime (e.g., in the deterministic simulations).
+    time_service: Option<Arc<dyn TimeService>>,
+    commit_subscribers: CommitSubscribers,
+    payload_validator: Arc<dyn PayloadValidator<Payload = T>>,
+    #[cfg(test)]
+    event_hooks: EventHooks,
 }
 
 impl<T: Payload> ChainedBftSMR<T> {
@@ -100,11 +530,18 @@ impl<T: Payload> ChainedBftSMR<T> {
             proposers,
             runtime: Some(runtime),
             block_store: None,
+            evidence_store: None,
+            introspector: None,
             network,
             config,
             storage,
             initial_data: Some(initial_data),
             epoch_mgr,
+            time_service: None,
+            commit_subscribers: CommitSubscribers::default(),
+            payload_validator: Arc::new(NoopPayloadValidator::default()),
+            #[cfg(test)]
+            event_hooks: EventHooks::default(),
         }
     }
 
@@ -113,77 +550,208 @@ impl<T: Payload> ChainedBftSMR<T> {
         self.block_store.clone()
     }
 
-    fn create_pacemaker(
+    /// The blocks of the node, for the consumers that must not block on storage lookups.
+    #[cfg(test)]
+    pub fn block_reader(&self) -> Option<Arc<dyn AsyncBlockReader<Payload = T>>> {
+        self.block_store
+            .clone()
+            .map(|block_store| block_store as Arc<dyn AsyncBlockReader<Payload = T>>)
+    }
+
+    /// The ledger info with signatures of the latest block committed by the node, None if the
+    /// SMR hasn't been started.
+    pub fn latest_commit_certificate(&self) -> Option<LedgerInfoWithSignatures> {
+        self.block_store
+            .as_ref()
+            .map(|block_store| block_store.latest_commit_cert())
+    }
+
+    /// Subscribes to the ledger infos with signatures of the commits following the subscription,
+    /// which can happen before or after start. Every subscriber is notified independently: a
+    /// subscriber that doesn't keep up skips the commits while its buffer is full.
+    pub fn subscribe_commits(&self) -> mpsc::Receiver<LedgerInfoWithSignatures> {
+        self.commit_subscribers
+            .subscribe(COMMIT_SUBSCRIPTION_BUFFER_SIZE)
+    }
+
+    /// Serves the latest commit certificate to the debug interface, None if the SMR hasn't been
+    /// started.
+    pub fn commit_certificate_provider(&self) -> Option<Arc<dyn CommitCertificateProvider>> {
+        self.block_store
+            .clone()
+            .map(|block_store| block_store as Arc<dyn CommitCertificateProvider>)
+    }
+
+    /// Serves the internal state of consensus to the debug interface, None if the SMR hasn't been
+    /// started.
+    pub fn consensus_state_provider(&self) -> Option<Arc<dyn ConsensusStateProvider>> {
+        self.introspector
+            .clone()
+            .map(|introspector| introspector as Arc<dyn ConsensusStateProvider>)
+    }
+
+    /// The evidence of the equivocations detected so far (including the evidence detected before
+    /// a restart), empty if the SMR hasn't been started.
+    pub fn evidence(&self) -> Vec<Evidence<T>> {
+        self.evidence_store
+            .as_ref()
+            .map_or_else(Vec::new, |evidence_store| evidence_store.evidence())
+    }
+
+    /// Rotates the consensus key of the node within the current epoch: the node signs the
+    /// messages of the handover round and the following rounds with the new signer. Returns the
+    /// record of the rotation, which must reach the other validators (see `add_key_rotation`)
+    /// before the handover round. The rotation and the new signer are persisted, such that a
+    /// restarted node keeps signing with the new signer. Fails if the SMR hasn't been started.
+    pub fn rotate_consensus_key(
         &self,
-        persistent_liveness_storage: Box<dyn PersistentLivenessStorage>,
-        time_service: Arc<dyn TimeService>,
-        timeout_sender: channel::Sender<Round>,
-        highest_timeout_certificate: HighestTimeoutCertificates,
-    ) -> Pacemaker {
-        // 1.5^6 ~= 11
-        // Timeout goes from initial_timeout to initial_timeout*11 in 6 steps
-        let time_interval = Box::new(ExponentialTimeInterval::new(
-            self.config.pacemaker_initial_timeout,
-            1.5,
-            6,
-        ));
-        Pacemaker::new(
-            persistent_liveness_storage,
-            time_interval,
-            time_service,
-            timeout_sender,
-            highest_timeout_certificate,
-        )
+        new_signer: ValidatorSigner,
+        handover_round: Round,
+    ) -> Result<KeyRotation> {
+        let block_store = self
+            .block_store
+            .as_ref()
+            .ok_or_else(|| format_err!("Cannot rotate the key before the SMR is started"))?;
+        let rotation = KeyRotation::new(
+            self.epoch_mgr.epoch(),
+            handover_round,
+            &block_store.latest_signer(),
+            &new_signer,
+        )?;
+        self.add_key_rotation(&rotation)?;
+        block_store.rotate_signer(new_signer, handover_round)?;
+        Ok(rotation)
     }
 
-    /// Create a proposer election handler based on proposers
-    fn create_proposer_election(&self) -> Box<dyn ProposerElection<T> + Send + Sync> {
-        assert!(!self.proposers.is_empty());
-        match self.config.proposer_type {
-            ConsensusProposerType::MultipleOrderedProposers => {
-                Box::new(MultiProposer::new(self.proposers.clone(), 2))
-            }
-            // We don't really have a fixed proposer!
-            _ => Box::new(RotatingProposer::new(
-                self.proposers.clone(),
-                self.config.contiguous_rounds,
-            )),
-        }
+    /// Accepts the record of a validator rotating its consensus key within the current epoch
+    /// (see `EpochManager::add_key_rotation`). The key rotations of the epoch are persisted, such
+    /// that a restarted node replays them.
+    pub fn add_key_rotation(&self, rotation: &KeyRotation) -> Result<()> {
+        self.epoch_mgr.add_key_rotation(rotation)?;
+        self.storage
+            .save_key_rotations(self.epoch_mgr.key_rotations())
+            .with_context(|e| format!("Failed to persist {}: {}", rotation, e))?;
+        Ok(())
+    }
+
+    /// Replaces the clock of the runtime by the given time service, which drives the timeouts of
+    /// the pacemaker and the timestamps of the proposals. Must be called before start.
+    #[cfg(test)]
+    pub fn set_time_service(&mut self, time_service: Arc<dyn TimeService>) {
+        self.time_service = Some(time_service);
+    }
+
+    /// Only the remote proposals whose payload the given validator accepts are voted for (all
+    /// of them by default). Must be called before start.
+    pub fn set_payload_validator(
+        &mut self,
+        payload_validator: Arc<dyn PayloadValidator<Payload = T>>,
+    ) {
+        self.payload_validator = payload_validator;
+    }
+
+    /// Registers a hook invoked around every event handled by the event loop. The hooks added
+    /// after start are invoked starting from the next event.
+    #[cfg(test)]
+    pub fn add_event_hook(&self, hook: Arc<dyn EventHook>) {
+        self.event_hooks.add(hook);
     }
 
     fn start_event_processing(
         &mut self,
         executor: TaskExecutor,
+        mut factory: EventProcessorFactory<T>,
         mut event_processor: EventProcessor<T>,
+        pending_votes: Vec<VoteMsg>,
         mut pacemaker_timeout_sender_rx: channel::Receiver<Round>,
+        mut timeout_retransmission_rx: channel::Receiver<Round>,
+        mut commit_heartbeat_rx: channel::Receiver<u64>,
         mut network_receivers: NetworkReceivers<T>,
     ) {
+        #[cfg(test)]
+        let event_hooks = self.event_hooks.clone();
+        // In test builds, the registered hooks are invoked around every handled event.
+        macro_rules! handle_event {
+            ($event:expr, $handler:expr) => {{
+                #[cfg(test)]
+                let event = $event;
+                #[cfg(test)]
+                event_hooks.before(&event).await;
+                $handler;
+                #[cfg(test)]
+                event_hooks.after(&event);
+            }};
+        }
         let fut = async move {
             event_processor.start().await;
+            event_processor.restore_pending_votes(pending_votes).await;
             loop {
                 select! {
                     proposal_msg = network_receivers.proposals.select_next_some() => {
-                        event_processor.process_proposal_msg(proposal_msg).await;
+                        handle_event!(
+                            ProcessedEvent::proposal(&proposal_msg),
+                            event_processor.process_proposal_msg(proposal_msg).await
+                        );
                     }
                     block_retrieval = network_receivers.block_retrieval.select_next_some() => {
-                        event_processor.process_block_retrieval(block_retrieval).await;
+                        handle_event!(
+                            ProcessedEvent::block_retrieval(&block_retrieval),
+                            event_processor.process_block_retrieval(block_retrieval).await
+                        );
                     }
                     vote_msg = network_receivers.votes.select_next_some() => {
-                        event_processor.process_vote(vote_msg).await;
+                        handle_event!(
+                            ProcessedEvent::vote(&vote_msg),
+                            event_processor.process_vote(vote_msg).await
+                        );
                     }
                     remote_timeout_msg = network_receivers.timeout_msgs.select_next_some() => {
-                        event_processor.process_remote_timeout_msg(remote_timeout_msg).await;
+                        handle_event!(
+                            ProcessedEvent::remote_timeout(&remote_timeout_msg),
+                            event_processor.process_remote_timeout_msg(remote_timeout_msg).await
+                        );
                     }
                     local_timeout_round = pacemaker_timeout_sender_rx.select_next_some() => {
-                        event_processor.process_local_timeout(local_timeout_round).await;
+                        handle_event!(
+                            ProcessedEvent::LocalTimeout(local_timeout_round),
+                            event_processor.process_local_timeout(local_timeout_round).await
+                        );
+                    }
+                    retransmission_round = timeout_retransmission_rx.select_next_some() => {
+                        handle_event!(
+                            ProcessedEvent::TimeoutRetransmission(retransmission_round),
+                            event_processor.process_timeout_retransmission(retransmission_round).await
+                        );
+                    }
+                    heartbeat_epoch = commit_heartbeat_rx.select_next_some() => {
+                        handle_event!(
+                            ProcessedEvent::CommitHeartbeat(heartbeat_epoch),
+                            event_processor.process_commit_heartbeat(heartbeat_epoch).await
+                        );
                     }
                     sync_info_msg = network_receivers.sync_info_msgs.select_next_some() => {
-                        event_processor.process_sync_info_msg(sync_info_msg.0, sync_info_msg.1).await;
+                        handle_event!(
+                            ProcessedEvent::SyncInfo { author: sync_info_msg.1 },
+                            event_processor.process_sync_info_msg(sync_info_msg.0, sync_info_msg.1).await
+                        );
+                    }
+                    peer = network_receivers.sync_info_requests.select_next_some() => {
+                        handle_event!(
+                            ProcessedEvent::SyncInfoRequest { author: peer },
+                            event_processor.process_sync_info_request(peer).await
+                        );
                     }
                     complete => {
                         break;
                     }
                 }
+                // Once a reconfiguration is committed, the remaining events are processed by the
+                // event processor of the next epoch.
+                if let Some(epoch_change) = event_processor.take_reconfiguration() {
+                    let consensus_state = event_processor.consensus_state();
+                    event_processor = factory.start_new_epoch(epoch_change, consensus_state);
+                    event_processor.start().await;
+                }
             }
         };
         executor.spawn(fut.boxed().unit_error().compat());
@@ -203,16 +771,57 @@ impl<T: Payload> StateMachineReplication for ChainedBftSMR<T> {
             .as_mut()
             .expect("Consensus start: No valid runtime found!")
             .executor();
-        // Start network receivers before blocking on state synchronizer to unblock delivery of
-        // network events.
-        let network_receivers = self.network.start(&executor);
-        let time_service = Arc::new(ClockTimeService::new(executor.clone()));
-        let initial_data = self
+        let mut initial_data = self
             .initial_data
             .take()
             .expect("already started, initial data is None");
-        let consensus_state = initial_data.state();
+        // A restarted node resumes in the last epoch it switched to, before any message is
+        // received. The QCs of the recovered block tree were verified before they were persisted.
+        for epoch_change in initial_data.epoch_changes() {
+            let validators = self
+                .epoch_mgr
+                .validators()
+                .with_validator_set(epoch_change.validator_set());
+            let authors = validators.get_ordered_account_addresses();
+            self.epoch_mgr.start_new_epoch(
+                epoch_change.clone(),
+                validators,
+                initial_data.quorum_certs(),
+            );
+            self.proposers = epoch_proposers(&self.config.proposer_type, &self.proposers, authors);
+        }
+        // The key rotations of the last epoch are replayed in order, the ones of the previous
+        // epochs are obsolete.
+        for rotation in initial_data.key_rotations() {
+            if rotation.epoch() != self.epoch_mgr.epoch() {
+                continue;
+            }
+            if let Err(e) = self.epoch_mgr.add_key_rotation(rotation) {
+                warn!("Failed to replay {}: {:?}", rotation, e);
+            }
+        }
+        let rotated_signers = initial_data.take_rotated_signers();
+        // Start network receivers before blocking on state synchronizer to unblock delivery of
+        // network events.
+        let network_receivers = self.network.start(&executor);
+        let time_service = self.time_service.clone().unwrap_or_else(|| {
+            Arc::new(ClockTimeService::new(executor.clone())) as Arc<dyn TimeService>
+        });
+        let consensus_state = self
+            .storage
+            .persistent_safety_storage()
+            .consensus_state()
+            .expect("unable to recover consensus state");
+        debug!("Recovered consensus state: {}", consensus_state);
         let highest_timeout_certificates = initial_data.highest_timeout_certificates().clone();
+        let pending_votes = initial_data.pending_votes().to_vec();
+        let last_vote = initial_data.last_vote().cloned();
+        let last_timeout = initial_data.last_timeout().cloned();
+        let evidence_store = Arc::new(EvidenceStore::new(
+            Arc::clone(&self.storage),
+            initial_data.evidence().to_vec(),
+        ));
+        self.evidence_store = Some(Arc::clone(&evidence_store));
         if initial_data.need_sync() {
             // make sure we sync to the root state in case we're not
             state_computer.sync_to_or_bail(initial_data.root_ledger_info());
@@ -229,53 +838,78 @@ impl<T: Payload> StateMachineReplication for ChainedBftSMR<T> {
             signer,
             Arc::clone(&state_computer),
             true,
-            self.config.max_pruned_blocks_in_mem,
+            self.config.pruning_policy,
         )));
+        // The node keeps signing with its rotated key, as long as the rotation is part of the
+        // current epoch.
+        if let Some((previous_signer, handover_round, new_signer)) = rotated_signers {
+            if self.epoch_mgr.key_rotations().iter().any(|rotation| {
+                rotation.author() == new_signer.author()
+                    && rotation.handover_round() == handover_round
+                    && rotation.new_public_key() == &new_signer.public_key()
+            }) {
+                block_store.restore_rotated_signers(
+                    Arc::new(previous_signer),
+                    handover_round,
+                    Arc::new(new_signer),
+                );
+            }
+        }
 
         self.block_store = Some(Arc::clone(&block_store));
+        let introspector = Arc::new(ConsensusIntrospector::new(
+            Arc::clone(&block_store),
+            Arc::clone(&self.epoch_mgr),
+        ));
+        self.introspector = Some(Arc::clone(&introspector));
 
-        // txn manager is required both by proposal generator (to pull the proposers)
-        // and by event processor (to update their status).
-        let proposal_generator = ProposalGenerator::new(
-            block_store.clone(),
-            Arc::clone(&txn_manager),
-            time_service.clone(),
-            self.config.max_block_size,
-            true,
-        );
-
-        let safety_rules = SafetyRules::new(consensus_state);
-
+        // The pacemakers of all the epochs share the same timeout channel: the timeouts of the
+        // previous epochs are ignored by the pacemaker of the current one.
         let (timeout_sender, timeout_receiver) =
             channel::new(1_024, &counters::PENDING_PACEMAKER_TIMEOUTS);
-        let pacemaker = self.create_pacemaker(
-            self.storage.persistent_liveness_storage(),
-            time_service.clone(),
-            timeout_sender,
-            highest_timeout_certificates,
-        );
-
-        let proposer_election = self.create_proposer_election();
-        let event_processor = EventProcessor::new(
-            self.author,
+        let (timeout_retransmission_sender, timeout_retransmission_receiver) =
+            channel::new(1_024, &counters::PENDING_TIMEOUT_RETRANSMISSIONS);
+        let (commit_heartbeat_sender, commit_heartbeat_receiver) =
+            channel::new(1_024, &counters::PENDING_COMMIT_HEARTBEATS);
+        // The commits of the ordered blocks are applied in the background, in order.
+        let commit_pipeline = CommitPipeline::spawn(
+            &executor,
             Arc::clone(&block_store),
-            pacemaker,
-            proposer_election,
-            proposal_generator,
-            safety_rules,
-            state_computer,
-            txn_manager,
-            self.network.clone(),
-            Arc::clone(&self.storage),
-            time_service.clone(),
-            true,
-            Arc::clone(&self.epoch_mgr),
+            Arc::clone(&state_computer),
+            Arc::clone(&txn_manager),
+            self.commit_subscribers.clone(),
         );
+        let factory = EventProcessorFactory {
+            author: self.author,
+            proposers: self.proposers.clone(),
+            config: self.config.clone(),
+            block_store,
+            txn_manager,
+            state_computer,
+            commit_pipeline,
+            network: self.network.clone(),
+            storage: Arc::clone(&self.storage),
+            time_service,
+            timeout_sender,
+            timeout_retransmission_sender,
+            commit_heartbeat_sender,
+            epoch_mgr: Arc::clone(&self.epoch_mgr),
+            evidence_store,
+            introspector,
+            payload_validator: Arc::clone(&self.payload_validator),
+        };
+        let mut event_processor =
+            factory.create_event_processor(consensus_state, highest_timeout_certificates);
+        event_processor.restore_round_state(last_vote, last_timeout);
 
         self.start_event_processing(
             executor,
+            factory,
             event_processor,
+            pending_votes,
             timeout_receiver,
+            timeout_retransmission_receiver,
+            commit_heartbeat_receiver,
             network_receivers,
         );
 
@@ -3,38 +3,74 @@
 
 use crate::{
     chained_bft::{
-        block_storage::BlockReader,
+        block_storage::{
+            BlockReader, BlockStore, NeedFetchResult, PruningPolicy, VoteReceptionResult,
+        },
         chained_bft_smr::{ChainedBftSMR, ChainedBftSMRConfig},
-        common::Author,
+        common::{Author, Round},
         consensus_types::{
+            block::Block,
             proposal_msg::{ProposalMsg, ProposalUncheckedSignatures},
+            quorum_cert::QuorumCert,
+            sync_info::SyncInfo,
+            vote_data::VoteData,
             vote_msg::VoteMsg,
         },
-        network::ConsensusNetworkImpl,
-        network_tests::NetworkPlayground,
-        test_utils::{MockStateComputer, MockStorage, MockTransactionManager, TestPayload},
+        evidence::Evidence,
+        liveness::{
+            commit_heartbeat::CommitHeartbeatPolicy,
+            timeout_retransmission::TimeoutRetransmissionPolicy,
+        },
+        network::{network_fuzzing, ConsensusNetworkImpl, NetworkReceivers},
+        network_rate_limit::InboundRateLimitPolicy,
+        network_tests::{
+            LatencyDistribution, MessageTrace, NetworkPlayground, FUZZ_CORPUS_DIR_ENV,
+        },
+        safety::safety_rules::{ConsensusState, SafetyRules},
+        sync_manager::BlockRetrievalPolicy,
+        test_utils::{
+            EmptyPayloads, FailingPayloads, FullPayloads, MockPayloadValidator, MockStateComputer,
+            MockStorage, MockTransactionManager, PayloadStrategy, Simulation, TestPayload,
+            VariableSizePayloads,
+        },
     },
     state_replication::StateMachineReplication,
+    util::time_service::duration_since_epoch,
 };
 use channel;
-use crypto::hash::CryptoHash;
-use futures::{channel::mpsc, executor::block_on, prelude::*};
-use network::validator_network::{ConsensusNetworkEvents, ConsensusNetworkSender};
-use proto_conv::FromProto;
+use crypto::{ed25519, hash::CryptoHash, x25519, HashValue};
+use futures::{channel::mpsc, executor::block_on, prelude::*, select};
+use network::{
+    proto::ConsensusMsg,
+    validator_network::{ConsensusNetworkEvents, ConsensusNetworkSender},
+};
+use proto_conv::{FromProto, IntoProto};
+use protobuf::Message as ProtoMessage;
 use std::sync::Arc;
 
 use crate::chained_bft::{
     consensus_types::timeout_msg::TimeoutMsg,
     epoch_manager::EpochManager,
+    event_hooks::{EventGate, EventRecorder, ProcessedEvent},
     persistent_storage::RecoveryData,
     test_utils::{consensus_runtime, with_smr_id},
 };
-use config::config::ConsensusProposerType::{
-    self, FixedProposer, MultipleOrderedProposers, RotatingProposer,
+use config::config::{
+    CommitRule,
+    ConsensusProposerType::{
+        self, FixedProposer, LeaderReputation, MultipleOrderedProposers, RotatingProposer,
+        WeightedRotatingProposer,
+    },
+    SafetyRulesBackend,
 };
-use std::{collections::HashMap, time::Duration};
+use std::{collections::HashMap, env, path::Path, thread, time::Duration};
 use tokio::runtime;
-use types::crypto_proxies::{LedgerInfoWithSignatures, ValidatorSigner, ValidatorVerifier};
+use tools::tempdir::TempPath;
+use types::{
+    crypto_proxies::{LedgerInfoWithSignatures, ValidatorSigner, ValidatorVerifier},
+    validator_public_keys::ValidatorPublicKeys,
+    validator_set::ValidatorSet,
+};
 
 /// Auxiliary struct that is preparing SMR for the test
 struct SMRNode {
@@ -49,6 +85,46 @@ struct SMRNode {
     mempool: Arc<MockTransactionManager>,
     mempool_notif_receiver: mpsc::Receiver<usize>,
     storage: Arc<MockStorage<TestPayload>>,
+    reconfiguration: Option<(u64, ValidatorSet)>,
+    options: SMRNodeOptions,
+}
+
+/// The settings of a node that differ between the tests.
+#[derive(Clone)]
+struct SMRNodeOptions {
+    commit_rule: CommitRule,
+    nil_blocks_enabled: bool,
+    observer: bool,
+    // The observers the node sends its proposals and timeouts to.
+    observers: Vec<Author>,
+    commit_heartbeat_policy: CommitHeartbeatPolicy,
+    // Shapes the payloads proposed by the node and decides which of them fail to execute.
+    payload_strategy: Arc<dyn PayloadStrategy>,
+    max_block_size: u64,
+    max_block_bytes: u64,
+    // Checks the payloads of the proposals of the other nodes, all of them are accepted if unset.
+    payload_validator: Option<Arc<MockPayloadValidator>>,
+}
+
+impl Default for SMRNodeOptions {
+    fn default() -> Self {
+        Self {
+            commit_rule: CommitRule::ThreeChain,
+            nil_blocks_enabled: true,
+            observer: false,
+            observers: vec![],
+            // The heartbeats are disabled unless a test expects them, they would show up among
+            // the messages awaited by the other tests.
+            commit_heartbeat_policy: CommitHeartbeatPolicy {
+                interval: Duration::from_secs(0),
+                ..CommitHeartbeatPolicy::default()
+            },
+            payload_strategy: Arc::new(FullPayloads),
+            max_block_size: 50,
+            max_block_bytes: 4 * 1024 * 1024,
+            payload_validator: None,
+        }
+    }
 }
 
 impl SMRNode {
@@ -61,6 +137,8 @@ impl SMRNode {
         storage: Arc<MockStorage<TestPayload>>,
         initial_data: RecoveryData<TestPayload>,
         proposer_type: ConsensusProposerType,
+        reconfiguration: Option<(u64, ValidatorSet)>,
+        options: SMRNodeOptions,
     ) -> Self {
         let author = signer.author();
 
@@ -79,14 +157,32 @@ impl SMRNode {
             network_sender,
             network_events,
             Arc::clone(&epoch_mgr),
-        );
+        )
+        .with_observers(options.observers.clone());
 
         let config = ChainedBftSMRConfig {
-            max_pruned_blocks_in_mem: 10000,
+            pruning_policy: PruningPolicy::MaxBlocks(10000),
             pacemaker_initial_timeout: Duration::from_secs(3),
+            pacemaker_timeout_exponent_base: 1.5,
+            pacemaker_max_exponent: 6,
+            pacemaker_max_timeout: None,
+            pacemaker_adaptive_timeout_percentile: None,
+            timeout_retransmission_policy: TimeoutRetransmissionPolicy::default(),
+            commit_heartbeat_policy: options.commit_heartbeat_policy.clone(),
             proposer_type,
             contiguous_rounds: 2,
-            max_block_size: 50,
+            max_block_size: options.max_block_size,
+            max_block_bytes: options.max_block_bytes,
+            max_uncommitted_blocks: 20,
+            event_log_file: None,
+            block_retrieval_policy: BlockRetrievalPolicy::default(),
+            inbound_rate_limit_policy: InboundRateLimitPolicy::default(),
+            signature_verification_threads: 0,
+            safety_rules_backend: SafetyRulesBackend::InProcess,
+            commit_rule: options.commit_rule,
+            nil_blocks_enabled: options.nil_blocks_enabled,
+            leader_skipping_enabled: false,
+            observer: options.observer,
         };
         let mut smr = ChainedBftSMR::new(
             author,
@@ -99,18 +195,25 @@ impl SMRNode {
             initial_data,
             Arc::clone(&epoch_mgr),
         );
+        if let Some(simulation) = playground.simulation() {
+            smr.set_time_service(Arc::new(simulation.clone()));
+        }
+        if let Some(payload_validator) = &options.payload_validator {
+            smr.set_payload_validator(payload_validator.clone());
+        }
         let (commit_cb_sender, commit_cb_receiver) = mpsc::unbounded::<LedgerInfoWithSignatures>();
-        let mut mp = MockTransactionManager::new();
+        let mut mp =
+            MockTransactionManager::with_payload_strategy(Arc::clone(&options.payload_strategy));
         let commit_receiver = mp.take_commit_receiver();
         let mempool = Arc::new(mp);
-        smr.start(
-            mempool.clone(),
-            Arc::new(MockStateComputer::new(
-                commit_cb_sender.clone(),
-                Arc::clone(&storage),
-            )),
-        )
-        .expect("Failed to start SMR!");
+        let mut state_computer =
+            MockStateComputer::new(commit_cb_sender.clone(), Arc::clone(&storage))
+                .with_payload_strategy(Arc::clone(&options.payload_strategy));
+        if let Some((height, validators)) = reconfiguration.clone() {
+            state_computer = state_computer.with_reconfiguration(height, validators);
+        }
+        smr.start(mempool.clone(), Arc::new(state_computer))
+            .expect("Failed to start SMR!");
         Self {
             author,
             signer,
@@ -123,16 +226,22 @@ impl SMRNode {
             mempool,
             mempool_notif_receiver: commit_receiver,
             storage,
+            reconfiguration,
+            options,
         }
     }
 
-    fn restart(mut self, playground: &mut NetworkPlayground) -> Self {
+    fn restart(self, playground: &mut NetworkPlayground) -> Self {
+        self.try_restart(playground)
+            .unwrap_or_else(|e| panic!("fail to restart due to: {}", e))
+    }
+
+    /// Restarts the node from the data recovered by its storage, fails if the recovery fails
+    /// (in which case the node remains stopped).
+    fn try_restart(mut self, playground: &mut NetworkPlayground) -> failure::Result<Self> {
         self.smr.stop();
-        let recover_data = self
-            .storage
-            .get_recovery_data()
-            .unwrap_or_else(|e| panic!("fail to restart due to: {}", e));
-        Self::start(
+        let recover_data = self.storage.recover()?;
+        Ok(Self::start(
             playground,
             self.signer,
             self.epoch_mgr,
@@ -141,14 +250,39 @@ impl SMRNode {
             self.storage,
             recover_data,
             self.proposer_type,
-        )
+            self.reconfiguration,
+            self.options,
+        ))
     }
 
+    /// Starts `num_nodes` validators, the nodes with the given ids are byzantine: they are driven
+    /// by the malicious event loop of their mode instead of `ChainedBftSMR`. Returns the honest
+    /// nodes only (in the order of their ids).
     fn start_num_nodes(
         num_nodes: usize,
         quorum_size: usize,
         playground: &mut NetworkPlayground,
         proposer_type: ConsensusProposerType,
+        byzantine_nodes: &[(usize, ByzantineMode)],
+    ) -> Vec<Self> {
+        Self::start_num_nodes_with_options(
+            num_nodes,
+            quorum_size,
+            playground,
+            proposer_type,
+            byzantine_nodes,
+            SMRNodeOptions::default(),
+        )
+    }
+
+    /// Same as `start_num_nodes` with the given options for every node.
+    fn start_num_nodes_with_options(
+        num_nodes: usize,
+        quorum_size: usize,
+        playground: &mut NetworkPlayground,
+        proposer_type: ConsensusProposerType,
+        byzantine_nodes: &[(usize, ByzantineMode)],
+        options: SMRNodeOptions,
     ) -> Vec<Self> {
         let mut signers = vec![];
         let mut author_to_public_keys = HashMap::new();
@@ -169,45 +303,311 @@ impl SMRNode {
         let proposer = {
             match proposer_type {
                 FixedProposer => vec![peers[0]],
-                RotatingProposer | MultipleOrderedProposers => peers,
+                RotatingProposer
+                | MultipleOrderedProposers
+                | LeaderReputation
+                | WeightedRotatingProposer => peers,
             }
         };
         let mut nodes = vec![];
-        for smr_id in 0..num_nodes {
+        for (smr_id, signer) in signers.into_iter().enumerate() {
+            if let Some((_, mode)) = byzantine_nodes.iter().find(|(id, _)| *id == smr_id) {
+                ByzantineNode::start(playground, signer, Arc::clone(&epoch_mgr), *mode);
+                continue;
+            }
             let (storage, initial_data) = MockStorage::start_for_testing();
             nodes.push(Self::start(
                 playground,
-                signers.remove(0),
+                signer,
                 Arc::clone(&epoch_mgr),
                 proposer.clone(),
                 smr_id,
                 storage,
                 initial_data,
                 proposer_type,
+                None,
+                options.clone(),
             ));
         }
         nodes
     }
 }
 
-fn verify_finality_proof(node: &SMRNode, ledger_info_with_sig: &LedgerInfoWithSignatures) {
-    let ledger_info_hash = ledger_info_with_sig.ledger_info().hash();
-    for (author, signature) in ledger_info_with_sig.signatures() {
-        assert_eq!(
-            Ok(()),
-            node.epoch_mgr
-                .validators()
-                .verify_signature(*author, ledger_info_hash, &signature)
+/// The misbehavior of a byzantine node.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum ByzantineMode {
+    /// Proposes a second block in the rounds it leads, sent to half of the peers.
+    EquivocatingProposer,
+    /// Never votes.
+    WithholdVotes,
+    /// Votes for every proposal, as well as for a block of its own in the same round.
+    ConflictingVotes,
+    /// Answers every proposal with a sync info certifying the genesis block only.
+    StaleSyncInfo,
+}
+
+/// A malicious event loop run instead of `ChainedBftSMR`: the node follows the proposals of the
+/// honest nodes and proposes in the rounds it leads just enough to produce validly signed
+/// messages, which misbehave according to its mode. It never commits, times out or serves the
+/// block retrieval requests.
+struct ByzantineNode {
+    author: Author,
+    mode: ByzantineMode,
+    epoch_mgr: Arc<EpochManager>,
+    network_sender: ConsensusNetworkSender,
+    block_store: Arc<BlockStore<TestPayload>>,
+}
+
+impl ByzantineNode {
+    fn start(
+        playground: &mut NetworkPlayground,
+        signer: ValidatorSigner,
+        epoch_mgr: Arc<EpochManager>,
+        mode: ByzantineMode,
+    ) {
+        let author = signer.author();
+
+        let (network_reqs_tx, network_reqs_rx) = channel::new_test(8);
+        let (consensus_tx, consensus_rx) = channel::new_test(8);
+        let network_sender = ConsensusNetworkSender::new(network_reqs_tx);
+        let network_events = ConsensusNetworkEvents::new(consensus_rx);
+
+        playground.add_node(author, consensus_tx, network_reqs_rx);
+        let executor = playground.executor();
+        let mut network = ConsensusNetworkImpl::new(
+            author,
+            network_sender.clone(),
+            network_events,
+            Arc::clone(&epoch_mgr),
+        );
+        let network_receivers = network.start::<TestPayload>(&executor);
+
+        let (storage, initial_data) = MockStorage::start_for_testing();
+        // Nothing is ever committed by the byzantine node.
+        let (commit_cb_sender, _) = mpsc::unbounded::<LedgerInfoWithSignatures>();
+        let state_computer = MockStateComputer::new(commit_cb_sender, Arc::clone(&storage));
+        let block_store = Arc::new(block_on(BlockStore::new(
+            storage,
+            initial_data,
+            signer,
+            Arc::new(state_computer),
+            true,
+            PruningPolicy::MaxBlocks(10000),
+        )));
+        let node = Self {
+            author,
+            mode,
+            epoch_mgr,
+            network_sender,
+            block_store,
+        };
+        executor.spawn(node.run(network_receivers).boxed().unit_error().compat());
+    }
+
+    async fn run(mut self, mut network_receivers: NetworkReceivers<TestPayload>) {
+        self.propose(QuorumCert::certificate_for_genesis()).await;
+        loop {
+            select! {
+                proposal_msg = network_receivers.proposals.select_next_some() => {
+                    self.process_proposal_msg(proposal_msg).await;
+                }
+                vote_msg = network_receivers.votes.select_next_some() => {
+                    self.process_vote(vote_msg).await;
+                }
+                // The other messages are drained, such that the network task is never blocked.
+                _ = network_receivers.block_retrieval.select_next_some() => (),
+                _ = network_receivers.timeout_msgs.select_next_some() => (),
+                _ = network_receivers.sync_info_msgs.select_next_some() => (),
+                _ = network_receivers.sync_info_requests.select_next_some() => (),
+                complete => break,
+            }
+        }
+    }
+
+    async fn process_proposal_msg(&mut self, proposal_msg: ProposalMsg<TestPayload>) {
+        let proposal = proposal_msg.take_proposal();
+        // The missing blocks are not retrieved: the node just stops following the chain.
+        let qc = proposal.quorum_cert().clone();
+        if self.block_store.need_fetch_for_quorum_cert(&qc) == NeedFetchResult::QCBlockExist
+            && self.block_store.insert_single_quorum_cert(qc).is_err()
+        {
+            return;
+        }
+        let proposal = match self.block_store.execute_and_insert_block(proposal).await {
+            Ok(executed_block) => executed_block.block().clone(),
+            Err(_) => return,
+        };
+        match self.mode {
+            ByzantineMode::EquivocatingProposer => self.vote(&proposal).await,
+            ByzantineMode::WithholdVotes => (),
+            ByzantineMode::ConflictingVotes => {
+                self.vote(&proposal).await;
+                let parent = match self.block_store.get_block(proposal.parent_id()) {
+                    Some(parent) => parent,
+                    None => return,
+                };
+                let conflicting_block = Block::make_block(
+                    parent.block(),
+                    vec![],
+                    proposal.round(),
+                    proposal.timestamp_usecs(),
+                    proposal.quorum_cert().clone(),
+                    self.block_store.signer(),
+                );
+                if let Ok(executed_block) = self
+                    .block_store
+                    .execute_and_insert_block(conflicting_block)
+                    .await
+                {
+                    self.vote(executed_block.block()).await;
+                }
+            }
+            ByzantineMode::StaleSyncInfo => {
+                self.vote(&proposal).await;
+                let genesis_qc = QuorumCert::certificate_for_genesis();
+                let mut msg = ConsensusMsg::new();
+                msg.set_epoch(self.epoch_mgr.epoch());
+                msg.set_sync_info(SyncInfo::new(genesis_qc.clone(), genesis_qc, None).into_proto());
+                if let Some(proposer) = proposal.author() {
+                    self.send(msg, vec![proposer]).await;
+                }
+            }
+        }
+    }
+
+    /// Proposes as soon as the votes form a QC (the votes are only sent to the proposers of the
+    /// next round).
+    async fn process_vote(&mut self, vote_msg: VoteMsg) {
+        if let VoteReceptionResult::NewQuorumCertificate(qc) = self
+            .block_store
+            .insert_vote(vote_msg, &self.epoch_mgr.validators())
+        {
+            self.propose(qc.as_ref().clone()).await;
+        }
+    }
+
+    /// Proposes a block extending the given QC to all the peers, which drop it unless the node is
+    /// a valid proposer of the round.
+    async fn propose(&mut self, qc: QuorumCert) {
+        if self
+            .block_store
+            .insert_single_quorum_cert(qc.clone())
+            .is_err()
+        {
+            return;
+        }
+        let parent = match self.block_store.get_block(qc.certified_block_id()) {
+            Some(parent) => parent,
+            None => return,
+        };
+        let round = qc.certified_block_round() + 1;
+        let timestamp_usecs = std::cmp::max(
+            duration_since_epoch().as_micros() as u64,
+            parent.timestamp_usecs() + 1,
+        );
+        let sync_info = SyncInfo::new(
+            qc.clone(),
+            self.block_store.highest_ledger_info().as_ref().clone(),
+            None,
+        );
+        let proposal = |payload| {
+            let block = Block::make_block(
+                parent.block(),
+                payload,
+                round,
+                timestamp_usecs,
+                qc.clone(),
+                self.block_store.signer(),
+            );
+            let mut msg = ConsensusMsg::new();
+            msg.set_epoch(self.epoch_mgr.epoch());
+            msg.set_proposal(ProposalMsg::new(block, sync_info.clone()).into_proto());
+            msg
+        };
+        let peers = self.peers();
+        let honest_proposal = proposal(vec![]);
+        let equivocation = proposal(vec![round as usize]);
+        self.send(honest_proposal, peers.clone()).await;
+        if self.mode == ByzantineMode::EquivocatingProposer {
+            self.send(equivocation, peers[..peers.len() / 2].to_vec())
+                .await;
+        }
+    }
+
+    /// Votes for the given executed block regardless of the previous votes of the node.
+    async fn vote(&mut self, block: &Block<TestPayload>) {
+        let vote_info = SafetyRules::new(ConsensusState::default())
+            .voting_rule(block)
+            .expect("A fresh consensus state accepts any proposal");
+        let executed_state_id = self
+            .block_store
+            .get_compute_result(block.id())
+            .expect("The block voted for must be executed")
+            .executed_state
+            .state_id;
+        let vote_msg = VoteMsg::new(
+            VoteData::new(
+                vote_info.proposal_id(),
+                executed_state_id,
+                block.round(),
+                vote_info.parent_block_id(),
+                vote_info.parent_block_round(),
+                vote_info.grandparent_block_id(),
+                vote_info.grandparent_block_round(),
+            ),
+            self.author,
+            self.block_store
+                .ledger_info_placeholder(vote_info.potential_commit_id(), self.epoch_mgr.epoch()),
+            self.block_store.signer(),
         );
+        let mut msg = ConsensusMsg::new();
+        msg.set_epoch(self.epoch_mgr.epoch());
+        msg.set_vote(vote_msg.into_proto());
+        // The peers, which are not the proposers of the next round, drop the vote.
+        let peers = self.peers();
+        self.send(msg, peers).await;
+    }
+
+    fn peers(&self) -> Vec<Author> {
+        self.epoch_mgr
+            .validators()
+            .get_ordered_account_addresses()
+            .into_iter()
+            .filter(|peer| *peer != self.author)
+            .collect()
+    }
+
+    async fn send(&mut self, msg: ConsensusMsg, recipients: Vec<Author>) {
+        for peer in recipients {
+            // The playground is gone once the test is over.
+            if self
+                .network_sender
+                .send_to(peer, msg.clone())
+                .await
+                .is_err()
+            {
+                return;
+            }
+        }
     }
 }
 
+fn verify_finality_proof(node: &SMRNode, ledger_info_with_sig: &LedgerInfoWithSignatures) {
+    let ledger_info_hash = ledger_info_with_sig.ledger_info().hash();
+    assert_eq!(
+        Ok(()),
+        node.epoch_mgr
+            .validators()
+            .batch_verify(ledger_info_hash, ledger_info_with_sig.signatures())
+    );
+}
+
 #[test]
 /// Should receive a new proposal upon start
 fn basic_start_test() {
     let runtime = consensus_runtime();
     let mut playground = NetworkPlayground::new(runtime.executor());
-    let nodes = SMRNode::start_num_nodes(2, 2, &mut playground, RotatingProposer);
+    let nodes = SMRNode::start_num_nodes(2, 2, &mut playground, RotatingProposer, &[]);
     let genesis = nodes[0]
         .smr
         .block_store()
@@ -235,7 +635,7 @@ fn basic_start_test() {
 fn start_with_proposal_test() {
     let runtime = consensus_runtime();
     let mut playground = NetworkPlayground::new(runtime.executor());
-    let nodes = SMRNode::start_num_nodes(2, 2, &mut playground, RotatingProposer);
+    let nodes = SMRNode::start_num_nodes(2, 2, &mut playground, RotatingProposer, &[]);
 
     block_on(async move {
         let _proposals = playground
@@ -263,13 +663,21 @@ fn start_with_proposal_test() {
             .unwrap()
             .get_block(proposed_block_id)
             .is_some());
+        assert!(nodes[1]
+            .smr
+            .block_reader()
+            .unwrap()
+            .get_block_async(proposed_block_id)
+            .await
+            .is_some());
     });
 }
 
 fn basic_full_round(num_nodes: usize, quorum_size: usize, proposer_type: ConsensusProposerType) {
     let runtime = consensus_runtime();
     let mut playground = NetworkPlayground::new(runtime.executor());
-    let _nodes = SMRNode::start_num_nodes(num_nodes, quorum_size, &mut playground, proposer_type);
+    let _nodes =
+        SMRNode::start_num_nodes(num_nodes, quorum_size, &mut playground, proposer_type, &[]);
 
     // In case we're using multi-proposer, every proposal and vote is sent to two participants.
     let num_messages_to_send = if proposer_type == MultipleOrderedProposers {
@@ -311,13 +719,19 @@ fn happy_path_with_multi_proposer() {
     basic_full_round(2, 2, MultipleOrderedProposers);
 }
 
+#[test]
+/// Basic happy path with the proposers elected by their reputation
+fn happy_path_with_leader_reputation() {
+    basic_full_round(2, 2, LeaderReputation);
+}
+
 /// Verify the basic e2e flow: blocks are committed, txn manager is notified, block tree is
 /// pruned, restart the node and we can still continue.
 #[test]
 fn basic_commit_and_restart() {
     let runtime = consensus_runtime();
     let mut playground = NetworkPlayground::new(runtime.executor());
-    let mut nodes = SMRNode::start_num_nodes(2, 2, &mut playground, RotatingProposer);
+    let mut nodes = SMRNode::start_num_nodes(2, 2, &mut playground, RotatingProposer, &[]);
     let mut block_ids = vec![];
 
     block_on(async {
@@ -410,88 +824,607 @@ fn basic_commit_and_restart() {
 }
 
 #[test]
-fn basic_block_retrieval() {
+/// Same as `basic_commit_and_restart` with the 2-chain commit rule: a block is committed one
+/// round earlier.
+fn basic_commit_and_restart_two_chain() {
     let runtime = consensus_runtime();
     let mut playground = NetworkPlayground::new(runtime.executor());
-    // This test depends on the fixed proposer on nodes[0]
-    let mut nodes = SMRNode::start_num_nodes(3, 2, &mut playground, FixedProposer);
-    block_on(async move {
-        let mut first_proposals = vec![];
-        // First three proposals are delivered just to nodes[0[ and nodes[1].
-        playground.drop_message_for(&nodes[0].author, nodes[2].author);
-        for _ in 0..2 {
-            playground
-                .wait_for_messages(1, NetworkPlayground::proposals_only)
+    let mut nodes = SMRNode::start_num_nodes_with_options(
+        2,
+        2,
+        &mut playground,
+        RotatingProposer,
+        &[],
+        SMRNodeOptions {
+            commit_rule: CommitRule::TwoChain,
+            ..SMRNodeOptions::default()
+        },
+    );
+    let mut block_ids = vec![];
+
+    block_on(async {
+        let num_rounds = 10;
+
+        for round in 0..num_rounds {
+            let _proposals = playground
+                .wait_for_messages(1, NetworkPlayground::exclude_timeout_msg)
                 .await;
+
+            // A proposal is carrying a QC that commits a block of round - 2.
+            if round >= 2 {
+                let block_id_to_commit = block_ids[round - 2];
+                let commit_v1 = nodes[0].commit_cb_receiver.next().await.unwrap();
+                let commit_v2 = nodes[1].commit_cb_receiver.next().await.unwrap();
+                assert_eq!(
+                    commit_v1.ledger_info().consensus_block_id(),
+                    block_id_to_commit
+                );
+                verify_finality_proof(&nodes[0], &commit_v1);
+                assert_eq!(
+                    commit_v2.ledger_info().consensus_block_id(),
+                    block_id_to_commit
+                );
+                verify_finality_proof(&nodes[1], &commit_v2);
+            }
+
+            // v1 and v2 send votes
             let mut votes = playground
                 .wait_for_messages(1, NetworkPlayground::votes_only)
                 .await;
             let vote_msg = VoteMsg::from_proto(votes[0].1.take_vote()).unwrap();
-            let proposal_id = vote_msg.vote_data().block_id();
-            first_proposals.push(proposal_id);
+            block_ids.push(vote_msg.vote_data().block_id());
         }
-        // The next proposal is delivered to all: as a result nodes[2] should retrieve the missing
-        // blocks from nodes[0] and vote for the 3th proposal.
-        playground.stop_drop_message_for(&nodes[0].author, &nodes[2].author);
-
-        playground
-            .wait_for_messages(2, NetworkPlayground::proposals_only)
-            .await;
-        // Wait until nodes[2] sent out a vote, drop the vote from nodes[1] so that nodes[0]
-        // won't move too far and prune the requested block
-        playground.drop_message_for(&nodes[1].author, nodes[0].author);
+        assert!(
+            nodes[0].smr.block_store().unwrap().root().height() >= 7,
+            "height of node 0 is {}",
+            nodes[0].smr.block_store().unwrap().root().height()
+        );
+        assert!(
+            nodes[1].smr.block_store().unwrap().root().height() >= 7,
+            "height of node 1 is {}",
+            nodes[1].smr.block_store().unwrap().root().height()
+        );
+        // This message is for proposal with round 11 to delivery the QC, but not gather the QC
+        // so after restart, proposer will propose round 11 again.
         playground
-            .wait_for_messages(1, NetworkPlayground::votes_only)
+            .wait_for_messages(1, NetworkPlayground::exclude_timeout_msg)
             .await;
-        playground.stop_drop_message_for(&nodes[1].author, &nodes[0].author);
-        // the first two proposals should be present at nodes[2]
-        for block_id in &first_proposals {
-            assert!(nodes[2]
-                .smr
-                .block_store()
-                .unwrap()
-                .get_block(*block_id)
-                .is_some());
-        }
+    });
+    // create a new playground to avoid polling potential vote messages in previous one.
+    playground = NetworkPlayground::new(runtime.executor());
+    nodes = nodes
+        .into_iter()
+        .map(|node| node.restart(&mut playground))
+        .collect();
 
-        // Both nodes[1] and nodes[2] are going to vote for 4th proposal and commit the 1th one.
+    block_on(async {
+        let mut round = 0;
 
-        // Verify that nodes[2] commits the first proposal.
-        playground
-            .wait_for_messages(2, NetworkPlayground::votes_only)
-            .await;
-        if let Some(commit_v3) = nodes[2].commit_cb_receiver.next().await {
-            assert_eq!(
-                commit_v3.ledger_info().consensus_block_id(),
-                first_proposals[0],
-            );
+        while round < 10 {
+            // Same race as in `basic_commit_and_restart`: collect a network vote before moving
+            // the round forward.
+            loop {
+                let msg = playground
+                    .wait_for_messages(1, NetworkPlayground::exclude_timeout_msg)
+                    .await;
+                if msg[0].1.has_vote() {
+                    round += 1;
+                    break;
+                }
+            }
         }
+        // After receiving the vote for round 20, the root should be at least height 17.
+        assert!(
+            nodes[0].smr.block_store().unwrap().root().height() >= 17,
+            "height of node 0 is {}",
+            nodes[0].smr.block_store().unwrap().root().height()
+        );
+        assert!(
+            nodes[1].smr.block_store().unwrap().root().height() >= 17,
+            "height of node 1 is {}",
+            nodes[1].smr.block_store().unwrap().root().height()
+        );
     });
 }
 
+/// Returns the rounds of the votes sent by the given author among the messages.
+fn vote_rounds_of(author: Author, msgs: Vec<(Author, ConsensusMsg)>) -> Vec<Round> {
+    msgs.into_iter()
+        .filter(|(_, msg)| msg.has_vote())
+        .map(|(_, mut msg)| VoteMsg::from_proto(msg.take_vote()).unwrap())
+        .filter(|vote| vote.author() == author)
+        .map(|vote| vote.vote_data().block_round())
+        .collect()
+}
+
 #[test]
-fn block_retrieval_with_timeout() {
+/// A node whose storage crashes in the middle of a write restarts from the data flushed before
+/// the crash without forgetting the votes it sent, and consensus resumes.
+fn restart_after_storage_crash() {
     let runtime = consensus_runtime();
     let mut playground = NetworkPlayground::new(runtime.executor());
-    let nodes = SMRNode::start_num_nodes(3, 2, &mut playground, FixedProposer);
-    block_on(async move {
-        let mut first_proposals = vec![];
-        // First three proposals are delivered just to nodes[0] and nodes[1].
-        playground.drop_message_for(&nodes[0].author, nodes[2].author);
-        for _ in 0..2 {
-            playground
-                .wait_for_messages(1, NetworkPlayground::proposals_only)
+    let nodes = SMRNode::start_num_nodes(2, 2, &mut playground, RotatingProposer, &[]);
+    let author = nodes[1].author;
+    let storage = Arc::clone(&nodes[1].storage);
+    storage.crash_after_writes(10);
+
+    let mut vote_rounds = vec![];
+    block_on(async {
+        while !storage.has_crashed() {
+            let msgs = playground
+                .wait_for_messages(1, NetworkPlayground::take_all)
                 .await;
-            let mut votes = playground
-                .wait_for_messages(1, NetworkPlayground::votes_only)
+            vote_rounds.extend(vote_rounds_of(author, msgs));
+        }
+        // The crashed node can neither vote nor time out: wait for the other node to time out
+        // while delivering the messages sent before the crash.
+        loop {
+            let msgs = playground
+                .wait_for_messages(1, NetworkPlayground::take_all)
                 .await;
-            let vote_msg = VoteMsg::from_proto(votes[0].1.take_vote()).unwrap();
-            let proposal_id = vote_msg.vote_data().block_id();
-            first_proposals.push(proposal_id);
+            let is_timeout = msgs[0].1.has_timeout_msg();
+            vote_rounds.extend(vote_rounds_of(author, msgs));
+            if is_timeout {
+                break;
+            }
         }
-        // The next proposal is delivered to all: as a result nodes[2] should retrieve the missing
-        // blocks from v1 and vote for the 4th proposal.
-        playground.stop_drop_message_for(&nodes[0].author, &nodes[2].author);
+    });
+    // Every vote sent was persisted before the crash.
+    let last_vote_round = storage
+        .shared_storage
+        .state
+        .lock()
+        .unwrap()
+        .last_vote_round();
+    assert!(vote_rounds.iter().all(|round| *round <= last_vote_round));
+
+    playground = NetworkPlayground::new(runtime.executor());
+    let _nodes: Vec<_> = nodes
+        .into_iter()
+        .map(|node| node.restart(&mut playground))
+        .collect();
+    block_on(async {
+        let mut new_vote_rounds = vec![];
+        while new_vote_rounds.len() < 3 {
+            let msgs = playground
+                .wait_for_messages(1, NetworkPlayground::votes_only)
+                .await;
+            new_vote_rounds.extend(vote_rounds_of(author, msgs));
+        }
+        // The restarted node doesn't vote again in the rounds it voted in before the crash.
+        assert!(new_vote_rounds.iter().all(|round| *round > last_vote_round));
+    });
+}
+
+/// Records the block voted for by every author in every round, the votes being sent on their own
+/// or attached to the timeouts, and panics if an author voted for two blocks in the same round.
+fn check_no_conflicting_votes(
+    voted_blocks: &mut HashMap<(Author, Round), HashValue>,
+    msgs: Vec<(Author, ConsensusMsg)>,
+) {
+    for (_, mut msg) in msgs {
+        let vote = if msg.has_vote() {
+            VoteMsg::from_proto(msg.take_vote()).unwrap()
+        } else if msg.has_timeout_msg() {
+            let timeout_msg = TimeoutMsg::from_proto(msg.take_timeout_msg()).unwrap();
+            match timeout_msg.pacemaker_timeout().vote_msg() {
+                Some(vote) => vote.clone(),
+                None => continue,
+            }
+        } else {
+            continue;
+        };
+        let round = vote.vote_data().block_round();
+        let block_id = vote.vote_data().block_id();
+        let voted_block = voted_blocks
+            .entry((vote.author(), round))
+            .or_insert(block_id);
+        assert_eq!(
+            *voted_block,
+            block_id,
+            "{} voted for two blocks in round {}",
+            vote.author().short_str(),
+            round
+        );
+    }
+}
+
+#[test]
+/// The nodes restarted one after the other while the others keep running never send conflicting
+/// votes: the vote and the timeout a node sent before restarting are sent again after it.
+fn restart_under_load_without_equivocation() {
+    let runtime = consensus_runtime();
+    let mut playground = NetworkPlayground::new(runtime.executor());
+    let mut nodes = SMRNode::start_num_nodes(4, 3, &mut playground, RotatingProposer, &[]);
+    let mut voted_blocks = HashMap::new();
+
+    for restart in 0..8 {
+        block_on(async {
+            let mut num_msgs = 0;
+            while num_msgs < 20 {
+                let msgs = playground
+                    .wait_for_messages(1, NetworkPlayground::take_all)
+                    .await;
+                num_msgs += msgs.len();
+                check_no_conflicting_votes(&mut voted_blocks, msgs);
+            }
+        });
+        // The node restarts in the same playground: the messages in flight are still delivered.
+        let index = restart % nodes.len();
+        let node = nodes.remove(index);
+        nodes.insert(index, node.restart(&mut playground));
+    }
+
+    // Every node keeps committing after the restarts.
+    for node in nodes.iter_mut() {
+        while let Ok(Some(_)) = node.commit_cb_receiver.try_next() {}
+    }
+    block_on(async {
+        let mut committed = vec![false; nodes.len()];
+        while committed.iter().any(|committed| !committed) {
+            let msgs = playground
+                .wait_for_messages(1, NetworkPlayground::take_all)
+                .await;
+            check_no_conflicting_votes(&mut voted_blocks, msgs);
+            for (node, committed) in nodes.iter_mut().zip(committed.iter_mut()) {
+                if let Ok(Some(_)) = node.commit_cb_receiver.try_next() {
+                    *committed = true;
+                }
+            }
+        }
+    });
+    assert!(nodes.iter().all(|node| node.smr.evidence().is_empty()));
+}
+
+#[test]
+/// A node fails to restart from corrupted data instead of starting from an inconsistent state,
+/// and restarts once its data can be read again.
+fn restart_fails_on_corrupted_storage() {
+    let runtime = consensus_runtime();
+    let mut playground = NetworkPlayground::new(runtime.executor());
+    let mut nodes = SMRNode::start_num_nodes(2, 2, &mut playground, RotatingProposer, &[]);
+    block_on(async {
+        playground
+            .wait_for_messages(3, NetworkPlayground::votes_only)
+            .await;
+    });
+
+    playground = NetworkPlayground::new(runtime.executor());
+    let node = nodes.pop().unwrap();
+    let signer = node.signer.clone();
+    let epoch_mgr = Arc::clone(&node.epoch_mgr);
+    let proposer = node.proposer.clone();
+    let storage = Arc::clone(&node.storage);
+    storage.corrupt_next_recovery();
+    assert!(node.try_restart(&mut playground).is_err());
+
+    let mut nodes = vec![
+        nodes.pop().unwrap().restart(&mut playground),
+        SMRNode::start(
+            &mut playground,
+            signer,
+            epoch_mgr,
+            proposer,
+            11,
+            Arc::clone(&storage),
+            storage.recover().unwrap(),
+            RotatingProposer,
+            None,
+            SMRNodeOptions::default(),
+        ),
+    ];
+    block_on(async {
+        loop {
+            playground
+                .wait_for_messages(1, NetworkPlayground::votes_only)
+                .await;
+            if let Ok(Some(_)) = nodes[1].commit_cb_receiver.try_next() {
+                break;
+            }
+        }
+    });
+}
+
+#[test]
+/// The failed writes of a node prevent it from voting, but don't prevent consensus from
+/// committing once the writes succeed again.
+fn commit_after_failed_writes() {
+    let runtime = consensus_runtime();
+    let mut playground = NetworkPlayground::new(runtime.executor());
+    let mut nodes = SMRNode::start_num_nodes(2, 2, &mut playground, RotatingProposer, &[]);
+    let author = nodes[1].author;
+    nodes[1].storage.fail_next_writes(5);
+
+    block_on(async {
+        let mut vote_rounds = vec![];
+        loop {
+            let msgs = playground
+                .wait_for_messages(1, NetworkPlayground::take_all)
+                .await;
+            vote_rounds.extend(vote_rounds_of(author, msgs));
+            if let Ok(Some(_)) = nodes[1].commit_cb_receiver.try_next() {
+                break;
+            }
+        }
+        let last_vote_round = nodes[1]
+            .storage
+            .shared_storage
+            .state
+            .lock()
+            .unwrap()
+            .last_vote_round();
+        assert!(vote_rounds.iter().all(|round| *round <= last_vote_round));
+    });
+}
+
+#[test]
+/// Every commit subscriber of a node is notified of the same commits as the state computer.
+fn multiple_commit_subscribers() {
+    let runtime = consensus_runtime();
+    let mut playground = NetworkPlayground::new(runtime.executor());
+    let mut nodes = SMRNode::start_num_nodes(2, 2, &mut playground, RotatingProposer, &[]);
+    let mut subscribers = vec![
+        nodes[0].smr.subscribe_commits(),
+        nodes[0].smr.subscribe_commits(),
+    ];
+
+    block_on(async move {
+        let mut committed = vec![];
+        while committed.len() < 3 {
+            playground
+                .wait_for_messages(1, NetworkPlayground::take_all)
+                .await;
+            while let Ok(Some(commit)) = nodes[0].commit_cb_receiver.try_next() {
+                committed.push(commit);
+            }
+        }
+        for subscriber in subscribers.iter_mut() {
+            for commit in &committed {
+                assert_eq!(subscriber.next().await.as_ref(), Some(commit));
+            }
+        }
+    });
+}
+
+#[test]
+/// The consensus state served to the debug interface follows the rounds and the commits.
+fn consensus_state_view() {
+    let runtime = consensus_runtime();
+    let mut playground = NetworkPlayground::new(runtime.executor());
+    let mut nodes = SMRNode::start_num_nodes(2, 2, &mut playground, RotatingProposer, &[]);
+    let provider = nodes[0].smr.consensus_state_provider().unwrap();
+
+    block_on(async move {
+        loop {
+            playground
+                .wait_for_messages(1, NetworkPlayground::take_all)
+                .await;
+            if let Ok(Some(_)) = nodes[0].commit_cb_receiver.try_next() {
+                break;
+            }
+        }
+        let state = provider.consensus_state();
+        assert!(state.root.round > 0);
+        assert!(state.hqc_round >= state.root.round);
+        // The rounds of the two following blocks were entered before the commit.
+        assert!(state.current_round > state.root.round + 1);
+        assert!(state.round_deadline_ms.is_some());
+        assert!(state.proposer.is_some());
+        assert!(state.last_vote.is_some());
+        assert!(state
+            .pending_blocks
+            .iter()
+            .all(|block| block.round > state.root.round));
+    });
+}
+
+#[test]
+/// An observer receiving the proposals of the validators commits the same blocks, without ever
+/// sending a vote, a proposal or a timeout.
+fn observer_follows_commits() {
+    let runtime = consensus_runtime();
+    let mut playground = NetworkPlayground::new(runtime.executor());
+    let observer_signer = ValidatorSigner::from_int(2);
+    let observer_author = observer_signer.author();
+    let mut nodes = SMRNode::start_num_nodes_with_options(
+        2,
+        2,
+        &mut playground,
+        RotatingProposer,
+        &[],
+        SMRNodeOptions {
+            observers: vec![observer_author],
+            ..SMRNodeOptions::default()
+        },
+    );
+    let (storage, initial_data) = MockStorage::start_for_testing();
+    let mut observer = SMRNode::start(
+        &mut playground,
+        observer_signer,
+        Arc::clone(&nodes[0].epoch_mgr),
+        nodes[0].proposer.clone(),
+        2,
+        storage,
+        initial_data,
+        RotatingProposer,
+        None,
+        SMRNodeOptions {
+            observer: true,
+            ..SMRNodeOptions::default()
+        },
+    );
+
+    block_on(async move {
+        let mut validator_commits = vec![];
+        let mut observer_commits = vec![];
+        while observer_commits.len() < 5 {
+            let msg = playground
+                .wait_for_messages(1, NetworkPlayground::take_all)
+                .await;
+            if msg[0].0 == observer_author {
+                assert!(!msg[0].1.has_vote());
+                assert!(!msg[0].1.has_proposal());
+                assert!(!msg[0].1.has_timeout_msg());
+            }
+            while let Ok(Some(commit)) = nodes[0].commit_cb_receiver.try_next() {
+                validator_commits.push(commit.ledger_info().consensus_block_id());
+            }
+            while let Ok(Some(commit)) = observer.commit_cb_receiver.try_next() {
+                observer_commits.push(commit.ledger_info().consensus_block_id());
+            }
+        }
+        // The validators commit the blocks from the same proposals.
+        while validator_commits.len() < observer_commits.len() {
+            let commit = nodes[0].commit_cb_receiver.next().await.unwrap();
+            validator_commits.push(commit.ledger_info().consensus_block_id());
+        }
+        assert_eq!(
+            observer_commits[..],
+            validator_commits[..observer_commits.len()]
+        );
+    });
+}
+
+#[test]
+/// Hold the handling of the proposals by node 2 until node 1 commits a block: the held node
+/// catches up once released and commits the same block.
+fn hold_proposals_until_commit() {
+    let runtime = consensus_runtime();
+    let mut playground = NetworkPlayground::new(runtime.executor());
+    // nodes[0] is the fixed proposer, nodes[0] and nodes[1] form a quorum without nodes[2].
+    let mut nodes = SMRNode::start_num_nodes(3, 2, &mut playground, FixedProposer, &[]);
+    let gate = EventGate::new(|event| match event {
+        ProcessedEvent::Proposal { .. } => true,
+        _ => false,
+    });
+    let (recorder, mut node2_events) = EventRecorder::new();
+    nodes[2].smr.add_event_hook(gate.clone());
+    nodes[2].smr.add_event_hook(recorder);
+
+    block_on(async move {
+        let node1_commit = loop {
+            playground
+                .wait_for_messages(1, NetworkPlayground::exclude_timeout_msg)
+                .await;
+            if let Ok(Some(commit)) = nodes[1].commit_cb_receiver.try_next() {
+                break commit;
+            }
+        };
+
+        // nodes[2] is stuck on its first proposal.
+        assert_eq!(gate.num_held(), 1);
+        while let Ok(Some(event)) = node2_events.try_next() {
+            if let ProcessedEvent::Proposal { .. } = event {
+                panic!("Unexpected proposal handled: {:?}", event);
+            }
+        }
+        assert!(nodes[2].commit_cb_receiver.try_next().is_err());
+
+        gate.open();
+        let node2_commit = loop {
+            if let Ok(Some(commit)) = nodes[2].commit_cb_receiver.try_next() {
+                break commit;
+            }
+            playground
+                .wait_for_messages(1, NetworkPlayground::exclude_timeout_msg)
+                .await;
+        };
+        assert_eq!(
+            node2_commit.ledger_info().consensus_block_id(),
+            node1_commit.ledger_info().consensus_block_id()
+        );
+        let first_proposal = loop {
+            if let ProcessedEvent::Proposal { round, author } = node2_events.next().await.unwrap() {
+                break (round, author);
+            }
+        };
+        assert_eq!(first_proposal, (1, nodes[0].author));
+    });
+}
+
+#[test]
+fn basic_block_retrieval() {
+    let runtime = consensus_runtime();
+    let mut playground = NetworkPlayground::new(runtime.executor());
+    // This test depends on the fixed proposer on nodes[0]
+    let mut nodes = SMRNode::start_num_nodes(3, 2, &mut playground, FixedProposer, &[]);
+    block_on(async move {
+        let mut first_proposals = vec![];
+        // First three proposals are delivered just to nodes[0[ and nodes[1].
+        playground.drop_message_for(&nodes[0].author, nodes[2].author);
+        for _ in 0..2 {
+            playground
+                .wait_for_messages(1, NetworkPlayground::proposals_only)
+                .await;
+            let mut votes = playground
+                .wait_for_messages(1, NetworkPlayground::votes_only)
+                .await;
+            let vote_msg = VoteMsg::from_proto(votes[0].1.take_vote()).unwrap();
+            let proposal_id = vote_msg.vote_data().block_id();
+            first_proposals.push(proposal_id);
+        }
+        // The next proposal is delivered to all: as a result nodes[2] should retrieve the missing
+        // blocks from nodes[0] and vote for the 3th proposal.
+        playground.stop_drop_message_for(&nodes[0].author, &nodes[2].author);
+
+        playground
+            .wait_for_messages(2, NetworkPlayground::proposals_only)
+            .await;
+        // Wait until nodes[2] sent out a vote, drop the vote from nodes[1] so that nodes[0]
+        // won't move too far and prune the requested block
+        playground.drop_message_for(&nodes[1].author, nodes[0].author);
+        playground
+            .wait_for_messages(1, NetworkPlayground::votes_only)
+            .await;
+        playground.stop_drop_message_for(&nodes[1].author, &nodes[0].author);
+        // the first two proposals should be present at nodes[2]
+        for block_id in &first_proposals {
+            assert!(nodes[2]
+                .smr
+                .block_store()
+                .unwrap()
+                .get_block(*block_id)
+                .is_some());
+        }
+
+        // Both nodes[1] and nodes[2] are going to vote for 4th proposal and commit the 1th one.
+
+        // Verify that nodes[2] commits the first proposal.
+        playground
+            .wait_for_messages(2, NetworkPlayground::votes_only)
+            .await;
+        if let Some(commit_v3) = nodes[2].commit_cb_receiver.next().await {
+            assert_eq!(
+                commit_v3.ledger_info().consensus_block_id(),
+                first_proposals[0],
+            );
+        }
+    });
+}
+
+#[test]
+fn block_retrieval_with_timeout() {
+    let runtime = consensus_runtime();
+    let mut playground = NetworkPlayground::new(runtime.executor());
+    let nodes = SMRNode::start_num_nodes(3, 2, &mut playground, FixedProposer, &[]);
+    block_on(async move {
+        let mut first_proposals = vec![];
+        // First three proposals are delivered just to nodes[0] and nodes[1].
+        playground.drop_message_for(&nodes[0].author, nodes[2].author);
+        for _ in 0..2 {
+            playground
+                .wait_for_messages(1, NetworkPlayground::proposals_only)
+                .await;
+            let mut votes = playground
+                .wait_for_messages(1, NetworkPlayground::votes_only)
+                .await;
+            let vote_msg = VoteMsg::from_proto(votes[0].1.take_vote()).unwrap();
+            let proposal_id = vote_msg.vote_data().block_id();
+            first_proposals.push(proposal_id);
+        }
+        // The next proposal is delivered to all: as a result nodes[2] should retrieve the missing
+        // blocks from v1 and vote for the 4th proposal.
+        playground.stop_drop_message_for(&nodes[0].author, &nodes[2].author);
 
         playground
             .wait_for_messages(2, NetworkPlayground::proposals_only)
@@ -529,7 +1462,7 @@ fn basic_state_sync() {
     let runtime = consensus_runtime();
     let mut playground = NetworkPlayground::new(runtime.executor());
     // This test depends on the fixed proposer on nodes[0]
-    let mut nodes = SMRNode::start_num_nodes(3, 2, &mut playground, FixedProposer);
+    let mut nodes = SMRNode::start_num_nodes(3, 2, &mut playground, FixedProposer, &[]);
     block_on(async move {
         let mut proposals = vec![];
         // The first ten proposals are delivered just to nodes[0] and nodes[1], which should commit
@@ -606,7 +1539,7 @@ fn state_sync_on_timeout() {
     let runtime = consensus_runtime();
     let mut playground = NetworkPlayground::new(runtime.executor());
     // This test depends on the fixed proposer on nodes[0]
-    let mut nodes = SMRNode::start_num_nodes(3, 2, &mut playground, FixedProposer);
+    let mut nodes = SMRNode::start_num_nodes(3, 2, &mut playground, FixedProposer, &[]);
     block_on(async move {
         let mut proposals = vec![];
         // The first ten proposals are delivered just to nodes[0] and nodes[1], which should commit
@@ -661,7 +1594,7 @@ fn sync_info_sent_if_remote_stale() {
     // We're going to drop messages from 0 to 2: as a result we expect node 2 to broadcast timeout
     // messages, for which node 1 should respond with sync_info, which should eventually
     // help node 2 to catch up.
-    let mut nodes = SMRNode::start_num_nodes(3, 2, &mut playground, FixedProposer);
+    let mut nodes = SMRNode::start_num_nodes(3, 2, &mut playground, FixedProposer, &[]);
     block_on(async move {
         playground.drop_message_for(&nodes[0].author, nodes[2].author);
         // Don't want to receive timeout messages from 2 until 1 has some real stuff to contribute.
@@ -709,6 +1642,60 @@ fn sync_info_sent_if_remote_stale() {
     });
 }
 
+#[test]
+/// A node that missed all the proposals and timeouts learns the commits of its peers from their
+/// commit heartbeats.
+fn commit_heartbeat_catches_up_isolated_node() {
+    let runtime = consensus_runtime();
+    let mut playground = NetworkPlayground::new(runtime.executor());
+    // nodes[0] is the fixed proposer, nodes[0] and nodes[1] form a quorum without nodes[2].
+    let mut nodes = SMRNode::start_num_nodes_with_options(
+        3,
+        2,
+        &mut playground,
+        FixedProposer,
+        &[],
+        SMRNodeOptions {
+            // The proposals received by nodes[0] and nodes[1] don't suppress the heartbeats.
+            commit_heartbeat_policy: CommitHeartbeatPolicy {
+                interval: Duration::from_millis(100),
+                quiet_period: Duration::from_secs(0),
+            },
+            ..SMRNodeOptions::default()
+        },
+    );
+    // nodes[2] neither sends nor receives anything but the sync infos, and never requests them.
+    let isolated = nodes[2].author;
+    playground.add_mutator(Box::new(
+        move |src: Author, dst: Author, msg: &mut ConsensusMsg| {
+            if (src == isolated || dst == isolated) && !msg.has_sync_info() {
+                *msg = ConsensusMsg::new();
+            }
+            vec![]
+        },
+    ));
+    block_on(async move {
+        let node2_commit = loop {
+            if let Ok(Some(commit)) = nodes[2].commit_cb_receiver.try_next() {
+                break commit;
+            }
+            playground
+                .wait_for_messages(1, NetworkPlayground::sync_info_only)
+                .await;
+        };
+        let node2_commit_id = node2_commit.ledger_info().consensus_block_id();
+        nodes[1].commit_cb_receiver.close();
+        let mut found = false;
+        while let Ok(Some(node1_commit)) = nodes[1].commit_cb_receiver.try_next() {
+            if node1_commit.ledger_info().consensus_block_id() == node2_commit_id {
+                found = true;
+                break;
+            }
+        }
+        assert!(found);
+    });
+}
+
 #[test]
 /// Verify that a QC can be formed by aggregating the votes piggybacked by TimeoutMsgs
 fn aggregate_timeout_votes() {
@@ -719,7 +1706,7 @@ fn aggregate_timeout_votes() {
     // because their messages are dropped.
     // Upon timeout nodes 1 and 2 are sending timeout messages with attached votes for the original
     // proposal: both can then aggregate the QC for the first proposal.
-    let nodes = SMRNode::start_num_nodes(3, 2, &mut playground, FixedProposer);
+    let nodes = SMRNode::start_num_nodes(3, 2, &mut playground, FixedProposer, &[]);
     block_on(async move {
         playground.drop_message_for(&nodes[1].author, nodes[0].author);
         playground.drop_message_for(&nodes[2].author, nodes[0].author);
@@ -787,7 +1774,7 @@ fn chain_with_nil_blocks() {
     // The proposer node[0] sends 3 proposals, after that its proposals are dropped and it cannot
     // communicate with nodes 1 and 2. Nodes 1 and 2 should be able to commit the 3 proposal
     // via NIL blocks commit chain.
-    let nodes = SMRNode::start_num_nodes(3, 2, &mut playground, FixedProposer);
+    let nodes = SMRNode::start_num_nodes(3, 2, &mut playground, FixedProposer, &[]);
     block_on(async move {
         // Wait for the first 3 proposals (each one sent to two nodes).
         playground
@@ -820,13 +1807,115 @@ fn chain_with_nil_blocks() {
     });
 }
 
+#[test]
+/// Same as `chain_with_nil_blocks` with the 2-chain commit rule: the rounds of the NIL blocks are
+/// justified by timeout certificates, and the commits keep up with the NIL blocks.
+fn chain_with_nil_blocks_two_chain() {
+    let runtime = consensus_runtime();
+    let mut playground = NetworkPlayground::new(runtime.executor());
+
+    let nodes = SMRNode::start_num_nodes_with_options(
+        3,
+        2,
+        &mut playground,
+        FixedProposer,
+        &[],
+        SMRNodeOptions {
+            commit_rule: CommitRule::TwoChain,
+            ..SMRNodeOptions::default()
+        },
+    );
+    block_on(async move {
+        // Wait for the first 3 proposals (each one sent to two nodes).
+        playground
+            .wait_for_messages(2 * 3, NetworkPlayground::proposals_only)
+            .await;
+        playground.drop_message_for(&nodes[0].author, nodes[1].author);
+        playground.drop_message_for(&nodes[0].author, nodes[2].author);
+
+        // G <- p1 <- p2 <- p3 <- NIL1 <- NIL2
+        playground
+            .wait_for_messages(4 * 3, NetworkPlayground::timeout_msg_only)
+            .await;
+        assert!(
+            nodes[2]
+                .smr
+                .block_store()
+                .unwrap()
+                .highest_quorum_cert()
+                .certified_block_round()
+                >= 4
+        );
+
+        // The QC of NIL1 at round 4 commits p3 at round 3, allow for the last QC not being
+        // processed yet.
+        assert!(nodes[2].smr.block_store().unwrap().root().round() >= 2)
+    });
+}
+
+#[test]
+/// Without NIL blocks, the rounds of a crashed proposer only advance with timeout certificates:
+/// the other nodes keep committing the blocks they propose, which extend the highest certified
+/// block across the rounds that timed out.
+fn chain_without_nil_blocks() {
+    let runtime = consensus_runtime();
+    let mut playground = NetworkPlayground::new(runtime.executor());
+
+    let mut nodes = SMRNode::start_num_nodes_with_options(
+        3,
+        2,
+        &mut playground,
+        RotatingProposer,
+        &[],
+        SMRNodeOptions {
+            nil_blocks_enabled: false,
+            ..SMRNodeOptions::default()
+        },
+    );
+    // The proposer of rounds 4 and 5 (proposers rotate every 2 rounds) can't reach the other
+    // nodes: none of them can vote in these rounds.
+    let crashed = nodes[0].proposer[2];
+    for node in &nodes {
+        if node.author != crashed {
+            playground.drop_message_for(&crashed, node.author);
+        }
+    }
+    let live_node = nodes
+        .iter_mut()
+        .find(|node| node.author != crashed)
+        .unwrap();
+    block_on(async move {
+        // The votes for p3 are sent to the crashed node, p6 extends the highest certified block:
+        // G <- p1 <- p2 <- p6 <- p7 <- p8, the QC of p8 commits p6.
+        let block_store = live_node.smr.block_store().unwrap();
+        while block_store.root().round() < 6 {
+            playground
+                .wait_for_messages(1, NetworkPlayground::take_all)
+                .await;
+            while let Ok(Some(_)) = live_node.commit_cb_receiver.try_next() {}
+        }
+
+        // The pruned blocks are kept in memory: the committed chain has no NIL block and skips
+        // the rounds that timed out.
+        let mut block = block_store.root();
+        let mut skipped_rounds = false;
+        while block.round() > 0 {
+            assert!(!block.is_nil_block());
+            let parent = block_store.get_block(block.parent_id()).unwrap();
+            skipped_rounds |= parent.round() + 1 < block.round();
+            block = parent;
+        }
+        assert!(skipped_rounds);
+    });
+}
+
 #[test]
 /// Test secondary proposal processing
 fn secondary_proposers() {
     let runtime = consensus_runtime();
     let mut playground = NetworkPlayground::new(runtime.executor());
 
-    let mut nodes = SMRNode::start_num_nodes(3, 2, &mut playground, MultipleOrderedProposers);
+    let mut nodes = SMRNode::start_num_nodes(3, 2, &mut playground, MultipleOrderedProposers, &[]);
     block_on(async move {
         // Node 0 is disconnected.
         playground.drop_message_for(&nodes[0].author, nodes[1].author);
@@ -875,3 +1964,636 @@ fn secondary_proposers() {
         assert_eq!(secondary_proposal_committed, true);
     });
 }
+
+#[test]
+/// The execution of the block at height 3 removes the validator 3 from the validator set: the
+/// validators switch to the next epoch and keep committing blocks without the removed validator.
+fn reconfiguration_removes_validator() {
+    let runtime = consensus_runtime();
+    let mut playground = NetworkPlayground::new(runtime.executor());
+    let signers: Vec<_> = (0..4).map(ValidatorSigner::from_int).collect();
+    let validator_verifier = ValidatorVerifier::new(
+        signers
+            .iter()
+            .map(|signer| (signer.author(), signer.public_key()))
+            .collect(),
+    );
+    let next_validator_set = ValidatorSet::new(
+        signers
+            .iter()
+            .take(3)
+            .map(|signer| {
+                ValidatorPublicKeys::new(
+                    signer.author(),
+                    signer.public_key(),
+                    ed25519::compat::generate_keypair(None).1,
+                    x25519::compat::generate_keypair(None).1,
+                )
+            })
+            .collect(),
+    );
+    let removed_validator = signers[3].author();
+    let proposers = validator_verifier.get_ordered_account_addresses();
+    let mut nodes = vec![];
+    for (smr_id, signer) in signers.into_iter().enumerate() {
+        // Every node switches its own epoch manager.
+        let epoch_mgr = Arc::new(EpochManager::new(0, validator_verifier.clone()));
+        let (storage, initial_data) = MockStorage::start_for_testing();
+        nodes.push(SMRNode::start(
+            &mut playground,
+            signer,
+            epoch_mgr,
+            proposers.clone(),
+            smr_id,
+            storage,
+            initial_data,
+            RotatingProposer,
+            Some((3, next_validator_set.clone())),
+            SMRNodeOptions::default(),
+        ));
+    }
+
+    let next_validators = validator_verifier.with_validator_set(&next_validator_set);
+    block_on(async {
+        // Deliver the messages until node 0 commits a block certified in the next epoch.
+        let commit = 'delivery: loop {
+            playground
+                .wait_for_messages(1, NetworkPlayground::take_all)
+                .await;
+            while let Ok(Some(commit)) = nodes[0].commit_cb_receiver.try_next() {
+                if commit.ledger_info().epoch_num() == 1 {
+                    break 'delivery commit;
+                }
+            }
+        };
+        assert_eq!(
+            Ok(()),
+            next_validators.batch_verify(commit.ledger_info().hash(), commit.signatures())
+        );
+        assert!(!commit.signatures().contains_key(&removed_validator));
+        for node in nodes.iter().take(3) {
+            assert_eq!(node.epoch_mgr.epoch(), 1);
+            assert_eq!(
+                node.epoch_mgr.validators().get_ordered_account_addresses(),
+                next_validators.get_ordered_account_addresses()
+            );
+        }
+    });
+
+    // A restarted node resumes in the epoch it switched to rather than in the initial one.
+    let mut node = nodes.remove(1);
+    node.epoch_mgr = Arc::new(EpochManager::new(0, validator_verifier));
+    let node = node.restart(&mut playground);
+    assert_eq!(node.epoch_mgr.epoch(), 1);
+    assert_eq!(
+        node.epoch_mgr.validators().get_ordered_account_addresses(),
+        next_validators.get_ordered_account_addresses()
+    );
+    assert!(node.epoch_mgr.epoch_proof(0).is_some());
+}
+
+/// Delivers the messages until every node commits `num_commits` blocks, and checks that the
+/// nodes commit the same blocks.
+async fn wait_for_agreed_commits<'a>(
+    playground: &'a mut NetworkPlayground,
+    nodes: &'a mut [SMRNode],
+    num_commits: usize,
+) {
+    let mut commits = vec![vec![]; nodes.len()];
+    while commits
+        .iter()
+        .any(|node_commits| node_commits.len() < num_commits)
+    {
+        playground
+            .wait_for_messages(1, NetworkPlayground::take_all)
+            .await;
+        for (node, node_commits) in nodes.iter_mut().zip(commits.iter_mut()) {
+            while let Ok(Some(commit)) = node.commit_cb_receiver.try_next() {
+                node_commits.push(commit.ledger_info().consensus_block_id());
+            }
+        }
+    }
+    for node_commits in &commits {
+        assert_eq!(node_commits[..num_commits], commits[0][..num_commits]);
+    }
+}
+
+/// Runs 3 honest nodes and a byzantine node until every honest node commits 10 blocks. The
+/// byzantine node is the fixed proposer if it equivocates on the proposals, otherwise one of the
+/// voters. The honest nodes must commit the same blocks, returns the evidence they collected
+/// against the byzantine node.
+fn run_with_byzantine_node(mode: ByzantineMode) -> Vec<Evidence<TestPayload>> {
+    let runtime = consensus_runtime();
+    let mut playground = NetworkPlayground::new(runtime.executor());
+    // The fixed proposer is the validator with the lowest address.
+    let proposer_id = (0..4)
+        .min_by_key(|smr_id| ValidatorSigner::from_int(*smr_id as u8).author())
+        .unwrap();
+    let byzantine_id = if mode == ByzantineMode::EquivocatingProposer {
+        proposer_id
+    } else {
+        (proposer_id + 1) % 4
+    };
+    let byzantine_author = ValidatorSigner::from_int(byzantine_id as u8).author();
+    // The 3 honest nodes form a quorum without the byzantine node.
+    let mut nodes = SMRNode::start_num_nodes(
+        4,
+        3,
+        &mut playground,
+        FixedProposer,
+        &[(byzantine_id, mode)],
+    );
+    block_on(async move {
+        wait_for_agreed_commits(&mut playground, &mut nodes, 10).await;
+        nodes
+            .iter()
+            .flat_map(|node| node.smr.evidence())
+            .filter(|evidence| evidence.author() == byzantine_author)
+            .collect()
+    })
+}
+
+#[test]
+/// The honest nodes ignore the second proposal of an equivocating proposer and record the
+/// evidence of the equivocation.
+fn byzantine_equivocating_proposer() {
+    let evidence = run_with_byzantine_node(ByzantineMode::EquivocatingProposer);
+    assert!(evidence.iter().any(|evidence| match evidence {
+        Evidence::DoubleProposal(..) => true,
+        _ => false,
+    }));
+}
+
+#[test]
+/// The honest nodes keep committing without the votes of a byzantine node.
+fn byzantine_withheld_votes() {
+    run_with_byzantine_node(ByzantineMode::WithholdVotes);
+}
+
+#[test]
+/// The conflicting votes of a byzantine node cannot certify its blocks, the next proposers record
+/// the evidence of the double votes.
+fn byzantine_conflicting_votes() {
+    let evidence = run_with_byzantine_node(ByzantineMode::ConflictingVotes);
+    assert!(evidence.iter().any(|evidence| match evidence {
+        Evidence::DoubleVote(..) => true,
+        _ => false,
+    }));
+}
+
+#[test]
+/// The stale sync info sent by a byzantine node doesn't move the honest nodes backwards.
+fn byzantine_stale_sync_info() {
+    run_with_byzantine_node(ByzantineMode::StaleSyncInfo);
+}
+
+/// Runs 3 nodes in the deterministic simulation of the given seed, the fixed proposer node 0
+/// being disconnected such that the others rely on timeouts. Returns the first delivered
+/// messages.
+fn run_simulation(seed: u64) -> Vec<(Author, Vec<u8>)> {
+    let runtime = consensus_runtime();
+    let mut playground =
+        NetworkPlayground::with_simulation(runtime.executor(), Simulation::new(seed));
+    let nodes = SMRNode::start_num_nodes(3, 2, &mut playground, FixedProposer, &[]);
+    block_on(async move {
+        playground.drop_message_for(&nodes[0].author, nodes[1].author);
+        playground.drop_message_for(&nodes[0].author, nodes[2].author);
+        playground
+            .wait_for_messages(40, NetworkPlayground::take_all)
+            .await
+            .into_iter()
+            .map(|(author, msg)| (author, msg.write_to_bytes().unwrap()))
+            .collect()
+    })
+}
+
+#[test]
+/// A simulation is replayed exactly with the same seed, including the timeouts.
+fn deterministic_simulation_replay() {
+    let seed = Simulation::from_env().seed();
+    let messages = run_simulation(seed);
+    assert!(messages
+        .iter()
+        .any(|(_, msg)| protobuf::parse_from_bytes::<ConsensusMsg>(msg)
+            .unwrap()
+            .has_timeout_msg()));
+    assert_eq!(messages, run_simulation(seed));
+}
+
+#[test]
+/// The nodes keep committing the same blocks when the messages are delayed by a random latency,
+/// duplicated and reordered.
+fn commit_under_network_jitter() {
+    let runtime = consensus_runtime();
+    let mut playground = NetworkPlayground::new(runtime.executor());
+    let mut nodes = SMRNode::start_num_nodes(4, 3, &mut playground, RotatingProposer, &[]);
+    playground.set_latency(Some(LatencyDistribution::uniform(
+        Duration::from_millis(0),
+        Duration::from_millis(50),
+    )));
+    playground.set_duplication_probability(0.1);
+    playground.set_reordering_probability(0.1);
+    block_on(async move {
+        wait_for_agreed_commits(&mut playground, &mut nodes, 5).await;
+    });
+}
+
+#[test]
+/// The messages recorded in a run are replayed against fresh nodes, which receive the same
+/// proposals.
+fn record_and_replay_messages() {
+    let trace_file = TempPath::new();
+    let proposals = {
+        let runtime = consensus_runtime();
+        let mut playground = NetworkPlayground::new(runtime.executor());
+        playground.start_recording();
+        let _nodes = SMRNode::start_num_nodes(2, 2, &mut playground, RotatingProposer, &[]);
+        let proposals: Vec<_> =
+            block_on(playground.wait_for_messages(2, NetworkPlayground::proposals_only))
+                .into_iter()
+                .map(|(_, mut msg)| -> ProposalMsg<TestPayload> {
+                    ProposalUncheckedSignatures::from_proto(msg.take_proposal())
+                        .unwrap()
+                        .into()
+                })
+                .collect();
+        let trace = playground.stop_recording().unwrap();
+        trace.save(trace_file.path()).unwrap();
+        proposals
+    };
+
+    let trace = MessageTrace::load(trace_file.path()).unwrap();
+    assert_eq!(
+        trace
+            .messages()
+            .iter()
+            .filter(|msg| msg.msg_type == "proposal")
+            .count(),
+        2
+    );
+    let runtime = consensus_runtime();
+    let mut playground = NetworkPlayground::new(runtime.executor());
+    let nodes = SMRNode::start_num_nodes(2, 2, &mut playground, RotatingProposer, &[]);
+    block_on(playground.replay(&trace)).unwrap();
+    for proposal in proposals {
+        let receiver = nodes
+            .iter()
+            .find(|node| Some(node.author) != proposal.proposal().author())
+            .unwrap();
+        let block_store = receiver.smr.block_store().unwrap();
+        let mut retries = 0;
+        while block_store.get_block(proposal.proposal().id()).is_none() {
+            assert!(retries < 100, "The replayed proposal is not processed");
+            retries += 1;
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+}
+
+#[test]
+/// The proposals whose signature is corrupted in flight are rejected: the receiver doesn't vote.
+fn reject_corrupted_proposal_signature() {
+    let runtime = consensus_runtime();
+    let mut playground = NetworkPlayground::new(runtime.executor());
+    // This test depends on the fixed proposer on nodes[0]
+    let nodes = SMRNode::start_num_nodes(3, 2, &mut playground, FixedProposer, &[]);
+    let (proposer, victim) = (nodes[0].author, nodes[2].author);
+    playground.add_mutator(Box::new(
+        move |src: Author, dst: Author, msg: &mut ConsensusMsg| {
+            if src == proposer && dst == victim && msg.has_proposal() {
+                let signature = msg.mut_proposal().mut_proposed_block().mut_signature();
+                let last = signature.len() - 1;
+                signature[last] ^= 0xff;
+            }
+            vec![]
+        },
+    ));
+    block_on(async move {
+        let votes = playground
+            .wait_for_messages(3, NetworkPlayground::votes_only)
+            .await;
+        for (_, mut msg) in votes {
+            let vote = VoteMsg::from_proto(msg.take_vote()).unwrap();
+            assert_eq!(vote.author(), nodes[1].author);
+        }
+    });
+}
+
+#[test]
+/// The votes whose round is flipped in flight are rejected: they are never part of a QC.
+fn reject_tampered_vote_round() {
+    let runtime = consensus_runtime();
+    let mut playground = NetworkPlayground::new(runtime.executor());
+    // This test depends on the fixed proposer on nodes[0]
+    let nodes = SMRNode::start_num_nodes(3, 2, &mut playground, FixedProposer, &[]);
+    let (proposer, tampered) = (nodes[0].author, nodes[1].author);
+    playground.add_mutator(Box::new(
+        move |src: Author, dst: Author, msg: &mut ConsensusMsg| {
+            if src == tampered && dst == proposer && msg.has_vote() {
+                let vote_data = msg.mut_vote().mut_vote_data();
+                vote_data.set_round(vote_data.get_round() + 1);
+            }
+            vec![]
+        },
+    ));
+    block_on(async move {
+        for _ in 0..3 {
+            let mut proposals = playground
+                .wait_for_messages(2, NetworkPlayground::proposals_only)
+                .await;
+            let proposal: ProposalMsg<TestPayload> =
+                ProposalUncheckedSignatures::from_proto(proposals[0].1.take_proposal())
+                    .unwrap()
+                    .into();
+            let signatures = proposal.proposal().quorum_cert().ledger_info().signatures();
+            assert!(!signatures.contains_key(&tampered));
+        }
+    });
+}
+
+#[test]
+/// An old proposal replayed in flight is ignored: the receiver never votes twice in a round.
+fn reject_replayed_proposal() {
+    let runtime = consensus_runtime();
+    let mut playground = NetworkPlayground::new(runtime.executor());
+    // This test depends on the fixed proposer on nodes[0]
+    let nodes = SMRNode::start_num_nodes(3, 2, &mut playground, FixedProposer, &[]);
+    let (proposer, victim) = (nodes[0].author, nodes[1].author);
+    let mut first_proposal = None;
+    playground.add_mutator(Box::new(
+        move |src: Author, dst: Author, msg: &mut ConsensusMsg| {
+            if src != proposer || dst != victim || !msg.has_proposal() {
+                return vec![];
+            }
+            match &first_proposal {
+                None => {
+                    first_proposal = Some(msg.clone());
+                    vec![]
+                }
+                Some(first_proposal) => vec![first_proposal.clone()],
+            }
+        },
+    ));
+    block_on(async move {
+        let votes = playground
+            .wait_for_messages(6, NetworkPlayground::votes_only)
+            .await;
+        let mut last_voted_rounds = HashMap::new();
+        for (_, mut msg) in votes {
+            let vote = VoteMsg::from_proto(msg.take_vote()).unwrap();
+            let round = vote.vote_data().block_round();
+            if let Some(last_voted_round) = last_voted_rounds.insert(vote.author(), round) {
+                assert!(round > last_voted_round);
+            }
+        }
+    });
+}
+
+#[test]
+/// The messages of real traffic fed to the fuzz targets must not make them panic. When
+/// `CONSENSUS_FUZZ_CORPUS_DIR` is set, the messages are saved there to seed the fuzz corpus.
+fn fuzz_targets_accept_playground_messages() {
+    let runtime = consensus_runtime();
+    let mut playground = NetworkPlayground::new(runtime.executor());
+    playground.start_recording();
+    let mut nodes = SMRNode::start_num_nodes(2, 2, &mut playground, RotatingProposer, &[]);
+    block_on(wait_for_agreed_commits(&mut playground, &mut nodes, 1));
+    let trace = playground.stop_recording().unwrap();
+
+    let corpus = trace.fuzz_corpus().unwrap();
+    for msg_type in &["proposal", "vote"] {
+        assert!(corpus.iter().any(|(t, _)| t == msg_type));
+    }
+    for (msg_type, data) in &corpus {
+        match *msg_type {
+            "proposal" => network_fuzzing::fuzz_proposal_msg(data),
+            "vote" => network_fuzzing::fuzz_vote_msg(data),
+            "timeout_msg" => network_fuzzing::fuzz_timeout_msg(data),
+            "sync_info" => network_fuzzing::fuzz_sync_info(data),
+            _ => unreachable!("Unexpected message type {}", msg_type),
+        }
+    }
+    if let Some(dir) = env::var_os(FUZZ_CORPUS_DIR_ENV) {
+        trace
+            .save_fuzz_corpus(Path::new(&dir), "playground")
+            .unwrap();
+    }
+}
+
+#[test]
+/// A validator rotates its consensus key while the nodes are running: the other validator accepts
+/// the new key right away, the rotating validator signs with it starting from the handover round
+/// and the nodes keep committing.
+fn rotate_consensus_key_mid_run() {
+    let runtime = consensus_runtime();
+    let mut playground = NetworkPlayground::new(runtime.executor());
+    let mut nodes = SMRNode::start_num_nodes(2, 2, &mut playground, RotatingProposer, &[]);
+    block_on(wait_for_agreed_commits(&mut playground, &mut nodes, 2));
+
+    let author = nodes[0].author;
+    let new_signer = ValidatorSigner::new(author, ed25519::compat::generate_keypair(None).0);
+    let new_public_key = new_signer.public_key();
+    let handover_round = nodes[0]
+        .smr
+        .block_store()
+        .unwrap()
+        .highest_certified_block()
+        .round()
+        + 2;
+    let rotation = nodes[0]
+        .smr
+        .rotate_consensus_key(new_signer, handover_round)
+        .unwrap();
+    // The record is broadcast to the other validators, which persist it.
+    nodes[1].smr.add_key_rotation(&rotation).unwrap();
+    for node in &nodes {
+        assert_eq!(
+            *node.storage.shared_storage.key_rotations.lock().unwrap(),
+            vec![rotation.clone()]
+        );
+    }
+
+    block_on(wait_for_agreed_commits(&mut playground, &mut nodes, 5));
+    for node in &nodes {
+        assert_eq!(
+            node.epoch_mgr.validators().get_public_key(author),
+            Some(new_public_key.clone())
+        );
+    }
+    // The latest certificate carries a vote of the rotated validator signed by the new key.
+    let highest_qc = nodes[1].smr.block_store().unwrap().highest_quorum_cert();
+    assert!(highest_qc.certified_block_round() >= handover_round);
+    let ledger_info_with_sig = highest_qc.ledger_info();
+    let signature = ledger_info_with_sig.signatures().get(&author).unwrap();
+    assert_eq!(
+        Ok(()),
+        ValidatorVerifier::new_single(author, new_public_key).verify_signature(
+            author,
+            ledger_info_with_sig.ledger_info().hash(),
+            signature
+        )
+    );
+}
+
+#[test]
+/// The nodes restarted after a key rotation replay it: the rotating validator keeps signing with
+/// its new key, the other validator keeps accepting it, and the nodes keep committing.
+fn rotated_consensus_key_survives_restart() {
+    let runtime = consensus_runtime();
+    let mut playground = NetworkPlayground::new(runtime.executor());
+    let mut nodes = SMRNode::start_num_nodes(2, 2, &mut playground, RotatingProposer, &[]);
+    block_on(wait_for_agreed_commits(&mut playground, &mut nodes, 2));
+
+    let author = nodes[0].author;
+    let new_signer = ValidatorSigner::new(author, ed25519::compat::generate_keypair(None).0);
+    let new_public_key = new_signer.public_key();
+    let handover_round = nodes[0]
+        .smr
+        .block_store()
+        .unwrap()
+        .highest_certified_block()
+        .round()
+        + 2;
+    let rotation = nodes[0]
+        .smr
+        .rotate_consensus_key(new_signer, handover_round)
+        .unwrap();
+    nodes[1].smr.add_key_rotation(&rotation).unwrap();
+    block_on(wait_for_agreed_commits(&mut playground, &mut nodes, 5));
+
+    // The restarted nodes start from the validators of the epoch, as they know them from the
+    // configuration, with a fresh epoch manager each.
+    let author_to_public_keys = nodes
+        .iter()
+        .map(|node| (node.author, node.signer.public_key()))
+        .collect();
+    let validators = ValidatorVerifier::new_with_quorum_size(author_to_public_keys, 2).unwrap();
+    let mut restarted_nodes = vec![];
+    for mut node in nodes {
+        node.epoch_mgr = Arc::new(EpochManager::new(0, validators.clone()));
+        restarted_nodes.push(node.restart(&mut playground));
+    }
+    let mut nodes = restarted_nodes;
+    for node in &nodes {
+        assert_eq!(node.epoch_mgr.key_rotations(), vec![rotation.clone()]);
+        assert_eq!(
+            node.epoch_mgr.validators().get_public_key(author),
+            Some(new_public_key.clone())
+        );
+    }
+    let block_store = nodes[0].smr.block_store().unwrap();
+    assert_eq!(block_store.latest_signer().public_key(), new_public_key);
+    assert_eq!(
+        block_store.signer_for_round(handover_round).public_key(),
+        new_public_key
+    );
+    assert_eq!(
+        block_store
+            .signer_for_round(handover_round - 1)
+            .public_key(),
+        nodes[0].signer.public_key()
+    );
+
+    block_on(wait_for_agreed_commits(&mut playground, &mut nodes, 3));
+}
+
+#[test]
+/// The blocks without transactions are certified and committed like the others.
+fn commit_empty_payloads() {
+    let runtime = consensus_runtime();
+    let mut playground = NetworkPlayground::new(runtime.executor());
+    let mut nodes = SMRNode::start_num_nodes_with_options(
+        2,
+        2,
+        &mut playground,
+        RotatingProposer,
+        &[],
+        SMRNodeOptions {
+            payload_strategy: Arc::new(EmptyPayloads),
+            ..SMRNodeOptions::default()
+        },
+    );
+    block_on(wait_for_agreed_commits(&mut playground, &mut nodes, 3));
+    for node in &nodes {
+        assert!(node.mempool.get_committed_txns().is_empty());
+    }
+}
+
+#[test]
+/// The payloads that exceed the size limit are pulled again with fewer transactions: the blocks
+/// of variable sizes keep being committed within the limit.
+fn commit_large_variable_size_payloads() {
+    let runtime = consensus_runtime();
+    let mut playground = NetworkPlayground::new(runtime.executor());
+    let max_block_bytes = 16 * 1024;
+    let mut nodes = SMRNode::start_num_nodes_with_options(
+        2,
+        2,
+        &mut playground,
+        RotatingProposer,
+        &[],
+        SMRNodeOptions {
+            payload_strategy: Arc::new(VariableSizePayloads::new(vec![10_000, 0, 1, 5_000])),
+            max_block_size: 10_000,
+            max_block_bytes,
+            ..SMRNodeOptions::default()
+        },
+    );
+    block_on(wait_for_agreed_commits(&mut playground, &mut nodes, 5));
+    for node in &nodes {
+        assert!(!node.mempool.get_committed_txns().is_empty());
+        assert!(node.smr.block_store().unwrap().root().payload_size() <= max_block_bytes);
+    }
+}
+
+#[test]
+/// A proposal that fails to execute on every node is not certified: the nodes time out and
+/// commit the following blocks, which don't carry the failing transaction.
+fn commit_after_failed_execution() {
+    let runtime = consensus_runtime();
+    let mut playground = NetworkPlayground::new(runtime.executor());
+    // The first proposal of nodes[0] carries the transaction 0, which fails to execute.
+    let nodes = SMRNode::start_num_nodes_with_options(
+        3,
+        2,
+        &mut playground,
+        FixedProposer,
+        &[],
+        SMRNodeOptions {
+            payload_strategy: Arc::new(FailingPayloads::new(vec![0])),
+            ..SMRNodeOptions::default()
+        },
+    );
+    block_on(async move {
+        while nodes[1].mempool.get_committed_txns().is_empty() {
+            playground
+                .wait_for_messages(1, NetworkPlayground::take_all)
+                .await;
+        }
+        for node in &nodes {
+            assert!(!node.mempool.get_committed_txns().contains(&0));
+        }
+    });
+}
+
+#[test]
+/// The payload validator checks the proposals of the other nodes before voting for them: the
+/// honest proposals pass it and the nodes keep committing.
+fn payload_validator_accepts_honest_proposals() {
+    let runtime = consensus_runtime();
+    let mut playground = NetworkPlayground::new(runtime.executor());
+    let payload_validator = Arc::new(MockPayloadValidator::new(50));
+    let mut nodes = SMRNode::start_num_nodes_with_options(
+        2,
+        2,
+        &mut playground,
+        RotatingProposer,
+        &[],
+        SMRNodeOptions {
+            payload_validator: Some(Arc::clone(&payload_validator)),
+            ..SMRNodeOptions::default()
+        },
+    );
+    block_on(wait_for_agreed_commits(&mut playground, &mut nodes, 3));
+    assert!(payload_validator.num_validated() > 0);
+}
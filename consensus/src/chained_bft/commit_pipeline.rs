@@ -0,0 +1,299 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    chained_bft::{
+        block_storage::BlockStore, common::Payload, consensus_types::block::ExecutedBlock,
+    },
+    counters,
+    state_replication::{StateComputer, TxnManager},
+    util::time_service::duration_since_epoch,
+};
+use crypto::HashValue;
+use futures::{
+    channel::{mpsc, oneshot},
+    compat::Future01CompatExt,
+    FutureExt, StreamExt, TryFutureExt,
+};
+use logger::prelude::*;
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use tokio::{runtime::TaskExecutor, timer::Delay};
+use types::crypto_proxies::LedgerInfoWithSignatures;
+
+#[cfg(test)]
+#[path = "commit_pipeline_test.rs"]
+mod commit_pipeline_test;
+
+/// The blocks ordered by a commit certificate: the path from the previous root (excluded) to the
+/// new root (included), along with the blocks pruned from the block tree when its root moved.
+pub struct OrderedBlocks<T> {
+    pub blocks: Vec<Arc<ExecutedBlock<T>>>,
+    pub pruned_block_ids: VecDeque<HashValue>,
+    pub finality_proof: LedgerInfoWithSignatures,
+}
+
+/// The attempts to persist a commit before the node gives up on it.
+const MAX_COMMIT_ATTEMPTS: u32 = 10;
+/// The delay before the first retry of a failed commit, doubled on every retry.
+const COMMIT_RETRY_BASE_DELAY: Duration = Duration::from_millis(100);
+/// The longest delay between two attempts to persist a commit.
+const COMMIT_RETRY_MAX_DELAY: Duration = Duration::from_secs(5);
+
+enum CommitRequest<T> {
+    Commit(OrderedBlocks<T>),
+    // Notifies the sender once all the previously ordered blocks are committed.
+    Flush(oneshot::Sender<()>),
+}
+
+/// CommitPipeline is the last stage of the consensus pipeline: once a commit certificate orders
+/// some blocks, the event processor moves the root of the block tree right away and hands the
+/// ordered blocks over to the commit stage, which persists them through the state computer,
+/// notifies mempool, and finally removes the pruned blocks from the consensus storage. The SMR
+/// thus keeps proposing and voting while the commits catch up.
+///
+/// The blocks are still executed as they are inserted into the block store, since the votes
+/// certify the state resulting from their execution.
+pub struct CommitPipeline<T> {
+    stage: Arc<CommitStage<T>>,
+    // The queue of the commit stage running in its own task, None if the commits are applied
+    // inline.
+    sender: Option<mpsc::UnboundedSender<CommitRequest<T>>>,
+}
+
+impl<T> Clone for CommitPipeline<T> {
+    fn clone(&self) -> Self {
+        Self {
+            stage: Arc::clone(&self.stage),
+            sender: self.sender.clone(),
+        }
+    }
+}
+
+impl<T: Payload> CommitPipeline<T> {
+    /// Creates a pipeline that applies every commit before `commit` returns.
+    pub fn new(
+        block_store: Arc<BlockStore<T>>,
+        state_computer: Arc<dyn StateComputer<Payload = T>>,
+        txn_manager: Arc<dyn TxnManager<Payload = T>>,
+    ) -> Self {
+        Self::new_with_subscribers(
+            block_store,
+            state_computer,
+            txn_manager,
+            CommitSubscribers::default(),
+        )
+    }
+
+    fn new_with_subscribers(
+        block_store: Arc<BlockStore<T>>,
+        state_computer: Arc<dyn StateComputer<Payload = T>>,
+        txn_manager: Arc<dyn TxnManager<Payload = T>>,
+        subscribers: CommitSubscribers,
+    ) -> Self {
+        Self {
+            stage: Arc::new(CommitStage {
+                block_store,
+                state_computer,
+                txn_manager,
+                subscribers,
+            }),
+            sender: None,
+        }
+    }
+
+    /// Creates a pipeline whose commit stage runs in its own task: `commit` returns as soon as the
+    /// ordered blocks are queued, and the commits are applied in order. The given subscribers are
+    /// notified of every commit once it is persisted.
+    pub fn spawn(
+        executor: &TaskExecutor,
+        block_store: Arc<BlockStore<T>>,
+        state_computer: Arc<dyn StateComputer<Payload = T>>,
+        txn_manager: Arc<dyn TxnManager<Payload = T>>,
+        subscribers: CommitSubscribers,
+    ) -> Self {
+        let mut pipeline =
+            Self::new_with_subscribers(block_store, state_computer, txn_manager, subscribers);
+        let (sender, mut receiver) = mpsc::unbounded();
+        let stage = Arc::clone(&pipeline.stage);
+        let fut = async move {
+            while let Some(request) = receiver.next().await {
+                match request {
+                    CommitRequest::Commit(ordered_blocks) => stage.commit(ordered_blocks).await,
+                    CommitRequest::Flush(done) => {
+                        let _ = done.send(());
+                    }
+                }
+            }
+        };
+        executor.spawn(fut.boxed().unit_error().compat());
+        pipeline.sender = Some(sender);
+        pipeline
+    }
+
+    /// Hands the ordered blocks over to the commit stage.
+    pub async fn commit(&self, ordered_blocks: OrderedBlocks<T>) {
+        match &self.sender {
+            Some(sender) => {
+                if let Err(e) = sender.unbounded_send(CommitRequest::Commit(ordered_blocks)) {
+                    error!("The commit stage is stopped: {:?}", e);
+                }
+            }
+            None => self.stage.commit(ordered_blocks).await,
+        }
+    }
+
+    /// Waits until all the ordered blocks are committed, e.g., before the state is synchronized
+    /// or the next epoch starts.
+    pub async fn flush(&self) {
+        if let Some(sender) = &self.sender {
+            let (done_tx, done_rx) = oneshot::channel();
+            if sender.unbounded_send(CommitRequest::Flush(done_tx)).is_ok() {
+                let _ = done_rx.await;
+            }
+        }
+    }
+}
+
+struct CommitStage<T> {
+    block_store: Arc<BlockStore<T>>,
+    state_computer: Arc<dyn StateComputer<Payload = T>>,
+    txn_manager: Arc<dyn TxnManager<Payload = T>>,
+    subscribers: CommitSubscribers,
+}
+
+impl<T: Payload> CommitStage<T> {
+    async fn commit(&self, ordered_blocks: OrderedBlocks<T>) {
+        let OrderedBlocks {
+            blocks,
+            pruned_block_ids,
+            finality_proof,
+        } = ordered_blocks;
+        self.persist(&finality_proof).await;
+        // At this moment the new state is persisted and we can notify the clients.
+        // Multiple blocks might be committed at once: notify about all the transactions in the
+        // path from the old root to the new root.
+        for committed in &blocks {
+            if let Some(time_to_commit) = duration_since_epoch()
+                .checked_sub(Duration::from_micros(committed.timestamp_usecs()))
+            {
+                counters::CREATION_TO_COMMIT_S.observe_duration(time_to_commit);
+            }
+            if let Err(e) = self
+                .txn_manager
+                .commit_txns(
+                    committed.get_payload(),
+                    committed.compute_result().as_ref(),
+                    committed.timestamp_usecs(),
+                )
+                .await
+            {
+                error!("Failed to notify mempool: {:?}", e);
+            }
+        }
+        self.block_store.prune_storage(pruned_block_ids);
+        self.block_store
+            .set_latest_commit_cert(finality_proof.clone());
+        self.block_store.complete_commit(blocks.len());
+        self.subscribers.notify(&finality_proof);
+    }
+
+    // Persists the commit through the state computer, retrying with an exponential backoff: the
+    // root of the block tree has already moved, so the commit can't be dropped. The blocks of
+    // the commit stay in the consensus storage until it is persisted, a node that keeps failing
+    // to persist it crashes and recovers them on restart.
+    async fn persist(&self, finality_proof: &LedgerInfoWithSignatures) {
+        let mut delay = COMMIT_RETRY_BASE_DELAY;
+        for attempt in 1..=MAX_COMMIT_ATTEMPTS {
+            match self.state_computer.commit(finality_proof.clone()).await {
+                Ok(()) => return,
+                Err(e) => {
+                    error!(
+                        "Failed to persist commit {} (attempt {}/{}): {:?}",
+                        finality_proof.ledger_info(),
+                        attempt,
+                        MAX_COMMIT_ATTEMPTS,
+                        e
+                    );
+                    counters::FAILED_COMMIT_ATTEMPT_COUNT.inc();
+                }
+            }
+            if attempt < MAX_COMMIT_ATTEMPTS {
+                // The inline commits of the tests run without the tokio timer and are retried
+                // right away.
+                let _ = Delay::new(Instant::now() + delay).compat().await;
+                delay = (delay * 2).min(COMMIT_RETRY_MAX_DELAY);
+            }
+        }
+        panic!(
+            "[consensus] Failed to persist commit {} after {} attempts",
+            finality_proof.ledger_info(),
+            MAX_COMMIT_ATTEMPTS
+        );
+    }
+}
+
+struct CommitSubscriber {
+    sender: mpsc::Sender<LedgerInfoWithSignatures>,
+    // Whether the subscriber skipped the last commit because its buffer was full.
+    lagging: bool,
+}
+
+/// The independent subscribers to the commits of the SMR (e.g., mempool, state sync, metrics),
+/// each notified through its own bounded channel of the ledger info with signatures of every
+/// commit. A subscriber that doesn't keep up isn't waited for: the commits are skipped while its
+/// buffer is full, and it is notified again of the next commit that finds room in its buffer.
+/// Since a commit certifies all the previous ones, a lagging subscriber catches up with the
+/// latest commit it receives. The subscribers that dropped their receiver are removed.
+#[derive(Clone, Default)]
+pub struct CommitSubscribers {
+    subscribers: Arc<Mutex<Vec<CommitSubscriber>>>,
+}
+
+impl CommitSubscribers {
+    /// Adds a subscriber to the commits following the subscription, whose buffer holds the
+    /// given number of commits.
+    pub fn subscribe(&self, buffer_size: usize) -> mpsc::Receiver<LedgerInfoWithSignatures> {
+        let (sender, receiver) = mpsc::channel(buffer_size);
+        self.subscribers.lock().unwrap().push(CommitSubscriber {
+            sender,
+            lagging: false,
+        });
+        receiver
+    }
+
+    #[cfg(test)]
+    pub fn num_subscribers(&self) -> usize {
+        self.subscribers.lock().unwrap().len()
+    }
+
+    fn notify(&self, ledger_info: &LedgerInfoWithSignatures) {
+        let mut subscribers = self.subscribers.lock().unwrap();
+        *subscribers = subscribers
+            .drain(..)
+            .filter_map(|mut subscriber| {
+                match subscriber.sender.try_send(ledger_info.clone()) {
+                    Ok(()) => {
+                        if subscriber.lagging {
+                            info!("A lagging commit subscriber caught up");
+                        }
+                        subscriber.lagging = false;
+                    }
+                    Err(e) if e.is_full() => {
+                        if !subscriber.lagging {
+                            warn!("A commit subscriber is lagging, skipping the commits");
+                        }
+                        counters::SKIPPED_COMMIT_NOTIFICATION_COUNT.inc();
+                        subscriber.lagging = true;
+                    }
+                    // The receiver was dropped.
+                    Err(_) => return None,
+                }
+                Some(subscriber)
+            })
+            .collect();
+    }
+}
@@ -0,0 +1,135 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::chained_bft::{
+    commit_pipeline::{CommitPipeline, CommitSubscribers, OrderedBlocks},
+    test_utils::{build_empty_tree, MockStateComputer, MockStorage, MockTransactionManager},
+};
+use crypto::{hash::ACCUMULATOR_PLACEHOLDER_HASH, HashValue};
+use futures::{channel::mpsc, executor::block_on};
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Arc,
+};
+use types::{crypto_proxies::LedgerInfoWithSignatures, ledger_info::LedgerInfo};
+
+fn commit(version: u64) -> LedgerInfoWithSignatures {
+    LedgerInfoWithSignatures::new(
+        LedgerInfo::new(
+            version,
+            *ACCUMULATOR_PLACEHOLDER_HASH,
+            HashValue::zero(),
+            HashValue::zero(),
+            0,
+            0,
+            None,
+        ),
+        HashMap::new(),
+    )
+}
+
+fn received_versions(receiver: &mut mpsc::Receiver<LedgerInfoWithSignatures>) -> Vec<u64> {
+    let mut versions = vec![];
+    while let Ok(Some(ledger_info)) = receiver.try_next() {
+        versions.push(ledger_info.ledger_info().version());
+    }
+    versions
+}
+
+#[test]
+fn test_independent_subscribers() {
+    let subscribers = CommitSubscribers::default();
+    let mut first = subscribers.subscribe(10);
+    subscribers.notify(&commit(1));
+    let mut second = subscribers.subscribe(10);
+    subscribers.notify(&commit(2));
+
+    assert_eq!(received_versions(&mut first), vec![1, 2]);
+    // The subscribers are only notified of the commits following the subscription.
+    assert_eq!(received_versions(&mut second), vec![2]);
+}
+
+#[test]
+fn test_lagging_subscriber() {
+    let subscribers = CommitSubscribers::default();
+    let mut lagging = subscribers.subscribe(1);
+    let mut other = subscribers.subscribe(10);
+    for version in 1..=5 {
+        subscribers.notify(&commit(version));
+    }
+    // The buffer of a channel holds one more message for its sender.
+    assert_eq!(received_versions(&mut lagging), vec![1, 2]);
+    // The other subscriber is not held back by the lagging one.
+    assert_eq!(received_versions(&mut other), vec![1, 2, 3, 4, 5]);
+
+    // Once its buffer is read, the lagging subscriber catches up with the next commit.
+    subscribers.notify(&commit(6));
+    assert_eq!(received_versions(&mut lagging), vec![6]);
+}
+
+// A pipeline applying the commits inline, whose first commits fail to be persisted.
+fn pipeline_with_failing_commits(
+    failing_commits: usize,
+) -> (
+    CommitPipeline<Vec<usize>>,
+    CommitSubscribers,
+    mpsc::UnboundedReceiver<LedgerInfoWithSignatures>,
+) {
+    let (storage, _) = MockStorage::start_for_testing();
+    let (commit_cb_sender, commit_cb_receiver) = mpsc::unbounded();
+    let state_computer = Arc::new(
+        MockStateComputer::new(commit_cb_sender, storage).with_failing_commits(failing_commits),
+    );
+    let subscribers = CommitSubscribers::default();
+    let pipeline = CommitPipeline::new_with_subscribers(
+        build_empty_tree(),
+        state_computer,
+        Arc::new(MockTransactionManager::new()),
+        subscribers.clone(),
+    );
+    (pipeline, subscribers, commit_cb_receiver)
+}
+
+fn ordered_blocks(version: u64) -> OrderedBlocks<Vec<usize>> {
+    OrderedBlocks {
+        blocks: vec![],
+        pruned_block_ids: VecDeque::new(),
+        finality_proof: commit(version),
+    }
+}
+
+#[test]
+fn test_failed_commit_is_retried() {
+    let (pipeline, subscribers, mut committed) = pipeline_with_failing_commits(3);
+    let mut subscriber = subscribers.subscribe(10);
+    block_on(pipeline.commit(ordered_blocks(1)));
+    block_on(pipeline.commit(ordered_blocks(2)));
+
+    // The failed commit is persisted on retry, before the next one.
+    let mut versions = vec![];
+    while let Ok(Some(ledger_info)) = committed.try_next() {
+        versions.push(ledger_info.ledger_info().version());
+    }
+    assert_eq!(versions, vec![1, 2]);
+    assert_eq!(received_versions(&mut subscriber), vec![1, 2]);
+}
+
+#[test]
+#[should_panic]
+fn test_commit_never_persisted() {
+    let (pipeline, _, _) = pipeline_with_failing_commits(usize::max_value());
+    block_on(pipeline.commit(ordered_blocks(1)));
+}
+
+#[test]
+fn test_dropped_subscriber_is_removed() {
+    let subscribers = CommitSubscribers::default();
+    let dropped = subscribers.subscribe(10);
+    let mut kept = subscribers.subscribe(10);
+    assert_eq!(subscribers.num_subscribers(), 2);
+
+    drop(dropped);
+    subscribers.notify(&commit(1));
+    assert_eq!(subscribers.num_subscribers(), 1);
+    assert_eq!(received_versions(&mut kept), vec![1]);
+}
@@ -0,0 +1,522 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Runner of consensus conformance traces.
+//!
+//! A trace is a JSON document describing a scenario from the point of view of a single validator,
+//! the node under test: the messages it receives from the other validators and the outputs
+//! expected after handling each of them (whether it votes, its safety state, its highest quorum
+//! certificate and its last committed block). Blocks are referred to by labels and validators by
+//! their index, the runner builds and signs the corresponding messages. The traces don't depend
+//! on the implementation details, such that the test vectors of the protocol specification can be
+//! shared and checked against the implementation.
+//!
+//! The traces are executed against a real `EventProcessor` and its `SafetyRules`, while the
+//! storage, the state computer and the network are mocked. The node under test is always the
+//! validator 0; a message is never delivered to the node unless the trace says so.
+
+use crate::{
+    chained_bft::{
+        block_storage::{BlockReader, BlockStore, PruningPolicy},
+        commit_pipeline::CommitPipeline,
+        common::Round,
+        consensus_types::{
+            block::Block, proposal_msg::ProposalMsg, quorum_cert::QuorumCert, sync_info::SyncInfo,
+            vote_data::VoteData, vote_msg::VoteMsg,
+        },
+        epoch_manager::EpochManager,
+        event_processor::EventProcessor,
+        evidence::EvidenceStore,
+        liveness::{
+            pacemaker::{ExponentialTimeInterval, Pacemaker},
+            pacemaker_timeout_manager::HighestTimeoutCertificates,
+            proposal_generator::ProposalGenerator,
+            rotating_proposer_election::RotatingProposer,
+        },
+        network::ConsensusNetworkImpl,
+        network_tests::NetworkPlayground,
+        persistent_storage::PersistentStorage,
+        safety::safety_rules::{ConsensusState, SafetyRules},
+        sync_manager::BlockRetrievalPolicy,
+        test_utils::{
+            consensus_runtime, MockStateComputer, MockStorage, MockTransactionManager, TestPayload,
+        },
+    },
+    util::time_service::ClockTimeService,
+};
+use channel;
+use crypto::{hash::ACCUMULATOR_PLACEHOLDER_HASH, HashValue};
+use failure::prelude::*;
+use futures::{channel::mpsc, executor::block_on};
+use network::validator_network::{ConsensusNetworkEvents, ConsensusNetworkSender};
+use serde::Deserialize;
+use std::{collections::HashMap, fs, path::Path, sync::Arc, time::Duration};
+use tokio::runtime::Runtime;
+use types::{
+    crypto_proxies::{LedgerInfoWithSignatures, ValidatorSigner, ValidatorVerifier},
+    ledger_info::LedgerInfo,
+};
+
+#[cfg(test)]
+#[path = "conformance_test.rs"]
+mod conformance_test;
+
+/// The label of the genesis block, known to all the validators.
+pub const GENESIS_LABEL: &str = "genesis";
+
+/// A conformance scenario.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Trace {
+    pub name: String,
+    /// The number of validators, the quorum is the usual 2f + 1.
+    pub num_validators: usize,
+    /// The indices of the rotating proposers, each one is the proposer of a single round:
+    /// the proposer of round r is `proposers[r % proposers.len()]`.
+    pub proposers: Vec<usize>,
+    pub steps: Vec<TraceStep>,
+}
+
+/// A message delivered to (or an event of) the node under test. The unknown fields of a step,
+/// e.g., comments, are ignored.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TraceStep {
+    /// A proposal of a new block extending `parent`. The block carries a quorum certificate for
+    /// its parent signed by a quorum of validators.
+    Proposal {
+        block: String,
+        parent: String,
+        round: Round,
+        proposer: usize,
+        /// The block certified by the highest quorum certificate of the proposer, carried in the
+        /// sync info of the proposal. Defaults to the parent.
+        #[serde(default)]
+        highest_quorum_cert: Option<String>,
+        #[serde(default)]
+        expect: Expectation,
+    },
+    /// A vote for a block from the given validator.
+    Vote {
+        block: String,
+        voter: usize,
+        #[serde(default)]
+        expect: Expectation,
+    },
+    /// The local pacemaker times out in the given round.
+    LocalTimeout {
+        round: Round,
+        #[serde(default)]
+        expect: Expectation,
+    },
+}
+
+/// The state of the node under test expected once a step has been handled. Only the fields
+/// present are checked.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Expectation {
+    /// Whether the node voted while handling the step, i.e., the safety verdict on a proposal.
+    pub vote: Option<bool>,
+    pub last_vote_round: Option<Round>,
+    pub preferred_block_round: Option<Round>,
+    /// The label of the block certified by the highest quorum certificate.
+    pub highest_quorum_cert: Option<String>,
+    /// The label of the last committed block.
+    pub committed: Option<String>,
+}
+
+/// Loads all the traces (`*.json` files) of the given directory, sorted by file name.
+pub fn load_traces(dir: &Path) -> Result<Vec<Trace>> {
+    let mut paths = vec![];
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().map_or(false, |ext| ext == "json") {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+    paths
+        .iter()
+        .map(|path| {
+            let content = fs::read_to_string(path)?;
+            serde_json::from_str(&content)
+                .map_err(|e| format_err!("Invalid trace {:?}: {}", path, e))
+        })
+        .collect()
+}
+
+/// Executes the trace against a fresh node, the first violated expectation is returned as an
+/// error.
+pub fn run_trace(trace: &Trace) -> Result<()> {
+    let mut runner = TraceRunner::new(trace)?;
+    for (index, step) in trace.steps.iter().enumerate() {
+        runner
+            .run_step(step)
+            .map_err(|e| format_err!("Trace '{}', step {}: {}", trace.name, index, e))?;
+    }
+    Ok(())
+}
+
+/// A node under test together with the blocks built by the other validators.
+struct TraceRunner {
+    signers: Vec<ValidatorSigner>,
+    quorum_size: usize,
+    block_store: Arc<BlockStore<TestPayload>>,
+    event_processor: EventProcessor<TestPayload>,
+    blocks: HashMap<HashValue, Block<TestPayload>>,
+    labels: HashMap<String, HashValue>,
+    // The highest quorum certificate committing a block, carried by the next proposals.
+    highest_ledger_info: QuorumCert,
+    // The state computer fails in case nobody listens to the commits, which are rather
+    // observed via the block store.
+    _commit_receiver: mpsc::UnboundedReceiver<LedgerInfoWithSignatures>,
+    _playground: NetworkPlayground,
+    _runtime: Runtime,
+}
+
+impl TraceRunner {
+    fn new(trace: &Trace) -> Result<Self> {
+        ensure!(trace.num_validators > 0, "A trace needs validators");
+        ensure!(!trace.proposers.is_empty(), "A trace needs proposers");
+        let signers: Vec<_> = (0..trace.num_validators)
+            .map(|i| ValidatorSigner::random([i as u8; 32]))
+            .collect();
+        let mut proposers = vec![];
+        for index in &trace.proposers {
+            proposers.push(
+                signers
+                    .get(*index)
+                    .ok_or_else(|| format_err!("Unknown proposer {}", index))?
+                    .author(),
+            );
+        }
+        let validators = ValidatorVerifier::new(
+            signers
+                .iter()
+                .map(|signer| (signer.author(), signer.public_key()))
+                .collect(),
+        );
+        let quorum_size = validators.quorum_size();
+        let epoch_mgr = Arc::new(EpochManager::new(0, validators));
+
+        let runtime = consensus_runtime();
+        let mut playground = NetworkPlayground::new(runtime.executor());
+        let signer = signers[0].clone();
+        let author = signer.author();
+        let (network_reqs_tx, network_reqs_rx) = channel::new_test(8);
+        let (consensus_tx, consensus_rx) = channel::new_test(8);
+        playground.add_node(author, consensus_tx, network_reqs_rx);
+        let network = ConsensusNetworkImpl::new(
+            author,
+            ConsensusNetworkSender::new(network_reqs_tx),
+            ConsensusNetworkEvents::new(consensus_rx),
+            Arc::clone(&epoch_mgr),
+        );
+
+        let (storage, initial_data) = MockStorage::<TestPayload>::start_for_testing();
+        let (commit_sender, commit_receiver) = mpsc::unbounded();
+        let state_computer = Arc::new(MockStateComputer::new(commit_sender, Arc::clone(&storage)));
+        let block_store = Arc::new(block_on(BlockStore::new(
+            storage.clone(),
+            initial_data,
+            signer,
+            state_computer.clone(),
+            true,
+            PruningPolicy::MaxBlocks(10),
+        )));
+        let time_service = Arc::new(ClockTimeService::new(runtime.executor()));
+        let proposal_generator = ProposalGenerator::new(
+            Arc::clone(&block_store),
+            Arc::new(MockTransactionManager::new()),
+            time_service.clone(),
+            1,
+            u64::max_value(),
+            usize::max_value(),
+            true,
+        );
+        // The pacemaker never times out on its own, timeouts are driven by the trace.
+        let (timeout_sender, _) = channel::new_test(1_024);
+        let pacemaker = Pacemaker::new(
+            storage.persistent_liveness_storage(),
+            Box::new(ExponentialTimeInterval::fixed(Duration::from_secs(3600))),
+            time_service.clone(),
+            timeout_sender,
+            HighestTimeoutCertificates::default(),
+        );
+        let evidence_store = Arc::new(EvidenceStore::new(storage.clone(), vec![]));
+        let commit_pipeline = CommitPipeline::new(
+            Arc::clone(&block_store),
+            state_computer.clone(),
+            Arc::new(MockTransactionManager::new()),
+        );
+        let mut event_processor = EventProcessor::new(
+            author,
+            Arc::clone(&block_store),
+            pacemaker,
+            Box::new(RotatingProposer::new(proposers, 1)),
+            proposal_generator,
+            Box::new(SafetyRules::new_with_storage(
+                storage
+                    .persistent_safety_storage()
+                    .consensus_state()
+                    .unwrap(),
+                storage.persistent_safety_storage(),
+            )),
+            state_computer,
+            commit_pipeline,
+            network,
+            storage,
+            time_service,
+            true,
+            epoch_mgr,
+            None,
+            BlockRetrievalPolicy::default(),
+            evidence_store,
+        );
+        block_on(event_processor.start());
+
+        let genesis = block_store.root().block().clone();
+        let mut labels = HashMap::new();
+        labels.insert(GENESIS_LABEL.to_string(), genesis.id());
+        let mut blocks = HashMap::new();
+        blocks.insert(genesis.id(), genesis);
+        Ok(Self {
+            signers,
+            quorum_size,
+            block_store,
+            event_processor,
+            blocks,
+            labels,
+            highest_ledger_info: QuorumCert::certificate_for_genesis(),
+            _commit_receiver: commit_receiver,
+            _playground: playground,
+            _runtime: runtime,
+        })
+    }
+
+    fn run_step(&mut self, step: &TraceStep) -> Result<()> {
+        let before = self.event_processor.consensus_state();
+        let expect = match step {
+            TraceStep::Proposal {
+                block,
+                parent,
+                round,
+                proposer,
+                highest_quorum_cert,
+                expect,
+            } => {
+                let proposal = self.build_proposal(
+                    block,
+                    parent,
+                    *round,
+                    *proposer,
+                    highest_quorum_cert.as_ref(),
+                )?;
+                block_on(self.event_processor.process_proposal_msg(proposal));
+                expect
+            }
+            TraceStep::Vote {
+                block,
+                voter,
+                expect,
+            } => {
+                let vote = self.build_vote(self.block(block)?, self.signer(*voter)?);
+                block_on(self.event_processor.process_vote(vote));
+                expect
+            }
+            TraceStep::LocalTimeout { round, expect } => {
+                block_on(self.event_processor.process_local_timeout(*round));
+                expect
+            }
+        };
+        self.check(&before, expect)
+    }
+
+    fn check(&self, before: &ConsensusState, expect: &Expectation) -> Result<()> {
+        let state = self.event_processor.consensus_state();
+        if let Some(vote) = expect.vote {
+            let voted = state.last_vote_round() > before.last_vote_round();
+            ensure!(
+                voted == vote,
+                "Expected vote: {}, but the node {}",
+                vote,
+                if voted { "voted" } else { "didn't vote" }
+            );
+        }
+        if let Some(round) = expect.last_vote_round {
+            ensure!(
+                state.last_vote_round() == round,
+                "Expected last vote round {}, got {}",
+                round,
+                state.last_vote_round()
+            );
+        }
+        if let Some(round) = expect.preferred_block_round {
+            ensure!(
+                state.preferred_block_round() == round,
+                "Expected preferred block round {}, got {}",
+                round,
+                state.preferred_block_round()
+            );
+        }
+        if let Some(label) = &expect.highest_quorum_cert {
+            let certified = self.block_store.highest_quorum_cert().certified_block_id();
+            ensure!(
+                *self.block_id(label)? == certified,
+                "Expected highest quorum cert for {}, got {}",
+                label,
+                self.label(certified)
+            );
+        }
+        if let Some(label) = &expect.committed {
+            let committed = self.block_store.root().id();
+            ensure!(
+                *self.block_id(label)? == committed,
+                "Expected last committed block {}, got {}",
+                label,
+                self.label(committed)
+            );
+        }
+        Ok(())
+    }
+
+    fn build_proposal(
+        &mut self,
+        label: &str,
+        parent: &str,
+        round: Round,
+        proposer: usize,
+        highest_quorum_cert: Option<&String>,
+    ) -> Result<ProposalMsg<TestPayload>> {
+        ensure!(
+            !self.labels.contains_key(label),
+            "Block {} is already defined",
+            label
+        );
+        let parent = self.block(parent)?;
+        ensure!(
+            round > parent.round(),
+            "The round of block {} must be higher than the round of its parent",
+            label
+        );
+        let parent_qc = self.quorum_cert(parent);
+        let highest_quorum_cert = match highest_quorum_cert {
+            Some(certified) => self.quorum_cert(self.block(certified)?),
+            None => parent_qc.clone(),
+        };
+        // The payload makes the blocks with the same parent and round distinct.
+        let block = Block::make_block(
+            parent,
+            vec![self.blocks.len()],
+            round,
+            // The timestamps are in the past, such that the node never waits before voting.
+            round,
+            parent_qc,
+            self.signer(proposer)?,
+        );
+        for qc in &[&highest_quorum_cert, block.quorum_cert()] {
+            if qc.committed_block_id().is_some()
+                && qc.certified_block_round() > self.highest_ledger_info.certified_block_round()
+            {
+                self.highest_ledger_info = (*qc).clone();
+            }
+        }
+        let sync_info = SyncInfo::new(highest_quorum_cert, self.highest_ledger_info.clone(), None);
+        self.labels.insert(label.to_string(), block.id());
+        self.blocks.insert(block.id(), block.clone());
+        Ok(ProposalMsg::new(block, sync_info))
+    }
+
+    /// The vote of a validator following the voting rules: the ledger info commits the
+    /// grandparent of the block in case the vote forms a 3-chain of contiguous rounds.
+    fn build_vote(&self, block: &Block<TestPayload>, signer: &ValidatorSigner) -> VoteMsg {
+        let qc = block.quorum_cert();
+        let committed = if qc.parent_block_round() + 1 == qc.certified_block_round()
+            && qc.certified_block_round() + 1 == block.round()
+        {
+            self.blocks.get(&qc.parent_block_id())
+        } else {
+            None
+        };
+        let ledger_info_placeholder = match committed {
+            Some(committed) => LedgerInfo::new(
+                0,
+                *ACCUMULATOR_PLACEHOLDER_HASH,
+                HashValue::zero(),
+                committed.id(),
+                0,
+                committed.timestamp_usecs(),
+                None,
+            ),
+            None => LedgerInfo::new(
+                0,
+                HashValue::zero(),
+                HashValue::zero(),
+                HashValue::zero(),
+                0,
+                0,
+                None,
+            ),
+        };
+        VoteMsg::new(
+            VoteData::new(
+                block.id(),
+                *ACCUMULATOR_PLACEHOLDER_HASH,
+                block.round(),
+                qc.certified_block_id(),
+                qc.certified_block_round(),
+                qc.parent_block_id(),
+                qc.parent_block_round(),
+            ),
+            signer.author(),
+            ledger_info_placeholder,
+            signer,
+        )
+    }
+
+    /// The quorum certificate for the block aggregating the votes of the first validators.
+    fn quorum_cert(&self, block: &Block<TestPayload>) -> QuorumCert {
+        if block.round() == 0 {
+            return QuorumCert::certificate_for_genesis();
+        }
+        let votes: Vec<_> = self
+            .signers
+            .iter()
+            .take(self.quorum_size)
+            .map(|signer| self.build_vote(block, signer))
+            .collect();
+        let mut ledger_info =
+            LedgerInfoWithSignatures::new(votes[0].ledger_info().clone(), HashMap::new());
+        for vote in &votes {
+            vote.signature()
+                .clone()
+                .add_to_li(vote.author(), &mut ledger_info);
+        }
+        QuorumCert::new(votes[0].vote_data().clone(), ledger_info)
+    }
+
+    fn block_id(&self, label: &str) -> Result<&HashValue> {
+        self.labels
+            .get(label)
+            .ok_or_else(|| format_err!("Unknown block {}", label))
+    }
+
+    fn block(&self, label: &str) -> Result<&Block<TestPayload>> {
+        Ok(&self.blocks[self.block_id(label)?])
+    }
+
+    fn signer(&self, index: usize) -> Result<&ValidatorSigner> {
+        self.signers
+            .get(index)
+            .ok_or_else(|| format_err!("Unknown validator {}", index))
+    }
+
+    // The label of a block built by the trace, or the short id of a block built by the node.
+    fn label(&self, id: HashValue) -> String {
+        self.labels
+            .iter()
+            .find(|(_, block_id)| **block_id == id)
+            .map_or_else(|| id.short_str(), |(label, _)| label.clone())
+    }
+}
@@ -0,0 +1,56 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::chained_bft::conformance::{load_traces, run_trace, Trace};
+use std::path::Path;
+
+#[test]
+fn test_conformance_traces() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("conformance_traces");
+    let traces = load_traces(&dir).unwrap();
+    assert!(!traces.is_empty());
+    for trace in &traces {
+        if let Err(e) = run_trace(trace) {
+            panic!("{}", e);
+        }
+    }
+}
+
+#[test]
+fn test_violated_expectation() {
+    let trace: Trace = serde_json::from_str(
+        r#"{
+            "name": "violated",
+            "num_validators": 4,
+            "proposers": [1, 2, 3],
+            "steps": [
+                {
+                    "type": "proposal", "block": "b1", "parent": "genesis", "round": 1,
+                    "proposer": 2, "expect": { "vote": true }
+                },
+                {
+                    "type": "proposal", "block": "b1_equivocation", "parent": "genesis",
+                    "round": 1, "proposer": 2, "expect": { "vote": true }
+                }
+            ]
+        }"#,
+    )
+    .unwrap();
+    let error = run_trace(&trace).unwrap_err().to_string();
+    assert!(error.contains("step 1"), "{}", error);
+}
+
+#[test]
+fn test_unknown_block() {
+    let trace: Trace = serde_json::from_str(
+        r#"{
+            "name": "unknown_block",
+            "num_validators": 1,
+            "proposers": [0],
+            "steps": [{ "type": "proposal", "block": "b2", "parent": "b1", "round": 2, "proposer": 0 }]
+        }"#,
+    )
+    .unwrap();
+    let error = run_trace(&trace).unwrap_err().to_string();
+    assert!(error.contains("Unknown block b1"), "{}", error);
+}
@@ -4,7 +4,9 @@
 use crate::chained_bft::{
     common::{Author, Height, Round},
     consensus_types::{
-        quorum_cert::QuorumCert, vote_data::VoteData, vote_msg::VoteMsgVerificationError,
+        quorum_cert::{QuorumCert, VerifiedQcCache},
+        vote_data::VoteData,
+        vote_msg::VoteMsgVerificationError,
     },
 };
 use canonical_serialization::{
@@ -301,11 +303,17 @@ where
         &self.payload
     }
 
+    /// The size in bytes of the payload as carried by the proposals on the wire.
+    pub fn payload_size(&self) -> u64 {
+        serialized_payload_size(&self.payload)
+    }
+
     /// Verifies that the proposal and the QC are correctly signed.
     /// If this is the genesis block, we skip these checks.
     pub fn validate_signatures(
         &self,
         validator: &ValidatorVerifier,
+        qc_cache: &VerifiedQcCache,
     ) -> ::std::result::Result<(), BlockVerificationError> {
         // if genesis block, we don't verify anything
         if self.is_genesis_block() {
@@ -314,12 +322,12 @@ where
         // verify signature from leader if it's a real proposal
         if let BlockSource::Proposal { author, signature } = &self.block_source {
             signature
-                .verify(validator, *author, self.hash())
+                .verify(&validator.at_round(self.round()), *author, self.hash())
                 .map_err(|_| BlockVerificationError::SigVerifyError)?;
         }
         // verify signatures of quorum cert
         self.quorum_cert
-            .verify(validator)
+            .verify_with_cache(validator, qc_cache)
             .map_err(BlockVerificationError::QCVerificationError)
     }
 
@@ -536,6 +544,13 @@ where
     }
 }
 
+/// Returns the size in bytes of the serialized payload, as set in the proto of a block.
+pub fn serialized_payload_size<T: Serialize>(payload: &T) -> u64 {
+    to_vec_named(payload)
+        .expect("fail to serialize payload")
+        .len() as u64
+}
+
 #[cfg(test)]
 impl<T> Block<T>
 where
@@ -5,7 +5,7 @@ use crate::chained_bft::{
     common::{Height, Round},
     consensus_types::{
         block::{Block, BlockSource},
-        quorum_cert::QuorumCert,
+        quorum_cert::{QuorumCert, VerifiedQcCache},
     },
     test_utils::placeholder_certificate_for_block,
 };
@@ -222,7 +222,7 @@ fn test_nil_block() {
 
     let dummy_verifier = Arc::new(ValidatorVerifier::new(HashMap::new()));
     assert!(nil_block
-        .validate_signatures(dummy_verifier.as_ref())
+        .validate_signatures(dummy_verifier.as_ref(), &VerifiedQcCache::new(0))
         .is_ok());
     assert!(nil_block.verify_well_formed().is_ok());
 
@@ -0,0 +1,157 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::chained_bft::common::{Author, Round};
+use canonical_serialization::{CanonicalSerialize, CanonicalSerializer, SimpleSerializer};
+use crypto::{
+    hash::{CryptoHash, CryptoHasher, KeyRotationHasher},
+    HashValue,
+};
+use failure::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use types::crypto_proxies::{PublicKey, Signature, ValidatorSigner, ValidatorVerifier};
+
+#[cfg(test)]
+#[path = "key_rotation_test.rs"]
+mod key_rotation_test;
+
+/// The number of rounds after the handover round during which the signatures under the replaced
+/// key are still accepted, e.g., for the messages signed before the rotation took effect.
+pub const KEY_RETIREMENT_ROUNDS: Round = 3;
+
+// Internal use only. Contains all the fields in KeyRotation that contributes to the computation
+// of its hash.
+struct KeyRotationSerializer<'a> {
+    epoch: u64,
+    author: Author,
+    new_public_key: &'a PublicKey,
+    handover_round: Round,
+}
+
+impl<'a> CanonicalSerialize for KeyRotationSerializer<'a> {
+    fn serialize(&self, serializer: &mut impl CanonicalSerializer) -> failure::Result<()> {
+        serializer.encode_u64(self.epoch)?;
+        serializer.encode_struct(&self.author)?;
+        serializer.encode_bytes(&self.new_public_key.to_bytes())?;
+        serializer.encode_u64(self.handover_round)?;
+        Ok(())
+    }
+}
+
+impl<'a> CryptoHash for KeyRotationSerializer<'a> {
+    type Hasher = KeyRotationHasher;
+
+    fn hash(&self) -> HashValue {
+        let mut state = Self::Hasher::default();
+        state.write(&SimpleSerializer::<Vec<u8>>::serialize(self).expect("Should serialize."));
+        state.finish()
+    }
+}
+
+/// The record of a validator rotating its consensus key within an epoch: the validator signs
+/// with the new key starting from the handover round. The record is signed by the current key of
+/// the validator, which authenticates the rotation, and by the new key, which proves that the
+/// validator owns it. The replaced key is retired `KEY_RETIREMENT_ROUNDS` rounds after the
+/// handover round.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct KeyRotation {
+    epoch: u64,
+    author: Author,
+    new_public_key: PublicKey,
+    handover_round: Round,
+    signature: Signature,
+    new_key_signature: Signature,
+}
+
+impl fmt::Display for KeyRotation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "KeyRotation: [epoch: {}, author: {}, handover round: {}]",
+            self.epoch,
+            self.author.short_str(),
+            self.handover_round
+        )
+    }
+}
+
+impl KeyRotation {
+    /// Creates the record of the rotation from the current key of the signer to the key of the
+    /// new signer, which must have the same author.
+    pub fn new(
+        epoch: u64,
+        handover_round: Round,
+        signer: &ValidatorSigner,
+        new_signer: &ValidatorSigner,
+    ) -> Result<Self> {
+        ensure!(
+            signer.author() == new_signer.author(),
+            "The new signer of {} has another author: {}",
+            signer.author(),
+            new_signer.author()
+        );
+        let new_public_key = new_signer.public_key();
+        let digest = KeyRotationSerializer {
+            epoch,
+            author: signer.author(),
+            new_public_key: &new_public_key,
+            handover_round,
+        }
+        .hash();
+        Ok(Self {
+            epoch,
+            author: signer.author(),
+            new_public_key,
+            handover_round,
+            signature: signer.sign_message(digest)?.into(),
+            new_key_signature: new_signer.sign_message(digest)?.into(),
+        })
+    }
+
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    pub fn author(&self) -> Author {
+        self.author
+    }
+
+    pub fn new_public_key(&self) -> &PublicKey {
+        &self.new_public_key
+    }
+
+    pub fn handover_round(&self) -> Round {
+        self.handover_round
+    }
+
+    /// The first round whose signatures under the replaced key are rejected.
+    pub fn retirement_round(&self) -> Round {
+        self.handover_round + KEY_RETIREMENT_ROUNDS
+    }
+
+    fn digest(&self) -> HashValue {
+        KeyRotationSerializer {
+            epoch: self.epoch,
+            author: self.author,
+            new_public_key: &self.new_public_key,
+            handover_round: self.handover_round,
+        }
+        .hash()
+    }
+
+    /// Verifies that the record is signed by the current key of its author in the given
+    /// validator set and by the new key.
+    pub fn verify(&self, validator: &ValidatorVerifier) -> Result<()> {
+        let digest = self.digest();
+        self.signature
+            .verify(validator, self.author, digest)
+            .map_err(|e| format_err!("Invalid signature of {}: {}", self, e))?;
+        let new_key_validator =
+            ValidatorVerifier::new_single(self.author, self.new_public_key.clone());
+        self.new_key_signature
+            .verify(&new_key_validator, self.author, digest)
+            .map_err(|e| format_err!("Invalid signature of {} by the new key: {}", self, e))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,137 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::chained_bft::{
+    common::{Author, Round},
+    consensus_types::{
+        key_rotation::{KeyRotation, KEY_RETIREMENT_ROUNDS},
+        vote_data::VoteData,
+        vote_msg::VoteMsg,
+    },
+    epoch_manager::EpochManager,
+    test_utils::placeholder_ledger_info,
+};
+use crypto::{ed25519::compat, HashValue};
+use types::crypto_proxies::{ValidatorSigner, ValidatorVerifier};
+
+fn new_signer_for(author: Author) -> ValidatorSigner {
+    ValidatorSigner::new(author, compat::generate_keypair(None).0)
+}
+
+fn vote_for_round(round: Round, signer: &ValidatorSigner) -> VoteMsg {
+    VoteMsg::new(
+        VoteData::new(
+            HashValue::random(),
+            HashValue::random(),
+            round,
+            HashValue::random(),
+            round - 1,
+            HashValue::random(),
+            round - 2,
+        ),
+        signer.author(),
+        placeholder_ledger_info(),
+        signer,
+    )
+}
+
+#[test]
+fn test_key_rotation_verify() {
+    let signer = ValidatorSigner::random([0u8; 32]);
+    let new_signer = new_signer_for(signer.author());
+    let validator = ValidatorVerifier::new_single(signer.author(), signer.public_key());
+    let rotation = KeyRotation::new(1, 10, &signer, &new_signer).unwrap();
+    assert_eq!(rotation.author(), signer.author());
+    assert_eq!(rotation.new_public_key(), &new_signer.public_key());
+    assert!(rotation.verify(&validator).is_ok());
+
+    // The record must be signed by the current key of the author.
+    let impostor = new_signer_for(signer.author());
+    let forged = KeyRotation::new(1, 10, &impostor, &new_signer).unwrap();
+    assert!(forged.verify(&validator).is_err());
+
+    // The new signer must have the same author.
+    assert!(KeyRotation::new(1, 10, &signer, &ValidatorSigner::random([1u8; 32])).is_err());
+}
+
+#[test]
+fn test_epoch_manager_key_rotation() {
+    let signer = ValidatorSigner::random([0u8; 32]);
+    let new_signer = new_signer_for(signer.author());
+    let epoch_mgr = EpochManager::new(
+        1,
+        ValidatorVerifier::new_single(signer.author(), signer.public_key()),
+    );
+    let hash = HashValue::random();
+    let old_signature = signer.sign_message(hash).unwrap();
+    let new_signature = new_signer.sign_message(hash).unwrap();
+
+    // A record of another epoch is rejected.
+    let stale_rotation = KeyRotation::new(0, 10, &signer, &new_signer).unwrap();
+    assert!(epoch_mgr.add_key_rotation(&stale_rotation).is_err());
+    assert!(epoch_mgr
+        .validators()
+        .verify_signature(signer.author(), hash, &new_signature)
+        .is_err());
+
+    let rotation = KeyRotation::new(1, 10, &signer, &new_signer).unwrap();
+    epoch_mgr.add_key_rotation(&rotation).unwrap();
+    // Adding the record again is a no-op.
+    epoch_mgr.add_key_rotation(&rotation).unwrap();
+    assert_eq!(epoch_mgr.key_rotations(), vec![rotation]);
+    let validators = epoch_mgr.validators();
+    assert_eq!(
+        validators.get_public_key(signer.author()),
+        Some(new_signer.public_key())
+    );
+    // The new key is accepted from the handover round on.
+    assert!(validators
+        .at_round(9)
+        .verify_signature(signer.author(), hash, &new_signature)
+        .is_err());
+    assert!(validators
+        .at_round(10)
+        .verify_signature(signer.author(), hash, &new_signature)
+        .is_ok());
+    // The replaced key is accepted until its retirement.
+    assert!(validators
+        .at_round(9 + KEY_RETIREMENT_ROUNDS)
+        .verify_signature(signer.author(), hash, &old_signature)
+        .is_ok());
+    assert!(validators
+        .at_round(10 + KEY_RETIREMENT_ROUNDS)
+        .verify_signature(signer.author(), hash, &old_signature)
+        .is_err());
+
+    // A rotation handed over before the previous one is rejected.
+    let newest_signer = new_signer_for(signer.author());
+    let out_of_order = KeyRotation::new(1, 5, &new_signer, &newest_signer).unwrap();
+    assert!(epoch_mgr.add_key_rotation(&out_of_order).is_err());
+    assert_eq!(epoch_mgr.key_rotations().len(), 1);
+}
+
+#[test]
+fn test_vote_verified_under_key_of_its_round() {
+    let signer = ValidatorSigner::random([0u8; 32]);
+    let new_signer = new_signer_for(signer.author());
+    let epoch_mgr = EpochManager::new(
+        1,
+        ValidatorVerifier::new_single(signer.author(), signer.public_key()),
+    );
+    epoch_mgr
+        .add_key_rotation(&KeyRotation::new(1, 10, &signer, &new_signer).unwrap())
+        .unwrap();
+    let validators = epoch_mgr.validators();
+
+    assert!(vote_for_round(9, &signer).verify(&validators).is_ok());
+    assert!(vote_for_round(9, &new_signer).verify(&validators).is_err());
+    assert!(vote_for_round(10, &new_signer).verify(&validators).is_ok());
+    assert!(vote_for_round(10, &signer).verify(&validators).is_ok());
+    let retirement_round = 10 + KEY_RETIREMENT_ROUNDS;
+    assert!(vote_for_round(retirement_round, &new_signer)
+        .verify(&validators)
+        .is_ok());
+    assert!(vote_for_round(retirement_round, &signer)
+        .verify(&validators)
+        .is_err());
+}
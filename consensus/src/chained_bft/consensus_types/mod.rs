@@ -2,9 +2,11 @@
 // SPDX-License-Identifier: Apache-2.0
 
 pub(crate) mod block;
+pub(crate) mod key_rotation;
 pub(crate) mod proposal_msg;
 pub(crate) mod quorum_cert;
 pub(crate) mod sync_info;
+pub(crate) mod timeout_certificate;
 pub(crate) mod timeout_msg;
 pub(crate) mod vote_data;
 pub(crate) mod vote_msg;
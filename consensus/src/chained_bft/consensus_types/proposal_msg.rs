@@ -3,11 +3,17 @@
 
 use crate::chained_bft::{
     common::{Author, Payload, Round},
-    consensus_types::{block::Block, sync_info::SyncInfo},
+    consensus_types::{
+        block::Block,
+        quorum_cert::VerifiedQcCache,
+        sync_info::SyncInfo,
+        vote_msg::{verify_votes, VoteMsg},
+    },
 };
 use failure::prelude::*;
 use network::proto::Proposal as ProtoProposal;
 use proto_conv::{FromProto, IntoProto};
+use protobuf::RepeatedField;
 use std::fmt;
 use types::crypto_proxies::ValidatorVerifier;
 
@@ -17,6 +23,9 @@ use types::crypto_proxies::ValidatorVerifier;
 pub struct ProposalMsg<T> {
     proposal: Block<T>,
     sync_info: SyncInfo,
+    // The votes for the blocks of the previous round that didn't form a QC at the proposer: the
+    // recipients might complete a QC with them.
+    votes: Vec<VoteMsg>,
 }
 
 /// A ProposalMsg is only accessible after verifying the signatures of a ProposalUncheckedSignatures
@@ -29,9 +38,14 @@ impl<T: Payload> FromProto for ProposalUncheckedSignatures<T> {
     fn from_proto(mut object: Self::ProtoType) -> Result<Self> {
         let proposal = Block::<T>::from_proto(object.take_proposed_block())?;
         let sync_info = SyncInfo::from_proto(object.take_sync_info())?;
-        Ok(ProposalUncheckedSignatures(ProposalMsg::new(
-            proposal, sync_info,
-        )))
+        let votes = object
+            .take_votes()
+            .into_iter()
+            .map(VoteMsg::from_proto)
+            .collect::<Result<Vec<_>>>()?;
+        Ok(ProposalUncheckedSignatures(
+            ProposalMsg::new(proposal, sync_info).with_votes(votes),
+        ))
     }
 }
 
@@ -45,11 +59,16 @@ impl<T: Payload> From<ProposalUncheckedSignatures<T>> for ProposalMsg<T> {
 impl<T: Payload> ProposalUncheckedSignatures<T> {
     /// Validates the signatures of the proposal. This includes the leader's signature over the
     /// block and the QC, the timeout certificate signatures and the highest_ledger_info signatures.
-    pub fn validate_signatures(self, validator: &ValidatorVerifier) -> Result<ProposalMsg<T>> {
+    /// The QCs are verified only if they are not present in the given cache.
+    pub fn validate_signatures(
+        self,
+        validator: &ValidatorVerifier,
+        qc_cache: &VerifiedQcCache,
+    ) -> Result<ProposalMsg<T>> {
         // verify block leader's signature and QC
         self.0
             .proposal
-            .validate_signatures(validator)
+            .validate_signatures(validator, qc_cache)
             .map_err(|e| format_err!("{:?}", e))?;
         // if there is a timeout certificate, verify its signatures
         if let Some(tc) = self.0.sync_info.highest_timeout_certificate() {
@@ -59,8 +78,21 @@ impl<T: Payload> ProposalUncheckedSignatures<T> {
         self.0
             .sync_info
             .highest_ledger_info()
-            .verify(validator)
+            .verify_with_cache(validator, qc_cache)
             .map_err(|e| format_err!("{:?}", e))?;
+        // verify the piggybacked votes, batching their signatures over the same ledger info
+        ensure!(
+            self.0.votes.len() <= validator.len(),
+            "Proposal {} carries {} votes for {} validators",
+            self.0.proposal,
+            self.0.votes.len(),
+            validator.len(),
+        );
+        verify_votes(
+            self.0.votes.iter().chain(self.0.sync_info.last_vote()),
+            validator,
+        )
+        .map_err(|e| format_err!("{:?}", e))?;
         // return proposal
         Ok(self.0)
     }
@@ -72,9 +104,16 @@ impl<T: Payload> ProposalMsg<T> {
         Self {
             proposal,
             sync_info,
+            votes: vec![],
         }
     }
 
+    /// Piggybacks the votes for the blocks of the previous round.
+    pub fn with_votes(mut self, votes: Vec<VoteMsg>) -> Self {
+        self.votes = votes;
+        self
+    }
+
     /// Verifies that the ProposalMsg is well-formed.
     pub fn verify_well_formed(self) -> Result<Self> {
         if self.proposal.is_nil_block() {
@@ -122,6 +161,14 @@ impl<T: Payload> ProposalMsg<T> {
                 self.proposal
             ));
         }
+        for vote in &self.votes {
+            ensure!(
+                vote.vote_data().block_round() < self.proposal.round(),
+                "Proposal for {} carries a vote for the later round {}",
+                self.proposal,
+                vote.vote_data().block_round(),
+            );
+        }
         Ok(self)
     }
 
@@ -137,6 +184,10 @@ impl<T: Payload> ProposalMsg<T> {
         &self.sync_info
     }
 
+    pub fn votes(&self) -> &[VoteMsg] {
+        &self.votes
+    }
+
     pub fn round(&self) -> Round {
         self.proposal.round()
     }
@@ -165,6 +216,9 @@ impl<T: Payload> IntoProto for ProposalMsg<T> {
         let mut proto = Self::ProtoType::new();
         proto.set_proposed_block(self.proposal.into_proto());
         proto.set_sync_info(self.sync_info.into_proto());
+        proto.set_votes(RepeatedField::from_iter(
+            self.votes.into_iter().map(VoteMsg::into_proto),
+        ));
         proto
     }
 }
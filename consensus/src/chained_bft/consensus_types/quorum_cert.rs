@@ -1,33 +1,52 @@
 // Copyright (c) The Libra Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::chained_bft::{
-    common::Round,
-    consensus_types::{vote_data::VoteData, vote_msg::VoteMsgVerificationError},
+use crate::{
+    chained_bft::{
+        common::Round,
+        consensus_types::{
+            vote_data::VoteData,
+            vote_msg::{
+                check_signature_scheme, signature_scheme_from_proto, VoteMsgVerificationError,
+            },
+        },
+    },
+    counters,
 };
 use crypto::{
     hash::{CryptoHash, ACCUMULATOR_PLACEHOLDER_HASH, GENESIS_BLOCK_ID},
-    HashValue,
+    HashValue, Signature,
 };
 use failure::Result;
 use network::proto::QuorumCert as ProtoQuorumCert;
 use proto_conv::{FromProto, IntoProto};
 use serde::{Deserialize, Serialize};
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet, VecDeque},
     fmt::{Display, Formatter},
+    sync::Mutex,
 };
 use types::{
-    crypto_proxies::{LedgerInfoWithSignatures, ValidatorSigner, ValidatorVerifier},
+    crypto_proxies::{
+        LedgerInfoWithSignatures, SignatureScheme, ValidatorSigner, ValidatorVerifier,
+        SIGNATURE_SCHEME,
+    },
     ledger_info::LedgerInfo,
 };
 
+#[cfg(test)]
+#[path = "quorum_cert_test.rs"]
+pub mod quorum_cert_test;
+
 #[derive(Deserialize, Serialize, Clone, Debug, Eq, PartialEq)]
 pub struct QuorumCert {
     /// The vote information certified by the quorum.
     vote_data: VoteData,
     /// The signed LedgerInfo of a committed block that carries the data about the certified block.
     signed_ledger_info: LedgerInfoWithSignatures,
+    /// The scheme of the signatures of the LedgerInfo.
+    #[serde(default)]
+    signature_scheme: SignatureScheme,
 }
 
 impl Display for QuorumCert {
@@ -45,6 +64,7 @@ impl QuorumCert {
         QuorumCert {
             vote_data,
             signed_ledger_info,
+            signature_scheme: SIGNATURE_SCHEME,
         }
     }
     /// All the vote data getters are just proxies for retrieving the values from the VoteData
@@ -79,6 +99,16 @@ impl QuorumCert {
         &self.signed_ledger_info
     }
 
+    pub fn signature_scheme(&self) -> SignatureScheme {
+        self.signature_scheme
+    }
+
+    /// Replaces the signatures of the voters with a single aggregated signature and the bitmap of
+    /// the voters, which requires a signature scheme that supports aggregation.
+    pub fn aggregate_signatures(&mut self, validator: &ValidatorVerifier) -> Result<()> {
+        self.signed_ledger_info.aggregate_signatures(validator)
+    }
+
     pub fn committed_block_id(&self) -> Option<HashValue> {
         let id = self.ledger_info().ledger_info().consensus_block_id();
         if id.is_zero() {
@@ -147,10 +177,134 @@ impl QuorumCert {
         {
             return Ok(());
         }
+        check_signature_scheme(self.signature_scheme)?;
+        // The signatures are verified under the keys of the validators at the certified round.
         self.ledger_info()
-            .verify(validator)
+            .verify(&validator.at_round(self.vote_data.block_round()))
             .map_err(VoteMsgVerificationError::SigVerifyError)
     }
+
+    /// Same as `verify`, but skips the verification of the QCs that have already been
+    /// successfully verified with the given cache. The same QC typically arrives many times per
+    /// round (in the proposal, in the sync info of the timeout messages, etc.).
+    pub fn verify_with_cache(
+        &self,
+        validator: &ValidatorVerifier,
+        cache: &VerifiedQcCache,
+    ) -> ::std::result::Result<(), VoteMsgVerificationError> {
+        let digest = self.digest();
+        if cache.contains(&digest) || cache.is_trusted(self) {
+            counters::QC_VERIFICATION_CACHE_HIT_COUNT.inc();
+            return Ok(());
+        }
+        self.verify(validator)?;
+        cache.insert(digest);
+        Ok(())
+    }
+
+    /// A digest of the whole content of the QC: the vote data, the ledger info and all the
+    /// signatures, individual or aggregated. Unlike the certified block id, the digest differs for
+    /// two QCs certifying the same block with different sets of signatures.
+    pub fn digest(&self) -> HashValue {
+        let mut signatures: Vec<_> = self.ledger_info().signatures().iter().collect();
+        signatures.sort_by_key(|(author, _)| *author);
+        let mut bytes = self.vote_data.hash().to_vec();
+        bytes.extend(self.ledger_info().ledger_info().hash().to_vec());
+        bytes.extend_from_slice(&self.signature_scheme.id().to_le_bytes());
+        for (author, signature) in signatures {
+            bytes.extend_from_slice(author.as_ref());
+            bytes.extend(signature.to_bytes());
+        }
+        if let Some(aggregated_signature) = self.ledger_info().aggregated_signature() {
+            bytes.extend_from_slice(aggregated_signature.signer_bitmap());
+            bytes.extend(aggregated_signature.signature().to_bytes());
+        }
+        HashValue::from_sha3_256(&bytes)
+    }
+
+    /// A digest of the certified data: the vote data and the ledger info, without the signatures.
+    fn certified_data_digest(&self) -> HashValue {
+        let mut bytes = self.vote_data.hash().to_vec();
+        bytes.extend(self.ledger_info().ledger_info().hash().to_vec());
+        HashValue::from_sha3_256(&bytes)
+    }
+}
+
+/// Bounded set of the digests of the QCs that have been successfully verified against a given
+/// validator set. Once the capacity is reached the oldest digests are evicted first.
+/// The cache also holds the data certified by a previous validator set, which is trusted
+/// regardless of the signatures of the QCs certifying it.
+pub struct VerifiedQcCache {
+    capacity: usize,
+    inner: Mutex<VerifiedQcCacheInner>,
+    trusted: Mutex<HashSet<HashValue>>,
+}
+
+#[derive(Default)]
+struct VerifiedQcCacheInner {
+    digests: HashSet<HashValue>,
+    // Insertion order of the digests for eviction.
+    order: VecDeque<HashValue>,
+}
+
+impl VerifiedQcCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            inner: Mutex::new(VerifiedQcCacheInner::default()),
+            trusted: Mutex::new(HashSet::new()),
+        }
+    }
+
+    pub fn contains(&self, digest: &HashValue) -> bool {
+        self.inner.lock().unwrap().digests.contains(digest)
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.lock().unwrap().digests.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Forgets all the verified QCs and the trusted data, e.g., when the validator set changes.
+    pub fn clear(&self) {
+        *self.inner.lock().unwrap() = VerifiedQcCacheInner::default();
+        self.trusted.lock().unwrap().clear();
+    }
+
+    /// Trusts the data certified by the given QCs: any QC certifying the same data is accepted
+    /// without verifying its signatures.
+    pub fn trust<'a>(&self, qcs: impl Iterator<Item = &'a QuorumCert>) {
+        self.trusted
+            .lock()
+            .unwrap()
+            .extend(qcs.map(QuorumCert::certified_data_digest));
+    }
+
+    pub fn is_trusted(&self, qc: &QuorumCert) -> bool {
+        self.trusted
+            .lock()
+            .unwrap()
+            .contains(&qc.certified_data_digest())
+    }
+
+    fn insert(&self, digest: HashValue) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut inner = self.inner.lock().unwrap();
+        if !inner.digests.insert(digest) {
+            return;
+        }
+        inner.order.push_back(digest);
+        while inner.order.len() > self.capacity {
+            if let Some(evicted) = inner.order.pop_front() {
+                inner.digests.remove(&evicted);
+            }
+        }
+    }
 }
 
 impl IntoProto for QuorumCert {
@@ -160,6 +314,7 @@ impl IntoProto for QuorumCert {
         let mut proto = Self::ProtoType::new();
         proto.set_vote_data(self.vote_data.into_proto());
         proto.set_signed_ledger_info(self.signed_ledger_info.into_proto());
+        proto.set_signature_scheme(self.signature_scheme.id());
         proto
     }
 }
@@ -171,10 +326,12 @@ impl FromProto for QuorumCert {
         let vote_data = VoteData::from_proto(object.take_vote_data())?;
         let signed_ledger_info =
             LedgerInfoWithSignatures::from_proto(object.take_signed_ledger_info())?;
+        let signature_scheme = signature_scheme_from_proto(object.get_signature_scheme())?;
 
         Ok(QuorumCert {
             vote_data,
             signed_ledger_info,
+            signature_scheme,
         })
     }
 }
@@ -0,0 +1,130 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::chained_bft::{
+    consensus_types::quorum_cert::{QuorumCert, VerifiedQcCache},
+    test_utils::placeholder_certificate_for_block,
+};
+use crypto::HashValue;
+use proto_conv::{FromProto, IntoProto};
+use types::crypto_proxies::{ValidatorSigner, ValidatorVerifier, SIGNATURE_SCHEME};
+
+fn certificate_for_round(signers: Vec<&ValidatorSigner>, round: u64) -> QuorumCert {
+    placeholder_certificate_for_block(
+        signers,
+        HashValue::random(),
+        round,
+        HashValue::random(),
+        round - 1,
+        HashValue::random(),
+        round - 2,
+    )
+}
+
+#[test]
+fn test_qc_digest() {
+    let signer_1 = ValidatorSigner::random([0u8; 32]);
+    let signer_2 = ValidatorSigner::random([1u8; 32]);
+    let block_id = HashValue::random();
+    let parent_id = HashValue::random();
+    let qc = placeholder_certificate_for_block(
+        vec![&signer_1, &signer_2],
+        block_id,
+        2,
+        parent_id,
+        1,
+        parent_id,
+        1,
+    );
+    assert_eq!(qc.digest(), qc.clone().digest());
+
+    // The same block certified by a subset of the signers has a different digest.
+    let partial_qc =
+        placeholder_certificate_for_block(vec![&signer_1], block_id, 2, parent_id, 1, parent_id, 1);
+    assert_eq!(qc.certified_block_id(), partial_qc.certified_block_id());
+    assert_ne!(qc.digest(), partial_qc.digest());
+}
+
+#[test]
+fn test_verify_with_cache() {
+    let signer = ValidatorSigner::random(None);
+    let validator = ValidatorVerifier::new_single(signer.author(), signer.public_key());
+    let cache = VerifiedQcCache::new(2);
+
+    let qc = certificate_for_round(vec![&signer], 2);
+    assert!(qc.verify_with_cache(&validator, &cache).is_ok());
+    assert!(cache.contains(&qc.digest()));
+    // A cached QC is accepted again.
+    assert!(qc.verify_with_cache(&validator, &cache).is_ok());
+    assert_eq!(cache.len(), 1);
+
+    // A QC that fails verification is not cached.
+    let other_signer = ValidatorSigner::random(None);
+    let invalid_qc = certificate_for_round(vec![&other_signer], 3);
+    assert!(invalid_qc.verify_with_cache(&validator, &cache).is_err());
+    assert!(!cache.contains(&invalid_qc.digest()));
+    assert!(invalid_qc.verify_with_cache(&validator, &cache).is_err());
+
+    // The oldest digests are evicted first.
+    let qc_3 = certificate_for_round(vec![&signer], 3);
+    let qc_4 = certificate_for_round(vec![&signer], 4);
+    assert!(qc_3.verify_with_cache(&validator, &cache).is_ok());
+    assert!(qc_4.verify_with_cache(&validator, &cache).is_ok());
+    assert_eq!(cache.len(), 2);
+    assert!(!cache.contains(&qc.digest()));
+    assert!(cache.contains(&qc_3.digest()));
+    assert!(cache.contains(&qc_4.digest()));
+}
+
+#[test]
+fn test_trusted_qc() {
+    let old_signer = ValidatorSigner::random([0u8; 32]);
+    let new_signer = ValidatorSigner::random([1u8; 32]);
+    let validator = ValidatorVerifier::new_single(new_signer.author(), new_signer.public_key());
+    let cache = VerifiedQcCache::new(2);
+
+    // The QCs of the previous validator set are rejected unless their data is trusted.
+    let qc = certificate_for_round(vec![&old_signer], 2);
+    assert!(qc.verify_with_cache(&validator, &cache).is_err());
+    cache.trust(vec![&qc].into_iter());
+    assert!(cache.is_trusted(&qc));
+    assert!(qc.verify_with_cache(&validator, &cache).is_ok());
+    let other_qc = certificate_for_round(vec![&old_signer], 3);
+    assert!(other_qc.verify_with_cache(&validator, &cache).is_err());
+
+    cache.clear();
+    assert!(!cache.is_trusted(&qc));
+    assert!(qc.verify_with_cache(&validator, &cache).is_err());
+}
+
+#[test]
+fn test_qc_signature_scheme() {
+    let signer = ValidatorSigner::random([0u8; 32]);
+    let qc = certificate_for_round(vec![&signer], 2);
+    assert_eq!(qc.signature_scheme(), SIGNATURE_SCHEME);
+    let proto = qc.clone().into_proto();
+    assert_eq!(proto.get_signature_scheme(), SIGNATURE_SCHEME.id());
+    assert_eq!(QuorumCert::from_proto(proto.clone()).unwrap(), qc);
+
+    // The QCs signed with a scheme unknown to this validator are rejected.
+    let mut unknown_scheme = proto;
+    unknown_scheme.set_signature_scheme(u32::max_value());
+    assert!(QuorumCert::from_proto(unknown_scheme).is_err());
+}
+
+#[test]
+fn test_qc_aggregate_signatures() {
+    let signer = ValidatorSigner::random([0u8; 32]);
+    let validator = ValidatorVerifier::new_single(signer.author(), signer.public_key());
+    let mut qc = certificate_for_round(vec![&signer], 2);
+    let digest = qc.digest();
+
+    // Ed25519 signatures can't be aggregated: the QC keeps the individual signatures of the
+    // voters.
+    assert!(!SIGNATURE_SCHEME.supports_aggregation());
+    assert!(qc.aggregate_signatures(&validator).is_err());
+    assert!(qc.ledger_info().aggregated_signature().is_none());
+    assert_eq!(qc.ledger_info().signatures().len(), 1);
+    assert_eq!(qc.digest(), digest);
+    assert!(qc.verify(&validator).is_ok());
+}
@@ -1,13 +1,14 @@
 use crate::chained_bft::consensus_types::{
-    quorum_cert::QuorumCert, timeout_msg::PacemakerTimeoutCertificate,
+    quorum_cert::{QuorumCert, VerifiedQcCache},
+    timeout_certificate::TimeoutCertificate,
 };
 use network;
 
 use crate::chained_bft::{
     common::Round,
     consensus_types::{
-        timeout_msg::PacemakerTimeoutCertificateVerificationError,
-        vote_msg::VoteMsgVerificationError,
+        timeout_certificate::TimeoutCertificateVerificationError,
+        vote_msg::{VoteMsg, VoteMsgVerificationError},
     },
 };
 use proto_conv::{FromProto, IntoProto};
@@ -23,7 +24,9 @@ pub struct SyncInfo {
     /// Highest ledger info known to the peer.
     highest_ledger_info: QuorumCert,
     /// Optional highest timeout certificate if available.
-    highest_timeout_cert: Option<PacemakerTimeoutCertificate>,
+    highest_timeout_cert: Option<TimeoutCertificate>,
+    /// Optional last vote of the peer, which might complete a QC at the recipient.
+    last_vote: Option<VoteMsg>,
 }
 
 impl Display for SyncInfo {
@@ -49,7 +52,9 @@ pub enum SyncInfoVerificationError {
     #[fail(display = "QuorumCertificateError: {}", _0)]
     QuorumCertificateError(VoteMsgVerificationError),
     #[fail(display = "TimeoutCertificateError: {}", _0)]
-    TimeoutCertificateError(PacemakerTimeoutCertificateVerificationError),
+    TimeoutCertificateError(TimeoutCertificateVerificationError),
+    #[fail(display = "LastVoteError: {}", _0)]
+    LastVoteError(VoteMsgVerificationError),
 }
 
 impl From<VoteMsgVerificationError> for SyncInfoVerificationError {
@@ -58,8 +63,8 @@ impl From<VoteMsgVerificationError> for SyncInfoVerificationError {
     }
 }
 
-impl From<PacemakerTimeoutCertificateVerificationError> for SyncInfoVerificationError {
-    fn from(source: PacemakerTimeoutCertificateVerificationError) -> Self {
+impl From<TimeoutCertificateVerificationError> for SyncInfoVerificationError {
+    fn from(source: TimeoutCertificateVerificationError) -> Self {
         SyncInfoVerificationError::TimeoutCertificateError(source)
     }
 }
@@ -68,15 +73,22 @@ impl SyncInfo {
     pub fn new(
         highest_quorum_cert: QuorumCert,
         highest_ledger_info: QuorumCert,
-        highest_timeout_cert: Option<PacemakerTimeoutCertificate>,
+        highest_timeout_cert: Option<TimeoutCertificate>,
     ) -> Self {
         Self {
             highest_quorum_cert,
             highest_ledger_info,
             highest_timeout_cert,
+            last_vote: None,
         }
     }
 
+    /// Piggybacks the last vote of the sender.
+    pub fn with_last_vote(mut self, last_vote: VoteMsg) -> Self {
+        self.last_vote = Some(last_vote);
+        self
+    }
+
     /// Highest quorum certificate
     pub fn highest_quorum_cert(&self) -> &QuorumCert {
         &self.highest_quorum_cert
@@ -88,10 +100,15 @@ impl SyncInfo {
     }
 
     /// Highest timeout certificate if available
-    pub fn highest_timeout_certificate(&self) -> Option<&PacemakerTimeoutCertificate> {
+    pub fn highest_timeout_certificate(&self) -> Option<&TimeoutCertificate> {
         self.highest_timeout_cert.as_ref()
     }
 
+    /// Last vote of the sender if piggybacked
+    pub fn last_vote(&self) -> Option<&VoteMsg> {
+        self.last_vote.as_ref()
+    }
+
     pub fn hqc_round(&self) -> Round {
         self.highest_quorum_cert.certified_block_round()
     }
@@ -106,12 +123,22 @@ impl SyncInfo {
         std::cmp::max(self.hqc_round(), self.htc_round())
     }
 
-    pub fn verify(&self, validator: &ValidatorVerifier) -> Result<(), SyncInfoVerificationError> {
-        self.highest_quorum_cert.verify(validator)?;
-        self.highest_ledger_info.verify(validator)?;
+    pub fn verify(
+        &self,
+        validator: &ValidatorVerifier,
+        qc_cache: &VerifiedQcCache,
+    ) -> Result<(), SyncInfoVerificationError> {
+        self.highest_quorum_cert
+            .verify_with_cache(validator, qc_cache)?;
+        self.highest_ledger_info
+            .verify_with_cache(validator, qc_cache)?;
         if let Some(tc) = &self.highest_timeout_cert {
             tc.verify(validator)?;
         }
+        if let Some(vote) = &self.last_vote {
+            vote.verify(validator)
+                .map_err(SyncInfoVerificationError::LastVoteError)?;
+        }
         Ok(())
     }
 }
@@ -123,15 +150,19 @@ impl FromProto for SyncInfo {
         let highest_quorum_cert = QuorumCert::from_proto(object.take_highest_quorum_cert())?;
         let highest_ledger_info = QuorumCert::from_proto(object.take_highest_ledger_info())?;
         let highest_timeout_cert = if let Some(tc) = object.highest_timeout_cert.into_option() {
-            Some(PacemakerTimeoutCertificate::from_proto(tc)?)
+            Some(TimeoutCertificate::from_proto(tc)?)
         } else {
             None
         };
-        Ok(SyncInfo::new(
+        let sync_info = SyncInfo::new(
             highest_quorum_cert,
             highest_ledger_info,
             highest_timeout_cert,
-        ))
+        );
+        Ok(match object.last_vote.into_option() {
+            Some(vote) => sync_info.with_last_vote(VoteMsg::from_proto(vote)?),
+            None => sync_info,
+        })
     }
 }
 impl IntoProto for SyncInfo {
@@ -144,6 +175,9 @@ impl IntoProto for SyncInfo {
         if let Some(tc) = self.highest_timeout_cert {
             proto.set_highest_timeout_cert(tc.into_proto());
         }
+        if let Some(vote) = self.last_vote {
+            proto.set_last_vote(vote.into_proto());
+        }
         proto
     }
 }
@@ -0,0 +1,142 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::chained_bft::{
+    common::{Author, Round},
+    consensus_types::{
+        timeout_certificate::TimeoutCertificateVerificationError::*, timeout_msg::PacemakerTimeout,
+    },
+};
+use mirai_annotations::assumed_postcondition;
+use network;
+use proto_conv::{FromProto, IntoProto};
+use protobuf::RepeatedField;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashSet, fmt, iter::FromIterator};
+use types::{crypto_proxies::ValidatorVerifier, validator_verifier::VerifyError};
+
+#[cfg(test)]
+#[path = "timeout_certificate_test.rs"]
+pub mod timeout_certificate_test;
+
+/// The timeouts of a quorum of validators, the round of the certificate is the lowest round of
+/// the timeouts. Proposal can include this timeout certificate as justification for switching to
+/// next round, and a replica receiving it advances to the round after the certified round.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct TimeoutCertificate {
+    round: Round,
+    timeouts: Vec<PacemakerTimeout>,
+}
+
+/// TimeoutCertificate verification errors.
+#[derive(Debug, PartialEq, Fail)]
+pub enum TimeoutCertificateVerificationError {
+    /// Number of signed timeouts is less then required quorum size
+    #[fail(display = "NoQuorum")]
+    NoQuorum,
+    /// Round in message does not match calculated rounds based on signed timeouts
+    #[fail(display = "RoundMismatch {}", expected)]
+    RoundMismatch { expected: Round },
+    /// The signature on one of timeouts doesn't pass verification
+    #[fail(display = "SigVerifyError for {}: {}", _0, _1)]
+    SigVerifyError(Author, VerifyError),
+}
+
+impl fmt::Display for TimeoutCertificate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "TimeoutCertificate[round: {}, timeouts:[", self.round)?;
+        for (idx, timeout) in self.timeouts.iter().enumerate() {
+            write!(f, "<{}>", timeout.round())?;
+            if idx != self.timeouts.len() - 1 {
+                write!(f, ", ")?;
+            }
+        }
+        write!(f, "]")
+    }
+}
+
+impl TimeoutCertificate {
+    /// Creates new TimeoutCertificate. Only the signed part of the timeouts is kept: the votes
+    /// they carry are not needed to certify the round.
+    pub fn new(round: Round, timeouts: Vec<PacemakerTimeout>) -> TimeoutCertificate {
+        TimeoutCertificate {
+            round,
+            timeouts: timeouts
+                .into_iter()
+                .map(PacemakerTimeout::without_vote)
+                .collect(),
+        }
+    }
+
+    /// Verifies that timeouts in message actually certify the round
+    pub fn verify(
+        &self,
+        validator: &ValidatorVerifier,
+    ) -> Result<(), TimeoutCertificateVerificationError> {
+        let mut min_round: Option<Round> = None;
+        let mut unique_authors = HashSet::new();
+        for timeout in &self.timeouts {
+            if let Err(e) = timeout.signature().verify(
+                &validator.at_round(timeout.round()),
+                timeout.author(),
+                timeout.digest(),
+            ) {
+                return Err(SigVerifyError(timeout.author(), e));
+            }
+            unique_authors.insert(timeout.author());
+            let timeout_round = timeout.round();
+            min_round = Some(min_round.map_or(timeout_round, move |x| x.min(timeout_round)))
+        }
+        if unique_authors.len() < validator.quorum_size() {
+            return Err(NoQuorum);
+        }
+        if min_round == Some(self.round) {
+            Ok(())
+        } else {
+            Err(RoundMismatch {
+                expected: min_round.unwrap_or(0),
+            })
+        }
+    }
+
+    /// Returns the round of the timeout
+    pub fn round(&self) -> Round {
+        // Round numbers:
+        // - are reset to 0 periodically.
+        // - do not exceed std::u64::MAX - 2 per the 3 chain safety rule
+        // (ConsensusState::commit_rule_for_certified_block)
+        assumed_postcondition!(self.round < std::u64::MAX - 1);
+        self.round
+    }
+
+    /// Returns the timeouts that certify the TimeoutCertificate
+    pub fn timeouts(&self) -> &Vec<PacemakerTimeout> {
+        &self.timeouts
+    }
+}
+
+impl IntoProto for TimeoutCertificate {
+    type ProtoType = network::proto::PacemakerTimeoutCertificate;
+
+    fn into_proto(self) -> Self::ProtoType {
+        let mut proto = Self::ProtoType::new();
+        proto.set_timeouts(RepeatedField::from_iter(
+            self.timeouts.into_iter().map(PacemakerTimeout::into_proto),
+        ));
+        proto.set_round(self.round);
+        proto
+    }
+}
+
+impl FromProto for TimeoutCertificate {
+    type ProtoType = network::proto::PacemakerTimeoutCertificate;
+
+    fn from_proto(mut object: Self::ProtoType) -> failure::Result<Self> {
+        let timeouts = object
+            .take_timeouts()
+            .into_iter()
+            .map(PacemakerTimeout::from_proto)
+            .collect::<failure::Result<Vec<_>>>()?;
+        Ok(TimeoutCertificate::new(object.get_round(), timeouts))
+    }
+}
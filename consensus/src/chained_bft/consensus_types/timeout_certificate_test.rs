@@ -0,0 +1,69 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::chained_bft::{
+    common::Round,
+    consensus_types::{
+        timeout_certificate::{TimeoutCertificate, TimeoutCertificateVerificationError},
+        timeout_msg::PacemakerTimeout,
+        vote_data::VoteData,
+        vote_msg::VoteMsg,
+    },
+    test_utils::placeholder_ledger_info,
+};
+use crypto::HashValue;
+use executor::ExecutedState;
+use proto_conv::{FromProto, IntoProto};
+use types::crypto_proxies::{ValidatorSigner, ValidatorVerifier};
+
+fn timeout_with_vote(round: Round, signer: &ValidatorSigner) -> PacemakerTimeout {
+    let vote = VoteMsg::new(
+        VoteData::new(
+            HashValue::random(),
+            ExecutedState::state_for_genesis().state_id,
+            round,
+            HashValue::random(),
+            round - 1,
+            HashValue::random(),
+            round - 2,
+        ),
+        signer.author(),
+        placeholder_ledger_info(),
+        signer,
+    );
+    PacemakerTimeout::new(round, signer, Some(vote))
+}
+
+#[test]
+fn test_timeout_certificate() {
+    let signers: Vec<_> = (0..4).map(|i| ValidatorSigner::random([i; 32])).collect();
+    let validator = ValidatorVerifier::new(
+        signers
+            .iter()
+            .map(|signer| (signer.author(), signer.public_key()))
+            .collect(),
+    );
+    let timeouts: Vec<_> = signers
+        .iter()
+        .zip(vec![5, 6, 7])
+        .map(|(signer, round)| timeout_with_vote(round, signer))
+        .collect();
+
+    // The votes are dropped while the signatures of the timeouts still verify.
+    let tc = TimeoutCertificate::new(5, timeouts.clone());
+    assert!(tc.timeouts().iter().all(|t| t.vote_msg().is_none()));
+    assert_eq!(tc.verify(&validator), Ok(()));
+    assert_eq!(
+        TimeoutCertificate::from_proto(tc.clone().into_proto()).unwrap(),
+        tc
+    );
+
+    assert_eq!(
+        TimeoutCertificate::new(6, timeouts.clone()).verify(&validator),
+        Err(TimeoutCertificateVerificationError::RoundMismatch { expected: 5 })
+    );
+    assert_eq!(
+        TimeoutCertificate::new(5, timeouts[..2].to_vec()).verify(&validator),
+        Err(TimeoutCertificateVerificationError::NoQuorum)
+    );
+}
@@ -5,7 +5,6 @@ use crate::chained_bft::{
     common::{Author, Round},
     consensus_types::{
         sync_info::SyncInfo,
-        timeout_msg::PacemakerTimeoutCertificateVerificationError::*,
         vote_msg::{VoteMsg, VoteMsgVerificationError},
     },
 };
@@ -14,12 +13,10 @@ use crypto::{
     hash::{CryptoHash, CryptoHasher, PacemakerTimeoutHasher, TimeoutMsgHasher},
     HashValue,
 };
-use mirai_annotations::assumed_postcondition;
 use network;
 use proto_conv::{FromProto, IntoProto};
-use protobuf::RepeatedField;
 use serde::{Deserialize, Serialize};
-use std::{collections::HashSet, convert::TryFrom, fmt, iter::FromIterator};
+use std::{convert::TryFrom, fmt};
 use types::{
     account_address::AccountAddress,
     crypto_proxies::{Signature, ValidatorSigner, ValidatorVerifier},
@@ -31,12 +28,14 @@ use types::{
 struct PacemakerTimeoutSerializer {
     round: Round,
     author: Author,
+    reason: TimeoutReason,
 }
 
 impl CanonicalSerialize for PacemakerTimeoutSerializer {
     fn serialize(&self, serializer: &mut impl CanonicalSerializer) -> failure::Result<()> {
         serializer.encode_u64(self.round)?;
         serializer.encode_struct(&self.author)?;
+        serializer.encode_u32(self.reason.into_proto() as u32)?;
         Ok(())
     }
 }
@@ -62,6 +61,76 @@ pub enum TimeoutVerificationError {
     SigVerifyError(VerifyError),
 }
 
+/// The reason a validator timed out in a round. The reason is meant for diagnostics only (e.g.,
+/// to see why the rounds of a stalled network time out), it doesn't affect the protocol.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TimeoutReason {
+    /// The author doesn't report the reason.
+    Unknown,
+    /// No proposal was received in the round.
+    NoProposal,
+    /// A proposal was received but not voted for (e.g., it was sent by an invalid proposer or it
+    /// was rejected by the safety rules).
+    InvalidProposal,
+    /// The author voted but no quorum certificate was formed in time.
+    NoQuorumCert,
+    /// The execution of the proposed block failed.
+    ExecutorStalled,
+}
+
+impl TimeoutReason {
+    /// The snake case name of the reason, as used by the counters.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TimeoutReason::Unknown => "unknown",
+            TimeoutReason::NoProposal => "no_proposal",
+            TimeoutReason::InvalidProposal => "invalid_proposal",
+            TimeoutReason::NoQuorumCert => "no_quorum_cert",
+            TimeoutReason::ExecutorStalled => "executor_stalled",
+        }
+    }
+}
+
+impl Default for TimeoutReason {
+    fn default() -> Self {
+        TimeoutReason::Unknown
+    }
+}
+
+impl fmt::Display for TimeoutReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl IntoProto for TimeoutReason {
+    type ProtoType = network::proto::TimeoutReason;
+
+    fn into_proto(self) -> Self::ProtoType {
+        match self {
+            TimeoutReason::Unknown => network::proto::TimeoutReason::UNKNOWN,
+            TimeoutReason::NoProposal => network::proto::TimeoutReason::NO_PROPOSAL,
+            TimeoutReason::InvalidProposal => network::proto::TimeoutReason::INVALID_PROPOSAL,
+            TimeoutReason::NoQuorumCert => network::proto::TimeoutReason::NO_QUORUM_CERT,
+            TimeoutReason::ExecutorStalled => network::proto::TimeoutReason::EXECUTOR_STALLED,
+        }
+    }
+}
+
+impl FromProto for TimeoutReason {
+    type ProtoType = network::proto::TimeoutReason;
+
+    fn from_proto(object: Self::ProtoType) -> failure::Result<Self> {
+        Ok(match object {
+            network::proto::TimeoutReason::UNKNOWN => TimeoutReason::Unknown,
+            network::proto::TimeoutReason::NO_PROPOSAL => TimeoutReason::NoProposal,
+            network::proto::TimeoutReason::INVALID_PROPOSAL => TimeoutReason::InvalidProposal,
+            network::proto::TimeoutReason::NO_QUORUM_CERT => TimeoutReason::NoQuorumCert,
+            network::proto::TimeoutReason::EXECUTOR_STALLED => TimeoutReason::ExecutorStalled,
+        })
+    }
+}
+
 /// This message will be broadcast by a pacemaker as part of TimeoutMsg when its local
 /// timeout for a round is reached.  Once f+1 PacemakerTimeout structs
 /// from unique authors is gathered it forms a TimeoutCertificate.  A TimeoutCertificate is
@@ -72,13 +141,25 @@ pub struct PacemakerTimeout {
     author: Author,
     signature: Signature,
     vote: Option<VoteMsg>,
+    #[serde(default)]
+    reason: TimeoutReason,
 }
 
 impl PacemakerTimeout {
     /// Creates new PacemakerTimeout
     pub fn new(round: Round, validator_signer: &ValidatorSigner, vote: Option<VoteMsg>) -> Self {
+        Self::new_with_reason(round, validator_signer, vote, TimeoutReason::Unknown)
+    }
+
+    /// Creates new PacemakerTimeout reporting why the author timed out
+    pub fn new_with_reason(
+        round: Round,
+        validator_signer: &ValidatorSigner,
+        vote: Option<VoteMsg>,
+        reason: TimeoutReason,
+    ) -> Self {
         let author = validator_signer.author();
-        let digest = PacemakerTimeoutSerializer { round, author }.hash();
+        let digest = Self::pacemaker_timeout_digest(author, round, reason);
         let signature = validator_signer
             .sign_message(digest)
             .expect("Failed to sign PacemakerTimeout");
@@ -87,16 +168,26 @@ impl PacemakerTimeout {
             author,
             signature: signature.into(),
             vote,
+            reason,
         }
     }
 
-    fn pacemaker_timeout_digest(author: AccountAddress, round: Round) -> HashValue {
-        PacemakerTimeoutSerializer { round, author }.hash()
+    fn pacemaker_timeout_digest(
+        author: AccountAddress,
+        round: Round,
+        reason: TimeoutReason,
+    ) -> HashValue {
+        PacemakerTimeoutSerializer {
+            round,
+            author,
+            reason,
+        }
+        .hash()
     }
 
     /// Calculates digest for this struct
     pub fn digest(&self) -> HashValue {
-        Self::pacemaker_timeout_digest(self.author, self.round)
+        Self::pacemaker_timeout_digest(self.author, self.round, self.reason)
     }
 
     pub fn round(&self) -> Round {
@@ -110,7 +201,7 @@ impl PacemakerTimeout {
     /// Verifies that this message has valid signature
     pub fn verify(&self, validator: &ValidatorVerifier) -> Result<(), TimeoutVerificationError> {
         self.signature
-            .verify(validator, self.author, self.digest())
+            .verify(&validator.at_round(self.round), self.author, self.digest())
             .map_err(TimeoutVerificationError::SigVerifyError)?;
         if let Some(vote) = self.vote.as_ref() {
             vote.verify(validator)
@@ -124,10 +215,20 @@ impl PacemakerTimeout {
         self.author
     }
 
+    /// Returns the reason the author timed out
+    pub fn reason(&self) -> TimeoutReason {
+        self.reason
+    }
+
     /// Returns the signature of the author for this timeout
     pub fn signature(&self) -> &Signature {
         &self.signature
     }
+
+    /// Drops the vote carried by the timeout, which is not covered by the signature.
+    pub fn without_vote(self) -> Self {
+        Self { vote: None, ..self }
+    }
 }
 
 impl IntoProto for PacemakerTimeout {
@@ -141,6 +242,7 @@ impl IntoProto for PacemakerTimeout {
         if let Some(vote) = self.vote {
             proto.set_vote(vote.into_proto());
         }
+        proto.set_reason(self.reason.into_proto());
         proto
     }
 }
@@ -157,11 +259,13 @@ impl FromProto for PacemakerTimeout {
         } else {
             None
         };
+        let reason = TimeoutReason::from_proto(object.get_reason())?;
         Ok(PacemakerTimeout {
             round,
             author,
             signature,
             vote,
+            reason,
         })
     }
 }
@@ -251,6 +355,11 @@ impl TimeoutMsg {
         self.pacemaker_timeout.author()
     }
 
+    /// Returns the reason the author timed out
+    pub fn reason(&self) -> TimeoutReason {
+        self.pacemaker_timeout.reason()
+    }
+
     /// Returns a reference to the signature of the author
     #[allow(dead_code)]
     pub fn signature(&self) -> &Signature {
@@ -284,119 +393,3 @@ impl IntoProto for TimeoutMsg {
         proto
     }
 }
-
-#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
-/// Proposal can include this timeout certificate as justification for switching to next round
-pub struct PacemakerTimeoutCertificate {
-    round: Round,
-    timeouts: Vec<PacemakerTimeout>,
-}
-
-/// PacemakerTimeoutCertificate verification errors.
-#[derive(Debug, PartialEq, Fail)]
-pub enum PacemakerTimeoutCertificateVerificationError {
-    /// Number of signed timeouts is less then required quorum size
-    #[fail(display = "NoQuorum")]
-    NoQuorum,
-    /// Round in message does not match calculated rounds based on signed timeouts
-    #[fail(display = "RoundMismatch {}", expected)]
-    RoundMismatch { expected: Round },
-    /// The signature on one of timeouts doesn't pass verification
-    #[fail(display = "SigVerifyError for {}: {}", _0, _1)]
-    SigVerifyError(Author, VerifyError),
-}
-
-impl fmt::Display for PacemakerTimeoutCertificate {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "TimeoutCertificate[round: {}, timeouts:[", self.round)?;
-        for (idx, timeout) in self.timeouts.iter().enumerate() {
-            write!(f, "<{}>", timeout.round())?;
-            if idx != self.timeouts.len() - 1 {
-                write!(f, ", ")?;
-            }
-        }
-        write!(f, "]")
-    }
-}
-
-impl PacemakerTimeoutCertificate {
-    /// Creates new PacemakerTimeoutCertificate
-    pub fn new(round: Round, timeouts: Vec<PacemakerTimeout>) -> PacemakerTimeoutCertificate {
-        PacemakerTimeoutCertificate { round, timeouts }
-    }
-
-    /// Verifies that timeouts in message actually certify the round
-    pub fn verify(
-        &self,
-        validator: &ValidatorVerifier,
-    ) -> Result<(), PacemakerTimeoutCertificateVerificationError> {
-        let mut min_round: Option<Round> = None;
-        let mut unique_authors = HashSet::new();
-        for timeout in &self.timeouts {
-            if let Err(e) =
-                timeout
-                    .signature()
-                    .verify(validator, timeout.author(), timeout.digest())
-            {
-                return Err(SigVerifyError(timeout.author(), e));
-            }
-            unique_authors.insert(timeout.author());
-            let timeout_round = timeout.round();
-            min_round = Some(min_round.map_or(timeout_round, move |x| x.min(timeout_round)))
-        }
-        if unique_authors.len() < validator.quorum_size() {
-            return Err(NoQuorum);
-        }
-        if min_round == Some(self.round) {
-            Ok(())
-        } else {
-            Err(RoundMismatch {
-                expected: min_round.unwrap_or(0),
-            })
-        }
-    }
-
-    /// Returns the round of the timeout
-    pub fn round(&self) -> Round {
-        // Round numbers:
-        // - are reset to 0 periodically.
-        // - do not exceed std::u64::MAX - 2 per the 3 chain safety rule
-        // (ConsensusState::commit_rule_for_certified_block)
-        assumed_postcondition!(self.round < std::u64::MAX - 1);
-        self.round
-    }
-
-    /// Returns the timeouts that certify the PacemakerTimeoutCertificate
-    pub fn timeouts(&self) -> &Vec<PacemakerTimeout> {
-        &self.timeouts
-    }
-}
-
-impl IntoProto for PacemakerTimeoutCertificate {
-    type ProtoType = network::proto::PacemakerTimeoutCertificate;
-
-    fn into_proto(self) -> Self::ProtoType {
-        let mut proto = Self::ProtoType::new();
-        proto.set_timeouts(RepeatedField::from_iter(
-            self.timeouts.into_iter().map(PacemakerTimeout::into_proto),
-        ));
-        proto.set_round(self.round);
-        proto
-    }
-}
-
-impl FromProto for PacemakerTimeoutCertificate {
-    type ProtoType = network::proto::PacemakerTimeoutCertificate;
-
-    fn from_proto(mut object: Self::ProtoType) -> failure::Result<Self> {
-        let timeouts = object
-            .take_timeouts()
-            .into_iter()
-            .map(PacemakerTimeout::from_proto)
-            .collect::<failure::Result<Vec<_>>>()?;
-        Ok(PacemakerTimeoutCertificate::new(
-            object.get_round(),
-            timeouts,
-        ))
-    }
-}
@@ -1,18 +1,25 @@
 // Copyright (c) The Libra Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::chained_bft::{common::Author, consensus_types::vote_data::VoteData};
-use crypto::hash::CryptoHash;
+use crate::chained_bft::{
+    common::{Author, Round},
+    consensus_types::vote_data::VoteData,
+};
+use crypto::{hash::CryptoHash, HashValue};
 use failure::Result as ProtoResult;
 use network::proto::Vote as ProtoVote;
 use proto_conv::{FromProto, IntoProto};
 use serde::{Deserialize, Serialize};
 use std::{
+    collections::HashMap,
     convert::TryFrom,
     fmt::{Display, Formatter},
 };
 use types::{
-    crypto_proxies::{Signature, ValidatorSigner, ValidatorVerifier},
+    crypto_proxies::{
+        LedgerInfoWithSignatures, Signature, SignatureScheme, ValidatorSigner, ValidatorVerifier,
+        SIGNATURE_SCHEME,
+    },
     ledger_info::LedgerInfo,
     validator_verifier::VerifyError,
 };
@@ -26,6 +33,60 @@ pub enum VoteMsgVerificationError {
     /// The signature doesn't pass verification
     #[fail(display = "SigVerifyError: {}", _0)]
     SigVerifyError(VerifyError),
+    /// The message is signed with a scheme other than the one of the validator set.
+    #[fail(display = "SignatureSchemeMismatch: {}", _0)]
+    SignatureSchemeMismatch(SignatureScheme),
+}
+
+/// Checks that a message signed with the given scheme can be verified by the validator set.
+pub(crate) fn check_signature_scheme(
+    scheme: SignatureScheme,
+) -> Result<(), VoteMsgVerificationError> {
+    if scheme != SIGNATURE_SCHEME {
+        return Err(VoteMsgVerificationError::SignatureSchemeMismatch(scheme));
+    }
+    Ok(())
+}
+
+/// Decodes the signature scheme identifier of a message.
+pub(crate) fn signature_scheme_from_proto(id: u32) -> ProtoResult<SignatureScheme> {
+    SignatureScheme::from_id(id).ok_or_else(|| format_err!("Unknown signature scheme {}", id))
+}
+
+/// Verifies the given votes like `VoteMsg::verify`, except that the signatures over the same
+/// ledger info are verified in batch. Returns the error of the first invalid vote found.
+pub fn verify_votes<'a>(
+    votes: impl IntoIterator<Item = &'a VoteMsg>,
+    validator: &ValidatorVerifier,
+) -> Result<(), VoteMsgVerificationError> {
+    // The ledger info covers the vote data, hence the round of the votes of a batch.
+    let mut digest_to_signatures: HashMap<HashValue, (Round, LedgerInfoWithSignatures)> =
+        HashMap::new();
+    for vote in votes {
+        vote.verify_without_signature(validator)?;
+        let digest = vote.ledger_info().hash();
+        let (_, li_with_sig) = digest_to_signatures.entry(digest).or_insert_with(|| {
+            (
+                vote.vote_data().block_round(),
+                LedgerInfoWithSignatures::new(vote.ledger_info().clone(), HashMap::new()),
+            )
+        });
+        if li_with_sig.signatures().contains_key(&vote.author()) {
+            // A batch holds a single signature per author.
+            vote.verify(validator)?;
+        } else {
+            vote.signature()
+                .clone()
+                .add_to_li(vote.author(), li_with_sig);
+        }
+    }
+    for (digest, (round, li_with_sig)) in digest_to_signatures {
+        validator
+            .at_round(round)
+            .batch_verify(digest, li_with_sig.signatures())
+            .map_err(VoteMsgVerificationError::SigVerifyError)?;
+    }
+    Ok(())
 }
 
 /// VoteMsg is the struct that is ultimately sent by the voter in response for
@@ -42,6 +103,9 @@ pub struct VoteMsg {
     ledger_info: LedgerInfo,
     /// Signature of the LedgerInfo
     signature: Signature,
+    /// The scheme of the signature
+    #[serde(default)]
+    signature_scheme: SignatureScheme,
 }
 
 impl Display for VoteMsg {
@@ -72,6 +136,7 @@ impl VoteMsg {
             author,
             ledger_info: ledger_info_placeholder,
             signature: li_sig.into(),
+            signature_scheme: SIGNATURE_SCHEME,
         }
     }
 
@@ -94,16 +159,44 @@ impl VoteMsg {
         &self.signature
     }
 
+    /// Return the scheme of the signature of the vote
+    pub fn signature_scheme(&self) -> SignatureScheme {
+        self.signature_scheme
+    }
+
     /// Verifies that the consensus data hash of LedgerInfo corresponds to the vote info,
     /// and then verifies the signature.
     pub fn verify(&self, validator: &ValidatorVerifier) -> Result<(), VoteMsgVerificationError> {
         if self.ledger_info.consensus_data_hash() != self.vote_data.hash() {
             return Err(VoteMsgVerificationError::ConsensusDataMismatch);
         }
+        check_signature_scheme(self.signature_scheme)?;
         self.signature()
-            .verify(validator, self.author(), self.ledger_info.hash())
+            .verify(
+                &validator.at_round(self.vote_data.block_round()),
+                self.author(),
+                self.ledger_info.hash(),
+            )
             .map_err(VoteMsgVerificationError::SigVerifyError)
     }
+
+    /// Performs the checks of `verify` except for the signature verification: the signatures of
+    /// the votes are verified in batch when a quorum of votes is aggregated.
+    pub fn verify_without_signature(
+        &self,
+        validator: &ValidatorVerifier,
+    ) -> Result<(), VoteMsgVerificationError> {
+        if self.ledger_info.consensus_data_hash() != self.vote_data.hash() {
+            return Err(VoteMsgVerificationError::ConsensusDataMismatch);
+        }
+        check_signature_scheme(self.signature_scheme)?;
+        if validator.get_public_key(self.author()).is_none() {
+            return Err(VoteMsgVerificationError::SigVerifyError(
+                VerifyError::UnknownAuthor,
+            ));
+        }
+        Ok(())
+    }
 }
 
 impl IntoProto for VoteMsg {
@@ -115,6 +208,7 @@ impl IntoProto for VoteMsg {
         proto.set_author(self.author.into());
         proto.set_ledger_info(self.ledger_info.into_proto());
         proto.set_signature(bytes::Bytes::from(self.signature.to_bytes()));
+        proto.set_signature_scheme(self.signature_scheme.id());
         proto
     }
 }
@@ -127,11 +221,13 @@ impl FromProto for VoteMsg {
         let author = Author::try_from(object.take_author())?;
         let ledger_info = LedgerInfo::from_proto(object.take_ledger_info())?;
         let signature = Signature::try_from(object.get_signature())?;
+        let signature_scheme = signature_scheme_from_proto(object.get_signature_scheme())?;
         Ok(VoteMsg {
             vote_data,
             author,
             ledger_info,
             signature,
+            signature_scheme,
         })
     }
 }
@@ -26,6 +26,14 @@ fn test_put_get() {
     assert_eq!(db.get_blocks::<i64>().unwrap().len(), 1);
     assert_eq!(db.get_quorum_certificates().unwrap().len(), 1);
     assert!(!db.get_state().unwrap().is_none());
+
+    assert!(db.get_pending_votes().unwrap().is_none());
+    db.save_pending_votes(vec![0x04, 0x05]).unwrap();
+    assert_eq!(db.get_pending_votes().unwrap(), Some(vec![0x04, 0x05]));
+
+    assert!(db.get_evidence().unwrap().is_none());
+    db.save_evidence(vec![0x06]).unwrap();
+    assert_eq!(db.get_evidence().unwrap(), Some(vec![0x06]));
 }
 
 #[test]
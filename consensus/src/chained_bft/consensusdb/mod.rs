@@ -9,10 +9,9 @@ use crate::chained_bft::{
     common::Payload,
     consensus_types::{block::Block, quorum_cert::QuorumCert},
     consensusdb::schema::{
-        block::BlockSchema,
-        quorum_certificate::QCSchema,
-        single_entry::{SingleEntryKey, SingleEntrySchema},
+        block::BlockSchema, quorum_certificate::QCSchema, single_entry::SingleEntrySchema,
     },
+    persistent_storage::StorageBackend,
 };
 use crypto::HashValue;
 use failure::prelude::*;
@@ -23,8 +22,12 @@ use schemadb::{
 };
 use std::{collections::HashMap, iter::Iterator, path::Path, time::Instant};
 
+pub use schema::single_entry::SingleEntryKey;
+
 type HighestTimeoutCertificates = Vec<u8>;
 type ConsensusStateData = Vec<u8>;
+type PendingVotesData = Vec<u8>;
+type EvidenceData = Vec<u8>;
 
 pub struct ConsensusDB {
     db: DB,
@@ -65,11 +68,13 @@ impl ConsensusDB {
     ) -> Result<(
         Option<ConsensusStateData>,
         Option<HighestTimeoutCertificates>,
+        Option<PendingVotesData>,
         Vec<Block<T>>,
         Vec<QuorumCert>,
     )> {
         let consensus_state = self.get_state()?;
         let highest_timeout_certificates = self.get_highest_timeout_certificates()?;
+        let pending_votes = self.get_pending_votes()?;
         let consensus_blocks = self
             .get_blocks()?
             .into_iter()
@@ -83,6 +88,7 @@ impl ConsensusDB {
         Ok((
             consensus_state,
             highest_timeout_certificates,
+            pending_votes,
             consensus_blocks,
             consensus_qcs,
         ))
@@ -100,6 +106,24 @@ impl ConsensusDB {
         self.commit(batch)
     }
 
+    pub fn save_pending_votes(&self, pending_votes: PendingVotesData) -> Result<()> {
+        let mut batch = SchemaBatch::new();
+        batch.put::<SingleEntrySchema>(&SingleEntryKey::PendingVotes, &pending_votes)?;
+        self.commit(batch)
+    }
+
+    pub fn save_evidence(&self, evidence: EvidenceData) -> Result<()> {
+        let mut batch = SchemaBatch::new();
+        batch.put::<SingleEntrySchema>(&SingleEntryKey::Evidence, &evidence)?;
+        self.commit(batch)
+    }
+
+    /// Get the evidence of the equivocations collected so far (the evidence is stored as a
+    /// whole).
+    pub fn get_evidence(&self) -> Result<Option<EvidenceData>> {
+        self.db.get::<SingleEntrySchema>(&SingleEntryKey::Evidence)
+    }
+
     pub fn save_state(&self, state: ConsensusStateData) -> Result<()> {
         let mut batch = SchemaBatch::new();
         batch.put::<SingleEntrySchema>(&SingleEntryKey::ConsensusState, &state)?;
@@ -155,8 +179,14 @@ impl ConsensusDB {
             .get::<SingleEntrySchema>(&SingleEntryKey::HighestTimeoutCertificates)
     }
 
+    /// Get the votes collected for the latest round (we only store the latest pending votes).
+    fn get_pending_votes(&self) -> Result<Option<Vec<u8>>> {
+        self.db
+            .get::<SingleEntrySchema>(&SingleEntryKey::PendingVotes)
+    }
+
     /// Get latest consensus state (we only store the latest state).
-    fn get_state(&self) -> Result<Option<Vec<u8>>> {
+    pub fn get_state(&self) -> Result<Option<Vec<u8>>> {
         self.db
             .get::<SingleEntrySchema>(&SingleEntryKey::ConsensusState)
     }
@@ -175,3 +205,41 @@ impl ConsensusDB {
         iter.collect::<Result<HashMap<HashValue, QuorumCert>>>()
     }
 }
+
+/// The RocksDB backend of the consensus storage.
+impl<T: Payload> StorageBackend<T> for ConsensusDB {
+    fn save_blocks_and_quorum_certs(
+        &self,
+        blocks: Vec<Block<T>>,
+        quorum_certs: Vec<QuorumCert>,
+    ) -> Result<()> {
+        self.save_blocks_and_quorum_certificates(blocks, quorum_certs)
+    }
+
+    fn delete_blocks_and_quorum_certs(&self, block_ids: Vec<HashValue>) -> Result<()> {
+        self.delete_blocks_and_quorum_certificates::<T>(block_ids)
+    }
+
+    fn get_blocks_and_quorum_certs(&self) -> Result<(Vec<Block<T>>, Vec<QuorumCert>)> {
+        Ok((
+            self.get_blocks()?
+                .into_iter()
+                .map(|(_, block)| block)
+                .collect(),
+            self.get_quorum_certificates()?
+                .into_iter()
+                .map(|(_, qc)| qc)
+                .collect(),
+        ))
+    }
+
+    fn save_single_entry(&self, key: SingleEntryKey, value: Vec<u8>) -> Result<()> {
+        let mut batch = SchemaBatch::new();
+        batch.put::<SingleEntrySchema>(&key, &value)?;
+        self.commit(batch)
+    }
+
+    fn get_single_entry(&self, key: SingleEntryKey) -> Result<Option<Vec<u8>>> {
+        self.db.get::<SingleEntrySchema>(&key)
+    }
+}
@@ -28,13 +28,27 @@ define_schema!(
     SINGLE_ENTRY_CF_NAME
 );
 
-#[derive(Debug, Eq, PartialEq, FromPrimitive, ToPrimitive)]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, FromPrimitive, ToPrimitive)]
 #[repr(u8)]
 pub enum SingleEntryKey {
     // Used to store ConsensusState
     ConsensusState = 0,
     // Used to store the highest timeout certificates
     HighestTimeoutCertificates = 1,
+    // Used to store the votes collected for the current round
+    PendingVotes = 2,
+    // Used to store the evidence of the equivocations of the other validators
+    Evidence = 3,
+    // Used to store the last vote sent by this node
+    LastVote = 4,
+    // Used to store the last timeout message sent by this node
+    LastTimeout = 5,
+    // Used to store the epoch changes the node went through
+    EpochChanges = 6,
+    // Used to store the key rotations accepted within the current epoch
+    KeyRotations = 7,
+    // Used to store the signers of the latest rotation of the consensus key of this node
+    RotatedSigners = 8,
 }
 
 impl KeyCodec<SingleEntrySchema> for SingleEntryKey {
@@ -1,25 +1,100 @@
 // Copyright (c) The Libra Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use std::sync::{Arc, RwLock};
-use types::crypto_proxies::ValidatorVerifier;
+use crate::{
+    chained_bft::consensus_types::{
+        key_rotation::KeyRotation,
+        quorum_cert::{QuorumCert, VerifiedQcCache},
+    },
+    counters,
+};
+use failure::prelude::*;
+use logger::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt::{Display, Formatter},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, RwLock,
+    },
+};
+use types::{crypto_proxies::ValidatorVerifier, validator_set::ValidatorSet};
+
+/// Maximum number of verified QC digests remembered by the epoch manager.
+const VERIFIED_QC_CACHE_SIZE: usize = 1024;
+
+/// The switch to a new epoch: the validator set of the epoch and the proof that the previous
+/// epoch ended, i.e., the QC of the previous epoch committing the reconfiguration. The proof lets
+/// the nodes lagging behind verify the reconfiguration with the validators they know.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct EpochChange {
+    epoch: u64,
+    validator_set: ValidatorSet,
+    proof: QuorumCert,
+}
+
+impl Display for EpochChange {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "EpochChange: [epoch: {}, validators: {}, proof: {}]",
+            self.epoch, self.validator_set, self.proof
+        )
+    }
+}
+
+impl EpochChange {
+    pub fn new(epoch: u64, validator_set: ValidatorSet, proof: QuorumCert) -> Self {
+        Self {
+            epoch,
+            validator_set,
+            proof,
+        }
+    }
+
+    /// The epoch starting with this change.
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    pub fn validator_set(&self) -> &ValidatorSet {
+        &self.validator_set
+    }
+
+    /// The QC of the previous epoch committing the reconfiguration.
+    pub fn proof(&self) -> &QuorumCert {
+        &self.proof
+    }
+}
 
 /// Manages the current epoch and validator set to provide quorum size/voting power and signature
 /// verification.
 pub struct EpochManager {
-    #[allow(dead_code)]
-    epoch: usize,
+    epoch: AtomicU64,
     validators: RwLock<Arc<ValidatorVerifier>>,
+    // The QCs verified against the validator set of the current epoch.
+    qc_cache: VerifiedQcCache,
+    // The epoch changes the node went through, in order.
+    epoch_changes: RwLock<Vec<EpochChange>>,
+    // The key rotations accepted within the current epoch, in order.
+    key_rotations: RwLock<Vec<KeyRotation>>,
 }
 
 impl EpochManager {
-    pub fn new(epoch: usize, validators: ValidatorVerifier) -> Self {
+    pub fn new(epoch: u64, validators: ValidatorVerifier) -> Self {
         Self {
-            epoch,
+            epoch: AtomicU64::new(epoch),
             validators: RwLock::new(Arc::new(validators)),
+            qc_cache: VerifiedQcCache::new(VERIFIED_QC_CACHE_SIZE),
+            epoch_changes: RwLock::new(vec![]),
+            key_rotations: RwLock::new(vec![]),
         }
     }
 
+    pub fn epoch(&self) -> u64 {
+        self.epoch.load(Ordering::SeqCst)
+    }
+
     pub fn quorum_size(&self) -> usize {
         self.validators.read().unwrap().quorum_size()
     }
@@ -27,4 +102,91 @@ impl EpochManager {
     pub fn validators(&self) -> Arc<ValidatorVerifier> {
         Arc::clone(&self.validators.read().unwrap())
     }
+
+    pub fn qc_cache(&self) -> &VerifiedQcCache {
+        &self.qc_cache
+    }
+
+    /// The epoch changes the node went through, in order.
+    pub fn epoch_changes(&self) -> Vec<EpochChange> {
+        self.epoch_changes.read().unwrap().clone()
+    }
+
+    /// The proof that the given epoch ended, if the node went past it.
+    pub fn epoch_proof(&self, epoch: u64) -> Option<QuorumCert> {
+        self.epoch_changes
+            .read()
+            .unwrap()
+            .iter()
+            .find(|change| change.epoch() == epoch + 1)
+            .map(|change| change.proof().clone())
+    }
+
+    /// The key rotations accepted within the current epoch, in order.
+    pub fn key_rotations(&self) -> Vec<KeyRotation> {
+        self.key_rotations.read().unwrap().clone()
+    }
+
+    /// Accepts the record of a validator rotating its consensus key within the current epoch,
+    /// once verified against the current key of the validator. The signatures of the handover
+    /// round and the following rounds are verified under the new key, the signatures of the
+    /// previous rounds under the replaced key, which is still accepted until the retirement round
+    /// of the rotation for the messages in flight. Adding the same record again is a no-op.
+    pub fn add_key_rotation(&self, rotation: &KeyRotation) -> Result<()> {
+        let mut validators = self.validators.write().unwrap();
+        ensure!(
+            rotation.epoch() == self.epoch(),
+            "{} doesn't belong to the current epoch {}",
+            rotation,
+            self.epoch()
+        );
+        if validators.get_public_key(rotation.author()).as_ref() == Some(rotation.new_public_key())
+        {
+            return Ok(());
+        }
+        rotation.verify(&validators)?;
+        *validators = Arc::new(validators.with_rotated_key(
+            rotation.author(),
+            rotation.new_public_key().clone(),
+            rotation.handover_round(),
+            rotation.retirement_round(),
+        )?);
+        self.key_rotations.write().unwrap().push(rotation.clone());
+        info!("Accepted {}", rotation);
+        counters::KEY_ROTATIONS_COUNT.inc();
+        Ok(())
+    }
+
+    /// Switches to the epoch of the given change with the given validators, which are derived
+    /// from the validator set of the change, returns false if the epoch manager is already at
+    /// this epoch or a later one.
+    /// The given QCs were certified by the validator set of the previous epoch: they remain valid
+    /// in the new epoch, such that the first blocks of the new epoch can extend them.
+    pub fn start_new_epoch<'a>(
+        &self,
+        epoch_change: EpochChange,
+        validators: ValidatorVerifier,
+        previous_epoch_qcs: impl Iterator<Item = &'a QuorumCert>,
+    ) -> bool {
+        let mut current_validators = self.validators.write().unwrap();
+        let epoch = epoch_change.epoch();
+        if epoch <= self.epoch() {
+            return false;
+        }
+        info!(
+            "Starting epoch {} with {} validators",
+            epoch,
+            validators.len()
+        );
+        counters::EPOCH_NUM.set(epoch as i64);
+        counters::CURRENT_EPOCH_NUM_VALIDATORS.set(validators.len() as i64);
+        counters::CURRENT_EPOCH_QUORUM_SIZE.set(validators.quorum_size() as i64);
+        self.qc_cache.clear();
+        self.qc_cache.trust(previous_epoch_qcs);
+        *current_validators = Arc::new(validators);
+        self.epoch_changes.write().unwrap().push(epoch_change);
+        self.key_rotations.write().unwrap().clear();
+        self.epoch.store(epoch, Ordering::SeqCst);
+        true
+    }
 }
@@ -0,0 +1,187 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Hooks into the event loop of `ChainedBftSMR`, available in test builds only.
+//!
+//! A hook is invoked before and after every event handled by a node. The event loop awaits the
+//! future returned by `before_event`, which allows a test to hold the handling of specific events
+//! (e.g., the votes received by one node) until some condition is met elsewhere (e.g., another
+//! node has committed a block). Unlike sleeping or counting messages, the resulting interleaving
+//! is deterministic.
+
+use crate::chained_bft::{
+    common::{Author, Payload, Round},
+    consensus_types::{proposal_msg::ProposalMsg, timeout_msg::TimeoutMsg, vote_msg::VoteMsg},
+    network::BlockRetrievalRequest,
+};
+use crypto::HashValue;
+use futures::{
+    channel::{mpsc, oneshot},
+    future::{self, FutureExt},
+    Future,
+};
+use std::{
+    pin::Pin,
+    sync::{Arc, Mutex},
+};
+
+/// Summary of an event handled by the event loop of a node.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ProcessedEvent {
+    /// A proposal received from the network (or from the node itself).
+    Proposal { round: Round, author: Author },
+    /// A block retrieval request from a peer.
+    BlockRetrieval { block_id: HashValue },
+    /// A vote received from the network (or from the node itself).
+    Vote { round: Round, author: Author },
+    /// A timeout message received from the network.
+    RemoteTimeout { round: Round, author: Author },
+    /// The local pacemaker timed out in the given round.
+    LocalTimeout(Round),
+    /// A retransmission of the timeout message of the given round is due.
+    TimeoutRetransmission(Round),
+    /// A commit heartbeat scheduled in the given epoch is due.
+    CommitHeartbeat(u64),
+    /// A sync info message received from a peer.
+    SyncInfo { author: Author },
+    /// A request of the sync info of the node received from a peer.
+    SyncInfoRequest { author: Author },
+}
+
+impl ProcessedEvent {
+    pub fn proposal<T: Payload>(proposal: &ProposalMsg<T>) -> Self {
+        ProcessedEvent::Proposal {
+            round: proposal.round(),
+            author: proposal.proposer(),
+        }
+    }
+
+    pub fn block_retrieval<T>(request: &BlockRetrievalRequest<T>) -> Self {
+        ProcessedEvent::BlockRetrieval {
+            block_id: request.block_id,
+        }
+    }
+
+    pub fn vote(vote: &VoteMsg) -> Self {
+        ProcessedEvent::Vote {
+            round: vote.vote_data().block_round(),
+            author: vote.author(),
+        }
+    }
+
+    pub fn remote_timeout(timeout_msg: &TimeoutMsg) -> Self {
+        ProcessedEvent::RemoteTimeout {
+            round: timeout_msg.pacemaker_timeout().round(),
+            author: timeout_msg.author(),
+        }
+    }
+}
+
+/// Intercepts the event loop of a node.
+pub trait EventHook: Send + Sync {
+    /// Called before the event is handled, the event loop doesn't make any progress until the
+    /// returned future completes.
+    fn before_event(&self, _event: &ProcessedEvent) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+        future::ready(()).boxed()
+    }
+
+    /// Called once the event has been handled.
+    fn after_event(&self, _event: &ProcessedEvent) {}
+}
+
+/// The hooks registered with a node. The hooks can be added at any time, they are invoked for
+/// the events handled after they have been added, in the order they have been added.
+#[derive(Clone, Default)]
+pub struct EventHooks {
+    hooks: Arc<Mutex<Vec<Arc<dyn EventHook>>>>,
+}
+
+impl EventHooks {
+    pub fn add(&self, hook: Arc<dyn EventHook>) {
+        self.hooks.lock().unwrap().push(hook);
+    }
+
+    pub async fn before(&self, event: &ProcessedEvent) {
+        // The lock must not be held across the await points.
+        let hooks = self.hooks.lock().unwrap().clone();
+        for hook in hooks {
+            hook.before_event(event).await;
+        }
+    }
+
+    pub fn after(&self, event: &ProcessedEvent) {
+        let hooks = self.hooks.lock().unwrap().clone();
+        for hook in hooks {
+            hook.after_event(event);
+        }
+    }
+}
+
+#[derive(Default)]
+struct GateState {
+    open: bool,
+    waiters: Vec<oneshot::Sender<()>>,
+}
+
+/// Holds the handling of the events matching a filter until the gate is opened.
+pub struct EventGate {
+    filter: Box<dyn Fn(&ProcessedEvent) -> bool + Send + Sync>,
+    state: Mutex<GateState>,
+}
+
+impl EventGate {
+    pub fn new<F>(filter: F) -> Arc<Self>
+    where
+        F: Fn(&ProcessedEvent) -> bool + Send + Sync + 'static,
+    {
+        Arc::new(Self {
+            filter: Box::new(filter),
+            state: Mutex::new(GateState::default()),
+        })
+    }
+
+    /// Releases the held event and lets all the following events through.
+    pub fn open(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.open = true;
+        for waiter in state.waiters.drain(..) {
+            let _ = waiter.send(());
+        }
+    }
+
+    /// The number of events currently held, which is at most one as the event loop is blocked
+    /// until the held event is released.
+    pub fn num_held(&self) -> usize {
+        self.state.lock().unwrap().waiters.len()
+    }
+}
+
+impl EventHook for EventGate {
+    fn before_event(&self, event: &ProcessedEvent) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+        let mut state = self.state.lock().unwrap();
+        if state.open || !(self.filter)(event) {
+            return future::ready(()).boxed();
+        }
+        let (tx, rx) = oneshot::channel();
+        state.waiters.push(tx);
+        rx.map(|_| ()).boxed()
+    }
+}
+
+/// Reports every handled event to a channel.
+pub struct EventRecorder {
+    sender: mpsc::UnboundedSender<ProcessedEvent>,
+}
+
+impl EventRecorder {
+    pub fn new() -> (Arc<Self>, mpsc::UnboundedReceiver<ProcessedEvent>) {
+        let (sender, receiver) = mpsc::unbounded();
+        (Arc::new(Self { sender }), receiver)
+    }
+}
+
+impl EventHook for EventRecorder {
+    fn after_event(&self, event: &ProcessedEvent) {
+        let _ = self.sender.unbounded_send(event.clone());
+    }
+}
@@ -0,0 +1,247 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Structured JSON-lines log of the consensus events observed by a single node.
+//!
+//! Each line of the log is a serialized `EventLogEntry`. The log is written by the
+//! `EventProcessor` when configured to do so, and can be inspected offline (e.g., after a swarm
+//! run) via the `EventLogAnalyzer`.
+
+use crate::{
+    chained_bft::common::{Author, Round},
+    util::time_service::duration_since_epoch,
+};
+use failure::prelude::*;
+use logger::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::{File, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    path::Path,
+    sync::Mutex,
+    time::Duration,
+};
+
+#[cfg(test)]
+#[path = "event_log_test.rs"]
+mod event_log_test;
+
+/// A single consensus event. Block ids are kept in full hex form, authors in their short string
+/// form.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ConsensusEvent {
+    /// The local pacemaker moved to a new round.
+    RoundStarted {
+        /// The new round.
+        round: Round,
+        /// The reason for starting the round (QC or timeout certificate).
+        reason: String,
+    },
+    /// A proposal has been received from the network.
+    ProposalReceived {
+        /// The round of the proposed block.
+        round: Round,
+        /// The id of the proposed block.
+        block_id: String,
+        /// The author of the proposal.
+        proposer: String,
+    },
+    /// A vote has been received from the network.
+    VoteReceived {
+        /// The round of the voted block.
+        round: Round,
+        /// The id of the voted block.
+        block_id: String,
+        /// The author of the vote.
+        author: String,
+    },
+    /// The votes received by this node formed a new quorum certificate.
+    QcFormed {
+        /// The round of the certified block.
+        round: Round,
+        /// The id of the certified block.
+        block_id: String,
+    },
+    /// A block has been committed, either certified by a commit QC or as an ancestor of such a
+    /// block.
+    Committed {
+        /// The round of the committed block.
+        round: Round,
+        /// The id of the committed block.
+        block_id: String,
+    },
+}
+
+/// A line of the event log.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct EventLogEntry {
+    /// Microseconds since the unix epoch at the moment the event was logged.
+    pub timestamp_usecs: u64,
+    /// The node that logged the event.
+    pub node: String,
+    /// The logged event.
+    pub event: ConsensusEvent,
+}
+
+/// Appends the consensus events of a node to a JSON-lines file.
+pub struct EventLogger {
+    node: String,
+    file: Mutex<File>,
+}
+
+impl EventLogger {
+    /// Opens (or creates) the event log file, new events are appended to the existing ones.
+    pub fn new(author: Author, path: &Path) -> Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|_| format!("Failed to open consensus event log {:?}", path))?;
+        Ok(Self {
+            node: author.short_str(),
+            file: Mutex::new(file),
+        })
+    }
+
+    /// Writes the event to the log. Failures are not propagated: the event log is a debugging
+    /// facility and should never affect consensus.
+    pub fn log(&self, event: ConsensusEvent) {
+        let entry = EventLogEntry {
+            timestamp_usecs: duration_since_epoch().as_micros() as u64,
+            node: self.node.clone(),
+            event,
+        };
+        let line = match serde_json::to_string(&entry) {
+            Ok(line) => line,
+            Err(e) => {
+                warn!("Failed to serialize consensus event {:?}: {:?}", entry, e);
+                return;
+            }
+        };
+        if let Err(e) = writeln!(self.file.lock().unwrap(), "{}", line) {
+            warn!("Failed to write consensus event {}: {:?}", line, e);
+        }
+    }
+}
+
+/// Reads all the entries of an event log file.
+pub fn read_event_log(path: &Path) -> Result<Vec<EventLogEntry>> {
+    let file = File::open(path).with_context(|_| format!("Failed to open {:?}", path))?;
+    let mut entries: Vec<EventLogEntry> = vec![];
+    for (idx, line) in BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        entries.push(
+            serde_json::from_str(&line)
+                .with_context(|_| format!("Malformed event at {:?}:{}", path, idx + 1))?,
+        );
+    }
+    Ok(entries)
+}
+
+/// Offline analysis of the event logs of one or several nodes.
+pub struct EventLogAnalyzer {
+    // All the entries ordered by their timestamps.
+    entries: Vec<EventLogEntry>,
+}
+
+impl EventLogAnalyzer {
+    /// Creates an analyzer for the given entries, which can come from different nodes.
+    pub fn new(mut entries: Vec<EventLogEntry>) -> Self {
+        entries.sort_by_key(|entry| entry.timestamp_usecs);
+        Self { entries }
+    }
+
+    /// Merges the event logs of multiple nodes (e.g., all the nodes of a swarm).
+    pub fn from_files<P: AsRef<Path>>(paths: &[P]) -> Result<Self> {
+        let mut entries = vec![];
+        for path in paths {
+            entries.extend(read_event_log(path.as_ref())?);
+        }
+        Ok(Self::new(entries))
+    }
+
+    /// All the entries ordered by their timestamps.
+    pub fn entries(&self) -> &[EventLogEntry] {
+        &self.entries
+    }
+
+    /// The events logged by the given node.
+    pub fn node_events<'a>(&'a self, node: &'a str) -> impl Iterator<Item = &'a EventLogEntry> {
+        self.entries.iter().filter(move |entry| entry.node == node)
+    }
+
+    /// The highest committed round of the given node.
+    pub fn last_committed_round(&self, node: &str) -> Option<Round> {
+        self.node_events(node)
+            .filter_map(|entry| match &entry.event {
+                ConsensusEvent::Committed { round, .. } => Some(*round),
+                _ => None,
+            })
+            .max()
+    }
+
+    /// The authors of the votes received by the given node for the given round.
+    pub fn vote_authors(&self, node: &str, round: Round) -> Vec<String> {
+        self.node_events(node)
+            .filter_map(|entry| match &entry.event {
+                ConsensusEvent::VoteReceived {
+                    round: vote_round,
+                    author,
+                    ..
+                } if *vote_round == round => Some(author.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// For every round the given node started, the time it took the node to observe the
+    /// following round start (regardless of whether the round ended with a QC or a timeout).
+    pub fn round_durations(&self, node: &str) -> BTreeMap<Round, Duration> {
+        let starts: Vec<(Round, u64)> = self
+            .node_events(node)
+            .filter_map(|entry| match &entry.event {
+                ConsensusEvent::RoundStarted { round, .. } => Some((*round, entry.timestamp_usecs)),
+                _ => None,
+            })
+            .collect();
+        starts
+            .iter()
+            .zip(starts.iter().skip(1))
+            .map(|((round, start), (_, end))| {
+                (*round, Duration::from_micros(end.saturating_sub(*start)))
+            })
+            .collect()
+    }
+
+    /// For every block committed by at least one node, the time between the moment its proposal
+    /// was first received and the moment it was first committed.
+    pub fn commit_latencies(&self) -> HashMap<String, Duration> {
+        let mut proposal_times = HashMap::new();
+        let mut latencies = HashMap::new();
+        for entry in &self.entries {
+            match &entry.event {
+                ConsensusEvent::ProposalReceived { block_id, .. } => {
+                    proposal_times
+                        .entry(block_id.clone())
+                        .or_insert(entry.timestamp_usecs);
+                }
+                ConsensusEvent::Committed { block_id, .. } => {
+                    if let Some(proposal_time) = proposal_times.get(block_id) {
+                        latencies.entry(block_id.clone()).or_insert_with(|| {
+                            Duration::from_micros(
+                                entry.timestamp_usecs.saturating_sub(*proposal_time),
+                            )
+                        });
+                    }
+                }
+                _ => (),
+            }
+        }
+        latencies
+    }
+}
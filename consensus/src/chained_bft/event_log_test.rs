@@ -0,0 +1,190 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::chained_bft::event_log::{
+    read_event_log, ConsensusEvent, EventLogAnalyzer, EventLogEntry, EventLogger,
+};
+use std::time::Duration;
+use tools::tempdir::TempPath;
+use types::account_address::AccountAddress;
+
+fn entry(timestamp_usecs: u64, node: &str, event: ConsensusEvent) -> EventLogEntry {
+    EventLogEntry {
+        timestamp_usecs,
+        node: node.to_string(),
+        event,
+    }
+}
+
+#[test]
+fn test_write_and_read_event_log() {
+    let tmp_file = TempPath::new();
+    let author = AccountAddress::random();
+    let logger = EventLogger::new(author, tmp_file.path()).unwrap();
+    let events = vec![
+        ConsensusEvent::RoundStarted {
+            round: 1,
+            reason: "QCReady".to_string(),
+        },
+        ConsensusEvent::VoteReceived {
+            round: 1,
+            block_id: "abcd".to_string(),
+            author: author.short_str(),
+        },
+        ConsensusEvent::QcFormed {
+            round: 1,
+            block_id: "abcd".to_string(),
+        },
+    ];
+    for event in &events {
+        logger.log(event.clone());
+    }
+    // Reopening the log appends to the existing events.
+    EventLogger::new(author, tmp_file.path())
+        .unwrap()
+        .log(ConsensusEvent::Committed {
+            round: 1,
+            block_id: "abcd".to_string(),
+        });
+
+    let entries = read_event_log(tmp_file.path()).unwrap();
+    assert_eq!(entries.len(), 4);
+    assert!(entries.iter().all(|e| e.node == author.short_str()));
+    assert_eq!(
+        entries
+            .iter()
+            .take(3)
+            .map(|e| e.event.clone())
+            .collect::<Vec<_>>(),
+        events
+    );
+}
+
+#[test]
+fn test_event_log_analyzer() {
+    let analyzer = EventLogAnalyzer::new(vec![
+        entry(
+            3_000,
+            "b",
+            ConsensusEvent::ProposalReceived {
+                round: 1,
+                block_id: "b1".to_string(),
+                proposer: "a".to_string(),
+            },
+        ),
+        entry(
+            1_000,
+            "a",
+            ConsensusEvent::RoundStarted {
+                round: 1,
+                reason: "QCReady".to_string(),
+            },
+        ),
+        entry(
+            2_000,
+            "a",
+            ConsensusEvent::ProposalReceived {
+                round: 1,
+                block_id: "b1".to_string(),
+                proposer: "a".to_string(),
+            },
+        ),
+        entry(
+            4_000,
+            "a",
+            ConsensusEvent::VoteReceived {
+                round: 1,
+                block_id: "b1".to_string(),
+                author: "a".to_string(),
+            },
+        ),
+        entry(
+            5_000,
+            "a",
+            ConsensusEvent::VoteReceived {
+                round: 1,
+                block_id: "b1".to_string(),
+                author: "b".to_string(),
+            },
+        ),
+        entry(
+            6_000,
+            "a",
+            ConsensusEvent::RoundStarted {
+                round: 2,
+                reason: "QCReady".to_string(),
+            },
+        ),
+        entry(
+            9_000,
+            "a",
+            ConsensusEvent::Committed {
+                round: 1,
+                block_id: "b1".to_string(),
+            },
+        ),
+        entry(
+            10_000,
+            "b",
+            ConsensusEvent::Committed {
+                round: 1,
+                block_id: "b1".to_string(),
+            },
+        ),
+    ]);
+
+    assert_eq!(analyzer.entries()[0].timestamp_usecs, 1_000);
+    assert_eq!(analyzer.node_events("b").count(), 2);
+    assert_eq!(analyzer.last_committed_round("a"), Some(1));
+    assert_eq!(analyzer.last_committed_round("c"), None);
+    assert_eq!(analyzer.vote_authors("a", 1), vec!["a", "b"]);
+    assert!(analyzer.vote_authors("b", 1).is_empty());
+    let round_durations = analyzer.round_durations("a");
+    assert_eq!(round_durations.len(), 1);
+    assert_eq!(round_durations[&1], Duration::from_micros(5_000));
+    assert_eq!(
+        analyzer.commit_latencies()["b1"],
+        Duration::from_micros(7_000)
+    );
+}
+
+#[test]
+fn test_commit_latencies_of_ancestors() {
+    // The ids of the blocks share their first bytes, only the full ids tell them apart.
+    let parent_id = format!("abcd{}01", "0".repeat(58));
+    let child_id = format!("abcd{}02", "0".repeat(58));
+    let proposal = |timestamp_usecs, round, block_id: &String| {
+        entry(
+            timestamp_usecs,
+            "a",
+            ConsensusEvent::ProposalReceived {
+                round,
+                block_id: block_id.clone(),
+                proposer: "a".to_string(),
+            },
+        )
+    };
+    // The parent is committed along with the child by the commit QC of the child.
+    let commit = |round, block_id: &String| {
+        entry(
+            5_000,
+            "a",
+            ConsensusEvent::Committed {
+                round,
+                block_id: block_id.clone(),
+            },
+        )
+    };
+    let analyzer = EventLogAnalyzer::new(vec![
+        proposal(1_000, 1, &parent_id),
+        proposal(2_000, 2, &child_id),
+        commit(1, &parent_id),
+        commit(2, &child_id),
+    ]);
+
+    assert_eq!(analyzer.last_committed_round("a"), Some(2));
+    let latencies = analyzer.commit_latencies();
+    assert_eq!(latencies.len(), 2);
+    assert_eq!(latencies[&parent_id], Duration::from_micros(4_000));
+    assert_eq!(latencies[&child_id], Duration::from_micros(3_000));
+}
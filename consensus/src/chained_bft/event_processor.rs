@@ -1,49 +1,61 @@
 // Copyright (c) The Libra Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-#[cfg(test)]
-use crate::chained_bft::safety::safety_rules::ConsensusState;
 use crate::{
     chained_bft::{
-        block_storage::{BlockReader, BlockStore, NeedFetchResult, VoteReceptionResult},
+        block_storage::{
+            AsyncBlockReader, BlockReader, BlockStore, NeedFetchResult, VoteReceptionResult,
+        },
+        commit_pipeline::{CommitPipeline, OrderedBlocks},
         common::{Author, Payload, Round},
         consensus_types::{
             block::Block,
             proposal_msg::ProposalMsg,
             quorum_cert::QuorumCert,
             sync_info::SyncInfo,
-            timeout_msg::{PacemakerTimeout, PacemakerTimeoutCertificate, TimeoutMsg},
-            vote_data::VoteData,
+            timeout_certificate::TimeoutCertificate,
+            timeout_msg::{PacemakerTimeout, TimeoutMsg, TimeoutReason},
             vote_msg::VoteMsg,
         },
-        epoch_manager::EpochManager,
+        epoch_manager::{EpochChange, EpochManager},
+        event_log::{ConsensusEvent, EventLogger},
+        evidence::EvidenceStore,
+        introspection::ConsensusIntrospector,
+        latency_tracker::LatencyTracker,
         liveness::{
+            commit_heartbeat::{CommitHeartbeat, CommitHeartbeatPolicy},
             pacemaker::{NewRoundEvent, NewRoundReason, Pacemaker},
             proposal_generator::{ProposalGenerationError, ProposalGenerator},
             proposer_election::ProposerElection,
+            qc_rebroadcast::QcRebroadcaster,
+            timeout_retransmission::{TimeoutRetransmissionPolicy, TimeoutRetransmitter},
         },
         network::{BlockRetrievalRequest, BlockRetrievalResponse, ConsensusNetworkImpl},
-        persistent_storage::PersistentStorage,
-        safety::safety_rules::SafetyRules,
-        sync_manager::{SyncManager, SyncMgrContext},
+        pending_votes_writer::PendingVotesWriter,
+        persistent_storage::{PersistentLivenessStorage, PersistentStorage},
+        safety::safety_rules::{ConsensusState, TSafetyRules},
+        sync_manager::{BlockRetrievalPolicy, SyncManager, SyncMgrContext},
     },
     counters,
-    state_replication::{StateComputer, TxnManager},
+    state_replication::{NoopPayloadValidator, PayloadValidator, StateComputer},
     util::time_service::{
         duration_since_epoch, wait_if_possible, TimeService, WaitingError, WaitingSuccess,
     },
 };
+use channel;
+use config::config::CommitRule;
 use crypto::HashValue;
-use failure::ResultExt;
 use logger::prelude::*;
 use mirai_annotations::{
     debug_checked_precondition, debug_checked_precondition_eq, debug_checked_verify,
     debug_checked_verify_eq,
 };
 use network::proto::BlockRetrievalStatus;
-use std::{sync::Arc, time::Duration};
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 use termion::color::*;
-use types::crypto_proxies::LedgerInfoWithSignatures;
 
 #[cfg(test)]
 #[path = "event_processor_test.rs"]
@@ -53,6 +65,10 @@ mod event_processor_test;
 #[path = "event_processor_fuzzing.rs"]
 pub mod event_processor_fuzzing;
 
+/// A node receiving a vote for a round this far ahead of its current round requests the sync
+/// info of the voter instead of waiting for the next timeout to catch up.
+const SYNC_INFO_REQUEST_ROUND_GAP: Round = 3;
+
 /// Consensus SMR is working in an event based fashion: EventProcessor is responsible for
 /// processing the individual events (e.g., process_new_round, process_proposal, process_vote,
 /// etc.). It is exposing the async processing functions for each event type.
@@ -64,17 +80,52 @@ pub struct EventProcessor<T> {
     pacemaker: Pacemaker,
     proposer_election: Box<dyn ProposerElection<T> + Send + Sync>,
     proposal_generator: ProposalGenerator<T>,
-    safety_rules: SafetyRules,
-    state_computer: Arc<dyn StateComputer<Payload = T>>,
-    txn_manager: Arc<dyn TxnManager<Payload = T>>,
+    safety_rules: Box<dyn TSafetyRules<T>>,
+    commit_pipeline: CommitPipeline<T>,
     network: ConsensusNetworkImpl,
     storage: Arc<dyn PersistentStorage<T>>,
+    // Persists the pending votes off the event loop.
+    pending_votes_writer: PendingVotesWriter,
     sync_manager: SyncManager<T>,
     time_service: Arc<dyn TimeService>,
     enforce_increasing_timestamps: bool,
     // Cache of the last sent vote message.
     last_vote_sent: Option<(VoteMsg, Round)>,
+    // Cache of the last sent timeout message.
+    last_timeout_sent: Option<TimeoutMsg>,
     epoch_mgr: Arc<EpochManager>,
+    // Optional structured log of the consensus events.
+    event_logger: Option<EventLogger>,
+    // Detects the equivocations of the other validators.
+    evidence_store: Arc<EvidenceStore<T>>,
+    // The highest round in which a proposal was received and the highest round in which the
+    // execution of a proposed block failed, used to report why a round times out.
+    last_proposal_round: Round,
+    last_failed_execution_round: Round,
+    // The change to the next epoch once a reconfiguration is committed: the processor stops
+    // proposing and voting, and is replaced by the processor of the next epoch.
+    pending_reconfiguration: Option<EpochChange>,
+    commit_rule: CommitRule,
+    // Whether a backup vote for a NIL block is sent when a round times out without proposal.
+    nil_blocks_enabled: bool,
+    // An observer never signs a vote, a proposal or a timeout.
+    observer: bool,
+    // The round in which the node last requested the sync info of a peer: the sync info is
+    // requested at most once per round.
+    last_sync_info_request_round: Round,
+    // The rounds of the last votes of the peers, to send the highest QC to the peers left behind
+    // when entering a new round.
+    qc_rebroadcaster: Mutex<QcRebroadcaster>,
+    // Feeds the latency histograms of the proposal, vote, QC and commit stages.
+    latency_tracker: LatencyTracker,
+    // Publishes the state of the rounds to the debug interface.
+    introspector: Option<Arc<ConsensusIntrospector<T>>>,
+    // Broadcasts the timeout message of the current round again until the round advances.
+    timeout_retransmitter: Option<TimeoutRetransmitter>,
+    // Broadcasts the highest commit certificate while no proposals are received.
+    commit_heartbeat: Option<CommitHeartbeat>,
+    // Rejects the remote proposals whose payload violates the policy of the deployment.
+    payload_validator: Arc<dyn PayloadValidator<Payload = T>>,
 }
 
 impl<T: Payload> EventProcessor<T> {
@@ -84,21 +135,27 @@ impl<T: Payload> EventProcessor<T> {
         pacemaker: Pacemaker,
         proposer_election: Box<dyn ProposerElection<T> + Send + Sync>,
         proposal_generator: ProposalGenerator<T>,
-        safety_rules: SafetyRules,
+        safety_rules: Box<dyn TSafetyRules<T>>,
         state_computer: Arc<dyn StateComputer<Payload = T>>,
-        txn_manager: Arc<dyn TxnManager<Payload = T>>,
+        commit_pipeline: CommitPipeline<T>,
         network: ConsensusNetworkImpl,
         storage: Arc<dyn PersistentStorage<T>>,
         time_service: Arc<dyn TimeService>,
         enforce_increasing_timestamps: bool,
         epoch_mgr: Arc<EpochManager>,
+        event_logger: Option<EventLogger>,
+        block_retrieval_policy: BlockRetrievalPolicy,
+        evidence_store: Arc<EvidenceStore<T>>,
     ) -> Self {
         let sync_manager = SyncManager::new(
             Arc::clone(&block_store),
             Arc::clone(&storage),
             network.clone(),
-            Arc::clone(&state_computer),
+            state_computer,
+            block_retrieval_policy,
         );
+        let latency_tracker = LatencyTracker::new(block_store.root().round());
+        let pending_votes_writer = PendingVotesWriter::new(storage.persistent_liveness_storage());
         Self {
             author,
             block_store,
@@ -106,15 +163,106 @@ impl<T: Payload> EventProcessor<T> {
             proposer_election,
             proposal_generator,
             safety_rules,
-            state_computer,
-            txn_manager,
+            commit_pipeline,
             network,
             storage,
+            pending_votes_writer,
             sync_manager,
             time_service,
             enforce_increasing_timestamps,
             last_vote_sent: None,
+            last_timeout_sent: None,
             epoch_mgr,
+            event_logger,
+            evidence_store,
+            last_proposal_round: 0,
+            last_failed_execution_round: 0,
+            pending_reconfiguration: None,
+            commit_rule: CommitRule::ThreeChain,
+            nil_blocks_enabled: true,
+            observer: false,
+            last_sync_info_request_round: 0,
+            qc_rebroadcaster: Mutex::new(QcRebroadcaster::default()),
+            latency_tracker,
+            introspector: None,
+            timeout_retransmitter: None,
+            commit_heartbeat: None,
+            payload_validator: Arc::new(NoopPayloadValidator::default()),
+        }
+    }
+
+    /// Sets the commit rule of the validator set, which must match the one of the safety rules.
+    pub fn with_commit_rule(mut self, commit_rule: CommitRule) -> Self {
+        self.commit_rule = commit_rule;
+        self
+    }
+
+    /// Without NIL blocks, the rounds without proposal only advance with timeout certificates.
+    pub fn with_nil_blocks_enabled(mut self, nil_blocks_enabled: bool) -> Self {
+        self.nil_blocks_enabled = nil_blocks_enabled;
+        self
+    }
+
+    /// An observer follows the proposals, the certificates and the commits of the validators
+    /// without taking part in consensus: it executes the proposed blocks without voting, and
+    /// neither proposes nor sends timeouts.
+    pub fn with_observer(mut self, observer: bool) -> Self {
+        self.observer = observer;
+        self
+    }
+
+    /// Publishes the state of the rounds and the votes of the processor to the given introspector.
+    pub fn with_introspector(mut self, introspector: Arc<ConsensusIntrospector<T>>) -> Self {
+        self.introspector = Some(introspector);
+        self
+    }
+
+    /// Retransmits the timeout messages according to the given policy: the retransmissions are
+    /// scheduled to the given channel, which is expected to be drained by the event loop into
+    /// `process_timeout_retransmission`.
+    pub fn with_timeout_retransmission(
+        mut self,
+        policy: TimeoutRetransmissionPolicy,
+        retransmission_sender: channel::Sender<Round>,
+    ) -> Self {
+        self.timeout_retransmitter = Some(TimeoutRetransmitter::new(
+            policy,
+            Arc::clone(&self.time_service),
+            retransmission_sender,
+        ));
+        self
+    }
+
+    /// Broadcasts the sync info of the node, which carries its highest commit certificate,
+    /// according to the given policy: the heartbeats are scheduled to the given channel once the
+    /// processor is started, which is expected to be drained by the event loop into
+    /// `process_commit_heartbeat`.
+    pub fn with_commit_heartbeat(
+        mut self,
+        policy: CommitHeartbeatPolicy,
+        heartbeat_sender: channel::Sender<u64>,
+    ) -> Self {
+        self.commit_heartbeat = Some(CommitHeartbeat::new(
+            policy,
+            Arc::clone(&self.time_service),
+            heartbeat_sender,
+            self.epoch_mgr.epoch(),
+        ));
+        self
+    }
+
+    /// The remote proposals are only voted for if the given validator accepts their payload.
+    pub fn with_payload_validator(
+        mut self,
+        payload_validator: Arc<dyn PayloadValidator<Payload = T>>,
+    ) -> Self {
+        self.payload_validator = payload_validator;
+        self
+    }
+
+    fn log_event(&self, event: ConsensusEvent) {
+        if let Some(event_logger) = &self.event_logger {
+            event_logger.log(event);
         }
     }
 
@@ -132,6 +280,10 @@ impl<T: Payload> EventProcessor<T> {
     /// Do nothing
     async fn process_new_round_event(&self, new_round_event: NewRoundEvent) {
         debug!("Processing {}", new_round_event);
+        self.log_event(ConsensusEvent::RoundStarted {
+            round: new_round_event.round,
+            reason: new_round_event.reason.to_string(),
+        });
         counters::CURRENT_ROUND.set(new_round_event.round as i64);
         counters::ROUND_TIMEOUT_MS.set(new_round_event.timeout.as_millis() as i64);
         match new_round_event.reason {
@@ -142,10 +294,30 @@ impl<T: Payload> EventProcessor<T> {
                 counters::TIMEOUT_ROUNDS_COUNT.inc();
             }
         };
-        if self
+        let proposer = self
             .proposer_election
-            .is_valid_proposer(self.author, new_round_event.round)
-            .is_none()
+            .get_valid_proposers(new_round_event.round)
+            .first()
+            .cloned();
+        if proposer == Some(self.author) {
+            counters::ROUNDS_LED_COUNT.inc();
+        }
+        if let Some(introspector) = &self.introspector {
+            introspector.new_round(
+                new_round_event.round,
+                self.time_service.get_current_timestamp() + new_round_event.timeout,
+                proposer,
+            );
+        }
+        if !self.observer {
+            self.rebroadcast_highest_qc(new_round_event.round).await;
+        }
+        if self.observer
+            || self.pending_reconfiguration.is_some()
+            || self
+                .proposer_election
+                .is_valid_proposer(self.author, new_round_event.round)
+                .is_none()
         {
             return;
         }
@@ -189,15 +361,43 @@ impl<T: Payload> EventProcessor<T> {
             (*self.block_store.highest_ledger_info()).clone(),
             timeout_certificate,
         );
+        // The votes of the previous round that didn't form a QC here (e.g., the votes for a
+        // NIL block, or the straggler votes for a block certified by another replica) might
+        // complete a QC at the recipients.
+        let votes = self.block_store.pending_votes(proposal.round() - 1);
+        counters::PIGGYBACKED_VOTES_SENT_COUNT.inc_by(votes.len() as i64);
         // return proposal
-        Ok(ProposalMsg::new(proposal, sync_info))
+        Ok(ProposalMsg::new(proposal, sync_info).with_votes(votes))
     }
 
     /// Process a ProposalMsg, pre_process would bring all the dependencies and filter out invalid
     /// proposal, process_proposed_block would execute and decide whether to vote for it.
     pub async fn process_proposal_msg(&mut self, proposal_msg: ProposalMsg<T>) {
+        self.log_event(ConsensusEvent::ProposalReceived {
+            round: proposal_msg.round(),
+            block_id: format!("{:x}", proposal_msg.proposal().id()),
+            proposer: proposal_msg.proposer().short_str(),
+        });
+        self.latency_tracker.proposal_received(
+            proposal_msg.proposal().id(),
+            self.time_service.get_current_timestamp(),
+        );
+        if let Some(evidence) = self.evidence_store.check_proposal(
+            proposal_msg.proposal(),
+            self.epoch_mgr.validators().as_ref(),
+        ) {
+            warn!("Equivocation detected: {}", evidence);
+        }
+        self.last_proposal_round = std::cmp::max(self.last_proposal_round, proposal_msg.round());
+        if let Some(commit_heartbeat) = &mut self.commit_heartbeat {
+            commit_heartbeat.proposal_received();
+        }
         if let Some(block) = self.pre_process_proposal(proposal_msg).await {
-            self.process_proposed_block(block).await
+            // The proposal might have committed a reconfiguration: the votes of the previous
+            // epoch are not counted anymore.
+            if self.pending_reconfiguration.is_none() {
+                self.process_proposed_block(block).await
+            }
         }
     }
 
@@ -227,6 +427,24 @@ impl<T: Payload> EventProcessor<T> {
             );
             return None;
         }
+        if self.commit_rule == CommitRule::TwoChain && !Self::is_round_justified(&proposal_msg) {
+            warn!(
+                "Proposal {} is rejected because neither its QC nor a TC certifies the previous round",
+                proposal_msg
+            );
+            return None;
+        }
+        let payload_size = proposal_msg.proposal().payload_size();
+        if payload_size > self.proposal_generator.max_block_bytes() {
+            counters::OVERSIZED_PROPOSAL_COUNT.inc();
+            warn!(
+                "Proposal {} is rejected because its payload of {} bytes exceeds the limit of {} bytes",
+                proposal_msg,
+                payload_size,
+                self.proposal_generator.max_block_bytes()
+            );
+            return None;
+        }
         if let Err(e) = self
             .sync_up(proposal_msg.sync_info(), proposal_msg.proposer(), true)
             .await
@@ -237,6 +455,12 @@ impl<T: Payload> EventProcessor<T> {
             );
             return None;
         }
+        // The proposer's highest ledger info might commit blocks that this replica has not
+        // committed yet (e.g., because it missed the QC that formed the 3-chain).
+        self.commit_highest_ledger_info(proposal_msg.sync_info().highest_ledger_info())
+            .await;
+        self.add_piggybacked_votes(proposal_msg.votes().to_vec())
+            .await;
 
         // pacemaker may catch up with the SyncInfo, check again
         let current_round = self.pacemaker.current_round();
@@ -254,6 +478,19 @@ impl<T: Payload> EventProcessor<T> {
             .process_proposal(proposal_msg.take_proposal())
     }
 
+    /// With the 2-chain commit rule, a proposal either extends the block certified in the
+    /// previous round, or carries the timeout certificate of the previous round: a leader can't
+    /// skip the rounds in which the replicas might have locked on a higher certified block.
+    fn is_round_justified(proposal_msg: &ProposalMsg<T>) -> bool {
+        let round = proposal_msg.round();
+        let qc = proposal_msg.proposal().quorum_cert();
+        qc.certified_block_round() + 1 == round
+            || proposal_msg
+                .sync_info()
+                .highest_timeout_certificate()
+                .map_or(false, |tc| tc.round() + 1 == round)
+    }
+
     /// Upon receiving TimeoutMsg, ensure that any branches with higher quorum certificates are
     /// populated to this replica prior to processing the pacemaker timeout.  This ensures that when
     /// a pacemaker timeout certificate is formed with 2f+1 timeouts, the next proposer will be
@@ -261,10 +498,15 @@ impl<T: Payload> EventProcessor<T> {
     /// can vote for it.
     pub async fn process_remote_timeout_msg(&mut self, timeout_msg: TimeoutMsg) {
         debug!(
-            "Received timeout msg for round {} from {}",
+            "Received timeout msg for round {} from {}, reason: {}",
             timeout_msg.pacemaker_timeout().round(),
-            timeout_msg.author().short_str()
+            timeout_msg.author().short_str(),
+            timeout_msg.reason()
         );
+        counters::OP_COUNTERS.inc(&format!(
+            "timeout_reason_received_{}",
+            timeout_msg.reason().as_str()
+        ));
 
         if self
             .sync_up(timeout_msg.sync_info(), timeout_msg.author(), true)
@@ -275,8 +517,7 @@ impl<T: Payload> EventProcessor<T> {
             return;
         };
         if let Some(vote) = timeout_msg.pacemaker_timeout().vote_msg() {
-            self.add_vote(vote.clone(), self.epoch_mgr.quorum_size())
-                .await;
+            self.add_vote(vote.clone()).await;
         }
         if let Some(new_round_event) = self.pacemaker.process_remote_timeout(
             timeout_msg.pacemaker_timeout().clone(),
@@ -304,11 +545,7 @@ impl<T: Payload> EventProcessor<T> {
                     .highest_quorum_cert()
                     .certified_block_round()
         {
-            let sync_info = SyncInfo::new(
-                self.block_store.highest_quorum_cert().as_ref().clone(),
-                self.block_store.highest_ledger_info().as_ref().clone(),
-                self.pacemaker.highest_timeout_certificate(),
-            );
+            let sync_info = self.gen_sync_info();
 
             debug!(
                 "Peer {} is at round {} with hqc round {}, sending it {}",
@@ -322,6 +559,64 @@ impl<T: Payload> EventProcessor<T> {
         }
     }
 
+    /// In case the given round reached by a peer is far ahead of the current round, request the
+    /// sync info of that peer instead of waiting for the next timeout to catch up.
+    async fn request_sync_info_if_lagging(&mut self, peer: Author, remote_round: Round) {
+        let current_round = self.pacemaker.current_round();
+        if self.author == peer
+            || remote_round < current_round + SYNC_INFO_REQUEST_ROUND_GAP
+            || self.last_sync_info_request_round >= current_round
+        {
+            return;
+        }
+        debug!(
+            "Peer {} is at round {} while the current round is {}, requesting its sync info",
+            peer.short_str(),
+            remote_round,
+            current_round,
+        );
+        self.last_sync_info_request_round = current_round;
+        counters::SYNC_INFO_REQUESTS_SENT_COUNT.inc();
+        self.network.request_sync_info(peer).await;
+    }
+
+    /// The proposal of the new round may not reach the peers that missed the QC formed in the
+    /// previous rounds: send the sync info to the peers whose last vote is older than the
+    /// previous round, so that they don't have to wait for a timeout to catch up.
+    async fn rebroadcast_highest_qc(&self, round: Round) {
+        let peers = self.qc_rebroadcaster.lock().unwrap().peers_behind(round);
+        if peers.is_empty() {
+            return;
+        }
+        let sync_info = self.gen_sync_info();
+        for peer in peers {
+            debug!(
+                "Peer {} didn't vote since round {}, sending it {}",
+                peer.short_str(),
+                round - 1,
+                sync_info,
+            );
+            counters::SYNC_INFO_MSGS_SENT_COUNT.inc();
+            self.network.send_sync_info(sync_info.clone(), peer).await;
+        }
+    }
+
+    /// The sync info carries the last vote of this node unless its block is already certified.
+    fn gen_sync_info(&self) -> SyncInfo {
+        let sync_info = SyncInfo::new(
+            self.block_store.highest_quorum_cert().as_ref().clone(),
+            self.block_store.highest_ledger_info().as_ref().clone(),
+            self.pacemaker.highest_timeout_certificate(),
+        );
+        match &self.last_vote_sent {
+            Some((vote, round)) if *round > sync_info.hqc_round() => {
+                counters::PIGGYBACKED_VOTES_SENT_COUNT.inc();
+                sync_info.with_last_vote(vote.clone())
+            }
+            _ => sync_info,
+        }
+    }
+
     /// The function makes sure that it brings the missing dependencies from the QC and LedgerInfo
     /// of the given sync info and update the pacemaker with the certificates if succeed.
     /// Returns Error in case sync mgr failed to bring the missing dependencies.
@@ -348,6 +643,8 @@ impl<T: Payload> EventProcessor<T> {
                 current_hqc_round,
                 sync_info.hqc_round(),
             );
+            // The state synchronization must not race with the commits of the ordered blocks.
+            self.commit_pipeline.flush().await;
             let deadline = self.pacemaker.current_round_deadline();
             let sync_mgr_context = SyncMgrContext::new(sync_info, author);
             self.sync_manager
@@ -379,7 +676,22 @@ impl<T: Payload> EventProcessor<T> {
         // To avoid a ping-pong cycle between two peers that move forward together.
         if let Err(e) = self.sync_up(&sync_info, peer, false).await {
             error!("Fail to process sync info: {:?}", e);
+            return;
         }
+        self.add_piggybacked_votes(sync_info.last_vote().cloned().into_iter().collect())
+            .await;
+    }
+
+    /// Responds to a peer that detected it lags behind with the sync info of the node.
+    pub async fn process_sync_info_request(&self, peer: Author) {
+        let sync_info = self.gen_sync_info();
+        debug!(
+            "Peer {} requested the sync info, sending it {}",
+            peer.short_str(),
+            sync_info
+        );
+        counters::SYNC_INFO_MSGS_SENT_COUNT.inc();
+        self.network.send_sync_info(sync_info, peer).await;
     }
 
     /// The replica stops voting for this round and saves its consensus state.  Voting is halted
@@ -387,55 +699,134 @@ impl<T: Payload> EventProcessor<T> {
     /// Saving the consensus state ensures that on restart, the replicas will not waste time
     /// on previous rounds.
     pub async fn process_local_timeout(&mut self, round: Round) {
-        if !self.pacemaker.process_local_timeout(round) {
+        if !self.pacemaker.process_local_timeout(round) || self.observer {
             return;
         }
         let last_vote_round = self.safety_rules.consensus_state().last_vote_round();
+        let reason = self.local_timeout_reason(round, last_vote_round);
+        counters::OP_COUNTERS.inc(&format!("timeout_reason_local_{}", reason.as_str()));
         warn!(
-            "Round {} timed out: {}, expected round proposer was {:?}, broadcasting new round to all replicas",
+            "Round {} timed out ({}): {}, expected round proposer was {:?}, broadcasting new round to all replicas",
             round,
+            reason,
             if last_vote_round == round { "already executed and voted at this round" } else { "will try to generate a backup vote" },
             self.proposer_election.get_valid_proposers(round).iter().map(|p| p.short_str()).collect::<Vec<String>>(),
         );
 
-        let vote_msg_to_attach = match self.last_vote_sent.as_ref() {
-            Some((vote, vote_round)) if (*vote_round == round) => Some(vote.clone()),
+        let signer = self.block_store.signer_for_round(round);
+        // The timeout of this round sent before (possibly before a restart) is sent again with an
+        // up-to-date sync info: the node never signs two different timeouts for the same round.
+        let pacemaker_timeout = match self.last_timeout_sent.as_ref() {
+            Some(timeout_msg) if timeout_msg.pacemaker_timeout().round() == round => {
+                timeout_msg.pacemaker_timeout().clone()
+            }
             _ => {
-                // Try to generate a backup vote
-                let backup_vote_res = self.gen_backup_vote(round).await;
-                match &backup_vote_res {
-                    Ok(backup_vote_msg) => {
-                        self.last_vote_sent
-                            .replace((backup_vote_msg.clone(), round));
+                let vote_msg_to_attach = match self.last_vote_sent.as_ref() {
+                    Some((vote, vote_round)) if (*vote_round == round) => Some(vote.clone()),
+                    _ => {
+                        // Try to generate a backup vote, persisted before it is sent like any
+                        // other vote.
+                        let backup_vote_res =
+                            self.gen_backup_vote(round)
+                                .await
+                                .and_then(|backup_vote_msg| {
+                                    self.storage.save_last_vote(backup_vote_msg.clone())?;
+                                    Ok(backup_vote_msg)
+                                });
+                        match &backup_vote_res {
+                            Ok(backup_vote_msg) => {
+                                self.record_vote(backup_vote_msg);
+                                self.last_vote_sent
+                                    .replace((backup_vote_msg.clone(), round));
+                            }
+                            Err(e) => warn!("Failed to generate a backup vote: {}", e),
+                        };
+                        backup_vote_res.ok()
                     }
-                    Err(e) => warn!("Failed to generate a backup vote: {}", e),
                 };
-                backup_vote_res.ok()
+
+                // Stop voting at this round, persist the consensus state to support restarting
+                // from a recent round (i.e. > the last vote round)  and then send the SyncInfo
+                if let Err(e) = self.safety_rules.increase_last_vote_round(round) {
+                    error!("Failed to persist consensus state after increasing the last vote round due to {:?}", e);
+                    return;
+                }
+                PacemakerTimeout::new_with_reason(round, &signer, vote_msg_to_attach, reason)
             }
         };
 
-        // Stop voting at this round, persist the consensus state to support restarting from
-        // a recent round (i.e. > the last vote round)  and then send the SyncInfo
-        let consensus_state = self.safety_rules.increase_last_vote_round(round);
+        let timeout_msg = TimeoutMsg::new(
+            SyncInfo::new(
+                self.block_store.highest_quorum_cert().as_ref().clone(),
+                self.block_store.highest_ledger_info().as_ref().clone(),
+                self.pacemaker.highest_timeout_certificate(),
+            ),
+            pacemaker_timeout,
+            &signer,
+        );
+        if let Err(e) = self.storage.save_last_timeout(timeout_msg.clone()) {
+            error!(
+                "Failed to persist the timeout msg of round {} due to {:?}",
+                round, e
+            );
+            return;
+        }
+        self.last_timeout_sent = Some(timeout_msg.clone());
+        if let Some(timeout_retransmitter) = &mut self.timeout_retransmitter {
+            timeout_retransmitter.timeout_sent(timeout_msg.clone());
+        }
+        self.network.broadcast_timeout_msg(timeout_msg).await;
+    }
 
-        if let Some(consensus_state) = consensus_state {
-            if let Err(e) = self.storage.save_consensus_state(consensus_state) {
-                error!("Failed to persist consensus state after increasing the last vote round due to {:?}", e);
-                return;
-            }
+    /// The timeout message broadcast in the current round might have been lost: a scheduled
+    /// retransmission broadcasts it again unless the round advanced since then.
+    pub async fn process_timeout_retransmission(&mut self, round: Round) {
+        let current_round = self.pacemaker.current_round();
+        let timeout_msg = match self
+            .timeout_retransmitter
+            .as_mut()
+            .and_then(|retransmitter| retransmitter.retransmission_due(round, current_round))
+        {
+            Some(timeout_msg) => timeout_msg,
+            None => return,
+        };
+        debug!("Retransmitting the timeout msg of round {}", round);
+        counters::TIMEOUT_MSGS_RETRANSMITTED_COUNT.inc();
+        self.network.broadcast_timeout_msg(timeout_msg).await;
+    }
+
+    /// The peers that missed both the proposals and the timeouts (e.g., after a partition heals)
+    /// learn the committed frontier from the heartbeats, which are only broadcast while the node
+    /// doesn't receive proposals.
+    pub async fn process_commit_heartbeat(&mut self, epoch: u64) {
+        let due = self
+            .commit_heartbeat
+            .as_mut()
+            .map_or(false, |commit_heartbeat| {
+                commit_heartbeat.heartbeat_due(epoch)
+            });
+        if !due || self.observer {
+            return;
         }
+        let sync_info = self.gen_sync_info();
+        debug!("Broadcasting a commit heartbeat: {}", sync_info);
+        counters::COMMIT_HEARTBEATS_SENT_COUNT.inc();
+        self.network.broadcast_sync_info(sync_info).await;
+    }
 
-        self.network
-            .broadcast_timeout_msg(TimeoutMsg::new(
-                SyncInfo::new(
-                    self.block_store.highest_quorum_cert().as_ref().clone(),
-                    self.block_store.highest_ledger_info().as_ref().clone(),
-                    self.pacemaker.highest_timeout_certificate(),
-                ),
-                PacemakerTimeout::new(round, self.block_store.signer(), vote_msg_to_attach),
-                self.block_store.signer(),
-            ))
-            .await;
+    /// The most likely reason the given round timed out, derived from what this replica observed
+    /// in the round: a vote means that no QC was formed, otherwise either the proposed block
+    /// failed to execute, the proposal was not voted for, or no proposal was received at all.
+    fn local_timeout_reason(&self, round: Round, last_vote_round: Round) -> TimeoutReason {
+        if last_vote_round == round {
+            TimeoutReason::NoQuorumCert
+        } else if self.last_failed_execution_round == round {
+            TimeoutReason::ExecutorStalled
+        } else if self.last_proposal_round == round {
+            TimeoutReason::InvalidProposal
+        } else {
+            TimeoutReason::NoProposal
+        }
     }
 
     async fn gen_backup_vote(&mut self, round: Round) -> failure::Result<VoteMsg> {
@@ -443,7 +834,8 @@ impl<T: Payload> EventProcessor<T> {
         // round, but having a duplicate proposal here would work ok because block store makes
         // sure the calls to `execute_and_insert_block` are idempotent.
 
-        // Either use the best proposal received in this round or a NIL block if nothing available.
+        // Either use the best proposal received in this round or a NIL block if nothing available
+        // and the NIL blocks are enabled.
         let block = match self.proposer_election.take_backup_proposal(round) {
             Some(b) => {
                 debug!("Planning to vote for a backup proposal {}", b);
@@ -451,6 +843,11 @@ impl<T: Payload> EventProcessor<T> {
                 b
             }
             None => {
+                ensure!(
+                    self.nil_blocks_enabled,
+                    "No backup proposal at round {} and the NIL blocks are disabled",
+                    round
+                );
                 let nil_block = self.proposal_generator.generate_nil_block(round)?;
                 debug!("Planning to vote for a NIL block {}", nil_block);
                 counters::VOTE_NIL_COUNT.inc();
@@ -460,12 +857,18 @@ impl<T: Payload> EventProcessor<T> {
         self.execute_and_vote(block).await
     }
 
-    async fn process_certificates(
-        &mut self,
-        qc: &QuorumCert,
-        tc: Option<&PacemakerTimeoutCertificate>,
-    ) {
-        self.safety_rules.update(qc);
+    async fn process_certificates(&mut self, qc: &QuorumCert, tc: Option<&TimeoutCertificate>) {
+        if let Err(e) = self.safety_rules.update(qc) {
+            error!("Failed to update the safety rules with {}: {:?}", qc, e);
+        }
+        if let Some(vote_to_qc) = self.latency_tracker.block_certified(
+            qc.certified_block_round(),
+            qc.certified_block_id(),
+            self.time_service.get_current_timestamp(),
+        ) {
+            counters::VOTE_TO_QC_S.observe_duration(vote_to_qc);
+            counters::LAST_VOTE_TO_QC_MS.set(vote_to_qc.as_millis() as i64);
+        }
 
         let mut highest_committed_proposal_round = None;
         if let Some(block) = qc
@@ -476,8 +879,7 @@ impl<T: Payload> EventProcessor<T> {
             if !block.is_nil_block() {
                 highest_committed_proposal_round = Some(block.round());
             }
-            let finality_proof = qc.ledger_info().clone();
-            self.process_commit(block.id(), finality_proof).await;
+            self.process_commit(block.id(), qc).await;
         }
 
         if let Some(new_round_event) = self.pacemaker.process_certificates(
@@ -489,6 +891,47 @@ impl<T: Payload> EventProcessor<T> {
         }
     }
 
+    /// Commits the block certified by the given highest ledger info in case it's a new commit
+    /// for this replica. The ledger info QC is persisted first, such that the new root can be
+    /// recovered after a restart.
+    async fn commit_highest_ledger_info(&mut self, highest_ledger_info: &QuorumCert) {
+        let block = match highest_ledger_info
+            .committed_block_id()
+            .and_then(|id| self.block_store.get_block(id))
+        {
+            Some(block) => block,
+            None => return,
+        };
+        if block.round() <= self.block_store.root().round() {
+            return;
+        }
+        match self
+            .block_store
+            .need_fetch_for_quorum_cert(highest_ledger_info)
+        {
+            NeedFetchResult::QCAlreadyExist => (),
+            NeedFetchResult::QCBlockExist => {
+                if let Err(e) = self
+                    .block_store
+                    .insert_single_quorum_cert(highest_ledger_info.clone())
+                {
+                    warn!(
+                        "Failed to insert highest ledger info {}: {:?}",
+                        highest_ledger_info, e
+                    );
+                    return;
+                }
+            }
+            _ => return,
+        }
+        debug!(
+            "Committing block {} from the highest ledger info of a proposal",
+            block
+        );
+        counters::COMMITS_FROM_PROPOSAL_COUNT.inc();
+        self.process_commit(block.id(), highest_ledger_info).await;
+    }
+
     /// This function processes a proposal that was chosen as a representative of its round:
     /// 1. Add it to a block store.
     /// 2. Try to vote for it following the safety rules.
@@ -522,6 +965,47 @@ impl<T: Payload> EventProcessor<T> {
         let proposal_parent_id = proposal.parent_id();
         let certified_parent_block_round = proposal.quorum_cert().parent_block_round();
 
+        if self.observer {
+            // The block is certified by the QC carried by a following proposal.
+            if let Err(e) = self.sync_manager.execute_and_insert_block(proposal).await {
+                self.last_failed_execution_round = proposal_round;
+                warn!("Failed to execute_and_insert the block: {:?}", e);
+            }
+            return;
+        }
+
+        // The payload of the node's own proposals comes from its own mempool.
+        if proposal.author() != Some(self.author) {
+            if let Err(e) = self.payload_validator.validate(proposal.get_payload()) {
+                counters::INVALID_PAYLOAD_PROPOSAL_COUNT.inc();
+                warn!(
+                    "Proposal {} is rejected by the payload validator: {:?}",
+                    proposal, e
+                );
+                return;
+            }
+        }
+
+        // The vote for this proposal was persisted before a restart: it is sent again, while the
+        // safety rules would reject voting twice in the same round.
+        let persisted_vote = match &self.last_vote_sent {
+            Some((vote_msg, round))
+                if *round == proposal_round && vote_msg.vote_data().block_id() == proposal_id =>
+            {
+                Some(vote_msg.clone())
+            }
+            _ => None,
+        };
+        if let Some(vote_msg) = persisted_vote {
+            counters::PERSISTED_VOTES_RESENT_COUNT.inc();
+            debug!("Sending again the persisted vote {}", vote_msg);
+            let recipients = self
+                .proposer_election
+                .get_valid_proposers(proposal_round + 1);
+            self.network.send_vote(vote_msg, recipients).await;
+            return;
+        }
+
         let vote_msg = match self.execute_and_vote(proposal).await {
             Err(e) => {
                 warn!("{:?}", e);
@@ -548,8 +1032,22 @@ impl<T: Payload> EventProcessor<T> {
                 >= certified_parent_block_round
         );
 
+        // The vote is persisted before it is sent: a restarted node sends the same vote again
+        // rather than a conflicting one.
+        if let Err(e) = self.storage.save_last_vote(vote_msg.clone()) {
+            error!("Failed to persist the vote {} due to {:?}", vote_msg, e);
+            return;
+        }
+        self.record_vote(&vote_msg);
         self.last_vote_sent
             .replace((vote_msg.clone(), proposal_round));
+        if let Some(proposal_to_vote) = self
+            .latency_tracker
+            .vote_sent(proposal_id, self.time_service.get_current_timestamp())
+        {
+            counters::PROPOSAL_TO_VOTE_S.observe_duration(proposal_to_vote);
+            counters::LAST_PROPOSAL_TO_VOTE_MS.set(proposal_to_vote.as_millis() as i64);
+        }
         let recipients = self
             .proposer_election
             .get_valid_proposers(proposal_round + 1);
@@ -621,19 +1119,35 @@ impl<T: Payload> EventProcessor<T> {
         Ok(())
     }
 
+    fn record_vote(&self, vote_msg: &VoteMsg) {
+        if let Some(introspector) = &self.introspector {
+            introspector.voted(
+                vote_msg.vote_data().block_id(),
+                vote_msg.vote_data().block_round(),
+            );
+        }
+    }
+
     /// The function generates a VoteMsg for a given proposed_block:
     /// * first execute the block and add it to the block store
     /// * then verify the voting rules
-    /// * save the updated state to consensus DB
+    /// * save the updated state to the safety rules storage
     /// * return a VoteMsg with the LedgerInfo to be committed in case the vote gathers QC.
     ///
     /// This function assumes that it might be called from different tasks concurrently.
     async fn execute_and_vote(&mut self, proposed_block: Block<T>) -> failure::Result<VoteMsg> {
-        let executed_block = self
+        let proposed_round = proposed_block.round();
+        let executed_block = match self
             .sync_manager
             .execute_and_insert_block(proposed_block)
             .await
-            .with_context(|e| format!("Failed to execute_and_insert the block: {:?}", e))?;
+        {
+            Ok(executed_block) => executed_block,
+            Err(e) => {
+                self.last_failed_execution_round = proposed_round;
+                bail!("Failed to execute_and_insert the block: {:?}", e);
+            }
+        };
         let block = executed_block.block();
         // Checking pacemaker round again, because multiple proposed_block can now race
         // during async block retrieval
@@ -647,39 +1161,19 @@ impl<T: Payload> EventProcessor<T> {
         self.wait_before_vote_if_needed(block.timestamp_usecs())
             .await?;
 
-        let vote_info = self
-            .safety_rules
-            .voting_rule(block)
-            .map_err(|e| format_err!("{}Rejected{} {}: {:?}", Fg(Red), Fg(Reset), block, e))?;
-        self.storage
-            .save_consensus_state(vote_info.consensus_state().clone())
-            .with_context(|e| format!("Fail to persist consensus state: {:?}", e))?;
-
-        let proposal_id = vote_info.proposal_id();
-        let executed_state_id = self
-            .block_store
-            .get_compute_result(proposal_id)
-            .expect("Block proposed_block: no execution state found for inserted block.")
-            .executed_state
-            .state_id;
-
-        let ledger_info_placeholder = self
-            .block_store
-            .ledger_info_placeholder(vote_info.potential_commit_id());
-        Ok(VoteMsg::new(
-            VoteData::new(
-                proposal_id,
-                executed_state_id,
-                block.round(),
-                vote_info.parent_block_id(),
-                vote_info.parent_block_round(),
-                vote_info.grandparent_block_id(),
-                vote_info.grandparent_block_round(),
-            ),
-            self.author,
-            ledger_info_placeholder,
-            self.block_store.signer(),
-        ))
+        let block_store = &self.block_store;
+        let epoch = self.epoch_mgr.epoch();
+        let signer = block_store.signer_for_round(block.round());
+        self.safety_rules
+            .construct_and_sign_vote(
+                self.author,
+                executed_block.as_ref(),
+                &|potential_commit_id| {
+                    block_store.ledger_info_placeholder(potential_commit_id, epoch)
+                },
+                &signer,
+            )
+            .map_err(|e| format_err!("{}Rejected{} {}: {:?}", Fg(Red), Fg(Reset), block, e))
     }
 
     /// Upon new vote:
@@ -688,6 +1182,9 @@ impl<T: Payload> EventProcessor<T> {
     /// 2. Add the vote to the store and check whether it finishes a QC.
     /// 3. Once the QC successfully formed, notify the Pacemaker.
     pub async fn process_vote(&mut self, vote_msg: VoteMsg) {
+        // A vote far ahead of the current round shows that a quorum moved on without this node.
+        self.request_sync_info_if_lagging(vote_msg.author(), vote_msg.vote_data().block_round())
+            .await;
         // Check whether this validator is a valid recipient of the vote.
         let next_round = vote_msg.vote_data().block_round() + 1;
         if self
@@ -707,7 +1204,7 @@ impl<T: Payload> EventProcessor<T> {
             return;
         }
 
-        self.add_vote(vote_msg, self.epoch_mgr.quorum_size()).await;
+        self.add_vote(vote_msg).await;
     }
 
     /// Add a vote. Fetch missing dependencies if required.
@@ -718,34 +1215,133 @@ impl<T: Payload> EventProcessor<T> {
     /// 2) pass the new QC to the pacemaker, which can generate a new round in return.
     /// The function returns an Option for a newly generate QuorumCert in case it's been
     /// successfully added with all its dependencies.
-    async fn add_vote(&mut self, vote: VoteMsg, quorum_size: usize) -> Option<Arc<QuorumCert>> {
+    async fn add_vote(&mut self, vote: VoteMsg) -> Option<Arc<QuorumCert>> {
         let deadline = self.pacemaker.current_round_deadline();
         let preferred_peer = vote.author();
-        // TODO [Reconfiguration] Verify epoch of the vote message.
-        // Add the vote and check whether it completes a new QC.
-        if let VoteReceptionResult::NewQuorumCertificate(qc) =
-            self.block_store.insert_vote(vote, quorum_size)
+        self.log_event(ConsensusEvent::VoteReceived {
+            round: vote.vote_data().block_round(),
+            block_id: format!("{:x}", vote.vote_data().block_id()),
+            author: vote.author().short_str(),
+        });
+        let vote_round = vote.vote_data().block_round();
+        // The votes that were queued before a reconfiguration are stale.
+        let epoch = self.epoch_mgr.epoch();
+        if vote.ledger_info().epoch_num() != epoch {
+            debug!(
+                "Ignoring {} of epoch {} in epoch {}",
+                vote,
+                vote.ledger_info().epoch_num(),
+                epoch
+            );
+            return None;
+        }
+        if vote.author() != self.author {
+            self.qc_rebroadcaster
+                .lock()
+                .unwrap()
+                .observe_vote(vote.author(), vote_round);
+        }
+        if let Some(evidence) = self
+            .evidence_store
+            .check_vote(&vote, self.epoch_mgr.validators().as_ref())
         {
-            if self.block_store.need_fetch_for_quorum_cert(&qc) == NeedFetchResult::NeedFetch {
-                if let Err(e) = self
-                    .sync_manager
-                    .fetch_quorum_cert(qc.as_ref().clone(), preferred_peer, deadline)
-                    .await
-                {
-                    error!("Error syncing to qc {}: {:?}", qc, e);
-                    return None;
-                }
-            } else if let Err(e) = self
-                .block_store
-                .insert_single_quorum_cert(qc.as_ref().clone())
-            {
-                error!("Error inserting qc {}: {:?}", qc, e);
+            warn!("Equivocation detected: {}", evidence);
+        }
+        // Add the vote and check whether it completes a new QC. The signatures of the votes are
+        // verified by the network, whether the votes are sent on their own or piggybacked, and
+        // the restored votes are verified once read from the storage: only verified votes are
+        // persisted as pending votes.
+        let qc = match self
+            .block_store
+            .insert_verified_vote(vote, self.epoch_mgr.validators().as_ref())
+        {
+            VoteReceptionResult::NewQuorumCertificate(qc) => qc,
+            VoteReceptionResult::VoteAdded(_) | VoteReceptionResult::InvalidVotesDropped(_) => {
+                self.persist_pending_votes(vote_round);
                 return None;
             }
-            self.process_certificates(qc.as_ref(), None).await;
-            return Some(qc);
+            _ => return None,
         };
-        None
+        self.log_event(ConsensusEvent::QcFormed {
+            round: qc.certified_block_round(),
+            block_id: format!("{:x}", qc.certified_block_id()),
+        });
+        if self.block_store.need_fetch_for_quorum_cert(&qc) == NeedFetchResult::NeedFetch {
+            if let Err(e) = self
+                .sync_manager
+                .fetch_quorum_cert(qc.as_ref().clone(), preferred_peer, deadline)
+                .await
+            {
+                error!("Error syncing to qc {}: {:?}", qc, e);
+                return None;
+            }
+        } else if let Err(e) = self
+            .block_store
+            .insert_single_quorum_cert(qc.as_ref().clone())
+        {
+            error!("Error inserting qc {}: {:?}", qc, e);
+            return None;
+        }
+        self.process_certificates(qc.as_ref(), None).await;
+        Some(qc)
+    }
+
+    /// Adds the votes piggybacked by a proposal or a sync info, as the votes attached to the
+    /// timeouts: the recipient might complete a QC with them and its own vote.
+    async fn add_piggybacked_votes(&mut self, votes: Vec<VoteMsg>) {
+        let hqc_round = self
+            .block_store
+            .highest_quorum_cert()
+            .certified_block_round();
+        for vote in votes {
+            if vote.vote_data().block_round() > hqc_round {
+                counters::PIGGYBACKED_VOTES_RECEIVED_COUNT.inc();
+                self.add_vote(vote).await;
+            }
+        }
+    }
+
+    /// Persists the votes collected so far for the blocks of the given round, so that the QC can
+    /// still be formed if this node restarts before collecting the remaining votes. The votes are
+    /// written in the background.
+    fn persist_pending_votes(&self, round: Round) {
+        self.pending_votes_writer
+            .save(round, self.block_store.pending_votes(round));
+    }
+
+    /// Restores the last vote and the last timeout sent before a restart: they are sent again
+    /// instead of conflicting ones if their rounds are not over yet.
+    pub fn restore_round_state(
+        &mut self,
+        last_vote: Option<VoteMsg>,
+        last_timeout: Option<TimeoutMsg>,
+    ) {
+        if let Some(vote_msg) = last_vote {
+            let round = vote_msg.vote_data().block_round();
+            self.last_vote_sent = Some((vote_msg, round));
+        }
+        self.last_timeout_sent = last_timeout;
+    }
+
+    /// Re-inserts the votes persisted before a restart. The votes for the blocks that have been
+    /// certified since then are ignored, as the votes whose signature doesn't verify with the
+    /// validators of the current epoch.
+    pub async fn restore_pending_votes(&mut self, pending_votes: Vec<VoteMsg>) {
+        let hqc_round = self
+            .block_store
+            .highest_quorum_cert()
+            .certified_block_round();
+        let validators = self.epoch_mgr.validators();
+        for vote in pending_votes {
+            if vote.vote_data().block_round() <= hqc_round {
+                continue;
+            }
+            if let Err(e) = vote.verify(validators.as_ref()) {
+                warn!("Dropping the restored {}: {:?}", vote, e);
+                continue;
+            }
+            self.add_vote(vote).await;
+        }
     }
 
     /// Upon (potentially) new commit:
@@ -753,18 +1349,31 @@ impl<T: Payload> EventProcessor<T> {
     /// 1. Notify state computer with the finality proof.
     /// 2. After the state is finalized, update the txn manager with the status of the committed
     /// transactions.
-    /// 3. Prune the tree.
-    async fn process_commit(
-        &self,
-        block_id_to_commit: HashValue,
-        finality_proof: LedgerInfoWithSignatures,
-    ) {
+    /// 3. Prune the tree and the messages remembered for the detection of equivocations.
+    /// 4. In case one of the committed blocks reconfigures the validators, stop proposing and
+    /// voting until the processor of the next epoch takes over. The given QC is the proof of the
+    /// end of the epoch.
+    async fn process_commit(&mut self, block_id_to_commit: HashValue, commit_qc: &QuorumCert) {
         let block_to_commit = match self.block_store.get_block(block_id_to_commit) {
             Some(block) => block,
             None => {
                 return;
             }
         };
+        let finality_proof = commit_qc.ledger_info().clone();
+        let epoch = finality_proof.ledger_info().epoch_num();
+
+        // A node that synced past the reconfigured block has not executed it: the validators of
+        // the next epoch are taken from the finality proof.
+        if let Some(validators) = finality_proof.ledger_info().next_validator_set() {
+            if epoch == self.epoch_mgr.epoch() && self.pending_reconfiguration.is_none() {
+                self.pending_reconfiguration = Some(EpochChange::new(
+                    epoch + 1,
+                    validators.clone(),
+                    commit_qc.clone(),
+                ));
+            }
+        }
 
         // First make sure that this commit is new.
         if block_to_commit.round() <= self.block_store.root().round() {
@@ -778,68 +1387,73 @@ impl<T: Payload> EventProcessor<T> {
             block_to_commit.id()
         );
 
-        if let Err(e) = self.state_computer.commit(finality_proof).await {
-            // We assume that state computer cannot enter an inconsistent state that might
-            // violate safety of the protocol. Specifically, an executor service is going to panic
-            // if it fails to persist the commit requests, which would crash the whole process
-            // including consensus.
-            error!(
-                "Failed to persist commit, mempool will not be notified: {:?}",
-                e
-            );
-            return;
-        }
-        // At this moment the new state is persisted and we can notify the clients.
-        // Multiple blocks might be committed at once: notify about all the transactions in the
-        // path from the old root to the new root.
-        for committed in self
-            .block_store
-            .path_from_root(block_id_to_commit)
-            .unwrap_or_else(Vec::new)
-        {
-            if let Some(time_to_commit) = duration_since_epoch()
-                .checked_sub(Duration::from_micros(committed.timestamp_usecs()))
-            {
-                counters::CREATION_TO_COMMIT_S.observe_duration(time_to_commit);
+        // The blocks are ordered right away: the root of the tree moves to the committed block,
+        // while the commit stage persists the commit and notifies mempool in the background.
+        let (ordered_blocks, pruned_block_ids) = self.block_store.order_blocks(block_id_to_commit);
+        for committed in &ordered_blocks {
+            if committed.block().author() == Some(self.author) {
+                counters::COMMITTED_PROPOSALS_COUNT.inc();
             }
-            let compute_result = self
-                .block_store
-                .get_compute_result(committed.id())
-                .expect("Compute result of a pending block is unknown");
-            if let Err(e) = self
-                .txn_manager
-                .commit_txns(
-                    committed.get_payload(),
-                    compute_result.as_ref(),
-                    committed.timestamp_usecs(),
-                )
-                .await
-            {
-                error!("Failed to notify mempool: {:?}", e);
+            if let Some(validators) = &committed.compute_result().executed_state.validators {
+                info!(
+                    "Block {} reconfigures the validators of the next epoch: {}",
+                    committed, validators
+                );
+                self.pending_reconfiguration = Some(EpochChange::new(
+                    epoch + 1,
+                    validators.clone(),
+                    commit_qc.clone(),
+                ));
             }
         }
         counters::LAST_COMMITTED_ROUND.set(block_to_commit.round() as i64);
+        let commit_latency = self.latency_tracker.block_committed(
+            block_to_commit.round(),
+            block_to_commit.id(),
+            self.time_service.get_current_timestamp(),
+        );
+        if let Some(qc_to_commit) = commit_latency.qc_to_commit {
+            counters::QC_TO_COMMIT_S.observe_duration(qc_to_commit);
+            counters::LAST_QC_TO_COMMIT_MS.set(qc_to_commit.as_millis() as i64);
+        }
+        counters::ROUNDS_PER_COMMIT.observe(commit_latency.rounds_since_last_commit as f64);
+        counters::LAST_ROUNDS_PER_COMMIT.set(commit_latency.rounds_since_last_commit as i64);
         debug!("{}Committed{} {}", Fg(Blue), Fg(Reset), *block_to_commit);
         event!("committed",
             "block_id": block_to_commit.id().short_str(),
             "round": block_to_commit.round(),
             "parent_id": block_to_commit.parent_id().short_str(),
         );
-        self.block_store.prune_tree(block_to_commit.id());
+        // The ancestors committed along with the block are logged first.
+        for committed in ordered_blocks.iter().rev() {
+            self.log_event(ConsensusEvent::Committed {
+                round: committed.round(),
+                block_id: format!("{:x}", committed.id()),
+            });
+        }
+        self.evidence_store.prune(block_to_commit.round());
+        self.commit_pipeline
+            .commit(OrderedBlocks {
+                blocks: ordered_blocks,
+                pruned_block_ids,
+                finality_proof,
+            })
+            .await;
+        // The next epoch starts once the reconfiguration is committed.
+        if self.pending_reconfiguration.is_some() {
+            self.commit_pipeline.flush().await;
+        }
     }
 
     /// Retrieve a n chained blocks from the block store starting from
     /// an initial parent id, returning with <n (as many as possible) if
     /// id or its ancestors can not be found.
-    ///
-    /// The current version of the function is not really async, but keeping it this way for
-    /// future possible changes.
     pub async fn process_block_retrieval(&self, request: BlockRetrievalRequest<T>) {
         let mut blocks = vec![];
         let mut status = BlockRetrievalStatus::SUCCEEDED;
         let mut id = request.block_id;
         while (blocks.len() as u64) < request.num_blocks {
-            if let Some(executed_block) = self.block_store.get_block(id) {
+            if let Some(executed_block) = self.block_store.get_block_async(id).await {
                 id = executed_block.parent_id();
                 blocks.push(executed_block.block().clone());
             } else {
@@ -873,11 +1487,19 @@ impl<T: Payload> EventProcessor<T> {
             )
             .expect("Can not jump start a new round from existing certificates.");
         self.process_new_round_event(new_round_event).await;
+        if let Some(commit_heartbeat) = &self.commit_heartbeat {
+            commit_heartbeat.start();
+        }
     }
 
     /// Inspect the current consensus state.
-    #[cfg(test)]
     pub fn consensus_state(&self) -> ConsensusState {
         self.safety_rules.consensus_state()
     }
+
+    /// The change to the next epoch if a reconfiguration has been committed. Once it's taken,
+    /// the caller is expected to replace this processor with the one of the next epoch.
+    pub fn take_reconfiguration(&mut self) -> Option<EpochChange> {
+        self.pending_reconfiguration.take()
+    }
 }
@@ -1,9 +1,11 @@
 use crate::{
     chained_bft::{
-        block_storage::BlockStore,
+        block_storage::{BlockStore, PruningPolicy},
+        commit_pipeline::CommitPipeline,
         consensus_types::proposal_msg::{ProposalMsg, ProposalUncheckedSignatures},
         epoch_manager::EpochManager,
         event_processor::EventProcessor,
+        evidence::EvidenceStore,
         liveness::{
             pacemaker::{ExponentialTimeInterval, NewRoundEvent, NewRoundReason, Pacemaker},
             pacemaker_timeout_manager::HighestTimeoutCertificates,
@@ -13,6 +15,7 @@ use crate::{
         network::ConsensusNetworkImpl,
         persistent_storage::{PersistentStorage, RecoveryData},
         safety::safety_rules::SafetyRules,
+        sync_manager::BlockRetrievalPolicy,
         test_utils::{EmptyStateComputer, MockStorage, MockTransactionManager, TestPayload},
     },
     util::mock_time_service::SimulatedTimeService,
@@ -49,7 +52,7 @@ pub fn generate_corpus_proposal() -> Vec<u8> {
 // optimization for the fuzzer
 lazy_static! {
     static ref STATIC_RUNTIME: Runtime = Runtime::new().unwrap();
-    static ref FUZZING_SIGNER: ValidatorSigner = ValidatorSigner::from_int(1);
+    pub(crate) static ref FUZZING_SIGNER: ValidatorSigner = ValidatorSigner::from_int(1);
 }
 
 // helpers
@@ -66,7 +69,7 @@ fn build_empty_store(
         signer,
         Arc::new(EmptyStateComputer),
         true,
-        10, // max pruned blocks in mem
+        PruningPolicy::MaxBlocks(10),
     )))
 }
 
@@ -100,10 +103,14 @@ fn create_node_for_fuzzing() -> EventProcessor<TestPayload> {
 
     // TODO: EmptyStorage
     let (storage, initial_data) = MockStorage::<TestPayload>::start_for_testing();
-    let consensus_state = initial_data.state();
-
     // TODO: remove
-    let safety_rules = SafetyRules::new(consensus_state);
+    let safety_rules = Box::new(SafetyRules::new_with_storage(
+        storage
+            .persistent_safety_storage()
+            .consensus_state()
+            .unwrap(),
+        storage.persistent_safety_storage(),
+    ));
 
     // TODO: mock channels
     let (network_reqs_tx, _network_reqs_rx) = channel::new_test(8);
@@ -129,6 +136,8 @@ fn create_node_for_fuzzing() -> EventProcessor<TestPayload> {
         Arc::new(MockTransactionManager::new()),
         time_service.clone(),
         1,
+        u64::max_value(),
+        usize::max_value(),
         true,
     );
 
@@ -144,6 +153,14 @@ fn create_node_for_fuzzing() -> EventProcessor<TestPayload> {
     // We do not want to care about the time
     let enforce_increasing_timestamps = false;
 
+    let evidence_store = Arc::new(EvidenceStore::new(storage.clone(), vec![]));
+
+    let commit_pipeline = CommitPipeline::new(
+        Arc::clone(&block_store),
+        empty_state_computer.clone(),
+        Arc::new(MockTransactionManager::new()),
+    );
+
     // event processor
     EventProcessor::new(
         signer.author(),
@@ -153,12 +170,15 @@ fn create_node_for_fuzzing() -> EventProcessor<TestPayload> {
         proposal_generator,
         safety_rules,
         empty_state_computer,
-        Arc::new(MockTransactionManager::new()),
+        commit_pipeline,
         network,
         storage.clone(),
         time_service,
         enforce_increasing_timestamps,
         Arc::clone(&epoch_mgr),
+        None,
+        BlockRetrievalPolicy::default(),
+        evidence_store,
     )
 }
 
@@ -3,33 +3,39 @@
 
 use crate::{
     chained_bft::{
-        block_storage::{BlockReader, BlockStore},
+        block_storage::{BlockReader, BlockStore, PruningPolicy},
+        commit_pipeline::CommitPipeline,
         common::Author,
         consensus_types::{
             block::Block,
             proposal_msg::{ProposalMsg, ProposalUncheckedSignatures},
             quorum_cert::QuorumCert,
             sync_info::SyncInfo,
-            timeout_msg::{PacemakerTimeout, PacemakerTimeoutCertificate, TimeoutMsg},
+            timeout_certificate::TimeoutCertificate,
+            timeout_msg::{PacemakerTimeout, TimeoutMsg, TimeoutReason},
             vote_data::VoteData,
             vote_msg::VoteMsg,
         },
         epoch_manager::EpochManager,
         event_processor::EventProcessor,
+        evidence::EvidenceStore,
         liveness::{
             pacemaker::{ExponentialTimeInterval, NewRoundEvent, NewRoundReason, Pacemaker},
             pacemaker_timeout_manager::HighestTimeoutCertificates,
             proposal_generator::ProposalGenerator,
             proposer_election::ProposerElection,
             rotating_proposer_election::RotatingProposer,
+            timeout_retransmission::TimeoutRetransmissionPolicy,
         },
         network::{BlockRetrievalRequest, BlockRetrievalResponse, ConsensusNetworkImpl},
         network_tests::NetworkPlayground,
         persistent_storage::{PersistentStorage, RecoveryData},
         safety::safety_rules::{ConsensusState, SafetyRules},
+        sync_manager::BlockRetrievalPolicy,
         test_utils::{
             consensus_runtime, placeholder_certificate_for_block, placeholder_ledger_info,
-            MockStateComputer, MockStorage, MockTransactionManager, TestPayload, TreeInserter,
+            MockPayloadValidator, MockStateComputer, MockStorage, MockTransactionManager,
+            TestPayload, TreeInserter,
         },
     },
     state_replication::StateComputer,
@@ -41,13 +47,18 @@ use futures::{
     channel::{mpsc, oneshot},
     compat::Future01CompatExt,
     executor::block_on,
+    StreamExt,
 };
 use network::{
     proto::BlockRetrievalStatus,
     validator_network::{ConsensusNetworkEvents, ConsensusNetworkSender},
 };
 use proto_conv::FromProto;
-use std::{collections::HashMap, sync::Arc, time::Duration};
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 use tokio::runtime::TaskExecutor;
 use types::crypto_proxies::{LedgerInfoWithSignatures, ValidatorSigner, ValidatorVerifier};
 
@@ -75,7 +86,7 @@ impl NodeSetup {
             signer,
             state_computer,
             true,
-            10, // max pruned blocks in mem
+            PruningPolicy::MaxBlocks(10),
         )))
     }
 
@@ -154,7 +165,6 @@ impl NodeSetup {
             network_events,
             Arc::clone(&epoch_mgr),
         );
-        let consensus_state = initial_data.state();
 
         let (commit_cb_sender, _commit_cb_receiver) = mpsc::unbounded::<LedgerInfoWithSignatures>();
         let state_computer = Arc::new(MockStateComputer::new(
@@ -174,13 +184,27 @@ impl NodeSetup {
             Arc::new(MockTransactionManager::new()),
             time_service.clone(),
             1,
+            u64::max_value(),
+            usize::max_value(),
             true,
         );
-        let safety_rules = SafetyRules::new(consensus_state);
+        let safety_rules = Box::new(SafetyRules::new_with_storage(
+            storage
+                .persistent_safety_storage()
+                .consensus_state()
+                .unwrap(),
+            storage.persistent_safety_storage(),
+        ));
 
         let pacemaker = Self::create_pacemaker(time_service.clone());
 
         let proposer_election = Self::create_proposer_election(proposer_author);
+        let evidence_store = Arc::new(EvidenceStore::new(storage.clone(), vec![]));
+        let commit_pipeline = CommitPipeline::new(
+            Arc::clone(&block_store),
+            state_computer.clone(),
+            Arc::new(MockTransactionManager::new()),
+        );
         let mut event_processor = EventProcessor::new(
             author,
             Arc::clone(&block_store),
@@ -189,12 +213,15 @@ impl NodeSetup {
             proposal_generator,
             safety_rules,
             state_computer,
-            Arc::new(MockTransactionManager::new()),
+            commit_pipeline,
             network,
             storage.clone(),
             time_service,
             true,
             Arc::clone(&epoch_mgr),
+            None,
+            BlockRetrievalPolicy::default(),
+            evidence_store,
         );
         block_on(event_processor.start());
         Self {
@@ -361,6 +388,69 @@ fn process_successful_proposal_test() {
     });
 }
 
+#[test]
+/// The proposals whose payload is rejected by the payload validator are not voted for.
+fn process_proposal_rejected_by_payload_validator() {
+    let runtime = consensus_runtime();
+    let mut playground = NetworkPlayground::new(runtime.executor());
+    let mut nodes = NodeSetup::create_nodes(&mut playground, runtime.executor(), 2);
+    let mut node = nodes.pop().unwrap();
+    let proposer_signer = nodes[0].signer.clone();
+    let payload_validator = Arc::new(MockPayloadValidator::new(2));
+    node.event_processor = node
+        .event_processor
+        .with_payload_validator(payload_validator.clone());
+
+    let genesis = node.block_store.root();
+    let genesis_qc = QuorumCert::certificate_for_genesis();
+    let duplicate_txns = Block::make_block(
+        genesis.block(),
+        vec![1, 1],
+        1,
+        1,
+        genesis_qc.clone(),
+        &proposer_signer,
+    );
+    let too_many_txns = Block::make_block(
+        genesis.block(),
+        vec![1, 2, 3],
+        1,
+        2,
+        genesis_qc.clone(),
+        &proposer_signer,
+    );
+    let valid_block = Block::make_block(
+        genesis.block(),
+        vec![1, 2],
+        1,
+        3,
+        genesis_qc.clone(),
+        &proposer_signer,
+    );
+    let valid_block_id = valid_block.id();
+    block_on(async move {
+        node.event_processor
+            .process_proposed_block(duplicate_txns)
+            .await;
+        node.event_processor
+            .process_proposed_block(too_many_txns)
+            .await;
+        node.event_processor
+            .process_proposed_block(valid_block)
+            .await;
+        let votes = playground
+            .wait_for_messages(1, NetworkPlayground::votes_only)
+            .await
+            .into_iter()
+            .filter(|m| m.0 == node.author)
+            .map(|mut m| VoteMsg::from_proto(m.1.take_vote()).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(votes.len(), 1);
+        assert_eq!(votes[0].vote_data().block_id(), valid_block_id);
+        assert_eq!(payload_validator.num_validated(), 3);
+    });
+}
+
 #[test]
 /// If the proposal does not pass voting rules,
 /// No votes are sent, but the block is still added to the block tree.
@@ -626,8 +716,7 @@ fn process_timeout_certificate_test() {
         genesis_qc.clone(),
         node.block_store.signer(),
     );
-    let tc =
-        PacemakerTimeoutCertificate::new(1, vec![PacemakerTimeout::new(1, &node.signer, None)]);
+    let tc = TimeoutCertificate::new(1, vec![PacemakerTimeout::new(1, &node.signer, None)]);
     block_on(async move {
         let skip_round_proposal = ProposalMsg::<TestPayload>::new(
             block_skip_round,
@@ -694,6 +783,201 @@ fn process_votes_basic_test() {
     block_on(runtime.shutdown_now().compat()).unwrap();
 }
 
+#[test]
+/// A node receiving a vote far ahead of its current round requests the sync info of the voter,
+/// which responds with its sync info.
+fn sync_info_requested_on_lag() {
+    let runtime = consensus_runtime();
+    let mut playground = NetworkPlayground::new(runtime.executor());
+    let mut nodes = NodeSetup::create_nodes(&mut playground, runtime.executor(), 2);
+    let genesis = nodes[0].block_store.root();
+    let vote_data = VoteData::new(
+        HashValue::random(),
+        HashValue::random(),
+        10,
+        genesis.id(),
+        0,
+        genesis.id(),
+        0,
+    );
+    let vote_msg = VoteMsg::new(
+        vote_data,
+        nodes[1].author,
+        placeholder_ledger_info(),
+        &nodes[1].signer,
+    );
+    block_on(async move {
+        nodes[0].event_processor.process_vote(vote_msg).await;
+        let request = playground
+            .wait_for_messages(1, NetworkPlayground::sync_info_requests_only)
+            .await;
+        assert_eq!(request[0].0, nodes[0].author);
+
+        nodes[1]
+            .event_processor
+            .process_sync_info_request(nodes[0].author)
+            .await;
+        let sync_info = playground
+            .wait_for_messages(1, NetworkPlayground::sync_info_only)
+            .await;
+        assert_eq!(sync_info[0].0, nodes[1].author);
+    });
+}
+
+#[test]
+fn highest_qc_sent_to_lagging_peer() {
+    let runtime = consensus_runtime();
+    let mut playground = NetworkPlayground::new(runtime.executor());
+    let mut nodes = NodeSetup::create_nodes(&mut playground, runtime.executor(), 2);
+    let genesis = nodes[0].block_store.root();
+    let vote_data = VoteData::new(
+        HashValue::random(),
+        HashValue::random(),
+        1,
+        genesis.id(),
+        0,
+        genesis.id(),
+        0,
+    );
+    let vote_msg = VoteMsg::new(
+        vote_data,
+        nodes[1].author,
+        placeholder_ledger_info(),
+        &nodes[1].signer,
+    );
+    block_on(async move {
+        nodes[0].event_processor.process_vote(vote_msg).await;
+        // The last vote of the peer is in the previous round: it isn't behind.
+        nodes[0]
+            .event_processor
+            .process_new_round_event(NewRoundEvent {
+                round: 2,
+                reason: NewRoundReason::QCReady,
+                timeout: Duration::new(5, 0),
+            })
+            .await;
+        nodes[0]
+            .event_processor
+            .process_new_round_event(NewRoundEvent {
+                round: 5,
+                reason: NewRoundReason::QCReady,
+                timeout: Duration::new(5, 0),
+            })
+            .await;
+        let sync_info = playground
+            .wait_for_messages(1, NetworkPlayground::sync_info_only)
+            .await;
+        assert_eq!(sync_info[0].0, nodes[0].author);
+    });
+}
+
+#[test]
+/// The vote piggybacked on a sync info is added to the votes of the recipient.
+fn piggybacked_vote_on_sync_info() {
+    let runtime = consensus_runtime();
+    let mut playground = NetworkPlayground::new(runtime.executor());
+    let mut nodes = NodeSetup::create_nodes(&mut playground, runtime.executor(), 2);
+    let genesis = nodes[0].block_store.root();
+    let mut inserter = TreeInserter::new(nodes[0].block_store.clone());
+    let a1 = inserter.insert_block_with_qc(QuorumCert::certificate_for_genesis(), &genesis, 1);
+    let vote_data = VoteData::new(
+        a1.id(),
+        nodes[0]
+            .block_store
+            .get_compute_result(a1.id())
+            .unwrap()
+            .executed_state
+            .state_id,
+        a1.round(),
+        a1.quorum_cert().parent_block_id(),
+        a1.quorum_cert().parent_block_round(),
+        a1.quorum_cert().grandparent_block_id(),
+        a1.quorum_cert().grandparent_block_round(),
+    );
+    let vote_msg = VoteMsg::new(
+        vote_data,
+        nodes[1].author,
+        placeholder_ledger_info(),
+        &nodes[1].signer,
+    );
+    let sync_info = SyncInfo::new(
+        QuorumCert::certificate_for_genesis(),
+        QuorumCert::certificate_for_genesis(),
+        None,
+    )
+    .with_last_vote(vote_msg.clone());
+    block_on(async move {
+        nodes[0]
+            .event_processor
+            .process_sync_info_msg(sync_info, nodes[1].author)
+            .await;
+        assert_eq!(nodes[0].block_store.pending_votes(1), vec![vote_msg]);
+    });
+}
+
+#[test]
+/// The votes persisted before a restart are restored unless their signature doesn't verify, and
+/// the restored votes are persisted again in the background.
+fn restore_pending_votes_test() {
+    let runtime = consensus_runtime();
+    let mut playground = NetworkPlayground::new(runtime.executor());
+    let mut nodes = NodeSetup::create_nodes(&mut playground, runtime.executor(), 3);
+    let genesis = nodes[0].block_store.root();
+    let mut inserter = TreeInserter::new(nodes[0].block_store.clone());
+    let a1 = inserter.insert_block_with_qc(QuorumCert::certificate_for_genesis(), &genesis, 1);
+    let vote_data = VoteData::new(
+        a1.id(),
+        nodes[0]
+            .block_store
+            .get_compute_result(a1.id())
+            .unwrap()
+            .executed_state
+            .state_id,
+        a1.round(),
+        a1.quorum_cert().parent_block_id(),
+        a1.quorum_cert().parent_block_round(),
+        a1.quorum_cert().grandparent_block_id(),
+        a1.quorum_cert().grandparent_block_round(),
+    );
+    let vote_msg = VoteMsg::new(
+        vote_data.clone(),
+        nodes[1].author,
+        placeholder_ledger_info(),
+        &nodes[1].signer,
+    );
+    // A vote on behalf of the third validator, signed with another key.
+    let forged_vote_msg = VoteMsg::new(
+        vote_data,
+        nodes[2].author,
+        placeholder_ledger_info(),
+        &ValidatorSigner::random([42u8; 32]),
+    );
+    block_on(
+        nodes[0]
+            .event_processor
+            .restore_pending_votes(vec![vote_msg.clone(), forged_vote_msg]),
+    );
+    assert_eq!(
+        nodes[0].block_store.pending_votes(1),
+        vec![vote_msg.clone()]
+    );
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while *nodes[0]
+        .storage
+        .shared_storage
+        .pending_votes
+        .lock()
+        .unwrap()
+        != vec![vote_msg.clone()]
+    {
+        assert!(
+            Instant::now() < deadline,
+            "The pending votes were not persisted"
+        );
+        std::thread::sleep(Duration::from_millis(10));
+    }
+}
+
 #[test]
 fn process_block_retrieval() {
     let runtime = consensus_runtime();
@@ -850,3 +1134,152 @@ fn nil_vote_on_timeout() {
         assert_eq!(vote_msg.vote_data().parent_block_id(), genesis_id);
     });
 }
+
+#[test]
+/// The timeout messages report whether the round timed out without a proposal or without a QC.
+fn timeout_reason_test() {
+    let runtime = consensus_runtime();
+    let mut playground = NetworkPlayground::new(runtime.executor());
+    let mut nodes = NodeSetup::create_nodes(&mut playground, runtime.executor(), 2);
+    let genesis = nodes[1].block_store.root();
+    let genesis_qc = QuorumCert::certificate_for_genesis();
+    let proposal = Block::make_block(
+        genesis.block(),
+        vec![1],
+        1,
+        1,
+        genesis_qc.clone(),
+        nodes[1].block_store.signer(),
+    );
+    block_on(async move {
+        // Node 0 hasn't received anything in round 1.
+        nodes[0].event_processor.process_local_timeout(1).await;
+        // Node 1 voted for the proposal of round 1, but no QC was formed.
+        nodes[1]
+            .event_processor
+            .process_proposed_block(proposal)
+            .await;
+        nodes[1].event_processor.process_local_timeout(1).await;
+        let mut reasons = playground
+            .wait_for_messages(2, NetworkPlayground::timeout_msg_only)
+            .await
+            .into_iter()
+            .map(|mut m| {
+                let timeout_msg = TimeoutMsg::from_proto(m.1.take_timeout_msg()).unwrap();
+                (timeout_msg.author(), timeout_msg.reason())
+            })
+            .collect::<Vec<_>>();
+        reasons.sort_by_key(|(author, _)| *author != nodes[0].author);
+        assert_eq!(
+            reasons,
+            vec![
+                (nodes[0].author, TimeoutReason::NoProposal),
+                (nodes[1].author, TimeoutReason::NoQuorumCert),
+            ]
+        );
+    });
+}
+
+#[test]
+/// A timeout message lost by the network is retransmitted while the round doesn't advance.
+fn timeout_msg_retransmission() {
+    let runtime = consensus_runtime();
+    let mut playground = NetworkPlayground::new(runtime.executor());
+    let mut nodes = NodeSetup::create_nodes(&mut playground, runtime.executor(), 2);
+    let mut node = nodes.pop().unwrap();
+    let (retransmission_tx, mut retransmission_rx) = channel::new_test(8);
+    node.event_processor = node.event_processor.with_timeout_retransmission(
+        TimeoutRetransmissionPolicy {
+            interval: Duration::from_millis(10),
+            max_jitter: Duration::from_millis(10),
+            max_retransmissions: 2,
+        },
+        retransmission_tx,
+    );
+    // The first timeout broadcast is lost.
+    let mut dropped = false;
+    playground.add_mutator(Box::new(move |_, _, msg| {
+        if !dropped && msg.has_timeout_msg() {
+            dropped = true;
+            msg.clear_timeout_msg();
+        }
+        vec![]
+    }));
+    block_on(async move {
+        node.event_processor.process_local_timeout(1).await;
+        let round = retransmission_rx.next().await.unwrap();
+        assert_eq!(round, 1);
+        node.event_processor
+            .process_timeout_retransmission(round)
+            .await;
+        let timeout_msg = TimeoutMsg::from_proto(
+            playground
+                .wait_for_messages(1, NetworkPlayground::timeout_msg_only)
+                .await[0]
+                .1
+                .take_timeout_msg(),
+        )
+        .unwrap();
+        assert_eq!(timeout_msg.author(), node.author);
+        assert_eq!(timeout_msg.pacemaker_timeout().round(), 1);
+    });
+}
+
+#[test]
+/// The highest ledger info carried by a proposal commits the blocks this replica hasn't
+/// committed yet, even though it never formed the corresponding 3-chain itself.
+fn commit_highest_ledger_info_test() {
+    let runtime = consensus_runtime();
+    let mut playground = NetworkPlayground::new(runtime.executor());
+    let mut nodes = NodeSetup::create_nodes(&mut playground, runtime.executor(), 1);
+    let node = &mut nodes[0];
+    let genesis = node.block_store.root();
+    let mut inserter = TreeInserter::new(node.block_store.clone());
+    let a1 = inserter.insert_block(&genesis, 1);
+    let a2 = inserter.insert_block(&a1, 2);
+    let a3 = inserter.insert_block(&a2, 3);
+
+    let vote = VoteMsg::new(
+        VoteData::new(
+            a3.id(),
+            node.block_store
+                .get_compute_result(a3.id())
+                .unwrap()
+                .executed_state
+                .state_id,
+            a3.round(),
+            a2.id(),
+            a2.round(),
+            a1.id(),
+            a1.round(),
+        ),
+        node.author,
+        node.block_store.ledger_info_placeholder(Some(a1.id()), 0),
+        node.block_store.signer(),
+    );
+    let mut li_with_sig = LedgerInfoWithSignatures::new(vote.ledger_info().clone(), HashMap::new());
+    vote.signature()
+        .clone()
+        .add_to_li(node.author, &mut li_with_sig);
+    let highest_ledger_info = QuorumCert::new(vote.vote_data().clone(), li_with_sig);
+    assert_eq!(highest_ledger_info.committed_block_id(), Some(a1.id()));
+
+    block_on(async move {
+        node.event_processor
+            .commit_highest_ledger_info(&highest_ledger_info)
+            .await;
+        assert_eq!(node.block_store.root().id(), a1.id());
+        // The finality proof of the new root is the latest commit certificate.
+        assert_eq!(
+            &node.block_store.latest_commit_cert(),
+            highest_ledger_info.ledger_info()
+        );
+        // The ledger info QC is persisted such that the new root can be recovered.
+        assert!(node
+            .block_store
+            .get_quorum_cert_for_block(a3.id())
+            .is_some());
+        let ((recovered_root, _, _), _, _) = node.storage.get_recovery_data().unwrap().take();
+        assert_eq!(recovered_root.id(), a1.id());
+    });
+}
@@ -0,0 +1,251 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Detection of equivocations in consensus.
+//!
+//! An honest validator proposes at most one block and votes at most once per round (the safety
+//! rules guarantee it even across restarts). Two conflicting messages signed by the same author
+//! for the same round are therefore a proof of misbehavior: the `EvidenceStore` remembers the
+//! proposals and votes received in the recent rounds, and records (and persists) the two signed
+//! messages whenever an author equivocates. The collected evidence can be queried by the
+//! operators, or consumed by slashing logic in the future.
+//!
+//! A message in the name of an author only proves something if it is signed by the author: the
+//! signatures of the two conflicting messages are verified before any evidence is recorded, as
+//! the votes reach the store before their signatures are verified in batch.
+
+use crate::{
+    chained_bft::{
+        common::{Author, Payload, Round},
+        consensus_types::{block::Block, vote_msg::VoteMsg},
+        persistent_storage::PersistentStorage,
+    },
+    counters,
+};
+use crypto::hash::CryptoHash;
+use logger::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::{Display, Formatter},
+    mem,
+    sync::{Arc, Mutex},
+};
+use types::crypto_proxies::ValidatorVerifier;
+
+#[cfg(test)]
+#[path = "evidence_test.rs"]
+mod evidence_test;
+
+/// Two conflicting messages signed by the same author for the same round.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum Evidence<T> {
+    /// Two different blocks proposed by the same author in the same round.
+    DoubleProposal(Block<T>, Block<T>),
+    /// Two votes of the same author for different blocks of the same round.
+    DoubleVote(VoteMsg, VoteMsg),
+}
+
+impl<T: Payload> Evidence<T> {
+    /// The author of the conflicting messages.
+    pub fn author(&self) -> Author {
+        match self {
+            Evidence::DoubleProposal(first, _) => first
+                .author()
+                .expect("The blocks of a double proposal have an author"),
+            Evidence::DoubleVote(first, _) => first.author(),
+        }
+    }
+
+    /// The round of the conflicting messages.
+    pub fn round(&self) -> Round {
+        match self {
+            Evidence::DoubleProposal(first, _) => first.round(),
+            Evidence::DoubleVote(first, _) => first.vote_data().block_round(),
+        }
+    }
+
+    // The same author might send many conflicting messages in the same round, a single evidence
+    // per kind of equivocation is recorded.
+    fn is_same_equivocation(&self, other: &Self) -> bool {
+        mem::discriminant(self) == mem::discriminant(other)
+            && self.author() == other.author()
+            && self.round() == other.round()
+    }
+}
+
+impl<T: Payload> Display for Evidence<T> {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            Evidence::DoubleProposal(first, second) => write!(
+                f,
+                "[double proposal of {} in round {}: {} and {}]",
+                self.author().short_str(),
+                self.round(),
+                first.id().short_str(),
+                second.id().short_str()
+            ),
+            Evidence::DoubleVote(first, second) => write!(
+                f,
+                "[double vote of {} in round {}: {} and {}]",
+                self.author().short_str(),
+                self.round(),
+                first.vote_data().block_id().short_str(),
+                second.vote_data().block_id().short_str()
+            ),
+        }
+    }
+}
+
+/// Records the evidence of the equivocations of the other validators.
+///
+/// The store only remembers the first proposal and the first vote of every author in the rounds
+/// that haven't been pruned yet, the collected evidence is kept forever.
+pub struct EvidenceStore<T> {
+    storage: Arc<dyn PersistentStorage<T>>,
+    inner: Mutex<EvidenceStoreInner<T>>,
+}
+
+struct EvidenceStoreInner<T> {
+    proposals: BTreeMap<Round, HashMap<Author, Block<T>>>,
+    votes: BTreeMap<Round, HashMap<Author, VoteMsg>>,
+    evidence: Vec<Evidence<T>>,
+}
+
+impl<T: Payload> EvidenceStore<T> {
+    /// Creates a store with the evidence recovered from the persistent storage.
+    pub fn new(storage: Arc<dyn PersistentStorage<T>>, evidence: Vec<Evidence<T>>) -> Self {
+        // Our counters are initialized via lazy_static, so they're not going to appear in
+        // Prometheus if some conditions never happen.  Invoking get() function enforces creation.
+        counters::EQUIVOCATION_COUNT.get();
+        Self {
+            storage,
+            inner: Mutex::new(EvidenceStoreInner {
+                proposals: BTreeMap::new(),
+                votes: BTreeMap::new(),
+                evidence,
+            }),
+        }
+    }
+
+    /// Checks the given proposal against the previous proposals of its author in the same round.
+    /// Returns the evidence in case the author proposed a different block before, and both
+    /// blocks are signed by the author.
+    pub fn check_proposal(
+        &self,
+        proposal: &Block<T>,
+        validator: &ValidatorVerifier,
+    ) -> Option<Evidence<T>> {
+        // The NIL blocks are not signed.
+        let author = proposal.author()?;
+        let mut inner = self.inner.lock().unwrap();
+        let proposals = inner
+            .proposals
+            .entry(proposal.round())
+            .or_insert_with(HashMap::new);
+        let first = proposals
+            .entry(author)
+            .or_insert_with(|| proposal.clone())
+            .clone();
+        if first.id() == proposal.id() || !is_signed_proposal(proposal, validator) {
+            return None;
+        }
+        if !is_signed_proposal(&first, validator) {
+            // The first block was forged, the signed block takes its place.
+            proposals.insert(author, proposal.clone());
+            return None;
+        }
+        let evidence = Evidence::DoubleProposal(first, proposal.clone());
+        self.record(&mut inner, evidence)
+    }
+
+    /// Checks the given vote against the previous votes of its author in the same round.
+    /// Returns the evidence in case the author voted for a different block before, and both votes
+    /// are signed by the author. The signatures are only verified upon a conflict.
+    pub fn check_vote(&self, vote: &VoteMsg, validator: &ValidatorVerifier) -> Option<Evidence<T>> {
+        let mut inner = self.inner.lock().unwrap();
+        let votes = inner
+            .votes
+            .entry(vote.vote_data().block_round())
+            .or_insert_with(HashMap::new);
+        let first = votes
+            .entry(vote.author())
+            .or_insert_with(|| vote.clone())
+            .clone();
+        if first.vote_data().block_id() == vote.vote_data().block_id()
+            || vote.verify(validator).is_err()
+        {
+            return None;
+        }
+        if first.verify(validator).is_err() {
+            // The first vote was forged, the signed vote takes its place.
+            votes.insert(vote.author(), vote.clone());
+            return None;
+        }
+        let evidence = Evidence::DoubleVote(first, vote.clone());
+        self.record(&mut inner, evidence)
+    }
+
+    /// Forgets the proposals and votes of the rounds lower than the given one: the equivocations
+    /// in these rounds are not detected anymore.
+    pub fn prune(&self, min_round: Round) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.proposals = inner.proposals.split_off(&min_round);
+        inner.votes = inner.votes.split_off(&min_round);
+    }
+
+    /// All the evidence collected so far, in the order of detection.
+    pub fn evidence(&self) -> Vec<Evidence<T>> {
+        self.inner.lock().unwrap().evidence.clone()
+    }
+
+    /// The evidence collected so far against the given author.
+    pub fn evidence_of(&self, author: Author) -> Vec<Evidence<T>> {
+        self.inner
+            .lock()
+            .unwrap()
+            .evidence
+            .iter()
+            .filter(|evidence| evidence.author() == author)
+            .cloned()
+            .collect()
+    }
+
+    // Records the given evidence, the signatures of its messages have been verified.
+    fn record(
+        &self,
+        inner: &mut EvidenceStoreInner<T>,
+        evidence: Evidence<T>,
+    ) -> Option<Evidence<T>> {
+        if inner
+            .evidence
+            .iter()
+            .any(|recorded| recorded.is_same_equivocation(&evidence))
+        {
+            return None;
+        }
+        security_log(SecurityEvent::ConsensusEquivocation)
+            .error("Equivocation")
+            .data(&evidence)
+            .log();
+        counters::EQUIVOCATION_COUNT.inc();
+        inner.evidence.push(evidence.clone());
+        if let Err(e) = self.storage.save_evidence(inner.evidence.clone()) {
+            error!("Failed to persist the evidence {}: {:?}", evidence, e);
+        }
+        Some(evidence)
+    }
+}
+
+fn is_signed_proposal<T: Payload>(proposal: &Block<T>, validator: &ValidatorVerifier) -> bool {
+    match (proposal.author(), proposal.signature()) {
+        (Some(author), Some(signature)) => signature
+            .verify(
+                &validator.at_round(proposal.round()),
+                author,
+                proposal.hash(),
+            )
+            .is_ok(),
+        _ => false,
+    }
+}
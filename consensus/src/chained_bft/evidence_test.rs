@@ -0,0 +1,171 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::chained_bft::{
+    common::Round,
+    consensus_types::{
+        block::Block, quorum_cert::QuorumCert, vote_data::VoteData, vote_msg::VoteMsg,
+    },
+    evidence::{Evidence, EvidenceStore},
+    test_utils::{placeholder_ledger_info, MockStorage, TestPayload},
+};
+use crypto::HashValue;
+use executor::ExecutedState;
+use std::sync::Arc;
+use types::crypto_proxies::{ValidatorSigner, ValidatorVerifier};
+
+fn vote_for(block_id: HashValue, round: Round, signer: &ValidatorSigner) -> VoteMsg {
+    forged_vote_for(block_id, round, signer, signer)
+}
+
+// A vote in the name of `signer`, signed by `forger`.
+fn forged_vote_for(
+    block_id: HashValue,
+    round: Round,
+    signer: &ValidatorSigner,
+    forger: &ValidatorSigner,
+) -> VoteMsg {
+    VoteMsg::new(
+        VoteData::new(
+            block_id,
+            ExecutedState::state_for_genesis().state_id,
+            round,
+            HashValue::random(),
+            round - 1,
+            HashValue::random(),
+            round - 2,
+        ),
+        signer.author(),
+        placeholder_ledger_info(),
+        forger,
+    )
+}
+
+fn verifier_for(signers: &[&ValidatorSigner]) -> ValidatorVerifier {
+    ValidatorVerifier::new(
+        signers
+            .iter()
+            .map(|signer| (signer.author(), signer.public_key()))
+            .collect(),
+    )
+}
+
+#[test]
+fn test_double_proposal() {
+    let (storage, _) = MockStorage::<TestPayload>::start_for_testing();
+    let evidence_store = EvidenceStore::new(storage.clone(), vec![]);
+    let signer = ValidatorSigner::random([0u8; 32]);
+    let other_signer = ValidatorSigner::random([1u8; 32]);
+    let genesis = Block::make_genesis_block();
+    let qc = QuorumCert::certificate_for_genesis();
+    let make_block =
+        |payload, signer| Block::make_block(&genesis, vec![payload], 1, 1, qc.clone(), signer);
+    let validator = verifier_for(&[&signer, &other_signer]);
+    let first = make_block(1, &signer);
+
+    // The same block can be delivered again, and other authors can propose in the same round.
+    assert_eq!(evidence_store.check_proposal(&first, &validator), None);
+    assert_eq!(evidence_store.check_proposal(&first, &validator), None);
+    assert_eq!(
+        evidence_store.check_proposal(&make_block(2, &other_signer), &validator),
+        None
+    );
+
+    let second = make_block(2, &signer);
+    let evidence = Evidence::DoubleProposal(first.clone(), second.clone());
+    assert_eq!(
+        evidence_store.check_proposal(&second, &validator),
+        Some(evidence.clone())
+    );
+    // A single evidence is recorded for the same equivocation.
+    assert_eq!(
+        evidence_store.check_proposal(&make_block(3, &signer), &validator),
+        None
+    );
+    assert_eq!(evidence_store.evidence(), vec![evidence.clone()]);
+    assert_eq!(
+        evidence_store.evidence_of(signer.author()),
+        vec![evidence.clone()]
+    );
+    assert!(evidence_store.evidence_of(other_signer.author()).is_empty());
+    assert_eq!(
+        *storage.shared_storage.evidence.lock().unwrap(),
+        vec![evidence.clone()]
+    );
+
+    // The evidence is recovered after a restart.
+    let recovered_store = EvidenceStore::new(
+        storage.clone(),
+        storage.get_recovery_data().unwrap().evidence().to_vec(),
+    );
+    assert_eq!(recovered_store.evidence(), vec![evidence]);
+}
+
+#[test]
+fn test_double_vote() {
+    let (storage, _) = MockStorage::<TestPayload>::start_for_testing();
+    let evidence_store = EvidenceStore::new(storage, vec![]);
+    let signer = ValidatorSigner::random([0u8; 32]);
+    let validator = verifier_for(&[&signer]);
+    let first = vote_for(HashValue::random(), 3, &signer);
+
+    assert_eq!(evidence_store.check_vote(&first, &validator), None);
+    assert_eq!(evidence_store.check_vote(&first, &validator), None);
+    assert_eq!(
+        evidence_store.check_vote(&vote_for(HashValue::random(), 4, &signer), &validator),
+        None
+    );
+
+    let second = vote_for(HashValue::random(), 3, &signer);
+    assert_eq!(
+        evidence_store.check_vote(&second, &validator),
+        Some(Evidence::DoubleVote(first, second))
+    );
+
+    // The equivocations in the pruned rounds are not detected anymore.
+    evidence_store.prune(5);
+    assert_eq!(
+        evidence_store.check_vote(&vote_for(HashValue::random(), 4, &signer), &validator),
+        None
+    );
+    assert_eq!(evidence_store.evidence().len(), 1);
+}
+
+#[test]
+fn test_forged_double_vote() {
+    let (storage, _) = MockStorage::<TestPayload>::start_for_testing();
+    let evidence_store = EvidenceStore::new(storage.clone(), vec![]);
+    let signer = ValidatorSigner::random([0u8; 32]);
+    let forger = ValidatorSigner::random([1u8; 32]);
+    let validator = verifier_for(&[&signer, &forger]);
+    let first = vote_for(HashValue::random(), 3, &signer);
+    assert_eq!(evidence_store.check_vote(&first, &validator), None);
+
+    // A conflicting vote that is not signed by its author is not an evidence.
+    let forged = forged_vote_for(HashValue::random(), 3, &signer, &forger);
+    assert_eq!(evidence_store.check_vote(&forged, &validator), None);
+
+    // A forged vote received first does not frame its author either: it is replaced by the
+    // signed vote.
+    let forged = forged_vote_for(HashValue::random(), 4, &signer, &forger);
+    assert_eq!(evidence_store.check_vote(&forged, &validator), None);
+    let signed = vote_for(HashValue::random(), 4, &signer);
+    assert_eq!(evidence_store.check_vote(&signed, &validator), None);
+    assert_eq!(
+        evidence_store.check_vote(
+            &forged_vote_for(HashValue::random(), 4, &signer, &forger),
+            &validator
+        ),
+        None
+    );
+
+    assert!(evidence_store.evidence().is_empty());
+    assert!(storage.shared_storage.evidence.lock().unwrap().is_empty());
+
+    // The author is still caught once it signs a conflicting vote.
+    let second = vote_for(HashValue::random(), 4, &signer);
+    assert_eq!(
+        evidence_store.check_vote(&second, &validator),
+        Some(Evidence::DoubleVote(signed, second))
+    );
+}
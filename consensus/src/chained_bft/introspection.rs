@@ -0,0 +1,116 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Live view of the internal state of consensus for the debug interface.
+//!
+//! The block tree is read from the block store when the view is requested, while the state of
+//! the round (which is owned by the event processor) is published by the event processor every
+//! time it enters a new round or votes.
+
+use crate::chained_bft::{
+    block_storage::{BlockReader, BlockStore},
+    common::{Author, Payload, Round},
+    consensus_types::block::ExecutedBlock,
+    epoch_manager::EpochManager,
+};
+use crypto::HashValue;
+use debug_interface::{
+    consensus_state::{BlockView, ConsensusStateView, VoteView},
+    node_debug_service::ConsensusStateProvider,
+};
+use std::{
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+#[cfg(test)]
+#[path = "introspection_test.rs"]
+mod introspection_test;
+
+#[derive(Default)]
+struct RoundState {
+    current_round: Round,
+    round_deadline: Option<Duration>,
+    proposer: Option<Author>,
+    last_vote: Option<(HashValue, Round)>,
+}
+
+/// Shared by the event processors of all the epochs and the debug interface.
+pub struct ConsensusIntrospector<T> {
+    block_store: Arc<BlockStore<T>>,
+    epoch_mgr: Arc<EpochManager>,
+    round_state: RwLock<RoundState>,
+}
+
+impl<T: Payload> ConsensusIntrospector<T> {
+    pub fn new(block_store: Arc<BlockStore<T>>, epoch_mgr: Arc<EpochManager>) -> Self {
+        Self {
+            block_store,
+            epoch_mgr,
+            round_state: RwLock::new(RoundState::default()),
+        }
+    }
+
+    /// Publishes the new round, its deadline (since the Unix epoch) and its primary proposer.
+    pub fn new_round(&self, round: Round, deadline: Duration, proposer: Option<Author>) {
+        let mut round_state = self.round_state.write().unwrap();
+        round_state.current_round = round;
+        round_state.round_deadline = Some(deadline);
+        round_state.proposer = proposer;
+    }
+
+    /// Publishes the last vote sent by the node.
+    pub fn voted(&self, block_id: HashValue, round: Round) {
+        self.round_state.write().unwrap().last_vote = Some((block_id, round));
+    }
+
+    fn block_view(&self, block: &ExecutedBlock<T>) -> BlockView {
+        BlockView {
+            id: format!("{:x}", block.id()),
+            parent_id: format!("{:x}", block.parent_id()),
+            round: block.round(),
+            author: block.author().map(|author| format!("{:x}", author)),
+            certified: self
+                .block_store
+                .get_quorum_cert_for_block(block.id())
+                .is_some(),
+        }
+    }
+}
+
+impl<T: Payload> ConsensusStateProvider for ConsensusIntrospector<T> {
+    fn consensus_state(&self) -> ConsensusStateView {
+        let root = self.block_store.root();
+        let mut pending_blocks: Vec<_> = self
+            .block_store
+            .tree_blocks()
+            .into_iter()
+            .filter(|block| block.id() != root.id())
+            .collect();
+        pending_blocks.sort_by_key(|block| block.round());
+        let round_state = self.round_state.read().unwrap();
+        ConsensusStateView {
+            epoch: self.epoch_mgr.epoch(),
+            current_round: round_state.current_round,
+            round_deadline_ms: round_state
+                .round_deadline
+                .map(|deadline| deadline.as_millis() as u64),
+            proposer: round_state
+                .proposer
+                .map(|proposer| format!("{:x}", proposer)),
+            hqc_round: self
+                .block_store
+                .highest_quorum_cert()
+                .certified_block_round(),
+            root: self.block_view(&root),
+            pending_blocks: pending_blocks
+                .iter()
+                .map(|block| self.block_view(block))
+                .collect(),
+            last_vote: round_state.last_vote.map(|(block_id, round)| VoteView {
+                block_id: format!("{:x}", block_id),
+                round,
+            }),
+        }
+    }
+}
@@ -0,0 +1,52 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::chained_bft::{
+    block_storage::BlockReader,
+    consensus_types::quorum_cert::QuorumCert,
+    epoch_manager::EpochManager,
+    introspection::ConsensusIntrospector,
+    test_utils::{build_empty_tree, TreeInserter},
+};
+use debug_interface::node_debug_service::ConsensusStateProvider;
+use std::{sync::Arc, time::Duration};
+use types::crypto_proxies::{ValidatorSigner, ValidatorVerifier};
+
+#[test]
+fn test_consensus_state() {
+    let signer = ValidatorSigner::random(None);
+    let epoch_mgr = Arc::new(EpochManager::new(
+        0,
+        ValidatorVerifier::new_single(signer.author(), signer.public_key()),
+    ));
+    let block_tree = build_empty_tree();
+    let mut inserter = TreeInserter::new(block_tree.clone());
+    let genesis = block_tree.root();
+    let a1 = inserter.insert_block_with_qc(QuorumCert::certificate_for_genesis(), &genesis, 1);
+    let a2 = inserter.insert_block(&a1, 2);
+    let introspector = ConsensusIntrospector::new(block_tree, epoch_mgr);
+
+    let state = introspector.consensus_state();
+    assert_eq!(state.current_round, 0);
+    assert_eq!(state.round_deadline_ms, None);
+    assert_eq!(state.hqc_round, 1);
+    assert_eq!(state.root.round, 0);
+    assert_eq!(
+        state
+            .pending_blocks
+            .iter()
+            .map(|block| (block.round, block.certified))
+            .collect::<Vec<_>>(),
+        vec![(1, true), (2, false)]
+    );
+    assert_eq!(state.pending_blocks[1].id, format!("{:x}", a2.id()));
+    assert_eq!(state.last_vote, None);
+
+    introspector.voted(a2.id(), 2);
+    introspector.new_round(3, Duration::from_millis(1_000), Some(signer.author()));
+    let state = introspector.consensus_state();
+    assert_eq!(state.current_round, 3);
+    assert_eq!(state.round_deadline_ms, Some(1_000));
+    assert_eq!(state.proposer, Some(format!("{:x}", signer.author())));
+    assert_eq!(state.last_vote.unwrap().round, 2);
+}
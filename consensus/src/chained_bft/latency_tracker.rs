@@ -0,0 +1,97 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::chained_bft::common::Round;
+use crypto::HashValue;
+use std::{collections::BTreeMap, time::Duration};
+
+#[cfg(test)]
+#[path = "latency_tracker_test.rs"]
+mod latency_tracker_test;
+
+/// The latencies reported when a block is committed.
+#[derive(Debug, PartialEq)]
+pub struct CommitLatency {
+    /// The time since the QC of the committed block was observed, None if it wasn't observed
+    /// (e.g., the block was retrieved from a peer together with its QC).
+    pub qc_to_commit: Option<Duration>,
+    /// The number of rounds since the previous commit.
+    pub rounds_since_last_commit: Round,
+}
+
+/// Measures the time a block spends in each stage of the protocol as observed by this replica:
+/// from the receipt of its proposal to the vote, from the vote to its QC, and from its QC to its
+/// commit. All the times are given by the caller (see `TimeService::get_current_timestamp`).
+pub struct LatencyTracker {
+    // The proposal being processed and the time it was received.
+    pending_proposal: Option<(HashValue, Duration)>,
+    // The block of the last vote and the time the vote was sent.
+    last_vote: Option<(HashValue, Duration)>,
+    // The certified blocks that are not committed yet and the time their QC was observed.
+    certified_blocks: BTreeMap<Round, (HashValue, Duration)>,
+    last_committed_round: Round,
+}
+
+impl LatencyTracker {
+    pub fn new(root_round: Round) -> Self {
+        Self {
+            pending_proposal: None,
+            last_vote: None,
+            certified_blocks: BTreeMap::new(),
+            last_committed_round: root_round,
+        }
+    }
+
+    pub fn proposal_received(&mut self, block_id: HashValue, now: Duration) {
+        self.pending_proposal = Some((block_id, now));
+    }
+
+    /// Returns the time since the proposal of the voted block was received.
+    pub fn vote_sent(&mut self, block_id: HashValue, now: Duration) -> Option<Duration> {
+        self.last_vote = Some((block_id, now));
+        match self.pending_proposal.take() {
+            Some((proposal_id, received)) if proposal_id == block_id => now.checked_sub(received),
+            _ => None,
+        }
+    }
+
+    /// Returns the time since the vote for the certified block was sent, only the first QC of a
+    /// block is reported.
+    pub fn block_certified(
+        &mut self,
+        round: Round,
+        block_id: HashValue,
+        now: Duration,
+    ) -> Option<Duration> {
+        if round <= self.last_committed_round || self.certified_blocks.contains_key(&round) {
+            return None;
+        }
+        self.certified_blocks.insert(round, (block_id, now));
+        match self.last_vote {
+            Some((vote_block_id, sent)) if vote_block_id == block_id => now.checked_sub(sent),
+            _ => None,
+        }
+    }
+
+    /// Reports the latencies of a new commit and forgets the blocks it commits.
+    pub fn block_committed(
+        &mut self,
+        round: Round,
+        block_id: HashValue,
+        now: Duration,
+    ) -> CommitLatency {
+        let qc_to_commit = match self.certified_blocks.get(&round) {
+            Some((certified_id, certified)) if *certified_id == block_id => {
+                now.checked_sub(*certified)
+            }
+            _ => None,
+        };
+        let rounds_since_last_commit = round.saturating_sub(self.last_committed_round);
+        self.last_committed_round = std::cmp::max(self.last_committed_round, round);
+        self.certified_blocks = self.certified_blocks.split_off(&(round + 1));
+        CommitLatency {
+            qc_to_commit,
+            rounds_since_last_commit,
+        }
+    }
+}
@@ -0,0 +1,56 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::chained_bft::latency_tracker::{CommitLatency, LatencyTracker};
+use crypto::HashValue;
+use std::time::Duration;
+
+fn ms(millis: u64) -> Duration {
+    Duration::from_millis(millis)
+}
+
+#[test]
+fn test_block_stages() {
+    let mut tracker = LatencyTracker::new(0);
+    let b1 = HashValue::random();
+    tracker.proposal_received(b1, ms(100));
+    assert_eq!(tracker.vote_sent(b1, ms(130)), Some(ms(30)));
+    assert_eq!(tracker.block_certified(1, b1, ms(180)), Some(ms(50)));
+    // A QC received again doesn't count.
+    assert_eq!(tracker.block_certified(1, b1, ms(200)), None);
+    assert_eq!(
+        tracker.block_committed(1, b1, ms(500)),
+        CommitLatency {
+            qc_to_commit: Some(ms(320)),
+            rounds_since_last_commit: 1,
+        }
+    );
+    // The committed rounds are not tracked anymore.
+    assert_eq!(tracker.block_certified(1, b1, ms(600)), None);
+}
+
+#[test]
+fn test_unobserved_stages() {
+    let mut tracker = LatencyTracker::new(0);
+    let b1 = HashValue::random();
+    let b2 = HashValue::random();
+    tracker.proposal_received(b1, ms(100));
+    // Voting for another block (e.g., a NIL block after a timeout).
+    assert_eq!(tracker.vote_sent(b2, ms(200)), None);
+    assert_eq!(tracker.block_certified(1, b1, ms(300)), None);
+    tracker.block_certified(3, b2, ms(400));
+    assert_eq!(
+        tracker.block_committed(2, HashValue::random(), ms(500)),
+        CommitLatency {
+            qc_to_commit: None,
+            rounds_since_last_commit: 2,
+        }
+    );
+    assert_eq!(
+        tracker.block_committed(3, b2, ms(600)),
+        CommitLatency {
+            qc_to_commit: Some(ms(200)),
+            rounds_since_last_commit: 1,
+        }
+    );
+}
@@ -0,0 +1,96 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::util::time_service::{SendTask, TimeService};
+use channel;
+use std::{sync::Arc, time::Duration};
+
+#[cfg(test)]
+#[path = "commit_heartbeat_test.rs"]
+mod commit_heartbeat_test;
+
+/// How often a node broadcasts its highest commit certificate while it receives no proposals.
+#[derive(Clone, Debug)]
+pub struct CommitHeartbeatPolicy {
+    /// Minimal delay between two heartbeats, 0 disables them
+    pub interval: Duration,
+    /// The heartbeats are suppressed until no proposal was received for that long: the proposals
+    /// already carry the highest commit certificate
+    pub quiet_period: Duration,
+}
+
+impl Default for CommitHeartbeatPolicy {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(2),
+            quiet_period: Duration::from_secs(2),
+        }
+    }
+}
+
+/// Schedules the heartbeats carrying the highest commit certificate of the node, such that the
+/// peers that missed both the proposals and the timeouts (e.g., after a partition heals) learn
+/// the committed frontier without waiting for the next proposal. The heartbeats are sent to the
+/// given channel as the epoch they were scheduled in, at most one heartbeat is pending at any
+/// time.
+pub struct CommitHeartbeat {
+    policy: CommitHeartbeatPolicy,
+    time_service: Arc<dyn TimeService>,
+    heartbeat_sender: channel::Sender<u64>,
+    epoch: u64,
+    // The time the last proposal was received at, since the Unix epoch
+    last_proposal_received: Option<Duration>,
+}
+
+impl CommitHeartbeat {
+    pub fn new(
+        policy: CommitHeartbeatPolicy,
+        time_service: Arc<dyn TimeService>,
+        heartbeat_sender: channel::Sender<u64>,
+        epoch: u64,
+    ) -> Self {
+        Self {
+            policy,
+            time_service,
+            heartbeat_sender,
+            epoch,
+            last_proposal_received: None,
+        }
+    }
+
+    /// Schedules the first heartbeat of the epoch.
+    pub fn start(&self) {
+        self.schedule();
+    }
+
+    /// Records that a proposal was received, which suppresses the heartbeats for the quiet period.
+    pub fn proposal_received(&mut self) {
+        self.last_proposal_received = Some(self.time_service.get_current_timestamp());
+    }
+
+    /// Processes a heartbeat scheduled in the given epoch: schedules the next one and returns
+    /// whether the highest commit certificate must be broadcast. The heartbeats scheduled by the
+    /// event processor of a previous epoch are dropped, the current one has its own schedule.
+    pub fn heartbeat_due(&mut self, epoch: u64) -> bool {
+        if epoch != self.epoch {
+            return false;
+        }
+        self.schedule();
+        match self.last_proposal_received {
+            Some(received) => {
+                self.time_service.get_current_timestamp() >= received + self.policy.quiet_period
+            }
+            None => true,
+        }
+    }
+
+    fn schedule(&self) {
+        if self.policy.interval == Duration::from_secs(0) {
+            return;
+        }
+        self.time_service.run_after(
+            self.policy.interval,
+            SendTask::make(self.heartbeat_sender.clone(), self.epoch),
+        );
+    }
+}
@@ -0,0 +1,103 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    chained_bft::liveness::commit_heartbeat::{CommitHeartbeat, CommitHeartbeatPolicy},
+    util::{mock_time_service::SimulatedTimeService, time_service::TimeService},
+};
+use channel;
+use futures::{
+    executor::block_on,
+    task::{noop_waker, Context, Poll},
+    StreamExt,
+};
+use std::{sync::Arc, time::Duration};
+
+// The epoch of the heartbeat sent to the channel, if any.
+fn sent_epoch(rx: &mut channel::Receiver<u64>) -> Option<u64> {
+    let waker = noop_waker();
+    let mut cx = Context::from_waker(&waker);
+    match rx.poll_next_unpin(&mut cx) {
+        Poll::Ready(epoch) => epoch,
+        Poll::Pending => None,
+    }
+}
+
+// Runs the tasks scheduled within the given duration and moves the clock forward.
+fn advance(time_service: &mut SimulatedTimeService, duration_ms: u64) {
+    let duration = Duration::from_millis(duration_ms);
+    time_service.update_auto_advance_limit(duration);
+    block_on(time_service.sleep(duration));
+}
+
+fn policy() -> CommitHeartbeatPolicy {
+    CommitHeartbeatPolicy {
+        interval: Duration::from_millis(100),
+        quiet_period: Duration::from_millis(200),
+    }
+}
+
+#[test]
+fn test_heartbeats_without_proposals() {
+    let mut time_service = SimulatedTimeService::new();
+    let (tx, mut rx) = channel::new_test(8);
+    let mut heartbeat = CommitHeartbeat::new(policy(), Arc::new(time_service.clone()), tx, 1);
+
+    heartbeat.start();
+    advance(&mut time_service, 99);
+    assert_eq!(sent_epoch(&mut rx), None);
+    advance(&mut time_service, 1);
+    assert_eq!(sent_epoch(&mut rx), Some(1));
+    assert!(heartbeat.heartbeat_due(1));
+    advance(&mut time_service, 100);
+    assert_eq!(sent_epoch(&mut rx), Some(1));
+    assert!(heartbeat.heartbeat_due(1));
+    assert_eq!(sent_epoch(&mut rx), None);
+}
+
+#[test]
+fn test_heartbeats_suppressed_by_proposals() {
+    let mut time_service = SimulatedTimeService::new();
+    let (tx, mut rx) = channel::new_test(8);
+    let mut heartbeat = CommitHeartbeat::new(policy(), Arc::new(time_service.clone()), tx, 1);
+
+    heartbeat.start();
+    advance(&mut time_service, 50);
+    heartbeat.proposal_received();
+    // The heartbeats keep being scheduled, but nothing is broadcast during the quiet period.
+    advance(&mut time_service, 50);
+    assert_eq!(sent_epoch(&mut rx), Some(1));
+    assert!(!heartbeat.heartbeat_due(1));
+    advance(&mut time_service, 100);
+    assert_eq!(sent_epoch(&mut rx), Some(1));
+    assert!(!heartbeat.heartbeat_due(1));
+    advance(&mut time_service, 100);
+    assert_eq!(sent_epoch(&mut rx), Some(1));
+    assert!(heartbeat.heartbeat_due(1));
+}
+
+#[test]
+fn test_stale_epoch_and_disabled_heartbeats() {
+    let mut time_service = SimulatedTimeService::new();
+    let (tx, mut rx) = channel::new_test(8);
+    let mut heartbeat = CommitHeartbeat::new(policy(), Arc::new(time_service.clone()), tx, 2);
+
+    // The heartbeat of the previous epoch is neither broadcast nor rescheduled.
+    assert!(!heartbeat.heartbeat_due(1));
+    advance(&mut time_service, 200);
+    assert_eq!(sent_epoch(&mut rx), None);
+
+    let (tx, mut rx) = channel::new_test(8);
+    let heartbeat = CommitHeartbeat::new(
+        CommitHeartbeatPolicy {
+            interval: Duration::from_secs(0),
+            ..policy()
+        },
+        Arc::new(time_service.clone()),
+        tx,
+        2,
+    );
+    heartbeat.start();
+    advance(&mut time_service, 200);
+    assert_eq!(sent_epoch(&mut rx), None);
+}
@@ -0,0 +1,140 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::chained_bft::{
+    block_storage::BlockReader,
+    common::{Author, Payload, Round},
+    consensus_types::block::Block,
+    liveness::proposer_election::ProposerElection,
+};
+use crypto::HashValue;
+use std::{collections::HashSet, convert::TryInto, sync::Arc};
+
+/// The leader reputation elects the proposer of a round among the proposers that have recently
+/// been active: the authors of the last committed blocks and the validators that signed their
+/// quorum certificates. The active proposers are elected far more often than the inactive ones,
+/// such that a crashed leader quickly stops being elected, while it still gets elected every now
+/// and then in order to rejoin once it recovers.
+///
+/// The history is read from the committed blocks of the block store, which are the same on all
+/// the honest replicas. The window of history excludes the last rounds before the elected round,
+/// such that the replicas that are a couple of rounds behind (e.g., because they didn't receive
+/// the last quorum certificate yet) agree on the elected proposer.
+pub struct LeaderReputation<T> {
+    // Ordering of the candidates (all honest replicas must agree on this)
+    proposers: Vec<Author>,
+    block_reader: Arc<dyn BlockReader<Payload = T>>,
+    // Number of committed blocks looked at
+    window_size: usize,
+    // Number of rounds before the elected round excluded from the history
+    exclude_rounds: Round,
+    active_weight: u64,
+    inactive_weight: u64,
+}
+
+impl<T: Payload> LeaderReputation<T> {
+    pub fn new(
+        proposers: Vec<Author>,
+        block_reader: Arc<dyn BlockReader<Payload = T>>,
+        window_size: usize,
+        exclude_rounds: Round,
+        active_weight: u64,
+        inactive_weight: u64,
+    ) -> Self {
+        assert!(!proposers.is_empty());
+        assert!(
+            inactive_weight > 0,
+            "The inactive proposers must be electable"
+        );
+        Self {
+            proposers,
+            block_reader,
+            window_size,
+            exclude_rounds,
+            active_weight,
+            inactive_weight,
+        }
+    }
+
+    /// The authors and the voters of the committed blocks in the window of history of the
+    /// given round. The history is shorter in case the older blocks are not in memory anymore.
+    fn active_proposers(&self, round: Round) -> HashSet<Author> {
+        let max_round = round.saturating_sub(self.exclude_rounds);
+        let mut active = HashSet::new();
+        let mut num_blocks = 0;
+        let mut block = Some(self.block_reader.root());
+        while let Some(current) = block {
+            // The genesis block has neither an author nor voters.
+            if num_blocks >= self.window_size || current.round() == 0 {
+                break;
+            }
+            if current.round() <= max_round {
+                active.extend(current.block().author());
+                active.extend(
+                    current
+                        .quorum_cert()
+                        .ledger_info()
+                        .signatures()
+                        .keys()
+                        .cloned(),
+                );
+                num_blocks += 1;
+            }
+            block = self.block_reader.get_block(current.parent_id());
+        }
+        active
+    }
+
+    fn get_proposer(&self, round: Round) -> Author {
+        let active = self.active_proposers(round);
+        let weights: Vec<u64> = self
+            .proposers
+            .iter()
+            .map(|proposer| {
+                if active.contains(proposer) {
+                    self.active_weight
+                } else {
+                    self.inactive_weight
+                }
+            })
+            .collect();
+        let total_weight: u64 = weights.iter().sum();
+        // All the replicas draw the same pseudo-random value for the round.
+        let seed = HashValue::from_sha3_256(&round.to_le_bytes());
+        let mut target = u64::from_le_bytes(seed.to_vec()[..8].try_into().unwrap()) % total_weight;
+        for (proposer, weight) in self.proposers.iter().zip(weights) {
+            if target < weight {
+                return *proposer;
+            }
+            target -= weight;
+        }
+        unreachable!("The target is lower than the total weight")
+    }
+}
+
+impl<T: Payload> ProposerElection<T> for LeaderReputation<T> {
+    fn is_valid_proposer(&self, author: Author, round: Round) -> Option<Author> {
+        if self.get_proposer(round) == author {
+            Some(author)
+        } else {
+            None
+        }
+    }
+
+    fn get_valid_proposers(&self, round: Round) -> Vec<Author> {
+        vec![self.get_proposer(round)]
+    }
+
+    fn process_proposal(&mut self, proposal: Block<T>) -> Option<Block<T>> {
+        let round_author = self.get_proposer(proposal.round());
+        if Some(round_author) != proposal.author() {
+            None
+        } else {
+            Some(proposal)
+        }
+    }
+
+    fn take_backup_proposal(&mut self, _round: Round) -> Option<Block<T>> {
+        None
+    }
+}
@@ -0,0 +1,63 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::chained_bft::{
+    block_storage::BlockReader,
+    common::Round,
+    liveness::{leader_reputation::LeaderReputation, proposer_election::ProposerElection},
+    test_utils::{build_empty_tree_with_custom_signing, TestPayload, TreeInserter},
+};
+use types::validator_signer::ValidatorSigner;
+
+#[test]
+fn test_leader_reputation() {
+    let active_signer = ValidatorSigner::random([0u8; 32]);
+    let active_author = active_signer.author();
+    let inactive_author = ValidatorSigner::random([1u8; 32]).author();
+    let proposers = vec![inactive_author, active_author];
+
+    // The active author proposed and voted for all the committed blocks.
+    let block_store = build_empty_tree_with_custom_signing(active_signer);
+    let mut inserter = TreeInserter::new(block_store.clone());
+    let mut parent = block_store.root();
+    for round in 1..=4 {
+        parent = inserter.insert_block(&parent, round);
+    }
+    block_store.prune_tree(parent.id());
+    assert_eq!(block_store.root().round(), 4);
+
+    let pe: Box<dyn ProposerElection<TestPayload>> = Box::new(LeaderReputation::new(
+        proposers.clone(),
+        block_store.clone(),
+        10,
+        0,
+        100,
+        1,
+    ));
+    let elected_inactive = (10..110 as Round)
+        .filter(|round| pe.get_valid_proposers(*round) == vec![inactive_author])
+        .count();
+    assert!(elected_inactive < 10, "{}", elected_inactive);
+    for round in 10..20 {
+        let proposer = pe.get_valid_proposers(round)[0];
+        assert_eq!(pe.is_valid_proposer(proposer, round), Some(proposer));
+    }
+
+    // Without any history in the window, the proposers are equally likely to be elected.
+    let pe: Box<dyn ProposerElection<TestPayload>> = Box::new(LeaderReputation::new(
+        proposers.clone(),
+        block_store.clone(),
+        10,
+        1_000,
+        100,
+        1,
+    ));
+    let elected_inactive = (10..110 as Round)
+        .filter(|round| pe.get_valid_proposers(*round) == vec![inactive_author])
+        .count();
+    assert!(
+        elected_inactive > 20 && elected_inactive < 80,
+        "{}",
+        elected_inactive
+    );
+}
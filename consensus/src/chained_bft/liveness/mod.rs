@@ -1,13 +1,19 @@
 // Copyright (c) The Libra Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
+pub(crate) mod commit_heartbeat;
+pub(crate) mod leader_reputation;
 pub(crate) mod multi_proposer_election;
 pub(crate) mod pacemaker;
 pub(crate) mod pacemaker_timeout_manager;
 pub(crate) mod proposal_generator;
 pub(crate) mod proposer_election;
+pub(crate) mod qc_rebroadcast;
 pub(crate) mod rotating_proposer_election;
+pub(crate) mod timeout_retransmission;
 
+#[cfg(test)]
+mod leader_reputation_test;
 #[cfg(test)]
 mod multi_proposer_test;
 #[cfg(test)]
@@ -2,13 +2,19 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::chained_bft::{
+    block_storage::BlockReader,
     common::{Author, Payload, Round},
     consensus_types::block::Block,
     liveness::proposer_election::ProposerElection,
 };
 use logger::prelude::*;
 use siphasher::sip::SipHasher24;
-use std::hash::{Hash, Hasher};
+use std::{
+    cmp::{max, min},
+    collections::{HashMap, HashSet},
+    hash::{Hash, Hasher},
+    sync::Arc,
+};
 
 // A deterministic hashing function based on SipHash 2-4 hasher
 pub fn hash(val: u64) -> u64 {
@@ -29,6 +35,9 @@ pub fn hash(val: u64) -> u64 {
 /// While each round has more than a single valid proposer, only the primary proposer is
 /// considered for `process_proposal`. The best backup proposer is returned in
 /// `get_best_backup_proposal()`.
+///
+/// With the leader skipping enabled, a primary proposer that failed in its last rounds is
+/// demoted to the last rank for a cool-down of rounds, see `with_leader_skipping()`.
 pub struct MultiProposer<T> {
     // Ordering of proposers to rotate through (all honest replicas must agree on this)
     proposers: Vec<Author>,
@@ -41,9 +50,22 @@ pub struct MultiProposer<T> {
     backup_proposal_round: Round,
     // The proposal is kept in a tuple (rank, block)
     backup_proposal: Option<(usize, Block<T>)>,
+    leader_skipping: Option<LeaderSkipping<T>>,
+}
+
+struct LeaderSkipping<T> {
+    block_reader: Arc<dyn BlockReader<Payload = T>>,
+    // Number of consecutive failed rounds after which a primary proposer is demoted
+    max_failed_rounds: usize,
+    // Number of rounds a proposer stays demoted
+    cool_down_rounds: Round,
+    // Number of rounds of committed history looked at
+    window_rounds: Round,
+    // Number of rounds before the elected round excluded from the history
+    exclude_rounds: Round,
 }
 
-impl<T> MultiProposer<T> {
+impl<T: Payload> MultiProposer<T> {
     pub fn new(proposers: Vec<Author>, mut num_proposers_per_round: usize) -> Self {
         assert!(num_proposers_per_round > 0);
         if num_proposers_per_round > proposers.len() {
@@ -62,10 +84,114 @@ impl<T> MultiProposer<T> {
             num_proposers_per_round,
             backup_proposal_round: 0,
             backup_proposal: None,
+            leader_skipping: None,
         }
     }
 
+    /// Demotes a primary proposer to the last rank (it remains a valid backup proposer) for
+    /// `cool_down_rounds` rounds after it failed to propose the committed block of
+    /// `max_failed_rounds` consecutive rounds it led.
+    ///
+    /// The failed rounds are read from the committed blocks of the block store, which are the
+    /// same on all the honest replicas. As for the leader reputation, the history excludes the
+    /// last rounds before the elected round, such that the replicas that are a couple of rounds
+    /// behind agree on the order of the proposers.
+    pub fn with_leader_skipping(
+        mut self,
+        block_reader: Arc<dyn BlockReader<Payload = T>>,
+        max_failed_rounds: usize,
+        cool_down_rounds: Round,
+        window_rounds: Round,
+        exclude_rounds: Round,
+    ) -> Self {
+        assert!(max_failed_rounds > 0);
+        self.leader_skipping = Some(LeaderSkipping {
+            block_reader,
+            max_failed_rounds,
+            cool_down_rounds,
+            window_rounds,
+            exclude_rounds,
+        });
+        self
+    }
+
     fn get_candidates(&self, round: Round) -> Vec<Author> {
+        let candidates = self.get_ordered_candidates(round);
+        let demoted = match &self.leader_skipping {
+            Some(leader_skipping) => self.demoted_proposers(leader_skipping, round),
+            None => return candidates,
+        };
+        let (mut res, demoted_candidates): (Vec<_>, Vec<_>) = candidates
+            .into_iter()
+            .partition(|candidate| !demoted.contains(candidate));
+        res.extend(demoted_candidates);
+        res
+    }
+
+    /// The primary proposer of the round before any demotion.
+    fn get_primary(&self, round: Round) -> Author {
+        self.proposers[(hash(round) % self.proposers.len() as u64) as usize]
+    }
+
+    /// The proposers demoted in the given round: the committed history is replayed from its
+    /// oldest round, a round failed if its primary proposer didn't author its committed block.
+    fn demoted_proposers(
+        &self,
+        leader_skipping: &LeaderSkipping<T>,
+        round: Round,
+    ) -> HashSet<Author> {
+        let root = leader_skipping.block_reader.root();
+        let max_round = min(
+            root.round(),
+            round.saturating_sub(leader_skipping.exclude_rounds),
+        );
+        let mut min_round = max_round.saturating_sub(leader_skipping.window_rounds);
+        // The authors of the committed blocks of the rounds in (min_round, max_round].
+        let mut authors = HashMap::new();
+        let mut block = Some(root);
+        while let Some(current) = block {
+            if current.round() <= min_round {
+                break;
+            }
+            if current.round() <= max_round {
+                if let Some(author) = current.block().author() {
+                    authors.insert(current.round(), author);
+                }
+            }
+            block = leader_skipping.block_reader.get_block(current.parent_id());
+            if block.is_none() {
+                // The older blocks are not in memory anymore, the history starts after the
+                // round of the parent.
+                min_round = max(min_round, current.quorum_cert().certified_block_round());
+            }
+        }
+
+        // Per proposer: the number of consecutive failed rounds and the last demoted round.
+        let mut failures: HashMap<Author, (usize, Round)> = HashMap::new();
+        for history_round in min_round + 1..=max_round {
+            let primary = self.get_primary(history_round);
+            let (failed_rounds, demoted_until) = failures.entry(primary).or_insert((0, 0));
+            if history_round <= *demoted_until {
+                continue;
+            }
+            if authors.get(&history_round) == Some(&primary) {
+                *failed_rounds = 0;
+            } else {
+                *failed_rounds += 1;
+                if *failed_rounds >= leader_skipping.max_failed_rounds {
+                    *failed_rounds = 0;
+                    *demoted_until = history_round + leader_skipping.cool_down_rounds;
+                }
+            }
+        }
+        failures
+            .into_iter()
+            .filter(|(_, (_, demoted_until))| round <= *demoted_until)
+            .map(|(author, _)| author)
+            .collect()
+    }
+
+    fn get_ordered_candidates(&self, round: Round) -> Vec<Author> {
         let mut res = vec![];
         let mut candidates = self.proposers.clone();
         let mut cur_val = round;
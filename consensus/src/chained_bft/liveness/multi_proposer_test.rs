@@ -2,11 +2,13 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::chained_bft::{
+    block_storage::BlockReader,
     consensus_types::{block::Block, quorum_cert::QuorumCert},
     liveness::{
         multi_proposer_election::{self, MultiProposer},
         proposer_election::ProposerElection,
     },
+    test_utils::{build_empty_tree_with_custom_signing, TestPayload, TreeInserter},
 };
 use crypto::ed25519::*;
 use types::validator_signer::ValidatorSigner;
@@ -109,3 +111,74 @@ fn test_multi_proposer_hash() {
         assert!(c > 900);
     }
 }
+
+#[test]
+fn test_leader_skipping() {
+    let signer_a = ValidatorSigner::random([0u8; 32]);
+    let author_a = signer_a.author();
+    let signer_b = ValidatorSigner::random([1u8; 32]);
+    let author_b = signer_b.author();
+    let proposers = vec![author_a, author_b];
+    // The primary proposers of the rounds 1 to 11 are A, B, A, A, A, B, A, B, A, A, B.
+    let primary = |round| proposers[(multi_proposer_election::hash(round) % 2) as usize];
+    assert_eq!(
+        (1..=11).map(primary).collect::<Vec<_>>(),
+        vec![
+            author_a, author_b, author_a, author_a, author_a, author_b, author_a, author_b,
+            author_a, author_a, author_b
+        ]
+    );
+
+    // A authored all the committed blocks: B failed in all the rounds it led.
+    let block_store = build_empty_tree_with_custom_signing(signer_a);
+    let mut inserter = TreeInserter::new(block_store.clone());
+    let mut parent = block_store.root();
+    for round in 1..=5 {
+        parent = inserter.insert_block(&parent, round);
+    }
+    block_store.prune_tree(parent.id());
+    let mut pe: Box<dyn ProposerElection<TestPayload>> = Box::new(
+        MultiProposer::new(proposers.clone(), 2).with_leader_skipping(
+            block_store.clone(),
+            2,
+            4,
+            100,
+            0,
+        ),
+    );
+    // A single failed round (2) doesn't demote B.
+    assert_eq!(pe.get_valid_proposers(8), vec![author_b, author_a]);
+
+    parent = inserter.insert_block(&parent, 6);
+    block_store.prune_tree(parent.id());
+    // B is demoted after its second failed round (6) up to round 10.
+    assert_eq!(pe.get_valid_proposers(7), vec![author_a, author_b]);
+    assert_eq!(pe.get_valid_proposers(8), vec![author_a, author_b]);
+    assert_eq!(pe.get_valid_proposers(11), vec![author_b, author_a]);
+    assert_eq!(pe.is_valid_proposer(author_b, 8), Some(author_b));
+
+    // The proposal of B is a backup proposal in round 8.
+    let genesis_block = Block::make_genesis_block();
+    let proposal_b = Block::make_block(
+        &genesis_block,
+        vec![],
+        8,
+        1,
+        QuorumCert::certificate_for_genesis(),
+        &signer_b,
+    );
+    assert_eq!(pe.process_proposal(proposal_b.clone()), None);
+    assert_eq!(pe.take_backup_proposal(8), Some(proposal_b));
+
+    // The last rounds are excluded from the history.
+    let pe: Box<dyn ProposerElection<TestPayload>> = Box::new(
+        MultiProposer::new(proposers.clone(), 2).with_leader_skipping(
+            block_store.clone(),
+            2,
+            4,
+            100,
+            3,
+        ),
+    );
+    assert_eq!(pe.get_valid_proposers(8), vec![author_b, author_a]);
+}
@@ -4,7 +4,7 @@
 use crate::{
     chained_bft::{
         common::Round,
-        consensus_types::timeout_msg::{PacemakerTimeout, PacemakerTimeoutCertificate},
+        consensus_types::{timeout_certificate::TimeoutCertificate, timeout_msg::PacemakerTimeout},
         liveness::pacemaker_timeout_manager::{
             HighestTimeoutCertificates, PacemakerTimeoutManager,
         },
@@ -16,6 +16,7 @@ use crate::{
 use channel;
 use logger::prelude::*;
 use std::{
+    collections::VecDeque,
     fmt,
     sync::Arc,
     time::{Duration, Instant},
@@ -26,7 +27,7 @@ use termion::color::*;
 #[derive(Eq, Debug, PartialEq)]
 pub enum NewRoundReason {
     QCReady,
-    Timeout { cert: PacemakerTimeoutCertificate },
+    Timeout { cert: TimeoutCertificate },
 }
 
 impl fmt::Display for NewRoundReason {
@@ -72,6 +73,10 @@ pub trait PacemakerTimeInterval: Send + Sync + 'static {
     /// to calculate the round duration of round 6 and the highest committed round is 3 (meaning
     /// the highest round to commit a block is round 5, then the round index is 0.
     fn get_round_duration(&self, round_index_after_committed_qc: usize) -> Duration;
+
+    /// Reports the duration of a successful round, i.e., a round that ended with a quorum
+    /// certificate, from the start of the round until the quorum certificate was received.
+    fn record_round_duration(&mut self, _duration: Duration) {}
 }
 
 /// Round durations increase exponentially
@@ -89,6 +94,8 @@ pub struct ExponentialTimeInterval {
     // max_interval.  Alternatively, we can consider using max_interval to meet partial synchrony
     // assumptions where while delta is unknown, it is <= max_interval.
     max_exponent: usize,
+    // Optional upper bound of the interval, which takes precedence over the exponent.
+    max_interval_ms: Option<u64>,
 }
 
 impl ExponentialTimeInterval {
@@ -110,16 +117,115 @@ impl ExponentialTimeInterval {
             base_ms: base.as_millis() as u64, // any reasonable ms timeout fits u64 perfectly
             exponent_base,
             max_exponent,
+            max_interval_ms: None,
         }
     }
+
+    /// Caps the time interval: the interval grows exponentially up to the given duration.
+    pub fn with_max_interval(mut self, max_interval: Duration) -> Self {
+        assert!(
+            max_interval.as_millis() as u64 >= self.base_ms,
+            "The maximum interval should not be lower than the base interval"
+        );
+        self.max_interval_ms = Some(max_interval.as_millis() as u64);
+        self
+    }
 }
 
-impl PacemakerTimeInterval for ExponentialTimeInterval {
-    fn get_round_duration(&self, round_index_after_committed_qc: usize) -> Duration {
+impl ExponentialTimeInterval {
+    fn get_round_duration_from_base(
+        &self,
+        base_ms: u64,
+        round_index_after_committed_qc: usize,
+    ) -> Duration {
         let pow = round_index_after_committed_qc.min(self.max_exponent) as u32;
         let base_multiplier = self.exponent_base.powf(f64::from(pow));
-        let duration_ms = ((self.base_ms as f64) * base_multiplier).ceil() as u64;
-        Duration::from_millis(duration_ms)
+        let duration_ms = ((base_ms as f64) * base_multiplier).ceil() as u64;
+        match self.max_interval_ms {
+            Some(max_interval_ms) => Duration::from_millis(duration_ms.min(max_interval_ms)),
+            None => Duration::from_millis(duration_ms),
+        }
+    }
+}
+
+impl PacemakerTimeInterval for ExponentialTimeInterval {
+    fn get_round_duration(&self, round_index_after_committed_qc: usize) -> Duration {
+        self.get_round_duration_from_base(self.base_ms, round_index_after_committed_qc)
+    }
+}
+
+/// Round durations adapt to the observed latency of the successful rounds: the base interval is
+/// a multiple of a percentile of the durations of the last successful rounds, and grows
+/// exponentially from there (as `ExponentialTimeInterval`) while the rounds don't commit.
+/// The base interval never exceeds the configured base, which is also used until enough rounds
+/// have been observed.
+pub struct AdaptiveTimeInterval {
+    backoff: ExponentialTimeInterval,
+    // The percentile of the recent round durations, in (0, 1].
+    percentile: f64,
+    // The base interval is the percentile times this multiplier, which leaves room for the
+    // variance of the round durations.
+    multiplier: f64,
+    // Lower bound of the base interval.
+    min_base_ms: u64,
+    // Number of rounds required before adapting the base interval.
+    min_samples: usize,
+    window_size: usize,
+    recent_durations_ms: VecDeque<u64>,
+}
+
+impl AdaptiveTimeInterval {
+    pub fn new(
+        backoff: ExponentialTimeInterval,
+        percentile: f64,
+        multiplier: f64,
+        min_base: Duration,
+        window_size: usize,
+    ) -> Self {
+        assert!(
+            percentile > 0.0 && percentile <= 1.0,
+            "The percentile should be in (0, 1]"
+        );
+        assert!(multiplier >= 1.0, "The multiplier should be at least 1");
+        assert!(window_size > 0);
+        Self {
+            backoff,
+            percentile,
+            multiplier,
+            min_base_ms: min_base.as_millis() as u64,
+            min_samples: (window_size / 4).max(1),
+            window_size,
+            recent_durations_ms: VecDeque::with_capacity(window_size),
+        }
+    }
+
+    fn base_ms(&self) -> u64 {
+        if self.recent_durations_ms.len() < self.min_samples {
+            return self.backoff.base_ms;
+        }
+        let mut durations: Vec<u64> = self.recent_durations_ms.iter().cloned().collect();
+        durations.sort();
+        let index = ((self.percentile * durations.len() as f64).ceil() as usize)
+            .max(1)
+            .min(durations.len())
+            - 1;
+        let base_ms = ((durations[index] as f64) * self.multiplier).ceil() as u64;
+        base_ms.max(self.min_base_ms).min(self.backoff.base_ms)
+    }
+}
+
+impl PacemakerTimeInterval for AdaptiveTimeInterval {
+    fn get_round_duration(&self, round_index_after_committed_qc: usize) -> Duration {
+        self.backoff
+            .get_round_duration_from_base(self.base_ms(), round_index_after_committed_qc)
+    }
+
+    fn record_round_duration(&mut self, duration: Duration) {
+        if self.recent_durations_ms.len() == self.window_size {
+            self.recent_durations_ms.pop_front();
+        }
+        self.recent_durations_ms
+            .push_back(duration.as_millis() as u64);
     }
 }
 
@@ -155,11 +261,13 @@ pub struct Pacemaker {
     current_round: Round,
     // Approximate deadline when current round ends
     current_round_deadline: Instant,
+    // When the current round started according to the time service
+    current_round_start: Duration,
     // Service for timer
     time_service: Arc<dyn TimeService>,
     // To send timeout events to other pacemakers
     timeout_sender: channel::Sender<Round>,
-    // Manages the PacemakerTimeout and PacemakerTimeoutCertificate structs
+    // Manages the PacemakerTimeout and TimeoutCertificate structs
     pacemaker_timeout_manager: PacemakerTimeoutManager,
 }
 
@@ -183,6 +291,7 @@ impl Pacemaker {
             highest_qc_round: 0,
             current_round: 0,
             current_round_deadline: Instant::now(),
+            current_round_start: time_service.get_current_timestamp(),
             time_service,
             timeout_sender,
             pacemaker_timeout_manager: PacemakerTimeoutManager::new(
@@ -269,7 +378,16 @@ impl Pacemaker {
             self.current_round,
             new_round
         );
+        let now = self.time_service.get_current_timestamp();
+        // The current round has ended with a quorum certificate.
+        if best_reason == NewRoundReason::QCReady && best_round == self.current_round {
+            self.time_interval.record_round_duration(
+                now.checked_sub(self.current_round_start)
+                    .unwrap_or_default(),
+            );
+        }
         self.current_round = new_round;
+        self.current_round_start = now;
         let timeout = self.setup_timeout();
         Some(NewRoundEvent {
             round: self.current_round,
@@ -279,7 +397,7 @@ impl Pacemaker {
     }
 
     /// Validate timeout certificate and update local state if it's correct
-    fn check_and_update_highest_received_tc(&mut self, tc: Option<&PacemakerTimeoutCertificate>) {
+    fn check_and_update_highest_received_tc(&mut self, tc: Option<&TimeoutCertificate>) {
         if let Some(tc) = tc {
             self.pacemaker_timeout_manager
                 .update_highest_received_timeout_certificate(tc);
@@ -298,7 +416,7 @@ impl Pacemaker {
 
     /// Return a optional reference to the highest timeout certificate (locally generated or
     /// remotely received)
-    pub fn highest_timeout_certificate(&self) -> Option<PacemakerTimeoutCertificate> {
+    pub fn highest_timeout_certificate(&self) -> Option<TimeoutCertificate> {
         self.pacemaker_timeout_manager
             .highest_timeout_certificate()
             .cloned()
@@ -311,7 +429,7 @@ impl Pacemaker {
         &mut self,
         qc_round: Round,
         highest_committed_round: Option<Round>,
-        timeout_certificate: Option<&PacemakerTimeoutCertificate>,
+        timeout_certificate: Option<&TimeoutCertificate>,
     ) -> Option<NewRoundEvent> {
         self.check_and_update_highest_received_tc(timeout_certificate);
         self.update_highest_qc_round(qc_round);
@@ -7,8 +7,8 @@ use crate::{
         consensus_types::timeout_msg::PacemakerTimeout,
         liveness::{
             pacemaker::{
-                ExponentialTimeInterval, NewRoundEvent, NewRoundReason, Pacemaker,
-                PacemakerTimeInterval,
+                AdaptiveTimeInterval, ExponentialTimeInterval, NewRoundEvent, NewRoundReason,
+                Pacemaker, PacemakerTimeInterval,
             },
             pacemaker_timeout_manager::HighestTimeoutCertificates,
         },
@@ -33,6 +33,42 @@ fn test_pacemaker_time_interval() {
     );
     // Test that there is no integer overflow
     assert_eq!(6750, interval.get_round_duration(1000).as_millis());
+
+    let capped_interval = ExponentialTimeInterval::new(Duration::from_millis(3000), 2.0, 10)
+        .with_max_interval(Duration::from_millis(10000));
+    assert_eq!(3000, capped_interval.get_round_duration(0).as_millis());
+    assert_eq!(6000, capped_interval.get_round_duration(1).as_millis());
+    assert_eq!(10000, capped_interval.get_round_duration(2).as_millis());
+    assert_eq!(10000, capped_interval.get_round_duration(1000).as_millis());
+}
+
+#[test]
+fn test_adaptive_time_interval() {
+    let backoff = ExponentialTimeInterval::new(Duration::from_millis(3000), 1.5, 2);
+    let mut interval = AdaptiveTimeInterval::new(backoff, 0.9, 3.0, Duration::from_millis(100), 8);
+    // Not enough samples yet, the configured base is used.
+    interval.record_round_duration(Duration::from_millis(200));
+    assert_eq!(3000, interval.get_round_duration(0).as_millis());
+
+    for duration_ms in &[100, 300, 200, 100, 200, 200, 100] {
+        interval.record_round_duration(Duration::from_millis(*duration_ms));
+    }
+    // The 90th percentile of the 8 recent rounds is 300ms.
+    assert_eq!(900, interval.get_round_duration(0).as_millis());
+    assert_eq!(1350, interval.get_round_duration(1).as_millis());
+    assert_eq!(2025, interval.get_round_duration(1000).as_millis());
+
+    // The oldest rounds are forgotten, the base doesn't go below the minimum.
+    for _ in 0..8 {
+        interval.record_round_duration(Duration::from_millis(1));
+    }
+    assert_eq!(100, interval.get_round_duration(0).as_millis());
+
+    // The base doesn't exceed the configured base.
+    for _ in 0..8 {
+        interval.record_round_duration(Duration::from_millis(2000));
+    }
+    assert_eq!(3000, interval.get_round_duration(0).as_millis());
 }
 
 #[test]
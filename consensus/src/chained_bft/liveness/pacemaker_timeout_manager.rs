@@ -3,7 +3,7 @@
 
 use crate::chained_bft::{
     common::Author,
-    consensus_types::timeout_msg::{PacemakerTimeout, PacemakerTimeoutCertificate},
+    consensus_types::{timeout_certificate::TimeoutCertificate, timeout_msg::PacemakerTimeout},
     persistent_storage::PersistentLivenessStorage,
 };
 use logger::prelude::*;
@@ -18,9 +18,9 @@ mod pacemaker_timeout_manager_test;
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct HighestTimeoutCertificates {
     // Highest timeout certificate gathered locally
-    highest_local_timeout_certificate: Option<PacemakerTimeoutCertificate>,
+    highest_local_timeout_certificate: Option<TimeoutCertificate>,
     // Highest timeout certificate received from another replica
-    highest_received_timeout_certificate: Option<PacemakerTimeoutCertificate>,
+    highest_received_timeout_certificate: Option<TimeoutCertificate>,
 }
 
 impl fmt::Display for HighestTimeoutCertificates {
@@ -37,8 +37,8 @@ impl fmt::Display for HighestTimeoutCertificates {
 impl HighestTimeoutCertificates {
     #[cfg(any(test, feature = "fuzzing"))]
     pub fn new(
-        highest_local_timeout_certificate: Option<PacemakerTimeoutCertificate>,
-        highest_received_timeout_certificate: Option<PacemakerTimeoutCertificate>,
+        highest_local_timeout_certificate: Option<TimeoutCertificate>,
+        highest_received_timeout_certificate: Option<TimeoutCertificate>,
     ) -> Self {
         Self {
             highest_local_timeout_certificate,
@@ -48,7 +48,7 @@ impl HighestTimeoutCertificates {
 
     /// Return a optional reference to the highest timeout certificate (locally generated or
     /// remotely received)
-    pub fn highest_timeout_certificate(&self) -> Option<&PacemakerTimeoutCertificate> {
+    pub fn highest_timeout_certificate(&self) -> Option<&TimeoutCertificate> {
         if let Some(highest_received_timeout_certificate) =
             self.highest_received_timeout_certificate.as_ref()
         {
@@ -106,26 +106,26 @@ impl PacemakerTimeoutManager {
         }
     }
 
-    /// Returns the highest round PacemakerTimeoutCertificate from a map of author to
+    /// Returns the highest round TimeoutCertificate from a map of author to
     /// timeout messages or None if there are not enough timeout messages available.
-    /// A PacemakerTimeoutCertificate is made of the N highest timeout messages received where
-    /// N=timeout_quorum_size.  The round of PacemakerTimeoutCertificate is determined as
+    /// A TimeoutCertificate is made of the N highest timeout messages received where
+    /// N=timeout_quorum_size.  The round of TimeoutCertificate is determined as
     /// the smallest of round of all messages used to generate this certificate.
     ///
     /// For example, if timeout_certificate_quorum_size=3 and we received unique author timeouts
-    /// for rounds (1,2,3,4), then rounds (2,3,4) would form PacemakerTimeoutCertificate with
+    /// for rounds (1,2,3,4), then rounds (2,3,4) would form TimeoutCertificate with
     /// round=2.
     fn generate_timeout_certificate(
         author_to_received_timeouts: &HashMap<Author, PacemakerTimeout>,
         quorum_size: usize,
-    ) -> Option<PacemakerTimeoutCertificate> {
+    ) -> Option<TimeoutCertificate> {
         if author_to_received_timeouts.values().len() < quorum_size {
             return None;
         }
         let mut values: Vec<&PacemakerTimeout> = author_to_received_timeouts.values().collect();
         values.sort_by(|x, y| y.round().cmp(&x.round()));
         let slice = &values[..quorum_size];
-        Some(PacemakerTimeoutCertificate::new(
+        Some(TimeoutCertificate::new(
             // expect does not panic here because code above verifies values length
             slice
                 .last()
@@ -136,7 +136,7 @@ impl PacemakerTimeoutManager {
     }
 
     /// Updates internal state according to received message from remote pacemaker and returns true
-    /// if round derived from highest PacemakerTimeoutCertificate has increased.
+    /// if round derived from highest TimeoutCertificate has increased.
     pub fn update_received_timeout(
         &mut self,
         pacemaker_timeout: PacemakerTimeout,
@@ -164,7 +164,7 @@ impl PacemakerTimeoutManager {
             .highest_timeout_certificates
             .highest_local_timeout_certificate
             .as_ref()
-            .map(PacemakerTimeoutCertificate::round);
+            .map(TimeoutCertificate::round);
         assert!(
             highest_round >= prev_highest_round.unwrap_or(0),
             "Went down on highest timeout quorum round from {:?} to {:?}.
@@ -192,14 +192,14 @@ impl PacemakerTimeoutManager {
     /// timeout certificate.  Returns true if highest_received_timeout_certificate has changed
     pub fn update_highest_received_timeout_certificate(
         &mut self,
-        timeout_certificate: &PacemakerTimeoutCertificate,
+        timeout_certificate: &TimeoutCertificate,
     ) -> bool {
         if timeout_certificate.round()
             > self
                 .highest_timeout_certificates
                 .highest_received_timeout_certificate
                 .as_ref()
-                .map_or(0, PacemakerTimeoutCertificate::round)
+                .map_or(0, TimeoutCertificate::round)
         {
             debug!(
                 "Received remote timeout certificate at round {}",
@@ -224,7 +224,7 @@ impl PacemakerTimeoutManager {
 
     /// Return a optional reference to the highest timeout certificate (locally generated or
     /// remotely received)
-    pub fn highest_timeout_certificate(&self) -> Option<&PacemakerTimeoutCertificate> {
+    pub fn highest_timeout_certificate(&self) -> Option<&TimeoutCertificate> {
         self.highest_timeout_certificates
             .highest_timeout_certificate()
     }
@@ -2,7 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::chained_bft::{
-    consensus_types::timeout_msg::{PacemakerTimeout, PacemakerTimeoutCertificate},
+    consensus_types::{timeout_certificate::TimeoutCertificate, timeout_msg::PacemakerTimeout},
     liveness::pacemaker_timeout_manager::{HighestTimeoutCertificates, PacemakerTimeoutManager},
     persistent_storage::PersistentStorage,
     test_utils::{MockStorage, TestPayload},
@@ -79,7 +79,7 @@ fn test_basic() {
     );
 
     // Simulate received a higher received timeout certificate
-    let received_timeout_certificate = PacemakerTimeoutCertificate::new(
+    let received_timeout_certificate = TimeoutCertificate::new(
         10,
         vec![
             PacemakerTimeout::new(10, &validator_signer1, None),
@@ -106,7 +106,7 @@ fn test_recovery_from_highest_timeout_certificate() {
 
     let timeout1 = PacemakerTimeout::new(10, &validator_signer1, None);
     let timeout2 = PacemakerTimeout::new(11, &validator_signer2, None);
-    let tc = PacemakerTimeoutCertificate::new(10, vec![timeout1, timeout2]);
+    let tc = TimeoutCertificate::new(10, vec![timeout1, timeout2]);
 
     let timeout_manager = PacemakerTimeoutManager::new(
         HighestTimeoutCertificates::new(Some(tc), None),
@@ -1,7 +1,10 @@
 // Copyright (c) The Libra Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::chained_bft::{common::Round, consensus_types::block::Block};
+use crate::chained_bft::{
+    common::Round,
+    consensus_types::block::{serialized_payload_size, Block, ExecutedBlock},
+};
 
 use crate::{
     chained_bft::{block_storage::BlockReader, common::Payload},
@@ -9,6 +12,7 @@ use crate::{
     state_replication::TxnManager,
     util::time_service::{wait_if_possible, TimeService, WaitingError, WaitingSuccess},
 };
+use crypto::HashValue;
 use logger::prelude::*;
 use std::{
     sync::{Arc, Mutex},
@@ -59,10 +63,27 @@ pub struct ProposalGenerator<T> {
     time_service: Arc<dyn TimeService>,
     // Max number of transactions to be added to a proposed block.
     max_block_size: u64,
+    // Max size in bytes of the serialized payload of a proposed block.
+    max_block_bytes: u64,
+    // Back-pressure from the execution and commit pipeline: the proposals carry no transactions
+    // while more than this number of ordered blocks are not committed yet.
+    max_uncommitted_blocks: usize,
     // Support increasing block timestamps
     enforce_increasing_timestamps: bool,
     // Last round that a proposal was generated
     last_round_generated: Mutex<Round>,
+    // The payload of the last non-empty proposal generated by this node. It is proposed again in
+    // the next proposal of the node if the previous one has not been certified, so that its
+    // transactions do not starve behind repeated proposal failures.
+    retained_payload: Mutex<Option<RetainedPayload<T>>>,
+}
+
+struct RetainedPayload<T> {
+    // The id of the block that carried the payload.
+    block_id: HashValue,
+    // The root of the block tree when the block was proposed.
+    root_id: HashValue,
+    payload: T,
 }
 
 impl<T: Payload> ProposalGenerator<T> {
@@ -71,6 +92,8 @@ impl<T: Payload> ProposalGenerator<T> {
         txn_manager: Arc<dyn TxnManager<Payload = T>>,
         time_service: Arc<dyn TimeService>,
         max_block_size: u64,
+        max_block_bytes: u64,
+        max_uncommitted_blocks: usize,
         enforce_increasing_timestamps: bool,
     ) -> Self {
         Self {
@@ -78,11 +101,19 @@ impl<T: Payload> ProposalGenerator<T> {
             txn_manager,
             time_service,
             max_block_size,
+            max_block_bytes,
+            max_uncommitted_blocks,
             enforce_increasing_timestamps,
             last_round_generated: Mutex::new(0),
+            retained_payload: Mutex::new(None),
         }
     }
 
+    /// The max size in bytes of the serialized payload of a proposal.
+    pub fn max_block_bytes(&self) -> u64 {
+        self.max_block_bytes
+    }
+
     /// Creates a NIL block proposal extending the highest certified block from the block store.
     pub fn generate_nil_block(&self, round: Round) -> Result<Block<T>, ProposalGenerationError> {
         let hqc_block = self.block_store.highest_certified_block();
@@ -143,11 +174,16 @@ impl<T: Payload> ProposalGenerator<T> {
                 return Err(ProposalGenerationError::GivenRoundTooLow(hqc_block.round()));
             }
         };
+        // The blocks ordered by a commit certificate are not in the tree anymore, but their
+        // transactions are not committed yet either.
+        let ordered_blocks = self.block_store.uncommitted_ordered_blocks();
         //let pending_blocks = self.get_pending_blocks(Arc::clone(&hqc_block));
         // Exclude all the pending transactions: these are all the ancestors of
-        // parent (including) up to the root (excluding).
-        let exclude_payload = pending_blocks
+        // parent (including) up to the root (excluding), and the ordered blocks that are in
+        // flight in the commit pipeline.
+        let exclude_payload = ordered_blocks
             .iter()
+            .chain(pending_blocks.iter())
             .map(|block| block.get_payload())
             .collect();
 
@@ -220,18 +256,89 @@ impl<T: Payload> ProposalGenerator<T> {
         };
 
         let block_store = Arc::clone(&self.block_store);
-        match self
-            .txn_manager
-            .pull_txns(self.max_block_size, exclude_payload)
-            .await
-        {
-            Ok(txns) => Ok(block_store.create_block(
-                hqc_block.block(),
-                txns,
-                round,
-                block_timestamp.as_micros() as u64,
-            )),
-            Err(_) => Err(ProposalGenerationError::TxnRetrievalError),
+        let uncommitted_blocks = pending_blocks.len() + ordered_blocks.len();
+        let txns = if uncommitted_blocks > self.max_uncommitted_blocks {
+            // The ordered blocks are not committed as fast as they are proposed: do not add more
+            // transactions to the backlog of the pipeline until it catches up.
+            counters::BACK_PRESSURE_EMPTY_PROPOSAL_COUNT.inc();
+            warn!(
+                "{} ordered blocks are not committed yet (limit {}), proposing an empty block",
+                uncommitted_blocks, self.max_uncommitted_blocks
+            );
+            T::default()
+        } else {
+            match self.take_failed_payload(&pending_blocks) {
+                Some(payload) => {
+                    counters::REPROPOSED_PAYLOAD_COUNT.inc();
+                    payload
+                }
+                None => self.pull_txns(exclude_payload).await?,
+            }
+        };
+        let block = block_store.create_block(
+            hqc_block.block(),
+            txns,
+            round,
+            block_timestamp.as_micros() as u64,
+        );
+        if *block.get_payload() != T::default() {
+            *self.retained_payload.lock().unwrap() = Some(RetainedPayload {
+                block_id: block.id(),
+                root_id: self.block_store.root().id(),
+                payload: block.get_payload().clone(),
+            });
+        }
+        Ok(block)
+    }
+
+    /// Pulls at most max_block_size transactions whose serialized size is at most
+    /// max_block_bytes: as the payload cannot be trimmed, the transactions are pulled again with
+    /// a lower count limit, proportional to the excess of bytes, until they fit.
+    async fn pull_txns<'a>(
+        &'a self,
+        exclude_payload: Vec<&'a T>,
+    ) -> Result<T, ProposalGenerationError> {
+        let mut max_size = self.max_block_size;
+        while max_size > 0 {
+            let txns = self
+                .txn_manager
+                .pull_txns(max_size, exclude_payload.clone())
+                .await
+                .map_err(|_| ProposalGenerationError::TxnRetrievalError)?;
+            let payload_size = serialized_payload_size(&txns);
+            if payload_size <= self.max_block_bytes {
+                return Ok(txns);
+            }
+            counters::OVERSIZED_PAYLOAD_COUNT.inc();
+            warn!(
+                "Payload of {} bytes exceeds the limit of {} bytes, pulling fewer transactions",
+                payload_size, self.max_block_bytes
+            );
+            let fitting_size = (u128::from(max_size) * u128::from(self.max_block_bytes)
+                / u128::from(payload_size)) as u64;
+            max_size = std::cmp::min(fitting_size, max_size - 1);
+        }
+        Ok(T::default())
+    }
+
+    /// Returns the payload of the previous proposal of this node if that proposal failed: it has
+    /// not been certified and is not an ancestor of the branch being extended. The payload is
+    /// dropped once some block gets committed, as its transactions might have been committed by
+    /// another proposal in the meantime.
+    fn take_failed_payload(&self, pending_blocks: &[Arc<ExecutedBlock<T>>]) -> Option<T> {
+        let retained = self.retained_payload.lock().unwrap().take()?;
+        let failed = self
+            .block_store
+            .get_quorum_cert_for_block(retained.block_id)
+            .is_none()
+            && pending_blocks
+                .iter()
+                .all(|block| block.id() != retained.block_id)
+            && self.block_store.root().id() == retained.root_id;
+        if failed {
+            Some(retained.payload)
+        } else {
+            None
         }
     }
 }
@@ -3,8 +3,10 @@
 
 use crate::{
     chained_bft::{
-        block_storage::BlockReader,
-        consensus_types::{quorum_cert::QuorumCert, vote_data::VoteData, vote_msg::VoteMsg},
+        block_storage::{BlockReader, BlockStore},
+        consensus_types::{
+            block::ExecutedBlock, quorum_cert::QuorumCert, vote_data::VoteData, vote_msg::VoteMsg,
+        },
         liveness::proposal_generator::{ProposalGenerationError, ProposalGenerator},
         test_utils::{
             build_empty_tree, placeholder_ledger_info, MockTransactionManager, TreeInserter,
@@ -14,6 +16,7 @@ use crate::{
 };
 use futures::executor::block_on;
 use std::{
+    collections::HashSet,
     sync::Arc,
     time::{Duration, Instant},
 };
@@ -22,6 +25,29 @@ fn minute_from_now() -> Instant {
     Instant::now() + Duration::new(60, 0)
 }
 
+// Inserts a QC for the given block, signed by the signer of the block store.
+fn certify(block_store: &BlockStore<Vec<usize>>, block: &ExecutedBlock<Vec<usize>>) {
+    let vote_msg = VoteMsg::new(
+        VoteData::new(
+            block.id(),
+            block_store
+                .get_compute_result(block.id())
+                .unwrap()
+                .executed_state
+                .state_id,
+            block.round(),
+            block.quorum_cert().parent_block_id(),
+            block.quorum_cert().parent_block_round(),
+            block.quorum_cert().grandparent_block_id(),
+            block.quorum_cert().grandparent_block_round(),
+        ),
+        block_store.signer().author(),
+        placeholder_ledger_info(),
+        block_store.signer(),
+    );
+    block_store.insert_vote_and_qc(vote_msg, 1);
+}
+
 #[test]
 fn test_proposal_generation_empty_tree() {
     let block_store = build_empty_tree();
@@ -30,6 +56,8 @@ fn test_proposal_generation_empty_tree() {
         Arc::new(MockTransactionManager::new()),
         Arc::new(SimulatedTimeService::new()),
         1,
+        u64::max_value(),
+        usize::max_value(),
         true,
     );
     let genesis = block_store.root();
@@ -58,6 +86,8 @@ fn test_proposal_generation_parent() {
         Arc::new(MockTransactionManager::new()),
         Arc::new(SimulatedTimeService::new()),
         1,
+        u64::max_value(),
+        usize::max_value(),
         true,
     );
     let genesis = block_store.root();
@@ -138,6 +168,8 @@ fn test_old_proposal_generation() {
         Arc::new(MockTransactionManager::new()),
         Arc::new(SimulatedTimeService::new()),
         1,
+        u64::max_value(),
+        usize::max_value(),
         true,
     );
     let genesis = block_store.root();
@@ -168,3 +200,141 @@ fn test_old_proposal_generation() {
         ProposalGenerationError::GivenRoundTooLow(1)
     );
 }
+
+#[test]
+fn test_failed_payload_reproposal() {
+    let block_store = build_empty_tree();
+    let proposal_generator = ProposalGenerator::new(
+        block_store.clone(),
+        Arc::new(MockTransactionManager::new()),
+        Arc::new(SimulatedTimeService::new()),
+        1,
+        u64::max_value(),
+        usize::max_value(),
+        true,
+    );
+    let genesis = block_store.root();
+
+    // The first proposal never gets certified: its payload is proposed again.
+    let failed_proposal =
+        block_on(proposal_generator.generate_proposal(1, minute_from_now())).unwrap();
+    let reproposal = block_on(proposal_generator.generate_proposal(2, minute_from_now())).unwrap();
+    assert_eq!(reproposal.parent_id(), genesis.id());
+    assert_eq!(reproposal.get_payload(), failed_proposal.get_payload());
+
+    // Once the proposal is certified, a fresh payload is pulled.
+    let executed_reproposal = block_on(block_store.execute_and_insert_block(reproposal)).unwrap();
+    let vote_msg = VoteMsg::new(
+        VoteData::new(
+            executed_reproposal.id(),
+            block_store
+                .get_compute_result(executed_reproposal.id())
+                .unwrap()
+                .executed_state
+                .state_id,
+            executed_reproposal.round(),
+            executed_reproposal.quorum_cert().parent_block_id(),
+            executed_reproposal.quorum_cert().parent_block_round(),
+            executed_reproposal.quorum_cert().grandparent_block_id(),
+            executed_reproposal.quorum_cert().grandparent_block_round(),
+        ),
+        block_store.signer().author(),
+        placeholder_ledger_info(),
+        block_store.signer(),
+    );
+    block_store.insert_vote_and_qc(vote_msg, 1);
+    let next_proposal =
+        block_on(proposal_generator.generate_proposal(3, minute_from_now())).unwrap();
+    assert_eq!(next_proposal.parent_id(), executed_reproposal.id());
+    assert_ne!(
+        next_proposal.get_payload(),
+        executed_reproposal.get_payload()
+    );
+}
+
+#[test]
+fn test_proposal_generation_max_block_bytes() {
+    let block_store = build_empty_tree();
+    // Each mock transaction below 128 is serialized in a single byte, after a 1-byte header.
+    let proposal_generator = ProposalGenerator::new(
+        block_store.clone(),
+        Arc::new(MockTransactionManager::new()),
+        Arc::new(SimulatedTimeService::new()),
+        10,
+        6,
+        usize::max_value(),
+        true,
+    );
+    assert_eq!(proposal_generator.max_block_bytes(), 6);
+
+    // The 10 transactions exceed the limit: fewer transactions are pulled again.
+    let proposal = block_on(proposal_generator.generate_proposal(1, minute_from_now())).unwrap();
+    assert!(proposal.payload_size() <= 6);
+    assert!(!proposal.get_payload().is_empty());
+}
+
+#[test]
+fn test_proposal_generation_back_pressure() {
+    let block_store = build_empty_tree();
+    let mut inserter = TreeInserter::new(block_store.clone());
+    let new_proposal_generator = |max_uncommitted_blocks| {
+        ProposalGenerator::new(
+            block_store.clone(),
+            Arc::new(MockTransactionManager::new()),
+            Arc::new(SimulatedTimeService::new()),
+            1,
+            u64::max_value(),
+            max_uncommitted_blocks,
+            true,
+        )
+    };
+    let genesis = block_store.root();
+    // a1 is certified by the QC of a2, but not committed.
+    let a1 = inserter.insert_block(&genesis, 1);
+    inserter.insert_block(&a1, 2);
+
+    // Below the limit, the proposal carries transactions.
+    let proposal =
+        block_on(new_proposal_generator(1).generate_proposal(3, minute_from_now())).unwrap();
+    assert_eq!(proposal.parent_id(), a1.id());
+    assert!(!proposal.get_payload().is_empty());
+
+    // Above the limit, the proposal is empty until the uncommitted blocks are committed.
+    let proposal =
+        block_on(new_proposal_generator(0).generate_proposal(3, minute_from_now())).unwrap();
+    assert_eq!(proposal.parent_id(), a1.id());
+    assert!(proposal.get_payload().is_empty());
+}
+
+#[test]
+fn test_proposal_generation_excludes_in_flight_payloads() {
+    let block_store = build_empty_tree();
+    // The mock transaction manager pulls the uncommitted transactions again unless excluded.
+    let proposal_generator = ProposalGenerator::new(
+        block_store.clone(),
+        Arc::new(MockTransactionManager::new()),
+        Arc::new(SimulatedTimeService::new()),
+        2,
+        u64::max_value(),
+        usize::max_value(),
+        true,
+    );
+    let mut proposed_txns = HashSet::new();
+    let mut chain = vec![block_store.root()];
+    for round in 1..=4 {
+        let proposal =
+            block_on(proposal_generator.generate_proposal(round, minute_from_now())).unwrap();
+        assert_eq!(proposal.parent_id(), chain.last().unwrap().id());
+        for txn in proposal.get_payload() {
+            assert!(proposed_txns.insert(*txn), "txn {} proposed twice", txn);
+        }
+        let executed_proposal = block_on(block_store.execute_and_insert_block(proposal)).unwrap();
+        certify(&block_store, &executed_proposal);
+        chain.push(executed_proposal);
+        if round == 2 {
+            // The first proposal is ordered, but its transactions are not committed yet.
+            block_store.order_blocks(chain[1].id());
+            assert_eq!(block_store.num_uncommitted_ordered_blocks(), 1);
+        }
+    }
+}
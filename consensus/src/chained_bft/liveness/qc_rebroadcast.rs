@@ -0,0 +1,54 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::chained_bft::common::{Author, Round};
+use std::collections::HashMap;
+
+#[cfg(test)]
+#[path = "qc_rebroadcast_test.rs"]
+mod qc_rebroadcast_test;
+
+/// The minimal number of rounds between two rebroadcasts to the same peer, such that a peer that
+/// is down doesn't receive the highest QC in every round.
+pub const QC_REBROADCAST_INTERVAL_ROUNDS: Round = 3;
+
+/// Tracks the rounds in which the peers last voted, to send the highest QC (and commit cert) of
+/// the node to the peers left behind when it enters a new round: a peer that missed the proposal
+/// carrying a QC would otherwise only catch up on timeout.
+#[derive(Default)]
+pub struct QcRebroadcaster {
+    // The highest round in which each peer is known to have voted.
+    vote_rounds: HashMap<Author, Round>,
+    // The round in which the highest QC was last sent to each peer.
+    rebroadcast_rounds: HashMap<Author, Round>,
+}
+
+impl QcRebroadcaster {
+    /// Records a vote of the given peer.
+    pub fn observe_vote(&mut self, author: Author, round: Round) {
+        let vote_round = self.vote_rounds.entry(author).or_insert(round);
+        *vote_round = std::cmp::max(*vote_round, round);
+    }
+
+    /// Returns the peers to send the highest QC to when entering the given round: the peers whose
+    /// last vote is older than the previous round, unless they were sent the highest QC in the
+    /// last `QC_REBROADCAST_INTERVAL_ROUNDS` rounds.
+    pub fn peers_behind(&mut self, round: Round) -> Vec<Author> {
+        let mut peers = vec![];
+        for (author, vote_round) in &self.vote_rounds {
+            let recently_sent = self
+                .rebroadcast_rounds
+                .get(author)
+                .map_or(false, |sent_round| {
+                    sent_round + QC_REBROADCAST_INTERVAL_ROUNDS > round
+                });
+            if vote_round + 1 < round && !recently_sent {
+                peers.push(*author);
+            }
+        }
+        for author in &peers {
+            self.rebroadcast_rounds.insert(*author, round);
+        }
+        peers
+    }
+}
@@ -0,0 +1,42 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::chained_bft::liveness::qc_rebroadcast::{
+    QcRebroadcaster, QC_REBROADCAST_INTERVAL_ROUNDS,
+};
+use types::account_address::AccountAddress;
+
+#[test]
+fn test_peers_behind() {
+    let mut rebroadcaster = QcRebroadcaster::default();
+    let up_to_date = AccountAddress::random();
+    let behind = AccountAddress::random();
+    rebroadcaster.observe_vote(up_to_date, 4);
+    rebroadcaster.observe_vote(behind, 2);
+    // An older vote doesn't override the last one.
+    rebroadcaster.observe_vote(up_to_date, 1);
+
+    assert!(rebroadcaster.peers_behind(3).is_empty());
+    assert_eq!(rebroadcaster.peers_behind(5), vec![behind]);
+
+    // The peer catches up.
+    rebroadcaster.observe_vote(behind, 5);
+    rebroadcaster.observe_vote(up_to_date, 5);
+    assert!(rebroadcaster.peers_behind(6).is_empty());
+}
+
+#[test]
+fn test_rebroadcast_interval() {
+    let mut rebroadcaster = QcRebroadcaster::default();
+    let peer = AccountAddress::random();
+    rebroadcaster.observe_vote(peer, 1);
+
+    assert_eq!(rebroadcaster.peers_behind(3), vec![peer]);
+    for round in 4..3 + QC_REBROADCAST_INTERVAL_ROUNDS {
+        assert!(rebroadcaster.peers_behind(round).is_empty());
+    }
+    assert_eq!(
+        rebroadcaster.peers_behind(3 + QC_REBROADCAST_INTERVAL_ROUNDS),
+        vec![peer]
+    );
+}
@@ -6,6 +6,10 @@ use crate::chained_bft::{
     consensus_types::block::Block,
     liveness::proposer_election::ProposerElection,
 };
+use std::cmp::Reverse;
+
+// The longest schedule of a weighted rotation, the weights are scaled down beyond that.
+const MAX_WEIGHTED_SCHEDULE_LEN: u128 = 10_000;
 
 /// The rotating proposer maps a round to an author according to a round-robin rotation.
 /// A fixed proposer strategy loses liveness when the fixed proposer is down. Rotating proposers
@@ -27,6 +31,14 @@ impl RotatingProposer {
         }
     }
 
+    /// The weighted rotation goes through a schedule in which every proposer is active in a
+    /// number of slots proportional to its weight (e.g., its voting power). The schedule only
+    /// depends on the proposers and their weights, such that all the honest replicas agree on
+    /// the proposer of any round.
+    pub fn new_weighted(weighted_proposers: Vec<(Author, u64)>, contiguous_rounds: u32) -> Self {
+        Self::new(weighted_schedule(weighted_proposers), contiguous_rounds)
+    }
+
     fn get_proposer(&self, round: Round) -> Author {
         self.proposers
             [((round / u64::from(self.contiguous_rounds)) % self.proposers.len() as u64) as usize]
@@ -61,3 +73,52 @@ impl<T: Payload> ProposerElection<T> for RotatingProposer {
         None
     }
 }
+
+/// Smooth weighted round-robin: in every slot each proposer earns its weight in credit, the
+/// proposer with the most credit (the first one in case of a tie) is picked and pays the total
+/// weight. The slots of a proposer are hence evenly spread over the schedule rather than
+/// contiguous.
+fn weighted_schedule(weighted_proposers: Vec<(Author, u64)>) -> Vec<Author> {
+    assert!(!weighted_proposers.is_empty());
+    assert!(
+        weighted_proposers.iter().all(|(_, weight)| *weight > 0),
+        "Every proposer needs a positive weight"
+    );
+    let divisor = weighted_proposers
+        .iter()
+        .fold(0, |divisor, (_, weight)| gcd(divisor, *weight));
+    let mut weights: Vec<u128> = weighted_proposers
+        .iter()
+        .map(|(_, weight)| u128::from(*weight / divisor))
+        .collect();
+    let total_weight: u128 = weights.iter().sum();
+    if total_weight > MAX_WEIGHTED_SCHEDULE_LEN {
+        for weight in weights.iter_mut() {
+            *weight = std::cmp::max(1, *weight * MAX_WEIGHTED_SCHEDULE_LEN / total_weight);
+        }
+    }
+    let total_weight: u128 = weights.iter().sum();
+    let mut credits = vec![0i128; weights.len()];
+    let mut schedule = Vec::with_capacity(total_weight as usize);
+    for _ in 0..total_weight {
+        for (credit, weight) in credits.iter_mut().zip(&weights) {
+            *credit += *weight as i128;
+        }
+        let (index, _) = credits
+            .iter()
+            .enumerate()
+            .max_by_key(|(index, credit)| (**credit, Reverse(*index)))
+            .expect("The proposers are not empty");
+        credits[index] -= total_weight as i128;
+        schedule.push(weighted_proposers[index].0);
+    }
+    schedule
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
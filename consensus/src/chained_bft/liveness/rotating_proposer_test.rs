@@ -177,3 +177,42 @@ fn test_fixed_proposer() {
     assert_eq!(pe.is_valid_proposer(another_author, 1), None);
     assert_eq!(pe.get_valid_proposers(1), vec![chosen_author]);
 }
+
+#[test]
+fn test_weighted_rotating_proposer() {
+    let heavy_author = ValidatorSigner::random([0u8; 32]).author();
+    let light_author = ValidatorSigner::random([1u8; 32]).author();
+    // The weights are normalized, the schedule is the same as with the weights 3 and 1.
+    let pe: Box<dyn ProposerElection<u32>> = Box::new(RotatingProposer::new_weighted(
+        vec![(heavy_author, 30), (light_author, 10)],
+        1,
+    ));
+    let schedule: Vec<_> = (0..8)
+        .map(|round| pe.get_valid_proposers(round)[0])
+        .collect();
+    assert_eq!(
+        schedule,
+        vec![
+            heavy_author,
+            heavy_author,
+            light_author,
+            heavy_author,
+            heavy_author,
+            heavy_author,
+            light_author,
+            heavy_author
+        ]
+    );
+    assert_eq!(pe.is_valid_proposer(light_author, 2), Some(light_author));
+    assert_eq!(pe.is_valid_proposer(heavy_author, 2), None);
+
+    // Huge weights are scaled down, the proportions are preserved.
+    let pe: Box<dyn ProposerElection<u32>> = Box::new(RotatingProposer::new_weighted(
+        vec![(heavy_author, 3_000_000_007), (light_author, 1_000_000_007)],
+        1,
+    ));
+    let light_rounds = (0..10_000)
+        .filter(|round| pe.get_valid_proposers(*round)[0] == light_author)
+        .count();
+    assert!(light_rounds >= 2_400 && light_rounds <= 2_600);
+}
@@ -0,0 +1,124 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    chained_bft::{common::Round, consensus_types::timeout_msg::TimeoutMsg},
+    util::time_service::{SendTask, TimeService},
+};
+use channel;
+use rand::{thread_rng, Rng};
+use std::{sync::Arc, time::Duration};
+
+#[cfg(test)]
+#[path = "timeout_retransmission_test.rs"]
+mod timeout_retransmission_test;
+
+/// How often the timeout message of a round is broadcast again until the round advances.
+#[derive(Clone, Debug)]
+pub struct TimeoutRetransmissionPolicy {
+    /// Minimal delay between two broadcasts of the timeout message
+    pub interval: Duration,
+    /// Upper bound of the random delay added to the interval, such that the replicas that timed
+    /// out together don't retransmit together
+    pub max_jitter: Duration,
+    /// Maximal number of retransmissions of the timeout message of a round, 0 disables them
+    pub max_retransmissions: usize,
+}
+
+impl Default for TimeoutRetransmissionPolicy {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_millis(500),
+            max_jitter: Duration::from_millis(250),
+            max_retransmissions: 4,
+        }
+    }
+}
+
+impl TimeoutRetransmissionPolicy {
+    /// The delay before the next retransmission: the interval plus a random jitter.
+    pub fn next_delay(&self) -> Duration {
+        let max_jitter_ms = self.max_jitter.as_millis() as u64;
+        self.interval + Duration::from_millis(thread_rng().gen_range(0, max_jitter_ms + 1))
+    }
+}
+
+/// Keeps the timeout message of the round the node timed out in and schedules its
+/// retransmissions: a lost timeout message would otherwise only be sent again once the round
+/// times out again. The retransmissions are sent to the given channel as the round of the
+/// timeout message, at most one retransmission is pending at any time.
+pub struct TimeoutRetransmitter {
+    policy: TimeoutRetransmissionPolicy,
+    time_service: Arc<dyn TimeService>,
+    retransmission_sender: channel::Sender<Round>,
+    // The last timeout message sent and the number of retransmissions left
+    timeout_msg: Option<(TimeoutMsg, usize)>,
+    // Whether a retransmission is scheduled and not processed yet
+    scheduled: bool,
+}
+
+impl TimeoutRetransmitter {
+    pub fn new(
+        policy: TimeoutRetransmissionPolicy,
+        time_service: Arc<dyn TimeService>,
+        retransmission_sender: channel::Sender<Round>,
+    ) -> Self {
+        Self {
+            policy,
+            time_service,
+            retransmission_sender,
+            timeout_msg: None,
+            scheduled: false,
+        }
+    }
+
+    /// Records the timeout message broadcast by the node and schedules its first retransmission.
+    pub fn timeout_sent(&mut self, timeout_msg: TimeoutMsg) {
+        let round = timeout_msg.pacemaker_timeout().round();
+        self.timeout_msg = Some((timeout_msg, self.policy.max_retransmissions));
+        self.schedule(round);
+    }
+
+    /// Processes a scheduled retransmission of the given round: returns the timeout message to
+    /// broadcast again if the node is still in the round of its last timeout message and the
+    /// retransmissions of that round are not exhausted.
+    pub fn retransmission_due(
+        &mut self,
+        scheduled_round: Round,
+        current_round: Round,
+    ) -> Option<TimeoutMsg> {
+        self.scheduled = false;
+        let (timeout_round, retransmissions_left) = match &self.timeout_msg {
+            Some((timeout_msg, left)) => (timeout_msg.pacemaker_timeout().round(), *left),
+            None => return None,
+        };
+        if timeout_round != current_round || retransmissions_left == 0 {
+            self.timeout_msg = None;
+            return None;
+        }
+        if scheduled_round != timeout_round {
+            // The retransmission was scheduled for the timeout message of a previous round.
+            self.schedule(timeout_round);
+            return None;
+        }
+        let timeout_msg = self.timeout_msg.as_mut().map(|(timeout_msg, left)| {
+            *left -= 1;
+            timeout_msg.clone()
+        });
+        if retransmissions_left > 1 {
+            self.schedule(timeout_round);
+        }
+        timeout_msg
+    }
+
+    fn schedule(&mut self, round: Round) {
+        if self.scheduled || self.policy.max_retransmissions == 0 {
+            return;
+        }
+        self.scheduled = true;
+        self.time_service.run_after(
+            self.policy.next_delay(),
+            SendTask::make(self.retransmission_sender.clone(), round),
+        );
+    }
+}
@@ -0,0 +1,135 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    chained_bft::{
+        common::Round,
+        consensus_types::{
+            quorum_cert::QuorumCert,
+            sync_info::SyncInfo,
+            timeout_msg::{PacemakerTimeout, TimeoutMsg},
+        },
+        liveness::timeout_retransmission::{TimeoutRetransmissionPolicy, TimeoutRetransmitter},
+    },
+    util::mock_time_service::SimulatedTimeService,
+};
+use channel;
+use futures::{
+    task::{noop_waker, Context, Poll},
+    StreamExt,
+};
+use std::{sync::Arc, time::Duration};
+use types::crypto_proxies::ValidatorSigner;
+
+// The round of the retransmission sent to the channel, if any.
+fn sent_round(rx: &mut channel::Receiver<Round>) -> Option<Round> {
+    let waker = noop_waker();
+    let mut cx = Context::from_waker(&waker);
+    match rx.poll_next_unpin(&mut cx) {
+        Poll::Ready(round) => round,
+        Poll::Pending => None,
+    }
+}
+
+fn timeout_msg(round: Round, signer: &ValidatorSigner) -> TimeoutMsg {
+    TimeoutMsg::new(
+        SyncInfo::new(
+            QuorumCert::certificate_for_genesis(),
+            QuorumCert::certificate_for_genesis(),
+            None,
+        ),
+        PacemakerTimeout::new(round, signer, None),
+        signer,
+    )
+}
+
+fn policy(max_retransmissions: usize) -> TimeoutRetransmissionPolicy {
+    TimeoutRetransmissionPolicy {
+        interval: Duration::from_millis(100),
+        max_jitter: Duration::from_millis(50),
+        max_retransmissions,
+    }
+}
+
+#[test]
+fn test_jitter() {
+    let policy = policy(1);
+    for _ in 0..100 {
+        let delay = policy.next_delay();
+        assert!(delay >= Duration::from_millis(100) && delay <= Duration::from_millis(150));
+    }
+}
+
+#[test]
+fn test_retransmissions_until_round_advances() {
+    let signer = ValidatorSigner::random(None);
+    let mut time_service = SimulatedTimeService::new();
+    let (tx, mut rx) = channel::new_test(8);
+    let mut retransmitter =
+        TimeoutRetransmitter::new(policy(5), Arc::new(time_service.clone()), tx);
+
+    retransmitter.timeout_sent(timeout_msg(1, &signer));
+    // Nothing is sent before the interval.
+    time_service.update_auto_advance_limit(Duration::from_millis(99));
+    assert_eq!(sent_round(&mut rx), None);
+    time_service.update_auto_advance_limit(Duration::from_millis(51));
+    assert_eq!(sent_round(&mut rx), Some(1));
+
+    let retransmitted = retransmitter.retransmission_due(1, 1).unwrap();
+    assert_eq!(retransmitted.pacemaker_timeout().round(), 1);
+    time_service.update_auto_advance_limit(Duration::from_millis(150));
+    assert_eq!(sent_round(&mut rx), Some(1));
+
+    // The round advanced: no more retransmissions.
+    assert_eq!(retransmitter.retransmission_due(1, 2), None);
+    time_service.update_auto_advance_limit(Duration::from_millis(150));
+    assert_eq!(sent_round(&mut rx), None);
+}
+
+#[test]
+fn test_max_retransmissions() {
+    let signer = ValidatorSigner::random(None);
+    let mut time_service = SimulatedTimeService::new();
+    let (tx, mut rx) = channel::new_test(8);
+    let mut retransmitter =
+        TimeoutRetransmitter::new(policy(2), Arc::new(time_service.clone()), tx);
+
+    retransmitter.timeout_sent(timeout_msg(1, &signer));
+    for _ in 0..2 {
+        time_service.update_auto_advance_limit(Duration::from_millis(150));
+        assert_eq!(sent_round(&mut rx), Some(1));
+        assert!(retransmitter.retransmission_due(1, 1).is_some());
+    }
+    time_service.update_auto_advance_limit(Duration::from_millis(150));
+    assert_eq!(sent_round(&mut rx), None);
+    assert_eq!(retransmitter.retransmission_due(1, 1), None);
+
+    // The retransmissions are disabled.
+    let (tx, mut rx) = channel::new_test(8);
+    let mut retransmitter =
+        TimeoutRetransmitter::new(policy(0), Arc::new(time_service.clone()), tx);
+    retransmitter.timeout_sent(timeout_msg(2, &signer));
+    time_service.update_auto_advance_limit(Duration::from_millis(150));
+    assert_eq!(sent_round(&mut rx), None);
+}
+
+#[test]
+fn test_stale_retransmission_rescheduled() {
+    let signer = ValidatorSigner::random(None);
+    let mut time_service = SimulatedTimeService::new();
+    let (tx, mut rx) = channel::new_test(8);
+    let mut retransmitter =
+        TimeoutRetransmitter::new(policy(5), Arc::new(time_service.clone()), tx);
+
+    retransmitter.timeout_sent(timeout_msg(1, &signer));
+    // The node timed out in the next round before the retransmission of round 1.
+    retransmitter.timeout_sent(timeout_msg(2, &signer));
+    time_service.update_auto_advance_limit(Duration::from_millis(150));
+    assert_eq!(sent_round(&mut rx), Some(1));
+    assert_eq!(sent_round(&mut rx), None);
+    assert_eq!(retransmitter.retransmission_due(1, 2), None);
+    time_service.update_auto_advance_limit(Duration::from_millis(150));
+    assert_eq!(sent_round(&mut rx), Some(2));
+    let retransmitted = retransmitter.retransmission_due(2, 2).unwrap();
+    assert_eq!(retransmitted.pacemaker_timeout().round(), 2);
+}
@@ -5,20 +5,38 @@ mod common;
 mod consensus_types;
 mod consensusdb;
 mod safety;
+pub use safety::remote_safety_rules::run_safety_rules_service;
 
 mod block_storage;
 pub mod chained_bft_consensus_provider;
 pub use consensus_types::quorum_cert::QuorumCert;
 mod chained_bft_smr;
+mod commit_pipeline;
+#[cfg(not(feature = "fuzzing"))]
 mod network;
+#[cfg(feature = "fuzzing")]
+pub mod network;
+mod network_rate_limit;
 
 pub mod epoch_manager;
+#[cfg(test)]
+mod event_hooks;
+pub mod event_log;
+pub mod evidence;
+mod introspection;
+mod latency_tracker;
+mod pending_votes_writer;
 pub mod persistent_storage;
+mod signature_verifier;
+pub mod snapshot;
+pub mod state_dump;
 mod sync_manager;
 
 #[cfg(test)]
 mod chained_bft_smr_test;
 #[cfg(test)]
+mod conformance;
+#[cfg(test)]
 mod network_tests;
 #[cfg(test)]
 mod proto_test;
@@ -12,6 +12,8 @@ use crate::{
             vote_msg::VoteMsg,
         },
         epoch_manager::EpochManager,
+        network_rate_limit::{InboundMessageType, InboundRateLimitPolicy, InboundRateLimiter},
+        signature_verifier::SignatureVerifier,
     },
     counters,
 };
@@ -20,23 +22,34 @@ use channel;
 use crypto::HashValue;
 use failure;
 use futures::{
-    channel::oneshot, stream::select, FutureExt, SinkExt, Stream, StreamExt, TryFutureExt,
-    TryStreamExt,
+    channel::oneshot,
+    future::poll_fn,
+    stream::{select, FuturesUnordered},
+    task::Poll,
+    FutureExt, SinkExt, Stream, StreamExt, TryFutureExt, TryStreamExt,
 };
 use logger::prelude::*;
 use network::{
-    proto::{BlockRetrievalStatus, ConsensusMsg, RequestBlock, RespondBlock},
+    proto::{BlockRetrievalStatus, ConsensusMsg, RequestBlock, RequestSyncInfo, RespondBlock},
     validator_network::{ConsensusNetworkEvents, ConsensusNetworkSender, Event, RpcError},
 };
 use proto_conv::{FromProto, IntoProto};
 use protobuf::Message;
 use std::{
+    collections::VecDeque,
     sync::Arc,
     time::{Duration, Instant},
 };
 use tokio::runtime::TaskExecutor;
 use types::account_address::AccountAddress;
 
+#[cfg(any(feature = "fuzzing", test))]
+#[path = "network_fuzzing.rs"]
+pub mod network_fuzzing;
+
+// Maximal number of votes verified together by the signature verifier.
+const MAX_VOTE_BATCH_SIZE: usize = 256;
+
 /// The response sent back from EventProcessor for the BlockRetrievalRequest.
 #[derive(Debug)]
 pub struct BlockRetrievalResponse<T> {
@@ -80,6 +93,120 @@ pub struct BlockRetrievalRequest<T> {
     pub response_sender: oneshot::Sender<BlockRetrievalResponse<T>>,
 }
 
+/// Chunked variant of the block retrieval: the chain of `num_blocks` blocks ending at a given
+/// block id is retrieved in chunks of at most `chunk_size` blocks instead of a single response
+/// carrying the whole chain. Every chunk is a request of its own to the unary block retrieval
+/// RPC, the peers don't stream the blocks. The blocks of every chunk are validated as soon as
+/// the chunk is received.
+///
+/// A failed chunk request doesn't affect the blocks already retrieved: the stream stays
+/// positioned at the first missing block and the retrieval can be resumed (e.g., from another
+/// peer) by calling `next_block` again. A chunk can also be requested from several peers
+/// concurrently with `next_block_from_any`.
+pub struct ChunkedBlockRetrieval<T> {
+    network: ConsensusNetworkImpl,
+    // The id of the first block that hasn't been requested yet.
+    next_block_id: HashValue,
+    // The number of blocks that haven't been requested yet.
+    remaining: u64,
+    chunk_size: u64,
+    // The blocks received and validated, but not returned yet.
+    buffered: VecDeque<Block<T>>,
+}
+
+impl<T: Payload> ChunkedBlockRetrieval<T> {
+    pub fn new(
+        network: ConsensusNetworkImpl,
+        block_id: HashValue,
+        num_blocks: u64,
+        chunk_size: u64,
+    ) -> Self {
+        assert!(chunk_size > 0, "block retrieval chunk size can't be 0");
+        Self {
+            network,
+            next_block_id: block_id,
+            remaining: num_blocks,
+            chunk_size,
+            buffered: VecDeque::new(),
+        }
+    }
+
+    /// Returns the next block of the chain (following the parent links) or None in case all the
+    /// blocks have been returned. A new chunk is requested from the given peer only when all the
+    /// previously retrieved blocks have been consumed.
+    pub async fn next_block(
+        &mut self,
+        from: Author,
+        timeout: Duration,
+    ) -> failure::Result<Option<Block<T>>> {
+        self.next_block_from_any(&[from], timeout).await
+    }
+
+    /// Same as `next_block`, except that a new chunk is requested from all the given peers
+    /// concurrently: the first valid response is used and the other requests are dropped. Fails
+    /// only if none of the peers returns the chunk before the timeout.
+    pub async fn next_block_from_any<'a>(
+        &'a mut self,
+        peers: &'a [Author],
+        timeout: Duration,
+    ) -> failure::Result<Option<Block<T>>> {
+        if let Some(block) = self.buffered.pop_front() {
+            return Ok(Some(block));
+        }
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+        ensure!(!peers.is_empty(), "No peer to retrieve the blocks from");
+        let num_blocks = self.remaining.min(self.chunk_size);
+        let block_id = self.next_block_id;
+        let mut requests: FuturesUnordered<_> = peers
+            .iter()
+            .map(|peer| {
+                let mut network = self.network.clone();
+                let peer = *peer;
+                async move {
+                    let response = network
+                        .request_block::<T>(block_id, num_blocks, peer, timeout)
+                        .await;
+                    (peer, response)
+                }
+            })
+            .collect();
+        let mut errors = vec![];
+        while let Some((peer, response)) = requests.next().await {
+            match response {
+                Ok(response) if response.status == BlockRetrievalStatus::SUCCEEDED => {
+                    // `request_block` guarantees that the response carries a chain of exactly
+                    // `num_blocks`.
+                    let last_block = response.blocks.last().expect("empty block retrieval chunk");
+                    self.next_block_id = last_block.parent_id();
+                    self.remaining -= num_blocks;
+                    self.buffered.extend(response.blocks);
+                    return Ok(self.buffered.pop_front());
+                }
+                Ok(response) => errors.push(format!("{}: {:?}", peer.short_str(), response.status)),
+                Err(e) => errors.push(format!("{}: {:?}", peer.short_str(), e)),
+            }
+        }
+        bail!(
+            "Failed to retrieve {} blocks starting from {}: [{}]",
+            num_blocks,
+            block_id,
+            errors.join(", ")
+        )
+    }
+
+    /// The id of the first block that hasn't been retrieved yet.
+    pub fn next_block_id(&self) -> HashValue {
+        self.next_block_id
+    }
+
+    /// The number of blocks that haven't been retrieved yet.
+    pub fn remaining(&self) -> u64 {
+        self.remaining
+    }
+}
+
 /// Just a convenience struct to keep all the network proxy receiving queues in one place.
 /// Will be returned by the networking trait upon startup.
 pub struct NetworkReceivers<T> {
@@ -88,6 +215,8 @@ pub struct NetworkReceivers<T> {
     pub block_retrieval: channel::Receiver<BlockRetrievalRequest<T>>,
     pub timeout_msgs: channel::Receiver<TimeoutMsg>,
     pub sync_info_msgs: channel::Receiver<(SyncInfo, AccountAddress)>,
+    /// The peers requesting the sync info of the node.
+    pub sync_info_requests: channel::Receiver<AccountAddress>,
 }
 
 /// Implements the actual networking support for all consensus messaging.
@@ -101,6 +230,13 @@ pub struct ConsensusNetworkImpl {
     self_sender: channel::Sender<failure::Result<Event<ConsensusMsg>>>,
     self_receiver: Option<channel::Receiver<failure::Result<Event<ConsensusMsg>>>>,
     epoch_mgr: Arc<EpochManager>,
+    // The observers following consensus, which receive the proposals and the timeouts on top of
+    // the validators.
+    observers: Vec<Author>,
+    rate_limit_policy: InboundRateLimitPolicy,
+    // The number of threads verifying the signatures of the inbound messages, 0 for one thread per
+    // CPU.
+    signature_verification_threads: usize,
 }
 
 impl Clone for ConsensusNetworkImpl {
@@ -112,6 +248,9 @@ impl Clone for ConsensusNetworkImpl {
             self_sender: self.self_sender.clone(),
             self_receiver: None,
             epoch_mgr: Arc::clone(&self.epoch_mgr),
+            observers: self.observers.clone(),
+            rate_limit_policy: self.rate_limit_policy.clone(),
+            signature_verification_threads: self.signature_verification_threads,
         }
     }
 }
@@ -131,18 +270,72 @@ impl ConsensusNetworkImpl {
             self_sender,
             self_receiver: Some(self_receiver),
             epoch_mgr,
+            observers: vec![],
+            rate_limit_policy: InboundRateLimitPolicy::default(),
+            signature_verification_threads: 0,
         }
     }
 
+    /// Sends the broadcast messages to the given observers as well.
+    pub fn with_observers(mut self, observers: Vec<Author>) -> Self {
+        self.observers = observers;
+        self
+    }
+
+    /// Limits the rate of the messages received from every peer and the number of messages of
+    /// every type waiting for the event processor. Must be called before start.
+    pub fn with_rate_limit_policy(mut self, rate_limit_policy: InboundRateLimitPolicy) -> Self {
+        self.rate_limit_policy = rate_limit_policy;
+        self
+    }
+
+    /// Sets the number of threads verifying the signatures of the inbound messages, 0 for one
+    /// thread per CPU. Must be called before start.
+    pub fn with_signature_verification_threads(mut self, num_threads: usize) -> Self {
+        self.signature_verification_threads = num_threads;
+        self
+    }
+
+    // A message tagged with the current epoch, the peers drop the messages of the other epochs.
+    fn new_msg(&self) -> ConsensusMsg {
+        let mut msg = ConsensusMsg::new();
+        msg.set_epoch(self.epoch_mgr.epoch());
+        msg
+    }
+
     /// Establishes the initial connections with the peers and returns the receivers.
     pub fn start<T: Payload>(&mut self, executor: &TaskExecutor) -> NetworkReceivers<T> {
-        let (proposal_tx, proposal_rx) = channel::new(1_024, &counters::PENDING_PROPOSAL);
-        let (vote_tx, vote_rx) = channel::new(1_024, &counters::PENDING_VOTES);
-        let (block_request_tx, block_request_rx) =
-            channel::new(1_024, &counters::PENDING_BLOCK_REQUESTS);
-        let (timeout_msg_tx, timeout_msg_rx) =
-            channel::new(1_024, &counters::PENDING_NEW_ROUND_MESSAGES);
-        let (sync_info_tx, sync_info_rx) = channel::new(1_024, &counters::PENDING_SYNC_INFO_MSGS);
+        let policy = &self.rate_limit_policy;
+        let (proposal_tx, proposal_rx) = channel::new(
+            policy.queue_size(InboundMessageType::Proposal),
+            &counters::PENDING_PROPOSAL,
+        );
+        let (vote_tx, vote_rx) = channel::new(
+            policy.queue_size(InboundMessageType::Vote),
+            &counters::PENDING_VOTES,
+        );
+        // The votes of the peers wait for the verification of their signatures in their own
+        // queue, such that they can be verified in batch.
+        let (unverified_vote_tx, unverified_vote_rx) = channel::new(
+            policy.queue_size(InboundMessageType::Vote),
+            &counters::PENDING_UNVERIFIED_VOTES,
+        );
+        let (block_request_tx, block_request_rx) = channel::new(
+            policy.queue_size(InboundMessageType::BlockRequest),
+            &counters::PENDING_BLOCK_REQUESTS,
+        );
+        let (timeout_msg_tx, timeout_msg_rx) = channel::new(
+            policy.queue_size(InboundMessageType::TimeoutMsg),
+            &counters::PENDING_NEW_ROUND_MESSAGES,
+        );
+        let (sync_info_tx, sync_info_rx) = channel::new(
+            policy.queue_size(InboundMessageType::SyncInfo),
+            &counters::PENDING_SYNC_INFO_MSGS,
+        );
+        let (sync_info_request_tx, sync_info_request_rx) = channel::new(
+            policy.queue_size(InboundMessageType::SyncInfoRequest),
+            &counters::PENDING_SYNC_INFO_REQUESTS,
+        );
         let network_events = self
             .network_events
             .take()
@@ -153,15 +346,34 @@ impl ConsensusNetworkImpl {
             .take()
             .expect("[consensus]: self receiver is already taken");
         let all_events = select(network_events, own_msgs);
+        let verifier = SignatureVerifier::new(self.signature_verification_threads);
+        executor.spawn(
+            VoteVerificationTask {
+                unverified_votes: unverified_vote_rx,
+                vote_tx: vote_tx.clone(),
+                verifier: verifier.clone(),
+                epoch_mgr: Arc::clone(&self.epoch_mgr),
+            }
+            .run()
+            .boxed()
+            .unit_error()
+            .compat(),
+        );
         executor.spawn(
             NetworkTask {
+                author: self.author,
+                network_sender: self.network_sender.clone(),
                 proposal_tx,
                 vote_tx,
+                unverified_vote_tx,
                 block_request_tx,
                 timeout_msg_tx,
                 sync_info_tx,
+                sync_info_request_tx,
                 all_events,
                 epoch_mgr: Arc::clone(&self.epoch_mgr),
+                rate_limiter: InboundRateLimiter::new(self.rate_limit_policy.clone()),
+                verifier,
             }
             .run()
             .boxed()
@@ -174,6 +386,7 @@ impl ConsensusNetworkImpl {
             block_retrieval: block_request_rx,
             timeout_msgs: timeout_msg_rx,
             sync_info_msgs: sync_info_rx,
+            sync_info_requests: sync_info_request_rx,
         }
     }
 
@@ -203,7 +416,10 @@ impl ConsensusNetworkImpl {
             match Block::from_proto(block) {
                 Ok(block) => {
                     block
-                        .validate_signatures(self.epoch_mgr.validators().as_ref())
+                        .validate_signatures(
+                            self.epoch_mgr.validators().as_ref(),
+                            self.epoch_mgr.qc_cache(),
+                        )
                         .map_err(|e| format_err!("Invalid block because of {:?}", e))?;
                     block
                         .verify_well_formed()
@@ -231,13 +447,25 @@ impl ConsensusNetworkImpl {
     /// out. It does not give indication about when the message is delivered to the recipients,
     /// as well as there is no indication about the network failures.
     pub async fn broadcast_proposal<T: Payload>(&mut self, proposal: ProposalMsg<T>) {
-        let mut msg = ConsensusMsg::new();
+        let mut msg = self.new_msg();
         msg.set_proposal(proposal.into_proto());
         self.broadcast(msg).await
     }
 
+    // The validators of the current epoch followed by the observers.
+    fn recipients(&self) -> Vec<Author> {
+        let validators = self.epoch_mgr.validators().get_ordered_account_addresses();
+        let observers = self
+            .observers
+            .iter()
+            .filter(|observer| !validators.contains(observer))
+            .cloned()
+            .collect::<Vec<_>>();
+        validators.into_iter().chain(observers).collect()
+    }
+
     async fn broadcast(&mut self, msg: ConsensusMsg) {
-        for peer in self.epoch_mgr.validators().get_ordered_account_addresses() {
+        for peer in self.recipients() {
             if self.author == peer {
                 let self_msg = Event::Message((self.author, msg.clone()));
                 if let Err(err) = self.self_sender.send(Ok(self_msg)).await {
@@ -265,7 +493,7 @@ impl ConsensusNetworkImpl {
     pub async fn send_vote(&self, vote_msg: VoteMsg, recipients: Vec<Author>) {
         let mut network_sender = self.network_sender.clone();
         let mut self_sender = self.self_sender.clone();
-        let mut msg = ConsensusMsg::new();
+        let mut msg = self.new_msg();
         msg.set_vote(vote_msg.into_proto());
         for peer in recipients {
             if self.author == peer {
@@ -281,9 +509,9 @@ impl ConsensusNetworkImpl {
         }
     }
 
-    /// Broadcasts timeout message to all validators
+    /// Broadcasts timeout message to all validators and observers
     pub async fn broadcast_timeout_msg(&mut self, timeout_msg: TimeoutMsg) {
-        let mut msg = ConsensusMsg::new();
+        let mut msg = self.new_msg();
         msg.set_timeout_msg(timeout_msg.into_proto());
         self.broadcast(msg).await
     }
@@ -296,7 +524,7 @@ impl ConsensusNetworkImpl {
             error!("An attempt to deliver sync info msg to itself: ignore.");
             return;
         }
-        let mut msg = ConsensusMsg::new();
+        let mut msg = self.new_msg();
         msg.set_sync_info(sync_info.into_proto());
         let mut network_sender = self.network_sender.clone();
         if let Err(e) = network_sender.send_to(recipient, msg).await {
@@ -306,16 +534,147 @@ impl ConsensusNetworkImpl {
             );
         }
     }
+
+    /// Sends the given sync info to all the validators and observers but the node itself.
+    pub async fn broadcast_sync_info(&self, sync_info: SyncInfo) {
+        let mut msg = self.new_msg();
+        msg.set_sync_info(sync_info.into_proto());
+        let mut network_sender = self.network_sender.clone();
+        for peer in self.recipients() {
+            if peer == self.author {
+                continue;
+            }
+            if let Err(e) = network_sender.send_to(peer, msg.clone()).await {
+                warn!("Failed to send a sync info msg to peer {:?}: {:?}", peer, e);
+            }
+        }
+    }
+
+    /// Requests the sync info of the given author, which the node lags behind.
+    pub async fn request_sync_info(&self, recipient: Author) {
+        if recipient == self.author {
+            error!("An attempt to request sync info from itself: ignore.");
+            return;
+        }
+        let mut msg = self.new_msg();
+        msg.set_request_sync_info(RequestSyncInfo::new());
+        let mut network_sender = self.network_sender.clone();
+        if let Err(e) = network_sender.send_to(recipient, msg).await {
+            warn!(
+                "Failed to request the sync info of peer {:?}: {:?}",
+                recipient, e
+            );
+        }
+    }
+}
+
+// Sends a message to the queue of its type. The messages of the peers are dropped if the queue is
+// full, such that a flood of messages can't block the network task, while the messages of the
+// node itself wait for room in the queue.
+async fn enqueue<M>(
+    tx: &mut channel::Sender<M>,
+    msg: M,
+    msg_type: InboundMessageType,
+    from_self: bool,
+) -> failure::Result<()> {
+    if from_self {
+        tx.send(msg).await?;
+        return Ok(());
+    }
+    match tx.try_send(msg) {
+        Err(e) if e.is_full() => {
+            counters::DROPPED_INBOUND_MSGS_COUNT.inc();
+            counters::OP_COUNTERS.inc(&format!("queue_full_dropped_{}_msgs", msg_type.as_str()));
+            warn!(
+                "The {} queue is full, dropping the message",
+                msg_type.as_str()
+            );
+            Ok(())
+        }
+        r => r.map_err(Into::into),
+    }
+}
+
+// The type of the messages the network task handles, None for the unexpected messages.
+fn inbound_msg_type(msg: &ConsensusMsg) -> Option<InboundMessageType> {
+    if msg.has_proposal() {
+        Some(InboundMessageType::Proposal)
+    } else if msg.has_vote() {
+        Some(InboundMessageType::Vote)
+    } else if msg.has_timeout_msg() {
+        Some(InboundMessageType::TimeoutMsg)
+    } else if msg.has_sync_info() {
+        Some(InboundMessageType::SyncInfo)
+    } else if msg.has_request_sync_info() {
+        Some(InboundMessageType::SyncInfoRequest)
+    } else if msg.has_request_block() {
+        Some(InboundMessageType::BlockRequest)
+    } else {
+        None
+    }
+}
+
+// Verifies the signatures of the votes of the peers on the signature verifier and delivers the
+// valid votes to the event processor. The votes queued while a batch is verified are verified
+// together in the next batch.
+struct VoteVerificationTask {
+    unverified_votes: channel::Receiver<VoteMsg>,
+    vote_tx: channel::Sender<VoteMsg>,
+    verifier: SignatureVerifier,
+    epoch_mgr: Arc<EpochManager>,
+}
+
+impl VoteVerificationTask {
+    pub async fn run(mut self) {
+        while let Some(vote) = self.unverified_votes.next().await {
+            let mut votes = vec![vote];
+            // Take the votes already queued without waiting for more.
+            let unverified_votes = &mut self.unverified_votes;
+            poll_fn(|cx| {
+                while votes.len() < MAX_VOTE_BATCH_SIZE {
+                    match unverified_votes.poll_next_unpin(cx) {
+                        Poll::Ready(Some(vote)) => votes.push(vote),
+                        _ => break,
+                    }
+                }
+                Poll::Ready(())
+            })
+            .await;
+            let validators = self.epoch_mgr.validators();
+            for (vote, result) in self.verifier.verify_votes(validators, votes).await {
+                match result {
+                    Ok(()) => {
+                        if let Err(e) = self.vote_tx.send(vote).await {
+                            error!("Failed to deliver a verified vote: {:?}", e);
+                            return;
+                        }
+                    }
+                    Err(e) => security_log(SecurityEvent::InvalidConsensusVote)
+                        .error(&e)
+                        .data(&vote)
+                        .log(),
+                }
+            }
+        }
+    }
 }
 
 struct NetworkTask<T, S> {
+    author: Author,
+    // Sends the proof of the end of their epoch to the peers lagging behind.
+    network_sender: ConsensusNetworkSender,
     proposal_tx: channel::Sender<ProposalMsg<T>>,
     vote_tx: channel::Sender<VoteMsg>,
+    // The votes of the peers, whose signatures are not verified yet
+    unverified_vote_tx: channel::Sender<VoteMsg>,
     block_request_tx: channel::Sender<BlockRetrievalRequest<T>>,
     timeout_msg_tx: channel::Sender<TimeoutMsg>,
     sync_info_tx: channel::Sender<(SyncInfo, AccountAddress)>,
+    sync_info_request_tx: channel::Sender<AccountAddress>,
     all_events: S,
     epoch_mgr: Arc<EpochManager>,
+    rate_limiter: InboundRateLimiter,
+    verifier: SignatureVerifier,
 }
 
 impl<T, S> NetworkTask<T, S>
@@ -327,8 +686,11 @@ where
         while let Some(Ok(message)) = self.all_events.next().await {
             match message {
                 Event::Message((peer_id, mut msg)) => {
+                    if !self.allow(peer_id, &msg) || !self.check_epoch(peer_id, &msg).await {
+                        continue;
+                    }
                     let r = if msg.has_proposal() {
-                        self.process_proposal(&mut msg).await.map_err(|e| {
+                        self.process_proposal(&mut msg, peer_id).await.map_err(|e| {
                             security_log(SecurityEvent::InvalidConsensusProposal)
                                 .error(&e)
                                 .data(&msg)
@@ -336,11 +698,13 @@ where
                             e
                         })
                     } else if msg.has_vote() {
-                        self.process_vote(&mut msg).await
+                        self.process_vote(&mut msg, peer_id).await
                     } else if msg.has_timeout_msg() {
-                        self.process_timeout_msg(&mut msg).await
+                        self.process_timeout_msg(&mut msg, peer_id).await
                     } else if msg.has_sync_info() {
                         self.process_sync_info(&mut msg, peer_id).await
+                    } else if msg.has_request_sync_info() {
+                        self.process_sync_info_request(peer_id).await
                     } else {
                         warn!("Unexpected msg from {}: {:?}", peer_id, msg);
                         continue;
@@ -350,6 +714,9 @@ where
                     }
                 }
                 Event::RpcRequest((peer_id, mut msg, callback)) => {
+                    if !self.allow(peer_id, &msg) {
+                        continue;
+                    }
                     let r = if msg.has_request_block() {
                         self.process_request_block(&mut msg, callback).await
                     } else {
@@ -370,20 +737,101 @@ where
         }
     }
 
-    async fn process_proposal<'a>(&'a mut self, msg: &'a mut ConsensusMsg) -> failure::Result<()> {
+    // Whether the message of the given peer is within the rate limits of the peer, the messages of
+    // the node itself are never limited.
+    fn allow(&mut self, peer: AccountAddress, msg: &ConsensusMsg) -> bool {
+        if peer == self.author {
+            return true;
+        }
+        let msg_type = match inbound_msg_type(msg) {
+            Some(msg_type) => msg_type,
+            // The unexpected messages are logged and dropped anyway.
+            None => return true,
+        };
+        if self.rate_limiter.allow(peer, msg_type, Instant::now()) {
+            return true;
+        }
+        counters::DROPPED_INBOUND_MSGS_COUNT.inc();
+        counters::OP_COUNTERS.inc(&format!("rate_limited_{}_msgs", msg_type.as_str()));
+        debug!(
+            "Peer {} exceeded the rate limit of the {} messages",
+            peer.short_str(),
+            msg_type.as_str()
+        );
+        false
+    }
+
+    // Whether the message was sent in the current epoch. A peer lagging behind is sent the proof
+    // of the end of its epoch as a sync info, with which it catches up. The messages of a later
+    // epoch are dropped: the peer ahead sends the proof in turn.
+    async fn check_epoch<'a>(&'a mut self, peer: AccountAddress, msg: &'a ConsensusMsg) -> bool {
+        let epoch = self.epoch_mgr.epoch();
+        if msg.get_epoch() == epoch {
+            return true;
+        }
+        counters::EPOCH_MISMATCH_MSGS_COUNT.inc();
+        debug!(
+            "Dropping a message of epoch {} from {} in epoch {}",
+            msg.get_epoch(),
+            peer.short_str(),
+            epoch
+        );
+        if peer == self.author || msg.get_epoch() > epoch {
+            return false;
+        }
+        if let Some(proof) = self.epoch_mgr.epoch_proof(msg.get_epoch()) {
+            let mut proof_msg = ConsensusMsg::new();
+            proof_msg.set_epoch(msg.get_epoch());
+            proof_msg.set_sync_info(SyncInfo::new(proof.clone(), proof, None).into_proto());
+            counters::EPOCH_PROOFS_SENT_COUNT.inc();
+            if let Err(e) = self.network_sender.send_to(peer, proof_msg).await {
+                warn!("Failed to send an epoch proof to peer {:?}: {:?}", peer, e);
+            }
+        }
+        false
+    }
+
+    async fn process_proposal<'a>(
+        &'a mut self,
+        msg: &'a mut ConsensusMsg,
+        peer: AccountAddress,
+    ) -> failure::Result<()> {
         let proposal = ProposalUncheckedSignatures::<T>::from_proto(msg.take_proposal())?;
-        let proposal = proposal
-            .validate_signatures(self.epoch_mgr.validators().as_ref())?
-            .verify_well_formed()?;
+        let epoch_mgr = Arc::clone(&self.epoch_mgr);
+        let proposal = self
+            .verifier
+            .run(move || {
+                proposal
+                    .validate_signatures(epoch_mgr.validators().as_ref(), epoch_mgr.qc_cache())?
+                    .verify_well_formed()
+            })
+            .await?;
         debug!("Received proposal {}", proposal);
-        self.proposal_tx.send(proposal).await?;
-        Ok(())
+        let from_self = peer == self.author;
+        enqueue(
+            &mut self.proposal_tx,
+            proposal,
+            InboundMessageType::Proposal,
+            from_self,
+        )
+        .await
     }
 
-    async fn process_vote<'a>(&'a mut self, msg: &'a mut ConsensusMsg) -> failure::Result<()> {
+    async fn process_vote<'a>(
+        &'a mut self,
+        msg: &'a mut ConsensusMsg,
+        peer: AccountAddress,
+    ) -> failure::Result<()> {
         let vote = VoteMsg::from_proto(msg.take_vote())?;
         debug!("Received {}", vote);
-        vote.verify(self.epoch_mgr.validators().as_ref())
+        let epoch = self.epoch_mgr.epoch();
+        ensure!(
+            vote.ledger_info().epoch_num() == epoch,
+            "Vote of epoch {} in epoch {}",
+            vote.ledger_info().epoch_num(),
+            epoch
+        );
+        vote.verify_without_signature(self.epoch_mgr.validators().as_ref())
             .map_err(|e| {
                 security_log(SecurityEvent::InvalidConsensusVote)
                     .error(&e)
@@ -391,26 +839,49 @@ where
                     .log();
                 e
             })?;
-        self.vote_tx.send(vote).await?;
-        Ok(())
+        if peer == self.author {
+            enqueue(&mut self.vote_tx, vote, InboundMessageType::Vote, true).await
+        } else {
+            // The signature is verified in batch with the other votes of the peers.
+            enqueue(
+                &mut self.unverified_vote_tx,
+                vote,
+                InboundMessageType::Vote,
+                false,
+            )
+            .await
+        }
     }
 
     async fn process_timeout_msg<'a>(
         &'a mut self,
         msg: &'a mut ConsensusMsg,
+        peer: AccountAddress,
     ) -> failure::Result<()> {
         let timeout_msg = TimeoutMsg::from_proto(msg.take_timeout_msg())?;
-        timeout_msg
-            .verify(self.epoch_mgr.validators().as_ref())
-            .map_err(|e| {
-                security_log(SecurityEvent::InvalidConsensusRound)
-                    .error(&e)
-                    .data(&timeout_msg)
-                    .log();
-                e
-            })?;
-        self.timeout_msg_tx.send(timeout_msg).await?;
-        Ok(())
+        let epoch_mgr = Arc::clone(&self.epoch_mgr);
+        let (timeout_msg, result) = self
+            .verifier
+            .run(move || {
+                let result = timeout_msg.verify(epoch_mgr.validators().as_ref());
+                (timeout_msg, result)
+            })
+            .await;
+        result.map_err(|e| {
+            security_log(SecurityEvent::InvalidConsensusRound)
+                .error(&e)
+                .data(&timeout_msg)
+                .log();
+            e
+        })?;
+        let from_self = peer == self.author;
+        enqueue(
+            &mut self.timeout_msg_tx,
+            timeout_msg,
+            InboundMessageType::TimeoutMsg,
+            from_self,
+        )
+        .await
     }
 
     async fn process_sync_info<'a>(
@@ -419,17 +890,40 @@ where
         peer: AccountAddress,
     ) -> failure::Result<()> {
         let sync_info = SyncInfo::from_proto(msg.take_sync_info())?;
-        sync_info
-            .verify(self.epoch_mgr.validators().as_ref())
-            .map_err(|e| {
-                security_log(SecurityEvent::InvalidSyncInfoMsg)
-                    .error(&e)
-                    .data(&sync_info)
-                    .log();
-                e
-            })?;
-        self.sync_info_tx.send((sync_info, peer)).await?;
-        Ok(())
+        let epoch_mgr = Arc::clone(&self.epoch_mgr);
+        let (sync_info, result) = self
+            .verifier
+            .run(move || {
+                let result =
+                    sync_info.verify(epoch_mgr.validators().as_ref(), epoch_mgr.qc_cache());
+                (sync_info, result)
+            })
+            .await;
+        result.map_err(|e| {
+            security_log(SecurityEvent::InvalidSyncInfoMsg)
+                .error(&e)
+                .data(&sync_info)
+                .log();
+            e
+        })?;
+        enqueue(
+            &mut self.sync_info_tx,
+            (sync_info, peer),
+            InboundMessageType::SyncInfo,
+            false,
+        )
+        .await
+    }
+
+    async fn process_sync_info_request(&mut self, peer: AccountAddress) -> failure::Result<()> {
+        debug!("Received a sync info request from {}", peer);
+        enqueue(
+            &mut self.sync_info_request_tx,
+            peer,
+            InboundMessageType::SyncInfoRequest,
+            false,
+        )
+        .await
     }
 
     async fn process_request_block<'a>(
@@ -449,7 +943,14 @@ where
             num_blocks,
             response_sender: tx,
         };
-        self.block_request_tx.send(request).await?;
+        // A dropped request fails the RPC, hence the error instead of `enqueue`.
+        self.block_request_tx.try_send(request).map_err(|e| {
+            if e.is_full() {
+                counters::DROPPED_INBOUND_MSGS_COUNT.inc();
+                counters::OP_COUNTERS.inc("queue_full_dropped_block_request_msgs");
+            }
+            e
+        })?;
         let BlockRetrievalResponse { status, blocks } = rx.await?;
         let mut response_msg = ConsensusMsg::new();
         let mut response = RespondBlock::new();
@@ -0,0 +1,176 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Fuzzing entry points for the consensus messages received from the network: every message is
+//! parsed and verified the way `ConsensusNetworkImpl` does before handing it over to the event
+//! processor. Any peer can send arbitrary bytes, hence the parsing and the verification must
+//! never panic, whatever the input.
+
+use crate::chained_bft::{
+    consensus_types::{
+        proposal_msg::ProposalUncheckedSignatures,
+        quorum_cert::QuorumCert,
+        sync_info::SyncInfo,
+        timeout_msg::{PacemakerTimeout, TimeoutMsg},
+        vote_data::VoteData,
+        vote_msg::VoteMsg,
+    },
+    epoch_manager::EpochManager,
+    event_processor::event_processor_fuzzing::{generate_corpus_proposal, FUZZING_SIGNER},
+    signature_verifier::verify_vote_batch,
+    test_utils::{placeholder_ledger_info, TestPayload},
+};
+use crypto::HashValue;
+use failure::prelude::*;
+use proto_conv::{FromProtoBytes, IntoProtoBytes};
+use types::crypto_proxies::ValidatorVerifier;
+
+// The messages are verified against a validator set made of the fuzzing signer only, such that
+// the corpus messages pass the verification.
+fn fuzzing_epoch_mgr() -> EpochManager {
+    let signer = &*FUZZING_SIGNER;
+    EpochManager::new(
+        0,
+        ValidatorVerifier::new_single(signer.author(), signer.public_key()),
+    )
+}
+
+fn corpus_sync_info() -> SyncInfo {
+    SyncInfo::new(
+        QuorumCert::certificate_for_genesis(),
+        QuorumCert::certificate_for_genesis(),
+        None,
+    )
+}
+
+fn corpus_vote_msg() -> VoteMsg {
+    let genesis_qc = QuorumCert::certificate_for_genesis();
+    VoteMsg::new(
+        VoteData::new(
+            HashValue::zero(),
+            HashValue::zero(),
+            1,
+            genesis_qc.certified_block_id(),
+            genesis_qc.certified_block_round(),
+            genesis_qc.parent_block_id(),
+            genesis_qc.parent_block_round(),
+        ),
+        FUZZING_SIGNER.author(),
+        placeholder_ledger_info(),
+        &FUZZING_SIGNER,
+    )
+}
+
+/// A proposal for round 1 carrying a single transaction, as a `Proposal` protobuf.
+pub fn generate_corpus_proposal_msg() -> Vec<u8> {
+    generate_corpus_proposal()
+}
+
+/// A vote for a block of round 1, as a `Vote` protobuf.
+pub fn generate_corpus_vote_msg() -> Vec<u8> {
+    corpus_vote_msg()
+        .into_proto_bytes()
+        .expect("failed to convert to bytes")
+}
+
+/// A timeout of round 1 carrying a vote, as a `TimeoutMsg` protobuf.
+pub fn generate_corpus_timeout_msg() -> Vec<u8> {
+    TimeoutMsg::new(
+        corpus_sync_info(),
+        PacemakerTimeout::new(1, &FUZZING_SIGNER, Some(corpus_vote_msg())),
+        &FUZZING_SIGNER,
+    )
+    .into_proto_bytes()
+    .expect("failed to convert to bytes")
+}
+
+/// The sync info of a node that only knows genesis, as a `SyncInfo` protobuf.
+pub fn generate_corpus_sync_info() -> Vec<u8> {
+    corpus_sync_info()
+        .with_last_vote(corpus_vote_msg())
+        .into_proto_bytes()
+        .expect("failed to convert to bytes")
+}
+
+fn verify_proposal_msg(data: &[u8]) -> Result<()> {
+    let epoch_mgr = fuzzing_epoch_mgr();
+    ProposalUncheckedSignatures::<TestPayload>::from_proto_bytes(data)?
+        .validate_signatures(epoch_mgr.validators().as_ref(), epoch_mgr.qc_cache())?
+        .verify_well_formed()?;
+    Ok(())
+}
+
+fn verify_vote_msg(data: &[u8]) -> Result<()> {
+    let epoch_mgr = fuzzing_epoch_mgr();
+    let vote = VoteMsg::from_proto_bytes(data)?;
+    vote.verify_without_signature(epoch_mgr.validators().as_ref())?;
+    // The signature is verified in batch with the other votes by the signature verifier.
+    for (_, result) in verify_vote_batch(epoch_mgr.validators().as_ref(), vec![vote]) {
+        result?;
+    }
+    Ok(())
+}
+
+fn verify_timeout_msg(data: &[u8]) -> Result<()> {
+    let epoch_mgr = fuzzing_epoch_mgr();
+    TimeoutMsg::from_proto_bytes(data)?.verify(epoch_mgr.validators().as_ref())?;
+    Ok(())
+}
+
+fn verify_sync_info(data: &[u8]) -> Result<()> {
+    let epoch_mgr = fuzzing_epoch_mgr();
+    SyncInfo::from_proto_bytes(data)?
+        .verify(epoch_mgr.validators().as_ref(), epoch_mgr.qc_cache())?;
+    Ok(())
+}
+
+// Errors are OK -- the fuzzer cares about panics and OOMs.
+
+/// Parses a `Proposal` protobuf and verifies its signatures and that it is well formed.
+pub fn fuzz_proposal_msg(data: &[u8]) {
+    let _ = verify_proposal_msg(data);
+}
+
+/// Parses a `Vote` protobuf and verifies it, including its signature.
+pub fn fuzz_vote_msg(data: &[u8]) {
+    let _ = verify_vote_msg(data);
+}
+
+/// Parses a `TimeoutMsg` protobuf and verifies its signature.
+pub fn fuzz_timeout_msg(data: &[u8]) {
+    let _ = verify_timeout_msg(data);
+}
+
+/// Parses a `SyncInfo` protobuf and verifies its certificates.
+pub fn fuzz_sync_info(data: &[u8]) {
+    let _ = verify_sync_info(data);
+}
+
+// These tests are here so that the fuzzers can be maintained: the corpus messages must pass the
+// verification, otherwise the fuzzers would only explore the parsing errors.
+#[test]
+fn test_corpus_messages_verified() {
+    verify_proposal_msg(&generate_corpus_proposal_msg()).unwrap();
+    verify_vote_msg(&generate_corpus_vote_msg()).unwrap();
+    verify_timeout_msg(&generate_corpus_timeout_msg()).unwrap();
+    verify_sync_info(&generate_corpus_sync_info()).unwrap();
+}
+
+#[test]
+fn test_truncated_messages() {
+    let corpus = vec![
+        (
+            generate_corpus_proposal_msg(),
+            verify_proposal_msg as fn(&[u8]) -> Result<()>,
+        ),
+        (generate_corpus_vote_msg(), verify_vote_msg),
+        (generate_corpus_timeout_msg(), verify_timeout_msg),
+        (generate_corpus_sync_info(), verify_sync_info),
+    ];
+    for (data, verify) in corpus {
+        for len in 0..data.len() {
+            // Some prefixes might still be valid messages, none of them may panic.
+            let _ = verify(&data[..len]);
+        }
+    }
+}
@@ -0,0 +1,139 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::chained_bft::common::Author;
+use std::{collections::HashMap, time::Instant};
+
+#[cfg(test)]
+#[path = "network_rate_limit_test.rs"]
+mod network_rate_limit_test;
+
+/// The types of the messages received from the peers, each of them is rate limited and queued
+/// separately such that a flood of one type doesn't delay the others.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum InboundMessageType {
+    Proposal,
+    Vote,
+    TimeoutMsg,
+    SyncInfo,
+    SyncInfoRequest,
+    BlockRequest,
+}
+
+impl InboundMessageType {
+    pub const ALL: [InboundMessageType; 6] = [
+        InboundMessageType::Proposal,
+        InboundMessageType::Vote,
+        InboundMessageType::TimeoutMsg,
+        InboundMessageType::SyncInfo,
+        InboundMessageType::SyncInfoRequest,
+        InboundMessageType::BlockRequest,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            InboundMessageType::Proposal => "proposal",
+            InboundMessageType::Vote => "vote",
+            InboundMessageType::TimeoutMsg => "timeout_msg",
+            InboundMessageType::SyncInfo => "sync_info",
+            InboundMessageType::SyncInfoRequest => "sync_info_request",
+            InboundMessageType::BlockRequest => "block_request",
+        }
+    }
+
+    /// Parses the name returned by `as_str`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .find(|msg_type| msg_type.as_str() == name)
+            .cloned()
+    }
+}
+
+/// Limits of the messages received from the peers before they reach the event processor.
+#[derive(Clone, Debug)]
+pub struct InboundRateLimitPolicy {
+    /// Number of messages of every type a peer may send per second in the long run, 0 disables
+    /// the rate limiting
+    pub msgs_per_sec: u32,
+    /// Number of messages of every type a peer may send at once after being idle
+    pub burst: u32,
+    /// Number of messages of every type waiting for the event processor, the messages received
+    /// while the queue of their type is full are dropped
+    pub queue_size: usize,
+    /// Queue sizes of the message types that don't use `queue_size`
+    pub queue_size_overrides: HashMap<InboundMessageType, usize>,
+}
+
+impl Default for InboundRateLimitPolicy {
+    fn default() -> Self {
+        Self {
+            msgs_per_sec: 100,
+            burst: 200,
+            queue_size: 1_024,
+            queue_size_overrides: HashMap::new(),
+        }
+    }
+}
+
+impl InboundRateLimitPolicy {
+    /// The capacity of the queue of the given message type.
+    pub fn queue_size(&self, msg_type: InboundMessageType) -> usize {
+        self.queue_size_overrides
+            .get(&msg_type)
+            .cloned()
+            .unwrap_or(self.queue_size)
+    }
+}
+
+struct TokenBucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+/// Token buckets of every peer and message type: a bucket holds up to `burst` tokens and is
+/// refilled at `msgs_per_sec` tokens per second, every message takes a token.
+pub struct InboundRateLimiter {
+    policy: InboundRateLimitPolicy,
+    buckets: HashMap<(Author, InboundMessageType), TokenBucket>,
+}
+
+impl InboundRateLimiter {
+    pub fn new(policy: InboundRateLimitPolicy) -> Self {
+        Self {
+            policy,
+            buckets: HashMap::new(),
+        }
+    }
+
+    pub fn policy(&self) -> &InboundRateLimitPolicy {
+        &self.policy
+    }
+
+    /// Takes a token from the bucket of the peer for the given message type at the given time,
+    /// returns false if the peer exceeded its rate and the message must be dropped.
+    pub fn allow(&mut self, peer: Author, msg_type: InboundMessageType, now: Instant) -> bool {
+        if self.policy.msgs_per_sec == 0 {
+            return true;
+        }
+        let capacity = f64::from(self.policy.burst.max(1));
+        let rate = f64::from(self.policy.msgs_per_sec);
+        let bucket = self
+            .buckets
+            .entry((peer, msg_type))
+            .or_insert_with(|| TokenBucket {
+                tokens: capacity,
+                last_refill: now,
+            });
+        if now > bucket.last_refill {
+            let elapsed = now.duration_since(bucket.last_refill);
+            bucket.tokens = (bucket.tokens + elapsed.as_secs_f64() * rate).min(capacity);
+            bucket.last_refill = now;
+        }
+        if bucket.tokens < 1.0 {
+            return false;
+        }
+        bucket.tokens -= 1.0;
+        true
+    }
+}
@@ -0,0 +1,97 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::chained_bft::network_rate_limit::{
+    InboundMessageType, InboundRateLimitPolicy, InboundRateLimiter,
+};
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+use types::account_address::AccountAddress;
+
+fn policy(msgs_per_sec: u32, burst: u32) -> InboundRateLimitPolicy {
+    InboundRateLimitPolicy {
+        msgs_per_sec,
+        burst,
+        ..InboundRateLimitPolicy::default()
+    }
+}
+
+#[test]
+fn test_burst_and_refill() {
+    let mut limiter = InboundRateLimiter::new(policy(10, 5));
+    let peer = AccountAddress::random();
+    let start = Instant::now();
+    for _ in 0..5 {
+        assert!(limiter.allow(peer, InboundMessageType::Vote, start));
+    }
+    assert!(!limiter.allow(peer, InboundMessageType::Vote, start));
+
+    // A token is refilled every 100ms.
+    assert!(!limiter.allow(
+        peer,
+        InboundMessageType::Vote,
+        start + Duration::from_millis(50)
+    ));
+    assert!(limiter.allow(
+        peer,
+        InboundMessageType::Vote,
+        start + Duration::from_millis(100)
+    ));
+    assert!(!limiter.allow(
+        peer,
+        InboundMessageType::Vote,
+        start + Duration::from_millis(100)
+    ));
+
+    // The bucket doesn't hold more than the burst, however long the peer is idle.
+    let later = start + Duration::from_secs(60);
+    for _ in 0..5 {
+        assert!(limiter.allow(peer, InboundMessageType::Vote, later));
+    }
+    assert!(!limiter.allow(peer, InboundMessageType::Vote, later));
+}
+
+#[test]
+fn test_buckets_per_peer_and_type() {
+    let mut limiter = InboundRateLimiter::new(policy(1, 1));
+    let peer = AccountAddress::random();
+    let other_peer = AccountAddress::random();
+    let now = Instant::now();
+    assert!(limiter.allow(peer, InboundMessageType::Vote, now));
+    assert!(!limiter.allow(peer, InboundMessageType::Vote, now));
+    // A flood of votes doesn't limit the other messages of the peer nor the votes of the others.
+    assert!(limiter.allow(peer, InboundMessageType::Proposal, now));
+    assert!(limiter.allow(other_peer, InboundMessageType::Vote, now));
+}
+
+#[test]
+fn test_rate_limiting_disabled() {
+    let mut limiter = InboundRateLimiter::new(policy(0, 1));
+    let peer = AccountAddress::random();
+    let now = Instant::now();
+    for _ in 0..1_000 {
+        assert!(limiter.allow(peer, InboundMessageType::Proposal, now));
+    }
+}
+
+#[test]
+fn test_queue_sizes() {
+    let mut queue_size_overrides = HashMap::new();
+    queue_size_overrides.insert(InboundMessageType::Vote, 4_096);
+    let policy = InboundRateLimitPolicy {
+        queue_size: 16,
+        queue_size_overrides,
+        ..InboundRateLimitPolicy::default()
+    };
+    assert_eq!(policy.queue_size(InboundMessageType::Vote), 4_096);
+    assert_eq!(policy.queue_size(InboundMessageType::Proposal), 16);
+    for msg_type in InboundMessageType::ALL.iter() {
+        assert_eq!(
+            InboundMessageType::from_name(msg_type.as_str()),
+            Some(*msg_type)
+        );
+    }
+    assert_eq!(InboundMessageType::from_name("unknown"), None);
+}
@@ -2,37 +2,70 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::chained_bft::{
-    common::Author,
+    common::{Author, Payload, Round},
     consensus_types::{
-        block::Block, proposal_msg::ProposalMsg, quorum_cert::QuorumCert, sync_info::SyncInfo,
-        vote_data::VoteData, vote_msg::VoteMsg,
+        block::Block,
+        proposal_msg::ProposalMsg,
+        quorum_cert::QuorumCert,
+        sync_info::SyncInfo,
+        timeout_msg::{PacemakerTimeout, TimeoutMsg},
+        vote_data::VoteData,
+        vote_msg::VoteMsg,
+    },
+    epoch_manager::{EpochChange, EpochManager},
+    network::{
+        BlockRetrievalResponse, ChunkedBlockRetrieval, ConsensusNetworkImpl, NetworkReceivers,
+    },
+    network_rate_limit::{InboundMessageType, InboundRateLimitPolicy},
+    test_utils::{
+        consensus_runtime, placeholder_certificate_for_block, placeholder_ledger_info, Simulation,
     },
-    epoch_manager::EpochManager,
-    network::{BlockRetrievalResponse, ConsensusNetworkImpl, NetworkReceivers},
-    test_utils::{consensus_runtime, placeholder_ledger_info},
 };
+use bytes::Bytes;
 use channel;
-use crypto::HashValue;
+use crypto::{ed25519::compat, HashValue};
 use executor::ExecutedState;
-use futures::{channel::mpsc, executor::block_on, FutureExt, SinkExt, StreamExt, TryFutureExt};
+use futures::{
+    channel::{mpsc, oneshot},
+    compat::Future01CompatExt,
+    executor::block_on,
+    future::{self, Either},
+    FutureExt, SinkExt, StreamExt, TryFutureExt,
+};
 use network::{
     interface::{NetworkNotification, NetworkRequest},
     proto::{BlockRetrievalStatus, ConsensusMsg},
-    protocols::rpc::InboundRpcRequest,
-    validator_network::{ConsensusNetworkEvents, ConsensusNetworkSender},
+    protocols::{direct_send::Message, rpc::InboundRpcRequest},
+    validator_network::{
+        ConsensusNetworkEvents, ConsensusNetworkSender, CONSENSUS_DIRECT_SEND_PROTOCOL,
+    },
+    ProtocolId,
 };
+use proto_conv::IntoProto;
+use protobuf::Message as ProtoMessage;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet},
+    env, fs,
+    path::Path,
     sync::{Arc, Mutex, RwLock},
-    time::Duration,
+    time::{Duration, Instant},
+};
+use tokio::{runtime::TaskExecutor, timer::Delay};
+use types::{
+    crypto_proxies::{ValidatorSigner, ValidatorVerifier},
+    ledger_info::LedgerInfo,
+    validator_set::ValidatorSet,
 };
-use tokio::runtime::TaskExecutor;
-use types::crypto_proxies::{ValidatorSigner, ValidatorVerifier};
 
 /// `NetworkPlayground` mocks the network implementation and provides convenience
-/// methods for testing. Test clients can use `wait_for_messages` or
-/// `deliver_messages` to inspect the direct-send messages sent between peers.
-/// They can also configure network messages to be dropped between specific peers.
+/// methods for testing. Test clients can use `wait_for_messages`,
+/// `wait_for_quiescence` or `deliver_messages` to inspect the direct-send
+/// messages sent between peers.
+/// They can also configure network messages to be dropped between specific peers, as well as
+/// the latency, duplication and reordering of the messages, or mutate and forge the messages in
+/// flight.
 ///
 /// Currently, RPC messages are delivered immediately and are not controlled by
 /// `wait_for_messages` or `deliver_messages` for delivery. They are also not
@@ -51,8 +84,77 @@ pub struct NetworkPlayground {
     drop_config: Arc<RwLock<DropConfig>>,
     /// An executor for spawning node outbound network event handlers
     executor: TaskExecutor,
+    /// Optional delay injected before the delivery of every direct-send message.
+    message_delay: Option<Duration>,
+    /// Timestamps of the first observed QC of every round.
+    round_timer: RoundTimer,
+    /// The deterministic simulation choosing the delivery order and firing the timers, if any.
+    simulation: Option<Simulation>,
+    /// The messages sent by the nodes, which the simulation hasn't chosen to deliver yet.
+    pending_msgs: Vec<(Author, NetworkRequest)>,
+    /// The latencies of the links, the messages of the other links are delivered right away.
+    link_latencies: HashMap<(Author, Author), LatencyDistribution>,
+    /// The probability to deliver a message twice.
+    duplication_probability: f64,
+    /// The probability to deliver a random message in flight instead of the earliest one.
+    reordering_probability: f64,
+    /// The messages in flight by their delivery time (and their order of sending for the ties).
+    in_flight_msgs: BTreeMap<(Instant, u64), (Author, NetworkRequest)>,
+    next_msg_id: u64,
+    rng: StdRng,
+    /// The trace of the delivered messages, if recording.
+    trace: Option<MessageTrace>,
+    /// The hooks invoked on every message in flight, in the order of registration.
+    mutators: Vec<MessageMutator>,
+}
+
+/// A hook invoked on every message in flight from a source to a destination before its delivery:
+/// it can inspect and mutate the message (e.g., flip a round number or corrupt a signature), and
+/// returns the forged messages to deliver to the same destination right after it (e.g., an old
+/// proposal replayed).
+pub type MessageMutator =
+    Box<dyn FnMut(Author, Author, &mut ConsensusMsg) -> Vec<ConsensusMsg> + Send>;
+
+/// The environment variable with a directory, in which every playground recording since its
+/// creation saves its trace (named after the test) when dropped, e.g., to investigate the flaky
+/// tests.
+pub const MESSAGE_TRACE_DIR_ENV: &str = "CONSENSUS_MESSAGE_TRACE_DIR";
+
+/// The environment variable with a corpus directory of `libra_fuzzer` (e.g.,
+/// `testsuite/libra_fuzzer/fuzz/corpus`), in which the tests recording real traffic save the
+/// messages as seeds of the consensus fuzz targets.
+pub const FUZZ_CORPUS_DIR_ENV: &str = "CONSENSUS_FUZZ_CORPUS_DIR";
+
+/// The latency of the messages of a link, sampled uniformly in [min, max].
+#[derive(Clone, Copy, Debug)]
+pub struct LatencyDistribution {
+    min: Duration,
+    max: Duration,
 }
 
+impl LatencyDistribution {
+    pub fn constant(latency: Duration) -> Self {
+        Self::uniform(latency, latency)
+    }
+
+    pub fn uniform(min: Duration, max: Duration) -> Self {
+        assert!(min <= max, "Invalid latency range [{:?}, {:?}]", min, max);
+        Self { min, max }
+    }
+
+    fn sample(&self, rng: &mut StdRng) -> Duration {
+        if self.min == self.max {
+            return self.min;
+        }
+        let nanos = rng.gen_range(self.min.as_nanos() as u64, self.max.as_nanos() as u64 + 1);
+        Duration::from_nanos(nanos)
+    }
+}
+
+/// In the simulation mode, the nodes are idle (i.e., all the messages caused by the previous
+/// event are sent) once none of them has sent a message for this duration.
+const SIMULATION_IDLE_DURATION: Duration = Duration::from_millis(20);
+
 impl NetworkPlayground {
     pub fn new(executor: TaskExecutor) -> Self {
         let (outbound_msgs_tx, outbound_msgs_rx) = mpsc::channel(1_024);
@@ -63,9 +165,35 @@ impl NetworkPlayground {
             outbound_msgs_rx,
             drop_config: Arc::new(RwLock::new(DropConfig(HashMap::new()))),
             executor,
+            message_delay: None,
+            round_timer: RoundTimer::default(),
+            simulation: None,
+            pending_msgs: vec![],
+            link_latencies: HashMap::new(),
+            duplication_probability: 0.0,
+            reordering_probability: 0.0,
+            in_flight_msgs: BTreeMap::new(),
+            next_msg_id: 0,
+            rng: StdRng::from_entropy(),
+            trace: env::var_os(MESSAGE_TRACE_DIR_ENV).map(|_| MessageTrace::default()),
+            mutators: vec![],
         }
     }
 
+    /// Creates a playground in the deterministic simulation mode: whenever the nodes are idle,
+    /// `wait_for_messages` delivers one of the pending messages chosen by the simulation, or fires
+    /// the next timers of the simulation if no message is pending. The nodes started on this
+    /// playground must use the simulation as their time service.
+    pub fn with_simulation(executor: TaskExecutor, simulation: Simulation) -> Self {
+        let mut playground = Self::new(executor);
+        playground.simulation = Some(simulation);
+        playground
+    }
+
+    pub fn simulation(&self) -> Option<&Simulation> {
+        self.simulation.as_ref()
+    }
+
     /// Create a new async task that handles outbound messages sent by a node.
     ///
     /// All non-rpc messages are forwarded to the NetworkPlayground's
@@ -197,13 +325,14 @@ impl NetworkPlayground {
         };
 
         node_consensus_tx.send(msg_notif).await.unwrap();
+        self.record(src, dst, &msg_copy.1);
         msg_copy
     }
 
     /// Wait for exactly `num_messages` to be enqueued and delivered. Return a
     /// copy of all messages for verification.
     /// While all the sent messages are delivered, only the messages that satisfy the given
-    /// msg inspector are counted.
+    /// msg inspector are counted (including the messages forged by the mutators).
     pub async fn wait_for_messages<F>(
         &mut self,
         num_messages: usize,
@@ -215,13 +344,11 @@ impl NetworkPlayground {
         let mut msg_copies = vec![];
         while msg_copies.len() < num_messages {
             // Take the next queued message
-            let (src, net_req) = self.outbound_msgs_rx.next().await
-                .expect("[network playground] waiting for messages, but message queue has shutdown unexpectedly");
+            let (src, net_req) = self.next_message().await;
 
             // Deliver and copy message it if it's not dropped
-            if !self.is_message_dropped(&src, &net_req) {
-                let msg_copy = self.deliver_message(src, net_req).await;
-                if msg_inspector(&msg_copy) {
+            for msg_copy in self.deliver_unless_dropped(src, net_req).await {
+                if msg_copies.len() < num_messages && msg_inspector(&msg_copy) {
                     msg_copies.push(msg_copy);
                 }
             }
@@ -230,6 +357,221 @@ impl NetworkPlayground {
         msg_copies
     }
 
+    /// Deliver all the enqueued messages until no message has been sent by any node for
+    /// `idle_duration` and no message is in flight, i.e., until the network has settled. Return a
+    /// copy of all the delivered messages.
+    /// Unlike `wait_for_messages`, the test doesn't need to know how many messages a phase of
+    /// the protocol produces, e.g., after a partition is healed.
+    pub async fn wait_for_quiescence(
+        &mut self,
+        idle_duration: Duration,
+    ) -> Vec<(Author, ConsensusMsg)> {
+        let mut msg_copies = vec![];
+        // Every sent message restarts the idle period.
+        while let Some((src, net_req)) = self.next_in_flight_message(Some(idle_duration)).await {
+            msg_copies.extend(self.deliver_unless_dropped(src, net_req).await);
+        }
+        msg_copies
+    }
+
+    /// The next message to deliver: the next message in flight, or the message chosen by the
+    /// simulation in the simulation mode (which ignores the latency, duplication and reordering
+    /// settings).
+    async fn next_message(&mut self) -> (Author, NetworkRequest) {
+        let simulation = match &self.simulation {
+            Some(simulation) => simulation.clone(),
+            None => {
+                return self
+                    .next_in_flight_message(None)
+                    .await
+                    .expect("[network playground] no message in flight")
+            }
+        };
+        loop {
+            while let Some(msg) = self.next_message_within(SIMULATION_IDLE_DURATION).await {
+                self.pending_msgs.push(msg);
+            }
+            if !self.pending_msgs.is_empty() {
+                // The messages are sent concurrently by the nodes: they are sorted such that the
+                // choice doesn't depend on the order of arrival.
+                self.pending_msgs
+                    .sort_by_cached_key(|(src, net_req)| match net_req {
+                        NetworkRequest::SendMessage(dst, msg) => (*src, *dst, msg.mdata.clone()),
+                        msg => panic!("[network playground] Unexpected NetworkRequest: {:?}", msg),
+                    });
+                let index = simulation.choose(self.pending_msgs.len());
+                return self.pending_msgs.remove(index);
+            }
+            // Nothing happens until a node sends a message if there is no timer to fire either.
+            if !simulation.fire_next_timers().await {
+                let msg = self.outbound_msgs_rx.next().await.expect(
+                    "[network playground] waiting for messages, but message queue has shutdown unexpectedly",
+                );
+                self.pending_msgs.push(msg);
+            }
+        }
+    }
+
+    /// The next message in flight to deliver once its latency has elapsed. Returns None if no
+    /// message is in flight and no message is sent for `idle_duration`, waits for a message to be
+    /// sent otherwise.
+    async fn next_in_flight_message(
+        &mut self,
+        idle_duration: Option<Duration>,
+    ) -> Option<(Author, NetworkRequest)> {
+        loop {
+            let next_delivery = self.in_flight_msgs.keys().next().map(|(time, _)| *time);
+            let wait = match next_delivery {
+                Some(time) => {
+                    let now = Instant::now();
+                    if time <= now {
+                        return Some(self.take_in_flight_message());
+                    }
+                    Some(time - now)
+                }
+                None => idle_duration,
+            };
+            let msg = match wait {
+                Some(wait) => self.next_message_within(wait).await,
+                None => Some(self.outbound_msgs_rx.next().await.expect(
+                    "[network playground] waiting for messages, but message queue has shutdown unexpectedly",
+                )),
+            };
+            match msg {
+                Some((src, net_req)) => self.send_in_flight(src, net_req),
+                // The next message in flight is due.
+                None if next_delivery.is_some() => (),
+                None => return None,
+            }
+        }
+    }
+
+    // Samples the latency of the message (and of its duplicate, if any).
+    fn send_in_flight(&mut self, src: Author, net_req: NetworkRequest) {
+        let (dst, msg) = match net_req {
+            NetworkRequest::SendMessage(dst, msg) => (dst, msg),
+            msg => panic!("[network playground] Unexpected NetworkRequest: {:?}", msg),
+        };
+        let copies = if self.rng.gen_bool(self.duplication_probability) {
+            2
+        } else {
+            1
+        };
+        for _ in 0..copies {
+            let latency = match self.link_latencies.get(&(src, dst)) {
+                Some(latency) => latency.sample(&mut self.rng),
+                None => Duration::from_secs(0),
+            };
+            self.in_flight_msgs.insert(
+                (Instant::now() + latency, self.next_msg_id),
+                (src, NetworkRequest::SendMessage(dst, msg.clone())),
+            );
+            self.next_msg_id += 1;
+        }
+    }
+
+    // The earliest message in flight, or a random one in case of reordering.
+    fn take_in_flight_message(&mut self) -> (Author, NetworkRequest) {
+        let index = if self.rng.gen_bool(self.reordering_probability) {
+            self.rng.gen_range(0, self.in_flight_msgs.len())
+        } else {
+            0
+        };
+        let key = *self
+            .in_flight_msgs
+            .keys()
+            .nth(index)
+            .expect("[network playground] no message in flight");
+        self.in_flight_msgs.remove(&key).unwrap()
+    }
+
+    /// The next queued message, None if no message is sent for `idle_duration`.
+    async fn next_message_within(
+        &mut self,
+        idle_duration: Duration,
+    ) -> Option<(Author, NetworkRequest)> {
+        let idle_timer = self.timer(idle_duration);
+        match future::select(self.outbound_msgs_rx.next(), idle_timer).await {
+            Either::Left((Some(msg), _)) => Some(msg),
+            Either::Left((None, _)) => panic!(
+                "[network playground] waiting for messages, but message queue has shutdown unexpectedly"
+            ),
+            Either::Right(_) => None,
+        }
+    }
+
+    /// Deliver the message unless it is dropped by the drop config, returns a copy of the
+    /// delivered message (as mutated by the mutators) followed by the forged messages.
+    async fn deliver_unless_dropped(
+        &mut self,
+        src: Author,
+        net_req: NetworkRequest,
+    ) -> Vec<(Author, ConsensusMsg)> {
+        if self.is_message_dropped(&src, &net_req) {
+            return vec![];
+        }
+        if let Some(delay) = self.message_delay {
+            // Only the delivery is delayed, the executor keeps running the nodes meanwhile.
+            let _ = self.timer(delay).await;
+        }
+        let mut msg_copies = vec![];
+        for net_req in self.mutate(src, net_req) {
+            let msg_copy = self.deliver_message(src, net_req).await;
+            self.round_timer.observe_message(&msg_copy.1);
+            msg_copies.push(msg_copy);
+        }
+        msg_copies
+    }
+
+    /// Registers a hook invoked on every message in flight that is not dropped.
+    pub fn add_mutator(&mut self, mutator: MessageMutator) {
+        self.mutators.push(mutator);
+    }
+
+    pub fn clear_mutators(&mut self) {
+        self.mutators.clear();
+    }
+
+    // Applies the mutators to the message, returns the message followed by the forged messages.
+    fn mutate(&mut self, src: Author, net_req: NetworkRequest) -> Vec<NetworkRequest> {
+        if self.mutators.is_empty() {
+            return vec![net_req];
+        }
+        let (dst, msg) = match net_req {
+            NetworkRequest::SendMessage(dst, msg) => (dst, msg),
+            msg => panic!("[network playground] Unexpected NetworkRequest: {:?}", msg),
+        };
+        let mut consensus_msg: ConsensusMsg =
+            ::protobuf::parse_from_bytes(msg.mdata.as_ref()).unwrap();
+        let mut forged_msgs = vec![];
+        for mutator in self.mutators.iter_mut() {
+            forged_msgs.extend(mutator(src, dst, &mut consensus_msg));
+        }
+        std::iter::once(consensus_msg)
+            .chain(forged_msgs)
+            .map(|consensus_msg| {
+                NetworkRequest::SendMessage(
+                    dst,
+                    Message {
+                        protocol: msg.protocol.clone(),
+                        mdata: Bytes::from(consensus_msg.write_to_bytes().unwrap()),
+                    },
+                )
+            })
+            .collect()
+    }
+
+    // The timer runs on the executor of the playground, which provides the tokio timer.
+    fn timer(&self, duration: Duration) -> oneshot::Receiver<()> {
+        let (tx, rx) = oneshot::channel();
+        let fut = async move {
+            let _ = Delay::new(Instant::now() + duration).compat().await;
+            let _ = tx.send(());
+        };
+        self.executor.spawn(fut.boxed().unit_error().compat());
+        rx
+    }
+
     /// Returns true for any message
     pub fn take_all(_msg_copy: &(Author, ConsensusMsg)) -> bool {
         true
@@ -260,6 +602,11 @@ impl NetworkPlayground {
         msg_copy.1.has_sync_info()
     }
 
+    /// Returns true for sync info requests only.
+    pub fn sync_info_requests_only(msg_copy: &(Author, ConsensusMsg)) -> bool {
+        msg_copy.1.has_request_sync_info()
+    }
+
     fn is_message_dropped(&self, src: &Author, net_req: &NetworkRequest) -> bool {
         self.drop_config
             .read()
@@ -277,6 +624,433 @@ impl NetworkPlayground {
             .unwrap()
             .stop_drop_message_for(src, dst)
     }
+
+    /// Delay the delivery of every direct-send message by the given duration (None to disable).
+    pub fn set_message_delay(&mut self, delay: Option<Duration>) {
+        self.message_delay = delay;
+    }
+
+    /// Delays the messages from `src` to `dst` by a latency sampled from the given distribution
+    /// (None to deliver them right away): the messages of a link with a random latency can
+    /// overtake each other.
+    pub fn set_link_latency(
+        &mut self,
+        src: Author,
+        dst: Author,
+        latency: Option<LatencyDistribution>,
+    ) {
+        match latency {
+            Some(latency) => self.link_latencies.insert((src, dst), latency),
+            None => self.link_latencies.remove(&(src, dst)),
+        };
+    }
+
+    /// Sets the latency of all the links between the nodes added so far.
+    pub fn set_latency(&mut self, latency: Option<LatencyDistribution>) {
+        let nodes: Vec<_> = self
+            .node_consensus_txs
+            .lock()
+            .unwrap()
+            .keys()
+            .cloned()
+            .collect();
+        for src in &nodes {
+            for dst in nodes.iter().filter(|dst| *dst != src) {
+                self.set_link_latency(*src, *dst, latency);
+            }
+        }
+    }
+
+    /// Delivers every message twice with the given probability.
+    pub fn set_duplication_probability(&mut self, probability: f64) {
+        assert!(probability >= 0.0 && probability <= 1.0);
+        self.duplication_probability = probability;
+    }
+
+    /// Delivers a random message in flight (regardless of its latency) instead of the earliest one
+    /// with the given probability.
+    pub fn set_reordering_probability(&mut self, probability: f64) {
+        assert!(probability >= 0.0 && probability <= 1.0);
+        self.reordering_probability = probability;
+    }
+
+    /// Timestamps of the QCs observed in the delivered messages so far.
+    pub fn round_timer(&self) -> &RoundTimer {
+        &self.round_timer
+    }
+
+    /// The executor of the playground, on which the test actors can spawn their tasks.
+    pub fn executor(&self) -> TaskExecutor {
+        self.executor.clone()
+    }
+
+    /// Deliver the given message to `dst` right away as if it was sent by `src`, ignoring the
+    /// drop config. `src` doesn't have to be a node of the playground, which allows injecting
+    /// handcrafted messages (see `MessageForger`) from arbitrary identities.
+    pub async fn inject_message(&mut self, src: Author, dst: Author, msg: ConsensusMsg) {
+        let mut node_consensus_tx = self
+            .node_consensus_txs
+            .lock()
+            .unwrap()
+            .get(&dst)
+            .expect("[network playground] Unknown destination of the injected message")
+            .clone();
+        let network_msg = Message {
+            protocol: ProtocolId::from_static(CONSENSUS_DIRECT_SEND_PROTOCOL),
+            mdata: Bytes::from(msg.write_to_bytes().unwrap()),
+        };
+        node_consensus_tx
+            .send(NetworkNotification::RecvMessage(src, network_msg))
+            .await
+            .unwrap();
+        self.record(src, dst, &msg);
+    }
+
+    /// Starts recording the delivered messages (discarding the messages recorded so far).
+    pub fn start_recording(&mut self) {
+        self.trace = Some(MessageTrace::default());
+    }
+
+    /// Stops recording, returns the trace of the messages delivered since the recording started.
+    pub fn stop_recording(&mut self) -> Option<MessageTrace> {
+        self.trace.take()
+    }
+
+    fn record(&mut self, src: Author, dst: Author, msg: &ConsensusMsg) {
+        if let Some(trace) = self.trace.as_mut() {
+            trace.push(src, dst, msg);
+        }
+    }
+
+    /// Delivers the messages of the trace in their order as if sent by their original senders,
+    /// typically to fresh nodes with the same identities as the recorded ones. The nodes only
+    /// receive the recorded messages: the messages they send during the replay are discarded
+    /// (note that their local timeouts are not replayed).
+    pub async fn replay(&mut self, trace: &MessageTrace) -> failure::Result<()> {
+        for traced_msg in trace.messages() {
+            self.discard_sent_messages();
+            let msg = protobuf::parse_from_bytes(&traced_msg.payload)?;
+            self.inject_message(traced_msg.src, traced_msg.dst, msg)
+                .await;
+        }
+        self.discard_sent_messages();
+        Ok(())
+    }
+
+    fn discard_sent_messages(&mut self) {
+        while let Ok(Some(_)) = self.outbound_msgs_rx.try_next() {}
+    }
+}
+
+impl Drop for NetworkPlayground {
+    fn drop(&mut self) {
+        if let (Some(dir), Some(trace)) = (env::var_os(MESSAGE_TRACE_DIR_ENV), &self.trace) {
+            // The test threads are named after the tests.
+            let name = std::thread::current()
+                .name()
+                .unwrap_or("playground")
+                .replace("::", "-");
+            let path = Path::new(&dir).join(format!("{}.json", name));
+            if let Err(e) = trace.save(&path) {
+                println!("Failed to save the message trace to {:?}: {:?}", path, e);
+            }
+        }
+    }
+}
+
+/// A message delivered by the playground.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct TracedMessage {
+    /// The position of the message in the order of delivery.
+    pub order: u64,
+    pub src: Author,
+    pub dst: Author,
+    /// The type of the message, for the readers of the trace files.
+    pub msg_type: String,
+    /// The serialized `ConsensusMsg`.
+    pub payload: Vec<u8>,
+}
+
+/// The messages delivered by a playground, in the order of delivery.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct MessageTrace {
+    messages: Vec<TracedMessage>,
+}
+
+impl MessageTrace {
+    pub fn messages(&self) -> &[TracedMessage] {
+        &self.messages
+    }
+
+    pub fn load(path: &Path) -> failure::Result<Self> {
+        Ok(serde_json::from_slice(&fs::read(path)?)?)
+    }
+
+    pub fn save(&self, path: &Path) -> failure::Result<()> {
+        fs::write(path, serde_json::to_vec_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// The proposals, votes, timeout messages and sync infos of the trace as the serialized
+    /// protobufs wrapped in their `ConsensusMsg`, along with their message type: these are the
+    /// inputs of the consensus fuzz targets.
+    pub fn fuzz_corpus(&self) -> failure::Result<Vec<(&str, Vec<u8>)>> {
+        let mut corpus = vec![];
+        for traced_msg in &self.messages {
+            let mut msg: ConsensusMsg = protobuf::parse_from_bytes(&traced_msg.payload)?;
+            let inner = match traced_msg.msg_type.as_str() {
+                "proposal" => msg.take_proposal().write_to_bytes()?,
+                "vote" => msg.take_vote().write_to_bytes()?,
+                "timeout_msg" => msg.take_timeout_msg().write_to_bytes()?,
+                "sync_info" => msg.take_sync_info().write_to_bytes()?,
+                _ => continue,
+            };
+            corpus.push((traced_msg.msg_type.as_str(), inner));
+        }
+        Ok(corpus)
+    }
+
+    /// Saves the fuzz corpus of the trace in `<dir>/<fuzz target>/<prefix>-<n>`.
+    pub fn save_fuzz_corpus(&self, dir: &Path, prefix: &str) -> failure::Result<()> {
+        for (n, (msg_type, data)) in self.fuzz_corpus()?.into_iter().enumerate() {
+            let target = match msg_type {
+                "proposal" => "consensus_proposal_msg",
+                "vote" => "consensus_vote_msg",
+                "timeout_msg" => "consensus_timeout_msg",
+                _ => "consensus_sync_info",
+            };
+            let target_dir = dir.join(target);
+            fs::create_dir_all(&target_dir)?;
+            fs::write(target_dir.join(format!("{}-{}", prefix, n)), data)?;
+        }
+        Ok(())
+    }
+
+    fn push(&mut self, src: Author, dst: Author, msg: &ConsensusMsg) {
+        let msg_type = if msg.has_proposal() {
+            "proposal"
+        } else if msg.has_vote() {
+            "vote"
+        } else if msg.has_timeout_msg() {
+            "timeout_msg"
+        } else if msg.has_sync_info() {
+            "sync_info"
+        } else if msg.has_request_sync_info() {
+            "request_sync_info"
+        } else if msg.has_request_block() {
+            "request_block"
+        } else if msg.has_respond_block() {
+            "respond_block"
+        } else {
+            "unknown"
+        };
+        self.messages.push(TracedMessage {
+            order: self.messages.len() as u64,
+            src,
+            dst,
+            msg_type: msg_type.to_string(),
+            payload: msg.write_to_bytes().unwrap(),
+        });
+    }
+}
+
+/// Builds consensus messages signed by an arbitrary identity, which doesn't have to be part of
+/// the validator set, in order to inject them through `NetworkPlayground::inject_message`.
+pub struct MessageForger {
+    signer: ValidatorSigner,
+    epoch: u64,
+}
+
+impl MessageForger {
+    pub fn new(signer: ValidatorSigner) -> Self {
+        Self { signer, epoch: 0 }
+    }
+
+    /// Tags the messages with the given epoch rather than the epoch 0.
+    pub fn in_epoch(mut self, epoch: u64) -> Self {
+        self.epoch = epoch;
+        self
+    }
+
+    /// Claims the identity of the given author, but signs with a key that is not the one of the
+    /// author (e.g., the key the author had in a previous epoch).
+    pub fn impersonating(author: Author, seed: [u8; 32]) -> Self {
+        let (private_key, _) = compat::generate_keypair(&mut StdRng::from_seed(seed));
+        Self::new(ValidatorSigner::new(author, private_key))
+    }
+
+    pub fn author(&self) -> Author {
+        self.signer.author()
+    }
+
+    /// A proposal of a block extending the given parent, certified by the given QC.
+    pub fn proposal<T: Payload>(
+        &self,
+        parent: &Block<T>,
+        payload: T,
+        round: Round,
+        quorum_cert: QuorumCert,
+    ) -> ConsensusMsg {
+        let block = Block::make_block(
+            parent,
+            payload,
+            round,
+            round,
+            quorum_cert.clone(),
+            &self.signer,
+        );
+        let proposal =
+            ProposalMsg::new(block, SyncInfo::new(quorum_cert.clone(), quorum_cert, None));
+        let mut msg = ConsensusMsg::new();
+        msg.set_epoch(self.epoch);
+        msg.set_proposal(proposal.into_proto());
+        msg
+    }
+
+    /// A vote of the given epoch.
+    pub fn vote(&self, vote_data: VoteData, epoch: u64) -> ConsensusMsg {
+        let vote = VoteMsg::new(
+            vote_data,
+            self.author(),
+            LedgerInfo::new(
+                0,
+                HashValue::zero(),
+                HashValue::zero(),
+                HashValue::zero(),
+                epoch,
+                0,
+                None,
+            ),
+            &self.signer,
+        );
+        let mut msg = ConsensusMsg::new();
+        msg.set_epoch(self.epoch);
+        msg.set_vote(vote.into_proto());
+        msg
+    }
+
+    pub fn timeout_msg(&self, round: Round, sync_info: SyncInfo) -> ConsensusMsg {
+        let timeout_msg = TimeoutMsg::new(
+            sync_info,
+            PacemakerTimeout::new(round, &self.signer, None),
+            &self.signer,
+        );
+        let mut msg = ConsensusMsg::new();
+        msg.set_epoch(self.epoch);
+        msg.set_timeout_msg(timeout_msg.into_proto());
+        msg
+    }
+}
+
+/// Records the moment the playground first delivers a message carrying a QC for a given round
+/// (the QC of a proposal or the highest QC of a SyncInfo). The time between two consecutive
+/// certified rounds is the duration of the later round: if some rounds timed out in between,
+/// their duration is accounted to the next certified round.
+#[derive(Default)]
+pub struct RoundTimer {
+    qc_times: BTreeMap<Round, Instant>,
+}
+
+impl RoundTimer {
+    /// Record the time a QC for the given round was observed, keeping the first observation.
+    pub fn record_qc(&mut self, round: Round, time: Instant) {
+        self.qc_times.entry(round).or_insert(time);
+    }
+
+    fn observe_message(&mut self, msg: &ConsensusMsg) {
+        let sync_info = if msg.has_proposal() {
+            let proposal = msg.get_proposal();
+            let block_qc_round = proposal
+                .get_proposed_block()
+                .get_quorum_cert()
+                .get_vote_data()
+                .get_round();
+            self.record_qc(block_qc_round, Instant::now());
+            proposal.get_sync_info()
+        } else if msg.has_timeout_msg() {
+            msg.get_timeout_msg().get_sync_info()
+        } else if msg.has_sync_info() {
+            msg.get_sync_info()
+        } else {
+            return;
+        };
+        let hqc_round = sync_info
+            .get_highest_quorum_cert()
+            .get_vote_data()
+            .get_round();
+        self.record_qc(hqc_round, Instant::now());
+    }
+
+    /// The time the QC of the given round was first observed.
+    pub fn qc_time(&self, round: Round) -> Option<Instant> {
+        self.qc_times.get(&round).cloned()
+    }
+
+    /// Durations of the certified rounds (the genesis round 0 is only used as a starting point).
+    pub fn round_durations(&self) -> Vec<(Round, Duration)> {
+        self.qc_times
+            .iter()
+            .zip(self.qc_times.iter().skip(1))
+            .map(|((_, prev_time), (round, time))| {
+                let duration = if time > prev_time {
+                    *time - *prev_time
+                } else {
+                    Duration::from_millis(0)
+                };
+                (*round, duration)
+            })
+            .collect()
+    }
+
+    /// Statistics over all the round durations, None if less than two QCs were observed.
+    pub fn stats(&self) -> Option<RoundDurationStats> {
+        RoundDurationStats::new(
+            self.round_durations()
+                .into_iter()
+                .map(|(_, duration)| duration)
+                .collect(),
+        )
+    }
+}
+
+/// Summary statistics of a set of round durations.
+#[derive(Debug)]
+pub struct RoundDurationStats {
+    // Sorted in increasing order, never empty.
+    durations: Vec<Duration>,
+}
+
+impl RoundDurationStats {
+    pub fn new(mut durations: Vec<Duration>) -> Option<Self> {
+        if durations.is_empty() {
+            return None;
+        }
+        durations.sort();
+        Some(Self { durations })
+    }
+
+    pub fn count(&self) -> usize {
+        self.durations.len()
+    }
+
+    pub fn min(&self) -> Duration {
+        self.durations[0]
+    }
+
+    pub fn max(&self) -> Duration {
+        self.durations[self.durations.len() - 1]
+    }
+
+    pub fn mean(&self) -> Duration {
+        self.durations.iter().sum::<Duration>() / self.durations.len() as u32
+    }
+
+    /// Nearest-rank percentile, `p` is expected to be in (0, 100].
+    pub fn percentile(&self, p: f64) -> Duration {
+        assert!(p > 0.0 && p <= 100.0, "Percentile {} is out of range", p);
+        let rank = (p / 100.0 * self.durations.len() as f64).ceil() as usize;
+        self.durations[rank.max(1) - 1]
+    }
 }
 
 struct DropConfig(HashMap<Author, HashSet<Author>>);
@@ -380,6 +1154,181 @@ fn test_network_api() {
     });
 }
 
+#[test]
+fn test_wait_for_quiescence() {
+    let runtime = consensus_runtime();
+    let num_nodes = 3;
+    let mut playground = NetworkPlayground::new(runtime.executor());
+    let signers: Vec<_> = (0..num_nodes)
+        .map(|i| ValidatorSigner::random([i as u8; 32]))
+        .collect();
+    let peers: Vec<_> = signers.iter().map(|signer| signer.author()).collect();
+    let validator = ValidatorVerifier::new(
+        signers
+            .iter()
+            .map(|signer| (signer.author(), signer.public_key()))
+            .collect(),
+    );
+    let epoch_mgr = Arc::new(EpochManager::new(0, validator));
+    let mut nodes = Vec::new();
+    let mut receivers: Vec<NetworkReceivers<u64>> = Vec::new();
+    for peer in &peers {
+        let (network_reqs_tx, network_reqs_rx) = channel::new_test(8);
+        let (consensus_tx, consensus_rx) = channel::new_test(8);
+        playground.add_node(*peer, consensus_tx, network_reqs_rx);
+        let mut node = ConsensusNetworkImpl::new(
+            *peer,
+            ConsensusNetworkSender::new(network_reqs_tx),
+            ConsensusNetworkEvents::new(consensus_rx),
+            Arc::clone(&epoch_mgr),
+        );
+        receivers.push(node.start(&runtime.executor()));
+        nodes.push(node);
+    }
+    let vote = VoteMsg::new(
+        VoteData::new(
+            HashValue::random(),
+            ExecutedState::state_for_genesis().state_id,
+            1,
+            HashValue::random(),
+            0,
+            HashValue::random(),
+            0,
+        ),
+        peers[0],
+        placeholder_ledger_info(),
+        &signers[0],
+    );
+    block_on(async move {
+        playground.drop_message_for(&peers[0], peers[2]);
+        nodes[0].send_vote(vote.clone(), peers[1..].to_vec()).await;
+        // The dropped message is not part of the delivered messages.
+        let msgs = playground
+            .wait_for_quiescence(Duration::from_millis(200))
+            .await;
+        assert_eq!(msgs.len(), 1);
+        assert_eq!(msgs[0].0, peers[0]);
+        assert_eq!(receivers[1].votes.next().await.unwrap(), vote);
+
+        // Nothing is sent anymore.
+        assert!(playground
+            .wait_for_quiescence(Duration::from_millis(50))
+            .await
+            .is_empty());
+    });
+}
+
+#[test]
+fn test_latency_and_duplication() {
+    let runtime = consensus_runtime();
+    let num_nodes = 3;
+    let mut playground = NetworkPlayground::new(runtime.executor());
+    let signers: Vec<_> = (0..num_nodes)
+        .map(|i| ValidatorSigner::random([i as u8; 32]))
+        .collect();
+    let peers: Vec<_> = signers.iter().map(|signer| signer.author()).collect();
+    let validator = ValidatorVerifier::new(
+        signers
+            .iter()
+            .map(|signer| (signer.author(), signer.public_key()))
+            .collect(),
+    );
+    let epoch_mgr = Arc::new(EpochManager::new(0, validator));
+    let mut nodes = Vec::new();
+    let mut receivers: Vec<NetworkReceivers<u64>> = Vec::new();
+    for peer in &peers {
+        let (network_reqs_tx, network_reqs_rx) = channel::new_test(8);
+        let (consensus_tx, consensus_rx) = channel::new_test(8);
+        playground.add_node(*peer, consensus_tx, network_reqs_rx);
+        let mut node = ConsensusNetworkImpl::new(
+            *peer,
+            ConsensusNetworkSender::new(network_reqs_tx),
+            ConsensusNetworkEvents::new(consensus_rx),
+            Arc::clone(&epoch_mgr),
+        );
+        receivers.push(node.start(&runtime.executor()));
+        nodes.push(node);
+    }
+    let votes: Vec<_> = signers
+        .iter()
+        .take(2)
+        .map(|signer| {
+            VoteMsg::new(
+                VoteData::new(
+                    HashValue::random(),
+                    ExecutedState::state_for_genesis().state_id,
+                    1,
+                    HashValue::random(),
+                    0,
+                    HashValue::random(),
+                    0,
+                ),
+                signer.author(),
+                placeholder_ledger_info(),
+                signer,
+            )
+        })
+        .collect();
+    block_on(async move {
+        // The vote of node 1 overtakes the vote sent earlier by node 0 on a slower link.
+        playground.set_link_latency(
+            peers[0],
+            peers[2],
+            Some(LatencyDistribution::constant(Duration::from_millis(200))),
+        );
+        nodes[0].send_vote(votes[0].clone(), vec![peers[2]]).await;
+        nodes[1].send_vote(votes[1].clone(), vec![peers[2]]).await;
+        let msgs = playground
+            .wait_for_messages(2, NetworkPlayground::take_all)
+            .await;
+        assert_eq!(msgs[0].0, peers[1]);
+        assert_eq!(msgs[1].0, peers[0]);
+        assert_eq!(receivers[2].votes.next().await.unwrap(), votes[1]);
+        assert_eq!(receivers[2].votes.next().await.unwrap(), votes[0]);
+
+        // Every message is delivered twice.
+        playground.set_link_latency(peers[0], peers[2], None);
+        playground.set_duplication_probability(1.0);
+        nodes[0].send_vote(votes[0].clone(), vec![peers[2]]).await;
+        let msgs = playground
+            .wait_for_quiescence(Duration::from_millis(50))
+            .await;
+        assert_eq!(msgs.len(), 2);
+        assert_eq!(msgs[0], msgs[1]);
+    });
+}
+
+#[test]
+fn test_round_timer_stats() {
+    let mut round_timer = RoundTimer::default();
+    assert!(round_timer.stats().is_none());
+    let start = Instant::now();
+    for (round, offset_ms) in &[(0, 0), (1, 10), (2, 30), (4, 100), (5, 110)] {
+        round_timer.record_qc(*round, start + Duration::from_millis(*offset_ms));
+    }
+    // Only the first observation of a QC is taken into account.
+    round_timer.record_qc(2, start + Duration::from_millis(50));
+    assert_eq!(
+        round_timer.round_durations(),
+        vec![
+            (1, Duration::from_millis(10)),
+            (2, Duration::from_millis(20)),
+            (4, Duration::from_millis(70)),
+            (5, Duration::from_millis(10)),
+        ]
+    );
+    let stats = round_timer.stats().unwrap();
+    assert_eq!(stats.count(), 4);
+    assert_eq!(stats.min(), Duration::from_millis(10));
+    assert_eq!(stats.max(), Duration::from_millis(70));
+    assert_eq!(
+        stats.mean(),
+        Duration::from_millis(27) + Duration::from_micros(500)
+    );
+    assert_eq!(stats.percentile(50.0), Duration::from_millis(10));
+    assert_eq!(stats.percentile(99.0), Duration::from_millis(70));
+}
+
 #[test]
 fn test_rpc() {
     let runtime = consensus_runtime();
@@ -448,3 +1397,520 @@ fn test_rpc() {
         assert_eq!(response.blocks[0], *genesis);
     });
 }
+
+#[test]
+fn test_chunked_block_retrieval() {
+    let runtime = consensus_runtime();
+    let num_nodes = 2;
+    let mut playground = NetworkPlayground::new(runtime.executor());
+    let signers: Vec<_> = (0..num_nodes)
+        .map(|i| ValidatorSigner::random([i as u8; 32]))
+        .collect();
+    let author_to_public_keys = signers
+        .iter()
+        .map(|signer| (signer.author(), signer.public_key()))
+        .collect();
+    let epoch_mgr = Arc::new(EpochManager::new(
+        0,
+        ValidatorVerifier::new(author_to_public_keys),
+    ));
+    let mut nodes = Vec::new();
+    let mut receivers: Vec<NetworkReceivers<u64>> = Vec::new();
+    for signer in &signers {
+        let (network_reqs_tx, network_reqs_rx) = channel::new_test(8);
+        let (consensus_tx, consensus_rx) = channel::new_test(8);
+        let network_sender = ConsensusNetworkSender::new(network_reqs_tx);
+        let network_events = ConsensusNetworkEvents::new(consensus_rx);
+
+        playground.add_node(signer.author(), consensus_tx, network_reqs_rx);
+        let mut node = ConsensusNetworkImpl::new(
+            signer.author(),
+            network_sender,
+            network_events,
+            Arc::clone(&epoch_mgr),
+        );
+        receivers.push(node.start(&runtime.executor()));
+        nodes.push(node);
+    }
+
+    // genesis <- b1 <- b2 <- b3
+    let mut chain = vec![Block::<u64>::make_genesis_block()];
+    for round in 1..=3 {
+        let parent = chain.last().unwrap();
+        let qc = if round == 1 {
+            QuorumCert::certificate_for_genesis()
+        } else {
+            placeholder_certificate_for_block(
+                signers.iter().collect(),
+                parent.id(),
+                parent.round(),
+                parent.quorum_cert().certified_block_id(),
+                parent.quorum_cert().certified_block_round(),
+                parent.quorum_cert().parent_block_id(),
+                parent.quorum_cert().parent_block_round(),
+            )
+        };
+        let block = Block::make_block(parent, round, round, round, qc, &signers[0]);
+        chain.push(block);
+    }
+    let blocks: HashMap<HashValue, Block<u64>> = chain
+        .iter()
+        .map(|block| (block.id(), block.clone()))
+        .collect();
+
+    // The second chunk request fails, all the others are served from the chain.
+    let mut block_retrieval = receivers.remove(1).block_retrieval;
+    let on_request_block = async move {
+        let mut num_requests = 0;
+        while let Some(request) = block_retrieval.next().await {
+            num_requests += 1;
+            let mut response = BlockRetrievalResponse {
+                status: BlockRetrievalStatus::ID_NOT_FOUND,
+                blocks: vec![],
+            };
+            if num_requests != 2 {
+                let mut id = request.block_id;
+                while (response.blocks.len() as u64) < request.num_blocks {
+                    let block = blocks[&id].clone();
+                    id = block.parent_id();
+                    response.blocks.push(block);
+                }
+                response.status = BlockRetrievalStatus::SUCCEEDED;
+            }
+            request.response_sender.send(response).unwrap();
+        }
+    };
+    runtime
+        .executor()
+        .spawn(on_request_block.boxed().unit_error().compat());
+
+    let peer = signers[1].author();
+    let timeout = Duration::from_secs(5);
+    let mut retrieval = ChunkedBlockRetrieval::new(nodes.remove(0), chain[3].id(), 4, 2);
+    block_on(async move {
+        assert_eq!(
+            retrieval.next_block(peer, timeout).await.unwrap(),
+            Some(chain[3].clone())
+        );
+        assert_eq!(
+            retrieval.next_block(peer, timeout).await.unwrap(),
+            Some(chain[2].clone())
+        );
+        assert_eq!(retrieval.remaining(), 2);
+        // The failed chunk doesn't affect the position of the retrieval.
+        assert!(retrieval.next_block(peer, timeout).await.is_err());
+        assert_eq!(retrieval.next_block_id(), chain[1].id());
+        assert_eq!(retrieval.remaining(), 2);
+        // The retrieval is resumed from the first missing block.
+        assert_eq!(
+            retrieval.next_block(peer, timeout).await.unwrap(),
+            Some(chain[1].clone())
+        );
+        assert_eq!(
+            retrieval.next_block(peer, timeout).await.unwrap(),
+            Some(chain[0].clone())
+        );
+        assert_eq!(retrieval.next_block(peer, timeout).await.unwrap(), None);
+    });
+}
+
+#[test]
+fn test_block_retrieval_from_any_peer() {
+    let runtime = consensus_runtime();
+    let num_nodes = 3;
+    let mut playground = NetworkPlayground::new(runtime.executor());
+    let signers: Vec<_> = (0..num_nodes)
+        .map(|i| ValidatorSigner::random([i as u8; 32]))
+        .collect();
+    let author_to_public_keys = signers
+        .iter()
+        .map(|signer| (signer.author(), signer.public_key()))
+        .collect();
+    let epoch_mgr = Arc::new(EpochManager::new(
+        0,
+        ValidatorVerifier::new(author_to_public_keys),
+    ));
+    let mut nodes = Vec::new();
+    let mut receivers: Vec<NetworkReceivers<u64>> = Vec::new();
+    for signer in &signers {
+        let (network_reqs_tx, network_reqs_rx) = channel::new_test(8);
+        let (consensus_tx, consensus_rx) = channel::new_test(8);
+        let network_sender = ConsensusNetworkSender::new(network_reqs_tx);
+        let network_events = ConsensusNetworkEvents::new(consensus_rx);
+
+        playground.add_node(signer.author(), consensus_tx, network_reqs_rx);
+        let mut node = ConsensusNetworkImpl::new(
+            signer.author(),
+            network_sender,
+            network_events,
+            Arc::clone(&epoch_mgr),
+        );
+        receivers.push(node.start(&runtime.executor()));
+        nodes.push(node);
+    }
+
+    // genesis <- b1
+    let genesis = Block::<u64>::make_genesis_block();
+    let b1 = Block::make_block(
+        &genesis,
+        1,
+        1,
+        1,
+        QuorumCert::certificate_for_genesis(),
+        &signers[0],
+    );
+    let chain = vec![b1.clone(), genesis.clone()];
+
+    // The node 1 doesn't have the blocks, the node 2 serves them.
+    for (i, node_receivers) in receivers.into_iter().enumerate().skip(1) {
+        let has_blocks = i == 2;
+        let mut block_retrieval = node_receivers.block_retrieval;
+        let chain = chain.clone();
+        let on_request_block = async move {
+            while let Some(request) = block_retrieval.next().await {
+                let response = if has_blocks {
+                    BlockRetrievalResponse {
+                        status: BlockRetrievalStatus::SUCCEEDED,
+                        blocks: chain.clone(),
+                    }
+                } else {
+                    BlockRetrievalResponse {
+                        status: BlockRetrievalStatus::ID_NOT_FOUND,
+                        blocks: vec![],
+                    }
+                };
+                request.response_sender.send(response).unwrap();
+            }
+        };
+        runtime
+            .executor()
+            .spawn(on_request_block.boxed().unit_error().compat());
+    }
+
+    let peers = vec![signers[1].author(), signers[2].author()];
+    let timeout = Duration::from_secs(5);
+    let mut retrieval = ChunkedBlockRetrieval::new(nodes.remove(0), b1.id(), 2, 2);
+    block_on(async move {
+        assert!(retrieval
+            .next_block_from_any(&peers[..1], timeout)
+            .await
+            .is_err());
+        assert_eq!(retrieval.remaining(), 2);
+        // The failure of the node 1 doesn't prevent using the response of the node 2.
+        assert_eq!(
+            retrieval
+                .next_block_from_any(&peers, timeout)
+                .await
+                .unwrap(),
+            Some(b1)
+        );
+        assert_eq!(
+            retrieval
+                .next_block_from_any(&peers, timeout)
+                .await
+                .unwrap(),
+            Some(genesis)
+        );
+        assert_eq!(
+            retrieval
+                .next_block_from_any(&peers, timeout)
+                .await
+                .unwrap(),
+            None
+        );
+    });
+}
+
+#[test]
+fn test_reject_messages_from_unknown_signers() {
+    let runtime = consensus_runtime();
+    let mut playground = NetworkPlayground::new(runtime.executor());
+    // The validator 2 was part of the validator set of the previous epoch only.
+    let signers: Vec<_> = (0..3)
+        .map(|i| ValidatorSigner::random([i as u8; 32]))
+        .collect();
+    let author_to_public_keys = signers
+        .iter()
+        .take(2)
+        .map(|signer| (signer.author(), signer.public_key()))
+        .collect();
+    let epoch_mgr = Arc::new(EpochManager::new(
+        1,
+        ValidatorVerifier::new(author_to_public_keys),
+    ));
+    let receiver = signers[1].author();
+    let (network_reqs_tx, network_reqs_rx) = channel::new_test(8);
+    let (consensus_tx, consensus_rx) = channel::new_test(8);
+    playground.add_node(receiver, consensus_tx, network_reqs_rx);
+    let mut node = ConsensusNetworkImpl::new(
+        receiver,
+        ConsensusNetworkSender::new(network_reqs_tx),
+        ConsensusNetworkEvents::new(consensus_rx),
+        epoch_mgr,
+    );
+    let mut receivers: NetworkReceivers<u64> = node.start(&runtime.executor());
+
+    let non_validator = MessageForger::new(ValidatorSigner::random([10; 32])).in_epoch(1);
+    let previous_epoch_validator = MessageForger::new(signers[2].clone()).in_epoch(1);
+    let impersonator = MessageForger::impersonating(signers[0].author(), [11; 32]).in_epoch(1);
+    let validator = MessageForger::new(signers[0].clone()).in_epoch(1);
+
+    let genesis = Block::<u64>::make_genesis_block();
+    let genesis_qc = QuorumCert::certificate_for_genesis();
+    let vote_data = VoteData::new(
+        HashValue::random(),
+        ExecutedState::state_for_genesis().state_id,
+        1,
+        HashValue::random(),
+        0,
+        HashValue::random(),
+        0,
+    );
+    let sync_info = SyncInfo::new(genesis_qc.clone(), genesis_qc.clone(), None);
+    block_on(async move {
+        for forger in &[&non_validator, &previous_epoch_validator, &impersonator] {
+            let proposal = forger.proposal(&genesis, 1, 1, genesis_qc.clone());
+            playground
+                .inject_message(forger.author(), receiver, proposal)
+                .await;
+        }
+        for forger in &[&non_validator, &previous_epoch_validator] {
+            let vote = forger.vote(vote_data.clone(), 1);
+            playground
+                .inject_message(forger.author(), receiver, vote)
+                .await;
+            let timeout_msg = forger.timeout_msg(1, sync_info.clone());
+            playground
+                .inject_message(forger.author(), receiver, timeout_msg)
+                .await;
+        }
+
+        // The votes of a previous epoch are stale.
+        playground
+            .inject_message(
+                validator.author(),
+                receiver,
+                validator.vote(vote_data.clone(), 0),
+            )
+            .await;
+
+        // The messages are processed in order: the forged ones are not delivered to consensus,
+        // the following legitimate ones are.
+        playground
+            .inject_message(
+                validator.author(),
+                receiver,
+                validator.proposal(&genesis, 2, 1, genesis_qc.clone()),
+            )
+            .await;
+        playground
+            .inject_message(
+                validator.author(),
+                receiver,
+                validator.vote(vote_data.clone(), 1),
+            )
+            .await;
+        playground
+            .inject_message(
+                validator.author(),
+                receiver,
+                validator.timeout_msg(1, sync_info.clone()),
+            )
+            .await;
+        let proposal = receivers.proposals.next().await.unwrap();
+        assert_eq!(proposal.proposal().author(), Some(validator.author()));
+        assert_eq!(*proposal.proposal().get_payload(), 2);
+        let vote = receivers.votes.next().await.unwrap();
+        assert_eq!(vote.author(), validator.author());
+        let timeout_msg = receivers.timeout_msgs.next().await.unwrap();
+        assert_eq!(timeout_msg.author(), validator.author());
+    });
+}
+
+#[test]
+fn test_drop_messages_of_other_epochs() {
+    let runtime = consensus_runtime();
+    let mut playground = NetworkPlayground::new(runtime.executor());
+    let signers: Vec<_> = (0..2)
+        .map(|i| ValidatorSigner::random([i as u8; 32]))
+        .collect();
+    let validators = ValidatorVerifier::new(
+        signers
+            .iter()
+            .map(|signer| (signer.author(), signer.public_key()))
+            .collect(),
+    );
+    let genesis = Block::<u64>::make_genesis_block();
+    let genesis_qc = QuorumCert::certificate_for_genesis();
+    // The receiver went through the epoch 0, which the genesis QC stands as the proof of the
+    // end of, while the lagging peer is still in the epoch 0.
+    let receiver = signers[0].author();
+    let receiver_epoch_mgr = Arc::new(EpochManager::new(0, validators.clone()));
+    receiver_epoch_mgr.start_new_epoch(
+        EpochChange::new(1, ValidatorSet::new(vec![]), genesis_qc.clone()),
+        validators.clone(),
+        std::iter::empty(),
+    );
+    let lagging_peer = signers[1].author();
+    let mut receivers = vec![];
+    for (author, epoch_mgr) in &[
+        (receiver, receiver_epoch_mgr),
+        (lagging_peer, Arc::new(EpochManager::new(0, validators))),
+    ] {
+        let (network_reqs_tx, network_reqs_rx) = channel::new_test(8);
+        let (consensus_tx, consensus_rx) = channel::new_test(8);
+        playground.add_node(*author, consensus_tx, network_reqs_rx);
+        let mut node = ConsensusNetworkImpl::new(
+            *author,
+            ConsensusNetworkSender::new(network_reqs_tx),
+            ConsensusNetworkEvents::new(consensus_rx),
+            Arc::clone(epoch_mgr),
+        );
+        receivers.push(node.start::<u64>(&runtime.executor()));
+    }
+
+    block_on(async move {
+        for epoch in &[0, 2] {
+            let forger = MessageForger::new(signers[1].clone()).in_epoch(*epoch);
+            playground
+                .inject_message(
+                    lagging_peer,
+                    receiver,
+                    forger.proposal(&genesis, *epoch, 1, genesis_qc.clone()),
+                )
+                .await;
+        }
+        let forger = MessageForger::new(signers[1].clone()).in_epoch(1);
+        playground
+            .inject_message(
+                lagging_peer,
+                receiver,
+                forger.proposal(&genesis, 1, 1, genesis_qc.clone()),
+            )
+            .await;
+        let proposal = receivers[0].proposals.next().await.unwrap();
+        assert_eq!(*proposal.proposal().get_payload(), 1);
+
+        // The lagging peer receives the proof of the end of its epoch.
+        playground
+            .wait_for_messages(1, NetworkPlayground::take_all)
+            .await;
+        let (sync_info, author) = receivers[1].sync_info_msgs.next().await.unwrap();
+        assert_eq!(author, receiver);
+        assert_eq!(*sync_info.highest_ledger_info(), genesis_qc);
+    });
+}
+
+#[test]
+fn test_inbound_rate_limits() {
+    let runtime = consensus_runtime();
+    let mut playground = NetworkPlayground::new(runtime.executor());
+    let signers: Vec<_> = (0..3)
+        .map(|i| ValidatorSigner::random([i as u8; 32]))
+        .collect();
+    let author_to_public_keys = signers
+        .iter()
+        .map(|signer| (signer.author(), signer.public_key()))
+        .collect();
+    let epoch_mgr = Arc::new(EpochManager::new(
+        0,
+        ValidatorVerifier::new(author_to_public_keys),
+    ));
+    let start_node = |playground: &mut NetworkPlayground, policy| {
+        let author = signers[0].author();
+        let (network_reqs_tx, network_reqs_rx) = channel::new_test(8);
+        let (consensus_tx, consensus_rx) = channel::new_test(8);
+        playground.add_node(author, consensus_tx, network_reqs_rx);
+        let mut node = ConsensusNetworkImpl::new(
+            author,
+            ConsensusNetworkSender::new(network_reqs_tx),
+            ConsensusNetworkEvents::new(consensus_rx),
+            Arc::clone(&epoch_mgr),
+        )
+        .with_rate_limit_policy(policy);
+        let receivers: NetworkReceivers<u64> = node.start(&runtime.executor());
+        receivers
+    };
+    let receiver = signers[0].author();
+    let flooder = MessageForger::new(signers[1].clone());
+    let other = MessageForger::new(signers[2].clone());
+    let vote_data = VoteData::new(
+        HashValue::random(),
+        ExecutedState::state_for_genesis().state_id,
+        1,
+        HashValue::random(),
+        0,
+        HashValue::random(),
+        0,
+    );
+
+    // The flooder exceeds its burst, the votes of the other validator are not affected.
+    let mut receivers = start_node(
+        &mut playground,
+        InboundRateLimitPolicy {
+            msgs_per_sec: 1,
+            burst: 2,
+            ..InboundRateLimitPolicy::default()
+        },
+    );
+    block_on(async {
+        for _ in 0..5 {
+            playground
+                .inject_message(
+                    flooder.author(),
+                    receiver,
+                    flooder.vote(vote_data.clone(), 0),
+                )
+                .await;
+        }
+        playground
+            .inject_message(other.author(), receiver, other.vote(vote_data.clone(), 0))
+            .await;
+        // The messages are processed in order: the dropped votes would come before the last one.
+        for author in &[flooder.author(), flooder.author(), other.author()] {
+            assert_eq!(receivers.votes.next().await.unwrap().author(), *author);
+        }
+    });
+
+    // The votes received while the vote queue is full are dropped.
+    let mut queue_size_overrides = HashMap::new();
+    queue_size_overrides.insert(InboundMessageType::Vote, 1);
+    let mut receivers = start_node(
+        &mut playground,
+        InboundRateLimitPolicy {
+            msgs_per_sec: 0,
+            queue_size_overrides,
+            ..InboundRateLimitPolicy::default()
+        },
+    );
+    block_on(async {
+        for _ in 0..5 {
+            playground
+                .inject_message(
+                    flooder.author(),
+                    receiver,
+                    flooder.vote(vote_data.clone(), 0),
+                )
+                .await;
+        }
+        // The proposal queue is not full: once the proposal is received, all the votes are
+        // processed.
+        let genesis = Block::<u64>::make_genesis_block();
+        playground
+            .inject_message(
+                other.author(),
+                receiver,
+                other.proposal(&genesis, 1, 1, QuorumCert::certificate_for_genesis()),
+            )
+            .await;
+        receivers.proposals.next().await.unwrap();
+        playground
+            .inject_message(other.author(), receiver, other.vote(vote_data.clone(), 0))
+            .await;
+        let mut num_flooder_votes = 0;
+        while receivers.votes.next().await.unwrap().author() == flooder.author() {
+            num_flooder_votes += 1;
+        }
+        assert!(num_flooder_votes >= 1 && num_flooder_votes < 5);
+    });
+}
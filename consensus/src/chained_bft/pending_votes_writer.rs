@@ -0,0 +1,58 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::chained_bft::{
+    common::Round, consensus_types::vote_msg::VoteMsg,
+    persistent_storage::PersistentLivenessStorage,
+};
+use logger::prelude::*;
+use std::{
+    sync::{mpsc, Mutex},
+    thread,
+};
+
+/// Persists the pending votes on a dedicated thread, such that the event loop doesn't wait for
+/// the consensus DB. Every write overrides the previous one: when the writes fall behind, only
+/// the latest pending votes are written. The thread stops once the writer is dropped.
+pub struct PendingVotesWriter {
+    sender: Mutex<mpsc::Sender<(Round, Vec<VoteMsg>)>>,
+}
+
+impl PendingVotesWriter {
+    pub fn new(storage: Box<dyn PersistentLivenessStorage>) -> Self {
+        let (sender, receiver) = mpsc::channel::<(Round, Vec<VoteMsg>)>();
+        thread::Builder::new()
+            .name("consensus-pending-votes".to_string())
+            .spawn(move || {
+                while let Ok(mut pending_votes) = receiver.recv() {
+                    while let Ok(latest) = receiver.try_recv() {
+                        pending_votes = latest;
+                    }
+                    let (round, votes) = pending_votes;
+                    if let Err(e) = storage.save_pending_votes(votes) {
+                        warn!(
+                            "Failed to persist the pending votes of round {} due to {}",
+                            round, e
+                        );
+                    }
+                }
+            })
+            .expect("[consensus] Failed to spawn the pending votes writer");
+        Self {
+            sender: Mutex::new(sender),
+        }
+    }
+
+    /// Queues the pending votes of the given round to be persisted.
+    pub fn save(&self, round: Round, pending_votes: Vec<VoteMsg>) {
+        if self
+            .sender
+            .lock()
+            .unwrap()
+            .send((round, pending_votes))
+            .is_err()
+        {
+            warn!("The pending votes writer is stopped");
+        }
+    }
+}
@@ -3,24 +3,35 @@
 
 use crate::{
     chained_bft::{
-        common::Payload,
-        consensus_types::{block::Block, quorum_cert::QuorumCert},
-        consensusdb::ConsensusDB,
+        common::{Payload, Round},
+        consensus_types::{
+            block::Block, key_rotation::KeyRotation, quorum_cert::QuorumCert,
+            timeout_msg::TimeoutMsg, vote_msg::VoteMsg,
+        },
+        consensusdb::{ConsensusDB, SingleEntryKey},
+        epoch_manager::EpochChange,
+        evidence::Evidence,
         liveness::pacemaker_timeout_manager::HighestTimeoutCertificates,
-        safety::safety_rules::ConsensusState,
+        safety::{
+            persistent_safety_storage::PersistentSafetyStorage, safety_rules::ConsensusState,
+        },
     },
     consensus_provider::create_storage_read_client,
 };
-use config::config::NodeConfig;
+use config::config::{ConsensusStorageBackend, NodeConfig};
 use crypto::HashValue;
 use failure::{Result, ResultExt};
 use logger::prelude::*;
 use rmp_serde::{from_slice, to_vec_named};
 use std::{
     collections::{HashMap, HashSet},
-    sync::Arc,
+    sync::{Arc, Mutex},
 };
-use types::ledger_info::LedgerInfo;
+use types::{crypto_proxies::ValidatorSigner, ledger_info::LedgerInfo};
+
+#[cfg(test)]
+#[path = "persistent_storage_test.rs"]
+mod persistent_storage_test;
 
 /// Persistent storage for liveness data
 pub trait PersistentLivenessStorage: Send + Sync {
@@ -30,6 +41,18 @@ pub trait PersistentLivenessStorage: Send + Sync {
         &self,
         highest_timeout_certs: HighestTimeoutCertificates,
     ) -> Result<()>;
+
+    /// Persist the votes collected for the current round, so that a restarted proposer can
+    /// complete the QC it was building. Overrides the previously saved pending votes.
+    fn save_pending_votes(&self, pending_votes: Vec<VoteMsg>) -> Result<()>;
+
+    /// Persist the last vote of this node before it is sent, so that a restarted node sends the
+    /// same vote again instead of a conflicting one. Overrides the previously saved vote.
+    fn save_last_vote(&self, last_vote: VoteMsg) -> Result<()>;
+
+    /// Persist the last timeout message of this node before it is broadcast, so that a restarted
+    /// node broadcasts the same timeout again. Overrides the previously saved timeout message.
+    fn save_last_timeout(&self, last_timeout: TimeoutMsg) -> Result<()>;
 }
 
 /// Persistent storage is essential for maintaining safety when a node crashes.  Specifically,
@@ -43,14 +66,37 @@ pub trait PersistentStorage<T>: PersistentLivenessStorage + Send + Sync {
     /// (workaround for trait downcasting
     fn persistent_liveness_storage(&self) -> Box<dyn PersistentLivenessStorage>;
 
+    /// Get an instance of the storage of the safety rules state, which is persisted separately
+    /// from the block tree and is not part of the RecoveryData.
+    fn persistent_safety_storage(&self) -> Box<dyn PersistentSafetyStorage>;
+
     /// Persist the blocks and quorum certs into storage atomically.
     fn save_tree(&self, blocks: Vec<Block<T>>, quorum_certs: Vec<QuorumCert>) -> Result<()>;
 
     /// Delete the corresponding blocks and quorum certs atomically.
     fn prune_tree(&self, block_ids: Vec<HashValue>) -> Result<()>;
 
-    /// Persist the consensus state.
-    fn save_consensus_state(&self, state: ConsensusState) -> Result<()>;
+    /// Persist the evidence of the equivocations collected so far. Overrides the previously
+    /// saved evidence.
+    fn save_evidence(&self, evidence: Vec<Evidence<T>>) -> Result<()>;
+
+    /// Persist the epoch changes the node went through, such that a restarted node resumes in
+    /// its last epoch. Overrides the previously saved epoch changes.
+    fn save_epoch_changes(&self, epoch_changes: Vec<EpochChange>) -> Result<()>;
+
+    /// Persist the key rotations accepted within the current epoch, such that a restarted node
+    /// verifies the signatures under the rotated keys. Overrides the previously saved key
+    /// rotations.
+    fn save_key_rotations(&self, key_rotations: Vec<KeyRotation>) -> Result<()>;
+
+    /// Persist the signers of the latest rotation of the consensus key of this node, such that a
+    /// restarted node keeps signing with the rotated key. Overrides the previously saved signers.
+    fn save_rotated_signers(
+        &self,
+        previous_signer: &ValidatorSigner,
+        handover_round: Round,
+        new_signer: &ValidatorSigner,
+    ) -> Result<()>;
 
     /// When the node restart, construct the instance and returned the data read from db.
     /// This could guarantee we only read once during start, and we would panic if the
@@ -65,8 +111,6 @@ pub trait PersistentStorage<T>: PersistentLivenessStorage + Send + Sync {
 /// blocks that need cleanup or return error if the input data is inconsistent.
 #[derive(Debug)]
 pub struct RecoveryData<T> {
-    // Safety data
-    state: ConsensusState,
     root: (Block<T>, QuorumCert, QuorumCert),
     // 1. the blocks guarantee the topological ordering - parent <- child.
     // 2. all blocks are children of the root.
@@ -76,6 +120,21 @@ pub struct RecoveryData<T> {
 
     // Liveness data
     highest_timeout_certificates: HighestTimeoutCertificates,
+    pending_votes: Vec<VoteMsg>,
+    last_vote: Option<VoteMsg>,
+    last_timeout: Option<TimeoutMsg>,
+
+    // Evidence of the equivocations of the other validators
+    evidence: Vec<Evidence<T>>,
+
+    // The epoch changes the node went through, in order
+    epoch_changes: Vec<EpochChange>,
+
+    // The key rotations accepted within the last epoch, in order
+    key_rotations: Vec<KeyRotation>,
+    // The signers of the latest rotation of the consensus key of this node: the previous signer,
+    // the handover round and the new signer
+    rotated_signers: Option<(ValidatorSigner, Round, ValidatorSigner)>,
 
     // If root is not consistent with StateComputer, need to state synchronize before
     // starting
@@ -84,11 +143,17 @@ pub struct RecoveryData<T> {
 
 impl<T: Payload> RecoveryData<T> {
     pub fn new(
-        state: ConsensusState,
         mut blocks: Vec<Block<T>>,
         mut quorum_certs: Vec<QuorumCert>,
         storage_ledger: &LedgerInfo,
         highest_timeout_certificates: HighestTimeoutCertificates,
+        pending_votes: Vec<VoteMsg>,
+        last_vote: Option<VoteMsg>,
+        last_timeout: Option<TimeoutMsg>,
+        evidence: Vec<Evidence<T>>,
+        epoch_changes: Vec<EpochChange>,
+        key_rotations: Vec<KeyRotation>,
+        rotated_signers: Option<(ValidatorSigner, Round, ValidatorSigner)>,
     ) -> Result<Self> {
         let root =
             Self::find_root(&mut blocks, &mut quorum_certs, storage_ledger).with_context(|e| {
@@ -118,20 +183,22 @@ impl<T: Payload> RecoveryData<T> {
         // if the root is different than the LI(S).block, we need to sync before start
         let need_sync = storage_ledger.consensus_block_id() != root.0.id();
         Ok(RecoveryData {
-            state,
             root,
             blocks,
             quorum_certs,
             blocks_to_prune,
             highest_timeout_certificates,
+            pending_votes,
+            last_vote,
+            last_timeout,
+            evidence,
+            epoch_changes,
+            key_rotations,
+            rotated_signers,
             need_sync,
         })
     }
 
-    pub fn state(&self) -> ConsensusState {
-        self.state.clone()
-    }
-
     pub fn take(
         self,
     ) -> (
@@ -152,6 +219,41 @@ impl<T: Payload> RecoveryData<T> {
         &self.highest_timeout_certificates
     }
 
+    pub fn pending_votes(&self) -> &[VoteMsg] {
+        &self.pending_votes
+    }
+
+    pub fn last_vote(&self) -> Option<&VoteMsg> {
+        self.last_vote.as_ref()
+    }
+
+    pub fn last_timeout(&self) -> Option<&TimeoutMsg> {
+        self.last_timeout.as_ref()
+    }
+
+    pub fn evidence(&self) -> &[Evidence<T>] {
+        &self.evidence
+    }
+
+    pub fn epoch_changes(&self) -> &[EpochChange] {
+        &self.epoch_changes
+    }
+
+    pub fn key_rotations(&self) -> &[KeyRotation] {
+        &self.key_rotations
+    }
+
+    pub fn take_rotated_signers(&mut self) -> Option<(ValidatorSigner, Round, ValidatorSigner)> {
+        self.rotated_signers.take()
+    }
+
+    /// The quorum certs of the recovered block tree, including the ones of the root.
+    pub fn quorum_certs(&self) -> impl Iterator<Item = &QuorumCert> {
+        std::iter::once(&self.root.1)
+            .chain(std::iter::once(&self.root.2))
+            .chain(self.quorum_certs.iter())
+    }
+
     pub fn root_ledger_info(&self) -> QuorumCert {
         self.root.2.clone()
     }
@@ -282,67 +384,156 @@ impl<T: Payload> RecoveryData<T> {
     }
 }
 
-/// The proxy we use to persist data in libra db storage service via grpc.
-pub struct StorageWriteProxy {
-    db: Arc<ConsensusDB>,
+/// The storage engine the `StorageWriteProxy` persists the consensus data to: the blocks and
+/// quorum certs of the block tree, and the single entries holding serialized data (e.g., the
+/// highest timeout certificates), each of which is overridden on every save. Other storage
+/// engines are integrated by implementing this trait.
+pub trait StorageBackend<T>: Send + Sync {
+    /// Persist the blocks and quorum certs atomically.
+    fn save_blocks_and_quorum_certs(
+        &self,
+        blocks: Vec<Block<T>>,
+        quorum_certs: Vec<QuorumCert>,
+    ) -> Result<()>;
+
+    /// Delete the blocks and the quorum certs certifying them atomically.
+    fn delete_blocks_and_quorum_certs(&self, block_ids: Vec<HashValue>) -> Result<()>;
+
+    /// All the persisted blocks and quorum certs, in no particular order.
+    fn get_blocks_and_quorum_certs(&self) -> Result<(Vec<Block<T>>, Vec<QuorumCert>)>;
+
+    fn save_single_entry(&self, key: SingleEntryKey, value: Vec<u8>) -> Result<()>;
+
+    fn get_single_entry(&self, key: SingleEntryKey) -> Result<Option<Vec<u8>>>;
+}
+
+/// A storage backend keeping the consensus data in memory, which is lost when the node stops.
+pub struct InMemoryStorageBackend<T> {
+    blocks: Mutex<HashMap<HashValue, Block<T>>>,
+    quorum_certs: Mutex<HashMap<HashValue, QuorumCert>>,
+    single_entries: Mutex<HashMap<SingleEntryKey, Vec<u8>>>,
 }
 
-impl StorageWriteProxy {
-    pub fn new(db: Arc<ConsensusDB>) -> Self {
-        StorageWriteProxy { db }
+impl<T> Default for InMemoryStorageBackend<T> {
+    fn default() -> Self {
+        Self {
+            blocks: Mutex::new(HashMap::new()),
+            quorum_certs: Mutex::new(HashMap::new()),
+            single_entries: Mutex::new(HashMap::new()),
+        }
     }
 }
 
-impl PersistentLivenessStorage for StorageWriteProxy {
-    fn save_highest_timeout_cert(
+impl<T: Payload> StorageBackend<T> for InMemoryStorageBackend<T> {
+    fn save_blocks_and_quorum_certs(
         &self,
-        highest_timeout_certs: HighestTimeoutCertificates,
+        blocks: Vec<Block<T>>,
+        quorum_certs: Vec<QuorumCert>,
     ) -> Result<()> {
-        self.db
-            .save_highest_timeout_certificates(to_vec_named(&highest_timeout_certs)?)
+        // Both locks are held such that the blocks and the quorum certs are saved atomically.
+        let mut stored_blocks = self.blocks.lock().unwrap();
+        let mut stored_quorum_certs = self.quorum_certs.lock().unwrap();
+        for block in blocks {
+            stored_blocks.insert(block.id(), block);
+        }
+        for qc in quorum_certs {
+            stored_quorum_certs.insert(qc.certified_block_id(), qc);
+        }
+        Ok(())
     }
-}
 
-impl<T: Payload> PersistentStorage<T> for StorageWriteProxy {
-    fn persistent_liveness_storage(&self) -> Box<dyn PersistentLivenessStorage> {
-        Box::new(StorageWriteProxy::new(Arc::clone(&self.db)))
+    fn delete_blocks_and_quorum_certs(&self, block_ids: Vec<HashValue>) -> Result<()> {
+        let mut stored_blocks = self.blocks.lock().unwrap();
+        let mut stored_quorum_certs = self.quorum_certs.lock().unwrap();
+        for id in block_ids {
+            stored_blocks.remove(&id);
+            stored_quorum_certs.remove(&id);
+        }
+        Ok(())
     }
 
-    fn save_tree(&self, blocks: Vec<Block<T>>, quorum_certs: Vec<QuorumCert>) -> Result<()> {
-        self.db
-            .save_blocks_and_quorum_certificates(blocks, quorum_certs)
+    fn get_blocks_and_quorum_certs(&self) -> Result<(Vec<Block<T>>, Vec<QuorumCert>)> {
+        let blocks = self.blocks.lock().unwrap();
+        let quorum_certs = self.quorum_certs.lock().unwrap();
+        Ok((
+            blocks.values().cloned().collect(),
+            quorum_certs.values().cloned().collect(),
+        ))
     }
 
-    fn prune_tree(&self, block_ids: Vec<HashValue>) -> Result<()> {
-        if !block_ids.is_empty() {
-            // quorum certs that certified the block_ids will get removed
-            self.db
-                .delete_blocks_and_quorum_certificates::<T>(block_ids)?;
-        }
+    fn save_single_entry(&self, key: SingleEntryKey, value: Vec<u8>) -> Result<()> {
+        self.single_entries.lock().unwrap().insert(key, value);
         Ok(())
     }
 
-    fn save_consensus_state(&self, state: ConsensusState) -> Result<()> {
-        self.db.save_state(to_vec_named(&state)?)
+    fn get_single_entry(&self, key: SingleEntryKey) -> Result<Option<Vec<u8>>> {
+        Ok(self.single_entries.lock().unwrap().get(&key).cloned())
     }
+}
 
-    fn start(config: &NodeConfig) -> (Arc<Self>, RecoveryData<T>) {
-        info!("Start consensus recovery.");
-        let read_client = create_storage_read_client(config);
-        let db = Arc::new(ConsensusDB::new(config.storage.dir.clone()));
-        let proxy = Arc::new(Self::new(Arc::clone(&db)));
-        let initial_data = db.get_data().expect("unable to recover consensus data");
-        let consensus_state = initial_data.0.map_or_else(ConsensusState::default, |s| {
-            from_slice(&s[..]).expect("unable to deserialize consensus state")
-        });
-        debug!("Recovered consensus state: {}", consensus_state);
-        let highest_timeout_certificates = initial_data
-            .1
+/// The proxy we use to persist the consensus data to its storage backend, the ConsensusDB of
+/// the node by default.
+pub struct StorageWriteProxy<T> {
+    backend: Arc<dyn StorageBackend<T>>,
+}
+
+impl<T: Payload> StorageWriteProxy<T> {
+    pub fn new(backend: Arc<dyn StorageBackend<T>>) -> Self {
+        StorageWriteProxy { backend }
+    }
+
+    /// Recovers the consensus data persisted to the given backend, given the latest ledger info
+    /// of the storage of the node. An empty backend is bootstrapped with the genesis block.
+    pub fn start_with_backend(
+        backend: Arc<dyn StorageBackend<T>>,
+        storage_ledger: &LedgerInfo,
+    ) -> (Arc<Self>, RecoveryData<T>) {
+        let proxy = Arc::new(Self::new(Arc::clone(&backend)));
+        let highest_timeout_certificates = backend
+            .get_single_entry(SingleEntryKey::HighestTimeoutCertificates)
+            .expect("unable to recover the highest timeout certificates")
             .map_or_else(HighestTimeoutCertificates::default, |s| {
                 from_slice(&s[..]).expect("unable to deserialize highest timeout certificates")
             });
-        let mut blocks = initial_data.2;
-        let mut quorum_certs: Vec<_> = initial_data.3;
+        let pending_votes: Vec<VoteMsg> = backend
+            .get_single_entry(SingleEntryKey::PendingVotes)
+            .expect("unable to recover the pending votes")
+            .map_or_else(Vec::new, |s| {
+                from_slice(&s[..]).expect("unable to deserialize pending votes")
+            });
+        let last_vote: Option<VoteMsg> = backend
+            .get_single_entry(SingleEntryKey::LastVote)
+            .expect("unable to recover the last vote")
+            .map(|s| from_slice(&s[..]).expect("unable to deserialize the last vote"));
+        let last_timeout: Option<TimeoutMsg> = backend
+            .get_single_entry(SingleEntryKey::LastTimeout)
+            .expect("unable to recover the last timeout")
+            .map(|s| from_slice(&s[..]).expect("unable to deserialize the last timeout"));
+        let evidence: Vec<Evidence<T>> = backend
+            .get_single_entry(SingleEntryKey::Evidence)
+            .expect("unable to recover the evidence")
+            .map_or_else(Vec::new, |s| {
+                from_slice(&s[..]).expect("unable to deserialize the evidence")
+            });
+        let epoch_changes: Vec<EpochChange> = backend
+            .get_single_entry(SingleEntryKey::EpochChanges)
+            .expect("unable to recover the epoch changes")
+            .map_or_else(Vec::new, |s| {
+                from_slice(&s[..]).expect("unable to deserialize the epoch changes")
+            });
+        let key_rotations: Vec<KeyRotation> = backend
+            .get_single_entry(SingleEntryKey::KeyRotations)
+            .expect("unable to recover the key rotations")
+            .map_or_else(Vec::new, |s| {
+                from_slice(&s[..]).expect("unable to deserialize the key rotations")
+            });
+        let rotated_signers: Option<(ValidatorSigner, Round, ValidatorSigner)> = backend
+            .get_single_entry(SingleEntryKey::RotatedSigners)
+            .expect("unable to recover the rotated signers")
+            .map(|s| from_slice(&s[..]).expect("unable to deserialize the rotated signers"));
+        let (mut blocks, mut quorum_certs) = backend
+            .get_blocks_and_quorum_certs()
+            .expect("unable to recover consensus data");
         // bootstrap the empty store with genesis block and qc.
         if blocks.is_empty() && quorum_certs.is_empty() {
             blocks.push(Block::make_genesis_block());
@@ -353,7 +544,7 @@ impl<T: Payload> PersistentStorage<T> for StorageWriteProxy {
         }
         let blocks_repr: Vec<String> = blocks.iter().map(|b| format!("\n\t{}", b)).collect();
         info!(
-            "The following blocks were restored from ConsensusDB : {}",
+            "The following blocks were restored from the consensus storage: {}",
             blocks_repr.concat()
         );
         let qc_repr: Vec<String> = quorum_certs
@@ -361,24 +552,27 @@ impl<T: Payload> PersistentStorage<T> for StorageWriteProxy {
             .map(|qc| format!("\n\t{}", qc))
             .collect();
         info!(
-            "The following quorum certs were restored from ConsensusDB: {}",
+            "The following quorum certs were restored from the consensus storage: {}",
             qc_repr.concat()
         );
 
-        // find the block corresponding to storage latest ledger info
-        let (_, ledger_info, _) = read_client
-            .update_to_latest_ledger(0, vec![])
-            .expect("unable to read ledger info from storage");
         let mut initial_data = RecoveryData::new(
-            consensus_state,
             blocks,
             quorum_certs,
-            ledger_info.ledger_info(),
+            storage_ledger,
             highest_timeout_certificates,
+            pending_votes,
+            last_vote,
+            last_timeout,
+            evidence,
+            epoch_changes,
+            key_rotations,
+            rotated_signers,
         )
         .unwrap_or_else(|e| panic!("Can not construct recovery data due to {}", e));
 
-        <dyn PersistentStorage<T>>::prune_tree(proxy.as_ref(), initial_data.take_blocks_to_prune())
+        proxy
+            .prune_tree(initial_data.take_blocks_to_prune())
             .expect("unable to prune dangling blocks during restart");
 
         info!("Consensus root to start with: {}", initial_data.root.0);
@@ -391,3 +585,115 @@ impl<T: Payload> PersistentStorage<T> for StorageWriteProxy {
         (proxy, initial_data)
     }
 }
+
+impl<T: Payload> PersistentLivenessStorage for StorageWriteProxy<T> {
+    fn save_highest_timeout_cert(
+        &self,
+        highest_timeout_certs: HighestTimeoutCertificates,
+    ) -> Result<()> {
+        self.backend.save_single_entry(
+            SingleEntryKey::HighestTimeoutCertificates,
+            to_vec_named(&highest_timeout_certs)?,
+        )
+    }
+
+    fn save_pending_votes(&self, pending_votes: Vec<VoteMsg>) -> Result<()> {
+        self.backend
+            .save_single_entry(SingleEntryKey::PendingVotes, to_vec_named(&pending_votes)?)
+    }
+
+    fn save_last_vote(&self, last_vote: VoteMsg) -> Result<()> {
+        self.backend
+            .save_single_entry(SingleEntryKey::LastVote, to_vec_named(&last_vote)?)
+    }
+
+    fn save_last_timeout(&self, last_timeout: TimeoutMsg) -> Result<()> {
+        self.backend
+            .save_single_entry(SingleEntryKey::LastTimeout, to_vec_named(&last_timeout)?)
+    }
+}
+
+impl<T: Payload> PersistentSafetyStorage for StorageWriteProxy<T> {
+    fn consensus_state(&self) -> Result<ConsensusState> {
+        Ok(
+            match self
+                .backend
+                .get_single_entry(SingleEntryKey::ConsensusState)?
+            {
+                Some(state) => from_slice(&state[..])?,
+                None => ConsensusState::default(),
+            },
+        )
+    }
+
+    fn set_consensus_state(&self, state: &ConsensusState) -> Result<()> {
+        self.backend
+            .save_single_entry(SingleEntryKey::ConsensusState, to_vec_named(state)?)
+    }
+}
+
+impl<T: Payload> PersistentStorage<T> for StorageWriteProxy<T> {
+    fn persistent_liveness_storage(&self) -> Box<dyn PersistentLivenessStorage> {
+        Box::new(StorageWriteProxy::new(Arc::clone(&self.backend)))
+    }
+
+    fn persistent_safety_storage(&self) -> Box<dyn PersistentSafetyStorage> {
+        Box::new(StorageWriteProxy::new(Arc::clone(&self.backend)))
+    }
+
+    fn save_tree(&self, blocks: Vec<Block<T>>, quorum_certs: Vec<QuorumCert>) -> Result<()> {
+        self.backend
+            .save_blocks_and_quorum_certs(blocks, quorum_certs)
+    }
+
+    fn prune_tree(&self, block_ids: Vec<HashValue>) -> Result<()> {
+        if !block_ids.is_empty() {
+            // quorum certs that certified the block_ids will get removed
+            self.backend.delete_blocks_and_quorum_certs(block_ids)?;
+        }
+        Ok(())
+    }
+
+    fn save_evidence(&self, evidence: Vec<Evidence<T>>) -> Result<()> {
+        self.backend
+            .save_single_entry(SingleEntryKey::Evidence, to_vec_named(&evidence)?)
+    }
+
+    fn save_epoch_changes(&self, epoch_changes: Vec<EpochChange>) -> Result<()> {
+        self.backend
+            .save_single_entry(SingleEntryKey::EpochChanges, to_vec_named(&epoch_changes)?)
+    }
+
+    fn save_key_rotations(&self, key_rotations: Vec<KeyRotation>) -> Result<()> {
+        self.backend
+            .save_single_entry(SingleEntryKey::KeyRotations, to_vec_named(&key_rotations)?)
+    }
+
+    fn save_rotated_signers(
+        &self,
+        previous_signer: &ValidatorSigner,
+        handover_round: Round,
+        new_signer: &ValidatorSigner,
+    ) -> Result<()> {
+        self.backend.save_single_entry(
+            SingleEntryKey::RotatedSigners,
+            to_vec_named(&(previous_signer, handover_round, new_signer))?,
+        )
+    }
+
+    fn start(config: &NodeConfig) -> (Arc<Self>, RecoveryData<T>) {
+        info!("Start consensus recovery.");
+        let read_client = create_storage_read_client(config);
+        let backend: Arc<dyn StorageBackend<T>> = match config.consensus.get_storage_backend() {
+            ConsensusStorageBackend::RocksDB => {
+                Arc::new(ConsensusDB::new(config.storage.dir.clone()))
+            }
+            ConsensusStorageBackend::InMemory => Arc::new(InMemoryStorageBackend::default()),
+        };
+        // find the block corresponding to storage latest ledger info
+        let (_, ledger_info, _) = read_client
+            .update_to_latest_ledger(0, vec![])
+            .expect("unable to read ledger info from storage");
+        Self::start_with_backend(backend, ledger_info.ledger_info())
+    }
+}
@@ -0,0 +1,90 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::chained_bft::{
+    block_storage::BlockReader,
+    consensus_types::{key_rotation::KeyRotation, quorum_cert::QuorumCert},
+    consensusdb::ConsensusDB,
+    epoch_manager::EpochChange,
+    persistent_storage::{
+        InMemoryStorageBackend, PersistentStorage, StorageBackend, StorageWriteProxy,
+    },
+    safety::safety_rules::ConsensusState,
+    test_utils::{build_empty_tree, TestPayload, TreeInserter},
+};
+use crypto::ed25519::compat;
+use std::sync::Arc;
+use tools::tempdir::TempPath;
+use types::{crypto_proxies::ValidatorSigner, validator_set::ValidatorSet};
+
+// The consensus data saved through the proxy is recovered from the same backend.
+fn check_recovery(backend: Arc<dyn StorageBackend<TestPayload>>) {
+    let genesis_qc = QuorumCert::certificate_for_genesis();
+    let storage_ledger = genesis_qc.ledger_info().ledger_info().clone();
+    let (storage, initial_data) =
+        StorageWriteProxy::start_with_backend(Arc::clone(&backend), &storage_ledger);
+    let (root, blocks, _) = initial_data.take();
+    assert_eq!(root.0.round(), 0);
+    assert!(blocks.is_empty());
+
+    let block_tree = build_empty_tree();
+    let mut inserter = TreeInserter::new(block_tree.clone());
+    let a1 = inserter.insert_block_with_qc(genesis_qc.clone(), &block_tree.root(), 1);
+    let a2 = inserter.insert_block(&a1, 2);
+    storage
+        .save_tree(
+            vec![a1.block().clone(), a2.block().clone()],
+            vec![a2.quorum_cert().clone()],
+        )
+        .unwrap();
+    storage
+        .persistent_safety_storage()
+        .set_consensus_state(&ConsensusState::new(2, 0))
+        .unwrap();
+    let epoch_changes = vec![EpochChange::new(1, ValidatorSet::new(vec![]), genesis_qc)];
+    storage.save_epoch_changes(epoch_changes.clone()).unwrap();
+    let signer = ValidatorSigner::random([0u8; 32]);
+    let new_signer = ValidatorSigner::new(signer.author(), compat::generate_keypair(None).0);
+    let key_rotations = vec![KeyRotation::new(1, 5, &signer, &new_signer).unwrap()];
+    storage.save_key_rotations(key_rotations.clone()).unwrap();
+    storage
+        .save_rotated_signers(&signer, 5, &new_signer)
+        .unwrap();
+
+    let (storage, mut initial_data) =
+        StorageWriteProxy::start_with_backend(backend, &storage_ledger);
+    assert_eq!(initial_data.epoch_changes(), &epoch_changes[..]);
+    assert_eq!(initial_data.key_rotations(), &key_rotations[..]);
+    let (previous_signer, handover_round, rotated_signer) =
+        initial_data.take_rotated_signers().unwrap();
+    assert_eq!(previous_signer.public_key(), signer.public_key());
+    assert_eq!(handover_round, 5);
+    assert_eq!(rotated_signer.public_key(), new_signer.public_key());
+    assert_eq!(rotated_signer.author(), signer.author());
+    let (_, blocks, quorum_certs) = initial_data.take();
+    assert_eq!(
+        blocks.iter().map(|block| block.id()).collect::<Vec<_>>(),
+        vec![a1.id(), a2.id()]
+    );
+    assert!(quorum_certs
+        .iter()
+        .any(|qc| qc.certified_block_id() == a1.id()));
+    assert_eq!(
+        storage
+            .persistent_safety_storage()
+            .consensus_state()
+            .unwrap(),
+        ConsensusState::new(2, 0)
+    );
+}
+
+#[test]
+fn test_in_memory_backend_recovery() {
+    check_recovery(Arc::new(InMemoryStorageBackend::default()));
+}
+
+#[test]
+fn test_rocksdb_backend_recovery() {
+    let tmp_dir = TempPath::new();
+    check_recovery(Arc::new(ConsensusDB::new(&tmp_dir)));
+}
@@ -1,4 +1,6 @@
 // Copyright (c) The Libra Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
+pub(crate) mod persistent_safety_storage;
+pub(crate) mod remote_safety_rules;
 pub(crate) mod safety_rules;
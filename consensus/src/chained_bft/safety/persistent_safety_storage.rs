@@ -0,0 +1,83 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::chained_bft::safety::safety_rules::ConsensusState;
+use failure::prelude::*;
+use std::{
+    fs::{self, File},
+    io::Write,
+    path::PathBuf,
+    sync::Mutex,
+};
+
+/// Persistent storage of the safety-critical state of a replica (see `ConsensusState`). It is
+/// owned by `SafetyRules` and kept apart from the block tree storage, such that the state that
+/// prevents a replica from equivocating can be audited and persisted on its own.
+pub trait PersistentSafetyStorage: Send + Sync {
+    /// The persisted consensus state, the default state if nothing was persisted yet.
+    fn consensus_state(&self) -> Result<ConsensusState>;
+
+    /// Atomically overrides the persisted consensus state.
+    fn set_consensus_state(&self, state: &ConsensusState) -> Result<()>;
+}
+
+/// A safety storage that keeps the consensus state in memory, for the replicas that do not need
+/// to survive a restart (e.g., in tests).
+#[derive(Default)]
+pub struct InMemorySafetyStorage {
+    state: Mutex<ConsensusState>,
+}
+
+impl InMemorySafetyStorage {
+    pub fn new(state: ConsensusState) -> Self {
+        Self {
+            state: Mutex::new(state),
+        }
+    }
+}
+
+impl PersistentSafetyStorage for InMemorySafetyStorage {
+    fn consensus_state(&self) -> Result<ConsensusState> {
+        Ok(self.state.lock().unwrap().clone())
+    }
+
+    fn set_consensus_state(&self, state: &ConsensusState) -> Result<()> {
+        *self.state.lock().unwrap() = state.clone();
+        Ok(())
+    }
+}
+
+/// A safety storage that keeps the consensus state in a JSON file of its own, used by the safety
+/// rules service. The file is replaced atomically on every update.
+pub struct OnDiskSafetyStorage {
+    file_path: PathBuf,
+}
+
+impl OnDiskSafetyStorage {
+    pub fn new(file_path: PathBuf) -> Self {
+        Self { file_path }
+    }
+}
+
+impl PersistentSafetyStorage for OnDiskSafetyStorage {
+    fn consensus_state(&self) -> Result<ConsensusState> {
+        if !self.file_path.exists() {
+            return Ok(ConsensusState::default());
+        }
+        let file = File::open(&self.file_path)
+            .with_context(|_| format!("Failed to open {:?}", self.file_path))?;
+        Ok(serde_json::from_reader(file)
+            .with_context(|_| format!("Failed to deserialize {:?}", self.file_path))?)
+    }
+
+    fn set_consensus_state(&self, state: &ConsensusState) -> Result<()> {
+        // The state is written to a temporary file first, such that a crash leaves either the
+        // previous or the new state.
+        let tmp_path = self.file_path.with_extension("tmp");
+        let mut file = File::create(&tmp_path)?;
+        file.write_all(&serde_json::to_vec(state)?)?;
+        file.sync_all()?;
+        fs::rename(&tmp_path, &self.file_path)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,299 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Runs the safety rules in a separate process: the `SafetyRulesService` owns the consensus
+//! state and its storage and serves the requests of the `RemoteSafetyRules` client of consensus
+//! over a local socket. The service decides whether a proposal can be voted on, while the vote
+//! itself is signed by consensus.
+
+use crate::chained_bft::{
+    common::{Author, Payload, Round},
+    consensus_types::{block::ExecutedBlock, quorum_cert::QuorumCert, vote_msg::VoteMsg},
+    safety::{
+        persistent_safety_storage::{OnDiskSafetyStorage, PersistentSafetyStorage},
+        safety_rules::{ConsensusState, SafetyRules, TSafetyRules, VoteInfo},
+    },
+};
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use config::config::{CommitRule, ConsensusConfig, SafetyRulesBackend};
+use crypto::HashValue;
+use failure::prelude::*;
+use logger::prelude::*;
+use rmp_serde::{from_slice, to_vec_named};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{
+    fs,
+    io::{Read, Write},
+    os::unix::net::{UnixListener, UnixStream},
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, Instant},
+};
+use types::{crypto_proxies::ValidatorSigner, ledger_info::LedgerInfo};
+
+#[cfg(test)]
+#[path = "remote_safety_rules_test.rs"]
+mod remote_safety_rules_test;
+
+/// Timeout of a single request to the safety rules service.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+/// How long the client waits for the service to accept connections when it starts.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+const CONNECT_RETRY_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Deserialize, Serialize)]
+enum SafetyRulesRequest {
+    ConsensusState,
+    Update(QuorumCert),
+    IncreaseLastVoteRound(Round),
+    ApproveVote {
+        proposal_id: HashValue,
+        proposal_round: Round,
+        quorum_cert: QuorumCert,
+    },
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+enum SafetyRulesResponse {
+    // The state of the service after the request.
+    ConsensusState(ConsensusState),
+    VoteInfo(VoteInfo),
+    Error(String),
+}
+
+// Every message is sent as its length followed by its serialized content.
+fn write_message<M: Serialize>(stream: &mut UnixStream, message: &M) -> Result<()> {
+    let bytes = to_vec_named(message)?;
+    stream.write_u32::<BigEndian>(bytes.len() as u32)?;
+    stream.write_all(&bytes)?;
+    stream.flush()?;
+    Ok(())
+}
+
+fn read_message<M: DeserializeOwned>(stream: &mut UnixStream) -> Result<M> {
+    let len = stream.read_u32::<BigEndian>()?;
+    let mut bytes = vec![0; len as usize];
+    stream.read_exact(&mut bytes)?;
+    Ok(from_slice(&bytes)?)
+}
+
+/// The client of the safety rules service used by consensus in place of `SafetyRules`. It keeps
+/// the latest state reported by the service for monitoring, and reconnects to the service after
+/// a failure: a request fails if the service can't be reached, in which case consensus doesn't
+/// vote.
+pub struct RemoteSafetyRules {
+    socket_path: PathBuf,
+    stream: Option<UnixStream>,
+    consensus_state: ConsensusState,
+    // The highest quorum certificate that could not be delivered to the service, delivered
+    // before the next vote such that the preferred block is never missed.
+    pending_update: Option<QuorumCert>,
+}
+
+impl RemoteSafetyRules {
+    /// Connects to the service listening on the given socket, waiting for it to start.
+    pub fn connect(socket_path: PathBuf) -> Result<Self> {
+        let mut client = Self {
+            socket_path,
+            stream: None,
+            consensus_state: ConsensusState::default(),
+            pending_update: None,
+        };
+        let deadline = Instant::now() + CONNECT_TIMEOUT;
+        loop {
+            match client.request(SafetyRulesRequest::ConsensusState) {
+                Ok(_) => return Ok(client),
+                Err(e) if Instant::now() > deadline => {
+                    bail!(
+                        "Failed to connect to the safety rules service at {:?}: {}",
+                        client.socket_path,
+                        e
+                    );
+                }
+                Err(_) => thread::sleep(CONNECT_RETRY_INTERVAL),
+            }
+        }
+    }
+
+    fn request(&mut self, request: SafetyRulesRequest) -> Result<SafetyRulesResponse> {
+        let result = self.send_request(&request);
+        if result.is_err() {
+            // The connection is re-established by the next request.
+            self.stream = None;
+        }
+        match result? {
+            SafetyRulesResponse::Error(e) => bail!("Safety rules service error: {}", e),
+            SafetyRulesResponse::ConsensusState(state) => {
+                self.consensus_state = state.clone();
+                Ok(SafetyRulesResponse::ConsensusState(state))
+            }
+            SafetyRulesResponse::VoteInfo(vote_info) => {
+                self.consensus_state = vote_info.consensus_state().clone();
+                Ok(SafetyRulesResponse::VoteInfo(vote_info))
+            }
+        }
+    }
+
+    fn send_request(&mut self, request: &SafetyRulesRequest) -> Result<SafetyRulesResponse> {
+        if self.stream.is_none() {
+            let stream = UnixStream::connect(&self.socket_path)?;
+            stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+            stream.set_write_timeout(Some(REQUEST_TIMEOUT))?;
+            self.stream = Some(stream);
+        }
+        let stream = self.stream.as_mut().unwrap();
+        write_message(stream, request)?;
+        read_message(stream)
+    }
+
+    fn deliver_pending_update(&mut self) -> Result<()> {
+        if let Some(qc) = self.pending_update.take() {
+            if let Err(e) = self.request(SafetyRulesRequest::Update(qc.clone())) {
+                self.pending_update = Some(qc);
+                return Err(e);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<T: Payload> TSafetyRules<T> for RemoteSafetyRules {
+    fn consensus_state(&self) -> ConsensusState {
+        self.consensus_state.clone()
+    }
+
+    fn update(&mut self, qc: &QuorumCert) -> Result<()> {
+        let result = self
+            .deliver_pending_update()
+            .and_then(|_| self.request(SafetyRulesRequest::Update(qc.clone())));
+        if let Err(e) = result {
+            // Only the highest undelivered certificate matters for the preferred block.
+            let is_higher = self.pending_update.as_ref().map_or(true, |pending| {
+                qc.parent_block_round() > pending.parent_block_round()
+            });
+            if is_higher {
+                self.pending_update = Some(qc.clone());
+            }
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    fn increase_last_vote_round(&mut self, round: Round) -> Result<()> {
+        self.request(SafetyRulesRequest::IncreaseLastVoteRound(round))?;
+        Ok(())
+    }
+
+    fn construct_and_sign_vote(
+        &mut self,
+        author: Author,
+        proposed_block: &ExecutedBlock<T>,
+        ledger_info_placeholder: &dyn Fn(Option<HashValue>) -> LedgerInfo,
+        signer: &ValidatorSigner,
+    ) -> Result<VoteMsg> {
+        self.deliver_pending_update()?;
+        let block = proposed_block.block();
+        let vote_info = match self.request(SafetyRulesRequest::ApproveVote {
+            proposal_id: block.id(),
+            proposal_round: block.round(),
+            quorum_cert: block.quorum_cert().clone(),
+        })? {
+            SafetyRulesResponse::VoteInfo(vote_info) => vote_info,
+            response => bail!("Unexpected safety rules response: {:?}", response),
+        };
+        Ok(vote_info.sign_vote(
+            author,
+            proposed_block.compute_result().executed_state.state_id,
+            ledger_info_placeholder(vote_info.potential_commit_id()),
+            signer,
+        ))
+    }
+}
+
+/// The safety rules service: applies the requests of consensus to its `SafetyRules`, whose
+/// state is persisted to the storage of the service. The clients are served one at a time.
+/// The service must follow the commit rule of consensus.
+pub struct SafetyRulesService {
+    safety_rules: SafetyRules,
+}
+
+impl SafetyRulesService {
+    pub fn new(storage: Box<dyn PersistentSafetyStorage>, commit_rule: CommitRule) -> Result<Self> {
+        let state = storage.consensus_state()?;
+        info!("Safety rules service recovered {}", state);
+        Ok(Self {
+            safety_rules: SafetyRules::new_with_storage(state, storage)
+                .with_commit_rule(commit_rule),
+        })
+    }
+
+    /// Serves the clients connecting to the given socket, never returns unless the socket can't
+    /// be bound.
+    pub fn run(&mut self, socket_path: &Path) -> Result<()> {
+        // A socket left by a previous run of the service would prevent binding.
+        if socket_path.exists() {
+            fs::remove_file(socket_path)?;
+        }
+        let listener = UnixListener::bind(socket_path)
+            .with_context(|_| format!("Failed to bind {:?}", socket_path))?;
+        info!("Safety rules service listening on {:?}", socket_path);
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    if let Err(e) = self.serve(stream) {
+                        warn!("Safety rules client disconnected: {}", e);
+                    }
+                }
+                Err(e) => error!("Failed to accept a safety rules client: {}", e),
+            }
+        }
+        Ok(())
+    }
+
+    fn serve(&mut self, mut stream: UnixStream) -> Result<()> {
+        loop {
+            let request = read_message(&mut stream)?;
+            let response = self.handle(request);
+            write_message(&mut stream, &response)?;
+        }
+    }
+
+    fn handle(&mut self, request: SafetyRulesRequest) -> SafetyRulesResponse {
+        let result = match request {
+            SafetyRulesRequest::ConsensusState => Ok(None),
+            SafetyRulesRequest::Update(qc) => {
+                self.safety_rules.update(&qc);
+                Ok(None)
+            }
+            SafetyRulesRequest::IncreaseLastVoteRound(round) => self
+                .safety_rules
+                .increase_last_vote_round(round)
+                .map(|_| None),
+            SafetyRulesRequest::ApproveVote {
+                proposal_id,
+                proposal_round,
+                quorum_cert,
+            } => self
+                .safety_rules
+                .approve_vote(proposal_id, proposal_round, &quorum_cert)
+                .map(Some),
+        };
+        match result {
+            Ok(Some(vote_info)) => SafetyRulesResponse::VoteInfo(vote_info),
+            Ok(None) => SafetyRulesResponse::ConsensusState(self.safety_rules.consensus_state()),
+            Err(e) => SafetyRulesResponse::Error(e.to_string()),
+        }
+    }
+}
+
+/// Runs the safety rules service of a validator with the given consensus config, persisting the
+/// consensus state to its safety rules storage file. Fails unless the config selects the
+/// "process" safety rules backend.
+pub fn run_safety_rules_service(config: &ConsensusConfig) -> Result<()> {
+    let socket_path = match config.get_safety_rules_backend() {
+        SafetyRulesBackend::Process(socket_path) => socket_path,
+        SafetyRulesBackend::InProcess => bail!("The safety rules of the node run within consensus"),
+    };
+    let storage = OnDiskSafetyStorage::new(config.safety_rules_storage_file().clone());
+    SafetyRulesService::new(Box::new(storage), config.get_commit_rule())?.run(&socket_path)
+}
@@ -0,0 +1,96 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::chained_bft::{
+    consensus_types::quorum_cert::QuorumCert,
+    safety::{
+        persistent_safety_storage::OnDiskSafetyStorage,
+        remote_safety_rules::{RemoteSafetyRules, SafetyRulesService},
+        safety_rules::{ConsensusState, TSafetyRules},
+    },
+    test_utils::{build_empty_tree, TreeInserter},
+};
+use config::config::CommitRule;
+use std::{path::PathBuf, thread};
+use tools::tempdir::TempPath;
+
+fn start_service(dir: &TempPath) -> PathBuf {
+    let socket_path = dir.path().join("safety_rules.sock");
+    let storage = OnDiskSafetyStorage::new(dir.path().join("safety_rules.json"));
+    let mut service = SafetyRulesService::new(Box::new(storage), CommitRule::ThreeChain).unwrap();
+    let service_socket_path = socket_path.clone();
+    thread::spawn(move || service.run(&service_socket_path).unwrap());
+    socket_path
+}
+
+#[test]
+fn test_remote_voting() {
+    let dir = TempPath::new();
+    dir.create_as_dir().unwrap();
+    let socket_path = start_service(&dir);
+    let mut safety_rules = RemoteSafetyRules::connect(socket_path).unwrap();
+
+    let block_tree = build_empty_tree();
+    let mut inserter = TreeInserter::new(block_tree.clone());
+    let genesis = block_tree.root();
+    let a1 = inserter.insert_block_with_qc(QuorumCert::certificate_for_genesis(), &genesis, 1);
+    let a2 = inserter.insert_block(&a1, 2);
+    let a3 = inserter.insert_block(&a2, 3);
+    let signer = block_tree.signer();
+    let ledger_info_placeholder = |id| block_tree.ledger_info_placeholder(id, 0);
+
+    let vote = safety_rules
+        .construct_and_sign_vote(
+            signer.author(),
+            a2.as_ref(),
+            &ledger_info_placeholder,
+            signer,
+        )
+        .unwrap();
+    assert_eq!(vote.vote_data().block_id(), a2.id());
+    assert_eq!(vote.author(), signer.author());
+    assert_eq!(
+        TSafetyRules::<Vec<usize>>::consensus_state(&safety_rules),
+        ConsensusState::new(2, 0)
+    );
+
+    // The service rejects the proposals that are not newer than the last vote.
+    assert!(safety_rules
+        .construct_and_sign_vote(
+            signer.author(),
+            a1.as_ref(),
+            &ledger_info_placeholder,
+            signer,
+        )
+        .is_err());
+
+    TSafetyRules::<Vec<usize>>::update(&mut safety_rules, a3.quorum_cert()).unwrap();
+    TSafetyRules::<Vec<usize>>::increase_last_vote_round(&mut safety_rules, 2).unwrap();
+    assert_eq!(
+        TSafetyRules::<Vec<usize>>::consensus_state(&safety_rules),
+        ConsensusState::new(2, 1)
+    );
+}
+
+#[test]
+fn test_remote_state_survives_service_restart() {
+    let dir = TempPath::new();
+    dir.create_as_dir().unwrap();
+    let socket_path = start_service(&dir);
+    let mut safety_rules = RemoteSafetyRules::connect(socket_path).unwrap();
+    TSafetyRules::<Vec<usize>>::increase_last_vote_round(&mut safety_rules, 5).unwrap();
+
+    // A new service recovers the state persisted by the previous one.
+    let storage = OnDiskSafetyStorage::new(dir.path().join("safety_rules.json"));
+    let restarted_dir = TempPath::new();
+    restarted_dir.create_as_dir().unwrap();
+    let socket_path = restarted_dir.path().join("safety_rules.sock");
+    let mut service = SafetyRulesService::new(Box::new(storage), CommitRule::ThreeChain).unwrap();
+    let service_socket_path = socket_path.clone();
+    thread::spawn(move || service.run(&service_socket_path).unwrap());
+    let safety_rules = RemoteSafetyRules::connect(socket_path).unwrap();
+    assert_eq!(
+        TSafetyRules::<Vec<usize>>::consensus_state(&safety_rules),
+        ConsensusState::new(5, 0)
+    );
+}
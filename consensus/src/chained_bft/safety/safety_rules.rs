@@ -3,15 +3,24 @@
 
 use crate::{
     chained_bft::{
-        common::{Payload, Round},
-        consensus_types::{block::Block, quorum_cert::QuorumCert},
+        common::{Author, Payload, Round},
+        consensus_types::{
+            block::{Block, ExecutedBlock},
+            quorum_cert::QuorumCert,
+            vote_data::VoteData,
+            vote_msg::VoteMsg,
+        },
+        safety::persistent_safety_storage::{InMemorySafetyStorage, PersistentSafetyStorage},
     },
     counters,
 };
 
+use config::config::CommitRule;
 use crypto::HashValue;
+use failure::ResultExt;
 use serde::{Deserialize, Serialize};
 use std::fmt::{Display, Formatter};
+use types::{crypto_proxies::ValidatorSigner, ledger_info::LedgerInfo};
 
 #[cfg(test)]
 #[path = "safety_rules_test.rs"]
@@ -22,7 +31,7 @@ mod safety_rules_test;
 /// vote message.
 /// Vote info also includes the block id that is going to be committed in case this vote gathers
 /// QC.
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct VoteInfo {
     /// Block id of the proposed block.
     proposal_id: HashValue,
@@ -72,6 +81,31 @@ impl VoteInfo {
     pub fn grandparent_block_round(&self) -> Round {
         self.grandparent_block_round
     }
+
+    /// Signs the vote for the proposal, given the id of its executed state and the LedgerInfo to
+    /// be committed if the vote gathers a QC.
+    pub fn sign_vote(
+        &self,
+        author: Author,
+        executed_state_id: HashValue,
+        ledger_info_placeholder: LedgerInfo,
+        signer: &ValidatorSigner,
+    ) -> VoteMsg {
+        VoteMsg::new(
+            VoteData::new(
+                self.proposal_id,
+                executed_state_id,
+                self.proposal_round,
+                self.parent_block_id,
+                self.parent_block_round,
+                self.grandparent_block_id,
+                self.grandparent_block_round,
+            ),
+            author,
+            ledger_info_placeholder,
+            signer,
+        )
+    }
 }
 
 #[derive(Debug, Fail, Eq, PartialEq)]
@@ -103,7 +137,8 @@ pub enum ProposalReject {
 pub struct ConsensusState {
     last_vote_round: Round,
 
-    // A "preferred block" is the two-chain head with the highest block round.
+    // A "preferred block" is the two-chain head with the highest block round (the highest
+    // certified block with the 2-chain commit rule).
     // We're using the `head` / `tail` terminology for describing the chains of QCs for describing
     // `head` <-- <block>* <-- `tail` chains.
 
@@ -168,18 +203,53 @@ impl ConsensusState {
 
 /// SafetyRules is responsible for two things that are critical for the safety of the consensus:
 /// 1) voting rules,
-/// 2) commit rules.
+/// 2) commit rules, following either the 3-chain or the 2-chain commit rule.
+/// With the 2-chain commit rule a replica locks on the highest certified block rather than on the
+/// highest 2-chain head: a block is committed one round earlier, while a replica holding a lock
+/// higher than the QC of a proposal doesn't vote for it until the proposer learns the higher QC
+/// (e.g., from the sync info of the timeout messages).
 /// SafetyRules is NOT THREAD SAFE (should be protected outside via e.g., RwLock).
 /// The commit decisions are returned to the caller as result of learning about a new QuorumCert.
+/// The state is persisted to its own storage before a vote is signed, independently of the
+/// block tree.
 pub struct SafetyRules {
     // Keeps the state.
     state: ConsensusState,
+    // The storage the state is persisted to.
+    storage: Box<dyn PersistentSafetyStorage>,
+    commit_rule: CommitRule,
 }
 
 impl SafetyRules {
-    /// Constructs a new instance of SafetyRules given the BlockTree and ConsensusState.
+    /// Constructs a new instance of SafetyRules given the ConsensusState, the state is only kept
+    /// in memory.
     pub fn new(state: ConsensusState) -> Self {
-        Self { state }
+        Self::new_with_storage(state, Box::new(InMemorySafetyStorage::default()))
+    }
+
+    /// Constructs a new instance of SafetyRules given the ConsensusState (typically recovered
+    /// from the storage) and the storage to persist the updates of the state to. The 3-chain
+    /// commit rule applies unless specified otherwise with `with_commit_rule`.
+    pub fn new_with_storage(
+        state: ConsensusState,
+        storage: Box<dyn PersistentSafetyStorage>,
+    ) -> Self {
+        Self {
+            state,
+            storage,
+            commit_rule: CommitRule::ThreeChain,
+        }
+    }
+
+    /// Sets the commit rule. All the replicas of a validator set must follow the same rule, and
+    /// the consensus state locked with one rule is not safe to keep with the other.
+    pub fn with_commit_rule(mut self, commit_rule: CommitRule) -> Self {
+        self.commit_rule = commit_rule;
+        self
+    }
+
+    pub fn commit_rule(&self) -> CommitRule {
+        self.commit_rule
     }
 
     /// Learn about a new quorum certificate. Several things can happen as a result of that:
@@ -190,39 +260,64 @@ impl SafetyRules {
     /// committed block, might panic otherwise.
     /// The update function is invoked whenever a system learns about a potentially high QC.
     pub fn update(&mut self, qc: &QuorumCert) {
-        // Preferred block rule: choose the highest 2-chain head.
-        if qc.parent_block_round() > self.state.preferred_block_round() {
-            self.state
-                .set_preferred_block_round(qc.parent_block_round());
+        // Preferred block rule: choose the highest 2-chain head, or the highest certified block
+        // with the 2-chain commit rule.
+        let preferred_block_round = match self.commit_rule {
+            CommitRule::ThreeChain => qc.parent_block_round(),
+            CommitRule::TwoChain => qc.certified_block_round(),
+        };
+        if preferred_block_round > self.state.preferred_block_round() {
+            self.state.set_preferred_block_round(preferred_block_round);
         }
     }
 
-    /// Check if a one-chain at round r+2 causes a commit at round r and return the committed
-    /// block id at round r if possible
+    /// Check if a one-chain at round r+2 (r+1 with the 2-chain commit rule) causes a commit at
+    /// round r and return the committed block id at round r if possible
     fn commit_rule_for_certified_block(
         &self,
         block_parent_qc: &QuorumCert,
         block_round: u64,
     ) -> Option<HashValue> {
-        // We're using a so-called 3-chain commit rule: B0 (as well as its prefix)
-        // can be committed if there exist certified blocks B1 and B2 that satisfy:
-        // 1) B0 <- B1 <- B2 <--
-        // 2) round(B0) + 1 = round(B1), and
-        // 3) round(B1) + 1 = round(B2).
-
-        if block_parent_qc.parent_block_round() + 1 == block_parent_qc.certified_block_round()
-            && block_parent_qc.certified_block_round() + 1 == block_round
-        {
-            return Some(block_parent_qc.parent_block_id());
+        match self.commit_rule {
+            // We're using a so-called 3-chain commit rule: B0 (as well as its prefix)
+            // can be committed if there exist certified blocks B1 and B2 that satisfy:
+            // 1) B0 <- B1 <- B2 <--
+            // 2) round(B0) + 1 = round(B1), and
+            // 3) round(B1) + 1 = round(B2).
+            CommitRule::ThreeChain => {
+                if block_parent_qc.parent_block_round() + 1
+                    == block_parent_qc.certified_block_round()
+                    && block_parent_qc.certified_block_round() + 1 == block_round
+                {
+                    return Some(block_parent_qc.parent_block_id());
+                }
+            }
+            // With the 2-chain commit rule B0 can be committed if there exists a certified block
+            // B1 such that B0 <- B1 <-- and round(B0) + 1 = round(B1).
+            CommitRule::TwoChain => {
+                if block_parent_qc.certified_block_round() + 1 == block_round {
+                    return Some(block_parent_qc.certified_block_id());
+                }
+            }
         }
         None
     }
 
-    /// Return the new state if the voting round was increased, otherwise ignore.  Increasing the
-    /// last vote round is always safe, but can affect liveness and must be increasing
-    /// to protect safety.
-    pub fn increase_last_vote_round(&mut self, round: Round) -> Option<ConsensusState> {
-        self.state.set_last_vote_round(round)
+    /// Increases the last vote round and persists the new state, ignored if the round is not
+    /// higher than the last vote round.  Increasing the last vote round is always safe, but can
+    /// affect liveness and must be increasing to protect safety.
+    pub fn increase_last_vote_round(&mut self, round: Round) -> failure::Result<()> {
+        if self.state.set_last_vote_round(round).is_some() {
+            self.persist_state()?;
+        }
+        Ok(())
+    }
+
+    fn persist_state(&self) -> failure::Result<()> {
+        self.storage
+            .set_consensus_state(&self.state)
+            .with_context(|e| format!("Fail to persist consensus state: {:?}", e))?;
+        Ok(())
     }
 
     /// Clones the up-to-date state of consensus (for monitoring / debugging purposes)
@@ -240,35 +335,46 @@ impl SafetyRules {
         &mut self,
         proposed_block: &Block<T>,
     ) -> Result<VoteInfo, ProposalReject> {
-        if proposed_block.round() <= self.state.last_vote_round() {
+        self.voting_rule_for_proposal(
+            proposed_block.id(),
+            proposed_block.round(),
+            proposed_block.quorum_cert(),
+        )
+    }
+
+    fn voting_rule_for_proposal(
+        &mut self,
+        proposal_id: HashValue,
+        proposal_round: Round,
+        quorum_cert: &QuorumCert,
+    ) -> Result<VoteInfo, ProposalReject> {
+        if proposal_round <= self.state.last_vote_round() {
             return Err(ProposalReject::OldProposal {
-                proposal_round: proposed_block.round(),
+                proposal_round,
                 last_vote_round: self.state.last_vote_round(),
             });
         }
 
-        let respects_preferred_block = proposed_block.quorum_cert().certified_block_round()
-            >= self.state.preferred_block_round();
+        let respects_preferred_block =
+            quorum_cert.certified_block_round() >= self.state.preferred_block_round();
         if respects_preferred_block {
-            self.state.set_last_vote_round(proposed_block.round());
+            self.state.set_last_vote_round(proposal_round);
 
             // If the vote for the given proposal is gathered into QC, then this QC might eventually
             // commit another block following the rules defined in
             // `commit_rule_for_certified_block()` function.
-            let potential_commit_id = self.commit_rule_for_certified_block(
-                proposed_block.quorum_cert(),
-                proposed_block.round(),
-            );
+            let potential_commit_id =
+                self.commit_rule_for_certified_block(quorum_cert, proposal_round);
 
             Ok(VoteInfo {
-                proposal_id: proposed_block.id(),
-                proposal_round: proposed_block.round(),
+                proposal_id,
+                proposal_round,
                 consensus_state: self.state.clone(),
                 potential_commit_id,
-                parent_block_id: proposed_block.quorum_cert().certified_block_id(),
-                parent_block_round: proposed_block.quorum_cert().certified_block_round(),
-                grandparent_block_id: proposed_block.quorum_cert().parent_block_id(),
-                grandparent_block_round: proposed_block.quorum_cert().parent_block_round(),
+                parent_block_id: quorum_cert.certified_block_id(),
+                parent_block_round: quorum_cert.certified_block_round(),
+                grandparent_block_id: quorum_cert.parent_block_id(),
+                grandparent_block_round: quorum_cert.parent_block_round(),
             })
         } else {
             Err(ProposalReject::ProposalRoundLowerThenPreferredBlock {
@@ -276,4 +382,95 @@ impl SafetyRules {
             })
         }
     }
+
+    /// Applies the voting rules to the proposal with the given id, round and quorum certificate
+    /// and, if they pass, persists the updated state before the vote can be signed.
+    pub fn approve_vote(
+        &mut self,
+        proposal_id: HashValue,
+        proposal_round: Round,
+        quorum_cert: &QuorumCert,
+    ) -> failure::Result<VoteInfo> {
+        let vote_info = self.voting_rule_for_proposal(proposal_id, proposal_round, quorum_cert)?;
+        self.persist_state()?;
+        Ok(vote_info)
+    }
+
+    /// Applies the voting rules to the executed proposal and, if they pass, persists the updated
+    /// state and returns the signed vote. `ledger_info_placeholder` builds the LedgerInfo to be
+    /// committed if the vote gathers a QC, given the id of the block it would commit.
+    pub fn construct_and_sign_vote<T: Payload, F>(
+        &mut self,
+        author: Author,
+        proposed_block: &ExecutedBlock<T>,
+        ledger_info_placeholder: F,
+        signer: &ValidatorSigner,
+    ) -> failure::Result<VoteMsg>
+    where
+        F: FnOnce(Option<HashValue>) -> LedgerInfo,
+    {
+        let block = proposed_block.block();
+        let vote_info = self.approve_vote(block.id(), block.round(), block.quorum_cert())?;
+        Ok(vote_info.sign_vote(
+            author,
+            proposed_block.compute_result().executed_state.state_id,
+            ledger_info_placeholder(vote_info.potential_commit_id()),
+            signer,
+        ))
+    }
+}
+
+/// The safety rules as seen by the event processor: either `SafetyRules` running within
+/// consensus, or a client of the safety rules service running in a separate process.
+pub trait TSafetyRules<T>: Send {
+    /// The up-to-date state of consensus (for monitoring / debugging purposes).
+    fn consensus_state(&self) -> ConsensusState;
+
+    /// Learn about a new quorum certificate, which might update the preferred block.
+    fn update(&mut self, qc: &QuorumCert) -> failure::Result<()>;
+
+    /// Increases the last vote round and persists the new state, ignored if the round is not
+    /// higher than the last vote round.
+    fn increase_last_vote_round(&mut self, round: Round) -> failure::Result<()>;
+
+    /// Applies the voting rules to the executed proposal and, if they pass, persists the updated
+    /// state and returns the signed vote.
+    fn construct_and_sign_vote(
+        &mut self,
+        author: Author,
+        proposed_block: &ExecutedBlock<T>,
+        ledger_info_placeholder: &dyn Fn(Option<HashValue>) -> LedgerInfo,
+        signer: &ValidatorSigner,
+    ) -> failure::Result<VoteMsg>;
+}
+
+impl<T: Payload> TSafetyRules<T> for SafetyRules {
+    fn consensus_state(&self) -> ConsensusState {
+        SafetyRules::consensus_state(self)
+    }
+
+    fn update(&mut self, qc: &QuorumCert) -> failure::Result<()> {
+        SafetyRules::update(self, qc);
+        Ok(())
+    }
+
+    fn increase_last_vote_round(&mut self, round: Round) -> failure::Result<()> {
+        SafetyRules::increase_last_vote_round(self, round)
+    }
+
+    fn construct_and_sign_vote(
+        &mut self,
+        author: Author,
+        proposed_block: &ExecutedBlock<T>,
+        ledger_info_placeholder: &dyn Fn(Option<HashValue>) -> LedgerInfo,
+        signer: &ValidatorSigner,
+    ) -> failure::Result<VoteMsg> {
+        SafetyRules::construct_and_sign_vote(
+            self,
+            author,
+            proposed_block,
+            ledger_info_placeholder,
+            signer,
+        )
+    }
 }
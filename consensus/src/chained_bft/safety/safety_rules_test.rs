@@ -8,10 +8,14 @@ use crate::chained_bft::{
         block::{block_test, ExecutedBlock},
         quorum_cert::QuorumCert,
     },
+    persistent_storage::PersistentStorage,
     safety::safety_rules::{ConsensusState, ProposalReject, SafetyRules},
-    test_utils::{build_empty_tree, build_empty_tree_with_custom_signing, TreeInserter},
+    test_utils::{
+        build_empty_tree, build_empty_tree_with_custom_signing, MockStorage, TreeInserter,
+    },
 };
 use cached::{cached_key, SizedCache};
+use config::config::CommitRule;
 use crypto::HashValue;
 use proptest::prelude::*;
 use std::{
@@ -408,3 +412,140 @@ fn test_commit_rule_consecutive_rounds() {
         Some(a2.id())
     );
 }
+
+#[test]
+fn test_two_chain_commit_rule() {
+    let block_tree = build_empty_tree();
+    let mut inserter = TreeInserter::new(block_tree.clone());
+    let safety_rules =
+        SafetyRules::new(ConsensusState::default()).with_commit_rule(CommitRule::TwoChain);
+
+    // build a tree of the following form:
+    //             ___________
+    //            /           \
+    // genesis---a1  b1---b2   a2---a3---a4
+    //         \_____/
+    //
+    // a1 cannot be committed after a2 gathers QC because a1 and a2 are not consecutive
+    // a2 can be committed after a3 gathers QC, a3 after a4 gathers QC
+
+    let genesis = block_tree.root();
+    let a1 = inserter.insert_block_with_qc(QuorumCert::certificate_for_genesis(), &genesis, 1);
+    let b1 = inserter.insert_block_with_qc(QuorumCert::certificate_for_genesis(), &genesis, 2);
+    let b2 = inserter.insert_block(&b1, 3);
+    let a2 = inserter.insert_block(&a1, 4);
+    let a3 = inserter.insert_block(&a2, 5);
+    let a4 = inserter.insert_block(&a3, 6);
+
+    assert_eq!(
+        safety_rules.commit_rule_for_certified_block(a1.quorum_cert(), a1.round()),
+        Some(genesis.id())
+    );
+    assert_eq!(
+        safety_rules.commit_rule_for_certified_block(b1.quorum_cert(), b1.round()),
+        None
+    );
+    assert_eq!(
+        safety_rules.commit_rule_for_certified_block(b2.quorum_cert(), b2.round()),
+        Some(b1.id())
+    );
+    assert_eq!(
+        safety_rules.commit_rule_for_certified_block(a2.quorum_cert(), a2.round()),
+        None
+    );
+    assert_eq!(
+        safety_rules.commit_rule_for_certified_block(a3.quorum_cert(), a3.round()),
+        Some(a2.id())
+    );
+    assert_eq!(
+        safety_rules.commit_rule_for_certified_block(a4.quorum_cert(), a4.round()),
+        Some(a3.id())
+    );
+}
+
+#[test]
+fn test_two_chain_locks_highest_certified_block() {
+    let block_tree = build_empty_tree();
+    let mut inserter = TreeInserter::new(block_tree.clone());
+    let mut safety_rules =
+        SafetyRules::new(ConsensusState::default()).with_commit_rule(CommitRule::TwoChain);
+
+    // genesis---a1---a2---a3
+    //       \
+    //        ----b1
+    let genesis = block_tree.root();
+    let a1 = inserter.insert_block_with_qc(QuorumCert::certificate_for_genesis(), &genesis, 1);
+    let a2 = inserter.insert_block(&a1, 2);
+    let a3 = inserter.insert_block(&a2, 3);
+    let b1 = inserter.insert_block_with_qc(QuorumCert::certificate_for_genesis(), &genesis, 4);
+
+    // The QC of a3 certifies a2, which becomes the preferred block.
+    safety_rules.update(a3.quorum_cert());
+    assert_eq!(
+        safety_rules.consensus_state().preferred_block_round(),
+        a2.round()
+    );
+    assert_eq!(
+        safety_rules.voting_rule(b1.block()),
+        Err(ProposalReject::ProposalRoundLowerThenPreferredBlock {
+            preferred_block_round: a2.round(),
+        })
+    );
+    assert!(safety_rules.voting_rule(a3.block()).is_ok());
+}
+
+#[test]
+fn test_construct_and_sign_vote_persists_state() {
+    let block_tree = build_empty_tree();
+    let mut inserter = TreeInserter::new(block_tree.clone());
+    let (storage, _) = MockStorage::<Vec<usize>>::start_for_testing();
+    let mut safety_rules = SafetyRules::new_with_storage(
+        ConsensusState::default(),
+        storage.persistent_safety_storage(),
+    );
+    let persisted_state = || {
+        storage
+            .persistent_safety_storage()
+            .consensus_state()
+            .unwrap()
+    };
+
+    let genesis = block_tree.root();
+    let a1 = inserter.insert_block_with_qc(QuorumCert::certificate_for_genesis(), &genesis, 1);
+    let a2 = inserter.insert_block(&a1, 2);
+    let signer = block_tree.signer();
+    let ledger_info_placeholder = |id| block_tree.ledger_info_placeholder(id, 0);
+
+    let vote = safety_rules
+        .construct_and_sign_vote(
+            signer.author(),
+            a2.as_ref(),
+            ledger_info_placeholder,
+            signer,
+        )
+        .unwrap();
+    assert_eq!(vote.author(), signer.author());
+    assert_eq!(vote.vote_data().block_id(), a2.id());
+    assert_eq!(vote.vote_data().block_round(), 2);
+    assert_eq!(vote.vote_data().parent_block_id(), a1.id());
+    assert_eq!(persisted_state(), ConsensusState::new(2, 0));
+
+    // A rejected proposal does not change the persisted state.
+    assert!(safety_rules
+        .construct_and_sign_vote(
+            signer.author(),
+            a1.as_ref(),
+            ledger_info_placeholder,
+            signer
+        )
+        .is_err());
+    assert_eq!(persisted_state(), ConsensusState::new(2, 0));
+
+    safety_rules.increase_last_vote_round(5).unwrap();
+    assert_eq!(persisted_state(), ConsensusState::new(5, 0));
+
+    // A new instance recovers the persisted state.
+    let safety_rules =
+        SafetyRules::new_with_storage(persisted_state(), storage.persistent_safety_storage());
+    assert_eq!(safety_rules.consensus_state(), ConsensusState::new(5, 0));
+}
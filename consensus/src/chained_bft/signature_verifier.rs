@@ -0,0 +1,106 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    chained_bft::consensus_types::vote_msg::{verify_votes, VoteMsg, VoteMsgVerificationError},
+    counters,
+};
+use crypto::{hash::CryptoHash, HashValue};
+use futures::channel::oneshot;
+use rayon::{prelude::*, ThreadPool, ThreadPoolBuilder};
+use std::{collections::HashMap, sync::Arc, time::Instant};
+use types::crypto_proxies::ValidatorVerifier;
+
+#[cfg(test)]
+#[path = "signature_verifier_test.rs"]
+mod signature_verifier_test;
+
+/// Verifies the signatures of the consensus messages on a dedicated thread pool, such that the
+/// verification holds up neither the network task nor the event loop. Cloning a verifier shares
+/// its pool.
+#[derive(Clone)]
+pub struct SignatureVerifier {
+    pool: Arc<ThreadPool>,
+}
+
+impl SignatureVerifier {
+    /// Creates a pool of the given number of threads, 0 for one thread per CPU.
+    pub fn new(num_threads: usize) -> Self {
+        let pool = ThreadPoolBuilder::new()
+            .num_threads(num_threads)
+            .thread_name(|index| format!("consensus-verifier-{}", index))
+            .build()
+            .expect("[consensus] Failed to create the signature verification pool");
+        Self {
+            pool: Arc::new(pool),
+        }
+    }
+
+    /// Runs the given verification on the pool and returns its result.
+    pub async fn run<F, R>(&self, verification: F) -> R
+    where
+        F: FnOnce() -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        let (result_tx, result_rx) = oneshot::channel();
+        self.pool.spawn(move || {
+            // The receiver is only dropped if the caller gave up on the result.
+            let _ = result_tx.send(verification());
+        });
+        result_rx
+            .await
+            .expect("[consensus] The signature verification panicked")
+    }
+
+    /// Verifies the given votes on the pool (see `verify_vote_batch`).
+    pub async fn verify_votes(
+        &self,
+        validator: Arc<ValidatorVerifier>,
+        votes: Vec<VoteMsg>,
+    ) -> Vec<(VoteMsg, Result<(), VoteMsgVerificationError>)> {
+        // The parallel iterators of a task running on the pool use the threads of the pool.
+        self.run(move || verify_vote_batch(validator.as_ref(), votes))
+            .await
+    }
+}
+
+/// Verifies the given votes, in parallel when run on a rayon pool: the votes are grouped by the
+/// ledger info they sign and the signatures of every group are verified in batch. The votes of a
+/// group that fails the batch verification are verified one by one to sort the invalid votes out.
+/// Returns every vote, in the given order, with the result of its verification.
+pub fn verify_vote_batch(
+    validator: &ValidatorVerifier,
+    votes: Vec<VoteMsg>,
+) -> Vec<(VoteMsg, Result<(), VoteMsgVerificationError>)> {
+    let start = Instant::now();
+    counters::VOTE_VERIFICATION_BATCH_SIZE.observe(votes.len() as f64);
+    let mut digest_to_indices: HashMap<HashValue, Vec<usize>> = HashMap::new();
+    for (index, vote) in votes.iter().enumerate() {
+        digest_to_indices
+            .entry(vote.ledger_info().hash())
+            .or_insert_with(Vec::new)
+            .push(index);
+    }
+    let mut results: Vec<(usize, Result<(), VoteMsgVerificationError>)> = digest_to_indices
+        .into_par_iter()
+        .flat_map(|(_, indices)| {
+            if verify_votes(indices.iter().map(|index| &votes[*index]), validator).is_ok() {
+                indices
+                    .into_iter()
+                    .map(|index| (index, Ok(())))
+                    .collect::<Vec<_>>()
+            } else {
+                indices
+                    .into_iter()
+                    .map(|index| (index, votes[index].verify(validator)))
+                    .collect::<Vec<_>>()
+            }
+        })
+        .collect();
+    results.sort_by_key(|(index, _)| *index);
+    counters::VOTE_VERIFICATION_DURATION_S.observe_duration(start.elapsed());
+    votes
+        .into_iter()
+        .zip(results.into_iter().map(|(_, result)| result))
+        .collect()
+}
@@ -0,0 +1,128 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::chained_bft::{
+    consensus_types::{
+        vote_data::VoteData,
+        vote_msg::{verify_votes, VoteMsg},
+    },
+    signature_verifier::{verify_vote_batch, SignatureVerifier},
+    test_utils::placeholder_ledger_info,
+};
+use crypto::HashValue;
+use futures::executor::block_on;
+use std::sync::Arc;
+use types::crypto_proxies::{ValidatorSigner, ValidatorVerifier};
+
+fn random_vote_data() -> VoteData {
+    VoteData::new(
+        HashValue::random(),
+        HashValue::random(),
+        1,
+        HashValue::random(),
+        0,
+        HashValue::random(),
+        0,
+    )
+}
+
+fn signers_and_validator(num_signers: usize) -> (Vec<ValidatorSigner>, ValidatorVerifier) {
+    let signers: Vec<_> = (0..num_signers)
+        .map(|i| ValidatorSigner::random([i as u8; 32]))
+        .collect();
+    let validator = ValidatorVerifier::new(
+        signers
+            .iter()
+            .map(|signer| (signer.author(), signer.public_key()))
+            .collect(),
+    );
+    (signers, validator)
+}
+
+#[test]
+fn test_verify_votes() {
+    let (signers, validator) = signers_and_validator(4);
+    let vote_data = random_vote_data();
+    let votes: Vec<_> = signers
+        .iter()
+        .map(|signer| {
+            VoteMsg::new(
+                vote_data.clone(),
+                signer.author(),
+                placeholder_ledger_info(),
+                signer,
+            )
+        })
+        .collect();
+    assert!(verify_votes(&votes, &validator).is_ok());
+
+    // The same author voting twice for the same ledger info is verified twice.
+    let duplicate = VoteMsg::new(
+        vote_data.clone(),
+        signers[1].author(),
+        placeholder_ledger_info(),
+        &signers[0],
+    );
+    assert!(verify_votes(votes.iter().chain(Some(&duplicate)), &validator).is_err());
+}
+
+#[test]
+fn test_verify_vote_batch() {
+    let (signers, validator) = signers_and_validator(4);
+    let vote_data = [random_vote_data(), random_vote_data()];
+    let mut votes = vec![];
+    let mut expected = vec![];
+    for (i, signer) in signers.iter().enumerate() {
+        for data in vote_data.iter() {
+            votes.push(VoteMsg::new(
+                data.clone(),
+                signer.author(),
+                placeholder_ledger_info(),
+                signer,
+            ));
+            expected.push(true);
+        }
+        // Every other signer forges a vote of the next one for the first block.
+        if i % 2 == 0 {
+            votes.push(VoteMsg::new(
+                vote_data[0].clone(),
+                signers[i + 1].author(),
+                placeholder_ledger_info(),
+                signer,
+            ));
+            expected.push(false);
+        }
+    }
+
+    let results = verify_vote_batch(&validator, votes.clone());
+    assert_eq!(results.len(), votes.len());
+    for ((vote, result), (expected_vote, valid)) in
+        results.iter().zip(votes.iter().zip(expected.iter()))
+    {
+        assert_eq!(vote, expected_vote);
+        assert_eq!(result.is_ok(), *valid);
+    }
+
+    // The votes are verified the same way on the verifier.
+    let verifier = SignatureVerifier::new(2);
+    let pool_results = block_on(verifier.verify_votes(Arc::new(validator), votes));
+    assert_eq!(
+        pool_results
+            .iter()
+            .map(|(_, result)| result.is_ok())
+            .collect::<Vec<_>>(),
+        expected
+    );
+}
+
+#[test]
+fn test_run_on_verifier() {
+    let verifier = SignatureVerifier::new(1);
+    let thread_name = block_on(verifier.run(|| {
+        std::thread::current()
+            .name()
+            .map(ToString::to_string)
+            .unwrap()
+    }));
+    assert_eq!(thread_name, "consensus-verifier-0");
+}
@@ -0,0 +1,140 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Export and import of the consensus data of a node.
+//!
+//! A `ConsensusSnapshot` holds the block tree persisted by consensus (from which the root is
+//! recovered), the safety state of the node (its last vote) and its highest timeout
+//! certificates. The snapshot exported from the storage of a validator can be imported into the
+//! storage of a fresh node, e.g., to migrate the validator to new hardware: the node starts from
+//! the root of the snapshot and synchronizes its ledger up to it instead of replaying the blocks
+//! from genesis.
+
+use crate::chained_bft::{
+    common::Payload,
+    consensus_types::{block::Block, quorum_cert::QuorumCert},
+    consensusdb::{ConsensusDB, SingleEntryKey},
+    liveness::pacemaker_timeout_manager::HighestTimeoutCertificates,
+    persistent_storage::{RecoveryData, StorageBackend},
+    safety::safety_rules::ConsensusState,
+};
+use failure::prelude::*;
+use rmp_serde::{from_slice, to_vec_named};
+use serde::{Deserialize, Serialize};
+use std::{fs, path::Path};
+
+#[cfg(test)]
+#[path = "snapshot_test.rs"]
+mod snapshot_test;
+
+/// A portable snapshot of the consensus data persisted by a node.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ConsensusSnapshot<T> {
+    blocks: Vec<Block<T>>,
+    quorum_certs: Vec<QuorumCert>,
+    consensus_state: ConsensusState,
+    highest_timeout_certificates: HighestTimeoutCertificates,
+}
+
+impl<T: Payload> ConsensusSnapshot<T> {
+    /// Exports the content of the ConsensusDB located in the given storage directory (the
+    /// `storage.dir` of the node config). The node must not be running: the DB can't be shared
+    /// with a live process.
+    pub fn from_storage_dir(storage_dir: &Path) -> Result<Self> {
+        let db_path = storage_dir.join("consensusdb");
+        // Opening a DB that doesn't exist would create a new empty one.
+        ensure!(db_path.is_dir(), "No ConsensusDB found at {:?}", db_path);
+        Self::export(&ConsensusDB::new(storage_dir))
+    }
+
+    /// Imports the snapshot into the ConsensusDB located in the given storage directory, which
+    /// must not hold any consensus data: the node recovers from the snapshot when it starts.
+    pub fn import_to_storage_dir(self, storage_dir: &Path) -> Result<()> {
+        self.import(&ConsensusDB::new(storage_dir))
+    }
+
+    pub(crate) fn export(backend: &dyn StorageBackend<T>) -> Result<Self> {
+        let (blocks, quorum_certs) = backend.get_blocks_and_quorum_certs()?;
+        let consensus_state = backend
+            .get_single_entry(SingleEntryKey::ConsensusState)?
+            .map(|s| from_slice(&s[..]))
+            .transpose()
+            .context("Failed to deserialize the consensus state")?
+            .unwrap_or_default();
+        let highest_timeout_certificates = backend
+            .get_single_entry(SingleEntryKey::HighestTimeoutCertificates)?
+            .map(|s| from_slice(&s[..]))
+            .transpose()
+            .context("Failed to deserialize the highest timeout certificates")?
+            .unwrap_or_default();
+        let snapshot = Self {
+            blocks,
+            quorum_certs,
+            consensus_state,
+            highest_timeout_certificates,
+        };
+        snapshot.verify()?;
+        Ok(snapshot)
+    }
+
+    pub(crate) fn import(self, backend: &dyn StorageBackend<T>) -> Result<()> {
+        self.verify()?;
+        let (blocks, quorum_certs) = backend.get_blocks_and_quorum_certs()?;
+        ensure!(
+            blocks.is_empty() && quorum_certs.is_empty(),
+            "Can't import a snapshot into a consensus storage holding {} blocks",
+            blocks.len()
+        );
+        // The block tree is saved last: the import can be retried as long as it isn't saved.
+        backend.save_single_entry(
+            SingleEntryKey::ConsensusState,
+            to_vec_named(&self.consensus_state)?,
+        )?;
+        backend.save_single_entry(
+            SingleEntryKey::HighestTimeoutCertificates,
+            to_vec_named(&self.highest_timeout_certificates)?,
+        )?;
+        backend.save_blocks_and_quorum_certs(self.blocks, self.quorum_certs)
+    }
+
+    /// Writes the snapshot to the given file.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        fs::write(path, to_vec_named(self)?)
+            .with_context(|_| format!("Failed to write the snapshot to {:?}", path))?;
+        Ok(())
+    }
+
+    /// Reads a snapshot written by `save`.
+    pub fn load(path: &Path) -> Result<Self> {
+        let bytes =
+            fs::read(path).with_context(|_| format!("Failed to read the snapshot {:?}", path))?;
+        Ok(from_slice(&bytes[..])?)
+    }
+
+    /// The number of blocks of the snapshot, including its root.
+    pub fn num_blocks(&self) -> usize {
+        self.blocks.len()
+    }
+
+    // A fresh node recovers from the snapshot with the ledger info of genesis: the root of the
+    // snapshot must be committed by one of its QCs.
+    fn verify(&self) -> Result<()> {
+        RecoveryData::new(
+            self.blocks.clone(),
+            self.quorum_certs.clone(),
+            QuorumCert::certificate_for_genesis()
+                .ledger_info()
+                .ledger_info(),
+            self.highest_timeout_certificates.clone(),
+            vec![],
+            None,
+            None,
+            vec![],
+            vec![],
+            vec![],
+            None,
+        )
+        .context("Inconsistent consensus snapshot")?;
+        Ok(())
+    }
+}
@@ -0,0 +1,117 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::chained_bft::{
+    block_storage::BlockReader,
+    consensus_types::quorum_cert::QuorumCert,
+    consensusdb::SingleEntryKey,
+    persistent_storage::{
+        InMemoryStorageBackend, PersistentStorage, StorageBackend, StorageWriteProxy,
+    },
+    safety::safety_rules::ConsensusState,
+    snapshot::ConsensusSnapshot,
+    test_utils::{build_empty_tree, TestPayload, TreeInserter},
+};
+use std::sync::Arc;
+use tools::tempdir::TempPath;
+use types::ledger_info::LedgerInfo;
+
+fn genesis_ledger_info() -> LedgerInfo {
+    QuorumCert::certificate_for_genesis()
+        .ledger_info()
+        .ledger_info()
+        .clone()
+}
+
+// Persists two blocks on top of genesis and the state of a vote for the second one.
+fn populated_backend() -> Arc<InMemoryStorageBackend<TestPayload>> {
+    let backend = Arc::new(InMemoryStorageBackend::default());
+    let (storage, _) = StorageWriteProxy::start_with_backend(
+        Arc::clone(&backend) as Arc<dyn StorageBackend<TestPayload>>,
+        &genesis_ledger_info(),
+    );
+    let block_tree = build_empty_tree();
+    let mut inserter = TreeInserter::new(block_tree.clone());
+    let a1 =
+        inserter.insert_block_with_qc(QuorumCert::certificate_for_genesis(), &block_tree.root(), 1);
+    let a2 = inserter.insert_block(&a1, 2);
+    storage
+        .save_tree(
+            vec![a1.block().clone(), a2.block().clone()],
+            vec![a2.quorum_cert().clone()],
+        )
+        .unwrap();
+    storage
+        .persistent_safety_storage()
+        .set_consensus_state(&ConsensusState::new(2, 0))
+        .unwrap();
+    backend
+}
+
+#[test]
+fn test_import_into_fresh_node() {
+    let snapshot = ConsensusSnapshot::export(populated_backend().as_ref()).unwrap();
+    assert_eq!(snapshot.num_blocks(), 3);
+    let tmp_dir = TempPath::new();
+    tmp_dir.create_as_dir().unwrap();
+    let path = tmp_dir.path().join("snapshot");
+    snapshot.save(&path).unwrap();
+
+    let backend = Arc::new(InMemoryStorageBackend::<TestPayload>::default());
+    ConsensusSnapshot::load(&path)
+        .unwrap()
+        .import(backend.as_ref())
+        .unwrap();
+    let (storage, initial_data) =
+        StorageWriteProxy::start_with_backend(backend, &genesis_ledger_info());
+    let (root, blocks, quorum_certs) = initial_data.take();
+    assert_eq!(root.0.round(), 0);
+    assert_eq!(
+        blocks.iter().map(|block| block.round()).collect::<Vec<_>>(),
+        vec![1, 2]
+    );
+    assert_eq!(quorum_certs.len(), 2);
+    assert_eq!(
+        storage
+            .persistent_safety_storage()
+            .consensus_state()
+            .unwrap(),
+        ConsensusState::new(2, 0)
+    );
+}
+
+#[test]
+fn test_import_into_rocksdb() {
+    let snapshot = ConsensusSnapshot::export(populated_backend().as_ref()).unwrap();
+    let tmp_dir = TempPath::new();
+    snapshot.import_to_storage_dir(tmp_dir.path()).unwrap();
+    let exported = ConsensusSnapshot::<TestPayload>::from_storage_dir(tmp_dir.path()).unwrap();
+    assert_eq!(exported.num_blocks(), 3);
+    assert_eq!(exported.consensus_state, ConsensusState::new(2, 0));
+}
+
+#[test]
+fn test_import_into_used_storage() {
+    let backend = populated_backend();
+    let snapshot = ConsensusSnapshot::export(backend.as_ref()).unwrap();
+    assert!(snapshot.import(backend.as_ref()).is_err());
+}
+
+#[test]
+fn test_inconsistent_snapshot() {
+    let mut snapshot = ConsensusSnapshot::export(populated_backend().as_ref()).unwrap();
+    // Without the QCs the root can't be found.
+    snapshot.quorum_certs.clear();
+    let backend = InMemoryStorageBackend::<TestPayload>::default();
+    assert!(snapshot.import(&backend).is_err());
+    assert!(backend
+        .get_single_entry(SingleEntryKey::ConsensusState)
+        .unwrap()
+        .is_none());
+}
+
+#[test]
+fn test_export_missing_db() {
+    let tmp_dir = TempPath::new();
+    assert!(ConsensusSnapshot::<TestPayload>::from_storage_dir(tmp_dir.path()).is_err());
+}
@@ -0,0 +1,285 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Offline inspection of the consensus persistent storage of a node.
+//!
+//! `ConsensusStateDump` reads the ConsensusDB of a node that is not running (e.g., a stuck
+//! validator pulled from a swarm or from production) and represents the block tree, the QCs,
+//! the safety state, the pending votes and the highest timeout certificate in a readable form.
+//! The dump can be printed as text or serialized to JSON.
+
+use crate::chained_bft::{
+    common::{Payload, Round},
+    consensus_types::{block::Block, quorum_cert::QuorumCert, vote_msg::VoteMsg},
+    consensusdb::ConsensusDB,
+    liveness::pacemaker_timeout_manager::HighestTimeoutCertificates,
+    safety::safety_rules::ConsensusState,
+};
+use crypto::HashValue;
+use failure::prelude::*;
+use rmp_serde::from_slice;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashSet,
+    fmt::{Display, Formatter},
+    path::Path,
+};
+
+#[cfg(test)]
+#[path = "state_dump_test.rs"]
+mod state_dump_test;
+
+/// A block of the persisted block tree.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct BlockSummary {
+    /// The id of the block.
+    pub id: String,
+    /// The id of the parent block.
+    pub parent_id: String,
+    /// The round of the block.
+    pub round: Round,
+    /// The height of the block.
+    pub height: u64,
+    /// The author of the block (None for the genesis and NIL blocks).
+    pub author: Option<String>,
+    /// The timestamp of the block.
+    pub timestamp_usecs: u64,
+    /// True for the NIL blocks.
+    pub is_nil: bool,
+    /// True in case a QC certifying the block is persisted as well.
+    pub certified: bool,
+}
+
+/// A persisted quorum certificate.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct QuorumCertSummary {
+    /// The id of the certified block.
+    pub certified_block_id: String,
+    /// The round of the certified block.
+    pub certified_block_round: Round,
+    /// The id of the block committed by the QC, if any.
+    pub committed_block_id: Option<String>,
+    /// The authors of the signatures of the QC.
+    pub signers: Vec<String>,
+}
+
+/// A persisted vote that hasn't formed a QC yet.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct VoteSummary {
+    /// The author of the vote.
+    pub author: String,
+    /// The id of the voted block.
+    pub block_id: String,
+    /// The round of the voted block.
+    pub round: Round,
+}
+
+/// The highest persisted timeout certificate.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct TimeoutCertificateSummary {
+    /// The round of the timeout certificate.
+    pub round: Round,
+    /// The authors of the timeouts forming the certificate.
+    pub authors: Vec<String>,
+}
+
+/// A readable snapshot of the consensus persistent storage of a node.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ConsensusStateDump {
+    /// The round of the last vote of the node (None if the safety state was never persisted).
+    pub last_vote_round: Option<Round>,
+    /// The preferred block round of the node (None if the safety state was never persisted).
+    pub preferred_block_round: Option<Round>,
+    /// The highest timeout certificate known to the node.
+    pub highest_timeout_certificate: Option<TimeoutCertificateSummary>,
+    /// The votes collected by the node that haven't formed a QC yet.
+    pub pending_votes: Vec<VoteSummary>,
+    /// The persisted blocks ordered by their rounds.
+    pub blocks: Vec<BlockSummary>,
+    /// The persisted QCs ordered by the rounds of the certified blocks.
+    pub quorum_certs: Vec<QuorumCertSummary>,
+}
+
+impl ConsensusStateDump {
+    /// Opens the ConsensusDB located in the given storage directory (the `storage.dir` of the
+    /// node config) and dumps its content. The node must not be running: the DB can't be shared
+    /// with a live process.
+    pub fn from_storage_dir<T: Payload>(storage_dir: &Path) -> Result<Self> {
+        let db_path = storage_dir.join("consensusdb");
+        // Opening a DB that doesn't exist would create a new empty one.
+        ensure!(db_path.is_dir(), "No ConsensusDB found at {:?}", db_path);
+        Self::from_db::<T>(&ConsensusDB::new(storage_dir))
+    }
+
+    pub(crate) fn from_db<T: Payload>(db: &ConsensusDB) -> Result<Self> {
+        let (state, highest_timeout_certificates, pending_votes, blocks, quorum_certs) =
+            db.get_data::<T>()?;
+        let state: Option<ConsensusState> = state
+            .map(|s| from_slice(&s[..]))
+            .transpose()
+            .context("Failed to deserialize the consensus state")?;
+        let highest_timeout_certificates: Option<HighestTimeoutCertificates> =
+            highest_timeout_certificates
+                .map(|s| from_slice(&s[..]))
+                .transpose()
+                .context("Failed to deserialize the highest timeout certificates")?;
+        let pending_votes: Vec<VoteMsg> = pending_votes
+            .map(|s| from_slice(&s[..]))
+            .transpose()
+            .context("Failed to deserialize the pending votes")?
+            .unwrap_or_default();
+        Ok(Self::new(
+            state,
+            highest_timeout_certificates,
+            &pending_votes,
+            &blocks,
+            &quorum_certs,
+        ))
+    }
+
+    fn new<T: Payload>(
+        state: Option<ConsensusState>,
+        highest_timeout_certificates: Option<HighestTimeoutCertificates>,
+        pending_votes: &[VoteMsg],
+        blocks: &[Block<T>],
+        quorum_certs: &[QuorumCert],
+    ) -> Self {
+        let certified: HashSet<HashValue> = quorum_certs
+            .iter()
+            .map(QuorumCert::certified_block_id)
+            .collect();
+        let mut blocks: Vec<_> = blocks
+            .iter()
+            .map(|block| BlockSummary {
+                id: format!("{:x}", block.id()),
+                parent_id: format!("{:x}", block.parent_id()),
+                round: block.round(),
+                height: block.height(),
+                author: block.author().map(|author| format!("{:x}", author)),
+                timestamp_usecs: block.timestamp_usecs(),
+                is_nil: block.is_nil_block(),
+                certified: certified.contains(&block.id()),
+            })
+            .collect();
+        blocks.sort_by(|a, b| (a.round, &a.id).cmp(&(b.round, &b.id)));
+        let mut quorum_certs: Vec<_> = quorum_certs
+            .iter()
+            .map(|qc| {
+                let mut signers: Vec<_> = qc
+                    .ledger_info()
+                    .signatures()
+                    .keys()
+                    .map(|author| format!("{:x}", author))
+                    .collect();
+                signers.sort();
+                QuorumCertSummary {
+                    certified_block_id: format!("{:x}", qc.certified_block_id()),
+                    certified_block_round: qc.certified_block_round(),
+                    committed_block_id: qc.committed_block_id().map(|id| format!("{:x}", id)),
+                    signers,
+                }
+            })
+            .collect();
+        quorum_certs.sort_by(|a, b| {
+            (a.certified_block_round, &a.certified_block_id)
+                .cmp(&(b.certified_block_round, &b.certified_block_id))
+        });
+        let pending_votes = pending_votes
+            .iter()
+            .map(|vote| VoteSummary {
+                author: format!("{:x}", vote.author()),
+                block_id: format!("{:x}", vote.vote_data().block_id()),
+                round: vote.vote_data().block_round(),
+            })
+            .collect();
+        let highest_timeout_certificate = highest_timeout_certificates
+            .as_ref()
+            .and_then(HighestTimeoutCertificates::highest_timeout_certificate)
+            .map(|tc| TimeoutCertificateSummary {
+                round: tc.round(),
+                authors: tc
+                    .timeouts()
+                    .iter()
+                    .map(|timeout| format!("{:x}", timeout.author()))
+                    .collect(),
+            });
+        Self {
+            last_vote_round: state.as_ref().map(ConsensusState::last_vote_round),
+            preferred_block_round: state.as_ref().map(ConsensusState::preferred_block_round),
+            highest_timeout_certificate,
+            pending_votes,
+            blocks,
+            quorum_certs,
+        }
+    }
+
+    /// Serializes the dump to pretty-printed JSON.
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+}
+
+fn short(id: &str) -> &str {
+    &id[..id.len().min(8)]
+}
+
+fn round_or_none(round: Option<Round>) -> String {
+    round.map_or("None".to_string(), |round| round.to_string())
+}
+
+impl Display for ConsensusStateDump {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        writeln!(
+            f,
+            "ConsensusState: last_vote_round = {}, preferred_block_round = {}",
+            round_or_none(self.last_vote_round),
+            round_or_none(self.preferred_block_round)
+        )?;
+        match &self.highest_timeout_certificate {
+            Some(tc) => writeln!(
+                f,
+                "HighestTC: round {} with {} timeouts",
+                tc.round,
+                tc.authors.len()
+            )?,
+            None => writeln!(f, "HighestTC: None")?,
+        }
+        writeln!(f, "Blocks ({}):", self.blocks.len())?;
+        for block in &self.blocks {
+            writeln!(
+                f,
+                "\t[round {}, height {}] {} <- {}{}{}",
+                block.round,
+                block.height,
+                short(&block.parent_id),
+                short(&block.id),
+                if block.is_nil { " NIL" } else { "" },
+                if block.certified { " (certified)" } else { "" },
+            )?;
+        }
+        writeln!(f, "QuorumCerts ({}):", self.quorum_certs.len())?;
+        for qc in &self.quorum_certs {
+            writeln!(
+                f,
+                "\t[round {}] {} with {} signatures, commits {}",
+                qc.certified_block_round,
+                short(&qc.certified_block_id),
+                qc.signers.len(),
+                qc.committed_block_id
+                    .as_ref()
+                    .map_or("None", |id| short(id)),
+            )?;
+        }
+        writeln!(f, "PendingVotes ({}):", self.pending_votes.len())?;
+        for vote in &self.pending_votes {
+            writeln!(
+                f,
+                "\t[round {}] {} from {}",
+                vote.round,
+                short(&vote.block_id),
+                short(&vote.author)
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,85 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::chained_bft::{
+    consensus_types::{block::Block, quorum_cert::QuorumCert},
+    consensusdb::ConsensusDB,
+    safety::safety_rules::ConsensusState,
+    state_dump::ConsensusStateDump,
+    test_utils::{placeholder_certificate_for_block, TestPayload},
+};
+use rmp_serde::to_vec_named;
+use tools::tempdir::TempPath;
+use types::crypto_proxies::ValidatorSigner;
+
+#[test]
+fn test_dump_missing_db() {
+    let tmp_dir = TempPath::new();
+    assert!(ConsensusStateDump::from_storage_dir::<TestPayload>(tmp_dir.path()).is_err());
+}
+
+#[test]
+fn test_dump_consensus_db() {
+    let tmp_dir = TempPath::new();
+    let signer = ValidatorSigner::random(None);
+    {
+        let db = ConsensusDB::new(&tmp_dir);
+        let genesis = Block::<TestPayload>::make_genesis_block();
+        let b1 = Block::make_block(
+            &genesis,
+            vec![1],
+            1,
+            1,
+            QuorumCert::certificate_for_genesis(),
+            &signer,
+        );
+        let b1_qc = placeholder_certificate_for_block(
+            vec![&signer],
+            b1.id(),
+            1,
+            genesis.id(),
+            0,
+            genesis.id(),
+            0,
+        );
+        let b2 = Block::make_block(&b1, vec![2], 2, 2, b1_qc.clone(), &signer);
+        db.save_blocks_and_quorum_certificates(
+            vec![b2, genesis, b1],
+            vec![b1_qc, QuorumCert::certificate_for_genesis()],
+        )
+        .unwrap();
+        db.save_state(to_vec_named(&ConsensusState::new(2, 0)).unwrap())
+            .unwrap();
+    }
+
+    let dump = ConsensusStateDump::from_storage_dir::<TestPayload>(tmp_dir.path()).unwrap();
+    assert_eq!(dump.last_vote_round, Some(2));
+    assert_eq!(dump.preferred_block_round, Some(0));
+    assert!(dump.highest_timeout_certificate.is_none());
+    assert!(dump.pending_votes.is_empty());
+    assert_eq!(
+        dump.blocks.iter().map(|b| b.round).collect::<Vec<_>>(),
+        vec![0, 1, 2]
+    );
+    assert_eq!(
+        dump.blocks.iter().map(|b| b.certified).collect::<Vec<_>>(),
+        vec![true, true, false]
+    );
+    assert_eq!(
+        dump.blocks[2].author,
+        Some(format!("{:x}", signer.author()))
+    );
+    assert_eq!(dump.quorum_certs.len(), 2);
+    assert_eq!(dump.quorum_certs[1].certified_block_round, 1);
+    assert_eq!(
+        dump.quorum_certs[1].signers,
+        vec![format!("{:x}", signer.author())]
+    );
+
+    let json = dump.to_json().unwrap();
+    assert_eq!(
+        serde_json::from_str::<ConsensusStateDump>(&json).unwrap(),
+        dump
+    );
+    assert!(dump.to_string().contains("Blocks (3):"));
+}
@@ -3,14 +3,14 @@
 
 use crate::{
     chained_bft::{
-        block_storage::{BlockReader, BlockStore, NeedFetchResult},
+        block_storage::{AsyncBlockReader, BlockReader, BlockStore, NeedFetchResult},
         common::{Author, Payload},
         consensus_types::{
             block::{Block, ExecutedBlock},
             quorum_cert::QuorumCert,
             sync_info::SyncInfo,
         },
-        network::ConsensusNetworkImpl,
+        network::{ChunkedBlockRetrieval, ConsensusNetworkImpl},
         persistent_storage::PersistentStorage,
     },
     counters,
@@ -18,15 +18,45 @@ use crate::{
 };
 use failure;
 use logger::prelude::*;
-use network::proto::BlockRetrievalStatus;
-use rand::{prelude::*, Rng};
+use rand::prelude::*;
 use std::{
     clone::Clone,
+    collections::{HashMap, VecDeque},
     sync::Arc,
     time::{Duration, Instant},
 };
 use termion::color::*;
-use types::account_address::AccountAddress;
+
+/// Policy of the retrieval of missing blocks from the signers of a quorum certificate.
+#[derive(Clone, Debug)]
+pub struct BlockRetrievalPolicy {
+    /// Number of peers every chunk of blocks is requested from concurrently: the first valid
+    /// response is used.
+    pub fanout: usize,
+    /// Deadline of the first request to a peer, doubled on every following attempt.
+    pub initial_peer_timeout: Duration,
+    /// Upper bound of the deadline of a request to a peer.
+    pub max_peer_timeout: Duration,
+    /// Number of times a peer that failed to return the blocks is asked again, once all the other
+    /// peers have been tried.
+    pub max_retries_per_peer: u32,
+    /// Signers of the quorum certificate asked before the others (in this order), right after the
+    /// peer that delivered the quorum certificate.
+    pub preferred_peers: Vec<Author>,
+}
+
+impl Default for BlockRetrievalPolicy {
+    fn default() -> Self {
+        // The request timeout doubles from 1s up to 16s=1s*(2^RETRIEVAL_MAX_EXP)
+        Self {
+            fanout: 1,
+            initial_peer_timeout: Duration::from_secs(1),
+            max_peer_timeout: Duration::from_secs(16),
+            max_retries_per_peer: 0,
+            preferred_peers: vec![],
+        }
+    }
+}
 
 /// SyncManager is responsible for fetching dependencies and 'catching up' for given qc/ledger info
 pub struct SyncManager<T> {
@@ -34,6 +64,7 @@ pub struct SyncManager<T> {
     storage: Arc<dyn PersistentStorage<T>>,
     network: ConsensusNetworkImpl,
     state_computer: Arc<dyn StateComputer<Payload = T>>,
+    retrieval_policy: BlockRetrievalPolicy,
 }
 
 /// Keeps the necessary context for `SyncMgr` to bring the missing information.
@@ -66,7 +97,12 @@ where
         storage: Arc<dyn PersistentStorage<T>>,
         network: ConsensusNetworkImpl,
         state_computer: Arc<dyn StateComputer<Payload = T>>,
+        retrieval_policy: BlockRetrievalPolicy,
     ) -> SyncManager<T> {
+        assert!(
+            retrieval_policy.fanout > 0,
+            "block retrieval fanout can't be 0"
+        );
         // Our counters are initialized via lazy_static, so they're not going to appear in
         // Prometheus if some conditions never happen.  Invoking get() function enforces creation.
         counters::BLOCK_RETRIEVAL_COUNT.get();
@@ -76,6 +112,7 @@ where
             storage,
             network,
             state_computer,
+            retrieval_policy,
         }
     }
 
@@ -124,8 +161,9 @@ where
 
     /// Insert the quorum certificate separately from the block, used to split the processing of
     /// updating the consensus state(with qc) and deciding whether to vote(with block)
-    /// The missing ancestors are going to be retrieved from the given peer. If a given peer
-    /// fails to provide the missing ancestors, the qc is not going to be added.
+    /// The missing ancestors are going to be retrieved from the given peer first, then from the
+    /// other signers of the qc according to the retrieval policy. If none of them provides the
+    /// missing ancestors, the qc is not going to be added.
     pub async fn fetch_quorum_cert(
         &self,
         qc: QuorumCert,
@@ -138,12 +176,14 @@ where
             network,
             deadline,
             preferred_peer,
+            policy: self.retrieval_policy.clone(),
         };
         let mut retrieve_qc = qc.clone();
         loop {
             if self
                 .block_store
-                .block_exists(retrieve_qc.certified_block_id())
+                .block_exists_async(retrieve_qc.certified_block_id())
+                .await
             {
                 break;
             }
@@ -197,6 +237,7 @@ where
             network,
             deadline,
             preferred_peer: peer,
+            policy: self.retrieval_policy.clone(),
         };
         let mut blocks = retriever
             .retrieve_block_for_qc(&highest_ledger_info, 3)
@@ -235,21 +276,23 @@ struct BlockRetriever {
     network: ConsensusNetworkImpl,
     deadline: Instant,
     preferred_peer: Author,
+    policy: BlockRetrievalPolicy,
 }
 
 impl BlockRetriever {
     /// Retrieve chain of n blocks for given QC
     ///
     /// Returns Result with Vec that has a guaranteed size of num_blocks
-    /// This guarantee is based on BlockRetrievalResponse::verify that ensures that number of
-    /// blocks in response is equal to number of blocks requested.  This method will
-    /// continue until either the round deadline is reached or the quorum certificate members all
-    /// fail to return the missing chain.
+    /// The blocks are requested in chunks of at most BLOCK_RETRIEVAL_CHUNK_SIZE blocks: each
+    /// chunk is verified to continue the chain, and once all the chunks are received the Vec has
+    /// exactly num_blocks elements.  This method will continue until either the round deadline
+    /// is reached or the quorum certificate members all fail to return the missing chain.
     ///
-    /// The first attempt of block retrieval will always be sent to preferred_peer to allow the
-    /// leader to drive quorum certificate creation The other peers from the quorum certificate
-    /// will be randomly tried next.  If all members of the quorum certificate are exhausted, an
-    /// error is returned
+    /// Every attempt requests the missing chunks from `fanout` peers concurrently, see
+    /// `ordered_peers` for the order in which the peers are tried.  A failed attempt doesn't
+    /// discard the blocks retrieved so far: the next peers are asked for the rest of the chain
+    /// only.  The peers of a failed attempt are tried again at the end of the queue, up to
+    /// `max_retries_per_peer` times.  Once all the peers are exhausted, an error is returned
     pub async fn retrieve_block_for_qc<'a, T>(
         &'a mut self,
         qc: &'a QuorumCert,
@@ -259,95 +302,113 @@ impl BlockRetriever {
         T: Payload,
     {
         let block_id = qc.certified_block_id();
-        let mut peers: Vec<&AccountAddress> = qc.ledger_info().signatures().keys().collect();
+        let mut peers = self.ordered_peers(qc);
+        let mut retries: HashMap<Author, u32> = HashMap::new();
+        let mut retrieval = ChunkedBlockRetrieval::new(
+            self.network.clone(),
+            block_id,
+            num_blocks,
+            BLOCK_RETRIEVAL_CHUNK_SIZE,
+        );
+        let mut blocks = vec![];
         let mut attempt = 0_u32;
         loop {
             if peers.is_empty() {
                 bail!(
                     "Failed to fetch block {} in {} attempts: no more peers available",
-                    block_id,
+                    retrieval.next_block_id(),
                     attempt
                 );
             }
-            let peer = self.pick_peer(attempt, &mut peers);
+            let fanout = self.policy.fanout.min(peers.len());
+            let attempt_peers: Vec<Author> = peers.drain(..fanout).collect();
             attempt += 1;
 
-            let timeout = retrieval_timeout(&self.deadline, attempt);
+            let timeout = self.retrieval_timeout(attempt);
             let timeout = if let Some(timeout) = timeout {
                 timeout
             } else {
-                bail!("Failed to fetch block {} from {}, attempt {}: round deadline was reached, won't make more attempts", block_id, peer, attempt);
+                bail!("Failed to fetch block {} from {:?}, attempt {}: round deadline was reached, won't make more attempts", retrieval.next_block_id(), attempt_peers, attempt);
             };
             debug!(
-                "Fetching {} from {}, attempt {}",
-                block_id,
-                peer.short_str(),
+                "Fetching {} from {:?}, attempt {}",
+                retrieval.next_block_id(),
+                attempt_peers
+                    .iter()
+                    .map(|peer| peer.short_str())
+                    .collect::<Vec<_>>(),
                 attempt
             );
-            let response = self
-                .network
-                .request_block(block_id, num_blocks, peer, timeout)
-                .await;
-            let response = match response {
-                Err(e) => {
-                    warn!(
-                        "Failed to fetch block {} from {}: {:?}, trying another peer",
-                        block_id,
-                        peer.short_str(),
-                        e
-                    );
-                    continue;
+            loop {
+                match retrieval.next_block_from_any(&attempt_peers, timeout).await {
+                    Ok(Some(block)) => blocks.push(block),
+                    Ok(None) => return Ok(blocks),
+                    Err(e) => {
+                        warn!(
+                            "Failed to fetch block {}: {:?}, trying other peers",
+                            retrieval.next_block_id(),
+                            e
+                        );
+                        break;
+                    }
                 }
-                Ok(response) => response,
-            };
-            if response.status != BlockRetrievalStatus::SUCCEEDED {
-                warn!(
-                    "Failed to fetch block {} from {}: {:?}, trying another peer",
-                    block_id,
-                    peer.short_str(),
-                    response.status
-                );
-                continue;
             }
-            return Ok(response.blocks);
-        }
-    }
-
-    fn pick_peer(&self, attempt: u32, peers: &mut Vec<&AccountAddress>) -> AccountAddress {
-        assert!(!peers.is_empty(), "pick_peer on empty peer list");
-
-        if attempt == 0 {
-            // remove preferred_peer if its in list of peers
-            // (strictly speaking it is not required to be there)
-            for i in 0..peers.len() {
-                if *peers[i] == self.preferred_peer {
-                    peers.remove(i);
-                    break;
+            for peer in attempt_peers {
+                let peer_retries = retries.entry(peer).or_insert(0);
+                if *peer_retries < self.policy.max_retries_per_peer {
+                    *peer_retries += 1;
+                    peers.push_back(peer);
                 }
             }
-            return self.preferred_peer;
         }
+    }
 
-        let peer_idx = thread_rng().gen_range(0, peers.len());
-        *peers.remove(peer_idx)
+    /// The order in which the peers are asked for the blocks certified by the given QC: the
+    /// preferred peer first (this is typically the leader that drives the quorum certificate
+    /// creation), then the signers of the QC that are preferred by the policy, then the other
+    /// signers of the QC in random order.
+    fn ordered_peers(&self, qc: &QuorumCert) -> VecDeque<Author> {
+        let signatures = qc.ledger_info().signatures();
+        let mut others: Vec<Author> = signatures
+            .keys()
+            .filter(|peer| {
+                **peer != self.preferred_peer && !self.policy.preferred_peers.contains(*peer)
+            })
+            .cloned()
+            .collect();
+        others.shuffle(&mut thread_rng());
+        // The preferred peer is not required to be a signer of the QC.
+        let mut peers = VecDeque::new();
+        peers.push_back(self.preferred_peer);
+        peers.extend(
+            self.policy
+                .preferred_peers
+                .iter()
+                .filter(|peer| **peer != self.preferred_peer && signatures.contains_key(*peer)),
+        );
+        peers.extend(others);
+        peers
+    }
+
+    /// Returns exponentially increasing timeout with limit of the max peer timeout of the policy
+    fn retrieval_timeout(&self, attempt: u32) -> Option<Duration> {
+        assert!(attempt > 0, "retrieval_timeout attempt can't be 0");
+        let exp = RETRIEVAL_MAX_EXP.min(attempt - 1); // [0..RETRIEVAL_MAX_EXP]
+        let request_timeout =
+            (self.policy.initial_peer_timeout * 2_u32.pow(exp)).min(self.policy.max_peer_timeout);
+        let now = Instant::now();
+        let deadline_timeout = if self.deadline >= now {
+            Some(self.deadline.duration_since(now))
+        } else {
+            None
+        };
+        deadline_timeout.map(|delay| request_timeout.min(delay))
     }
 }
 
-// Max timeout is 16s=RETRIEVAL_INITIAL_TIMEOUT*(2^RETRIEVAL_MAX_EXP)
-const RETRIEVAL_INITIAL_TIMEOUT: Duration = Duration::from_secs(1);
-const RETRIEVAL_MAX_EXP: u32 = 4;
+/// The max number of blocks requested from a peer at once, larger chains are requested in
+/// multiple chunks.
+const BLOCK_RETRIEVAL_CHUNK_SIZE: u64 = 10;
 
-/// Returns exponentially increasing timeout with
-/// limit of RETRIEVAL_INITIAL_TIMEOUT*(2^RETRIEVAL_MAX_EXP)
-fn retrieval_timeout(deadline: &Instant, attempt: u32) -> Option<Duration> {
-    assert!(attempt > 0, "retrieval_timeout attempt can't be 0");
-    let exp = RETRIEVAL_MAX_EXP.min(attempt - 1); // [0..RETRIEVAL_MAX_EXP]
-    let request_timeout = RETRIEVAL_INITIAL_TIMEOUT * 2_u32.pow(exp);
-    let now = Instant::now();
-    let deadline_timeout = if *deadline >= now {
-        Some(deadline.duration_since(now))
-    } else {
-        None
-    };
-    deadline_timeout.map(|delay| request_timeout.min(delay))
-}
+// The request timeout stops doubling after RETRIEVAL_MAX_EXP attempts.
+const RETRIEVAL_MAX_EXP: u32 = 4;
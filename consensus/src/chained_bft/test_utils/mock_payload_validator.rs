@@ -0,0 +1,51 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{chained_bft::test_utils::TestPayload, state_replication::PayloadValidator};
+use failure::prelude::*;
+use std::{
+    collections::HashSet,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// Rejects the payloads carrying duplicate transactions or more than the given number of
+/// transactions, and counts the payloads it checked.
+pub struct MockPayloadValidator {
+    max_txns: usize,
+    num_validated: AtomicUsize,
+}
+
+impl MockPayloadValidator {
+    pub fn new(max_txns: usize) -> Self {
+        Self {
+            max_txns,
+            num_validated: AtomicUsize::new(0),
+        }
+    }
+
+    /// The number of payloads checked so far, accepted or not.
+    pub fn num_validated(&self) -> usize {
+        self.num_validated.load(Ordering::SeqCst)
+    }
+}
+
+impl PayloadValidator for MockPayloadValidator {
+    type Payload = TestPayload;
+
+    fn validate(&self, payload: &TestPayload) -> Result<()> {
+        self.num_validated.fetch_add(1, Ordering::SeqCst);
+        ensure!(
+            payload.len() <= self.max_txns,
+            "{} transactions exceed the limit of {}",
+            payload.len(),
+            self.max_txns
+        );
+        let unique_txns: HashSet<_> = payload.iter().collect();
+        ensure!(
+            unique_txns.len() == payload.len(),
+            "Duplicate transactions in {:?}",
+            payload
+        );
+        Ok(())
+    }
+}
@@ -4,22 +4,37 @@
 use crate::{
     chained_bft::{
         consensus_types::quorum_cert::QuorumCert,
-        test_utils::{mock_storage::MockStorage, TestPayload},
+        test_utils::{mock_storage::MockStorage, payload_strategy::PayloadStrategy, TestPayload},
     },
     state_replication::StateComputer,
 };
-use crypto::{hash::ACCUMULATOR_PLACEHOLDER_HASH, HashValue};
+use crypto::{
+    hash::{ACCUMULATOR_PLACEHOLDER_HASH, GENESIS_BLOCK_ID},
+    HashValue,
+};
 use executor::{ExecutedState, StateComputeResult};
-use failure::Result;
+use failure::{format_err, Result};
 use futures::{channel::mpsc, future, Future, FutureExt};
 use logger::prelude::*;
-use std::{pin::Pin, sync::Arc};
+use std::{
+    collections::HashMap,
+    pin::Pin,
+    sync::{Arc, Mutex},
+};
 use termion::color::*;
-use types::crypto_proxies::LedgerInfoWithSignatures;
+use types::{crypto_proxies::LedgerInfoWithSignatures, validator_set::ValidatorSet};
 
 pub struct MockStateComputer {
     commit_callback: mpsc::UnboundedSender<LedgerInfoWithSignatures>,
     consensus_db: Arc<MockStorage<TestPayload>>,
+    // The blocks at the given height reconfigure the validators of the next epoch.
+    reconfiguration: Option<(u64, ValidatorSet)>,
+    // The height of the computed blocks, the genesis block is at height 0.
+    heights: Mutex<HashMap<HashValue, u64>>,
+    // Decides which payloads fail to execute, none if unset.
+    payload_strategy: Option<Arc<dyn PayloadStrategy>>,
+    // The number of the next commits that fail to be persisted.
+    failing_commits: Mutex<usize>,
 }
 
 impl MockStateComputer {
@@ -30,23 +45,75 @@ impl MockStateComputer {
         MockStateComputer {
             commit_callback,
             consensus_db,
+            reconfiguration: None,
+            heights: Mutex::new(HashMap::new()),
+            payload_strategy: None,
+            failing_commits: Mutex::new(0),
         }
     }
+
+    /// The given number of the next commits fail to be persisted.
+    pub fn with_failing_commits(mut self, failing_commits: usize) -> Self {
+        self.failing_commits = Mutex::new(failing_commits);
+        self
+    }
+
+    /// Fails the execution of the blocks whose payload the given strategy marks as failing.
+    pub fn with_payload_strategy(mut self, payload_strategy: Arc<dyn PayloadStrategy>) -> Self {
+        self.payload_strategy = Some(payload_strategy);
+        self
+    }
+
+    /// The execution of the blocks at the given height sets the validators of the next epoch.
+    pub fn with_reconfiguration(mut self, height: u64, validators: ValidatorSet) -> Self {
+        self.reconfiguration = Some((height, validators));
+        self
+    }
 }
 
 impl StateComputer for MockStateComputer {
     type Payload = Vec<usize>;
     fn compute(
         &self,
-        _parent_id: HashValue,
-        _block_id: HashValue,
-        _transactions: &Self::Payload,
+        parent_id: HashValue,
+        block_id: HashValue,
+        transactions: &Self::Payload,
     ) -> Pin<Box<dyn Future<Output = Result<StateComputeResult>> + Send>> {
+        if let Some(payload_strategy) = &self.payload_strategy {
+            if let Some(txn) = transactions
+                .iter()
+                .find(|txn| payload_strategy.fails_execution(**txn))
+            {
+                return future::err(format_err!(
+                    "Execution of block {} failed on transaction {}",
+                    block_id,
+                    txn
+                ))
+                .boxed();
+            }
+        }
+        let mut heights = self.heights.lock().unwrap();
+        let height = if parent_id == *GENESIS_BLOCK_ID {
+            Some(1)
+        } else {
+            heights.get(&parent_id).map(|height| height + 1)
+        };
+        if let Some(height) = height {
+            heights.insert(block_id, height);
+        }
+        let validators = match &self.reconfiguration {
+            Some((reconfiguration_height, validators))
+                if height == Some(*reconfiguration_height) =>
+            {
+                Some(validators.clone())
+            }
+            _ => None,
+        };
         future::ok(StateComputeResult {
             executed_state: ExecutedState {
                 state_id: *ACCUMULATOR_PLACEHOLDER_HASH,
                 version: 0,
-                validators: None,
+                validators,
             },
             compute_status: vec![],
         })
@@ -57,6 +124,13 @@ impl StateComputer for MockStateComputer {
         &self,
         commit: LedgerInfoWithSignatures,
     ) -> Pin<Box<dyn Future<Output = Result<()>> + Send>> {
+        {
+            let mut failing_commits = self.failing_commits.lock().unwrap();
+            if *failing_commits > 0 {
+                *failing_commits -= 1;
+                return future::err(format_err!("Injected failure of commit {}", commit)).boxed();
+            }
+        }
         self.consensus_db
             .commit_to_storage(commit.ledger_info().clone());
 
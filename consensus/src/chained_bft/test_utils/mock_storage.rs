@@ -2,20 +2,28 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::chained_bft::{
-    common::Payload,
-    consensus_types::{block::Block, quorum_cert::QuorumCert},
+    common::{Payload, Round},
+    consensus_types::{
+        block::Block, key_rotation::KeyRotation, quorum_cert::QuorumCert, timeout_msg::TimeoutMsg,
+        vote_msg::VoteMsg,
+    },
+    epoch_manager::EpochChange,
+    evidence::Evidence,
     liveness::pacemaker_timeout_manager::HighestTimeoutCertificates,
     persistent_storage::{PersistentLivenessStorage, PersistentStorage, RecoveryData},
-    safety::safety_rules::ConsensusState,
+    safety::{
+        persistent_safety_storage::{InMemorySafetyStorage, PersistentSafetyStorage},
+        safety_rules::ConsensusState,
+    },
 };
 use config::config::{NodeConfig, NodeConfigHelpers};
 use crypto::HashValue;
-use failure::Result;
+use failure::prelude::*;
 use std::{
     collections::HashMap,
     sync::{Arc, Mutex},
 };
-use types::ledger_info::LedgerInfo;
+use types::{crypto_proxies::ValidatorSigner, ledger_info::LedgerInfo};
 
 pub struct MockSharedStorage<T> {
     // Safety state
@@ -25,6 +33,69 @@ pub struct MockSharedStorage<T> {
 
     // Liveness state
     pub highest_timeout_certificates: Mutex<HighestTimeoutCertificates>,
+    pub pending_votes: Mutex<Vec<VoteMsg>>,
+    pub last_vote: Mutex<Option<VoteMsg>>,
+    pub last_timeout: Mutex<Option<TimeoutMsg>>,
+
+    // Evidence of the equivocations
+    pub evidence: Mutex<Vec<Evidence<T>>>,
+
+    // The epoch changes the node went through
+    pub epoch_changes: Mutex<Vec<EpochChange>>,
+
+    // The key rotations accepted within the current epoch
+    pub key_rotations: Mutex<Vec<KeyRotation>>,
+    // The signers of the latest rotation of the consensus key of the node
+    pub rotated_signers: Mutex<Option<(ValidatorSigner, Round, ValidatorSigner)>>,
+
+    // The faults injected in the storage, they persist across restarts
+    pub faults: Mutex<StorageFaults>,
+}
+
+/// The faults a `MockStorage` is programmed to inject to test the crash recovery.
+#[derive(Default)]
+pub struct StorageFaults {
+    // The number of upcoming writes failing without persisting anything.
+    failing_writes: usize,
+    // The number of writes before the storage crashes: the write at this point is only partially
+    // flushed, and the following writes fail until the storage recovers.
+    writes_before_crash: Option<usize>,
+    crashed: bool,
+    // Whether the next recovery reads corrupted data.
+    corrupted: bool,
+}
+
+/// How an injected fault affects a write.
+#[derive(Debug, Eq, PartialEq)]
+enum WriteFault {
+    // Nothing is persisted.
+    Failed,
+    // Only part of the data is persisted.
+    PartiallyFlushed,
+}
+
+impl StorageFaults {
+    fn next_write_fault(&mut self) -> Option<WriteFault> {
+        if self.crashed {
+            return Some(WriteFault::Failed);
+        }
+        if self.failing_writes > 0 {
+            self.failing_writes -= 1;
+            return Some(WriteFault::Failed);
+        }
+        match self.writes_before_crash {
+            Some(0) => {
+                self.writes_before_crash = None;
+                self.crashed = true;
+                Some(WriteFault::PartiallyFlushed)
+            }
+            Some(ref mut writes) => {
+                *writes -= 1;
+                None
+            }
+            None => None,
+        }
+    }
 }
 
 /// A storage that simulates the operations in-memory, used in the tests that cares about storage
@@ -47,7 +118,55 @@ impl<T: Payload> MockStorage<T> {
         }
     }
 
+    /// Fails the next `num_writes` writes to the storage.
+    pub fn fail_next_writes(&self, num_writes: usize) {
+        self.shared_storage.faults.lock().unwrap().failing_writes = num_writes;
+    }
+
+    /// Crashes the storage after `num_writes` successful writes: the write at this point is
+    /// partially flushed (a tree update only persists its blocks), and the following writes fail
+    /// until the storage recovers.
+    pub fn crash_after_writes(&self, num_writes: usize) {
+        self.shared_storage
+            .faults
+            .lock()
+            .unwrap()
+            .writes_before_crash = Some(num_writes);
+    }
+
+    pub fn has_crashed(&self) -> bool {
+        self.shared_storage.faults.lock().unwrap().crashed
+    }
+
+    /// Corrupts the data read by the next recovery: the quorum certs are missing.
+    pub fn corrupt_next_recovery(&self) {
+        self.shared_storage.faults.lock().unwrap().corrupted = true;
+    }
+
+    /// Recovers the data persisted before a restart, applying the injected faults. The storage
+    /// accepts the writes again after the recovery.
+    pub fn recover(&self) -> Result<RecoveryData<T>> {
+        let corrupted = {
+            let mut faults = self.shared_storage.faults.lock().unwrap();
+            faults.crashed = false;
+            std::mem::replace(&mut faults.corrupted, false)
+        };
+        self.read_recovery_data(corrupted)
+    }
+
+    fn next_write_fault(&self) -> Option<WriteFault> {
+        self.shared_storage
+            .faults
+            .lock()
+            .unwrap()
+            .next_write_fault()
+    }
+
     pub fn get_recovery_data(&self) -> Result<RecoveryData<T>> {
+        self.read_recovery_data(false)
+    }
+
+    fn read_recovery_data(&self, corrupted: bool) -> Result<RecoveryData<T>> {
         let mut blocks: Vec<_> = self
             .shared_storage
             .block
@@ -57,18 +176,20 @@ impl<T: Payload> MockStorage<T> {
             .into_iter()
             .map(|(_, v)| v)
             .collect();
-        let quorum_certs = self
-            .shared_storage
-            .qc
-            .lock()
-            .unwrap()
-            .clone()
-            .into_iter()
-            .map(|(_, v)| v)
-            .collect();
+        let quorum_certs = if corrupted {
+            vec![]
+        } else {
+            self.shared_storage
+                .qc
+                .lock()
+                .unwrap()
+                .clone()
+                .into_iter()
+                .map(|(_, v)| v)
+                .collect()
+        };
         blocks.sort_by_key(Block::round);
         RecoveryData::new(
-            self.shared_storage.state.lock().unwrap().clone(),
             blocks,
             quorum_certs,
             &self.storage_ledger.lock().unwrap(),
@@ -77,6 +198,13 @@ impl<T: Payload> MockStorage<T> {
                 .lock()
                 .unwrap()
                 .clone(),
+            self.shared_storage.pending_votes.lock().unwrap().clone(),
+            self.shared_storage.last_vote.lock().unwrap().clone(),
+            self.shared_storage.last_timeout.lock().unwrap().clone(),
+            self.shared_storage.evidence.lock().unwrap().clone(),
+            self.shared_storage.epoch_changes.lock().unwrap().clone(),
+            self.shared_storage.key_rotations.lock().unwrap().clone(),
+            self.shared_storage.rotated_signers.lock().unwrap().clone(),
         )
     }
 
@@ -102,6 +230,9 @@ impl<T: Payload> PersistentLivenessStorage for MockStorage<T> {
         &self,
         highest_timeout_certificates: HighestTimeoutCertificates,
     ) -> Result<()> {
+        if let Some(fault) = self.next_write_fault() {
+            bail!("Injected fault: {:?}", fault);
+        }
         *self
             .shared_storage
             .highest_timeout_certificates
@@ -109,6 +240,44 @@ impl<T: Payload> PersistentLivenessStorage for MockStorage<T> {
             .unwrap() = highest_timeout_certificates;
         Ok(())
     }
+
+    fn save_pending_votes(&self, pending_votes: Vec<VoteMsg>) -> Result<()> {
+        if let Some(fault) = self.next_write_fault() {
+            bail!("Injected fault: {:?}", fault);
+        }
+        *self.shared_storage.pending_votes.lock().unwrap() = pending_votes;
+        Ok(())
+    }
+
+    fn save_last_vote(&self, last_vote: VoteMsg) -> Result<()> {
+        if let Some(fault) = self.next_write_fault() {
+            bail!("Injected fault: {:?}", fault);
+        }
+        *self.shared_storage.last_vote.lock().unwrap() = Some(last_vote);
+        Ok(())
+    }
+
+    fn save_last_timeout(&self, last_timeout: TimeoutMsg) -> Result<()> {
+        if let Some(fault) = self.next_write_fault() {
+            bail!("Injected fault: {:?}", fault);
+        }
+        *self.shared_storage.last_timeout.lock().unwrap() = Some(last_timeout);
+        Ok(())
+    }
+}
+
+impl<T: Payload> PersistentSafetyStorage for MockStorage<T> {
+    fn consensus_state(&self) -> Result<ConsensusState> {
+        Ok(self.shared_storage.state.lock().unwrap().clone())
+    }
+
+    fn set_consensus_state(&self, state: &ConsensusState) -> Result<()> {
+        if let Some(fault) = self.next_write_fault() {
+            bail!("Injected fault: {:?}", fault);
+        }
+        *self.shared_storage.state.lock().unwrap() = state.clone();
+        Ok(())
+    }
 }
 
 // A impl that always start from genesis.
@@ -117,7 +286,15 @@ impl<T: Payload> PersistentStorage<T> for MockStorage<T> {
         Box::new(MockStorage::new(Arc::clone(&self.shared_storage)))
     }
 
+    fn persistent_safety_storage(&self) -> Box<dyn PersistentSafetyStorage> {
+        Box::new(MockStorage::new(Arc::clone(&self.shared_storage)))
+    }
+
     fn save_tree(&self, blocks: Vec<Block<T>>, quorum_certs: Vec<QuorumCert>) -> Result<()> {
+        let fault = self.next_write_fault();
+        if fault == Some(WriteFault::Failed) {
+            bail!("Injected fault: {:?}", WriteFault::Failed);
+        }
         for block in blocks {
             self.shared_storage
                 .block
@@ -125,6 +302,9 @@ impl<T: Payload> PersistentStorage<T> for MockStorage<T> {
                 .unwrap()
                 .insert(block.id(), block);
         }
+        if let Some(fault) = fault {
+            bail!("Injected fault: {:?}", fault);
+        }
         for qc in quorum_certs {
             self.shared_storage
                 .qc
@@ -139,6 +319,9 @@ impl<T: Payload> PersistentStorage<T> for MockStorage<T> {
     }
 
     fn prune_tree(&self, block_id: Vec<HashValue>) -> Result<()> {
+        if let Some(fault) = self.next_write_fault() {
+            bail!("Injected fault: {:?}", fault);
+        }
         for id in block_id {
             self.shared_storage.block.lock().unwrap().remove(&id);
             self.shared_storage.qc.lock().unwrap().remove(&id);
@@ -149,8 +332,41 @@ impl<T: Payload> PersistentStorage<T> for MockStorage<T> {
         Ok(())
     }
 
-    fn save_consensus_state(&self, state: ConsensusState) -> Result<()> {
-        *self.shared_storage.state.lock().unwrap() = state;
+    fn save_evidence(&self, evidence: Vec<Evidence<T>>) -> Result<()> {
+        if let Some(fault) = self.next_write_fault() {
+            bail!("Injected fault: {:?}", fault);
+        }
+        *self.shared_storage.evidence.lock().unwrap() = evidence;
+        Ok(())
+    }
+
+    fn save_epoch_changes(&self, epoch_changes: Vec<EpochChange>) -> Result<()> {
+        if let Some(fault) = self.next_write_fault() {
+            bail!("Injected fault: {:?}", fault);
+        }
+        *self.shared_storage.epoch_changes.lock().unwrap() = epoch_changes;
+        Ok(())
+    }
+
+    fn save_key_rotations(&self, key_rotations: Vec<KeyRotation>) -> Result<()> {
+        if let Some(fault) = self.next_write_fault() {
+            bail!("Injected fault: {:?}", fault);
+        }
+        *self.shared_storage.key_rotations.lock().unwrap() = key_rotations;
+        Ok(())
+    }
+
+    fn save_rotated_signers(
+        &self,
+        previous_signer: &ValidatorSigner,
+        handover_round: Round,
+        new_signer: &ValidatorSigner,
+    ) -> Result<()> {
+        if let Some(fault) = self.next_write_fault() {
+            bail!("Injected fault: {:?}", fault);
+        }
+        *self.shared_storage.rotated_signers.lock().unwrap() =
+            Some((previous_signer.clone(), handover_round, new_signer.clone()));
         Ok(())
     }
 
@@ -160,6 +376,14 @@ impl<T: Payload> PersistentStorage<T> for MockStorage<T> {
             qc: Mutex::new(HashMap::new()),
             state: Mutex::new(ConsensusState::default()),
             highest_timeout_certificates: Mutex::new(HighestTimeoutCertificates::new(None, None)),
+            pending_votes: Mutex::new(vec![]),
+            last_vote: Mutex::new(None),
+            last_timeout: Mutex::new(None),
+            evidence: Mutex::new(vec![]),
+            epoch_changes: Mutex::new(vec![]),
+            key_rotations: Mutex::new(vec![]),
+            rotated_signers: Mutex::new(None),
+            faults: Mutex::new(StorageFaults::default()),
         });
         let storage = MockStorage::new(Arc::clone(&shared_storage));
 
@@ -190,6 +414,18 @@ impl PersistentLivenessStorage for EmptyStorage {
     fn save_highest_timeout_cert(&self, _: HighestTimeoutCertificates) -> Result<()> {
         Ok(())
     }
+
+    fn save_pending_votes(&self, _: Vec<VoteMsg>) -> Result<()> {
+        Ok(())
+    }
+
+    fn save_last_vote(&self, _: VoteMsg) -> Result<()> {
+        Ok(())
+    }
+
+    fn save_last_timeout(&self, _: TimeoutMsg) -> Result<()> {
+        Ok(())
+    }
 }
 
 impl<T: Payload> PersistentStorage<T> for EmptyStorage {
@@ -197,6 +433,10 @@ impl<T: Payload> PersistentStorage<T> for EmptyStorage {
         Box::new(EmptyStorage)
     }
 
+    fn persistent_safety_storage(&self) -> Box<dyn PersistentSafetyStorage> {
+        Box::new(InMemorySafetyStorage::default())
+    }
+
     fn save_tree(&self, _: Vec<Block<T>>, _: Vec<QuorumCert>) -> Result<()> {
         Ok(())
     }
@@ -205,7 +445,24 @@ impl<T: Payload> PersistentStorage<T> for EmptyStorage {
         Ok(())
     }
 
-    fn save_consensus_state(&self, _: ConsensusState) -> Result<()> {
+    fn save_evidence(&self, _: Vec<Evidence<T>>) -> Result<()> {
+        Ok(())
+    }
+
+    fn save_epoch_changes(&self, _: Vec<EpochChange>) -> Result<()> {
+        Ok(())
+    }
+
+    fn save_key_rotations(&self, _: Vec<KeyRotation>) -> Result<()> {
+        Ok(())
+    }
+
+    fn save_rotated_signers(
+        &self,
+        _: &ValidatorSigner,
+        _: Round,
+        _: &ValidatorSigner,
+    ) -> Result<()> {
         Ok(())
     }
 
@@ -216,11 +473,17 @@ impl<T: Payload> PersistentStorage<T> for EmptyStorage {
         (
             Arc::new(EmptyStorage),
             RecoveryData::new(
-                ConsensusState::default(),
                 vec![genesis],
                 vec![genesis_qc.clone()],
                 genesis_qc.ledger_info().ledger_info(),
                 htc,
+                vec![],
+                None,
+                None,
+                vec![],
+                vec![],
+                vec![],
+                None,
             )
             .unwrap(),
         )
@@ -1,23 +1,32 @@
 // Copyright (c) The Libra Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::state_replication::TxnManager;
+use crate::{
+    chained_bft::test_utils::payload_strategy::{FullPayloads, PayloadStrategy},
+    state_replication::TxnManager,
+};
 use executor::StateComputeResult;
 use failure::Result;
 use futures::{channel::mpsc, future, Future, FutureExt, SinkExt};
 use std::{
+    collections::{BTreeSet, HashSet},
     pin::Pin,
     sync::{
         atomic::{AtomicUsize, Ordering},
-        Arc, RwLock,
+        Arc, Mutex, RwLock,
     },
 };
 
 pub type MockTransaction = usize;
 
 /// Trivial mock: generates MockTransactions on the fly. Each next transaction is the next value.
+/// Like mempool, the transactions pulled but not committed yet are pulled again unless they are
+/// excluded. The transactions that fail execution are only pulled once, like a mempool evicting
+/// them after the failure.
 pub struct MockTransactionManager {
+    payload_strategy: Arc<dyn PayloadStrategy>,
     next_val: AtomicUsize,
+    pending_txns: Mutex<BTreeSet<MockTransaction>>,
     committed_txns: Arc<RwLock<Vec<MockTransaction>>>,
     commit_receiver: Option<mpsc::Receiver<usize>>,
     commit_sender: mpsc::Sender<usize>,
@@ -25,9 +34,16 @@ pub struct MockTransactionManager {
 
 impl MockTransactionManager {
     pub fn new() -> Self {
+        Self::with_payload_strategy(Arc::new(FullPayloads))
+    }
+
+    /// The payloads are shaped by the given strategy instead of filling the requested size.
+    pub fn with_payload_strategy(payload_strategy: Arc<dyn PayloadStrategy>) -> Self {
         let (commit_sender, commit_receiver) = mpsc::channel(1024);
         Self {
+            payload_strategy,
             next_val: AtomicUsize::new(0),
+            pending_txns: Mutex::new(BTreeSet::new()),
             committed_txns: Arc::new(RwLock::new(vec![])),
             commit_receiver: Some(commit_receiver),
             commit_sender,
@@ -54,12 +70,27 @@ impl TxnManager for MockTransactionManager {
     fn pull_txns(
         &self,
         max_size: u64,
-        _exclude_txns: Vec<&Self::Payload>,
+        exclude_txns: Vec<&Self::Payload>,
     ) -> Pin<Box<dyn Future<Output = Result<Self::Payload>> + Send>> {
-        let next_value = self.next_val.load(Ordering::SeqCst);
-        let upper_bound = next_value + max_size as usize;
-        let res = (next_value..upper_bound).collect();
-        self.next_val.store(upper_bound, Ordering::SeqCst);
+        let exclude_txns: HashSet<_> = exclude_txns.into_iter().flatten().collect();
+        let size = self.payload_strategy.payload_size(max_size as usize);
+        let mut pending_txns = self.pending_txns.lock().unwrap();
+        let mut res: Vec<_> = pending_txns
+            .iter()
+            .filter(|txn| !exclude_txns.contains(txn))
+            .take(size)
+            .cloned()
+            .collect();
+        while res.len() < size {
+            let txn = self.next_val.fetch_add(1, Ordering::SeqCst);
+            pending_txns.insert(txn);
+            res.push(txn);
+        }
+        for txn in &res {
+            if self.payload_strategy.fails_execution(*txn) {
+                pending_txns.remove(txn);
+            }
+        }
         future::ok(res).boxed()
     }
 
@@ -70,6 +101,12 @@ impl TxnManager for MockTransactionManager {
         _timestamp_usecs: u64,
     ) -> Pin<Box<dyn Future<Output = Result<()>> + Send + 'a>> {
         let committed_tns = txns.clone();
+        {
+            let mut pending_txns = self.pending_txns.lock().unwrap();
+            for txn in &committed_tns {
+                pending_txns.remove(txn);
+            }
+        }
         let mut commit_sender = self.commit_sender.clone();
         async move {
             for txn in committed_tns {
@@ -2,7 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::chained_bft::{
-    block_storage::BlockStore,
+    block_storage::{BlockStore, PruningPolicy},
     common::Round,
     consensus_types::{
         block::{Block, ExecutedBlock},
@@ -22,13 +22,21 @@ use types::{
     ledger_info::LedgerInfo,
 };
 
+mod mock_payload_validator;
 mod mock_state_computer;
 mod mock_storage;
 mod mock_txn_manager;
+mod payload_strategy;
+mod simulation;
 
+pub use mock_payload_validator::MockPayloadValidator;
 pub use mock_state_computer::{EmptyStateComputer, MockStateComputer};
 pub use mock_storage::{EmptyStorage, MockStorage};
 pub use mock_txn_manager::MockTransactionManager;
+pub use payload_strategy::{
+    EmptyPayloads, FailingPayloads, FullPayloads, PayloadStrategy, VariableSizePayloads,
+};
+pub use simulation::{Simulation, SIMULATION_SEED_ENV};
 
 pub type TestPayload = Vec<usize>;
 
@@ -39,6 +47,13 @@ pub fn build_empty_tree() -> Arc<BlockStore<Vec<usize>>> {
 
 pub fn build_empty_tree_with_custom_signing(
     my_signer: ValidatorSigner,
+) -> Arc<BlockStore<Vec<usize>>> {
+    build_empty_tree_with_pruning_policy(my_signer, PruningPolicy::MaxBlocks(10))
+}
+
+pub fn build_empty_tree_with_pruning_policy(
+    my_signer: ValidatorSigner,
+    pruning_policy: PruningPolicy,
 ) -> Arc<BlockStore<Vec<usize>>> {
     let (storage, initial_data) = EmptyStorage::start_for_testing();
     Arc::new(block_on(BlockStore::new(
@@ -47,7 +62,7 @@ pub fn build_empty_tree_with_custom_signing(
         my_signer,
         Arc::new(EmptyStateComputer),
         true,
-        10, // max pruned blocks in mem
+        pruning_policy,
     )))
 }
 
@@ -73,19 +88,24 @@ impl TreeInserter {
         round: Round,
     ) -> Arc<ExecutedBlock<Vec<usize>>> {
         // Node must carry a QC to its parent
-        let parent_qc = placeholder_certificate_for_block(
-            vec![self.block_store.signer()],
-            parent.id(),
-            parent.round(),
-            parent.quorum_cert().certified_block_id(),
-            parent.quorum_cert().certified_block_round(),
-            parent.quorum_cert().parent_block_id(),
-            parent.quorum_cert().parent_block_round(),
-        );
-
+        let parent_qc = self.create_qc_for_block(parent);
         self.insert_block_with_qc(parent_qc, parent, round)
     }
 
+    /// Generates a placeholder QC for the given block, signed by the signer kept by the block
+    /// store.
+    pub fn create_qc_for_block(&self, block: &ExecutedBlock<Vec<usize>>) -> QuorumCert {
+        placeholder_certificate_for_block(
+            vec![self.block_store.signer()],
+            block.id(),
+            block.round(),
+            block.quorum_cert().certified_block_id(),
+            block.quorum_cert().certified_block_round(),
+            block.quorum_cert().parent_block_id(),
+            block.quorum_cert().parent_block_round(),
+        )
+    }
+
     pub fn insert_block_with_qc(
         &mut self,
         parent_qc: QuorumCert,
@@ -0,0 +1,86 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::chained_bft::test_utils::mock_txn_manager::MockTransaction;
+use std::{
+    collections::HashSet,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// Shapes the payloads pulled from `MockTransactionManager` and decides which of them
+/// `MockStateComputer` fails to execute, such that the tests can cover the payload size limits
+/// and the execution errors.
+pub trait PayloadStrategy: Send + Sync {
+    /// The number of transactions of the next payload, given the maximal number requested.
+    fn payload_size(&self, max_size: usize) -> usize;
+
+    /// Whether the execution of the blocks carrying the given transaction fails.
+    fn fails_execution(&self, _txn: MockTransaction) -> bool {
+        false
+    }
+}
+
+/// Every payload is as large as requested.
+pub struct FullPayloads;
+
+impl PayloadStrategy for FullPayloads {
+    fn payload_size(&self, max_size: usize) -> usize {
+        max_size
+    }
+}
+
+/// Every payload is empty.
+pub struct EmptyPayloads;
+
+impl PayloadStrategy for EmptyPayloads {
+    fn payload_size(&self, _max_size: usize) -> usize {
+        0
+    }
+}
+
+/// The sizes of the payloads cycle through the given ones, capped by the requested size.
+pub struct VariableSizePayloads {
+    sizes: Vec<usize>,
+    next: AtomicUsize,
+}
+
+impl VariableSizePayloads {
+    pub fn new(sizes: Vec<usize>) -> Self {
+        assert!(!sizes.is_empty(), "No payload size given");
+        Self {
+            sizes,
+            next: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl PayloadStrategy for VariableSizePayloads {
+    fn payload_size(&self, max_size: usize) -> usize {
+        let index = self.next.fetch_add(1, Ordering::SeqCst) % self.sizes.len();
+        std::cmp::min(self.sizes[index], max_size)
+    }
+}
+
+/// The payloads are as large as requested, and the blocks carrying any of the given transactions
+/// fail to execute.
+pub struct FailingPayloads {
+    failing_txns: HashSet<MockTransaction>,
+}
+
+impl FailingPayloads {
+    pub fn new(failing_txns: impl IntoIterator<Item = MockTransaction>) -> Self {
+        Self {
+            failing_txns: failing_txns.into_iter().collect(),
+        }
+    }
+}
+
+impl PayloadStrategy for FailingPayloads {
+    fn payload_size(&self, max_size: usize) -> usize {
+        max_size
+    }
+
+    fn fails_execution(&self, txn: MockTransaction) -> bool {
+        self.failing_txns.contains(&txn)
+    }
+}
@@ -0,0 +1,138 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::util::time_service::{ScheduledTask, TimeService};
+use futures::{channel::oneshot, Future, FutureExt};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::{
+    collections::BTreeMap,
+    env,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+/// The environment variable with the seed of the simulations, used to replay a failing run.
+pub const SIMULATION_SEED_ENV: &str = "CONSENSUS_SIMULATION_SEED";
+
+/// The simulated time when a simulation starts.
+const SIMULATION_START_TIME: Duration = Duration::from_secs(1);
+
+/// A deterministic simulation of the time and of the scheduling decisions of a test, driven from
+/// a seed: two simulations with the same seed make the same choices given the same sequence of
+/// calls, such that a failing run can be replayed exactly.
+///
+/// The simulation is also the `TimeService` of the nodes: the time is frozen and the timers only
+/// fire when the test (typically the `NetworkPlayground`) calls `fire_next_timers`, which
+/// advances the time to the earliest deadline.
+#[derive(Clone)]
+pub struct Simulation {
+    inner: Arc<Mutex<SimulationInner>>,
+}
+
+struct SimulationInner {
+    seed: u64,
+    rng: StdRng,
+    now: Duration,
+    // The timers ordered by their deadline, the ties are broken by the order of scheduling.
+    timers: BTreeMap<(Duration, u64), Box<dyn ScheduledTask>>,
+    next_timer_id: u64,
+}
+
+impl Simulation {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(SimulationInner {
+                seed,
+                rng: StdRng::seed_from_u64(seed),
+                now: SIMULATION_START_TIME,
+                timers: BTreeMap::new(),
+                next_timer_id: 0,
+            })),
+        }
+    }
+
+    /// Creates a simulation with the seed of the `CONSENSUS_SIMULATION_SEED` environment
+    /// variable, or with a random seed. The seed is printed, such that the failing tests report
+    /// the seed to replay them with.
+    pub fn from_env() -> Self {
+        let seed = match env::var(SIMULATION_SEED_ENV) {
+            Ok(seed) => seed
+                .parse()
+                .unwrap_or_else(|e| panic!("Invalid {}: {}", SIMULATION_SEED_ENV, e)),
+            Err(_) => rand::thread_rng().gen(),
+        };
+        println!("Simulation seed: {}={}", SIMULATION_SEED_ENV, seed);
+        Self::new(seed)
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.inner.lock().unwrap().seed
+    }
+
+    /// Chooses an index in [0, num_choices).
+    pub fn choose(&self, num_choices: usize) -> usize {
+        assert!(num_choices > 0, "Nothing to choose from");
+        self.inner.lock().unwrap().rng.gen_range(0, num_choices)
+    }
+
+    /// The number of timers that haven't fired yet.
+    pub fn pending_timers(&self) -> usize {
+        self.inner.lock().unwrap().timers.len()
+    }
+
+    /// Advances the time to the earliest deadline and runs all the timers of this deadline.
+    /// Returns false if there is no timer to fire.
+    pub async fn fire_next_timers(&self) -> bool {
+        let tasks = {
+            let mut inner = self.inner.lock().unwrap();
+            let deadline = match inner.timers.keys().next() {
+                Some((deadline, _)) => *deadline,
+                None => return false,
+            };
+            inner.now = deadline;
+            let later_timers = inner.timers.split_off(&(deadline, std::u64::MAX));
+            std::mem::replace(&mut inner.timers, later_timers)
+        };
+        for (_, mut task) in tasks {
+            task.run().await;
+        }
+        true
+    }
+}
+
+impl TimeService for Simulation {
+    fn run_after(&self, timeout: Duration, task: Box<dyn ScheduledTask>) {
+        let mut inner = self.inner.lock().unwrap();
+        let deadline = inner.now + timeout;
+        let timer_id = inner.next_timer_id;
+        inner.next_timer_id += 1;
+        inner.timers.insert((deadline, timer_id), task);
+    }
+
+    fn get_current_timestamp(&self) -> Duration {
+        self.inner.lock().unwrap().now
+    }
+
+    fn sleep(&self, t: Duration) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+        let (tx, rx) = oneshot::channel();
+        self.run_after(t, Box::new(WakeTask(Some(tx))));
+        async move {
+            // The simulation can be dropped before the timer fires at the end of a test.
+            let _ = rx.await;
+        }
+            .boxed()
+    }
+}
+
+/// Wakes up a sleeping future.
+struct WakeTask(Option<oneshot::Sender<()>>);
+
+impl ScheduledTask for WakeTask {
+    fn run(&mut self) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+        if let Some(tx) = self.0.take() {
+            let _ = tx.send(());
+        }
+        async {}.boxed()
+    }
+}
@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use config::config::NodeConfig;
+use debug_interface::node_debug_service::{CommitCertificateProvider, ConsensusStateProvider};
 use failure::prelude::*;
 use network::validator_network::{ConsensusNetworkEvents, ConsensusNetworkSender};
 
@@ -12,6 +13,7 @@ use mempool::proto::mempool_grpc::MempoolClient;
 use state_synchronizer::StateSyncClient;
 use std::sync::Arc;
 use storage_client::{StorageRead, StorageReadServiceClient};
+use types::crypto_proxies::LedgerInfoWithSignatures;
 use vm_runtime::MoveVM;
 
 /// Public interface to a consensus protocol.
@@ -25,6 +27,18 @@ pub trait ConsensusProvider {
 
     /// Stop the consensus operations. The function returns after graceful shutdown.
     fn stop(&mut self);
+
+    /// The ledger info with signatures of the latest block committed by the node, i.e., a fresh
+    /// finality proof, None if consensus hasn't been started.
+    fn latest_commit_certificate(&self) -> Option<LedgerInfoWithSignatures>;
+
+    /// Serves the latest commit certificate to the debug interface (or any other reader that
+    /// outlives the borrow of the provider), None if consensus hasn't been started.
+    fn commit_certificate_provider(&self) -> Option<Arc<dyn CommitCertificateProvider>>;
+
+    /// Serves a view of the internal state of consensus (rounds, block tree, last vote) to the
+    /// debug interface, None if consensus hasn't been started.
+    fn consensus_state_provider(&self) -> Option<Arc<dyn ConsensusStateProvider>>;
 }
 
 /// Helper function to create a ConsensusProvider based on configuration
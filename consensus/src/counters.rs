@@ -28,6 +28,9 @@ pub static ref PREFERRED_BLOCK_ROUND: IntGauge = OP_COUNTERS.gauge("preferred_bl
 /// This counter is set to the last round reported by the local pacemaker.
 pub static ref CURRENT_ROUND: IntGauge = OP_COUNTERS.gauge("current_round");
 
+/// This counter is set to the round of the highest quorum certificate.
+pub static ref HIGHEST_QUORUM_CERT_ROUND: IntGauge = OP_COUNTERS.gauge("highest_quorum_cert_round");
+
 /// Count of the committed blocks since last restart.
 pub static ref COMMITTED_BLOCKS_COUNT: IntCounter = OP_COUNTERS.counter("committed_blocks_count");
 
@@ -49,6 +52,46 @@ pub static ref FAILED_TXNS_COUNT: IntCounter = OP_COUNTERS.counter("failed_txns_
 /// (both primary and secondary)
 pub static ref PROPOSALS_COUNT: IntCounter = OP_COUNTERS.counter("proposals_count");
 
+/// Count of the proposals that carried the payload of a previous failed proposal of this validator
+/// since last restart.
+pub static ref REPROPOSED_PAYLOAD_COUNT: IntCounter = OP_COUNTERS.counter("reproposed_payload_count");
+
+/// Count of the payloads pulled from mempool that exceeded the max block size in bytes and were
+/// pulled again with fewer transactions since last restart.
+pub static ref OVERSIZED_PAYLOAD_COUNT: IntCounter = OP_COUNTERS.counter("oversized_payload_count");
+
+/// Count of the proposals generated without transactions because too many ordered blocks were
+/// not committed yet since last restart.
+pub static ref BACK_PRESSURE_EMPTY_PROPOSAL_COUNT: IntCounter = OP_COUNTERS.counter("back_pressure_empty_proposal_count");
+
+/// Count of the remote proposals rejected because their payload exceeds the max block size in
+/// bytes since last restart.
+pub static ref OVERSIZED_PROPOSAL_COUNT: IntCounter = OP_COUNTERS.counter("oversized_proposal_count");
+
+/// Count of the remote proposals rejected by the payload validator since last restart.
+pub static ref INVALID_PAYLOAD_PROPOSAL_COUNT: IntCounter = OP_COUNTERS.counter("invalid_payload_proposal_count");
+
+/// Count of the commits triggered by the highest ledger info carried by a proposal (rather than
+/// by a locally formed 3-chain) since last restart.
+pub static ref COMMITS_FROM_PROPOSAL_COUNT: IntCounter = OP_COUNTERS.counter("commits_from_proposal_count");
+
+/// Count of the commit notifications skipped because the buffer of a lagging commit subscriber
+/// was full since last restart.
+pub static ref SKIPPED_COMMIT_NOTIFICATION_COUNT: IntCounter = OP_COUNTERS.counter("skipped_commit_notification_count");
+
+/// Count of the failed attempts to persist a commit through the state computer since last
+/// restart, the failed commits are retried.
+pub static ref FAILED_COMMIT_ATTEMPT_COUNT: IntCounter = OP_COUNTERS.counter("failed_commit_attempt_count");
+
+/// Count of the rounds in which this validator was the primary proposer since last restart.
+pub static ref ROUNDS_LED_COUNT: IntCounter = OP_COUNTERS.counter("rounds_led_count");
+
+/// Count of the committed blocks that were proposed by this validator since last restart.
+pub static ref COMMITTED_PROPOSALS_COUNT: IntCounter = OP_COUNTERS.counter("committed_proposals_count");
+
+/// Count of the QC verifications skipped because the same QC had already been verified.
+pub static ref QC_VERIFICATION_CACHE_HIT_COUNT: IntCounter = OP_COUNTERS.counter("qc_verification_cache_hit_count");
+
 /// Count the number of times a validator voted for secondary proposals (upon timeout) since
 /// last restart.
 pub static ref VOTE_SECONDARY_PROPOSAL_COUNT: IntCounter = OP_COUNTERS.counter("vote_secondary_proposal_count");
@@ -56,6 +99,10 @@ pub static ref VOTE_SECONDARY_PROPOSAL_COUNT: IntCounter = OP_COUNTERS.counter("
 /// Count the number of times a validator voted for a nil block since last restart.
 pub static ref VOTE_NIL_COUNT: IntCounter = OP_COUNTERS.counter("vote_nil_count");
 
+/// Count of the equivocations (conflicting proposals or votes signed by the same author for the
+/// same round) detected since last restart.
+pub static ref EQUIVOCATION_COUNT: IntCounter = OP_COUNTERS.counter("equivocation_count");
+
 //////////////////////
 // PACEMAKER COUNTERS
 //////////////////////
@@ -71,6 +118,14 @@ pub static ref TIMEOUT_ROUNDS_COUNT: IntCounter = OP_COUNTERS.counter("timeout_r
 /// timeouts to get the timeout certificate).
 pub static ref TIMEOUT_COUNT: IntCounter = OP_COUNTERS.counter("timeout_count");
 
+/// Count the number of timeout messages broadcast again because the round didn't advance
+/// since last restart.
+pub static ref TIMEOUT_MSGS_RETRANSMITTED_COUNT: IntCounter = OP_COUNTERS.counter("timeout_msgs_retransmitted_count");
+
+/// Count the number of heartbeats broadcast with the highest commit certificate since last
+/// restart.
+pub static ref COMMIT_HEARTBEATS_SENT_COUNT: IntCounter = OP_COUNTERS.counter("commit_heartbeats_sent_count");
+
 /// The timeout of the current round.
 pub static ref ROUND_TIMEOUT_MS: IntGauge = OP_COUNTERS.gauge("round_timeout_ms");
 
@@ -95,6 +150,18 @@ pub static ref SYNC_INFO_MSGS_SENT_COUNT: IntCounter = OP_COUNTERS.counter("sync
 /// Counts the number of times the sync info message has been received since last restart.
 pub static ref SYNC_INFO_MSGS_RECEIVED_COUNT: IntCounter = OP_COUNTERS.counter("sync_info_msg_received_count");
 
+/// Counts the votes persisted before a restart and sent again for the same proposal after it.
+pub static ref PERSISTED_VOTES_RESENT_COUNT: IntCounter = OP_COUNTERS.counter("persisted_votes_resent_count");
+
+/// Counts the votes piggybacked on the proposals and the sync info messages sent since last restart.
+pub static ref PIGGYBACKED_VOTES_SENT_COUNT: IntCounter = OP_COUNTERS.counter("piggybacked_votes_sent_count");
+
+/// Counts the piggybacked votes received for uncertified blocks since last restart.
+pub static ref PIGGYBACKED_VOTES_RECEIVED_COUNT: IntCounter = OP_COUNTERS.counter("piggybacked_votes_received_count");
+
+/// Counts the number of times the sync info of a peer has been requested since last restart.
+pub static ref SYNC_INFO_REQUESTS_SENT_COUNT: IntCounter = OP_COUNTERS.counter("sync_info_request_sent_count");
+
 //////////////////////
 // RECONFIGURATION COUNTERS
 //////////////////////
@@ -104,6 +171,12 @@ pub static ref EPOCH_NUM: IntGauge = OP_COUNTERS.gauge("epoch_num");
 pub static ref CURRENT_EPOCH_NUM_VALIDATORS: IntGauge = OP_COUNTERS.gauge("current_epoch_num_validators");
 /// Quorum size in the current epoch
 pub static ref CURRENT_EPOCH_QUORUM_SIZE: IntGauge = OP_COUNTERS.gauge("current_epoch_quorum_size");
+/// Count of the consensus key rotations accepted within the current epochs
+pub static ref KEY_ROTATIONS_COUNT: IntCounter = OP_COUNTERS.counter("key_rotations_count");
+/// Count of the messages dropped because they were sent in another epoch
+pub static ref EPOCH_MISMATCH_MSGS_COUNT: IntCounter = OP_COUNTERS.counter("epoch_mismatch_msgs_count");
+/// Count of the epoch proofs sent to the peers lagging behind an epoch
+pub static ref EPOCH_PROOFS_SENT_COUNT: IntCounter = OP_COUNTERS.counter("epoch_proofs_sent_count");
 
 
 //////////////////////
@@ -113,6 +186,10 @@ pub static ref CURRENT_EPOCH_QUORUM_SIZE: IntGauge = OP_COUNTERS.gauge("current_
 /// In a "happy path" with no collisions and timeouts, should be equal to 3 or 4.
 pub static ref NUM_BLOCKS_IN_TREE: IntGauge = OP_COUNTERS.gauge("num_blocks_in_tree");
 
+/// The size of the payloads of the blocks pruned from the block tree but still kept in memory
+/// (only tracked when the pruning policy limits the size of the pruned payloads).
+pub static ref PRUNED_PAYLOAD_BYTES_IN_MEM: IntGauge = OP_COUNTERS.gauge("pruned_payload_bytes_in_mem");
+
 //////////////////////
 // PERFORMANCE COUNTERS
 //////////////////////
@@ -143,6 +220,26 @@ pub static ref CREATION_TO_QC_S: DurationHistogram = OP_COUNTERS.duration_histog
 /// Duration between block generation time until the moment it is received and ready for execution.
 pub static ref CREATION_TO_RECEIVAL_S: DurationHistogram = OP_COUNTERS.duration_histogram("creation_to_receival_s");
 
+/// Histogram of the time between the receipt of a proposal and the vote for it.
+pub static ref PROPOSAL_TO_VOTE_S: DurationHistogram = OP_COUNTERS.duration_histogram("proposal_to_vote_s");
+
+/// Histogram of the time between a vote and the moment the QC of the voted block is observed.
+pub static ref VOTE_TO_QC_S: DurationHistogram = OP_COUNTERS.duration_histogram("vote_to_qc_s");
+
+/// Histogram of the time between the moment the QC of a block is observed and its commit.
+pub static ref QC_TO_COMMIT_S: DurationHistogram = OP_COUNTERS.duration_histogram("qc_to_commit_s");
+
+/// Histogram of the number of rounds between two consecutive commits (1 in happy path).
+pub static ref ROUNDS_PER_COMMIT: Histogram = OP_COUNTERS.histogram("rounds_per_commit");
+
+/// The latest latencies observed by the histograms above, in milliseconds, and the number of
+/// rounds of the latest commit: the histograms only report their number of samples through the
+/// debug interface.
+pub static ref LAST_PROPOSAL_TO_VOTE_MS: IntGauge = OP_COUNTERS.gauge("last_proposal_to_vote_ms");
+pub static ref LAST_VOTE_TO_QC_MS: IntGauge = OP_COUNTERS.gauge("last_vote_to_qc_ms");
+pub static ref LAST_QC_TO_COMMIT_MS: IntGauge = OP_COUNTERS.gauge("last_qc_to_commit_ms");
+pub static ref LAST_ROUNDS_PER_COMMIT: IntGauge = OP_COUNTERS.gauge("last_rounds_per_commit");
+
 ////////////////////////////////////
 // PROPSOSAL/VOTE TIMESTAMP COUNTERS
 ////////////////////////////////////
@@ -204,12 +301,34 @@ pub static ref PENDING_NEW_ROUND_MESSAGES: IntGauge = OP_COUNTERS.gauge("pending
 /// Count of the pending outbound pacemaker timeouts
 pub static ref PENDING_PACEMAKER_TIMEOUTS: IntGauge = OP_COUNTERS.gauge("pending_pacemaker_timeouts");
 
+/// Count of the pending retransmissions of the timeout messages
+pub static ref PENDING_TIMEOUT_RETRANSMISSIONS: IntGauge = OP_COUNTERS.gauge("pending_timeout_retransmissions");
+
+/// Count of the pending commit heartbeats
+pub static ref PENDING_COMMIT_HEARTBEATS: IntGauge = OP_COUNTERS.gauge("pending_commit_heartbeats");
+
 /// Count of the pending new round events.
 pub static ref PENDING_NEW_ROUND_EVENTS: IntGauge = OP_COUNTERS.gauge("pending_new_round_events");
 
 /// Count of the pending sync info messages.
 pub static ref PENDING_SYNC_INFO_MSGS: IntGauge = OP_COUNTERS.gauge("pending_sync_info_msgs");
 
+/// Count of the pending sync info requests.
+pub static ref PENDING_SYNC_INFO_REQUESTS: IntGauge = OP_COUNTERS.gauge("pending_sync_info_requests");
+
 /// Count of the pending winning proposals.
 pub static ref PENDING_WINNING_PROPOSALS: IntGauge = OP_COUNTERS.gauge("pending_winning_proposals");
+
+/// Histogram of the number of votes verified together by the signature verifier.
+pub static ref VOTE_VERIFICATION_BATCH_SIZE: Histogram = OP_COUNTERS.histogram("vote_verification_batch_size");
+
+/// Histogram of the time it takes to verify a batch of votes on the signature verifier.
+pub static ref VOTE_VERIFICATION_DURATION_S: DurationHistogram = OP_COUNTERS.duration_histogram("vote_verification_duration_s");
+
+/// Count of the pending votes waiting for the verification of their signatures.
+pub static ref PENDING_UNVERIFIED_VOTES: IntGauge = OP_COUNTERS.gauge("pending_unverified_votes");
+
+/// Count of the inbound messages dropped because their peer exceeded its rate limit or their
+/// queue was full (see the rate_limited_* and queue_full_dropped_* counters for the types).
+pub static ref DROPPED_INBOUND_MSGS_COUNT: IntCounter = OP_COUNTERS.counter("dropped_inbound_msgs_count");
 }
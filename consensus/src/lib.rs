@@ -30,6 +30,18 @@ pub mod util;
 /// use in the Libra Core blockchain.
 pub mod consensus_provider;
 
+/// Structured log of the consensus events and its offline analyzer.
+pub use chained_bft::event_log;
+
+/// Offline dump of the consensus persistent storage of a node.
+pub use chained_bft::state_dump;
+
+/// Export and import of the consensus data of a node.
+pub use chained_bft::snapshot;
+
+/// The safety rules service of a validator running in a separate process.
+pub use chained_bft::run_safety_rules_service;
+
 mod counters;
 
 mod state_computer;
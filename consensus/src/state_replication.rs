@@ -6,7 +6,7 @@ use crypto::HashValue;
 use executor::StateComputeResult;
 use failure::Result;
 use futures::Future;
-use std::{pin::Pin, sync::Arc};
+use std::{marker::PhantomData, pin::Pin, sync::Arc};
 use types::crypto_proxies::LedgerInfoWithSignatures;
 
 /// Retrieves and updates the status of transactions on demand (e.g., via talking with Mempool)
@@ -15,7 +15,8 @@ pub trait TxnManager: Send + Sync {
 
     /// Brings new transactions to be applied.
     /// The `exclude_txns` list includes the transactions that are already pending in the
-    /// branch of blocks consensus is trying to extend.
+    /// branch of blocks consensus is trying to extend, or in the blocks ordered by consensus
+    /// that are not committed yet: they must not be returned again.
     fn pull_txns(
         &self,
         max_size: u64,
@@ -33,6 +34,37 @@ pub trait TxnManager: Send + Sync {
     ) -> Pin<Box<dyn Future<Output = Result<()>> + Send + 'a>>;
 }
 
+/// Checks the payload of the proposals received from the other validators before voting for
+/// them, such that a deployment can reject the payloads violating its policy (e.g., duplicate
+/// transactions or oversized entries).
+pub trait PayloadValidator: Send + Sync {
+    type Payload;
+
+    /// Returns an error if the proposal carrying the given payload must not be voted for.
+    fn validate(&self, payload: &Self::Payload) -> Result<()>;
+}
+
+/// Accepts every payload.
+pub struct NoopPayloadValidator<T> {
+    payload: PhantomData<fn(&T)>,
+}
+
+impl<T> Default for NoopPayloadValidator<T> {
+    fn default() -> Self {
+        Self {
+            payload: PhantomData,
+        }
+    }
+}
+
+impl<T> PayloadValidator for NoopPayloadValidator<T> {
+    type Payload = T;
+
+    fn validate(&self, _payload: &T) -> Result<()> {
+        Ok(())
+    }
+}
+
 /// While Consensus is managing proposed blocks, `StateComputer` is managing the results of the
 /// (speculative) execution of their payload.
 /// StateComputer is using proposed block ids for identifying the transactions.
@@ -27,8 +27,40 @@
 //! **Note**: The above example generates a private key using a private function intended only for
 //! testing purposes. Production code should generate the key according to the spec [draft-irtf-cfrg-bls-signature-00](https://tools.ietf.org/id/draft-irtf-cfrg-bls-signature-00.html#keygen).
 //!
-//! This module is not currently used, but could be included in the future for improved
-//! performance in consensus.
+//! The signatures of the same message can be aggregated into a single signature, which verifies
+//! against the aggregation of the public keys of the signers:
+//!
+//! ```
+//! use crypto::hash::{CryptoHasher, TestOnlyHasher};
+//! use crypto::{
+//!     bls12381::*,
+//!     traits::{Signature, SigningKey, Uniform},
+//! };
+//! use rand::{rngs::StdRng, SeedableRng};
+//!
+//! let mut hasher = TestOnlyHasher::default();
+//! hasher.write("Test message".as_bytes());
+//! let hashed_message = hasher.finish();
+//!
+//! let mut rng: StdRng = SeedableRng::from_seed([0; 32]);
+//! let private_keys: Vec<_> = (0..3)
+//!     .map(|_| BLS12381PrivateKey::generate_for_testing(&mut rng))
+//!     .collect();
+//! let public_keys: Vec<BLS12381PublicKey> = private_keys.iter().map(|key| key.into()).collect();
+//! let signatures: Vec<_> = private_keys
+//!     .iter()
+//!     .map(|key| key.sign_message(&hashed_message))
+//!     .collect();
+//! let signature = BLS12381Signature::aggregate(signatures.iter().collect()).unwrap();
+//! let public_key = BLS12381Signature::aggregate_public_keys(public_keys.iter().collect()).unwrap();
+//! assert!(signature.verify(&hashed_message, &public_key).is_ok());
+//! ```
+//! **Note**: Aggregating public keys is only safe for keys whose owners proved the possession of
+//! the corresponding private keys (e.g., when registering as validators), otherwise a rogue key
+//! crafted from the keys of others could forge an aggregated signature.
+//!
+//! This module is not currently used by default, but can be instantiated in consensus (see
+//! `types::crypto_proxies`) to certify a quorum with a single aggregated signature.
 
 use crate::{traits::*, HashValue};
 use bincode::{deserialize, serialize};
@@ -36,8 +68,8 @@ use core::convert::TryFrom;
 use crypto_derive::{Deref, SilentDebug, SilentDisplay};
 use failure::prelude::*;
 use pairing::{
-    bls12_381::{Fr, FrRepr},
-    PrimeField,
+    bls12_381::{Fr, FrRepr, G1Compressed, G2Compressed},
+    CurveAffine, CurveProjective, EncodedPoint, PrimeField,
 };
 use rand::Rng;
 use serde::{Deserialize, Serialize};
@@ -250,6 +282,48 @@ impl Signature for BLS12381Signature {
     fn to_bytes(&self) -> Vec<u8> {
         self.0.to_bytes().to_vec()
     }
+
+    /// The aggregated signature is the sum of the signatures.
+    fn aggregate(signatures: Vec<&Self>) -> Result<Self> {
+        ensure!(
+            !signatures.is_empty(),
+            "Cannot aggregate an empty set of signatures"
+        );
+        let points: Vec<_> = signatures.iter().map(|sig| sig.to_bytes()).collect();
+        let sum: G2Compressed = sum_compressed_points(points.iter().map(|point| &point[..]))?;
+        Ok(BLS12381Signature::try_from(sum.as_ref())?)
+    }
+
+    /// The aggregated public key is the sum of the public keys.
+    fn aggregate_public_keys(public_keys: Vec<&BLS12381PublicKey>) -> Result<BLS12381PublicKey> {
+        ensure!(
+            !public_keys.is_empty(),
+            "Cannot aggregate an empty set of public keys"
+        );
+        let points: Vec<_> = public_keys.iter().map(|key| key.to_bytes()).collect();
+        let sum: G1Compressed = sum_compressed_points(points.iter().map(|point| &point[..]))?;
+        Ok(BLS12381PublicKey::try_from(sum.as_ref())?)
+    }
+}
+
+/// Sums curve points in the compressed encoding that threshold_crypto uses for the public keys
+/// (G1) and the signatures (G2).
+fn sum_compressed_points<'a, E: EncodedPoint>(points: impl Iterator<Item = &'a [u8]>) -> Result<E> {
+    let mut sum = <E::Affine as CurveAffine>::Projective::zero();
+    for point in points {
+        ensure!(
+            point.len() == E::size(),
+            "Wrong length of a compressed curve point: {}",
+            point.len()
+        );
+        let mut compressed = E::empty();
+        compressed.as_mut().copy_from_slice(point);
+        let affine = compressed
+            .into_affine()
+            .map_err(|e| format_err!("Invalid curve point: {}", e))?;
+        sum.add_assign(&affine.into_projective());
+    }
+    Ok(E::from_affine(sum.into_affine()))
 }
 
 impl TryFrom<&[u8]> for BLS12381Signature {
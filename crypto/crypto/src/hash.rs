@@ -559,6 +559,11 @@ define_hasher! {
     (VoteDataHasher, VOTE_DATA_HASHER, b"VoteData")
 }
 
+define_hasher! {
+    /// The hasher used to compute the hash of a KeyRotation object.
+    (KeyRotationHasher, KEY_ROTATION_HASHER, b"KeyRotation")
+}
+
 define_hasher! {
     /// The hasher used to compute the hash of a ContractEvent object.
     (ContractEventHasher, CONTRACT_EVENT_HASHER, b"ContractEvent")
@@ -215,6 +215,20 @@ pub trait Signature:
         }
         Ok(())
     }
+
+    /// Aggregates the signatures of the same message by different keys into a single signature,
+    /// which verifies against the aggregation of the keys (see `aggregate_public_keys`).
+    /// Schemes that don't support aggregation return an error.
+    fn aggregate(_signatures: Vec<&Self>) -> Result<Self> {
+        bail!("The signature scheme does not support aggregation")
+    }
+
+    /// Aggregates the keys of the signers of an aggregated signature.
+    fn aggregate_public_keys(
+        _public_keys: Vec<&Self::VerifyingKeyMaterial>,
+    ) -> Result<Self::VerifyingKeyMaterial> {
+        bail!("The signature scheme does not support aggregation")
+    }
 }
 
 /// A type family for schemes which know how to generate key material from
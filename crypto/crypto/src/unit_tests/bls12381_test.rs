@@ -90,3 +90,40 @@ proptest! {
         prop_assert!(keypair.public_key.verify_signature(&hash, &deserialized).is_ok());
     }
 }
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(10))]
+
+    #[test]
+    fn test_aggregate_and_verify(
+        hash in any::<HashValue>(),
+        keypairs in proptest::collection::vec(
+            uniform_keypair_strategy::<BLS12381PrivateKey, BLS12381PublicKey>(),
+            2..5,
+        )
+    ) {
+        let signatures: Vec<_> = keypairs
+            .iter()
+            .map(|keypair| keypair.private_key.sign_message(&hash))
+            .collect();
+        let public_keys: Vec<_> = keypairs.iter().map(|keypair| &keypair.public_key).collect();
+        let signature = BLS12381Signature::aggregate(signatures.iter().collect()).unwrap();
+        let public_key = BLS12381Signature::aggregate_public_keys(public_keys.clone()).unwrap();
+        prop_assert!(signature.verify(&hash, &public_key).is_ok());
+
+        // The aggregated signature doesn't verify against the keys of a subset of the signers.
+        let subset_key =
+            BLS12381Signature::aggregate_public_keys(public_keys[1..].to_vec()).unwrap();
+        prop_assert!(signature.verify(&hash, &subset_key).is_err());
+        let subset_signature =
+            BLS12381Signature::aggregate(signatures[1..].iter().collect()).unwrap();
+        prop_assert!(subset_signature.verify(&hash, &public_key).is_err());
+        prop_assert!(subset_signature.verify(&hash, &subset_key).is_ok());
+    }
+}
+
+#[test]
+fn test_aggregate_nothing() {
+    assert!(BLS12381Signature::aggregate(vec![]).is_err());
+    assert!(BLS12381Signature::aggregate_public_keys(vec![]).is_err());
+}
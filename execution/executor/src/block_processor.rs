@@ -7,6 +7,7 @@ use crate::{
     Command, ExecutedState, ExecutedTrees, StateComputeResult, OP_COUNTERS,
 };
 use backoff::{ExponentialBackoff, Operation};
+use canonical_serialization::SimpleDeserializer;
 use config::config::VMConfig;
 use crypto::{
     hash::{CryptoHash, EventAccumulatorHasher},
@@ -25,6 +26,7 @@ use std::{
 };
 use storage_client::{StorageRead, StorageWrite, VerifiedStateView};
 use types::{
+    access_path::VALIDATOR_SET_ACCESS_PATH,
     account_address::AccountAddress,
     account_state_blob::AccountStateBlob,
     crypto_proxies::LedgerInfoWithSignatures,
@@ -33,6 +35,7 @@ use types::{
         SignedTransaction, TransactionInfo, TransactionListWithProof, TransactionOutput,
         TransactionPayload, TransactionStatus, TransactionToCommit, Version,
     },
+    validator_set::ValidatorSet,
     write_set::{WriteOp, WriteSet},
 };
 use vm_runtime::VMExecutor;
@@ -618,27 +621,28 @@ where
         }
 
         let (account_to_btree, account_to_proof) = state_view.into();
-        match Self::process_vm_outputs(
-            account_to_btree,
-            account_to_proof,
-            block_to_execute.transactions(),
-            vm_outputs,
-            &parent_trees,
-        ) {
-            Ok(output) => {
+        match Self::find_validator_set_change(&vm_outputs).and_then(|validators| {
+            Self::process_vm_outputs(
+                account_to_btree,
+                account_to_proof,
+                block_to_execute.transactions(),
+                vm_outputs,
+                &parent_trees,
+            )
+            .map(|output| (output, validators))
+        }) {
+            Ok((output, validators)) => {
                 let accu_root_hash = output.executed_trees().txn_accumulator().root_hash();
                 let version = output.executed_trees().txn_accumulator().num_leaves() - 1;
                 block_to_execute.set_output(output);
 
                 // Now that we have the root hash and execution status we can send the response to
                 // consensus.
-                // TODO: The VM will support a special transaction to set the validators for the
-                // next epoch that is part of a block execution.
                 let state_compute_result = StateComputeResult {
                     executed_state: ExecutedState {
                         state_id: accu_root_hash,
                         version,
-                        validators: None,
+                        validators,
                     },
                     compute_status: status,
                 };
@@ -670,6 +674,28 @@ where
         }
     }
 
+    /// Returns the validator set written by the last transaction of the block that updates the
+    /// ValidatorSet resource, if any: the validators of the next epoch once the block is committed.
+    /// The discarded transactions don't change the state.
+    fn find_validator_set_change(vm_outputs: &[TransactionOutput]) -> Result<Option<ValidatorSet>> {
+        let mut validator_set = None;
+        for vm_output in vm_outputs {
+            if let TransactionStatus::Keep(_) = vm_output.status() {
+                for (access_path, write_op) in vm_output.write_set() {
+                    if *access_path == *VALIDATOR_SET_ACCESS_PATH {
+                        match write_op {
+                            WriteOp::Value(blob) => {
+                                validator_set = Some(SimpleDeserializer::deserialize(blob)?)
+                            }
+                            WriteOp::Deletion => bail!("The validator set cannot be deleted"),
+                        }
+                    }
+                }
+            }
+        }
+        Ok(validator_set)
+    }
+
     /// Post-processing of what the VM outputs. Returns the entire block's output.
     fn process_vm_outputs(
         mut account_to_btree: HashMap<AccountAddress, BTreeMap<Vec<u8>, Vec<u8>>>,
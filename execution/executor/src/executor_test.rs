@@ -3,12 +3,13 @@
 
 use crate::{
     mock_vm::{
-        encode_mint_transaction, encode_transfer_transaction, MockVM, DISCARD_STATUS, KEEP_STATUS,
+        encode_mint_transaction, encode_reconfiguration_transaction, encode_transfer_transaction,
+        MockVM, DISCARD_STATUS, KEEP_STATUS,
     },
     Executor, OP_COUNTERS,
 };
 use config::config::{NodeConfig, NodeConfigHelpers};
-use crypto::{hash::GENESIS_BLOCK_ID, HashValue};
+use crypto::{ed25519::compat, hash::GENESIS_BLOCK_ID, x25519, HashValue};
 use futures::executor::block_on;
 use grpcio::{EnvBuilder, ServerBuilder};
 use proptest::prelude::*;
@@ -28,6 +29,8 @@ use types::{
     crypto_proxies::LedgerInfoWithSignatures,
     ledger_info::LedgerInfo,
     transaction::{SignedTransaction, TransactionListWithProof, Version},
+    validator_public_keys::ValidatorPublicKeys,
+    validator_set::ValidatorSet,
 };
 use vm_genesis::{encode_genesis_transaction, GENESIS_KEYPAIR};
 
@@ -202,6 +205,46 @@ fn test_executor_status() {
     );
 }
 
+#[test]
+fn test_executor_reconfiguration() {
+    let executor = TestExecutor::new();
+    let validator_set = ValidatorSet::new(
+        (0..3)
+            .map(|i| {
+                ValidatorPublicKeys::new(
+                    gen_address(i),
+                    compat::generate_keypair(None).1,
+                    compat::generate_keypair(None).1,
+                    x25519::compat::generate_keypair(None).1,
+                )
+            })
+            .collect(),
+    );
+
+    // A block that doesn't update the validator set doesn't reconfigure the validators.
+    let block_id = gen_block_id(1);
+    let response = block_on(executor.execute_block(
+        vec![encode_mint_transaction(gen_address(0), 100)],
+        *GENESIS_BLOCK_ID,
+        block_id,
+    ))
+    .unwrap()
+    .unwrap();
+    assert_eq!(response.executed_state.validators, None);
+
+    let response = block_on(executor.execute_block(
+        vec![
+            encode_reconfiguration_transaction(gen_address(0), &validator_set),
+            encode_mint_transaction(gen_address(1), 100),
+        ],
+        block_id,
+        gen_block_id(2),
+    ))
+    .unwrap()
+    .unwrap();
+    assert_eq!(response.executed_state.validators, Some(validator_set));
+}
+
 #[test]
 fn test_executor_one_block() {
     let executor = TestExecutor::new();
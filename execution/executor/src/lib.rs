@@ -87,8 +87,8 @@ pub struct ExecutedState {
     /// Version of after executing a proposed block.  This state must be persisted to ensure
     /// that on restart that the version is calculated correctly
     pub version: Version,
-    /// If set, this is the validator set that should be changed to if this block is committed.
-    /// TODO [Reconfiguration] the validators are currently ignored, no reconfiguration yet.
+    /// If set, this is the validator set that should be changed to if this block is committed,
+    /// i.e., the block updates the ValidatorSet resource.
     pub validators: Option<ValidatorSet>,
 }
 
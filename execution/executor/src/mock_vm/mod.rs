@@ -4,20 +4,23 @@
 #[cfg(test)]
 mod mock_vm_test;
 
+use canonical_serialization::SimpleSerializer;
 use config::config::VMConfig;
 use crypto::ed25519::compat;
 use lazy_static::lazy_static;
 use state_view::StateView;
 use std::collections::HashMap;
 use types::{
-    access_path::AccessPath,
+    access_path::{AccessPath, VALIDATOR_SET_ACCESS_PATH},
     account_address::{AccountAddress, ADDRESS_LENGTH},
+    byte_array::ByteArray,
     contract_event::ContractEvent,
     event::EventKey,
     transaction::{
         RawTransaction, Script, SignedTransaction, TransactionArgument, TransactionOutput,
         TransactionPayload, TransactionStatus,
     },
+    validator_set::ValidatorSet,
     vm_error::{StatusCode, VMStatus},
     write_set::{WriteOp, WriteSet, WriteSetMut},
 };
@@ -34,6 +37,10 @@ enum Transaction {
         recipient: AccountAddress,
         amount: u64,
     },
+    Reconfiguration {
+        sender: AccountAddress,
+        validator_set: Vec<u8>,
+    },
 }
 
 lazy_static! {
@@ -130,6 +137,20 @@ impl VMExecutor for MockVM {
                         TransactionStatus::Keep(VMStatus::new(StatusCode::EXECUTED)),
                     ));
                 }
+                Transaction::Reconfiguration {
+                    sender,
+                    validator_set,
+                } => {
+                    let new_seqnum = read_seqnum(&output_cache, state_view, sender) + 1;
+                    output_cache.insert(seqnum_ap(sender), new_seqnum);
+                    let write_set = gen_reconfiguration_writeset(sender, new_seqnum, validator_set);
+                    outputs.push(TransactionOutput::new(
+                        write_set,
+                        vec![],
+                        0,
+                        KEEP_STATUS.clone(),
+                    ));
+                }
             }
         }
 
@@ -241,6 +262,25 @@ fn gen_payment_writeset(
         .expect("payment write set should be valid")
 }
 
+fn gen_reconfiguration_writeset(
+    sender: AccountAddress,
+    seqnum: u64,
+    validator_set: Vec<u8>,
+) -> WriteSet {
+    let mut write_set = WriteSetMut::default();
+    write_set.push((
+        seqnum_ap(sender),
+        WriteOp::Value(seqnum.to_le_bytes().to_vec()),
+    ));
+    write_set.push((
+        VALIDATOR_SET_ACCESS_PATH.clone(),
+        WriteOp::Value(validator_set),
+    ));
+    write_set
+        .freeze()
+        .expect("reconfiguration write set should be valid")
+}
+
 fn gen_events(sender: AccountAddress) -> Vec<ContractEvent> {
     vec![ContractEvent::new(
         EventKey::new_from_address(&sender, 0),
@@ -272,6 +312,22 @@ pub fn encode_transfer_transaction(
     encode_transaction(sender, encode_transfer_program(recipient, amount))
 }
 
+/// A transaction replacing the validator set of the next epoch.
+pub fn encode_reconfiguration_transaction(
+    sender: AccountAddress,
+    validator_set: &ValidatorSet,
+) -> SignedTransaction {
+    let validator_set = SimpleSerializer::<Vec<u8>>::serialize(validator_set)
+        .expect("Failed to serialize the validator set.");
+    let program = Script::new(
+        vec![],
+        vec![TransactionArgument::ByteArray(ByteArray::new(
+            validator_set,
+        ))],
+    );
+    encode_transaction(sender, program)
+}
+
 fn encode_transaction(sender: AccountAddress, program: Script) -> SignedTransaction {
     let raw_transaction =
         RawTransaction::new_script(sender, 0, program, 0, 0, std::time::Duration::from_secs(0));
@@ -291,8 +347,15 @@ fn decode_transaction(txn: &SignedTransaction) -> Transaction {
             match script.args().len() {
                 1 => match script.args()[0] {
                     TransactionArgument::U64(amount) => Transaction::Mint { sender, amount },
+                    TransactionArgument::ByteArray(ref validator_set) => {
+                        Transaction::Reconfiguration {
+                            sender,
+                            validator_set: validator_set.as_bytes().to_vec(),
+                        }
+                    }
                     _ => unimplemented!(
-                        "Only one integer argument is allowed for mint transactions."
+                        "Only one integer argument is allowed for mint transactions, or one byte \
+                         array argument for reconfiguration transactions."
                     ),
                 },
                 2 => match (&script.args()[0], &script.args()[1]) {
@@ -24,6 +24,11 @@ pub fn mint() -> &'static str {
     include_str!("../transaction_scripts/mint.mvir")
 }
 
+/// Returns the source code for the remove-validator transaction script.
+pub fn remove_validator() -> &'static str {
+    include_str!("../transaction_scripts/remove_validator.mvir")
+}
+
 lazy_static! {
     pub static ref PEER_TO_PEER_TRANSFER_TXN_BODY: Program =
         { parse_program(peer_to_peer()).unwrap() };
@@ -41,3 +46,7 @@ lazy_static! {
 lazy_static! {
     pub static ref MINT_TXN_BODY: Program = parse_program(mint()).unwrap();
 }
+
+lazy_static! {
+    pub static ref REMOVE_VALIDATOR_TXN_BODY: Program = parse_program(remove_validator()).unwrap();
+}
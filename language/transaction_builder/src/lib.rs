@@ -9,7 +9,7 @@ use stdlib::{
     stdlib_modules,
     transaction_scripts::{
         CREATE_ACCOUNT_TXN_BODY, MINT_TXN_BODY, PEER_TO_PEER_TRANSFER_TXN_BODY,
-        ROTATE_AUTHENTICATION_KEY_TXN_BODY,
+        REMOVE_VALIDATOR_TXN_BODY, ROTATE_AUTHENTICATION_KEY_TXN_BODY,
     },
 };
 use types::{
@@ -26,6 +26,7 @@ lazy_static! {
     static ref ROTATE_AUTHENTICATION_KEY_TXN: Vec<u8> =
         { compile_script(&ROTATE_AUTHENTICATION_KEY_TXN_BODY) };
     static ref MINT_TXN: Vec<u8> = { compile_script(&MINT_TXN_BODY) };
+    static ref REMOVE_VALIDATOR_TXN: Vec<u8> = { compile_script(&REMOVE_VALIDATOR_TXN_BODY) };
 }
 
 fn compile_script(body: &ast::Program) -> Vec<u8> {
@@ -126,6 +127,15 @@ pub fn encode_mint_script(sender: &AccountAddress, amount: u64) -> Script {
     )
 }
 
+/// Encode a program removing `validator` from the validator set at the next reconfiguration.
+/// Fails unless the sender is the association and `validator` is a current validator.
+pub fn encode_remove_validator_script(validator: &AccountAddress) -> Script {
+    Script::new(
+        REMOVE_VALIDATOR_TXN.clone(),
+        vec![TransactionArgument::Address(*validator)],
+    )
+}
+
 /// Returns a user friendly mnemonic for the transaction type if the transaction is
 /// for a known, white listed, transaction.
 pub fn get_transaction_name(code: &[u8]) -> String {
@@ -137,6 +147,8 @@ pub fn get_transaction_name(code: &[u8]) -> String {
         return "mint_transaction".to_string();
     } else if code == &ROTATE_AUTHENTICATION_KEY_TXN[..] {
         return "rotate_authentication_key_transaction".to_string();
+    } else if code == &REMOVE_VALIDATOR_TXN[..] {
+        return "remove_validator_transaction".to_string();
     }
     "<unknown transaction>".to_string()
 }
@@ -147,6 +159,7 @@ pub fn allowing_script_hashes() -> Vec<[u8; SCRIPT_HASH_LENGTH]> {
         PEER_TO_PEER_TXN.clone(),
         ROTATE_AUTHENTICATION_KEY_TXN.clone(),
         CREATE_ACCOUNT_TXN.clone(),
+        REMOVE_VALIDATOR_TXN.clone(),
     ]
     .into_iter()
     .map(|s| *HashValue::from_sha3_256(&s).as_ref())
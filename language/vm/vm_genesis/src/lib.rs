@@ -192,6 +192,18 @@ pub fn encode_genesis_transaction_with_validator(
     public_key: Ed25519PublicKey,
     validator_set: ValidatorSet,
 ) -> SignatureCheckedTransaction {
+    encode_genesis_raw_transaction(&public_key, validator_set)
+        .sign(private_key, public_key)
+        .unwrap()
+}
+
+/// Returns the unsigned genesis transaction. The transaction is deterministic: given the same
+/// association key and validator set, every party computes the same transaction, which allows
+/// verifying a genesis built by somebody else without knowing the association private key.
+pub fn encode_genesis_raw_transaction(
+    public_key: &Ed25519PublicKey,
+    validator_set: ValidatorSet,
+) -> RawTransaction {
     const INIT_BALANCE: u64 = 1_000_000_000;
 
     // Compile the needed stdlib modules.
@@ -200,7 +212,7 @@ pub fn encode_genesis_transaction_with_validator(
     let state_view = FakeStateView;
     let vm_cache = VMModuleCache::new(&arena);
     let genesis_addr = account_config::association_address();
-    let genesis_auth_key = ByteArray::new(AccountAddress::from_public_key(&public_key).to_vec());
+    let genesis_auth_key = ByteArray::new(AccountAddress::from_public_key(public_key).to_vec());
 
     let genesis_write_set = {
         let fake_fetcher = FakeFetcher::new(modules.iter().map(|m| m.as_inner().clone()).collect());
@@ -313,7 +325,5 @@ pub fn encode_genesis_transaction_with_validator(
                 .into_mut()
         }
     };
-    let transaction =
-        RawTransaction::new_write_set(genesis_addr, 0, genesis_write_set.freeze().unwrap());
-    transaction.sign(private_key, public_key).unwrap()
+    RawTransaction::new_write_set(genesis_addr, 0, genesis_write_set.freeze().unwrap())
 }
@@ -0,0 +1,151 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! The health checks of the subsystems of the node, reported by the debug interface.
+
+use debug_interface::{
+    health::{Subsystem, SubsystemHealth},
+    node_debug_service::SubsystemHealthCheck,
+};
+use grpcio::CallOption;
+use mempool::proto::{mempool::HealthCheckRequest, mempool_grpc::MempoolClient};
+use std::{
+    fs,
+    path::PathBuf,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+const COMMITTED_ROUND_METRIC: &str = "consensus{op=committed_blocks_count}";
+const COMMITTED_VERSION_METRIC: &str = "state_sync_gauge{op=committed_version}";
+const TARGET_VERSION_METRIC: &str = "state_sync_gauge{op=target_version}";
+/// Consensus is stuck if the committed round didn't advance for that long.
+const CONSENSUS_STALL_TIMEOUT: Duration = Duration::from_secs(30);
+const MEMPOOL_HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(1);
+/// The file written and removed in the storage dir to check that it is writable.
+const STORAGE_PROBE_FILE: &str = ".health_probe";
+
+// The value of the given metric of the node, if it has been reported.
+fn metric(name: &str) -> Option<i64> {
+    metrics::get_all_metrics()
+        .get(name)
+        .and_then(|value| value.parse::<f64>().ok())
+        .map(|value| value as i64)
+}
+
+fn health(subsystem: Subsystem, healthy: bool, detail: String) -> SubsystemHealth {
+    SubsystemHealth {
+        subsystem,
+        healthy,
+        detail,
+    }
+}
+
+/// Mempool answers its health check requests.
+pub struct MempoolHealthCheck {
+    client: MempoolClient,
+}
+
+impl MempoolHealthCheck {
+    pub fn new(client: MempoolClient) -> Self {
+        Self { client }
+    }
+}
+
+impl SubsystemHealthCheck for MempoolHealthCheck {
+    fn check(&self) -> SubsystemHealth {
+        let call_option = CallOption::default().timeout(MEMPOOL_HEALTH_CHECK_TIMEOUT);
+        match self
+            .client
+            .health_check_opt(&HealthCheckRequest::new(), call_option)
+        {
+            Ok(response) if response.get_is_healthy() => {
+                health(Subsystem::Mempool, true, "alive".to_string())
+            }
+            Ok(_) => health(
+                Subsystem::Mempool,
+                false,
+                "reported itself unhealthy".to_string(),
+            ),
+            Err(e) => health(
+                Subsystem::Mempool,
+                false,
+                format!("health check failed: {:?}", e),
+            ),
+        }
+    }
+}
+
+/// Consensus committed a new round recently.
+pub struct ConsensusProgressCheck {
+    // The last committed round seen and when it was first seen, the start of the node initially.
+    last_progress: Mutex<(Option<i64>, Instant)>,
+}
+
+impl Default for ConsensusProgressCheck {
+    fn default() -> Self {
+        Self {
+            last_progress: Mutex::new((None, Instant::now())),
+        }
+    }
+}
+
+impl SubsystemHealthCheck for ConsensusProgressCheck {
+    fn check(&self) -> SubsystemHealth {
+        let round = metric(COMMITTED_ROUND_METRIC);
+        let mut last_progress = self.last_progress.lock().unwrap();
+        if round > last_progress.0 {
+            *last_progress = (round, Instant::now());
+        }
+        let since = last_progress.1.elapsed();
+        health(
+            Subsystem::Consensus,
+            since < CONSENSUS_STALL_TIMEOUT,
+            format!("committed round {} {:?} ago", round.unwrap_or(0), since),
+        )
+    }
+}
+
+/// State sync committed the version it targets.
+pub struct StateSyncHealthCheck;
+
+impl SubsystemHealthCheck for StateSyncHealthCheck {
+    fn check(&self) -> SubsystemHealth {
+        let committed = metric(COMMITTED_VERSION_METRIC).unwrap_or(0);
+        let target = metric(TARGET_VERSION_METRIC).unwrap_or(0);
+        health(
+            Subsystem::StateSync,
+            committed >= target,
+            format!("committed version {} of target {}", committed, target),
+        )
+    }
+}
+
+/// The storage dir of the node is writable.
+pub struct StorageHealthCheck {
+    dir: PathBuf,
+}
+
+impl StorageHealthCheck {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+}
+
+impl SubsystemHealthCheck for StorageHealthCheck {
+    fn check(&self) -> SubsystemHealth {
+        let probe = self.dir.join(STORAGE_PROBE_FILE);
+        match fs::write(&probe, b"").and_then(|()| fs::remove_file(&probe)) {
+            Ok(()) => health(
+                Subsystem::Storage,
+                true,
+                format!("{:?} is writable", self.dir),
+            ),
+            Err(e) => health(
+                Subsystem::Storage,
+                false,
+                format!("{:?} is not writable: {}", self.dir, e),
+            ),
+        }
+    }
+}
@@ -1,6 +1,9 @@
 // Copyright (c) The Libra Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::health::{
+    ConsensusProgressCheck, MempoolHealthCheck, StateSyncHealthCheck, StorageHealthCheck,
+};
 use admission_control_proto::proto::admission_control_grpc::{
     create_admission_control, AdmissionControlClient,
 };
@@ -129,11 +132,10 @@ fn setup_executor(config: &NodeConfig) -> Arc<Executor<MoveVM>> {
     ))
 }
 
-fn setup_debug_interface(config: &NodeConfig) -> ::grpcio::Server {
+fn setup_debug_interface(config: &NodeConfig, service: NodeDebugService) -> ::grpcio::Server {
     let env = Arc::new(EnvBuilder::new().name_prefix("grpc-debug-").build());
     // Start Debug interface
-    let debug_service =
-        node_debug_interface_grpc::create_node_debug_interface(NodeDebugService::new());
+    let debug_service = node_debug_interface_grpc::create_node_debug_interface(service);
     ::grpcio::ServerBuilder::new(env)
         .register_service(debug_service)
         .bind(
@@ -276,7 +278,14 @@ pub fn setup_environment(node_config: &mut NodeConfig) -> (AdmissionControlClien
         }
     }
 
-    let debug_if = ServerHandle::setup(setup_debug_interface(&node_config));
+    // The service is shared with the server, such that consensus can serve its commit
+    // certificates once started.
+    let debug_service = NodeDebugService::new();
+    let debug_if = ServerHandle::setup(setup_debug_interface(&node_config, debug_service.clone()));
+    debug_service.add_health_check(Arc::new(StorageHealthCheck::new(
+        node_config.storage.dir.clone(),
+    )));
+    debug_service.add_health_check(Arc::new(StateSyncHealthCheck));
 
     let metrics_port = node_config.debug_interface.metrics_server_port;
     let metric_host = node_config.debug_interface.address.clone();
@@ -320,6 +329,13 @@ pub fn setup_environment(node_config: &mut NodeConfig) -> (AdmissionControlClien
             mempool_network_events,
         ));
         debug!("Mempool started in {} ms", instant.elapsed().as_millis());
+        let mempool_env = Arc::new(EnvBuilder::new().name_prefix("grpc-health-mem-").build());
+        debug_service.add_health_check(Arc::new(MempoolHealthCheck::new(MempoolClient::new(
+            ChannelBuilder::new(mempool_env).connect(&format!(
+                "localhost:{}",
+                node_config.mempool.mempool_service_port
+            )),
+        ))));
 
         // Initialize and start consensus.
         instant = Instant::now();
@@ -333,6 +349,13 @@ pub fn setup_environment(node_config: &mut NodeConfig) -> (AdmissionControlClien
         consensus_provider
             .start()
             .expect("Failed to start consensus. Can't proceed.");
+        if let Some(provider) = consensus_provider.commit_certificate_provider() {
+            debug_service.set_commit_certificate_provider(provider);
+        }
+        if let Some(provider) = consensus_provider.consensus_state_provider() {
+            debug_service.set_consensus_state_provider(provider);
+        }
+        debug_service.add_health_check(Arc::new(ConsensusProgressCheck::default()));
         consensus = Some(consensus_provider);
         debug!("Consensus started in {} ms", instant.elapsed().as_millis());
     }
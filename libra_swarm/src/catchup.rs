@@ -0,0 +1,282 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Report of the validators catching up to a committed round, and of the full nodes catching up
+//! to the version committed by the validators, built by
+//! `LibraSwarm::wait_for_all_nodes_to_catchup`. Report of the nodes progressing to a committed
+//! round or version, built by `LibraSwarm::wait_for_round` and `LibraSwarm::wait_for_version`.
+
+use std::{collections::HashMap, fmt, process::ExitStatus, time::Duration};
+
+#[cfg(test)]
+#[path = "catchup_test.rs"]
+mod catchup_test;
+
+/// The committed round the validators have to catch up to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CatchupTarget {
+    /// A specific committed round.
+    Round(i64),
+    /// The highest committed round across the validators at the time of the call, plus the
+    /// given number of rounds.
+    MaxPlus(i64),
+}
+
+impl Default for CatchupTarget {
+    fn default() -> Self {
+        CatchupTarget::MaxPlus(0)
+    }
+}
+
+/// The committed round or version the nodes waited for have to reach.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProgressTarget {
+    /// A specific value.
+    Value(i64),
+    /// The highest value across the nodes at the time of the call, plus the given amount.
+    MaxPlus(i64),
+    /// The value of each node at the time of the call, plus the given amount, e.g. 20 more
+    /// rounds of progress on every node.
+    EachPlus(i64),
+}
+
+impl ProgressTarget {
+    /// The target of every node, given their values at the time of the call. A node that
+    /// doesn't report its value is assumed to start from 0.
+    pub fn targets(
+        &self,
+        peer_ids: &[String],
+        start_values: &HashMap<String, i64>,
+    ) -> HashMap<String, i64> {
+        let max = start_values.values().cloned().max().unwrap_or(0);
+        peer_ids
+            .iter()
+            .map(|peer_id| {
+                let target = match self {
+                    ProgressTarget::Value(value) => *value,
+                    ProgressTarget::MaxPlus(amount) => max + amount,
+                    ProgressTarget::EachPlus(amount) => {
+                        start_values.get(peer_id).cloned().unwrap_or(0) + amount
+                    }
+                };
+                (peer_id.clone(), target)
+            })
+            .collect()
+    }
+}
+
+/// The reason a node failed to catch up.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CatchupFailure {
+    /// The node didn't reach the target before the timeout.
+    Timeout,
+    /// The node never reported its committed round (its committed version for a full node).
+    RoundUnknown,
+    /// The node process has terminated.
+    Crashed(ExitStatus),
+}
+
+impl fmt::Display for CatchupFailure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CatchupFailure::Timeout => write!(f, "timeout"),
+            CatchupFailure::RoundUnknown => write!(f, "committed round unknown"),
+            CatchupFailure::Crashed(status) => write!(f, "crashed with {}", status),
+        }
+    }
+}
+
+/// Catch-up progress of a single node. The rounds of a full node are committed versions.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NodeCatchup {
+    /// The committed round reported when the wait started.
+    pub start_round: Option<i64>,
+    /// The last committed round reported.
+    pub end_round: Option<i64>,
+    /// The time it took to reach the target, or the reason the target was not reached.
+    pub outcome: std::result::Result<Duration, CatchupFailure>,
+}
+
+/// Catch-up progress of all the validators and full nodes in the swarm, keyed by peer id.
+#[derive(Clone, Debug)]
+pub struct CatchupReport {
+    target_round: i64,
+    nodes: HashMap<String, NodeCatchup>,
+    // The version the full nodes have to sync, None if the swarm has no full nodes.
+    target_version: Option<i64>,
+    full_nodes: HashMap<String, NodeCatchup>,
+}
+
+impl CatchupReport {
+    pub fn new(target_round: i64, nodes: HashMap<String, NodeCatchup>) -> Self {
+        Self {
+            target_round,
+            nodes,
+            target_version: None,
+            full_nodes: HashMap::new(),
+        }
+    }
+
+    /// Adds the progress of the full nodes catching up to the given committed version.
+    pub fn with_full_nodes(
+        mut self,
+        target_version: i64,
+        full_nodes: HashMap<String, NodeCatchup>,
+    ) -> Self {
+        self.target_version = Some(target_version);
+        self.full_nodes = full_nodes;
+        self
+    }
+
+    pub fn target_round(&self) -> i64 {
+        self.target_round
+    }
+
+    pub fn target_version(&self) -> Option<i64> {
+        self.target_version
+    }
+
+    /// The progress of the validators.
+    pub fn nodes(&self) -> &HashMap<String, NodeCatchup> {
+        &self.nodes
+    }
+
+    /// The progress of the full nodes.
+    pub fn full_nodes(&self) -> &HashMap<String, NodeCatchup> {
+        &self.full_nodes
+    }
+
+    /// The progress of the given validator or full node.
+    pub fn get(&self, peer_id: &str) -> Option<&NodeCatchup> {
+        self.nodes
+            .get(peer_id)
+            .or_else(|| self.full_nodes.get(peer_id))
+    }
+
+    fn all_nodes(&self) -> impl Iterator<Item = (&String, &NodeCatchup)> {
+        self.nodes.iter().chain(self.full_nodes.iter())
+    }
+
+    /// True if all the nodes have caught up.
+    pub fn is_success(&self) -> bool {
+        self.all_nodes().all(|(_, node)| node.outcome.is_ok())
+    }
+
+    /// The nodes that didn't catch up, with the reason.
+    pub fn failures(&self) -> impl Iterator<Item = (&String, &CatchupFailure)> {
+        self.all_nodes()
+            .filter_map(|(peer_id, node)| node.outcome.as_ref().err().map(|e| (peer_id, e)))
+    }
+
+    /// The time it took the slowest node to catch up, None if some node failed.
+    pub fn max_catchup_time(&self) -> Option<Duration> {
+        self.all_nodes()
+            .map(|(_, node)| node.outcome.as_ref().ok().cloned())
+            .collect::<Option<Vec<_>>>()
+            .map(|times| times.into_iter().max().unwrap_or_default())
+    }
+}
+
+fn round_or_unknown(round: Option<i64>) -> String {
+    round.map_or("?".to_string(), |round| round.to_string())
+}
+
+fn fmt_nodes(
+    f: &mut fmt::Formatter,
+    kind: &str,
+    nodes: &HashMap<String, NodeCatchup>,
+) -> fmt::Result {
+    let mut peer_ids: Vec<&String> = nodes.keys().collect();
+    peer_ids.sort();
+    writeln!(f, "{:<66} {:>8} {:>8}  {}", kind, "start", "end", "result")?;
+    for peer_id in peer_ids {
+        let node = &nodes[peer_id];
+        let result = match &node.outcome {
+            Ok(time) => format!("caught up in {:?}", time),
+            Err(e) => format!("failed: {}", e),
+        };
+        writeln!(
+            f,
+            "{:<66} {:>8} {:>8}  {}",
+            peer_id,
+            round_or_unknown(node.start_round),
+            round_or_unknown(node.end_round),
+            result
+        )?;
+    }
+    Ok(())
+}
+
+/// Progress of the nodes waited for to reach a committed round or version, keyed by peer id.
+#[derive(Clone, Debug)]
+pub struct ProgressReport {
+    // The metric the progress is measured with.
+    metric: String,
+    targets: HashMap<String, i64>,
+    nodes: HashMap<String, NodeCatchup>,
+}
+
+impl ProgressReport {
+    pub fn new<S: Into<String>>(
+        metric: S,
+        targets: HashMap<String, i64>,
+        nodes: HashMap<String, NodeCatchup>,
+    ) -> Self {
+        Self {
+            metric: metric.into(),
+            targets,
+            nodes,
+        }
+    }
+
+    /// The value the given node had to reach.
+    pub fn target(&self, peer_id: &str) -> Option<i64> {
+        self.targets.get(peer_id).cloned()
+    }
+
+    pub fn nodes(&self) -> &HashMap<String, NodeCatchup> {
+        &self.nodes
+    }
+
+    /// True if all the nodes have reached their target.
+    pub fn is_success(&self) -> bool {
+        self.nodes.values().all(|node| node.outcome.is_ok())
+    }
+
+    /// The nodes that didn't reach their target, sorted.
+    pub fn lagging(&self) -> Vec<&str> {
+        let mut lagging: Vec<&str> = self
+            .nodes
+            .iter()
+            .filter(|(_, node)| node.outcome.is_err())
+            .map(|(peer_id, _)| peer_id.as_str())
+            .collect();
+        lagging.sort();
+        lagging
+    }
+}
+
+impl fmt::Display for ProgressReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut targets: Vec<_> = self.targets.values().cloned().collect();
+        targets.sort();
+        targets.dedup();
+        match targets.as_slice() {
+            [target] => writeln!(f, "Progress of {} to {}", self.metric, target)?,
+            _ => writeln!(f, "Progress of {} to per node targets", self.metric)?,
+        }
+        fmt_nodes(f, "node", &self.nodes)
+    }
+}
+
+impl fmt::Display for CatchupReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Catch up to committed round {}", self.target_round)?;
+        fmt_nodes(f, "validator", &self.nodes)?;
+        if let Some(target_version) = self.target_version {
+            writeln!(f, "Catch up to committed version {}", target_version)?;
+            fmt_nodes(f, "full node", &self.full_nodes)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,135 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::catchup::{CatchupFailure, CatchupReport, NodeCatchup, ProgressReport, ProgressTarget};
+use std::{collections::HashMap, time::Duration};
+
+fn peer_ids(peer_ids: &[&str]) -> Vec<String> {
+    peer_ids.iter().map(|peer_id| peer_id.to_string()).collect()
+}
+
+fn values(values: &[(&str, i64)]) -> HashMap<String, i64> {
+    values
+        .iter()
+        .map(|(peer_id, value)| (peer_id.to_string(), *value))
+        .collect()
+}
+
+fn caught_up(secs: u64) -> NodeCatchup {
+    NodeCatchup {
+        start_round: Some(1),
+        end_round: Some(10),
+        outcome: Ok(Duration::from_secs(secs)),
+    }
+}
+
+fn failed(failure: CatchupFailure) -> NodeCatchup {
+    NodeCatchup {
+        start_round: None,
+        end_round: None,
+        outcome: Err(failure),
+    }
+}
+
+#[test]
+fn test_progress_targets() {
+    let nodes = peer_ids(&["a", "b", "c"]);
+    // The last node doesn't report its value.
+    let start_values = values(&[("a", 5), ("b", 8)]);
+
+    assert_eq!(
+        ProgressTarget::Value(20).targets(&nodes, &start_values),
+        values(&[("a", 20), ("b", 20), ("c", 20)])
+    );
+    assert_eq!(
+        ProgressTarget::MaxPlus(2).targets(&nodes, &start_values),
+        values(&[("a", 10), ("b", 10), ("c", 10)])
+    );
+    assert_eq!(
+        ProgressTarget::EachPlus(2).targets(&nodes, &start_values),
+        values(&[("a", 7), ("b", 10), ("c", 2)])
+    );
+    assert_eq!(
+        ProgressTarget::MaxPlus(3).targets(&nodes, &HashMap::new()),
+        values(&[("a", 3), ("b", 3), ("c", 3)])
+    );
+}
+
+#[test]
+fn test_catchup_report() {
+    let validators: HashMap<String, NodeCatchup> = vec![
+        ("a".to_string(), caught_up(3)),
+        ("b".to_string(), caught_up(5)),
+    ]
+    .into_iter()
+    .collect();
+    let report = CatchupReport::new(10, validators.clone());
+    assert!(report.is_success());
+    assert_eq!(report.failures().count(), 0);
+    assert_eq!(report.max_catchup_time(), Some(Duration::from_secs(5)));
+    assert_eq!(report.target_version(), None);
+
+    // A full node failing fails the whole report.
+    let full_nodes: HashMap<String, NodeCatchup> = vec![
+        ("f1".to_string(), caught_up(7)),
+        ("f2".to_string(), failed(CatchupFailure::RoundUnknown)),
+    ]
+    .into_iter()
+    .collect();
+    let report = CatchupReport::new(10, validators).with_full_nodes(100, full_nodes);
+    assert_eq!(report.target_version(), Some(100));
+    assert!(!report.is_success());
+    assert_eq!(report.max_catchup_time(), None);
+    let failures: Vec<_> = report.failures().collect();
+    assert_eq!(
+        failures,
+        vec![(&"f2".to_string(), &CatchupFailure::RoundUnknown)]
+    );
+    assert_eq!(report.get("a"), Some(&caught_up(3)));
+    assert_eq!(report.get("f1"), Some(&caught_up(7)));
+    assert_eq!(report.get("x"), None);
+
+    let display = report.to_string();
+    assert!(display.contains("Catch up to committed round 10"));
+    assert!(display.contains("Catch up to committed version 100"));
+    assert!(display.contains("failed: committed round unknown"));
+}
+
+#[test]
+fn test_empty_catchup_report() {
+    let report = CatchupReport::new(0, HashMap::new());
+    assert!(report.is_success());
+    assert_eq!(report.max_catchup_time(), Some(Duration::from_secs(0)));
+}
+
+#[test]
+fn test_progress_report() {
+    let nodes: HashMap<String, NodeCatchup> = vec![
+        ("c".to_string(), failed(CatchupFailure::Timeout)),
+        ("a".to_string(), caught_up(1)),
+        ("b".to_string(), failed(CatchupFailure::Timeout)),
+    ]
+    .into_iter()
+    .collect();
+    let report = ProgressReport::new(
+        "committed round",
+        values(&[("a", 20), ("b", 20), ("c", 20)]),
+        nodes.clone(),
+    );
+    assert!(!report.is_success());
+    assert_eq!(report.lagging(), vec!["b", "c"]);
+    assert_eq!(report.target("a"), Some(20));
+    assert_eq!(report.target("x"), None);
+    assert!(report
+        .to_string()
+        .starts_with("Progress of committed round to 20\n"));
+
+    let report = ProgressReport::new(
+        "committed round",
+        values(&[("a", 20), ("b", 22), ("c", 20)]),
+        nodes,
+    );
+    assert!(report
+        .to_string()
+        .starts_with("Progress of committed round to per node targets\n"));
+}
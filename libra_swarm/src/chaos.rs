@@ -0,0 +1,328 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Background chaos scheduler for long-running swarms.
+//!
+//! Every interval the scheduler draws random disruptions of the validators: a validator is
+//! killed and restarted after some downtime, or paused (SIGSTOP) and resumed (SIGCONT) after
+//! some downtime. At most f validators are down at any time by default, counting the validators
+//! stopped or paused by the test and the ones the scheduler failed to restore, such that a quorum
+//! remains available and the tests can assert that the commits keep progressing. Every action is
+//! recorded for the post-mortem of a failed test. The disrupted validators are restored when the
+//! scheduler is stopped, a failed restore is retried at the next interval.
+
+use crate::{
+    events::{EventBus, SwarmEvent},
+    liveness::{sleep_unless_stopped, SharedNodeProcess},
+    swarm::NodeLauncher,
+};
+use logger::prelude::*;
+use rand::{rngs::StdRng, seq::SliceRandom, FromEntropy, Rng, SeedableRng};
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+#[cfg(test)]
+#[path = "chaos_test.rs"]
+mod chaos_test;
+
+/// The rates and bounds of the disruptions.
+#[derive(Clone, Debug)]
+pub struct ChaosPolicy {
+    /// How often the disruptions are drawn
+    pub interval: Duration,
+    /// Probability that a validator is killed at every interval
+    pub kill_probability: f64,
+    /// Probability that a validator is paused at every interval, the remote validators are
+    /// never paused
+    pub pause_probability: f64,
+    /// The downtime of a disrupted validator is drawn uniformly between the min and the max
+    pub min_downtime: Duration,
+    pub max_downtime: Duration,
+    /// Max number of validators down at the same time, including the validators stopped by the
+    /// test, f out of 3f + 1 if None
+    pub max_disrupted: Option<usize>,
+    /// Seed of the schedule, such that a failed run can be replayed, random if None
+    pub seed: Option<u64>,
+}
+
+impl Default for ChaosPolicy {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(10),
+            kill_probability: 0.2,
+            pause_probability: 0.2,
+            min_downtime: Duration::from_secs(5),
+            max_downtime: Duration::from_secs(20),
+            max_disrupted: None,
+            seed: None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChaosAction {
+    Kill,
+    Restart,
+    Pause,
+    Resume,
+}
+
+/// An action of the scheduler.
+#[derive(Clone, Debug)]
+pub struct ChaosEvent {
+    /// The time since the scheduler was started
+    pub time: Duration,
+    pub peer_id: String,
+    pub action: ChaosAction,
+    /// The reason the action failed, if it did
+    pub error: Option<String>,
+}
+
+impl fmt::Display for ChaosEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "[{:?}] {:?} {}", self.time, self.action, self.peer_id)?;
+        if let Some(error) = &self.error {
+            write!(f, " failed: {}", error)?;
+        }
+        Ok(())
+    }
+}
+
+/// A validator disrupted by the scheduler.
+pub(crate) struct ChaosNode {
+    pub peer_id: String,
+    pub process: SharedNodeProcess,
+    // Whether the node is paused by the swarm, such a node is down but is not disrupted.
+    pub paused: Arc<AtomicBool>,
+    pub launcher: NodeLauncher,
+}
+
+/// Handle of the background scheduler, the scheduler is stopped when the handle is dropped.
+pub struct ChaosScheduler {
+    stop: Arc<AtomicBool>,
+    events: Arc<Mutex<Vec<ChaosEvent>>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl ChaosScheduler {
+    /// Starts disrupting the given validators. The validators stopped on purpose are left
+    /// alone, and a validator restarted by the swarm while it is disrupted is not restored.
+    pub(crate) fn start(nodes: Vec<ChaosNode>, policy: ChaosPolicy, bus: EventBus) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let events = Arc::new(Mutex::new(vec![]));
+        let handle = {
+            let stop = Arc::clone(&stop);
+            let events = Arc::clone(&events);
+            thread::Builder::new()
+                .name("swarm-chaos".to_string())
+                .spawn(move || Schedule::new(nodes, policy, events, bus).run(&stop))
+                .expect("Failed to spawn the chaos scheduler")
+        };
+        Self {
+            stop,
+            events,
+            handle: Some(handle),
+        }
+    }
+
+    /// The actions taken so far, in order.
+    pub fn events(&self) -> Vec<ChaosEvent> {
+        self.events.lock().unwrap().clone()
+    }
+
+    /// Stops the scheduler once the disrupted validators are restored.
+    pub fn stop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            handle.join().expect("Chaos scheduler panicked");
+        }
+    }
+}
+
+impl Drop for ChaosScheduler {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+struct Schedule {
+    nodes: Vec<ChaosNode>,
+    policy: ChaosPolicy,
+    max_disrupted: usize,
+    rng: StdRng,
+    start: Instant,
+    // The disrupted nodes, by index, with the disruption and the time the node is restored at. A
+    // node stays disrupted until it is successfully restored.
+    disrupted: HashMap<usize, (ChaosAction, Instant)>,
+    events: Arc<Mutex<Vec<ChaosEvent>>>,
+    // The restarts are also emitted as events of the swarm.
+    bus: EventBus,
+}
+
+impl Schedule {
+    fn new(
+        nodes: Vec<ChaosNode>,
+        policy: ChaosPolicy,
+        events: Arc<Mutex<Vec<ChaosEvent>>>,
+        bus: EventBus,
+    ) -> Self {
+        let max_disrupted = policy
+            .max_disrupted
+            .unwrap_or_else(|| nodes.len().saturating_sub(1) / 3);
+        let rng = match policy.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+        Self {
+            nodes,
+            policy,
+            max_disrupted,
+            rng,
+            start: Instant::now(),
+            disrupted: HashMap::new(),
+            events,
+            bus,
+        }
+    }
+
+    fn run(mut self, stop: &AtomicBool) {
+        while sleep_unless_stopped(self.policy.interval, stop) {
+            let now = Instant::now();
+            let due: Vec<usize> = self
+                .disrupted
+                .iter()
+                .filter(|(_, (_, restore_at))| *restore_at <= now)
+                .map(|(index, _)| *index)
+                .collect();
+            for index in due {
+                self.restore(index);
+            }
+            let kill_probability = self.policy.kill_probability;
+            let pause_probability = self.policy.pause_probability;
+            self.maybe_disrupt(ChaosAction::Kill, kill_probability);
+            self.maybe_disrupt(ChaosAction::Pause, pause_probability);
+        }
+        let disrupted: Vec<usize> = self.disrupted.keys().cloned().collect();
+        for index in disrupted {
+            self.restore(index);
+        }
+    }
+
+    // The number of nodes that are down: the nodes disrupted by the scheduler, including the ones
+    // it failed to restore, and the nodes stopped or paused by the test.
+    fn num_down(&self) -> usize {
+        (0..self.nodes.len())
+            .filter(|index| self.disrupted.contains_key(index) || self.is_held(*index))
+            .count()
+    }
+
+    // Whether the node is stopped or paused on purpose by the test.
+    fn is_held(&self, index: usize) -> bool {
+        let node = &self.nodes[index];
+        node.paused.load(Ordering::SeqCst) || node.process.lock().unwrap().is_none()
+    }
+
+    fn maybe_disrupt(&mut self, action: ChaosAction, probability: f64) {
+        if self.num_down() >= self.max_disrupted
+            || !self.rng.gen_bool(probability.max(0.0).min(1.0))
+        {
+            return;
+        }
+        let candidates: Vec<usize> = (0..self.nodes.len())
+            .filter(|index| !self.disrupted.contains_key(index))
+            .filter(|index| {
+                action != ChaosAction::Pause || !self.nodes[*index].launcher.is_remote()
+            })
+            // The nodes stopped or paused on purpose are left alone.
+            .filter(|index| !self.is_held(*index))
+            .collect();
+        let index = match candidates.choose(&mut self.rng) {
+            Some(index) => *index,
+            None => return,
+        };
+        let result = {
+            let mut process = self.nodes[index].process.lock().unwrap();
+            match action {
+                // Taking the process out tells the swarm that the node is stopped on purpose.
+                ChaosAction::Kill => process
+                    .take()
+                    .map_or(Ok(()), |mut process| process.kill_tree().map(|_| ())),
+                _ => process.as_mut().map_or(Ok(()), |process| process.pause()),
+            }
+        };
+        let downtime = self.downtime();
+        match result {
+            Ok(()) => {
+                self.disrupted
+                    .insert(index, (action, Instant::now() + downtime));
+                self.record(index, action, None);
+            }
+            Err(e) => self.record(index, action, Some(e.to_string())),
+        }
+    }
+
+    fn restore(&mut self, index: usize) {
+        let disruption = match self.disrupted.get(&index) {
+            Some((disruption, _)) => *disruption,
+            None => return,
+        };
+        let node = &self.nodes[index];
+        let (action, result) = {
+            let mut process = node.process.lock().unwrap();
+            match disruption {
+                ChaosAction::Kill => {
+                    // The swarm might have restarted the node in the meantime.
+                    let result = if process.is_some() {
+                        Ok(())
+                    } else {
+                        node.launcher.spawn().map(|new_process| {
+                            *process = Some(new_process);
+                        })
+                    };
+                    (ChaosAction::Restart, result.map_err(|e| e.to_string()))
+                }
+                _ => {
+                    let result = process.as_mut().map_or(Ok(()), |process| process.resume());
+                    (ChaosAction::Resume, result.map_err(|e| e.to_string()))
+                }
+            }
+        };
+        if result.is_ok() {
+            if action == ChaosAction::Restart {
+                self.bus.emit(SwarmEvent::NodeRestarted {
+                    peer_id: node.peer_id.clone(),
+                });
+            }
+            self.disrupted.remove(&index);
+        }
+        self.record(index, action, result.err());
+    }
+
+    fn downtime(&mut self) -> Duration {
+        let min = self.policy.min_downtime.as_millis() as u64;
+        let max = (self.policy.max_downtime.as_millis() as u64).max(min);
+        Duration::from_millis(self.rng.gen_range(min, max + 1))
+    }
+
+    fn record(&self, index: usize, action: ChaosAction, error: Option<String>) {
+        let event = ChaosEvent {
+            time: self.start.elapsed(),
+            peer_id: self.nodes[index].peer_id.clone(),
+            action,
+            error,
+        };
+        match &event.error {
+            Some(_) => error!("Chaos: {}", event),
+            None => info!("Chaos: {}", event),
+        }
+        self.events.lock().unwrap().push(event);
+    }
+}
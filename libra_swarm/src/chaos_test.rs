@@ -0,0 +1,179 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+#![cfg(unix)]
+
+use crate::{
+    chaos::{ChaosAction, ChaosNode, ChaosPolicy, Schedule},
+    events::EventBus,
+    process::NodeProcess,
+    swarm::NodeLauncher,
+};
+use std::{
+    fs::File,
+    path::Path,
+    process::Command,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+use tools::tempdir::TempPath;
+
+// A node running `sleep`, restarted with `true` which appends nothing to the given log.
+fn node(index: usize, log: &Path) -> ChaosNode {
+    let peer_id = format!("validator{}", index);
+    let process = NodeProcess::spawn(Command::new("sleep").arg("30")).unwrap();
+    ChaosNode {
+        launcher: NodeLauncher::for_test(&peer_id, Path::new("true"), log),
+        peer_id,
+        process: Arc::new(Mutex::new(Some(process))),
+        paused: Arc::new(AtomicBool::new(false)),
+    }
+}
+
+// A schedule which disrupts a node whenever it is allowed to.
+fn schedule(num_nodes: usize, log: &Path) -> Schedule {
+    let nodes = (0..num_nodes).map(|index| node(index, log)).collect();
+    let policy = ChaosPolicy {
+        kill_probability: 1.0,
+        pause_probability: 1.0,
+        min_downtime: Duration::from_millis(0),
+        max_downtime: Duration::from_millis(0),
+        max_disrupted: None,
+        seed: Some(0),
+        ..ChaosPolicy::default()
+    };
+    Schedule::new(
+        nodes,
+        policy,
+        Arc::new(Mutex::new(vec![])),
+        EventBus::default(),
+    )
+}
+
+fn kill_all(schedule: &Schedule) {
+    for node in &schedule.nodes {
+        if let Some(mut process) = node.process.lock().unwrap().take() {
+            let _ = process.kill_tree();
+        }
+    }
+}
+
+fn disrupt_repeatedly(schedule: &mut Schedule) {
+    for _ in 0..10 {
+        schedule.maybe_disrupt(ChaosAction::Kill, 1.0);
+        schedule.maybe_disrupt(ChaosAction::Pause, 1.0);
+    }
+}
+
+#[test]
+fn test_max_disrupted_defaults_to_f() {
+    let log = TempPath::new();
+    for (num_nodes, f) in &[(1, 0), (4, 1), (6, 1), (7, 2), (10, 3)] {
+        let schedule = schedule(*num_nodes, log.path());
+        assert_eq!(schedule.max_disrupted, *f);
+        kill_all(&schedule);
+    }
+}
+
+#[test]
+fn test_disruptions_are_bounded() {
+    let log = TempPath::new();
+    let mut schedule = schedule(7, log.path());
+    disrupt_repeatedly(&mut schedule);
+    assert_eq!(schedule.disrupted.len(), 2);
+    assert_eq!(schedule.num_down(), 2);
+    assert_eq!(schedule.events.lock().unwrap().len(), 2);
+    kill_all(&schedule);
+}
+
+#[test]
+fn test_stopped_nodes_count_against_bound() {
+    let log = TempPath::new();
+    let mut schedule = schedule(7, log.path());
+    // The test stops a node on purpose, only one more node may be disrupted.
+    let _ = schedule.nodes[3]
+        .process
+        .lock()
+        .unwrap()
+        .take()
+        .unwrap()
+        .kill_tree();
+    assert_eq!(schedule.num_down(), 1);
+    disrupt_repeatedly(&mut schedule);
+    assert_eq!(schedule.disrupted.len(), 1);
+    assert!(!schedule.disrupted.contains_key(&3));
+    assert_eq!(schedule.num_down(), 2);
+    assert_eq!(schedule.events.lock().unwrap().len(), 1);
+    kill_all(&schedule);
+}
+
+#[test]
+fn test_paused_nodes_count_against_bound() {
+    let log = TempPath::new();
+    let mut schedule = schedule(7, log.path());
+    // The test pauses a node on purpose, only one more node may be disrupted and the paused node
+    // isn't one of them.
+    schedule.nodes[2].paused.store(true, Ordering::SeqCst);
+    assert_eq!(schedule.num_down(), 1);
+    disrupt_repeatedly(&mut schedule);
+    assert_eq!(schedule.disrupted.len(), 1);
+    assert!(!schedule.disrupted.contains_key(&2));
+    assert_eq!(schedule.num_down(), 2);
+    kill_all(&schedule);
+}
+
+#[test]
+fn test_paused_node_is_resumed() {
+    let log = TempPath::new();
+    let mut schedule = schedule(4, log.path());
+    schedule.maybe_disrupt(ChaosAction::Pause, 1.0);
+    let index = *schedule.disrupted.keys().next().unwrap();
+    assert_eq!(schedule.num_down(), 1);
+
+    schedule.restore(index);
+    assert!(schedule.disrupted.is_empty());
+    let events = schedule.events.lock().unwrap().clone();
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[1].action, ChaosAction::Resume);
+    assert!(events[1].error.is_none());
+    kill_all(&schedule);
+}
+
+#[test]
+fn test_failed_restart_is_retried() {
+    // The log directory is missing such that the restarts fail.
+    let log_dir = TempPath::new();
+    let log = log_dir.path().join("log");
+    let mut schedule = schedule(4, &log);
+    schedule.maybe_disrupt(ChaosAction::Kill, 1.0);
+    let index = *schedule.disrupted.keys().next().unwrap();
+
+    schedule.restore(index);
+    // The node is still down and still counts against the bound.
+    assert!(schedule.disrupted.contains_key(&index));
+    assert!(schedule.nodes[index].process.lock().unwrap().is_none());
+    disrupt_repeatedly(&mut schedule);
+    assert_eq!(schedule.num_down(), 1);
+    {
+        let events = schedule.events.lock().unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[1].action, ChaosAction::Restart);
+        assert!(events[1].error.is_some());
+    }
+
+    log_dir.create_as_dir().unwrap();
+    File::create(&log).unwrap();
+    schedule.restore(index);
+    assert!(schedule.disrupted.is_empty());
+    assert!(schedule.nodes[index].process.lock().unwrap().is_some());
+    {
+        let events = schedule.events.lock().unwrap();
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[2].action, ChaosAction::Restart);
+        assert!(events[2].error.is_none());
+    }
+    kill_all(&schedule);
+}
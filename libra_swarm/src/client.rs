@@ -1,15 +1,22 @@
 // Copyright (c) The Libra Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::utils;
+use crate::{liveness::SharedNodeProcess, utils};
 use client_lib::{client_proxy::ClientProxy, commands};
+use failure::prelude::*;
 use std::{
     collections::HashMap,
     io::{self, Write},
-    path::Path,
+    path::{Path, PathBuf},
     process::{Child, Command, Output, Stdio},
     sync::Arc,
+    thread,
+    time::{Duration, Instant},
 };
+use tools::tempdir::TempPath;
+
+/// Delay between two connection checks while waiting for the node to accept connections.
+const CONNECTION_POLL_INTERVAL: Duration = Duration::from_millis(100);
 
 pub struct InteractiveClient {
     client: Option<Child>,
@@ -191,3 +198,121 @@ impl InProcessTestClient {
         &mut self.client
     }
 }
+
+/// A client of the admission control of a node of the swarm, see `LibraNode::ac_client`. The
+/// client reconnects when the node was restarted since the last call, keeping its accounts.
+pub struct NodeClient {
+    peer_id: String,
+    host: String,
+    ac_port: u16,
+    trusted_peers_file: PathBuf,
+    process: SharedNodeProcess,
+    // The process of the node the client connected to, None if the node wasn't running.
+    connected_to: Option<u32>,
+    // The wallet of the client, carried over when it reconnects.
+    mnemonic_file: TempPath,
+    client: ClientProxy,
+}
+
+impl NodeClient {
+    pub(crate) fn new(
+        peer_id: String,
+        host: String,
+        ac_port: u16,
+        trusted_peers_file: PathBuf,
+        process: SharedNodeProcess,
+    ) -> Result<Self> {
+        let mnemonic_file = TempPath::new();
+        mnemonic_file.create_as_file()?;
+        let connected_to = Self::process_id(&process);
+        let client = Self::connect(&host, ac_port, &trusted_peers_file, &mnemonic_file)?;
+        Ok(Self {
+            peer_id,
+            host,
+            ac_port,
+            trusted_peers_file,
+            process,
+            connected_to,
+            mnemonic_file,
+            client,
+        })
+    }
+
+    fn connect(
+        host: &str,
+        ac_port: u16,
+        trusted_peers_file: &Path,
+        mnemonic_file: &TempPath,
+    ) -> Result<ClientProxy> {
+        ClientProxy::new(
+            host,
+            &ac_port.to_string(),
+            &trusted_peers_file.to_string_lossy(),
+            /* faucet account file */ "",
+            false,
+            /* faucet server */ None,
+            Some(mnemonic_file.path().to_string_lossy().into_owned()),
+        )
+    }
+
+    fn process_id(process: &SharedNodeProcess) -> Option<u32> {
+        process.lock().unwrap().as_ref().map(|process| process.id())
+    }
+
+    pub fn peer_id(&self) -> &str {
+        &self.peer_id
+    }
+
+    pub fn ac_port(&self) -> u16 {
+        self.ac_port
+    }
+
+    /// The client connected to the current process of the node.
+    pub fn client(&mut self) -> Result<&mut ClientProxy> {
+        let process_id = Self::process_id(&self.process);
+        // A stopped node keeps the client of its last process, which fails until it restarts.
+        if process_id.is_some() && process_id != self.connected_to {
+            self.reconnect()?;
+        }
+        Ok(&mut self.client)
+    }
+
+    /// Connects again to the node, without waiting for the backoff of the current connection.
+    pub fn reconnect(&mut self) -> Result<()> {
+        let mnemonic_path = self.mnemonic_file.path().to_string_lossy().into_owned();
+        self.client.write_recovery(&["write", &mnemonic_path])?;
+        let mut client = Self::connect(
+            &self.host,
+            self.ac_port,
+            &self.trusted_peers_file,
+            &self.mnemonic_file,
+        )
+        .with_context(|_| format!("Failed to reconnect to node {}", self.peer_id))?;
+        client.set_accounts(self.client.copy_all_accounts());
+        client.faucet_account = self.client.faucet_account.take();
+        self.client = client;
+        self.connected_to = Self::process_id(&self.process);
+        Ok(())
+    }
+
+    /// Checks that the admission control of the node answers.
+    pub fn check_connection(&mut self) -> Result<()> {
+        let peer_id = self.peer_id.clone();
+        self.client()?
+            .test_validator_connection()
+            .with_context(|_| format!("Node {} doesn't answer", peer_id))?;
+        Ok(())
+    }
+
+    /// Waits for the admission control of the node to answer for at most the given timeout.
+    pub fn wait_for_connection(&mut self, timeout: Duration) -> Result<()> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            match self.check_connection() {
+                Ok(()) => return Ok(()),
+                Err(e) if Instant::now() >= deadline => return Err(e),
+                Err(_) => thread::sleep(CONNECTION_POLL_INTERVAL),
+            }
+        }
+    }
+}
@@ -0,0 +1,63 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Round and latency statistics reported by the consensus of a validator.
+
+use std::collections::HashMap;
+
+const LOCAL_TIMEOUT_REASON_PREFIX: &str = "consensus{op=timeout_reason_local_";
+
+/// The consensus statistics of a single validator. The latencies are the latest ones observed by
+/// the validator, the full histograms are exported to the metric server.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ConsensusStats {
+    /// Number of blocks committed since the last restart.
+    pub committed_blocks: i64,
+    /// The round of the highest committed block.
+    pub last_committed_round: i64,
+    /// The current round of the pacemaker.
+    pub current_round: i64,
+    /// Time between the receipt of the latest voted proposal and the vote, in milliseconds.
+    pub proposal_to_vote_ms: i64,
+    /// Time between the latest vote and the QC of the voted block, in milliseconds.
+    pub vote_to_qc_ms: i64,
+    /// Time between the QC of the latest committed block and its commit, in milliseconds.
+    pub qc_to_commit_ms: i64,
+    /// Number of rounds between the two latest commits.
+    pub rounds_per_commit: i64,
+    /// Number of local timeouts since the last restart, keyed by reason (e.g. "no_proposal").
+    pub timeouts: HashMap<String, i64>,
+}
+
+impl ConsensusStats {
+    /// Extracts the statistics from the metrics of a node, the missing counters are assumed to
+    /// be 0.
+    pub fn from_metrics(metrics: &HashMap<String, i64>) -> Self {
+        let get = |name: String| metrics.get(&name).cloned().unwrap_or(0);
+        let counter = |op: &str| get(format!("consensus{{op={}}}", op));
+        let gauge = |op: &str| get(format!("consensus_gauge{{op={}}}", op));
+        let timeouts = metrics
+            .iter()
+            .filter(|(name, _)| name.starts_with(LOCAL_TIMEOUT_REASON_PREFIX))
+            .map(|(name, value)| {
+                let reason = name[LOCAL_TIMEOUT_REASON_PREFIX.len()..].trim_end_matches('}');
+                (reason.to_string(), *value)
+            })
+            .collect();
+        Self {
+            committed_blocks: counter("committed_blocks_count"),
+            last_committed_round: gauge("last_committed_round"),
+            current_round: gauge("current_round"),
+            proposal_to_vote_ms: gauge("last_proposal_to_vote_ms"),
+            vote_to_qc_ms: gauge("last_vote_to_qc_ms"),
+            qc_to_commit_ms: gauge("last_qc_to_commit_ms"),
+            rounds_per_commit: gauge("last_rounds_per_commit"),
+            timeouts,
+        }
+    }
+
+    /// Total number of local timeouts since the last restart.
+    pub fn total_timeouts(&self) -> i64 {
+        self.timeouts.values().sum()
+    }
+}
@@ -0,0 +1,178 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Diagnostics of a crashed node.
+//!
+//! The local nodes run with `RUST_BACKTRACE` set, such that a panic logs its backtrace, and with
+//! core dumps enabled. When a node crashes, the report gathers its exit status, the end of its
+//! log, the backtrace of the panic and, if the kernel wrote one in the working directory of the
+//! node, the core dump, moved next to the log of the node together with its backtrace resolved
+//! by gdb when available.
+
+use std::{
+    fmt, fs,
+    path::{Path, PathBuf},
+    process::{Command, ExitStatus, Stdio},
+};
+
+/// The number of lines at the end of the log kept in a report.
+const CRASH_LOG_LINES: usize = 50;
+/// The max number of lines of a backtrace kept in a report.
+const BACKTRACE_LINES: usize = 200;
+const CORE_PATTERN_PATH: &str = "/proc/sys/kernel/core_pattern";
+
+/// The diagnostics of a crashed node.
+#[derive(Clone, Debug)]
+pub struct CrashReport {
+    pub peer_id: String,
+    pub status: ExitStatus,
+    /// The signal that terminated the node, on Unix
+    pub signal: Option<i32>,
+    pub log: PathBuf,
+    /// The last lines of the log
+    pub log_tail: Vec<String>,
+    /// The core dump of the node, if one was written
+    pub core_dump: Option<PathBuf>,
+    /// The backtrace of the panic logged by the node, or the one resolved from the core dump
+    pub backtrace: Option<String>,
+}
+
+impl CrashReport {
+    /// Collects the diagnostics of the node which crashed with the given status. The core dump
+    /// is looked for in the given working directory of the node, for the given process id.
+    pub(crate) fn collect(
+        peer_id: &str,
+        status: ExitStatus,
+        log: &Path,
+        binary: &Path,
+        core_search: Option<(&Path, u32)>,
+    ) -> Self {
+        let log_lines: Vec<String> = fs::read(log)
+            .map(|contents| {
+                String::from_utf8_lossy(&contents)
+                    .lines()
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default();
+        let log_tail = log_lines[log_lines.len().saturating_sub(CRASH_LOG_LINES)..].to_vec();
+        let core_dump = core_search
+            .and_then(|(working_dir, pid)| find_core_dump(working_dir, pid))
+            .map(|core| {
+                // The core dump is kept with the artifacts of the node.
+                let target = log.with_extension("core");
+                match fs::rename(&core, &target) {
+                    Ok(()) => target,
+                    Err(_) => core,
+                }
+            });
+        let backtrace = panic_backtrace(&log_lines).or_else(|| {
+            core_dump
+                .as_ref()
+                .and_then(|core| core_backtrace(binary, core))
+        });
+        Self {
+            peer_id: peer_id.to_string(),
+            status,
+            signal: signal(status),
+            log: log.to_path_buf(),
+            log_tail,
+            core_dump,
+            backtrace,
+        }
+    }
+}
+
+impl fmt::Display for CrashReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Node {} crashed with {}", self.peer_id, self.status)?;
+        if let Some(signal) = self.signal {
+            write!(f, " (signal {})", signal)?;
+        }
+        writeln!(f)?;
+        if let Some(core_dump) = &self.core_dump {
+            writeln!(f, "Core dump: {:?}", core_dump)?;
+        }
+        if let Some(backtrace) = &self.backtrace {
+            writeln!(f, "Backtrace:")?;
+            writeln!(f, "{}", backtrace)?;
+        }
+        writeln!(f, "Last {} lines of {:?}:", self.log_tail.len(), self.log)?;
+        for line in &self.log_tail {
+            writeln!(f, "\t{}", line)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(unix)]
+fn signal(status: ExitStatus) -> Option<i32> {
+    use std::os::unix::process::ExitStatusExt;
+    status.signal()
+}
+
+#[cfg(not(unix))]
+fn signal(_status: ExitStatus) -> Option<i32> {
+    None
+}
+
+// The last panic of the log, with its backtrace if the node logged one.
+fn panic_backtrace(log_lines: &[String]) -> Option<String> {
+    let start = log_lines
+        .iter()
+        .rposition(|line| line.contains("panicked at"))?;
+    let lines: Vec<&str> = log_lines[start..]
+        .iter()
+        .take(BACKTRACE_LINES)
+        .map(String::as_str)
+        .collect();
+    Some(lines.join("\n"))
+}
+
+// The core dump written by the kernel for the given process, if the kernel writes the core dumps
+// to files rather than piping them to a handler.
+fn find_core_dump(working_dir: &Path, pid: u32) -> Option<PathBuf> {
+    let pattern = fs::read_to_string(CORE_PATTERN_PATH).ok()?;
+    let pattern = pattern.trim();
+    if pattern.is_empty() || pattern.starts_with('|') {
+        return None;
+    }
+    let pid = pid.to_string();
+    let expanded = pattern.replace("%p", &pid).replace("%%", "%");
+    let candidates = vec![
+        working_dir.join(&expanded),
+        working_dir.join("core"),
+        working_dir.join(format!("core.{}", pid)),
+    ];
+    // The other specifiers (e.g. the time) can't be expanded, such a core dump isn't found.
+    candidates
+        .into_iter()
+        .find(|candidate| !candidate.to_string_lossy().contains('%') && candidate.is_file())
+}
+
+// The backtrace of the crashed thread resolved by gdb from the core dump, if gdb is installed.
+fn core_backtrace(binary: &Path, core: &Path) -> Option<String> {
+    let output = Command::new("gdb")
+        .arg("--batch")
+        .arg("-ex")
+        .arg("bt")
+        .arg(binary)
+        .arg(core)
+        .stdin(Stdio::null())
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines: Vec<&str> = stdout
+        .lines()
+        .filter(|line| line.starts_with('#'))
+        .take(BACKTRACE_LINES)
+        .collect();
+    if lines.is_empty() {
+        None
+    } else {
+        Some(lines.join("\n"))
+    }
+}
@@ -0,0 +1,177 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! The lifecycle events of a swarm, for the long-running tests to react to instead of polling
+//! the health of the nodes.
+//!
+//! Every subscriber receives the events emitted after it subscribed, in order. The crashes are
+//! detected by a background monitor polling the processes of the nodes, which runs as long as
+//! the swarm has subscribers.
+
+use crate::liveness::{sleep_unless_stopped, SharedNodeProcess};
+use logger::prelude::*;
+use std::{
+    collections::HashMap,
+    fmt,
+    process::ExitStatus,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+/// How often the monitor checks whether a node has crashed.
+const CRASH_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+#[derive(Clone, Debug)]
+pub enum SwarmEvent {
+    /// The process of the node has been spawned.
+    NodeLaunched { peer_id: String },
+    /// The node answers the debug interface requests after it was launched or restarted.
+    NodeHealthy { peer_id: String },
+    /// The process of the node has terminated without being stopped by the swarm.
+    NodeCrashed { peer_id: String, status: ExitStatus },
+    /// The node has been restarted, by the swarm or by the chaos scheduler.
+    NodeRestarted { peer_id: String },
+    /// The processes of the node have been suspended by the swarm.
+    NodePaused { peer_id: String },
+    /// The processes of the node paused by the swarm have been resumed.
+    NodeResumed { peer_id: String },
+    /// Every running node is connected to its expected peers.
+    ConnectivityAchieved,
+    /// Every validator has committed the round and every full node the version.
+    CatchupReached { round: i64, version: Option<i64> },
+    /// The validator has been removed from the validator set of the epoch, and from the swarm.
+    ValidatorRemoved { peer_id: String, epoch: u64 },
+}
+
+impl fmt::Display for SwarmEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SwarmEvent::NodeLaunched { peer_id } => write!(f, "Node {} launched", peer_id),
+            SwarmEvent::NodeHealthy { peer_id } => write!(f, "Node {} healthy", peer_id),
+            SwarmEvent::NodeCrashed { peer_id, status } => {
+                write!(f, "Node {} crashed with {}", peer_id, status)
+            }
+            SwarmEvent::NodeRestarted { peer_id } => write!(f, "Node {} restarted", peer_id),
+            SwarmEvent::NodePaused { peer_id } => write!(f, "Node {} paused", peer_id),
+            SwarmEvent::NodeResumed { peer_id } => write!(f, "Node {} resumed", peer_id),
+            SwarmEvent::ConnectivityAchieved => write!(f, "Nodes connected"),
+            SwarmEvent::CatchupReached { round, version } => {
+                write!(f, "Validators caught up to round {}", round)?;
+                if let Some(version) = version {
+                    write!(f, ", full nodes to version {}", version)?;
+                }
+                Ok(())
+            }
+            SwarmEvent::ValidatorRemoved { peer_id, epoch } => {
+                write!(f, "Validator {} removed in epoch {}", peer_id, epoch)
+            }
+        }
+    }
+}
+
+/// Dispatches the events of a swarm to its subscribers, shared with the builder and the
+/// background threads of the swarm.
+#[derive(Clone, Default)]
+pub(crate) struct EventBus {
+    subscribers: Arc<Mutex<Vec<Sender<SwarmEvent>>>>,
+    // The processes of the nodes watched for crashes, by peer id.
+    nodes: Arc<Mutex<HashMap<String, SharedNodeProcess>>>,
+}
+
+impl EventBus {
+    pub fn subscribe(&self) -> Receiver<SwarmEvent> {
+        let (sender, receiver) = mpsc::channel();
+        self.subscribers.lock().unwrap().push(sender);
+        receiver
+    }
+
+    pub fn has_subscribers(&self) -> bool {
+        !self.subscribers.lock().unwrap().is_empty()
+    }
+
+    pub fn emit(&self, event: SwarmEvent) {
+        debug!("Swarm event: {}", event);
+        // The subscribers which dropped their receiver are forgotten.
+        self.subscribers
+            .lock()
+            .unwrap()
+            .retain(|subscriber| subscriber.send(event.clone()).is_ok());
+    }
+
+    /// Watches the process of the given node for crashes, instead of its previous process.
+    pub fn watch(&self, peer_id: String, process: SharedNodeProcess) {
+        self.nodes.lock().unwrap().insert(peer_id, process);
+    }
+}
+
+/// Handle of the background crash monitor, the monitor is stopped when the handle is dropped.
+pub(crate) struct CrashMonitor {
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl CrashMonitor {
+    /// Starts emitting a `NodeCrashed` event for every watched process that terminates. A node
+    /// stopped on purpose has no process and isn't reported.
+    pub fn start(bus: EventBus) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let handle = {
+            let stop = Arc::clone(&stop);
+            thread::Builder::new()
+                .name("swarm-crash-monitor".to_string())
+                .spawn(move || run(&bus, &stop))
+                .expect("Failed to spawn the crash monitor")
+        };
+        Self {
+            stop,
+            handle: Some(handle),
+        }
+    }
+
+    pub fn stop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            handle.join().expect("Crash monitor panicked");
+        }
+    }
+}
+
+impl Drop for CrashMonitor {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+fn run(bus: &EventBus, stop: &AtomicBool) {
+    // The process reported last, by peer id, such that a crash is reported once.
+    let mut reported: HashMap<String, u32> = HashMap::new();
+    while sleep_unless_stopped(CRASH_POLL_INTERVAL, stop) {
+        let nodes: Vec<(String, SharedNodeProcess)> = bus
+            .nodes
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(peer_id, process)| (peer_id.clone(), Arc::clone(process)))
+            .collect();
+        for (peer_id, process) in nodes {
+            let crashed = match process.lock().unwrap().as_mut() {
+                Some(process) => match process.try_wait() {
+                    Ok(Some(status)) => Some((process.id(), status)),
+                    _ => None,
+                },
+                None => None,
+            };
+            if let Some((pid, status)) = crashed {
+                if reported.get(&peer_id) != Some(&pid) {
+                    reported.insert(peer_id.clone(), pid);
+                    bus.emit(SwarmEvent::NodeCrashed { peer_id, status });
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,117 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Proposer fairness report built from the per-validator consensus counters of a swarm.
+//! It is meant to quantitatively compare proposer election strategies.
+
+use std::{collections::HashMap, fmt};
+
+#[cfg(test)]
+#[path = "fairness_test.rs"]
+mod fairness_test;
+
+/// Proposer statistics reported by a single validator about itself.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ProposerStats {
+    /// Number of rounds in which the validator was the primary proposer.
+    pub rounds_led: i64,
+    /// Number of proposals the validator has broadcast.
+    pub proposals_made: i64,
+    /// Number of committed blocks proposed by the validator.
+    pub proposals_committed: i64,
+}
+
+/// Proposer statistics of all the validators in the swarm, keyed by peer id.
+#[derive(Clone, Debug, Default)]
+pub struct FairnessReport {
+    stats: HashMap<String, ProposerStats>,
+}
+
+impl FairnessReport {
+    pub fn new(stats: HashMap<String, ProposerStats>) -> Self {
+        Self { stats }
+    }
+
+    pub fn stats(&self) -> &HashMap<String, ProposerStats> {
+        &self.stats
+    }
+
+    pub fn get(&self, peer_id: &str) -> Option<&ProposerStats> {
+        self.stats.get(peer_id)
+    }
+
+    /// Total number of committed proposals across all the validators.
+    pub fn total_committed(&self) -> i64 {
+        self.stats.values().map(|s| s.proposals_committed).sum()
+    }
+
+    /// Fraction of the committed proposals that were proposed by each validator.
+    pub fn committed_share(&self) -> HashMap<String, f64> {
+        let total = self.total_committed();
+        self.stats
+            .iter()
+            .map(|(peer_id, s)| {
+                let share = if total == 0 {
+                    0.0
+                } else {
+                    s.proposals_committed as f64 / total as f64
+                };
+                (peer_id.clone(), share)
+            })
+            .collect()
+    }
+
+    /// Jain's fairness index of the committed proposals: (sum x)^2 / (n * sum x^2).
+    /// The index is 1.0 when all validators committed the same number of proposals and 1/n when
+    /// a single validator committed all of them. An empty report is considered fair.
+    pub fn jain_index(&self) -> f64 {
+        let sum: f64 = self
+            .stats
+            .values()
+            .map(|s| s.proposals_committed as f64)
+            .sum();
+        let sum_of_squares: f64 = self
+            .stats
+            .values()
+            .map(|s| (s.proposals_committed as f64).powi(2))
+            .sum();
+        if sum_of_squares == 0.0 {
+            return 1.0;
+        }
+        sum * sum / (self.stats.len() as f64 * sum_of_squares)
+    }
+
+    /// Ratio between the highest and the lowest number of committed proposals, or None if
+    /// some validator did not get any of its proposals committed.
+    pub fn max_min_ratio(&self) -> Option<f64> {
+        let max = self.stats.values().map(|s| s.proposals_committed).max()?;
+        let min = self.stats.values().map(|s| s.proposals_committed).min()?;
+        if min == 0 {
+            None
+        } else {
+            Some(max as f64 / min as f64)
+        }
+    }
+}
+
+impl fmt::Display for FairnessReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut peer_ids: Vec<&String> = self.stats.keys().collect();
+        peer_ids.sort();
+        let shares = self.committed_share();
+        writeln!(
+            f,
+            "{:<66} {:>10} {:>10} {:>10} {:>8}",
+            "validator", "led", "proposed", "committed", "share"
+        )?;
+        for peer_id in peer_ids {
+            let s = &self.stats[peer_id];
+            writeln!(
+                f,
+                "{:<66} {:>10} {:>10} {:>10} {:>8.3}",
+                peer_id, s.rounds_led, s.proposals_made, s.proposals_committed, shares[peer_id]
+            )?;
+        }
+        write!(f, "Jain's fairness index: {:.3}", self.jain_index())
+    }
+}
@@ -0,0 +1,94 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::fairness::{FairnessReport, ProposerStats};
+use std::collections::HashMap;
+
+const EPSILON: f64 = 1e-9;
+
+fn report(committed: &[i64]) -> FairnessReport {
+    let stats: HashMap<String, ProposerStats> = committed
+        .iter()
+        .enumerate()
+        .map(|(index, proposals_committed)| {
+            (
+                format!("validator{}", index),
+                ProposerStats {
+                    rounds_led: *proposals_committed,
+                    proposals_made: *proposals_committed,
+                    proposals_committed: *proposals_committed,
+                },
+            )
+        })
+        .collect();
+    FairnessReport::new(stats)
+}
+
+fn assert_close(actual: f64, expected: f64) {
+    assert!(
+        (actual - expected).abs() < EPSILON,
+        "{} != {}",
+        actual,
+        expected
+    );
+}
+
+#[test]
+fn test_empty_report() {
+    let report = report(&[]);
+    assert_eq!(report.total_committed(), 0);
+    assert!(report.committed_share().is_empty());
+    assert_close(report.jain_index(), 1.0);
+    assert_eq!(report.max_min_ratio(), None);
+}
+
+#[test]
+fn test_all_zero() {
+    let report = report(&[0, 0, 0]);
+    assert_eq!(report.total_committed(), 0);
+    let shares = report.committed_share();
+    assert_eq!(shares.len(), 3);
+    for share in shares.values() {
+        assert_close(*share, 0.0);
+    }
+    // Nobody got ahead of the others.
+    assert_close(report.jain_index(), 1.0);
+    assert_eq!(report.max_min_ratio(), None);
+}
+
+#[test]
+fn test_single_proposer() {
+    let report = report(&[10, 0, 0, 0]);
+    assert_eq!(report.total_committed(), 10);
+    let shares = report.committed_share();
+    assert_close(shares["validator0"], 1.0);
+    for peer_id in &["validator1", "validator2", "validator3"] {
+        assert_close(shares[*peer_id], 0.0);
+    }
+    // The lowest possible index for 4 validators.
+    assert_close(report.jain_index(), 0.25);
+    assert_eq!(report.max_min_ratio(), None);
+}
+
+#[test]
+fn test_even_shares() {
+    let report = report(&[5, 5, 5, 5]);
+    for share in report.committed_share().values() {
+        assert_close(*share, 0.25);
+    }
+    assert_close(report.jain_index(), 1.0);
+    assert_close(report.max_min_ratio().unwrap(), 1.0);
+}
+
+#[test]
+fn test_uneven_shares() {
+    let report = report(&[1, 2, 3]);
+    assert_eq!(report.total_committed(), 6);
+    let shares = report.committed_share();
+    assert_close(shares["validator0"], 1.0 / 6.0);
+    assert_close(shares["validator1"], 2.0 / 6.0);
+    assert_close(shares["validator2"], 3.0 / 6.0);
+    // (1 + 2 + 3)^2 / (3 * (1 + 4 + 9))
+    assert_close(report.jain_index(), 36.0 / 42.0);
+    assert_close(report.max_min_ratio().unwrap(), 3.0);
+}
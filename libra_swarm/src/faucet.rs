@@ -0,0 +1,157 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! In-process faucet of a swarm, such that the tests fund their accounts with a single call
+//! instead of driving a client through the mint commands.
+
+use client_lib::{client_proxy::ClientProxy, AccountData, AccountStatus};
+use crypto::{ed25519::*, test_utils::KeyPair};
+use failure::prelude::*;
+use logger::prelude::*;
+use std::{
+    path::Path,
+    thread,
+    time::{Duration, Instant},
+};
+use types::{account_address::AccountAddress, account_config::association_address};
+
+/// Time given to a mint transaction to be committed.
+const MINT_COMMIT_TIMEOUT: Duration = Duration::from_secs(60);
+/// Delay between two queries of the mint transaction while waiting for its commit.
+const MINT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Mints coins from the association account, through the admission control of a validator.
+pub struct Faucet {
+    client: ClientProxy,
+    // The validator the mint transactions are submitted to.
+    peer_id: String,
+    commit_timeout: Duration,
+}
+
+impl Faucet {
+    /// Connects to the admission control of the given validator, the association account is
+    /// signed for with the given keypair.
+    pub(crate) fn new(
+        peer_id: String,
+        host: &str,
+        ac_port: u16,
+        trusted_peers_path: &str,
+        keypair: KeyPair<Ed25519PrivateKey, Ed25519PublicKey>,
+        mnemonic_path: &Path,
+    ) -> Result<Self> {
+        let client = ClientProxy::new(
+            host,
+            &ac_port.to_string(),
+            trusted_peers_path,
+            /* faucet account file */ "",
+            false,
+            /* faucet server */ None,
+            Some(mnemonic_path.to_string_lossy().into_owned()),
+        )?;
+        let mut faucet = Self {
+            client,
+            peer_id,
+            commit_timeout: MINT_COMMIT_TIMEOUT,
+        };
+        faucet.client.faucet_account = Some(AccountData {
+            address: association_address(),
+            key_pair: Some(keypair),
+            sequence_number: 0,
+            status: AccountStatus::Persisted,
+        });
+        faucet.sync_sequence_number()?;
+        Ok(faucet)
+    }
+
+    /// Resumes from the sequence number of the association account on chain, e.g. after the
+    /// ledger was restored from a snapshot.
+    pub(crate) fn sync_sequence_number(&mut self) -> Result<()> {
+        let sequence_number = self
+            .client
+            .get_sequence_number(&["sequence", &format!("{:x}", association_address())])?;
+        if let Some(account) = self.client.faucet_account.as_mut() {
+            account.sequence_number = sequence_number;
+        }
+        Ok(())
+    }
+
+    /// The validator the mint transactions are submitted to.
+    pub fn peer_id(&self) -> &str {
+        &self.peer_id
+    }
+
+    /// The time given to a mint transaction to be committed.
+    pub fn set_commit_timeout(&mut self, timeout: Duration) {
+        self.commit_timeout = timeout;
+    }
+
+    /// Mints the given amount of microlibras to the given account, which is created if it
+    /// doesn't exist yet. Returns once the mint transaction is committed.
+    pub fn mint(&mut self, address: AccountAddress, amount: u64) -> Result<()> {
+        let sequence_number = self.sequence_number();
+        let libras = format!("{}.{:06}", amount / 1_000_000, amount % 1_000_000);
+        self.client
+            .mint_coins(&["mint", &format!("{:x}", address), &libras], false)
+            .with_context(|_| format!("Failed to mint {} to {}", libras, address))?;
+        self.wait_for_commit(sequence_number)
+            .with_context(|_| format!("Failed to mint {} to {}", libras, address))?;
+        debug!("Minted {} to {}", libras, address);
+        Ok(())
+    }
+
+    /// Removes the given validator from the validator set, the association also administers the
+    /// validator set. Returns once the reconfiguration transaction is committed.
+    pub fn remove_validator(&mut self, address: AccountAddress) -> Result<()> {
+        let sequence_number = self.sequence_number();
+        self.client
+            .execute_script_with_local_faucet_account(
+                transaction_builder::encode_remove_validator_script(&address),
+                false,
+            )
+            .with_context(|_| format!("Failed to remove the validator {}", address))?;
+        self.wait_for_commit(sequence_number)
+            .with_context(|_| format!("Failed to remove the validator {}", address))?;
+        debug!("Removed the validator {}", address);
+        Ok(())
+    }
+
+    // The sequence number of the next transaction of the association account.
+    fn sequence_number(&self) -> u64 {
+        self.client
+            .faucet_account
+            .as_ref()
+            .map_or(0, |account| account.sequence_number)
+    }
+
+    fn wait_for_commit(&mut self, sequence_number: u64) -> Result<()> {
+        let account = format!("{:x}", association_address());
+        let sequence_number = sequence_number.to_string();
+        let deadline = Instant::now() + self.commit_timeout;
+        loop {
+            if self
+                .client
+                .get_committed_txn_by_acc_seq(&[
+                    "txn_acc_seq",
+                    &account,
+                    &sequence_number,
+                    "false",
+                ])?
+                .is_some()
+            {
+                return Ok(());
+            }
+            ensure!(
+                Instant::now() < deadline,
+                "The transaction {} of the association wasn't committed within {:?}",
+                sequence_number,
+                self.commit_timeout
+            );
+            thread::sleep(MINT_POLL_INTERVAL);
+        }
+    }
+
+    /// The client the faucet submits its transactions with.
+    pub fn client(&mut self) -> &mut ClientProxy {
+        &mut self.client
+    }
+}
@@ -1,6 +1,26 @@
 // Copyright (c) The Libra Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
+pub mod catchup;
+pub mod chaos;
 pub mod client;
+pub mod consensus_stats;
+pub mod crash;
+pub mod events;
+pub mod fairness;
+pub mod faucet;
+pub mod liveness;
+pub mod log_tail;
+pub mod logs;
+pub mod metrics;
+pub mod partition;
+pub mod ports;
+pub mod preflight;
+pub mod process;
+pub mod regions;
+pub mod remote;
+pub mod resources;
 pub mod swarm;
+pub mod teardown;
+pub mod telemetry;
 pub mod utils;
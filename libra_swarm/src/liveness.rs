@@ -0,0 +1,230 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Background liveness checker for long-running swarms.
+//!
+//! Every interval the checker asserts that no validator has crashed and that the committed round
+//! has advanced on a quorum of the validators. On the first failure it records the failure with
+//! its context and kills all the monitored nodes, such that a test waiting on the swarm fails
+//! right away instead of hanging until an outer timeout.
+
+use crate::process::NodeProcess;
+use debug_interface::AsyncNodeDebugClient;
+use futures::{future, Future};
+use logger::prelude::*;
+use std::{
+    collections::HashMap,
+    fmt,
+    process::ExitStatus,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+const COMMITTED_ROUND_METRIC: &str = "consensus{op=committed_blocks_count}";
+// The stop flag is checked at this granularity while waiting for the next check.
+const STOP_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// The process of a node, None once the node has been stopped on purpose.
+pub type SharedNodeProcess = Arc<Mutex<Option<NodeProcess>>>;
+
+/// A validator monitored by the checker.
+pub struct MonitoredNode {
+    pub peer_id: String,
+    pub debug_client: AsyncNodeDebugClient,
+    pub process: SharedNodeProcess,
+}
+
+#[derive(Clone, Debug)]
+pub enum LivenessFailure {
+    /// A validator process has terminated.
+    NodeCrashed { peer_id: String, status: ExitStatus },
+    /// The committed round advanced on less than a quorum of validators during the interval.
+    NoProgress {
+        interval: Duration,
+        quorum: usize,
+        // The committed rounds at the beginning and at the end of the interval, per validator.
+        rounds: HashMap<String, (Option<i64>, Option<i64>)>,
+    },
+}
+
+impl fmt::Display for LivenessFailure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LivenessFailure::NodeCrashed { peer_id, status } => {
+                write!(f, "Validator {} crashed with {}", peer_id, status)
+            }
+            LivenessFailure::NoProgress {
+                interval,
+                quorum,
+                rounds,
+            } => {
+                writeln!(
+                    f,
+                    "The committed round advanced on less than {} validators in {:?}:",
+                    quorum, interval
+                )?;
+                let mut peer_ids: Vec<&String> = rounds.keys().collect();
+                peer_ids.sort();
+                for peer_id in peer_ids {
+                    let (before, after) = rounds[peer_id];
+                    writeln!(f, "\t{}: {:?} -> {:?}", peer_id, before, after)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Handle of the background checker, the checker is stopped when the handle is dropped.
+pub struct LivenessChecker {
+    stop: Arc<AtomicBool>,
+    failure: Arc<Mutex<Option<LivenessFailure>>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl LivenessChecker {
+    /// Starts checking the given validators every `interval`. The validators launched later are
+    /// not monitored and the ones stopped on purpose are ignored, but still count towards the
+    /// quorum.
+    pub fn start(nodes: Vec<MonitoredNode>, interval: Duration) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let failure = Arc::new(Mutex::new(None));
+        let handle = {
+            let stop = Arc::clone(&stop);
+            let failure = Arc::clone(&failure);
+            thread::Builder::new()
+                .name("swarm-liveness".to_string())
+                .spawn(move || run(nodes, interval, &stop, &failure))
+                .expect("Failed to spawn the liveness checker")
+        };
+        Self {
+            stop,
+            failure,
+            handle: Some(handle),
+        }
+    }
+
+    /// The first failure detected, if any.
+    pub fn failure(&self) -> Option<LivenessFailure> {
+        self.failure.lock().unwrap().clone()
+    }
+
+    pub fn stop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            handle.join().expect("Liveness checker panicked");
+        }
+    }
+}
+
+impl Drop for LivenessChecker {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+fn run(
+    nodes: Vec<MonitoredNode>,
+    interval: Duration,
+    stop: &AtomicBool,
+    failure: &Mutex<Option<LivenessFailure>>,
+) {
+    if nodes.is_empty() {
+        return;
+    }
+    // 2f + 1 out of n = 3f + 1 validators.
+    let quorum = nodes.len() - (nodes.len() - 1) / 3;
+    let mut rounds = committed_rounds(&nodes);
+    loop {
+        if !sleep_unless_stopped(interval, stop) {
+            return;
+        }
+        let result = check_crashes(&nodes)
+            .and_then(|()| check_progress(&nodes, &mut rounds, interval, quorum));
+        if let Err(e) = result {
+            error!("Swarm liveness check failed: {}", e);
+            *failure.lock().unwrap() = Some(e);
+            // Tear the swarm down, such that its users fail fast.
+            for node in &nodes {
+                if let Some(process) = node.process.lock().unwrap().as_mut() {
+                    if let Err(e) = process.kill_tree() {
+                        error!("Failed to kill validator {}: {}", node.peer_id, e);
+                    }
+                }
+            }
+            return;
+        }
+    }
+}
+
+// Returns false if the checker has been stopped.
+pub(crate) fn sleep_unless_stopped(duration: Duration, stop: &AtomicBool) -> bool {
+    let mut remaining = duration;
+    while remaining > Duration::from_secs(0) {
+        if stop.load(Ordering::Relaxed) {
+            return false;
+        }
+        let step = remaining.min(STOP_POLL_INTERVAL);
+        thread::sleep(step);
+        remaining -= step;
+    }
+    !stop.load(Ordering::Relaxed)
+}
+
+fn check_crashes(nodes: &[MonitoredNode]) -> Result<(), LivenessFailure> {
+    for node in nodes {
+        if let Some(process) = node.process.lock().unwrap().as_mut() {
+            if let Ok(Some(status)) = process.try_wait() {
+                return Err(LivenessFailure::NodeCrashed {
+                    peer_id: node.peer_id.clone(),
+                    status,
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+// Checks that the committed round advanced on a quorum of nodes since the previous rounds, which
+// are updated.
+fn check_progress(
+    nodes: &[MonitoredNode],
+    rounds: &mut Vec<Option<i64>>,
+    interval: Duration,
+    quorum: usize,
+) -> Result<(), LivenessFailure> {
+    let new_rounds = committed_rounds(nodes);
+    let advanced = rounds
+        .iter()
+        .zip(&new_rounds)
+        .filter(|(before, after)| after > before)
+        .count();
+    if advanced < quorum {
+        return Err(LivenessFailure::NoProgress {
+            interval,
+            quorum,
+            rounds: nodes
+                .iter()
+                .map(|node| node.peer_id.clone())
+                .zip(rounds.iter().cloned().zip(new_rounds))
+                .collect(),
+        });
+    }
+    *rounds = new_rounds;
+    Ok(())
+}
+
+// The committed rounds of all the nodes, queried concurrently.
+fn committed_rounds(nodes: &[MonitoredNode]) -> Vec<Option<i64>> {
+    future::join_all(nodes.iter().map(|node| {
+        node.debug_client
+            .get_node_metric(COMMITTED_ROUND_METRIC)
+            .then(|result| Ok::<_, ()>(result.unwrap_or(None)))
+    }))
+    .wait()
+    .unwrap_or_else(|_| vec![None; nodes.len()])
+}
@@ -0,0 +1,136 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Incremental reading of the log of a node, such that the tests can synchronize on the events
+//! the node logs without reading the whole log again and again.
+
+use failure::prelude::*;
+use regex::Regex;
+use std::{
+    collections::VecDeque,
+    fs::File,
+    io::{Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, Instant},
+};
+
+const LOG_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Follows the log of a node, the lines are returned once they are complete. A log that is
+/// truncated (e.g. when the node is added back to the swarm) is followed from its start again.
+pub struct LogTail {
+    path: PathBuf,
+    // The offset up to which the log has been read.
+    offset: u64,
+    // The beginning of the line being written.
+    partial_line: Vec<u8>,
+    // The complete lines read but not returned yet.
+    pending_lines: VecDeque<String>,
+}
+
+impl LogTail {
+    /// Follows the given log from its current end, i.e. only the lines written from now on are
+    /// returned.
+    pub fn new<P: Into<PathBuf>>(path: P) -> Result<Self> {
+        let path = path.into();
+        let offset = std::fs::metadata(&path)?.len();
+        Ok(Self::at_offset(path, offset))
+    }
+
+    /// Follows the given log from its start.
+    pub fn from_start<P: Into<PathBuf>>(path: P) -> Self {
+        Self::at_offset(path.into(), 0)
+    }
+
+    fn at_offset(path: PathBuf, offset: u64) -> Self {
+        Self {
+            path,
+            offset,
+            partial_line: vec![],
+            pending_lines: VecDeque::new(),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The complete lines written since the last call, without blocking.
+    pub fn new_lines(&mut self) -> Result<Vec<String>> {
+        self.read()?;
+        Ok(self.pending_lines.drain(..).collect())
+    }
+
+    /// The next complete line, waiting for at most the given timeout for it to be written.
+    pub fn next_line(&mut self, timeout: Duration) -> Result<Option<String>> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            self.read()?;
+            if let Some(line) = self.pending_lines.pop_front() {
+                return Ok(Some(line));
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return Ok(None);
+            }
+            thread::sleep(LOG_POLL_INTERVAL.min(deadline - now));
+        }
+    }
+
+    /// Waits for at most the given timeout for a line matching the pattern, and returns it. The
+    /// lines before the matching one are skipped.
+    pub fn wait_for_pattern(&mut self, pattern: &Regex, timeout: Duration) -> Result<String> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let now = Instant::now();
+            let remaining = if deadline > now {
+                deadline - now
+            } else {
+                Duration::from_secs(0)
+            };
+            match self.next_line(remaining)? {
+                Some(line) => {
+                    if pattern.is_match(&line) {
+                        return Ok(line);
+                    }
+                }
+                None => bail!(
+                    "No line of {:?} matched '{}' within {:?}",
+                    self.path,
+                    pattern,
+                    timeout
+                ),
+            }
+        }
+    }
+
+    // Reads what was appended to the log since the last read.
+    fn read(&mut self) -> Result<()> {
+        let mut file = File::open(&self.path)?;
+        let len = file.metadata()?.len();
+        if len < self.offset {
+            self.offset = 0;
+            self.partial_line.clear();
+        }
+        if len == self.offset {
+            return Ok(());
+        }
+        file.seek(SeekFrom::Start(self.offset))?;
+        let num_bytes = file
+            .take(len - self.offset)
+            .read_to_end(&mut self.partial_line)?;
+        self.offset += num_bytes as u64;
+        // A line is decoded once complete, such that no character is split.
+        if let Some(end) = self.partial_line.iter().rposition(|byte| *byte == b'\n') {
+            let rest = self.partial_line.split_off(end + 1);
+            let complete = std::mem::replace(&mut self.partial_line, rest);
+            self.pending_lines.extend(
+                String::from_utf8_lossy(&complete)
+                    .lines()
+                    .map(|line| line.trim_end_matches('\r').to_string()),
+            );
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,234 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Collection and query of the logs of the nodes of a swarm.
+//!
+//! The nodes of a swarm launched with structured logs write one JSON object per line (see the
+//! `LIBRA_LOG_FORMAT` variable of the logger), which the collector indexes by node, module and
+//! level. The other lines, e.g. the panics printed by the runtime, are collected as entries
+//! without level nor module, at the time of the previous entry of the node.
+
+use crate::log_tail::LogTail;
+use failure::prelude::*;
+use serde_json::Value;
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::PathBuf,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+#[cfg(test)]
+#[path = "logs_test.rs"]
+mod logs_test;
+
+/// The levels of the entries reporting errors.
+const ERROR_LEVELS: &[&str] = &["ERROR", "CRITICAL"];
+
+/// A line of the log of a node.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LogEntry {
+    /// The peer id of the node
+    pub node: String,
+    pub time: SystemTime,
+    /// The level of the entry (e.g. `INFO`), None for a line that isn't structured
+    pub level: Option<String>,
+    /// The module path of the entry, None for a line that isn't structured
+    pub module: Option<String>,
+    pub file: Option<String>,
+    pub line: Option<u64>,
+    pub message: String,
+    /// The KV values of the entry
+    pub fields: BTreeMap<String, String>,
+}
+
+impl LogEntry {
+    // Parses a line of the log of the given node, the lines that aren't structured take the
+    // given time.
+    fn parse(node: &str, line: String, default_time: SystemTime) -> Self {
+        let json = match serde_json::from_str::<Value>(&line) {
+            Ok(json @ Value::Object(_)) => json,
+            _ => {
+                return Self {
+                    node: node.to_string(),
+                    time: default_time,
+                    level: None,
+                    module: None,
+                    file: None,
+                    line: None,
+                    message: line,
+                    fields: BTreeMap::new(),
+                }
+            }
+        };
+        let string = |key: &str| json[key].as_str().map(str::to_string);
+        Self {
+            node: node.to_string(),
+            time: json["timestamp_us"]
+                .as_u64()
+                .map_or(default_time, |us| UNIX_EPOCH + Duration::from_micros(us)),
+            level: string("level"),
+            module: string("module"),
+            file: string("file"),
+            line: json["line"].as_u64(),
+            message: string("message").unwrap_or_default(),
+            fields: json["fields"]
+                .as_object()
+                .map(|fields| {
+                    fields
+                        .iter()
+                        .map(|(key, value)| {
+                            let value = match value {
+                                Value::String(value) => value.clone(),
+                                value => value.to_string(),
+                            };
+                            (key.clone(), value)
+                        })
+                        .collect()
+                })
+                .unwrap_or_default(),
+        }
+    }
+
+    /// The value of the given field: `node`, `level`, `module`, `file`, `message`, or a KV value
+    /// of the entry.
+    pub fn field(&self, name: &str) -> Option<&str> {
+        match name {
+            "node" => Some(&self.node),
+            "level" => self.level.as_ref().map(String::as_str),
+            "module" => self.module.as_ref().map(String::as_str),
+            "file" => self.file.as_ref().map(String::as_str),
+            "message" => Some(&self.message),
+            _ => self.fields.get(name).map(String::as_str),
+        }
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.level
+            .as_ref()
+            .map_or(false, |level| ERROR_LEVELS.contains(&level.as_str()))
+    }
+}
+
+/// The entries collected from the logs of the nodes. The results of the queries are ordered by
+/// time, the entries of a node remain in the order of its log.
+#[derive(Default)]
+pub struct SwarmLogs {
+    // The logs followed, by peer id.
+    tails: HashMap<String, LogTail>,
+    entries: Vec<LogEntry>,
+    // The indices of the entries, by node, module and level.
+    by_node: HashMap<String, Vec<usize>>,
+    by_module: HashMap<String, Vec<usize>>,
+    by_level: HashMap<String, Vec<usize>>,
+}
+
+impl SwarmLogs {
+    /// Collects the log of the given node from its start, the collection resumes where it
+    /// stopped if the node is already followed.
+    pub(crate) fn follow(&mut self, peer_id: &str, log: PathBuf) {
+        if !self.tails.contains_key(peer_id) {
+            self.tails
+                .insert(peer_id.to_string(), LogTail::from_start(log));
+        }
+    }
+
+    /// Collects the lines written since the last collection.
+    pub(crate) fn collect(&mut self) -> Result<()> {
+        let mut peer_ids: Vec<_> = self.tails.keys().cloned().collect();
+        peer_ids.sort();
+        for peer_id in peer_ids {
+            let lines = self.tails.get_mut(&peer_id).unwrap().new_lines()?;
+            let mut time = self
+                .by_node
+                .get(&peer_id)
+                .and_then(|indices| indices.last())
+                .map_or_else(SystemTime::now, |index| self.entries[*index].time);
+            for line in lines {
+                let entry = LogEntry::parse(&peer_id, line, time);
+                time = entry.time;
+                self.insert(entry);
+            }
+        }
+        Ok(())
+    }
+
+    fn insert(&mut self, entry: LogEntry) {
+        let index = self.entries.len();
+        self.by_node
+            .entry(entry.node.clone())
+            .or_default()
+            .push(index);
+        if let Some(module) = &entry.module {
+            self.by_module
+                .entry(module.clone())
+                .or_default()
+                .push(index);
+        }
+        if let Some(level) = &entry.level {
+            self.by_level.entry(level.clone()).or_default().push(index);
+        }
+        self.entries.push(entry);
+    }
+
+    /// All the entries.
+    pub fn entries(&self) -> Vec<&LogEntry> {
+        self.sorted((0..self.entries.len()).collect())
+    }
+
+    /// The entries of the given node.
+    pub fn node_entries(&self, peer_id: &str) -> Vec<&LogEntry> {
+        self.sorted(self.by_node.get(peer_id).cloned().unwrap_or_default())
+    }
+
+    /// The entries of the given module and of its submodules, e.g. `consensus::chained_bft`.
+    pub fn module_entries(&self, module: &str) -> Vec<&LogEntry> {
+        let prefix = format!("{}::", module);
+        self.sorted(
+            self.by_module
+                .iter()
+                .filter(|(path, _)| *path == module || path.starts_with(&prefix))
+                .flat_map(|(_, indices)| indices.iter().cloned())
+                .collect(),
+        )
+    }
+
+    /// The entries of the given level, e.g. `WARN`.
+    pub fn level_entries(&self, level: &str) -> Vec<&LogEntry> {
+        self.sorted(self.by_level.get(level).cloned().unwrap_or_default())
+    }
+
+    /// The errors logged at or after the given time.
+    pub fn errors_since(&self, time: SystemTime) -> Vec<&LogEntry> {
+        self.sorted(
+            ERROR_LEVELS
+                .iter()
+                .filter_map(|level| self.by_level.get(*level))
+                .flatten()
+                .cloned()
+                .filter(|index| self.entries[*index].time >= time)
+                .collect(),
+        )
+    }
+
+    /// The entries whose given field (see `LogEntry::field`) has the given value.
+    pub fn entries_matching(&self, field: &str, value: &str) -> Vec<&LogEntry> {
+        match field {
+            "node" => self.node_entries(value),
+            "level" => self.level_entries(value),
+            _ => self.sorted(
+                (0..self.entries.len())
+                    .filter(|index| self.entries[*index].field(field) == Some(value))
+                    .collect(),
+            ),
+        }
+    }
+
+    fn sorted(&self, mut indices: Vec<usize>) -> Vec<&LogEntry> {
+        indices.sort_by_key(|index| (self.entries[*index].time, *index));
+        indices.dedup();
+        indices
+            .into_iter()
+            .map(|index| &self.entries[index])
+            .collect()
+    }
+}
@@ -0,0 +1,156 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::logs::{LogEntry, SwarmLogs};
+use std::{
+    fs::{File, OpenOptions},
+    io::Write,
+    time::{Duration, UNIX_EPOCH},
+};
+use tools::tempdir::TempPath;
+
+fn line(timestamp_us: u64, level: &str, module: &str, message: &str) -> String {
+    format!(
+        r#"{{"timestamp_us":{},"level":"{}","module":"{}","file":"src/lib.rs","line":7,"message":"{}","fields":{{"round":{},"block":"b{}"}}}}"#,
+        timestamp_us, level, module, message, timestamp_us, timestamp_us
+    )
+}
+
+fn append(path: &TempPath, lines: &[String]) {
+    let mut file = OpenOptions::new().append(true).open(path.path()).unwrap();
+    for line in lines {
+        writeln!(file, "{}", line).unwrap();
+    }
+}
+
+fn messages(entries: Vec<&LogEntry>) -> Vec<&str> {
+    entries
+        .into_iter()
+        .map(|entry| entry.message.as_str())
+        .collect()
+}
+
+#[test]
+fn test_parse() {
+    let entry = LogEntry::parse(
+        "v0",
+        line(1_000_000, "INFO", "consensus::chained_bft", "committed"),
+        UNIX_EPOCH,
+    );
+    assert_eq!(entry.node, "v0");
+    assert_eq!(entry.time, UNIX_EPOCH + Duration::from_secs(1));
+    assert_eq!(entry.level, Some("INFO".to_string()));
+    assert_eq!(entry.module, Some("consensus::chained_bft".to_string()));
+    assert_eq!(entry.file, Some("src/lib.rs".to_string()));
+    assert_eq!(entry.line, Some(7));
+    assert_eq!(entry.field("message"), Some("committed"));
+    // The values which aren't strings are kept as JSON.
+    assert_eq!(entry.field("round"), Some("1000000"));
+    assert_eq!(entry.field("block"), Some("b1000000"));
+    assert_eq!(entry.field("node"), Some("v0"));
+    assert_eq!(entry.field("missing"), None);
+    assert!(!entry.is_error());
+
+    // A plain line takes the given time.
+    let time = UNIX_EPOCH + Duration::from_secs(5);
+    let entry = LogEntry::parse("v0", "thread 'main' panicked".to_string(), time);
+    assert_eq!(entry.time, time);
+    assert_eq!(entry.level, None);
+    assert_eq!(entry.module, None);
+    assert_eq!(entry.message, "thread 'main' panicked");
+    assert!(entry.fields.is_empty());
+    // So does a JSON value which isn't an object.
+    assert_eq!(
+        LogEntry::parse("v0", "[1, 2]".to_string(), time).level,
+        None
+    );
+}
+
+#[test]
+fn test_queries() {
+    let log0 = TempPath::new();
+    let log1 = TempPath::new();
+    File::create(log0.path()).unwrap();
+    File::create(log1.path()).unwrap();
+    append(
+        &log0,
+        &[
+            line(
+                1,
+                "INFO",
+                "consensus::chained_bft::block_store",
+                "v0 stored",
+            ),
+            line(4, "ERROR", "consensus::chained_bft", "v0 failed"),
+            // The panic is collected at the time of the previous entry.
+            "thread 'main' panicked".to_string(),
+        ],
+    );
+    append(
+        &log1,
+        &[
+            line(2, "WARN", "consensus_types", "v1 warned"),
+            line(3, "CRITICAL", "storage", "v1 critical"),
+        ],
+    );
+    let mut logs = SwarmLogs::default();
+    logs.follow("v0", log0.path().to_path_buf());
+    logs.follow("v1", log1.path().to_path_buf());
+    logs.collect().unwrap();
+
+    assert_eq!(
+        messages(logs.entries()),
+        vec![
+            "v0 stored",
+            "v1 warned",
+            "v1 critical",
+            "v0 failed",
+            "thread 'main' panicked"
+        ]
+    );
+    assert_eq!(
+        messages(logs.node_entries("v1")),
+        vec!["v1 warned", "v1 critical"]
+    );
+    assert!(logs.node_entries("v2").is_empty());
+    // The submodules are included, but not the modules sharing the prefix.
+    assert_eq!(
+        messages(logs.module_entries("consensus")),
+        vec!["v0 stored", "v0 failed"]
+    );
+    assert_eq!(
+        messages(logs.module_entries("consensus::chained_bft::block_store")),
+        vec!["v0 stored"]
+    );
+    assert_eq!(messages(logs.level_entries("WARN")), vec!["v1 warned"]);
+    assert_eq!(
+        messages(logs.errors_since(UNIX_EPOCH)),
+        vec!["v1 critical", "v0 failed"]
+    );
+    assert_eq!(
+        messages(logs.errors_since(UNIX_EPOCH + Duration::from_micros(4))),
+        vec!["v0 failed"]
+    );
+    assert_eq!(
+        messages(logs.entries_matching("node", "v0")),
+        vec!["v0 stored", "v0 failed", "thread 'main' panicked"]
+    );
+    assert_eq!(
+        messages(logs.entries_matching("level", "ERROR")),
+        vec!["v0 failed"]
+    );
+    assert_eq!(
+        messages(logs.entries_matching("block", "b2")),
+        vec!["v1 warned"]
+    );
+
+    // The collection resumes where it stopped.
+    append(&log1, &[line(5, "INFO", "storage", "v1 resumed")]);
+    logs.follow("v1", log1.path().to_path_buf());
+    logs.collect().unwrap();
+    assert_eq!(logs.entries().len(), 6);
+    assert_eq!(
+        messages(logs.node_entries("v1")).last(),
+        Some(&"v1 resumed")
+    );
+}
@@ -2,8 +2,12 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use config::config::RoleType;
-use libra_swarm::{client, swarm::LibraSwarm};
-use std::path::Path;
+use config_builder::genesis_cache::GenesisCache;
+use libra_swarm::{
+    client,
+    swarm::{ArtifactRetention, LibraSwarmBuilder},
+};
+use std::path::{Path, PathBuf};
 use structopt::StructOpt;
 use tools::tempdir::TempPath;
 
@@ -17,6 +21,9 @@ struct Args {
     /// Number of nodes to start (1 by default)
     #[structopt(short = "n", long = "num_nodes")]
     pub num_nodes: Option<usize>,
+    /// Number of full nodes to start for each validator (0 by default)
+    #[structopt(long = "num_full_nodes_per_validator")]
+    pub num_full_nodes_per_validator: Option<usize>,
     /// Enable logging
     #[structopt(short = "l", long = "enable_logging")]
     pub enable_logging: bool,
@@ -31,27 +38,62 @@ struct Args {
     /// If specified, load faucet key from this file. Otherwise generate new keypair file.
     #[structopt(short = "f", long = "faucet_key_path")]
     pub faucet_key_path: Option<String>,
+    /// Generate the validator keys and the genesis through a multi-party genesis ceremony
+    #[structopt(short = "g", long = "genesis_ceremony")]
+    pub genesis_ceremony: bool,
+    /// Generate the keys, the genesis and the faucet key anew instead of reusing the cached ones
+    #[structopt(long = "fresh")]
+    pub fresh: bool,
+    /// When to keep the logs, configs and DBs on exit: always, on_failure (default) or never
+    #[structopt(short = "r", long = "retain_artifacts")]
+    pub retain_artifacts: Option<ArtifactRetention>,
+    /// Export the retained logs, configs and DBs to this directory on exit
+    #[structopt(short = "e", long = "export_dir", parse(from_os_str))]
+    pub export_dir: Option<PathBuf>,
 }
 
 fn main() {
     let args = Args::from_args();
     let num_nodes = args.num_nodes.unwrap_or(1);
 
+    let genesis_cache = GenesisCache::new(GenesisCache::default_dir());
+    // A new faucet key means a new genesis, the cached key is reused unless fresh.
     let (faucet_account_keypair, faucet_key_file_path, _temp_dir) =
-        generate_keypair::load_faucet_key_or_create_default(args.faucet_key_path);
+        if args.faucet_key_path.is_none() && !args.fresh {
+            let (keypair, path) = genesis_cache
+                .faucet_keypair()
+                .expect("Unable to load the cached faucet key");
+            (keypair, path.to_string_lossy().into_owned(), None)
+        } else {
+            generate_keypair::load_faucet_key_or_create_default(args.faucet_key_path)
+        };
 
     println!(
         "Faucet account created in (loaded from) file {:?}",
         faucet_key_file_path
     );
 
-    let swarm = LibraSwarm::launch_swarm(
-        num_nodes,
-        !args.enable_logging,
-        faucet_account_keypair,
-        args.config_dir.clone(),
-        None, /* template_path */
-    );
+    let mut builder = LibraSwarmBuilder::new(faucet_account_keypair);
+    builder
+        .with_num_nodes(num_nodes)
+        .with_num_full_nodes_per_validator(args.num_full_nodes_per_validator.unwrap_or(0))
+        .with_logging(args.enable_logging)
+        .with_artifact_retention(args.retain_artifacts.unwrap_or_default());
+    if let Some(config_dir) = &args.config_dir {
+        builder.with_config_dir(config_dir.clone());
+    }
+    if args.genesis_ceremony {
+        builder.with_genesis_ceremony();
+    }
+    if args.fresh {
+        builder.fresh();
+    }
+    if let Some(export_dir) = args.export_dir {
+        builder.with_artifact_export_dir(export_dir);
+    }
+    let swarm = builder
+        .launch()
+        .unwrap_or_else(|e| panic!("Unable to launch swarm: {}", e));
 
     let config = &swarm.config.configs[0].1;
     let validator_set_file = &config.consensus.consensus_peers_file;
@@ -0,0 +1,29 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! The metrics scraped from the debug interface of the nodes.
+
+use std::collections::HashMap;
+
+/// All the metrics of a node, by name with labels, e.g. `consensus{op=committed_blocks_count}`.
+/// The value of a histogram is its number of samples.
+pub type NodeMetrics = HashMap<String, i64>;
+
+/// The metrics of the given family (e.g. `consensus`), by labels (e.g. `op=committed_blocks_count`,
+/// empty for a metric without labels).
+pub fn metric_family(metrics: &NodeMetrics, family: &str) -> HashMap<String, i64> {
+    metrics
+        .iter()
+        .filter_map(|(name, value)| {
+            let (name, labels) = match name.find('{') {
+                Some(start) => (&name[..start], name[start + 1..].trim_end_matches('}')),
+                None => (name.as_str(), ""),
+            };
+            if name == family {
+                Some((labels.to_string(), *value))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,515 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Network partitions and link shaping between the real node processes of a swarm.
+//!
+//! Every node keeps listening on its own port, but advertises the port of a TCP proxy run by the
+//! swarm, such that all the connections between the nodes go through the proxies. The proxy of
+//! a node identifies the node dialing it by the process owning the other end of the connection,
+//! which is only supported on Linux. A blocked link between two nodes has its connections cut
+//! and its new connections refused until the link is unblocked, in both directions. The data of
+//! a shaped link is delayed and throttled by the proxy as per the profile of the link, e.g. to
+//! emulate the latency between regions. The connections of the clients and of unidentified
+//! processes are neither blocked nor shaped.
+
+use crate::liveness::SharedNodeProcess;
+use failure::prelude::*;
+use logger::prelude::*;
+use rand::{thread_rng, Rng};
+use std::{
+    collections::{HashMap, HashSet},
+    io::{self, Read, Write},
+    net::{Ipv4Addr, Shutdown, SocketAddr, TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+#[cfg(test)]
+#[path = "partition_test.rs"]
+mod partition_test;
+
+const ACCEPT_POLL_INTERVAL: Duration = Duration::from_millis(50);
+const FORWARD_BUFFER_SIZE: usize = 16 * 1024;
+
+/// The conditions of a link between two nodes, applied to each of its connections in each
+/// direction. The default profile forwards the data as is.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LinkProfile {
+    /// Delay added to all the data
+    pub latency: Duration,
+    /// Extra delay drawn uniformly between 0 and the jitter, the data is still delivered in order
+    pub jitter: Duration,
+    /// Throughput cap, in bytes per second, unlimited if None
+    pub bandwidth: Option<u64>,
+}
+
+impl LinkProfile {
+    /// The profile of a link with the given latency.
+    pub fn with_latency(latency: Duration) -> Self {
+        Self {
+            latency,
+            ..Self::default()
+        }
+    }
+
+    /// The longest delay added to the data.
+    pub fn max_delay(&self) -> Duration {
+        self.latency + self.jitter
+    }
+
+    fn delay(&self) -> Duration {
+        let jitter = self.jitter.as_micros() as u64;
+        if jitter == 0 {
+            self.latency
+        } else {
+            self.latency + Duration::from_micros(thread_rng().gen_range(0, jitter + 1))
+        }
+    }
+
+    // The time it takes to send the given number of bytes.
+    fn transmission_time(&self, num_bytes: usize) -> Duration {
+        match self.bandwidth {
+            Some(bandwidth) if bandwidth > 0 => {
+                Duration::from_micros(num_bytes as u64 * 1_000_000 / bandwidth)
+            }
+            _ => Duration::from_secs(0),
+        }
+    }
+}
+
+/// The proxies of the nodes of a swarm, the proxies are stopped when dropped.
+pub struct NetworkProxies {
+    links: Arc<Links>,
+    stop: Arc<AtomicBool>,
+    handles: Vec<JoinHandle<()>>,
+}
+
+// The state shared by the proxies.
+struct Links {
+    // The node processes, by peer id, to identify the dialers.
+    nodes: Mutex<HashMap<String, SharedNodeProcess>>,
+    state: Mutex<LinkState>,
+}
+
+#[derive(Default)]
+struct LinkState {
+    // The blocked links, as ordered pairs of peer ids.
+    blocked: HashSet<(String, String)>,
+    // The profiles of the shaped links.
+    profiles: HashMap<(String, String), LinkProfile>,
+    // The open connections, by id.
+    connections: HashMap<u64, Connection>,
+    next_connection_id: u64,
+}
+
+struct Connection {
+    // The peer id of the node the connection goes to.
+    target: String,
+    // The peer id of the node that dialed, None if it is not identified yet.
+    dialer: Option<String>,
+    // The local ports of the dialer and of the proxy.
+    dialer_port: u16,
+    proxy_port: u16,
+    // Both ends of the connection, cut together.
+    streams: Vec<TcpStream>,
+}
+
+impl Connection {
+    fn cut(&self) {
+        for stream in &self.streams {
+            let _ = stream.shutdown(Shutdown::Both);
+        }
+    }
+}
+
+impl NetworkProxies {
+    pub fn new() -> Self {
+        Self {
+            links: Arc::new(Links {
+                nodes: Mutex::new(HashMap::new()),
+                state: Mutex::new(LinkState::default()),
+            }),
+            stop: Arc::new(AtomicBool::new(false)),
+            handles: vec![],
+        }
+    }
+
+    /// Starts the proxy of the given node, which forwards the connections to the given local
+    /// port of the node. Returns the port the proxy listens on, to be advertised by the node.
+    pub fn start_proxy(&mut self, peer_id: String, node_port: u16) -> Result<u16> {
+        let listener = TcpListener::bind((Ipv4Addr::UNSPECIFIED, 0))?;
+        listener.set_nonblocking(true)?;
+        let proxy_port = listener.local_addr()?.port();
+        let links = Arc::clone(&self.links);
+        let stop = Arc::clone(&self.stop);
+        let handle = thread::Builder::new()
+            .name(format!("swarm-proxy-{}", proxy_port))
+            .spawn(move || accept_loop(&listener, &peer_id, node_port, &links, &stop))?;
+        self.handles.push(handle);
+        Ok(proxy_port)
+    }
+
+    /// Identifies the connections dialed by the given process as coming from the given node. A
+    /// restarted node is registered again.
+    pub fn register_node(&self, peer_id: String, process: SharedNodeProcess) {
+        self.links.nodes.lock().unwrap().insert(peer_id, process);
+    }
+
+    /// Cuts the connections between the two nodes, and refuses the new ones until the link is
+    /// unblocked.
+    pub fn block_link(&self, a: &str, b: &str) -> Result<()> {
+        ensure!(
+            cfg!(target_os = "linux"),
+            "The links between the nodes can only be blocked on Linux"
+        );
+        let mut state = self.links.state.lock().unwrap();
+        state.blocked.insert(link(a, b));
+        self.links.identify_connections(&mut state);
+        for connection in state.connections.values() {
+            if let Some(dialer) = &connection.dialer {
+                if link(dialer, &connection.target) == link(a, b) {
+                    connection.cut();
+                }
+            }
+        }
+        info!("Blocked the link between {} and {}", a, b);
+        Ok(())
+    }
+
+    /// Lets the two nodes connect again.
+    pub fn unblock_link(&self, a: &str, b: &str) {
+        if self.links.state.lock().unwrap().blocked.remove(&link(a, b)) {
+            info!("Unblocked the link between {} and {}", a, b);
+        }
+    }
+
+    /// Applies the given profile to the current and future connections of the link between the
+    /// two nodes, the data already in flight keeps its delay.
+    pub fn set_link_profile(&self, a: &str, b: &str, profile: LinkProfile) -> Result<()> {
+        ensure!(
+            cfg!(target_os = "linux"),
+            "The links between the nodes can only be shaped on Linux"
+        );
+        let mut state = self.links.state.lock().unwrap();
+        if profile == LinkProfile::default() {
+            state.profiles.remove(&link(a, b));
+        } else {
+            state.profiles.insert(link(a, b), profile);
+        }
+        self.links.identify_connections(&mut state);
+        info!("Link between {} and {}: {:?}", a, b, profile);
+        Ok(())
+    }
+
+    /// The longest delay added by any link.
+    pub fn max_link_delay(&self) -> Duration {
+        self.links
+            .state
+            .lock()
+            .unwrap()
+            .profiles
+            .values()
+            .map(LinkProfile::max_delay)
+            .max()
+            .unwrap_or_else(|| Duration::from_secs(0))
+    }
+
+    /// Unblocks all the links.
+    pub fn unblock_all(&self) {
+        self.links.state.lock().unwrap().blocked.clear();
+        info!("Unblocked all the links");
+    }
+
+    pub fn is_blocked(&self, a: &str, b: &str) -> bool {
+        self.links
+            .state
+            .lock()
+            .unwrap()
+            .blocked
+            .contains(&link(a, b))
+    }
+
+    /// The blocked links, as ordered pairs of peer ids.
+    pub fn blocked_links(&self) -> Vec<(String, String)> {
+        let mut blocked: Vec<_> = self
+            .links
+            .state
+            .lock()
+            .unwrap()
+            .blocked
+            .iter()
+            .cloned()
+            .collect();
+        blocked.sort();
+        blocked
+    }
+}
+
+impl Default for NetworkProxies {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for NetworkProxies {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        for handle in self.handles.drain(..) {
+            if handle.join().is_err() {
+                error!("Network proxy panicked");
+            }
+        }
+        for connection in self.links.state.lock().unwrap().connections.values() {
+            connection.cut();
+        }
+    }
+}
+
+impl Links {
+    // The node owning the dialing end of the connection, if any.
+    fn identify(&self, connection: &Connection) -> Option<String> {
+        let inode = platform::socket_inode(connection.dialer_port, connection.proxy_port)?;
+        self.nodes
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|(_, process)| {
+                process
+                    .lock()
+                    .unwrap()
+                    .as_ref()
+                    .map_or(false, |process| platform::owns_socket(process.id(), &inode))
+            })
+            .map(|(peer_id, _)| peer_id.clone())
+    }
+
+    // Identifies the connections accepted before their dialer was registered.
+    fn identify_connections(&self, state: &mut LinkState) {
+        for connection in state.connections.values_mut() {
+            if connection.dialer.is_none() {
+                connection.dialer = self.identify(connection);
+            }
+        }
+    }
+
+    // The current profile of the given connection.
+    fn profile(&self, id: u64) -> LinkProfile {
+        let state = self.state.lock().unwrap();
+        state
+            .connections
+            .get(&id)
+            .and_then(|connection| {
+                let dialer = connection.dialer.as_ref()?;
+                state
+                    .profiles
+                    .get(&link(dialer, &connection.target))
+                    .cloned()
+            })
+            .unwrap_or_default()
+    }
+}
+
+// A link is the same in both directions.
+fn link(a: &str, b: &str) -> (String, String) {
+    if a <= b {
+        (a.to_string(), b.to_string())
+    } else {
+        (b.to_string(), a.to_string())
+    }
+}
+
+fn accept_loop(
+    listener: &TcpListener,
+    target: &str,
+    node_port: u16,
+    links: &Arc<Links>,
+    stop: &AtomicBool,
+) {
+    while !stop.load(Ordering::Relaxed) {
+        match listener.accept() {
+            Ok((stream, dialer)) => {
+                if let Err(e) = forward(stream, dialer, target, node_port, links) {
+                    debug!("Proxy of {} dropped a connection: {}", target, e);
+                }
+            }
+            Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {
+                thread::sleep(ACCEPT_POLL_INTERVAL)
+            }
+            Err(e) => warn!("Proxy of {} failed to accept a connection: {}", target, e),
+        }
+    }
+}
+
+// Forwards the accepted connection to the node, unless the link with the dialer is blocked.
+fn forward(
+    stream: TcpStream,
+    dialer: SocketAddr,
+    target: &str,
+    node_port: u16,
+    links: &Arc<Links>,
+) -> Result<()> {
+    stream.set_nonblocking(false)?;
+    let mut connection = Connection {
+        target: target.to_string(),
+        dialer: None,
+        dialer_port: dialer.port(),
+        proxy_port: stream.local_addr()?.port(),
+        streams: vec![],
+    };
+    connection.dialer = links.identify(&connection);
+    let upstream = TcpStream::connect((Ipv4Addr::LOCALHOST, node_port))?;
+    connection.streams = vec![stream.try_clone()?, upstream.try_clone()?];
+    let id = {
+        let mut state = links.state.lock().unwrap();
+        if let Some(dialer) = &connection.dialer {
+            ensure!(
+                !state.blocked.contains(&link(dialer, target)),
+                "the link with {} is blocked",
+                dialer
+            );
+        }
+        let id = state.next_connection_id;
+        state.next_connection_id += 1;
+        state.connections.insert(id, connection);
+        id
+    };
+    let inbound = {
+        let (from, to) = (stream.try_clone()?, upstream.try_clone()?);
+        let links = Arc::clone(links);
+        thread::spawn(move || pump(from, to, &links, id))
+    };
+    let links = Arc::clone(links);
+    thread::spawn(move || {
+        pump(upstream, stream, &links, id);
+        let _ = inbound.join();
+        links.state.lock().unwrap().connections.remove(&id);
+    });
+    Ok(())
+}
+
+// Copies the data from one end of the connection to the other until either end is closed. The
+// data is delayed and throttled as per the profile of the link at the time it is read.
+fn pump(mut from: TcpStream, mut to: TcpStream, links: &Links, id: u64) {
+    let (sender, receiver) = mpsc::channel::<(Instant, Vec<u8>, Duration)>();
+    let writer = {
+        let to = match to.try_clone() {
+            Ok(to) => to,
+            Err(_) => return,
+        };
+        thread::spawn(move || deliver(to, receiver))
+    };
+    let mut buffer = [0u8; FORWARD_BUFFER_SIZE];
+    let mut last_delivery = Instant::now();
+    loop {
+        let num_bytes = match from.read(&mut buffer) {
+            Ok(0) | Err(_) => break,
+            Ok(num_bytes) => num_bytes,
+        };
+        let profile = links.profile(id);
+        let delivery = (Instant::now() + profile.delay()).max(last_delivery);
+        last_delivery = delivery;
+        let data = buffer[..num_bytes].to_vec();
+        if sender
+            .send((delivery, data, profile.transmission_time(num_bytes)))
+            .is_err()
+        {
+            break;
+        }
+    }
+    drop(sender);
+    let _ = writer.join();
+    let _ = from.shutdown(Shutdown::Both);
+    let _ = to.shutdown(Shutdown::Both);
+}
+
+// Writes the data at its delivery time, and waits for its transmission time after it.
+fn deliver(mut to: TcpStream, receiver: mpsc::Receiver<(Instant, Vec<u8>, Duration)>) {
+    for (delivery, data, transmission_time) in receiver {
+        let now = Instant::now();
+        if delivery > now {
+            thread::sleep(delivery - now);
+        }
+        if to.write_all(&data).is_err() {
+            break;
+        }
+        thread::sleep(transmission_time);
+    }
+    let _ = to.shutdown(Shutdown::Both);
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use std::fs;
+
+    /// The inode of the TCP socket connected from the given local port to the given remote port.
+    pub fn socket_inode(local_port: u16, remote_port: u16) -> Option<String> {
+        for table in &["/proc/net/tcp", "/proc/net/tcp6"] {
+            let content = match fs::read_to_string(table) {
+                Ok(content) => content,
+                Err(_) => continue,
+            };
+            for line in content.lines().skip(1) {
+                let fields: Vec<&str> = line.split_whitespace().collect();
+                if fields.len() > 9
+                    && port(fields[1]) == Some(local_port)
+                    && port(fields[2]) == Some(remote_port)
+                {
+                    return Some(fields[9].to_string());
+                }
+            }
+        }
+        None
+    }
+
+    // The port of an address of the table, e.g. `0100007F:1F90`.
+    fn port(address: &str) -> Option<u16> {
+        u16::from_str_radix(address.rsplit(':').next()?, 16).ok()
+    }
+
+    /// Whether the given process has a descriptor of the socket of the given inode.
+    pub fn owns_socket(pid: u32, inode: &str) -> bool {
+        let socket = format!("socket:[{}]", inode);
+        match fs::read_dir(format!("/proc/{}/fd", pid)) {
+            Ok(fds) => fds.filter_map(|fd| fd.ok()).any(|fd| {
+                fs::read_link(fd.path()).map_or(false, |link| link.as_os_str() == socket.as_str())
+            }),
+            Err(_) => false,
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod platform {
+    pub fn socket_inode(_local_port: u16, _remote_port: u16) -> Option<String> {
+        None
+    }
+
+    pub fn owns_socket(_pid: u32, _inode: &str) -> bool {
+        false
+    }
+}
+
+/// The TCP port of the given multiaddr, e.g. `/ip4/0.0.0.0/tcp/6180`.
+pub fn tcp_port(addr: &str) -> Option<u16> {
+    let components: Vec<&str> = addr.split('/').collect();
+    components
+        .iter()
+        .position(|component| *component == "tcp")
+        .and_then(|index| components.get(index + 1))
+        .and_then(|port| port.parse().ok())
+}
+
+/// Replaces the TCP port of the given multiaddr.
+pub fn with_tcp_port(addr: &str, port: u16) -> String {
+    let mut components: Vec<String> = addr.split('/').map(str::to_string).collect();
+    if let Some(index) = components.iter().position(|component| component == "tcp") {
+        if index + 1 < components.len() {
+            components[index + 1] = port.to_string();
+        }
+    }
+    components.join("/")
+}
@@ -0,0 +1,144 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::partition::{self, link, Connection, LinkProfile, NetworkProxies};
+use std::time::Duration;
+
+fn millis(millis: u64) -> Duration {
+    Duration::from_millis(millis)
+}
+
+#[test]
+fn test_link_is_symmetric() {
+    assert_eq!(link("a", "b"), ("a".to_string(), "b".to_string()));
+    assert_eq!(link("b", "a"), link("a", "b"));
+    assert_eq!(link("a", "a"), ("a".to_string(), "a".to_string()));
+}
+
+#[test]
+fn test_link_profile_delay() {
+    let default = LinkProfile::default();
+    assert_eq!(default.delay(), millis(0));
+    assert_eq!(default.max_delay(), millis(0));
+
+    let profile = LinkProfile::with_latency(millis(20));
+    assert_eq!(profile.delay(), millis(20));
+    assert_eq!(profile.max_delay(), millis(20));
+
+    let profile = LinkProfile {
+        jitter: millis(5),
+        ..profile
+    };
+    assert_eq!(profile.max_delay(), millis(25));
+    for _ in 0..100 {
+        let delay = profile.delay();
+        assert!(delay >= millis(20) && delay <= millis(25), "{:?}", delay);
+    }
+}
+
+#[test]
+fn test_link_profile_transmission_time() {
+    let unlimited = LinkProfile::default();
+    assert_eq!(unlimited.transmission_time(1_000_000), millis(0));
+    let zero = LinkProfile {
+        bandwidth: Some(0),
+        ..LinkProfile::default()
+    };
+    assert_eq!(zero.transmission_time(1_000_000), millis(0));
+    let capped = LinkProfile {
+        bandwidth: Some(1000),
+        ..LinkProfile::default()
+    };
+    assert_eq!(capped.transmission_time(500), millis(500));
+    assert_eq!(capped.transmission_time(2000), millis(2000));
+}
+
+#[test]
+fn test_tcp_port() {
+    assert_eq!(partition::tcp_port("/ip4/0.0.0.0/tcp/6180"), Some(6180));
+    assert_eq!(partition::tcp_port("/ip6/::1/tcp/80"), Some(80));
+    assert_eq!(partition::tcp_port("/ip4/0.0.0.0/udp/6180"), None);
+    assert_eq!(partition::tcp_port("/ip4/0.0.0.0/tcp"), None);
+    assert_eq!(partition::tcp_port("/ip4/0.0.0.0/tcp/port"), None);
+
+    assert_eq!(
+        partition::with_tcp_port("/ip4/127.0.0.1/tcp/6180", 7000),
+        "/ip4/127.0.0.1/tcp/7000"
+    );
+    assert_eq!(
+        partition::with_tcp_port("/ip4/127.0.0.1/udp/6180", 7000),
+        "/ip4/127.0.0.1/udp/6180"
+    );
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_blocked_links() {
+    let proxies = NetworkProxies::new();
+    proxies.block_link("b", "a").unwrap();
+    proxies.block_link("c", "a").unwrap();
+    assert!(proxies.is_blocked("a", "b"));
+    assert!(proxies.is_blocked("b", "a"));
+    assert!(!proxies.is_blocked("b", "c"));
+    assert_eq!(
+        proxies.blocked_links(),
+        vec![
+            ("a".to_string(), "b".to_string()),
+            ("a".to_string(), "c".to_string())
+        ]
+    );
+
+    proxies.unblock_link("a", "b");
+    assert!(!proxies.is_blocked("b", "a"));
+    assert!(proxies.is_blocked("a", "c"));
+    proxies.unblock_all();
+    assert!(proxies.blocked_links().is_empty());
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_link_profiles() {
+    let proxies = NetworkProxies::new();
+    assert_eq!(proxies.max_link_delay(), millis(0));
+    let slow = LinkProfile {
+        latency: millis(100),
+        jitter: millis(10),
+        bandwidth: None,
+    };
+    proxies
+        .set_link_profile("a", "b", LinkProfile::with_latency(millis(50)))
+        .unwrap();
+    proxies.set_link_profile("c", "a", slow).unwrap();
+    assert_eq!(proxies.max_link_delay(), millis(110));
+
+    // The connections identified as going through a link take its profile, in both directions.
+    {
+        let mut state = proxies.links.state.lock().unwrap();
+        for (id, (dialer, target)) in [(Some("a"), "c"), (Some("c"), "a"), (None, "c")]
+            .iter()
+            .enumerate()
+        {
+            state.connections.insert(
+                id as u64,
+                Connection {
+                    target: target.to_string(),
+                    dialer: dialer.map(str::to_string),
+                    dialer_port: 0,
+                    proxy_port: 0,
+                    streams: vec![],
+                },
+            );
+        }
+    }
+    assert_eq!(proxies.links.profile(0), slow);
+    assert_eq!(proxies.links.profile(1), slow);
+    assert_eq!(proxies.links.profile(2), LinkProfile::default());
+    assert_eq!(proxies.links.profile(3), LinkProfile::default());
+
+    // The default profile removes the shaping of the link.
+    proxies
+        .set_link_profile("a", "c", LinkProfile::default())
+        .unwrap();
+    assert_eq!(proxies.links.profile(0), LinkProfile::default());
+    assert_eq!(proxies.max_link_delay(), millis(50));
+}
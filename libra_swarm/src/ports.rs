@@ -0,0 +1,211 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Reservation of the ports of the swarm nodes.
+//!
+//! The ports picked when the configs are generated are only free at that time: another process
+//! (e.g. a concurrent test on CI) can bind them before the nodes are spawned. The allocator binds
+//! every port of the configs until the node using it is spawned, and a port found taken is
+//! replaced by a free one in the config of its node, instead of retrying the whole launch.
+
+use crate::partition;
+use config::{
+    config::{NodeConfig, PersistableConfig},
+    seed_peers::SeedPeersConfig,
+};
+use config_builder::swarm_config::SwarmConfig;
+use failure::prelude::*;
+use logger::prelude::*;
+use parity_multiaddr::Multiaddr;
+use std::{
+    collections::{HashMap, HashSet},
+    net::TcpListener,
+    path::PathBuf,
+};
+
+#[cfg(test)]
+#[path = "ports_test.rs"]
+mod ports_test;
+
+/// Holds the ports of the nodes bound until the nodes are spawned.
+#[derive(Default)]
+pub struct PortAllocator {
+    // The listeners holding the reserved ports, by peer id.
+    reservations: HashMap<String, Vec<TcpListener>>,
+    reserved: HashSet<u16>,
+}
+
+impl PortAllocator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reserves the ports of the given nodes. A port that is taken, by another process or by
+    /// another service of the swarm, is replaced by a free one and the config of the node is
+    /// saved. Returns the new network addresses of the nodes whose network port was replaced,
+    /// by peer id, such that the seed peers can be updated.
+    pub fn reserve_nodes(
+        &mut self,
+        configs: &mut [(PathBuf, NodeConfig)],
+    ) -> Result<HashMap<String, Multiaddr>> {
+        let mut addresses = HashMap::new();
+        for (path, config) in configs.iter_mut() {
+            let peer_id = config.networks.get(0).unwrap().peer_id.clone();
+            let mut changed = false;
+            for (service, port) in service_ports_mut(config) {
+                if !self.reserve(&peer_id, *port) {
+                    let new_port = self.reserve_free(&peer_id)?;
+                    info!(
+                        "Port {} of the {} of node {} is taken, using {}",
+                        port, service, peer_id, new_port
+                    );
+                    *port = new_port;
+                    changed = true;
+                }
+            }
+            let network = config.networks.get_mut(0).unwrap();
+            let listen_address = network.listen_address.to_string();
+            if let Some(port) = partition::tcp_port(&listen_address) {
+                if !self.reserve(&peer_id, port) {
+                    let new_port = self.reserve_free(&peer_id)?;
+                    info!(
+                        "Network port {} of node {} is taken, using {}",
+                        port, peer_id, new_port
+                    );
+                    let parse = |addr: String| {
+                        addr.parse::<Multiaddr>()
+                            .map_err(|_| format_err!("Invalid address {}", addr))
+                    };
+                    network.listen_address =
+                        parse(partition::with_tcp_port(&listen_address, new_port))?;
+                    // The node is reached at the port it listens on.
+                    let advertised_address = network.advertised_address.to_string();
+                    if partition::tcp_port(&advertised_address) == Some(port) {
+                        network.advertised_address =
+                            parse(partition::with_tcp_port(&advertised_address, new_port))?;
+                        addresses.insert(peer_id.clone(), network.advertised_address.clone());
+                    }
+                    changed = true;
+                }
+            }
+            if changed {
+                config.save_config(path);
+            }
+        }
+        Ok(addresses)
+    }
+
+    /// Releases the ports of the given node, right before the node is spawned.
+    pub fn release(&mut self, peer_id: &str) {
+        if let Some(listeners) = self.reservations.remove(peer_id) {
+            for listener in listeners {
+                if let Ok(addr) = listener.local_addr() {
+                    self.reserved.remove(&addr.port());
+                }
+            }
+        }
+    }
+
+    // Binds the given port for the given node, returns false if the port is taken.
+    fn reserve(&mut self, peer_id: &str, port: u16) -> bool {
+        if self.reserved.contains(&port) {
+            return false;
+        }
+        match TcpListener::bind(("0.0.0.0", port)) {
+            Ok(listener) => {
+                self.hold(peer_id, port, listener);
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    // Binds a port picked by the OS for the given node.
+    fn reserve_free(&mut self, peer_id: &str) -> Result<u16> {
+        let listener = TcpListener::bind(("0.0.0.0", 0))?;
+        let port = listener.local_addr()?.port();
+        self.hold(peer_id, port, listener);
+        Ok(port)
+    }
+
+    fn hold(&mut self, peer_id: &str, port: u16, listener: TcpListener) {
+        self.reserved.insert(port);
+        self.reservations
+            .entry(peer_id.to_string())
+            .or_default()
+            .push(listener);
+    }
+}
+
+/// Replaces the addresses of the given nodes in all the seed peers files of the swarm.
+pub fn update_seed_peers(config: &mut SwarmConfig, addresses: &HashMap<String, Multiaddr>) {
+    // The full nodes have seed peers of their own.
+    let seed_peers_files: HashSet<PathBuf> = config
+        .configs
+        .iter()
+        .map(|(path, node_config)| {
+            path.with_file_name(&node_config.networks.get(0).unwrap().seed_peers_file)
+        })
+        .collect();
+    for seed_peers_file in seed_peers_files {
+        let mut seed_peers = SeedPeersConfig::load_config(&seed_peers_file);
+        for (peer_id, addrs) in seed_peers.seed_peers.iter_mut() {
+            if let Some(address) = addresses.get(peer_id) {
+                *addrs = vec![address.clone()];
+            }
+        }
+        seed_peers.save_config(&seed_peers_file);
+    }
+    let (seed_peers_file, seed_peers) = &mut config.seed_peers;
+    *seed_peers = SeedPeersConfig::load_config(&seed_peers_file);
+}
+
+// The ports of the services of a node, by service, borrowed as `&` or `&mut`. The services are
+// listed once for the checks and for the reassignment of the ports. The network port isn't
+// listed as it is part of the listen address of the node.
+macro_rules! service_ports {
+    ($config:expr, $($borrow:tt)+) => {
+        vec![
+            (
+                "admission control",
+                $($borrow)+ $config.admission_control.admission_control_service_port,
+            ),
+            (
+                "debug interface",
+                $($borrow)+ $config.debug_interface.admission_control_node_debug_port,
+            ),
+            (
+                "metrics server",
+                $($borrow)+ $config.debug_interface.metrics_server_port,
+            ),
+            (
+                "secret service debug interface",
+                $($borrow)+ $config.debug_interface.secret_service_node_debug_port,
+            ),
+            (
+                "storage debug interface",
+                $($borrow)+ $config.debug_interface.storage_node_debug_port,
+            ),
+            ("execution", $($borrow)+ $config.execution.port),
+            ("mempool", $($borrow)+ $config.mempool.mempool_service_port),
+            (
+                "secret service",
+                $($borrow)+ $config.secret_service.secret_service_port,
+            ),
+            ("storage", $($borrow)+ $config.storage.port),
+        ]
+    };
+}
+
+/// The ports of the services of a node, by service. The network port isn't listed as it is part
+/// of the listen address of the node.
+pub fn service_ports(config: &NodeConfig) -> Vec<(&'static str, u16)> {
+    service_ports!(config, &)
+        .into_iter()
+        .map(|(service, port)| (service, *port))
+        .collect()
+}
+
+fn service_ports_mut(config: &mut NodeConfig) -> Vec<(&'static str, &mut u16)> {
+    service_ports!(config, &mut)
+}
@@ -0,0 +1,90 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    partition,
+    ports::{self, PortAllocator},
+    utils,
+};
+use config::{
+    config::{NodeConfig, PersistableConfig},
+    seed_peers::SeedPeersConfig,
+};
+use std::net::TcpListener;
+use tools::tempdir::TempPath;
+
+fn network_port(config: &NodeConfig) -> u16 {
+    partition::tcp_port(&config.networks.get(0).unwrap().listen_address.to_string()).unwrap()
+}
+
+#[test]
+fn test_free_ports_are_kept() {
+    let dir = TempPath::new();
+    let mut config = utils::test_swarm_config(dir.path(), 2);
+    let ports_before: Vec<_> = config
+        .configs
+        .iter()
+        .map(|(_, node_config)| (ports::service_ports(node_config), network_port(node_config)))
+        .collect();
+
+    let mut allocator = PortAllocator::new();
+    let addresses = allocator.reserve_nodes(&mut config.configs).unwrap();
+    assert!(addresses.is_empty());
+    let ports_after: Vec<_> = config
+        .configs
+        .iter()
+        .map(|(_, node_config)| (ports::service_ports(node_config), network_port(node_config)))
+        .collect();
+    assert_eq!(ports_before, ports_after);
+}
+
+#[test]
+fn test_taken_ports_are_reassigned() {
+    let dir = TempPath::new();
+    let mut config = utils::test_swarm_config(dir.path(), 2);
+    // Another process binds the network port of the first node and the storage port of the
+    // second one in the meantime.
+    let network_port_taken = network_port(&config.configs[0].1);
+    let storage_port_taken = config.configs[1].1.storage.port;
+    let _network_listener = TcpListener::bind(("0.0.0.0", network_port_taken)).unwrap();
+    let _storage_listener = TcpListener::bind(("0.0.0.0", storage_port_taken)).unwrap();
+
+    let mut allocator = PortAllocator::new();
+    let addresses = allocator.reserve_nodes(&mut config.configs).unwrap();
+
+    // The new ports are saved in the configs of the nodes.
+    let (path, node_config) = &config.configs[0];
+    let new_network_port = network_port(node_config);
+    assert_ne!(new_network_port, network_port_taken);
+    assert_eq!(
+        network_port(&NodeConfig::load_config(path)),
+        new_network_port
+    );
+    let (path, node_config) = &config.configs[1];
+    assert_ne!(node_config.storage.port, storage_port_taken);
+    assert_eq!(
+        NodeConfig::load_config(path).storage.port,
+        node_config.storage.port
+    );
+
+    // Only the node whose network port changed is advertised at a new address.
+    let peer_id = config.configs[0].1.networks.get(0).unwrap().peer_id.clone();
+    assert_eq!(addresses.len(), 1);
+    let address = addresses[&peer_id].clone();
+    assert_eq!(
+        partition::tcp_port(&address.to_string()),
+        Some(new_network_port)
+    );
+
+    // The seed peers reach the node at its new address.
+    ports::update_seed_peers(&mut config, &addresses);
+    assert_eq!(
+        config.seed_peers.1.seed_peers[&peer_id],
+        vec![address.clone()]
+    );
+    let (seed_peers_file, _) = &config.seed_peers;
+    assert_eq!(
+        SeedPeersConfig::load_config(seed_peers_file).seed_peers[&peer_id],
+        vec![address]
+    );
+}
@@ -0,0 +1,428 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Checks run before spawning the swarm nodes, such that a broken environment is reported with
+//! a specific error instead of surfacing as nodes crashing at startup.
+
+use crate::ports;
+use config::{
+    config::{NetworkConfig, NodeConfig, NodeConfigHelpers, PersistableConfig},
+    seed_peers::SeedPeersConfig,
+    trusted_peers::{ConsensusPeersConfig, NetworkPeersConfig},
+};
+use config_builder::swarm_config::SwarmConfig;
+use failure::Fail;
+use std::{
+    collections::{HashMap, HashSet},
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+#[cfg(test)]
+#[path = "preflight_test.rs"]
+mod preflight_test;
+
+const PROPOSER_TYPES: &[&str] = &[
+    "fixed_proposer",
+    "rotating_proposer",
+    "multiple_ordered_proposers",
+    "leader_reputation",
+    "weighted_rotating_proposer",
+];
+const COMMIT_RULES: &[&str] = &["three_chain", "two_chain"];
+const STORAGE_BACKENDS: &[&str] = &["rocksdb", "in_memory"];
+const SAFETY_RULES_BACKENDS: &[&str] = &["in_process", "process"];
+
+#[derive(Debug, Fail)]
+pub enum PreflightFailure {
+    /// The workspace root doesn't look like the root of the Libra workspace
+    #[fail(display = "Invalid workspace root {:?}: no Cargo.toml found", _0)]
+    InvalidWorkspaceRoot(PathBuf),
+    /// The node binary doesn't exist
+    #[fail(display = "Node binary not found at {:?}", _0)]
+    BinaryNotFound(PathBuf),
+    /// The node binary exists but can't be executed
+    #[fail(display = "Node binary {:?} is not executable", _0)]
+    BinaryNotExecutable(PathBuf),
+    /// The template config doesn't exist
+    #[fail(display = "Template config not found at {:?}", _0)]
+    TemplateNotFound(PathBuf),
+    /// The template config isn't a valid node config
+    #[fail(display = "Invalid template config {:?}: {}", path, error)]
+    InvalidTemplate { path: PathBuf, error: String },
+    /// The generated node configs have problems which would prevent the nodes from starting
+    #[fail(display = "Invalid node configs:{}", _0)]
+    InvalidConfigs(ConfigProblems),
+}
+
+pub fn check_workspace_root(workspace_root: &Path) -> Result<(), PreflightFailure> {
+    if !workspace_root.join("Cargo.toml").is_file() {
+        return Err(PreflightFailure::InvalidWorkspaceRoot(
+            workspace_root.to_path_buf(),
+        ));
+    }
+    Ok(())
+}
+
+pub fn check_binary(bin_path: &Path) -> Result<(), PreflightFailure> {
+    let metadata = fs::metadata(bin_path)
+        .map_err(|_| PreflightFailure::BinaryNotFound(bin_path.to_path_buf()))?;
+    if !metadata.is_file() || !is_executable(&metadata) {
+        return Err(PreflightFailure::BinaryNotExecutable(
+            bin_path.to_path_buf(),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn is_executable(metadata: &fs::Metadata) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    metadata.permissions().mode() & 0o111 != 0
+}
+
+#[cfg(not(unix))]
+fn is_executable(_metadata: &fs::Metadata) -> bool {
+    true
+}
+
+pub fn check_template(template_path: &Path) -> Result<(), PreflightFailure> {
+    if !template_path.is_file() {
+        return Err(PreflightFailure::TemplateNotFound(
+            template_path.to_path_buf(),
+        ));
+    }
+    let invalid_template = |error: String| PreflightFailure::InvalidTemplate {
+        path: template_path.to_path_buf(),
+        error,
+    };
+    let contents =
+        fs::read_to_string(template_path).map_err(|e| invalid_template(e.to_string()))?;
+    NodeConfig::parse(&contents).map_err(|e| invalid_template(e.to_string()))?;
+    Ok(())
+}
+
+/// A problem of the generated config of a node.
+#[derive(Clone, Debug)]
+pub enum ConfigProblem {
+    /// Two nodes (or two services of the same node) are configured with the same port
+    DuplicatePort {
+        port: u16,
+        first: String,
+        second: String,
+    },
+    /// A file referenced by the config of the node doesn't exist or can't be parsed
+    InvalidFile {
+        peer_id: String,
+        file: &'static str,
+        path: PathBuf,
+        error: String,
+    },
+    /// A seed peer of the node isn't a node of the swarm, isn't trusted by the node or isn't
+    /// reachable at its seed address
+    InconsistentSeedPeer {
+        peer_id: String,
+        seed_peer: String,
+        reason: String,
+    },
+    /// The node is missing from the peers it is expected to be part of
+    MissingPeer {
+        peer_id: String,
+        peers: &'static str,
+    },
+    /// A consensus parameter of the node has an invalid value
+    InvalidConsensusParameter {
+        peer_id: String,
+        parameter: &'static str,
+        value: String,
+        expected: String,
+    },
+}
+
+impl fmt::Display for ConfigProblem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigProblem::DuplicatePort {
+                port,
+                first,
+                second,
+            } => write!(
+                f,
+                "Port {} is assigned to both {} and {}",
+                port, first, second
+            ),
+            ConfigProblem::InvalidFile {
+                peer_id,
+                file,
+                path,
+                error,
+            } => write!(
+                f,
+                "Node {}: invalid {} file {:?}: {}",
+                peer_id, file, path, error
+            ),
+            ConfigProblem::InconsistentSeedPeer {
+                peer_id,
+                seed_peer,
+                reason,
+            } => write!(f, "Node {}: seed peer {} {}", peer_id, seed_peer, reason),
+            ConfigProblem::MissingPeer { peer_id, peers } => {
+                write!(f, "Node {}: missing from its own {}", peer_id, peers)
+            }
+            ConfigProblem::InvalidConsensusParameter {
+                peer_id,
+                parameter,
+                value,
+                expected,
+            } => write!(
+                f,
+                "Node {}: invalid consensus {} {}, expected {}",
+                peer_id, parameter, value, expected
+            ),
+        }
+    }
+}
+
+/// Every problem found in the configs of a swarm, one per line.
+#[derive(Clone, Debug)]
+pub struct ConfigProblems(pub Vec<ConfigProblem>);
+
+impl fmt::Display for ConfigProblems {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for problem in &self.0 {
+            write!(f, "\n\t{}", problem)?;
+        }
+        Ok(())
+    }
+}
+
+/// Checks the generated configs of a swarm before spawning its nodes, returns every problem
+/// found instead of the first one: the ports shared by several services, the missing or invalid
+/// key and peers files, the seed peers inconsistent with the nodes of the swarm and the invalid
+/// consensus parameters.
+pub fn validate_configs(config: &SwarmConfig) -> Result<(), PreflightFailure> {
+    let mut problems = vec![];
+    let mut services = HashMap::new();
+    // The ports advertised by every node of the swarm, by peer id.
+    let mut advertised_ports: HashMap<&str, HashSet<u16>> = HashMap::new();
+    for (_, node_config) in &config.configs {
+        for network in &node_config.networks {
+            if let Some(port) = NodeConfigHelpers::get_tcp_port(&network.advertised_address) {
+                advertised_ports
+                    .entry(network.peer_id.as_str())
+                    .or_default()
+                    .insert(port);
+            }
+        }
+    }
+    for (path, node_config) in &config.configs {
+        let peer_id = &node_config.networks.get(0).unwrap().peer_id;
+        for (service, port) in node_ports(node_config) {
+            let service = format!("{} of node {}", service, peer_id);
+            if let Some(first) = services.insert(port, service.clone()) {
+                problems.push(ConfigProblem::DuplicatePort {
+                    port,
+                    first,
+                    second: service,
+                });
+            }
+        }
+        for network in &node_config.networks {
+            validate_network(path, network, &advertised_ports, &mut problems);
+        }
+        if node_config.is_validator() {
+            validate_consensus(path, node_config, &mut problems);
+        }
+    }
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(PreflightFailure::InvalidConfigs(ConfigProblems(problems)))
+    }
+}
+
+// The path of the given file of the node, None if the node doesn't load it.
+fn node_file(config_path: &Path, file: &Path) -> Option<PathBuf> {
+    if file.as_os_str().is_empty() {
+        None
+    } else {
+        Some(config_path.with_file_name(file))
+    }
+}
+
+// Parses the given file of the node, recording a problem if it is missing or invalid.
+fn load_file<T: PersistableConfig>(
+    peer_id: &str,
+    file: &'static str,
+    path: PathBuf,
+    problems: &mut Vec<ConfigProblem>,
+) -> Option<T> {
+    let result = fs::read_to_string(&path)
+        .map_err(|e| e.to_string())
+        .and_then(|contents| T::parse(&contents).map_err(|e| e.to_string()));
+    match result {
+        Ok(parsed) => Some(parsed),
+        Err(error) => {
+            problems.push(ConfigProblem::InvalidFile {
+                peer_id: peer_id.to_string(),
+                file,
+                path,
+                error,
+            });
+            None
+        }
+    }
+}
+
+fn validate_network(
+    path: &Path,
+    network: &NetworkConfig,
+    advertised_ports: &HashMap<&str, HashSet<u16>>,
+    problems: &mut Vec<ConfigProblem>,
+) {
+    let peer_id = &network.peer_id;
+    if let Some(keypairs_file) = node_file(path, &network.network_keypairs_file) {
+        if !keypairs_file.is_file() {
+            problems.push(ConfigProblem::InvalidFile {
+                peer_id: peer_id.clone(),
+                file: "network keypairs",
+                path: keypairs_file,
+                error: "not found".to_string(),
+            });
+        }
+    }
+    let network_peers = node_file(path, &network.network_peers_file)
+        .and_then(|file| load_file::<NetworkPeersConfig>(peer_id, "network peers", file, problems));
+    if let Some(network_peers) = &network_peers {
+        if network.is_permissioned && !network_peers.peers.contains_key(peer_id) {
+            problems.push(ConfigProblem::MissingPeer {
+                peer_id: peer_id.clone(),
+                peers: "network peers",
+            });
+        }
+    }
+    let seed_peers = node_file(path, &network.seed_peers_file)
+        .and_then(|file| load_file::<SeedPeersConfig>(peer_id, "seed peers", file, problems));
+    for (seed_peer, addresses) in seed_peers.iter().flat_map(|seeds| seeds.seed_peers.iter()) {
+        let inconsistent = |reason: &str| ConfigProblem::InconsistentSeedPeer {
+            peer_id: peer_id.clone(),
+            seed_peer: seed_peer.clone(),
+            reason: reason.to_string(),
+        };
+        let ports = match advertised_ports.get(seed_peer.as_str()) {
+            Some(ports) => ports,
+            None => {
+                problems.push(inconsistent("is not a node of the swarm"));
+                continue;
+            }
+        };
+        if network.is_permissioned {
+            if let Some(network_peers) = &network_peers {
+                if !network_peers.peers.contains_key(seed_peer) {
+                    problems.push(inconsistent("is missing from the network peers"));
+                }
+            }
+        }
+        if !addresses.iter().any(|address| {
+            NodeConfigHelpers::get_tcp_port(address).map_or(false, |port| ports.contains(&port))
+        }) {
+            problems.push(inconsistent(&format!(
+                "has no address with the port it advertises, {:?}",
+                addresses
+            )));
+        }
+    }
+}
+
+fn validate_consensus(path: &Path, node_config: &NodeConfig, problems: &mut Vec<ConfigProblem>) {
+    let peer_id = &node_config.networks.get(0).unwrap().peer_id;
+    let consensus = &node_config.consensus;
+    if let Some(keypair_file) = node_file(path, &consensus.consensus_keypair_file) {
+        if !keypair_file.is_file() {
+            problems.push(ConfigProblem::InvalidFile {
+                peer_id: peer_id.clone(),
+                file: "consensus keypair",
+                path: keypair_file,
+                error: "not found".to_string(),
+            });
+        }
+    }
+    if let Some(peers_file) = node_file(path, &consensus.consensus_peers_file) {
+        if let Some(consensus_peers) =
+            load_file::<ConsensusPeersConfig>(peer_id, "consensus peers", peers_file, problems)
+        {
+            if !consensus.observer && !consensus_peers.peers.contains_key(peer_id) {
+                problems.push(ConfigProblem::MissingPeer {
+                    peer_id: peer_id.clone(),
+                    peers: "consensus peers",
+                });
+            }
+        }
+    }
+    let mut invalid = |parameter: &'static str, value: String, expected: String| {
+        problems.push(ConfigProblem::InvalidConsensusParameter {
+            peer_id: peer_id.clone(),
+            parameter,
+            value,
+            expected,
+        })
+    };
+    let one_of = |values: &[&str]| format!("one of {}", values.join(", "));
+    for (parameter, value, values) in &[
+        ("proposer_type", &consensus.proposer_type, PROPOSER_TYPES),
+        ("commit_rule", &consensus.commit_rule, COMMIT_RULES),
+        (
+            "storage_backend",
+            &consensus.storage_backend,
+            STORAGE_BACKENDS,
+        ),
+        (
+            "safety_rules_backend",
+            &consensus.safety_rules_backend,
+            SAFETY_RULES_BACKENDS,
+        ),
+    ] {
+        if !values.contains(&value.as_str()) {
+            invalid(*parameter, format!("{:?}", value), one_of(*values));
+        }
+    }
+    if consensus.max_block_size == 0 {
+        invalid("max_block_size", "0".to_string(), "at least 1".to_string());
+    }
+    if consensus.contiguous_rounds == 0 {
+        invalid(
+            "contiguous_rounds",
+            "0".to_string(),
+            "at least 1".to_string(),
+        );
+    }
+    if let Some(base) = consensus.pacemaker_timeout_exponent_base {
+        if base.is_nan() || base < 1.0 {
+            invalid(
+                "pacemaker_timeout_exponent_base",
+                base.to_string(),
+                "at least 1".to_string(),
+            );
+        }
+    }
+    if let Some(percentile) = consensus.pacemaker_adaptive_timeout_percentile {
+        if percentile.is_nan() || percentile <= 0.0 || percentile > 1.0 {
+            invalid(
+                "pacemaker_adaptive_timeout_percentile",
+                percentile.to_string(),
+                "within (0, 1]".to_string(),
+            );
+        }
+    }
+}
+
+// The ports a node listens on.
+fn node_ports(config: &NodeConfig) -> Vec<(&'static str, u16)> {
+    let mut ports = ports::service_ports(config);
+    for network in &config.networks {
+        if let Some(port) = NodeConfigHelpers::get_tcp_port(&network.listen_address) {
+            ports.push(("network", port));
+        }
+    }
+    ports
+}
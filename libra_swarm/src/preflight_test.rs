@@ -0,0 +1,124 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    partition,
+    preflight::{self, ConfigProblem, ConfigProblems, PreflightFailure},
+    utils,
+};
+use config_builder::swarm_config::SwarmConfig;
+use std::fs;
+use tools::tempdir::TempPath;
+
+fn problems(config: &SwarmConfig) -> Vec<ConfigProblem> {
+    match preflight::validate_configs(config) {
+        Ok(()) => vec![],
+        Err(PreflightFailure::InvalidConfigs(ConfigProblems(problems))) => problems,
+        Err(e) => panic!("Unexpected failure {}", e),
+    }
+}
+
+fn peer_id(config: &SwarmConfig, index: usize) -> String {
+    config.configs[index]
+        .1
+        .networks
+        .get(0)
+        .unwrap()
+        .peer_id
+        .clone()
+}
+
+#[test]
+fn test_generated_configs_are_valid() {
+    let dir = TempPath::new();
+    let config = utils::test_swarm_config(dir.path(), 4);
+    assert!(problems(&config).is_empty());
+}
+
+#[test]
+fn test_every_problem_is_reported() {
+    let dir = TempPath::new();
+    let mut config = utils::test_swarm_config(dir.path(), 4);
+    let node0 = peer_id(&config, 0);
+    let node2 = peer_id(&config, 2);
+    let node3 = peer_id(&config, 3);
+
+    // Two nodes share a port.
+    let port = config.configs[0].1.storage.port;
+    config.configs[1].1.storage.port = port;
+    // The first node advertises a port its seed address doesn't have.
+    let network = config.configs[0].1.networks.get_mut(0).unwrap();
+    let advertised_address = network.advertised_address.to_string();
+    let advertised_port = partition::tcp_port(&advertised_address).unwrap();
+    network.advertised_address =
+        partition::with_tcp_port(&advertised_address, advertised_port.wrapping_add(1))
+            .parse()
+            .unwrap();
+    // The consensus keypair file of the third node is missing.
+    let (path, node_config) = &config.configs[2];
+    fs::remove_file(path.with_file_name(&node_config.consensus.consensus_keypair_file)).unwrap();
+    // The fourth node has two invalid consensus parameters.
+    let consensus = &mut config.configs[3].1.consensus;
+    consensus.max_block_size = 0;
+    consensus.proposer_type = "unknown_proposer".to_string();
+
+    let problems = problems(&config);
+    assert!(problems.iter().any(|problem| match problem {
+        ConfigProblem::DuplicatePort {
+            port: duplicate, ..
+        } => *duplicate == port,
+        _ => false,
+    }));
+    // The first node is an inconsistent seed peer of every node.
+    let num_inconsistent = problems
+        .iter()
+        .filter(|problem| match problem {
+            ConfigProblem::InconsistentSeedPeer { seed_peer, .. } => *seed_peer == node0,
+            _ => false,
+        })
+        .count();
+    assert_eq!(num_inconsistent, config.configs.len());
+    assert!(problems.iter().any(|problem| match problem {
+        ConfigProblem::InvalidFile { peer_id, file, .. } => {
+            *peer_id == node2 && *file == "consensus keypair"
+        }
+        _ => false,
+    }));
+    let mut invalid_parameters: Vec<&str> = problems
+        .iter()
+        .filter_map(|problem| match problem {
+            ConfigProblem::InvalidConsensusParameter {
+                peer_id, parameter, ..
+            } if *peer_id == node3 => Some(*parameter),
+            _ => None,
+        })
+        .collect();
+    invalid_parameters.sort();
+    assert_eq!(invalid_parameters, vec!["max_block_size", "proposer_type"]);
+    assert_eq!(problems.len(), 1 + config.configs.len() + 1 + 2);
+}
+
+#[test]
+fn test_missing_peers_files_are_reported() {
+    let dir = TempPath::new();
+    let config = utils::test_swarm_config(dir.path(), 4);
+    let (seed_peers_file, _) = &config.seed_peers;
+    fs::remove_file(seed_peers_file).unwrap();
+    let (consensus_peers_file, _) = &config.consensus_peers;
+    fs::write(consensus_peers_file, "not a consensus peers config").unwrap();
+
+    // Every node reports both of its files.
+    let problems = problems(&config);
+    for index in 0..config.configs.len() {
+        let node = peer_id(&config, index);
+        for expected_file in &["seed peers", "consensus peers"] {
+            assert!(problems.iter().any(|problem| match problem {
+                ConfigProblem::InvalidFile { peer_id, file, .. } => {
+                    *peer_id == node && file == expected_file
+                }
+                _ => false,
+            }));
+        }
+    }
+    assert_eq!(problems.len(), 2 * config.configs.len());
+}
@@ -0,0 +1,355 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Platform independent control of the node processes launched by the swarm.
+//!
+//! A node may spawn processes of its own, hence a node is always terminated together with all
+//! its descendants:
+//! * on Unix the node is the leader of a new process group, which is signaled as a whole;
+//! * on Windows the node is assigned to a job object, which is terminated as a whole and also kills
+//!   the processes in case the swarm itself dies.
+//!
+//! On the other platforms only the node process itself is killed.
+
+use std::{
+    io,
+    process::{Child, Command, ExitStatus},
+    thread,
+    time::{Duration, Instant},
+};
+
+const WAIT_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// A child process together with all its descendants.
+pub struct NodeProcess {
+    child: Child,
+    group: platform::ProcessGroup,
+}
+
+impl NodeProcess {
+    /// Spawns the command in a new process group (or job).
+    pub fn spawn(command: &mut Command) -> io::Result<Self> {
+        platform::configure(command);
+        let mut child = command.spawn()?;
+        match platform::ProcessGroup::attach(&child) {
+            Ok(group) => Ok(Self { child, group }),
+            Err(e) => {
+                // Don't leak a process we are not able to control.
+                let _ = child.kill();
+                let _ = child.wait();
+                Err(e)
+            }
+        }
+    }
+
+    pub fn id(&self) -> u32 {
+        self.child.id()
+    }
+
+    /// Returns the exit status in case the process has terminated, without blocking.
+    pub fn try_wait(&mut self) -> io::Result<Option<ExitStatus>> {
+        self.child.try_wait()
+    }
+
+    /// Waits for the process to terminate for at most the given timeout.
+    pub fn wait_timeout(&mut self, timeout: Duration) -> io::Result<Option<ExitStatus>> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            if let Some(status) = self.child.try_wait()? {
+                return Ok(Some(status));
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return Ok(None);
+            }
+            thread::sleep(WAIT_POLL_INTERVAL.min(deadline - now));
+        }
+    }
+
+    /// Asks the process tree to terminate. The request is graceful where the platform supports
+    /// it (SIGTERM on Unix), otherwise the processes are killed.
+    pub fn terminate(&mut self) -> io::Result<()> {
+        if self.child.try_wait()?.is_some() {
+            return Ok(());
+        }
+        self.group.terminate(&mut self.child)
+    }
+
+    /// Suspends the process tree (SIGSTOP on Unix), not supported on the other platforms.
+    pub fn pause(&mut self) -> io::Result<()> {
+        self.group.pause()
+    }
+
+    /// Resumes the process tree suspended by `pause`.
+    pub fn resume(&mut self) -> io::Result<()> {
+        self.group.resume()
+    }
+
+    /// Forcefully kills the process tree and reaps the process.
+    pub fn kill_tree(&mut self) -> io::Result<ExitStatus> {
+        // The descendants may still be alive after the process itself has terminated.
+        self.group.kill(&mut self.child)?;
+        self.child.wait()
+    }
+
+    /// Terminates the process tree, killing it in case it doesn't terminate within the grace
+    /// period.
+    pub fn shutdown(&mut self, grace_period: Duration) -> io::Result<ExitStatus> {
+        self.terminate()?;
+        self.wait_timeout(grace_period)?;
+        // Even if the process has terminated in time, make sure none of its descendants survives.
+        self.kill_tree()
+    }
+}
+
+/// Whether the process of the given id is running, e.g. a node launched by another swarm. Only
+/// checked on Unix, the process is assumed to be running on the other platforms.
+pub fn is_running(pid: u32) -> bool {
+    platform::is_running(pid)
+}
+
+/// Lets the process spawned by the command write a core dump when it crashes, by lifting its
+/// core file size limit. Only supported on Unix.
+pub fn enable_core_dumps(command: &mut Command) {
+    platform::enable_core_dumps(command)
+}
+
+#[cfg(not(unix))]
+fn unsupported(operation: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Other,
+        format!("{} is not supported on this platform", operation),
+    )
+}
+
+#[cfg(unix)]
+mod platform {
+    use std::{
+        io,
+        os::unix::process::CommandExt,
+        process::{Child, Command},
+    };
+
+    pub fn is_running(pid: u32) -> bool {
+        // The signal 0 only checks that the process exists.
+        unsafe { libc::kill(pid as libc::pid_t, 0) == 0 }
+        || io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+    }
+
+    pub fn configure(command: &mut Command) {
+        unsafe {
+            command.pre_exec(|| {
+                // The child becomes the leader of a new process group with the same id.
+                if libc::setpgid(0, 0) == 0 {
+                    Ok(())
+                } else {
+                    Err(io::Error::last_os_error())
+                }
+            });
+        }
+    }
+
+    pub fn enable_core_dumps(command: &mut Command) {
+        unsafe {
+            command.pre_exec(|| {
+                // The soft limit is raised up to the hard limit, which may forbid core dumps.
+                let mut limit = libc::rlimit {
+                    rlim_cur: 0,
+                    rlim_max: 0,
+                };
+                if libc::getrlimit(libc::RLIMIT_CORE, &mut limit) != 0 {
+                    return Err(io::Error::last_os_error());
+                }
+                limit.rlim_cur = limit.rlim_max;
+                // Failing to raise the limit only means no core dump.
+                libc::setrlimit(libc::RLIMIT_CORE, &limit);
+                Ok(())
+            });
+        }
+    }
+
+    pub struct ProcessGroup {
+        pgid: libc::pid_t,
+    }
+
+    impl ProcessGroup {
+        pub fn attach(child: &Child) -> io::Result<Self> {
+            Ok(Self {
+                pgid: child.id() as libc::pid_t,
+            })
+        }
+
+        pub fn terminate(&self, _child: &mut Child) -> io::Result<()> {
+            self.signal(libc::SIGTERM)
+        }
+
+        pub fn kill(&self, _child: &mut Child) -> io::Result<()> {
+            self.signal(libc::SIGKILL)
+        }
+
+        pub fn pause(&self) -> io::Result<()> {
+            self.signal(libc::SIGSTOP)
+        }
+
+        pub fn resume(&self) -> io::Result<()> {
+            self.signal(libc::SIGCONT)
+        }
+
+        fn signal(&self, signal: libc::c_int) -> io::Result<()> {
+            if unsafe { libc::kill(-self.pgid, signal) } == 0 {
+                return Ok(());
+            }
+            let error = io::Error::last_os_error();
+            // The whole group has already terminated.
+            if error.raw_os_error() == Some(libc::ESRCH) {
+                Ok(())
+            } else {
+                Err(error)
+            }
+        }
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use std::{
+        io, mem,
+        os::windows::io::AsRawHandle,
+        process::{Child, Command},
+        ptr,
+    };
+    use winapi::{
+        shared::minwindef::{DWORD, FALSE, LPVOID},
+        um::{
+            handleapi::CloseHandle,
+            jobapi2::{
+                AssignProcessToJobObject, CreateJobObjectW, SetInformationJobObject,
+                TerminateJobObject,
+            },
+            winnt::{
+                JobObjectExtendedLimitInformation, HANDLE, JOBOBJECT_EXTENDED_LIMIT_INFORMATION,
+                JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE,
+            },
+        },
+    };
+
+    pub fn is_running(_pid: u32) -> bool {
+        true
+    }
+
+    pub fn configure(_command: &mut Command) {}
+
+    pub fn enable_core_dumps(_command: &mut Command) {}
+
+    /// A job object owning the process and all its descendants. The processes are killed when
+    /// the last handle of the job is closed, i.e., also when the swarm process dies.
+    pub struct ProcessGroup {
+        job: HANDLE,
+    }
+
+    impl ProcessGroup {
+        pub fn attach(child: &Child) -> io::Result<Self> {
+            let job = unsafe { CreateJobObjectW(ptr::null_mut(), ptr::null()) };
+            if job.is_null() {
+                return Err(io::Error::last_os_error());
+            }
+            let group = Self { job };
+            let mut info: JOBOBJECT_EXTENDED_LIMIT_INFORMATION = unsafe { mem::zeroed() };
+            info.BasicLimitInformation.LimitFlags = JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE;
+            let set = unsafe {
+                SetInformationJobObject(
+                    group.job,
+                    JobObjectExtendedLimitInformation,
+                    &mut info as *mut _ as LPVOID,
+                    mem::size_of_val(&info) as DWORD,
+                )
+            };
+            if set == FALSE {
+                return Err(io::Error::last_os_error());
+            }
+            // The processes spawned by the child before this point are not part of the job.
+            if unsafe { AssignProcessToJobObject(group.job, child.as_raw_handle() as HANDLE) }
+                == FALSE
+            {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(group)
+        }
+
+        // Windows has no graceful termination of console processes that are not attached to
+        // the same console, the job is killed.
+        pub fn terminate(&self, child: &mut Child) -> io::Result<()> {
+            self.kill(child)
+        }
+
+        pub fn kill(&self, _child: &mut Child) -> io::Result<()> {
+            if unsafe { TerminateJobObject(self.job, 1) } == FALSE {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(())
+        }
+
+        pub fn pause(&self) -> io::Result<()> {
+            Err(super::unsupported("Pausing processes"))
+        }
+
+        pub fn resume(&self) -> io::Result<()> {
+            Err(super::unsupported("Resuming processes"))
+        }
+    }
+
+    // The job handle can be used from any thread.
+    unsafe impl Send for ProcessGroup {}
+
+    impl Drop for ProcessGroup {
+        fn drop(&mut self) {
+            unsafe {
+                CloseHandle(self.job);
+            }
+        }
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+mod platform {
+    use std::{
+        io,
+        process::{Child, Command},
+    };
+
+    pub fn is_running(_pid: u32) -> bool {
+        true
+    }
+
+    pub fn configure(_command: &mut Command) {}
+
+    pub fn enable_core_dumps(_command: &mut Command) {}
+
+    /// Without process groups only the process itself can be controlled.
+    pub struct ProcessGroup;
+
+    impl ProcessGroup {
+        pub fn attach(_child: &Child) -> io::Result<Self> {
+            Ok(ProcessGroup)
+        }
+
+        pub fn terminate(&self, child: &mut Child) -> io::Result<()> {
+            self.kill(child)
+        }
+
+        pub fn kill(&self, child: &mut Child) -> io::Result<()> {
+            match child.try_wait()? {
+                Some(_) => Ok(()),
+                None => child.kill(),
+            }
+        }
+
+        pub fn pause(&self) -> io::Result<()> {
+            Err(super::unsupported("Pausing processes"))
+        }
+
+        pub fn resume(&self) -> io::Result<()> {
+            Err(super::unsupported("Resuming processes"))
+        }
+    }
+}
@@ -0,0 +1,83 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Emulation of a geo-distributed swarm on the local machine.
+//!
+//! The nodes are assigned to named regions, and the links between them are shaped by the network
+//! proxies of the swarm: a low latency within a region and the latency of the pair of regions
+//! between them. The nodes without a region are not shaped.
+
+use crate::partition::LinkProfile;
+use std::{collections::HashMap, time::Duration};
+
+#[cfg(test)]
+#[path = "regions_test.rs"]
+mod regions_test;
+
+/// The regions of the nodes and the profiles of the links between them.
+#[derive(Clone, Debug)]
+pub struct RegionTopology {
+    /// The region of the nodes, by index of the node in the swarm config
+    pub regions: HashMap<usize, String>,
+    /// The profile of the links between the nodes of the same region
+    pub intra_region: LinkProfile,
+    /// The profiles of the links between two regions, by pair of regions in any order
+    pub inter_region: HashMap<(String, String), LinkProfile>,
+    /// The profile of the links between two regions missing from `inter_region`
+    pub default_inter_region: LinkProfile,
+}
+
+impl Default for RegionTopology {
+    fn default() -> Self {
+        Self {
+            regions: HashMap::new(),
+            intra_region: LinkProfile::with_latency(Duration::from_millis(1)),
+            inter_region: HashMap::new(),
+            default_inter_region: LinkProfile::with_latency(Duration::from_millis(50)),
+        }
+    }
+}
+
+impl RegionTopology {
+    /// Spreads the given number of first nodes of the swarm config (the validators come first)
+    /// over the given regions round-robin, with the default profiles.
+    pub fn round_robin(regions: &[&str], num_nodes: usize) -> Self {
+        let regions = if regions.is_empty() {
+            HashMap::new()
+        } else {
+            (0..num_nodes)
+                .map(|index| (index, regions[index % regions.len()].to_string()))
+                .collect()
+        };
+        Self {
+            regions,
+            ..Self::default()
+        }
+    }
+
+    /// Sets the profile of the links between the two regions, e.g. the latency between "eu" and
+    /// "us".
+    pub fn set_inter_region(&mut self, a: &str, b: &str, profile: LinkProfile) {
+        self.inter_region.insert(region_pair(a, b), profile);
+    }
+
+    /// The profile of the links between a node of the first region and a node of the second.
+    pub fn link_profile(&self, a: &str, b: &str) -> LinkProfile {
+        if a == b {
+            return self.intra_region;
+        }
+        self.inter_region
+            .get(&region_pair(a, b))
+            .cloned()
+            .unwrap_or(self.default_inter_region)
+    }
+}
+
+// A pair of regions is the same in both orders.
+fn region_pair(a: &str, b: &str) -> (String, String) {
+    if a <= b {
+        (a.to_string(), b.to_string())
+    } else {
+        (b.to_string(), a.to_string())
+    }
+}
@@ -0,0 +1,57 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{partition::LinkProfile, regions::RegionTopology};
+use std::time::Duration;
+
+#[test]
+fn test_round_robin() {
+    let topology = RegionTopology::round_robin(&["eu", "us", "asia"], 7);
+    let mut regions: Vec<(usize, &str)> = topology
+        .regions
+        .iter()
+        .map(|(index, region)| (*index, region.as_str()))
+        .collect();
+    regions.sort();
+    assert_eq!(
+        regions,
+        vec![
+            (0, "eu"),
+            (1, "us"),
+            (2, "asia"),
+            (3, "eu"),
+            (4, "us"),
+            (5, "asia"),
+            (6, "eu")
+        ]
+    );
+    assert!(RegionTopology::round_robin(&[], 4).regions.is_empty());
+}
+
+#[test]
+fn test_link_profile() {
+    let mut topology = RegionTopology::round_robin(&["eu", "us", "asia"], 3);
+    let transatlantic = LinkProfile {
+        latency: Duration::from_millis(80),
+        jitter: Duration::from_millis(5),
+        bandwidth: Some(1_000_000),
+    };
+    topology.set_inter_region("us", "eu", transatlantic);
+
+    assert_eq!(topology.link_profile("eu", "eu"), topology.intra_region);
+    assert_eq!(
+        topology.link_profile("eu", "eu").latency,
+        Duration::from_millis(1)
+    );
+    // A pair of regions has the same profile in both orders.
+    assert_eq!(topology.link_profile("eu", "us"), transatlantic);
+    assert_eq!(topology.link_profile("us", "eu"), transatlantic);
+    assert_eq!(
+        topology.link_profile("asia", "eu"),
+        topology.default_inter_region
+    );
+    assert_eq!(
+        topology.link_profile("eu", "asia").latency,
+        Duration::from_millis(50)
+    );
+}
@@ -0,0 +1,186 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Launches the nodes of a swarm on other machines over SSH.
+//!
+//! The swarm directory (configs, keys, genesis) is copied to every remote host at the same path
+//! as on the local machine, such that the paths in the configs remain valid. A remote node is
+//! run through an SSH session, which terminates when the node does; its output goes to its log
+//! on the remote host, which is tailed into the local log of the node.
+
+use failure::prelude::*;
+use std::{
+    net::Ipv4Addr,
+    path::Path,
+    process::{Command, Stdio},
+    time::Duration,
+};
+
+/// A machine the nodes of a swarm are launched on. The host has to accept non-interactive SSH
+/// logins (e.g. through an SSH agent) and to have the `libra_node` binary.
+#[derive(Clone, Debug)]
+pub struct RemoteHost {
+    /// The SSH destination, e.g. `user@host`
+    pub destination: String,
+    /// The address the other nodes and the swarm reach the host at
+    pub address: Ipv4Addr,
+    /// The `libra_node` binary on the host
+    pub binary: String,
+    /// Extra options of ssh and scp, e.g. `-i <identity file>`
+    pub ssh_options: Vec<String>,
+}
+
+impl RemoteHost {
+    pub fn new<S: Into<String>>(destination: S, address: Ipv4Addr) -> Self {
+        Self {
+            destination: destination.into(),
+            address,
+            binary: "libra_node".to_string(),
+            ssh_options: vec!["-o".to_string(), "BatchMode=yes".to_string()],
+        }
+    }
+
+    // An ssh command running the given shell command on the host.
+    fn ssh(&self, remote_command: &str) -> Command {
+        self.ssh_with_options(&[], remote_command)
+    }
+
+    fn ssh_with_options(&self, options: &[&str], remote_command: &str) -> Command {
+        let mut command = Command::new("ssh");
+        command
+            .args(&self.ssh_options)
+            .args(options)
+            .arg(&self.destination)
+            .arg(remote_command)
+            .stdin(Stdio::null());
+        command
+    }
+
+    /// Runs the given shell command on the host and returns its output, fails if the command
+    /// fails.
+    pub fn run(&self, remote_command: &str) -> Result<String> {
+        let output = self.ssh(remote_command).output()?;
+        ensure!(
+            output.status.success(),
+            "'{}' failed on {} with {}: {}",
+            remote_command,
+            self.destination,
+            output.status,
+            String::from_utf8_lossy(&output.stderr)
+        );
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+
+    /// Copies the given local directory to the same path on the host, replacing any previous
+    /// content.
+    pub fn copy_dir(&self, dir: &Path) -> Result<()> {
+        let parent = dir
+            .parent()
+            .ok_or_else(|| format_err!("Can't copy the root directory to {}", self.destination))?;
+        self.run(&format!(
+            "rm -rf {} && mkdir -p {}",
+            quote(dir),
+            quote(parent)
+        ))?;
+        let status = Command::new("scp")
+            .args(&self.ssh_options)
+            .arg("-rq")
+            .arg(dir)
+            .arg(format!("{}:{}", self.destination, parent.display()))
+            .stdin(Stdio::null())
+            .status()?;
+        ensure!(
+            status.success(),
+            "Failed to copy {:?} to {}: {}",
+            dir,
+            self.destination,
+            status
+        );
+        Ok(())
+    }
+
+    /// A command running the node with the given binary, arguments and environment variables on
+    /// the host, the output of the node is appended to the given log on the host. The command
+    /// terminates when the node does, and the node is hung up on when the command is terminated.
+    pub fn node_command(
+        &self,
+        binary: &str,
+        args: &[String],
+        env: &[(String, String)],
+        log: &Path,
+    ) -> Command {
+        let args: Vec<_> = args.iter().map(quote).collect();
+        let env: Vec<_> = env
+            .iter()
+            .map(|(name, value)| format!("{}={} ", name, quote(value)))
+            .collect();
+        let remote_command = format!(
+            "RUST_LOG=${{RUST_LOG:-debug}} {}exec {} {} >> {} 2>&1",
+            env.concat(),
+            quote(binary),
+            args.join(" "),
+            quote(log)
+        );
+        // The pseudo-terminal hangs up on the node when the session is closed.
+        self.ssh_with_options(&["-tt"], &remote_command)
+    }
+
+    /// A command following the given file of the host from the given byte offset on, and
+    /// writing it to its standard output.
+    pub fn tail_command(&self, file: &Path, offset: u64) -> Command {
+        self.ssh(&format!("tail -c +{} -F {}", offset + 1, quote(file)))
+    }
+
+    /// Asks the node running from the given config on the host to terminate, and kills it if
+    /// it is still running after the grace period.
+    pub fn stop_node(&self, config_path: &Path, grace_period: Duration) -> Result<()> {
+        // The bracket keeps the pattern from matching the shell running it.
+        let pattern = quote(format!("[-]f {}", config_path.display()));
+        self.run(&format!(
+            "pkill -TERM -f {0}; \
+             for i in $(seq {1}); do pgrep -f {0} > /dev/null || exit 0; sleep 1; done; \
+             pkill -KILL -f {0}; true",
+            pattern,
+            grace_period.as_secs().max(1)
+        ))?;
+        Ok(())
+    }
+
+    /// Reports the resource usage of the node running from the given config on the host, as
+    /// lines of a name and a value: `clk_tck`, `stat` (the content of `/proc/<pid>/stat`),
+    /// `rss_kb`, `fds` and `disk` (the size of the given data dir in bytes). Nothing is reported
+    /// if the node is not running.
+    pub fn node_resources(&self, config_path: &Path, data_dir: &Path) -> Result<String> {
+        // The bracket keeps the pattern from matching the shell running it.
+        let pattern = quote(format!("[-]f {}", config_path.display()));
+        self.run(&format!(
+            "pid=$(pgrep -f {} | head -n 1); [ -n \"$pid\" ] || exit 0; \
+             echo clk_tck $(getconf CLK_TCK); \
+             echo stat $(cat /proc/$pid/stat); \
+             echo rss_kb $(awk '/^VmRSS:/ {{print $2}}' /proc/$pid/status); \
+             echo fds $(ls /proc/$pid/fd | wc -l); \
+             echo disk $(du -sb {} | cut -f1)",
+            pattern,
+            quote(data_dir)
+        ))
+    }
+
+    /// Replaces the IP address of the given multiaddr (e.g. `/ip4/0.0.0.0/tcp/6180`) with the
+    /// address of the host.
+    pub fn host_multiaddr(&self, addr: &str) -> String {
+        let mut components: Vec<String> = addr.split('/').map(str::to_string).collect();
+        if components.len() > 2 && (components[1] == "ip4" || components[1] == "ip6") {
+            components[1] = "ip4".to_string();
+            components[2] = self.address.to_string();
+        }
+        components.join("/")
+    }
+}
+
+// Quotes the argument for the remote shell.
+fn quote<S: AsRef<std::ffi::OsStr>>(arg: S) -> String {
+    format!(
+        "'{}'",
+        arg.as_ref().to_string_lossy().replace('\'', "'\\''")
+    )
+}
@@ -0,0 +1,420 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Resource usage of the nodes of a swarm, and a background watcher of their budgets.
+//!
+//! The CPU time, resident memory and open file descriptors of a node are read from the `/proc`
+//! of its host (through SSH for a remote node), hence are only sampled on Linux hosts. They cover
+//! the node process itself, not the processes it spawned. The disk usage is the size of the
+//! storage dir of the node.
+
+use crate::{
+    liveness::{self, SharedNodeProcess},
+    remote::RemoteHost,
+};
+use failure::prelude::*;
+use logger::prelude::*;
+use std::{
+    collections::HashMap,
+    fmt, fs,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+/// A sample of the resource usage of a node.
+#[derive(Clone, Copy, Debug)]
+pub struct ResourceStats {
+    pub sampled_at: Instant,
+    /// The CPU time consumed by the node since it started, in user and kernel mode
+    pub cpu_time: Duration,
+    pub rss_bytes: u64,
+    pub open_fds: u64,
+    /// The size of the storage dir of the node
+    pub disk_bytes: u64,
+}
+
+impl ResourceStats {
+    /// The CPU usage between the given earlier sample and this one, in percent of a core.
+    pub fn cpu_percent_since(&self, earlier: &ResourceStats) -> f64 {
+        if self.sampled_at <= earlier.sampled_at {
+            return 0.0;
+        }
+        let elapsed = self
+            .sampled_at
+            .duration_since(earlier.sampled_at)
+            .as_secs_f64();
+        let cpu_time = self.cpu_time.as_secs_f64() - earlier.cpu_time.as_secs_f64();
+        (cpu_time / elapsed * 100.0).max(0.0)
+    }
+
+    // Parses the lines of `RemoteHost::node_resources`, the same fields are read locally.
+    fn parse(report: &str, sampled_at: Instant) -> Result<Self> {
+        let fields: HashMap<&str, &str> = report
+            .lines()
+            .filter_map(|line| {
+                let mut parts = line.splitn(2, ' ');
+                Some((parts.next()?, parts.next().unwrap_or("").trim()))
+            })
+            .collect();
+        let number = |name: &str| -> Result<u64> {
+            let value = fields
+                .get(name)
+                .ok_or_else(|| format_err!("No {} in the resource report", name))?;
+            value
+                .parse()
+                .map_err(|_| format_err!("Invalid {} in the resource report: {}", name, value))
+        };
+        let stat = fields
+            .get("stat")
+            .ok_or_else(|| format_err!("No stat in the resource report"))?;
+        Ok(Self {
+            sampled_at,
+            cpu_time: cpu_time(stat, number("clk_tck")?)?,
+            rss_bytes: number("rss_kb")? * 1024,
+            open_fds: number("fds")?,
+            disk_bytes: number("disk")?,
+        })
+    }
+}
+
+/// The limits of the resource usage of every node, None for no limit.
+#[derive(Clone, Debug, Default)]
+pub struct ResourceBudget {
+    /// The CPU usage between two samples, in percent of a core
+    pub cpu_percent: Option<f64>,
+    pub rss_bytes: Option<u64>,
+    pub open_fds: Option<u64>,
+    pub disk_bytes: Option<u64>,
+}
+
+impl ResourceBudget {
+    // The first resource of the sample over budget, with its usage and its budget.
+    fn check(
+        &self,
+        stats: &ResourceStats,
+        previous: Option<&ResourceStats>,
+    ) -> Option<(Resource, f64, f64)> {
+        let cpu_percent = previous.map(|previous| stats.cpu_percent_since(previous));
+        let usages = vec![
+            (Resource::Cpu, cpu_percent, self.cpu_percent),
+            (
+                Resource::Memory,
+                Some(stats.rss_bytes as f64),
+                self.rss_bytes.map(|budget| budget as f64),
+            ),
+            (
+                Resource::FileDescriptors,
+                Some(stats.open_fds as f64),
+                self.open_fds.map(|budget| budget as f64),
+            ),
+            (
+                Resource::Disk,
+                Some(stats.disk_bytes as f64),
+                self.disk_bytes.map(|budget| budget as f64),
+            ),
+        ];
+        usages
+            .into_iter()
+            .filter_map(|(resource, usage, budget)| match (usage, budget) {
+                (Some(usage), Some(budget)) if usage > budget => Some((resource, usage, budget)),
+                _ => None,
+            })
+            .next()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Resource {
+    Cpu,
+    Memory,
+    FileDescriptors,
+    Disk,
+}
+
+impl Resource {
+    fn format(self, usage: f64) -> String {
+        match self {
+            Resource::Cpu => format!("{:.1}%", usage),
+            Resource::Memory | Resource::Disk => format!("{} bytes", usage as u64),
+            Resource::FileDescriptors => format!("{}", usage as u64),
+        }
+    }
+}
+
+impl fmt::Display for Resource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Resource::Cpu => "CPU",
+            Resource::Memory => "resident memory",
+            Resource::FileDescriptors => "open file descriptors",
+            Resource::Disk => "disk usage",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// A node whose resource usage exceeded the budget.
+#[derive(Clone, Debug)]
+pub struct ResourceViolation {
+    pub peer_id: String,
+    pub resource: Resource,
+    pub usage: f64,
+    pub budget: f64,
+    /// The time the node had been watched for when it exceeded the budget
+    pub after: Duration,
+}
+
+impl fmt::Display for ResourceViolation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "The {} of node {} reached {} after {:?}, over its budget of {}",
+            self.resource,
+            self.peer_id,
+            self.resource.format(self.usage),
+            self.after,
+            self.resource.format(self.budget)
+        )
+    }
+}
+
+/// Samples the resource usage of a node, on its host.
+#[derive(Clone)]
+pub struct ResourceSampler {
+    process: SharedNodeProcess,
+    // The process of a node launched by another swarm, which this swarm is attached to.
+    attached_pid: Option<u32>,
+    host: Option<RemoteHost>,
+    config_path: PathBuf,
+    data_dir: PathBuf,
+}
+
+impl ResourceSampler {
+    pub(crate) fn new(
+        process: SharedNodeProcess,
+        attached_pid: Option<u32>,
+        host: Option<RemoteHost>,
+        config_path: PathBuf,
+        data_dir: PathBuf,
+    ) -> Self {
+        Self {
+            process,
+            attached_pid,
+            host,
+            config_path,
+            data_dir,
+        }
+    }
+
+    /// Samples the resource usage of the node, None if the node is not running.
+    pub fn sample(&self) -> Result<Option<ResourceStats>> {
+        let pid = match self.process.lock().unwrap().as_mut() {
+            Some(process) => {
+                if process.try_wait()?.is_some() {
+                    return Ok(None);
+                }
+                process.id()
+            }
+            None => match self.attached_pid {
+                Some(pid) => pid,
+                // The node is stopped.
+                None => return Ok(None),
+            },
+        };
+        let sampled_at = Instant::now();
+        match &self.host {
+            Some(host) => {
+                let report = host.node_resources(&self.config_path, &self.data_dir)?;
+                if report.trim().is_empty() {
+                    return Ok(None);
+                }
+                ResourceStats::parse(&report, sampled_at).map(Some)
+            }
+            None => {
+                let proc_dir = PathBuf::from(format!("/proc/{}", pid));
+                if !proc_dir.exists() {
+                    return Ok(None);
+                }
+                let rss_kb = fs::read_to_string(proc_dir.join("status"))?
+                    .lines()
+                    .find(|line| line.starts_with("VmRSS:"))
+                    .and_then(|line| line.split_whitespace().nth(1))
+                    .and_then(|rss_kb| rss_kb.parse::<u64>().ok())
+                    .unwrap_or(0);
+                Ok(Some(ResourceStats {
+                    sampled_at,
+                    cpu_time: cpu_time(
+                        &fs::read_to_string(proc_dir.join("stat"))?,
+                        clock_ticks_per_second(),
+                    )?,
+                    rss_bytes: rss_kb * 1024,
+                    open_fds: fs::read_dir(proc_dir.join("fd"))?.count() as u64,
+                    disk_bytes: dir_size(&self.data_dir),
+                }))
+            }
+        }
+    }
+}
+
+// The CPU time of a process from the content of its `/proc/<pid>/stat`, in clock ticks of the
+// given frequency.
+fn cpu_time(stat: &str, clk_tck: u64) -> Result<Duration> {
+    // The command name may contain spaces, the fields after it start with the state.
+    let fields: Vec<&str> = stat
+        .rsplitn(2, ')')
+        .next()
+        .unwrap_or("")
+        .split_whitespace()
+        .collect();
+    ensure!(fields.len() > 12, "Invalid process stat: {}", stat);
+    let ticks = |index: usize| -> Result<u64> {
+        fields[index]
+            .parse()
+            .map_err(|_| format_err!("Invalid process stat: {}", stat))
+    };
+    // The user time and the system time are the 14th and 15th fields.
+    let ticks = ticks(11)? + ticks(12)?;
+    Ok(Duration::from_millis(ticks * 1000 / clk_tck.max(1)))
+}
+
+#[cfg(unix)]
+fn clock_ticks_per_second() -> u64 {
+    unsafe { libc::sysconf(libc::_SC_CLK_TCK) }.max(1) as u64
+}
+
+// The process stats are only read on Linux, there is no clock tick to query otherwise.
+#[cfg(not(unix))]
+fn clock_ticks_per_second() -> u64 {
+    100
+}
+
+// The total size of the files in the given dir, 0 if it doesn't exist.
+fn dir_size(dir: &Path) -> u64 {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return 0,
+    };
+    entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| match entry.metadata() {
+            Ok(metadata) if metadata.is_dir() => dir_size(&entry.path()),
+            Ok(metadata) => metadata.len(),
+            Err(_) => 0,
+        })
+        .sum()
+}
+
+/// A node watched by the resource watcher.
+pub struct WatchedNode {
+    pub peer_id: String,
+    pub sampler: ResourceSampler,
+}
+
+/// Handle of the background resource watcher, the watcher is stopped when the handle is dropped.
+pub struct ResourceWatcher {
+    stop: Arc<AtomicBool>,
+    violation: Arc<Mutex<Option<ResourceViolation>>>,
+    // The latest sample of every node, by peer id.
+    latest: Arc<Mutex<HashMap<String, ResourceStats>>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl ResourceWatcher {
+    /// Starts sampling the given nodes every `interval`. The nodes which are not running are
+    /// skipped.
+    pub fn start(nodes: Vec<WatchedNode>, budget: ResourceBudget, interval: Duration) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let violation = Arc::new(Mutex::new(None));
+        let latest = Arc::new(Mutex::new(HashMap::new()));
+        let handle = {
+            let stop = Arc::clone(&stop);
+            let violation = Arc::clone(&violation);
+            let latest = Arc::clone(&latest);
+            thread::Builder::new()
+                .name("swarm-resources".to_string())
+                .spawn(move || run(nodes, &budget, interval, &stop, &violation, &latest))
+                .expect("Failed to spawn the resource watcher")
+        };
+        Self {
+            stop,
+            violation,
+            latest,
+            handle: Some(handle),
+        }
+    }
+
+    /// The first budget violation detected, if any.
+    pub fn violation(&self) -> Option<ResourceViolation> {
+        self.violation.lock().unwrap().clone()
+    }
+
+    /// The latest sample of every node, by peer id.
+    pub fn latest_stats(&self) -> HashMap<String, ResourceStats> {
+        self.latest.lock().unwrap().clone()
+    }
+
+    pub fn stop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            handle.join().expect("Resource watcher panicked");
+        }
+    }
+}
+
+impl Drop for ResourceWatcher {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+fn run(
+    nodes: Vec<WatchedNode>,
+    budget: &ResourceBudget,
+    interval: Duration,
+    stop: &AtomicBool,
+    violation: &Mutex<Option<ResourceViolation>>,
+    latest: &Mutex<HashMap<String, ResourceStats>>,
+) {
+    let start = Instant::now();
+    loop {
+        for node in &nodes {
+            let stats = match node.sampler.sample() {
+                Ok(Some(stats)) => stats,
+                Ok(None) => continue,
+                Err(e) => {
+                    debug!("Failed to sample the resources of {}: {}", node.peer_id, e);
+                    continue;
+                }
+            };
+            let previous = latest.lock().unwrap().insert(node.peer_id.clone(), stats);
+            if let Some((resource, usage, limit)) = budget.check(&stats, previous.as_ref()) {
+                let e = ResourceViolation {
+                    peer_id: node.peer_id.clone(),
+                    resource,
+                    usage,
+                    budget: limit,
+                    after: start.elapsed(),
+                };
+                error!("Swarm resource check failed: {}", e);
+                *violation.lock().unwrap() = Some(e);
+                // Tear the swarm down, such that its users fail fast.
+                for node in &nodes {
+                    if let Some(process) = node.sampler.process.lock().unwrap().as_mut() {
+                        if let Err(e) = process.kill_tree() {
+                            error!("Failed to kill node {}: {}", node.peer_id, e);
+                        }
+                    }
+                }
+                return;
+            }
+        }
+        if !liveness::sleep_unless_stopped(interval, stop) {
+            return;
+        }
+    }
+}
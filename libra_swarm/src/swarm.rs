@@ -1,48 +1,236 @@
 // Copyright (c) The Libra Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::utils;
-use config::config::{NodeConfig, RoleType};
-use config_builder::swarm_config::{SwarmConfig, SwarmConfigBuilder};
+use crate::{
+    catchup::{
+        CatchupFailure, CatchupReport, CatchupTarget, NodeCatchup, ProgressReport, ProgressTarget,
+    },
+    chaos::{ChaosEvent, ChaosNode, ChaosPolicy, ChaosScheduler},
+    client::NodeClient,
+    consensus_stats::ConsensusStats,
+    crash::CrashReport,
+    events::{CrashMonitor, EventBus, SwarmEvent},
+    fairness::{FairnessReport, ProposerStats},
+    faucet::Faucet,
+    liveness::{LivenessChecker, LivenessFailure, MonitoredNode, SharedNodeProcess},
+    log_tail::LogTail,
+    logs::SwarmLogs,
+    metrics::{self, NodeMetrics},
+    partition::{self, LinkProfile, NetworkProxies},
+    ports::{self, PortAllocator},
+    preflight::{self, PreflightFailure},
+    process::{self, NodeProcess},
+    regions::RegionTopology,
+    remote::RemoteHost,
+    resources::{
+        ResourceBudget, ResourceSampler, ResourceStats, ResourceViolation, ResourceWatcher,
+        WatchedNode,
+    },
+    teardown::{self, NodeState, NodeTeardown, TeardownReport, TEARDOWN_REPORT_FILE},
+    telemetry::{RoundTelemetry, RoundView},
+    utils,
+};
+use config::config::{NodeConfig, PersistableConfig, RoleType, SafetyRulesBackend};
+use config_builder::{
+    genesis_cache::GenesisCache,
+    swarm_config::{SwarmConfig, SwarmConfigBuilder},
+};
 use crypto::{ed25519::*, test_utils::KeyPair};
-use debug_interface::NodeDebugClient;
+use debug_interface::{
+    consensus_state::ConsensusStateView, health::SubsystemHealth, AsyncNodeDebugClient,
+};
 use failure::prelude::*;
-use logger::prelude::*;
+use futures::{future, stream, Future, Stream};
+use logger::{prelude::*, LOG_FORMAT_ENV_VAR};
+use proto_conv::FromProto;
+use regex::Regex;
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     env,
-    fs::File,
+    fs::{File, OpenOptions},
     io::Read,
     path::{Path, PathBuf},
-    process::{Child, Command},
+    process::{Command, ExitStatus, Stdio},
     str::FromStr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::Receiver,
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
 };
 use tools::tempdir::TempPath;
+use types::{
+    account_address::AccountAddress, crypto_proxies::LedgerInfoWithSignatures,
+    proto::ledger_info::LedgerInfoWithSignatures as ProtoLedgerInfoWithSignatures,
+};
 
 const LIBRA_NODE_BIN: &str = "libra_node";
+const SAFETY_RULES_SERVICE_BIN: &str = "safety_rules_service";
+/// Timeout of a single request to the debug interface of a node.
+const DEBUG_CLIENT_TIMEOUT: Duration = Duration::from_secs(5);
+/// Time given to a node to shut down gracefully before it is killed.
+const NODE_SHUTDOWN_GRACE_PERIOD: Duration = Duration::from_secs(5);
+/// Time given to the log tail of a stopped remote node to copy the end of the log.
+const LOG_TAIL_FLUSH_PERIOD: Duration = Duration::from_secs(1);
+/// Time given to the nodes to catch up, over links without any added delay.
+const CATCHUP_TIMEOUT: Duration = Duration::from_secs(60);
+/// A round takes a few message delays, the catch up timeout is extended by that many times the
+/// longest delay added to a link.
+const CATCHUP_TIMEOUT_DELAY_FACTOR: u32 = 600;
+/// Delay between two queries of the metrics of the nodes while waiting for a metric.
+const METRIC_POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// The last round committed by a node.
+const COMMITTED_ROUND_METRIC: &str = "consensus{op=committed_blocks_count}";
+/// The last version committed to the storage of a node.
+const COMMITTED_VERSION_METRIC: &str = "state_sync_gauge{op=committed_version}";
+/// Time given to the remaining validators to switch to the epoch without a removed validator.
+const RECONFIGURATION_TIMEOUT: Duration = Duration::from_secs(60);
+/// Rounds the remaining validators have to commit once a validator has been removed.
+const RECONFIGURATION_PROGRESS_ROUNDS: i64 = 3;
+/// The snapshots of the data of the nodes are kept in this subdirectory of the swarm directory.
+const SNAPSHOTS_DIR: &str = "snapshots";
+/// Within the snapshot of a node, the copy of its storage dir (libradb and consensusdb).
+const SNAPSHOT_DB_DIR: &str = "db";
+/// Within the snapshot of a validator, the copy of its safety rules state.
+const SNAPSHOT_SAFETY_RULES_FILE: &str = "safety_rules.json";
 
 pub struct LibraNode {
-    node: Child,
-    debug_client: NodeDebugClient,
+    node: SharedNodeProcess,
+    // The companion process of a validator whose safety rules run out of process.
+    safety_rules: Option<NodeProcess>,
+    debug_client: AsyncNodeDebugClient,
     ac_port: u16,
     peer_id: String,
+    // The libra_node binary the node runs, on its host.
+    binary: PathBuf,
+    // The host the node runs on, None for the local machine.
+    host: Option<RemoteHost>,
+    // Copies the log of a remote node into its local log.
+    log_tail: Option<NodeProcess>,
+    // The validators a full node syncs from, empty for a validator.
+    upstream_peers: Vec<String>,
+    config_path: PathBuf,
+    // The consensus peers of the swarm, which the clients verify the answers of the node with.
+    trusted_peers_file: PathBuf,
+    // The storage dir of the node, on its host.
+    data_dir: PathBuf,
     log: PathBuf,
+    // The log of the safety rules process, if the safety rules run out of process.
+    safety_rules_log: Option<PathBuf>,
+    disable_logging: bool,
+    // The environment variables the node runs with, on top of the ones of the swarm.
+    env: Vec<(String, String)>,
+    // The command line arguments the node runs with, on top of the config and peer id.
+    args: Vec<String>,
+    // Whether the node was launched by another swarm, which this swarm is attached to, and the
+    // process of the node if the other swarm recorded it.
+    attached: bool,
+    attached_pid: Option<u32>,
+    // Whether the processes of the node are suspended by `pause`, shared with the chaos
+    // scheduler which leaves the paused nodes alone.
+    paused: Arc<AtomicBool>,
 }
 
-impl Drop for LibraNode {
-    // When the LibraNode struct goes out of scope we need to kill the child process
-    fn drop(&mut self) {
-        // check if the process has already been terminated
-        match self.node.try_wait() {
-            // The child process has already terminated, perhaps due to a crash
-            Ok(Some(_)) => {}
-
-            // The node is still running so we need to attempt to kill it
-            _ => {
-                if let Err(e) = self.node.kill() {
-                    panic!("LibraNode process could not be killed: '{}'", e);
+/// Spawns the process of a node, such that the chaos scheduler can restart the nodes it kills.
+#[derive(Clone)]
+pub(crate) struct NodeLauncher {
+    peer_id: String,
+    binary: PathBuf,
+    host: Option<RemoteHost>,
+    config_path: PathBuf,
+    log: PathBuf,
+    disable_logging: bool,
+    env: Vec<(String, String)>,
+    args: Vec<String>,
+}
+
+impl NodeLauncher {
+    // A local launcher of the given binary, without config nor extra arguments.
+    #[cfg(test)]
+    pub(crate) fn for_test(peer_id: &str, binary: &Path, log: &Path) -> Self {
+        Self {
+            peer_id: peer_id.to_string(),
+            binary: binary.to_path_buf(),
+            host: None,
+            config_path: PathBuf::new(),
+            log: log.to_path_buf(),
+            disable_logging: false,
+            env: vec![],
+            args: vec![],
+        }
+    }
+
+    pub(crate) fn is_remote(&self) -> bool {
+        self.host.is_some()
+    }
+
+    // Spawns the node process, its output is appended to the log of the node (on its host for a
+    // remote node).
+    pub(crate) fn spawn(&self) -> Result<NodeProcess> {
+        let log_file = OpenOptions::new().append(true).open(&self.log)?;
+        let mut args = vec![
+            "-f".to_string(),
+            self.config_path.to_string_lossy().into_owned(),
+            "-p".to_string(),
+            self.peer_id.clone(),
+        ];
+        if self.disable_logging {
+            args.push("-d".to_string());
+        }
+        args.extend(self.args.iter().cloned());
+        let mut node_command = match &self.host {
+            Some(host) => {
+                host.node_command(&self.binary.to_string_lossy(), &args, &self.env, &self.log)
+            }
+            None => {
+                let mut node_command = Command::new(&self.binary);
+                node_command
+                    .current_dir(utils::workspace_root())
+                    .args(&args)
+                    .envs(self.env.iter().cloned());
+                if env::var("RUST_LOG").is_err()
+                    && self.env.iter().all(|(name, _)| name != "RUST_LOG")
+                {
+                    // Only set our RUST_LOG if its not present in environment
+                    node_command.env("RUST_LOG", "debug");
                 }
+                // A crash leaves a backtrace in the log and a core dump for its report.
+                if env::var("RUST_BACKTRACE").is_err()
+                    && self.env.iter().all(|(name, _)| name != "RUST_BACKTRACE")
+                {
+                    node_command.env("RUST_BACKTRACE", "1");
+                }
+                process::enable_core_dumps(&mut node_command);
+                node_command
             }
+        };
+
+        node_command.stdout(log_file.try_clone()?).stderr(log_file);
+
+        let process =
+            NodeProcess::spawn(&mut node_command).context("Error launching node process")?;
+        // The pid of a local node is recorded such that other swarms can attach to the node.
+        if self.host.is_none() {
+            std::fs::write(pid_path(&self.log), process.id().to_string())?;
+        }
+        Ok(process)
+    }
+}
+
+// The file the pid of a local node is recorded in, next to its log.
+fn pid_path(log: &Path) -> PathBuf {
+    log.with_extension("pid")
+}
+
+impl Drop for LibraNode {
+    // When the LibraNode struct goes out of scope we need to kill the child process, together
+    // with any process it has spawned. This is a no-op for the processes that have already
+    // terminated, perhaps due to a crash.
+    fn drop(&mut self) {
+        if let Err(e) = self.stop(NODE_SHUTDOWN_GRACE_PERIOD) {
+            panic!("{}", e);
         }
     }
 }
@@ -53,55 +241,354 @@ impl LibraNode {
         config_path: &Path,
         logdir: &Path,
         disable_logging: bool,
+        binary: &Path,
+        host: Option<RemoteHost>,
+        env: Vec<(String, String)>,
+        args: Vec<String>,
+    ) -> Result<Self> {
+        let mut node = Self::new(
+            config,
+            config_path,
+            logdir,
+            disable_logging,
+            binary,
+            host,
+            env,
+            args,
+        )?;
+        node.create_logs()?;
+        node.spawn()?;
+        Ok(node)
+    }
+
+    // Creates the empty logs of a node launched by this swarm.
+    fn create_logs(&self) -> Result<()> {
+        File::create(&self.log)?;
+        if let Some(safety_rules_log) = &self.safety_rules_log {
+            File::create(safety_rules_log)?;
+        }
+        Ok(())
+    }
+
+    /// Attaches to the local node running from the given config, launched by another swarm
+    /// whose logs are in the given directory. The node is queried and its log is read, but it is
+    /// never stopped nor restarted.
+    pub fn attach(config: &NodeConfig, config_path: &Path, logdir: &Path) -> Result<Self> {
+        let mut node = Self::new(
+            config,
+            config_path,
+            logdir,
+            false,
+            &utils::get_bin_path(LIBRA_NODE_BIN),
+            None,
+            vec![],
+            vec![],
+        )?;
+        ensure!(
+            node.log.is_file(),
+            "No log of node '{}' at {:?}",
+            node.peer_id,
+            node.log
+        );
+        node.attached = true;
+        node.attached_pid = std::fs::read_to_string(pid_path(&node.log))
+            .ok()
+            .and_then(|pid| pid.trim().parse().ok());
+        Ok(node)
+    }
+
+    fn new(
+        config: &NodeConfig,
+        config_path: &Path,
+        logdir: &Path,
+        disable_logging: bool,
+        binary: &Path,
+        host: Option<RemoteHost>,
+        env: Vec<(String, String)>,
+        args: Vec<String>,
     ) -> Result<Self> {
         // For now, We consider the peer id on the first network config as the node's peer id.
         // TODO: Create a peer id independent node identifier.
         let peer_id = config.networks.get(0).unwrap().peer_id.clone();
-        let log = logdir.join(format!("{}.log", SwarmConfig::get_alias(&config)));
-        let log_file = File::create(&log)?;
-        let mut node_command = Command::new(utils::get_bin(LIBRA_NODE_BIN));
-        node_command
+        let alias = SwarmConfig::get_alias(&config);
+        let log = logdir.join(format!("{}.log", alias));
+        let safety_rules_log = match config.consensus.get_safety_rules_backend() {
+            SafetyRulesBackend::Process(_) if config.is_validator() => {
+                Some(logdir.join(format!("{}.safety_rules.log", alias)))
+            }
+            _ => None,
+        };
+        ensure!(
+            host.is_none() || safety_rules_log.is_none(),
+            "The safety rules of node '{}' can't run out of process on a remote host",
+            peer_id
+        );
+        let debug_client = AsyncNodeDebugClient::new(
+            host.as_ref()
+                .map_or("localhost".to_string(), |host| host.address.to_string()),
+            config.debug_interface.admission_control_node_debug_port,
+            DEBUG_CLIENT_TIMEOUT,
+        );
+        Ok(Self {
+            node: Arc::new(Mutex::new(None)),
+            safety_rules: None,
+            debug_client,
+            ac_port: config.admission_control.admission_control_service_port,
+            peer_id,
+            binary: binary.to_path_buf(),
+            host,
+            log_tail: None,
+            upstream_peers: config.state_sync.upstream_peers.upstream_peers.clone(),
+            config_path: config_path.to_path_buf(),
+            trusted_peers_file: config_path.with_file_name(&config.consensus.consensus_peers_file),
+            data_dir: config.storage.dir.clone(),
+            log,
+            safety_rules_log,
+            disable_logging,
+            env,
+            args,
+            attached: false,
+            attached_pid: None,
+            paused: Arc::new(AtomicBool::new(false)),
+        })
+    }
+
+    // Spawns the processes of the node, their output is appended to the logs of the node.
+    fn spawn(&mut self) -> Result<()> {
+        // The safety rules service is started first, the node waits for it to accept connections.
+        if let Some(safety_rules_log) = &self.safety_rules_log {
+            self.safety_rules = Some(
+                Self::launch_safety_rules_service(&self.config_path, safety_rules_log)
+                    .context("Error launching safety rules process")?,
+            );
+        }
+        if let Some(host) = &self.host {
+            // The remote log is copied from where the local log ends, i.e. what was output since
+            // the last start of the node.
+            let log_file = OpenOptions::new().append(true).open(&self.log)?;
+            let offset = log_file.metadata()?.len();
+            let mut tail_command = host.tail_command(&self.log, offset);
+            tail_command.stdout(log_file).stderr(Stdio::null());
+            self.log_tail = Some(
+                NodeProcess::spawn(&mut tail_command).context("Error tailing the remote log")?,
+            );
+        }
+        let node = self.launcher().spawn()?;
+        *self.node.lock().unwrap() = Some(node);
+        self.paused.store(false, Ordering::SeqCst);
+        Ok(())
+    }
+
+    pub(crate) fn launcher(&self) -> NodeLauncher {
+        NodeLauncher {
+            peer_id: self.peer_id.clone(),
+            binary: self.binary.clone(),
+            host: self.host.clone(),
+            config_path: self.config_path.clone(),
+            log: self.log.clone(),
+            disable_logging: self.disable_logging,
+            env: self.env.clone(),
+            args: self.args.clone(),
+        }
+    }
+
+    fn launch_safety_rules_service(config_path: &Path, log: &Path) -> Result<NodeProcess> {
+        let log_file = OpenOptions::new().append(true).open(log)?;
+        let mut command = Command::new(utils::get_bin(SAFETY_RULES_SERVICE_BIN));
+        command
             .current_dir(utils::workspace_root())
             .arg("-f")
             .arg(config_path)
-            .args(&["-p", &peer_id]);
-        if env::var("RUST_LOG").is_err() {
-            // Only set our RUST_LOG if its not present in environment
-            node_command.env("RUST_LOG", "debug");
+            .stdout(log_file.try_clone()?)
+            .stderr(log_file);
+        Ok(NodeProcess::spawn(&mut command)?)
+    }
+
+    /// Stops the node gracefully: its processes are asked to terminate and are killed if they're
+    /// still running after the grace period. The node keeps its config and its data, it can be
+    /// restarted.
+    pub fn stop(&mut self, grace_period: Duration) -> Result<()> {
+        // A suspended process doesn't handle the termination signal before it is resumed.
+        if self.is_paused() {
+            self.resume()?;
+        }
+        // Taking the process out tells the liveness checker that the node is stopped on purpose.
+        let node = self.node.lock().unwrap().take();
+        if let Some(mut node) = node {
+            // The SSH session of a remote node terminates together with the node.
+            if let Some(host) = &self.host {
+                host.stop_node(&self.config_path, grace_period)?;
+            }
+            node.shutdown(grace_period)
+                .map_err(|e| format_err!("LibraNode process could not be killed: '{}'", e))?;
         }
-        if disable_logging {
-            node_command.arg("-d");
+        if let Some(mut log_tail) = self.log_tail.take() {
+            log_tail
+                .shutdown(LOG_TAIL_FLUSH_PERIOD)
+                .map_err(|e| format_err!("Log tail process could not be killed: '{}'", e))?;
         }
+        if let Some(mut safety_rules) = self.safety_rules.take() {
+            safety_rules
+                .shutdown(grace_period)
+                .map_err(|e| format_err!("Safety rules process could not be killed: '{}'", e))?;
+        }
+        Ok(())
+    }
 
-        node_command
-            .stdout(log_file.try_clone()?)
-            .stderr(log_file.try_clone()?);
+    /// Whether the node has been stopped, in which case it can be restarted. A node launched by
+    /// another swarm is stopped once its process has terminated.
+    pub fn is_stopped(&self) -> bool {
+        if self.attached {
+            return self
+                .attached_pid
+                .map_or(false, |pid| !process::is_running(pid));
+        }
+        self.node.lock().unwrap().is_none()
+    }
+
+    /// Suspends the processes of the node (SIGSTOP) without terminating them, as a long GC pause
+    /// or a frozen VM would: the node neither progresses nor answers until it is resumed. Only
+    /// the running local nodes launched by this swarm can be paused.
+    pub fn pause(&mut self) -> Result<()> {
+        ensure!(
+            !self.is_attached() && self.host.is_none(),
+            "Node '{}' can't be paused from this swarm",
+            self.peer_id
+        );
+        ensure!(
+            !self.is_paused(),
+            "Node '{}' is already paused",
+            self.peer_id
+        );
+        let mut node = self.node.lock().unwrap();
+        match node.as_mut() {
+            Some(node) => node
+                .pause()
+                .map_err(|e| format_err!("LibraNode process could not be paused: '{}'", e))?,
+            None => bail!("Node '{}' is stopped", self.peer_id),
+        }
+        if let Some(safety_rules) = &mut self.safety_rules {
+            if let Err(e) = safety_rules.pause() {
+                // Don't leave the node half paused.
+                if let Some(node) = node.as_mut() {
+                    node.resume().map_err(|e| {
+                        format_err!("LibraNode process could not be resumed: '{}'", e)
+                    })?;
+                }
+                bail!("Safety rules process could not be paused: '{}'", e);
+            }
+        }
+        self.paused.store(true, Ordering::SeqCst);
+        Ok(())
+    }
+
+    /// Resumes the processes of the node suspended by `pause` (SIGCONT).
+    pub fn resume(&mut self) -> Result<()> {
+        ensure!(self.is_paused(), "Node '{}' is not paused", self.peer_id);
+        if let Some(safety_rules) = &mut self.safety_rules {
+            safety_rules
+                .resume()
+                .map_err(|e| format_err!("Safety rules process could not be resumed: '{}'", e))?;
+        }
+        if let Some(node) = self.node.lock().unwrap().as_mut() {
+            node.resume()
+                .map_err(|e| format_err!("LibraNode process could not be resumed: '{}'", e))?;
+        }
+        self.paused.store(false, Ordering::SeqCst);
+        Ok(())
+    }
 
-        let node = node_command
-            .spawn()
-            .context("Error launching node process")?;
+    /// Whether the processes of the node are suspended by `pause`.
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::SeqCst)
+    }
 
-        let debug_client = NodeDebugClient::new(
-            "localhost",
-            config.debug_interface.admission_control_node_debug_port,
+    /// The process id of the node, None if it is stopped or if it was launched by another swarm
+    /// which didn't record it.
+    pub fn pid(&self) -> Option<u32> {
+        match self.node.lock().unwrap().as_ref() {
+            Some(node) => Some(node.id()),
+            None => self.attached_pid,
+        }
+    }
+
+    /// Whether the node was launched by another swarm, which this swarm is attached to.
+    pub fn is_attached(&self) -> bool {
+        self.attached
+    }
+
+    /// Relaunches a stopped node from the same config and data directory, the output of the
+    /// node is appended to its log.
+    pub fn restart(&mut self) -> Result<()> {
+        ensure!(
+            !self.is_attached(),
+            "Node '{}' is managed by the swarm that launched it",
+            self.peer_id
         );
-        Ok(Self {
-            node,
-            debug_client,
-            ac_port: config.admission_control.admission_control_service_port,
-            peer_id,
-            log,
-        })
+        ensure!(
+            self.is_stopped(),
+            "Node '{}' is still running",
+            self.peer_id
+        );
+        self.spawn()
+    }
+
+    /// The libra_node binary the node runs.
+    pub fn binary(&self) -> &Path {
+        &self.binary
+    }
+
+    /// Runs the node from the given binary from its next restart on.
+    pub fn set_binary<P: Into<PathBuf>>(&mut self, binary: P) {
+        self.binary = binary.into();
     }
 
     pub fn peer_id(&self) -> String {
         self.peer_id.clone()
     }
 
+    /// The address the node is reachable at: `localhost` or the address of its remote host.
+    pub fn host_address(&self) -> String {
+        self.host
+            .as_ref()
+            .map_or("localhost".to_string(), |host| host.address.to_string())
+    }
+
+    pub fn upstream_peers(&self) -> &[String] {
+        &self.upstream_peers
+    }
+
+    /// The config the node runs from, with the overrides of the node applied.
+    pub fn config_path(&self) -> &Path {
+        &self.config_path
+    }
+
+    /// The environment variables the node runs with, on top of the ones of the swarm.
+    pub fn env(&self) -> &[(String, String)] {
+        &self.env
+    }
+
+    /// The command line arguments the node runs with, on top of the config and peer id.
+    pub fn args(&self) -> &[String] {
+        &self.args
+    }
+
     pub fn ac_port(&self) -> u16 {
         self.ac_port
     }
 
+    /// A client of the admission control of the node, which reconnects after the node is
+    /// restarted.
+    pub fn ac_client(&self) -> Result<NodeClient> {
+        NodeClient::new(
+            self.peer_id.clone(),
+            self.host_address(),
+            self.ac_port,
+            self.trusted_peers_file.clone(),
+            self.node.clone(),
+        )
+    }
+
     pub fn get_log_contents(&self) -> Result<String> {
         let mut log = File::open(&self.log)?;
         let mut contents = String::new();
@@ -110,75 +597,246 @@ impl LibraNode {
         Ok(contents)
     }
 
-    fn get_metric(&self, metric_name: &str) -> Option<i64> {
-        match self.debug_client.get_node_metric(metric_name) {
-            Err(e) => {
-                debug!(
-                    "error getting {} for node: {}; error: {}",
-                    metric_name, self.peer_id, e
-                );
-                None
-            }
-            Ok(maybeval) => {
-                if maybeval.is_none() {
-                    debug!("Node: {} did not report {}", self.peer_id, metric_name);
+    /// The log the output of the node is appended to.
+    pub fn log_path(&self) -> &Path {
+        &self.log
+    }
+
+    /// Follows the log of the node from its current end, such that only the lines written from
+    /// now on are returned.
+    pub fn tail_log(&self) -> Result<LogTail> {
+        LogTail::new(&self.log)
+    }
+
+    /// Waits for at most the given timeout for a line of the log matching the pattern, and
+    /// returns it. The whole log is searched, such that an event logged before the call isn't
+    /// missed: use `tail_log` to only consider the lines written from now on.
+    pub fn wait_for_log_pattern(&self, pattern: &Regex, timeout: Duration) -> Result<String> {
+        LogTail::from_start(&self.log).wait_for_pattern(pattern, timeout)
+    }
+
+    /// The latest commit certificate of the node, i.e., the ledger info of the latest committed
+    /// block together with the signatures of a quorum of validators, which is a fresh finality
+    /// proof. None if consensus doesn't run on the node.
+    pub fn latest_commit_certificate(&self) -> Result<Option<LedgerInfoWithSignatures>> {
+        let bytes = match self.debug_client.get_latest_commit_certificate().wait()? {
+            Some(bytes) => bytes,
+            None => return Ok(None),
+        };
+        let proto = protobuf::parse_from_bytes::<ProtoLedgerInfoWithSignatures>(&bytes)?;
+        Ok(Some(LedgerInfoWithSignatures::from_proto(proto)?))
+    }
+
+    /// The internal state of consensus on the node (rounds, block tree, last vote). None if
+    /// consensus doesn't run on the node.
+    pub fn consensus_state(&self) -> Result<Option<ConsensusStateView>> {
+        self.debug_client.get_consensus_state().wait()
+    }
+
+    /// The future resolves to None in case the metric could not be retrieved, it never fails.
+    fn get_metric(&self, metric_name: &str) -> impl Future<Item = Option<i64>, Error = ()> {
+        let peer_id = self.peer_id.clone();
+        let metric_name = metric_name.to_string();
+        self.debug_client
+            .get_node_metric(metric_name.clone())
+            .then(move |result| match result {
+                Err(e) => {
+                    debug!(
+                        "error getting {} for node: {}; error: {}",
+                        metric_name, peer_id, e
+                    );
+                    Ok(None)
                 }
-                maybeval
-            }
-        }
+                Ok(maybeval) => {
+                    if maybeval.is_none() {
+                        debug!("Node: {} did not report {}", peer_id, metric_name);
+                    }
+                    Ok(maybeval)
+                }
+            })
+    }
+
+    /// All the metrics of the node.
+    pub fn get_metrics(&self) -> Result<NodeMetrics> {
+        self.debug_client.get_node_metrics().wait()
+    }
+
+    fn get_metrics_async(&self) -> impl Future<Item = Option<NodeMetrics>, Error = ()> {
+        let peer_id = self.peer_id.clone();
+        self.debug_client
+            .get_node_metrics()
+            .then(move |result| match result {
+                Err(e) => {
+                    debug!(
+                        "error getting the metrics of node: {}; error: {}",
+                        peer_id, e
+                    );
+                    Ok(None)
+                }
+                Ok(metrics) => Ok(Some(metrics)),
+            })
+    }
+
+    /// Proposer statistics reported by the node about itself: the counters that are not
+    /// reported yet are assumed to be 0.
+    pub fn proposer_stats(&self) -> ProposerStats {
+        self.proposer_stats_async().wait().unwrap_or_default()
+    }
+
+    // All the counters are read from a single metrics request.
+    fn proposer_stats_async(&self) -> impl Future<Item = ProposerStats, Error = ()> {
+        let peer_id = self.peer_id.clone();
+        self.debug_client.get_node_metrics().then(move |result| {
+            let metrics = result.unwrap_or_else(|e| {
+                debug!("error getting metrics for node: {}; error: {}", peer_id, e);
+                HashMap::new()
+            });
+            let get = |name: &str| metrics.get(name).cloned().unwrap_or(0);
+            Ok(ProposerStats {
+                rounds_led: get("consensus{op=rounds_led_count}"),
+                proposals_made: get("consensus{op=proposals_count}"),
+                proposals_committed: get("consensus{op=committed_proposals_count}"),
+            })
+        })
+    }
+
+    /// Round and latency statistics reported by the consensus of the node.
+    pub fn consensus_stats(&self) -> ConsensusStats {
+        self.consensus_stats_async().wait().unwrap_or_default()
+    }
+
+    fn consensus_stats_async(&self) -> impl Future<Item = ConsensusStats, Error = ()> {
+        let peer_id = self.peer_id.clone();
+        self.debug_client.get_node_metrics().then(move |result| {
+            let metrics = result.unwrap_or_else(|e| {
+                debug!("error getting metrics for node: {}; error: {}", peer_id, e);
+                HashMap::new()
+            });
+            Ok(ConsensusStats::from_metrics(&metrics))
+        })
     }
 
+    /// True if the node is connected to at least the given number of peers.
     pub fn check_connectivity(&self, expected_peers: i64) -> bool {
-        if let Some(num_connected_peers) = self.get_metric("network_gauge{op=connected_peers}") {
-            if num_connected_peers != expected_peers {
-                debug!(
-                    "Node '{}' Expected peers: {}, found peers: {}",
-                    self.peer_id, expected_peers, num_connected_peers
-                );
-                return false;
-            } else {
-                return true;
-            }
-        }
-        false
+        self.check_connectivity_async(expected_peers)
+            .wait()
+            .unwrap_or(false)
     }
 
-    pub fn health_check(&mut self) -> HealthStatus {
-        debug!("Health check on node '{}'", self.peer_id);
+    fn check_connectivity_async(
+        &self,
+        expected_peers: i64,
+    ) -> impl Future<Item = bool, Error = ()> {
+        let peer_id = self.peer_id.clone();
+        self.get_metric("network_gauge{op=connected_peers}")
+            .map(move |num_connected_peers| match num_connected_peers {
+                Some(num_connected_peers) if num_connected_peers < expected_peers => {
+                    debug!(
+                        "Node '{}' Expected at least {} peers, found peers: {}",
+                        peer_id, expected_peers, num_connected_peers
+                    );
+                    false
+                }
+                Some(_) => true,
+                None => false,
+            })
+    }
 
-        // check if the process has terminated
-        match self.node.try_wait() {
+    // Returns the exit status in case the process has terminated.
+    fn crash_status(&mut self) -> Option<ExitStatus> {
+        let status = match self.node.lock().unwrap().as_mut() {
+            Some(node) => node.try_wait(),
+            None => return None,
+        };
+        match status {
             // This would mean the child process has crashed
             Ok(Some(status)) => {
                 debug!("Node '{}' crashed with: {}", self.peer_id, status);
-                return HealthStatus::Crashed(status);
+                Some(status)
             }
 
             // This is the case where the node is still running
-            Ok(None) => {}
+            Ok(None) => None,
 
             // Some other unknown error
             Err(e) => {
                 panic!("error attempting to query Node: {}", e);
             }
         }
+    }
 
-        match self.debug_client.get_node_metrics() {
-            Ok(_) => {
-                debug!("Node '{}' is healthy", self.peer_id);
-                HealthStatus::Healthy
-            }
-            Err(e) => {
-                debug!("Error querying metrics for node '{}'", self.peer_id);
-                HealthStatus::RpcFailure(e)
-            }
+    /// Collects the diagnostics of the node, which crashed with the given status. The core dump
+    /// of a remote node isn't looked for.
+    pub fn crash_report(&self, status: ExitStatus) -> CrashReport {
+        let working_dir = utils::workspace_root();
+        let core_search = match (&self.host, self.pid()) {
+            (None, Some(pid)) => Some((working_dir.as_path(), pid)),
+            _ => None,
+        };
+        CrashReport::collect(&self.peer_id, status, &self.log, &self.binary, core_search)
+    }
+
+    /// Samples the CPU time, resident memory, open file descriptors and disk usage of the node.
+    pub fn resource_stats(&self) -> Result<ResourceStats> {
+        self.resource_sampler()
+            .sample()?
+            .ok_or_else(|| format_err!("Node '{}' is not running", self.peer_id))
+    }
+
+    pub(crate) fn resource_sampler(&self) -> ResourceSampler {
+        ResourceSampler::new(
+            Arc::clone(&self.node),
+            self.attached_pid,
+            self.host.clone(),
+            self.config_path.clone(),
+            self.data_dir.clone(),
+        )
+    }
+
+    pub fn health_check(&mut self) -> HealthStatus {
+        debug!("Health check on node '{}'", self.peer_id);
+
+        if let Some(status) = self.crash_status() {
+            return HealthStatus::Crashed(status);
         }
+        self.rpc_health_check()
+            .wait()
+            .unwrap_or_else(HealthStatus::RpcFailure)
+    }
+
+    // Checks that the node answers the debug interface requests and that its subsystems are
+    // healthy, the process is not checked. The nodes which don't report the health of their
+    // subsystems (e.g. older releases) are healthy if they serve their metrics.
+    fn rpc_health_check(&self) -> impl Future<Item = HealthStatus, Error = Error> {
+        let peer_id = self.peer_id.clone();
+        let metrics = self.debug_client.get_node_metrics();
+        self.debug_client
+            .get_health()
+            .or_else(|_| metrics.map(|_| None))
+            .then(move |result| match result {
+                Ok(report) => {
+                    let unhealthy = report.map_or_else(Vec::new, |report| report.unhealthy());
+                    if unhealthy.is_empty() {
+                        debug!("Node '{}' is healthy", peer_id);
+                        Ok(HealthStatus::Healthy)
+                    } else {
+                        debug!("Node '{}' is degraded: {:?}", peer_id, unhealthy);
+                        Ok(HealthStatus::Degraded(unhealthy))
+                    }
+                }
+                Err(e) => {
+                    debug!("Error querying metrics for node '{}'", peer_id);
+                    Ok(HealthStatus::RpcFailure(e))
+                }
+            })
     }
 }
 
 pub enum HealthStatus {
     Healthy,
-    Crashed(::std::process::ExitStatus),
+    /// The node answers, but some of its subsystems are not healthy, e.g. consensus is stuck
+    Degraded(Vec<SubsystemHealth>),
+    Crashed(ExitStatus),
     RpcFailure(failure::Error),
 }
 
@@ -198,14 +856,101 @@ impl AsRef<Path> for LibraSwarmDir {
     }
 }
 
+/// How the nodes of a swarm are brought up.
+#[derive(Clone, Debug)]
+pub struct StartupPolicy {
+    /// Max number of nodes spawned, or queried for their health or connectivity, at once
+    pub concurrency: usize,
+    /// Delay between two rounds of health or connectivity checks of the pending nodes
+    pub poll_interval: Duration,
+    /// Time given to all the nodes to answer the debug interface requests
+    pub startup_timeout: Duration,
+    /// Time given to all the nodes to connect to their peers
+    pub connectivity_timeout: Duration,
+}
+
+impl Default for StartupPolicy {
+    fn default() -> Self {
+        Self {
+            concurrency: 16,
+            poll_interval: Duration::from_millis(200),
+            startup_timeout: Duration::from_secs(120),
+            connectivity_timeout: Duration::from_secs(60),
+        }
+    }
+}
+
+/// What happens to the artifacts of the swarm (logs, configs, DBs) when the swarm is dropped.
+/// A persistent swarm directory is never removed, the policy only decides whether the artifacts
+/// are exported.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ArtifactRetention {
+    /// The artifacts are always kept.
+    Always,
+    /// The artifacts are kept if the swarm has failed: a node has crashed or the thread is
+    /// panicking.
+    OnFailure,
+    /// The artifacts are never kept.
+    Never,
+}
+
+impl Default for ArtifactRetention {
+    fn default() -> Self {
+        ArtifactRetention::OnFailure
+    }
+}
+
+impl FromStr for ArtifactRetention {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "always" => Ok(ArtifactRetention::Always),
+            "on_failure" => Ok(ArtifactRetention::OnFailure),
+            "never" => Ok(ArtifactRetention::Never),
+            _ => bail!(
+                "Invalid artifact retention '{}', expected always, on_failure or never",
+                s
+            ),
+        }
+    }
+}
+
 /// Struct holding instances and information of Libra Swarm
 pub struct LibraSwarm {
     // Output log, LibraNodes' config file, libradb etc, into this dir.
     pub dir: Option<LibraSwarmDir>,
     // Maps the peer id of a node to the LibraNode struct
     pub validator_nodes: HashMap<String, LibraNode>,
-    pub full_nodes: Vec<LibraNode>,
+    pub full_nodes: HashMap<String, LibraNode>,
     pub config: SwarmConfig,
+    // The libra_node binaries the nodes run, by peer id, such that a killed node is added back
+    // with the same binary.
+    binaries: HashMap<String, PathBuf>,
+    // The hosts of the remote nodes, by peer id.
+    hosts: HashMap<String, RemoteHost>,
+    // The environment variables and extra command line arguments of the nodes, by peer id.
+    node_env: HashMap<String, Vec<(String, String)>>,
+    node_args: HashMap<String, Vec<String>>,
+    logs: SwarmLogs,
+    // The proxies the nodes are reached through, if the links between them can be blocked.
+    proxies: Option<NetworkProxies>,
+    faucet: Option<Faucet>,
+    artifact_retention: ArtifactRetention,
+    // The retained artifacts are copied into this dir, if any.
+    artifact_export_dir: Option<PathBuf>,
+    // The retained artifacts are bundled into this tarball, if any.
+    artifact_bundle: Option<PathBuf>,
+    startup_policy: StartupPolicy,
+    liveness_checker: Option<LivenessChecker>,
+    resource_watcher: Option<ResourceWatcher>,
+    telemetry: Option<RoundTelemetry>,
+    chaos: Option<ChaosScheduler>,
+    events: EventBus,
+    // Emits the crashes of the nodes while the swarm has subscribers.
+    crash_monitor: Option<CrashMonitor>,
+    // The regions of the nodes, by peer id.
+    regions: HashMap<String, String>,
 }
 
 #[derive(Debug, Fail)]
@@ -214,168 +959,876 @@ pub enum SwarmLaunchFailure {
     #[fail(display = "Node launch check timeout")]
     LaunchTimeout,
     /// Node return status indicates a crash
-    #[fail(display = "Node crash")]
-    NodeCrash,
+    #[fail(display = "Node crash: {}", _0)]
+    NodeCrash(CrashReport),
     /// Timeout while waiting for the nodes to report that they're all interconnected
     #[fail(display = "Node connectivity check timeout")]
     ConnectivityTimeout,
+    /// The environment is not suitable for launching the nodes
+    #[fail(display = "Preflight check failed: {}", _0)]
+    Preflight(#[cause] PreflightFailure),
+    /// A node config or binary override targets a node that is not part of the swarm
+    #[fail(display = "No node {} to override", _0)]
+    InvalidNodeOverride(usize),
+    /// The swarm directory could not be set up on a remote host
+    #[fail(display = "Remote host setup failed: {}", _0)]
+    RemoteSetup(Error),
+    /// The network proxies of the nodes could not be set up
+    #[fail(display = "Network proxy setup failed: {}", _0)]
+    ProxySetup(Error),
+    /// The ports of the nodes could not be reserved
+    #[fail(display = "Port allocation failed: {}", _0)]
+    PortAllocation(Error),
+    /// The faucet could not connect to a validator
+    #[fail(display = "Faucet setup failed: {}", _0)]
+    FaucetSetup(Error),
+    /// Every attempt to launch the swarm failed
+    #[fail(display = "Max out {} attempts to launch swarm", _0)]
+    MaxAttempts(usize),
 }
 
-impl LibraSwarm {
-    pub fn launch_swarm(
-        num_nodes: usize,
-        disable_logging: bool,
-        faucet_account_keypair: KeyPair<Ed25519PrivateKey, Ed25519PublicKey>,
-        config_dir: Option<String>,
-        template_path: Option<String>,
-    ) -> Self {
-        let num_launch_attempts = 5;
-        for i in 0..num_launch_attempts {
-            let swarm_config_dir = Self::setup_config_dir(&config_dir);
-            info!("Launch swarm attempt: {} of {}", i, num_launch_attempts);
-            match Self::launch_swarm_attempt(
-                num_nodes,
-                disable_logging,
-                faucet_account_keypair.clone(),
-                swarm_config_dir,
-                &template_path,
-            ) {
-                Ok(swarm) => {
-                    return swarm;
-                }
-                Err(e) => error!("Error launching swarm: {}", e),
-            }
+/// A patch applied to the generated config of a node before the node is launched.
+pub type NodeConfigOverride = Arc<dyn Fn(&mut NodeConfig) + Send + Sync>;
+
+/// Builds the configs of a swarm and launches its nodes. A launch attempt that fails (e.g.
+/// because a port is already taken) is retried from scratch with new configs.
+pub struct LibraSwarmBuilder {
+    num_nodes: usize,
+    num_full_nodes_per_validator: usize,
+    role: RoleType,
+    disable_logging: bool,
+    faucet_account_keypair: KeyPair<Ed25519PrivateKey, Ed25519PublicKey>,
+    config_dir: Option<String>,
+    template_path: Option<String>,
+    genesis_ceremony: bool,
+    // The genesis is generated anew on every launch if None.
+    genesis_cache: Option<GenesisCache>,
+    num_launch_attempts: usize,
+    // The overrides of the configs of the nodes, by index of the node in the swarm config.
+    node_overrides: Vec<(usize, NodeConfigOverride)>,
+    // The libra_node binary of all the nodes, the one of the workspace if None.
+    binary: Option<PathBuf>,
+    // The libra_node binaries of specific nodes, by index of the node in the swarm config.
+    node_binaries: HashMap<usize, PathBuf>,
+    // The extra environment variables and command line arguments of specific nodes, by index of
+    // the node in the swarm config.
+    node_env: HashMap<usize, Vec<(String, String)>>,
+    node_args: HashMap<usize, Vec<String>>,
+    // The nodes are launched on these hosts round-robin, on the local machine if empty.
+    remote_hosts: Vec<RemoteHost>,
+    network_proxies: bool,
+    regions: Option<RegionTopology>,
+    structured_logs: bool,
+    faucet: bool,
+    artifact_retention: ArtifactRetention,
+    artifact_export_dir: Option<PathBuf>,
+    artifact_bundle: Option<PathBuf>,
+    startup_policy: StartupPolicy,
+    events: EventBus,
+}
+
+impl LibraSwarmBuilder {
+    /// A swarm of a single validator with logging enabled, in a temporary directory.
+    pub fn new(faucet_account_keypair: KeyPair<Ed25519PrivateKey, Ed25519PublicKey>) -> Self {
+        Self {
+            num_nodes: 1,
+            num_full_nodes_per_validator: 0,
+            role: RoleType::Validator,
+            disable_logging: false,
+            faucet_account_keypair,
+            config_dir: None,
+            template_path: None,
+            genesis_ceremony: false,
+            genesis_cache: Some(GenesisCache::new(GenesisCache::default_dir())),
+            num_launch_attempts: 5,
+            node_overrides: vec![],
+            binary: None,
+            node_binaries: HashMap::new(),
+            node_env: HashMap::new(),
+            node_args: HashMap::new(),
+            remote_hosts: vec![],
+            network_proxies: false,
+            regions: None,
+            structured_logs: false,
+            faucet: false,
+            artifact_retention: ArtifactRetention::default(),
+            artifact_export_dir: None,
+            artifact_bundle: None,
+            startup_policy: StartupPolicy::default(),
+            events: EventBus::default(),
         }
-        panic!("Max out {} attempts to launch swarm", num_launch_attempts);
     }
 
-    /// Either create a persistent directory for swarm or return a temporary one.
-    /// If specified persistent directory already exists,
-    /// assumably due to previous launch failure, it will be removed.
-    /// The directory for the last failed attempt won't be removed.
-    fn setup_config_dir(config_dir: &Option<String>) -> LibraSwarmDir {
-        let dir = match config_dir {
-            Some(dir_str) => {
-                let path_buf = PathBuf::from_str(&dir_str).expect("unable to create config dir");
-                if path_buf.exists() {
-                    std::fs::remove_dir_all(dir_str).expect("unable to delete previous config dir");
-                }
-                std::fs::create_dir_all(dir_str).expect("unable to create config dir");
-                LibraSwarmDir::Persistent(path_buf)
-            }
-            None => {
-                let temp_dir = TempPath::new();
-                temp_dir
-                    .create_as_dir()
-                    .expect("unable to create temporary config dir");
-                LibraSwarmDir::Temporary(temp_dir)
-            }
-        };
-        println!("Base directory containing logs and configs: {:?}", &dir);
-        dir
+    pub fn with_num_nodes(&mut self, num_nodes: usize) -> &mut Self {
+        self.num_nodes = num_nodes;
+        self
     }
 
-    fn launch_swarm_attempt(
-        num_nodes: usize,
-        disable_logging: bool,
-        faucet_account_keypair: KeyPair<Ed25519PrivateKey, Ed25519PublicKey>,
-        dir: LibraSwarmDir,
-        template_path: &Option<String>,
-    ) -> std::result::Result<Self, SwarmLaunchFailure> {
-        let logs_dir_path = dir.as_ref().join("logs");
-        std::fs::create_dir(&logs_dir_path).unwrap();
-        let base = utils::workspace_root().join(
-            template_path
-                .as_ref()
-                .unwrap_or(&"config/data/configs/node.config.toml".to_string()),
-        );
+    /// Launches the given number of full nodes syncing from each validator, in addition to the
+    /// validators.
+    pub fn with_num_full_nodes_per_validator(
+        &mut self,
+        num_full_nodes_per_validator: usize,
+    ) -> &mut Self {
+        self.num_full_nodes_per_validator = num_full_nodes_per_validator;
+        self
+    }
+
+    pub fn with_role(&mut self, role: RoleType) -> &mut Self {
+        self.role = role;
+        self
+    }
+
+    pub fn with_logging(&mut self, enable_logging: bool) -> &mut Self {
+        self.disable_logging = !enable_logging;
+        self
+    }
+
+    /// Outputs the configs, logs and DBs of the nodes into the given directory, which is removed
+    /// first if it exists, instead of a temporary directory.
+    pub fn with_config_dir<S: Into<String>>(&mut self, config_dir: S) -> &mut Self {
+        self.config_dir = Some(config_dir.into());
+        self
+    }
+
+    /// The template of the node configs, relative to the workspace root.
+    pub fn with_template<S: Into<String>>(&mut self, template_path: S) -> &mut Self {
+        self.template_path = Some(template_path.into());
+        self
+    }
+
+    /// Generates the validator keys and the genesis through a multi-party genesis ceremony:
+    /// every validator contributes its public keys only and verifies the assembled genesis
+    /// before it's launched.
+    pub fn with_genesis_ceremony(&mut self) -> &mut Self {
+        self.genesis_ceremony = true;
+        self
+    }
+
+    /// Reuses the genesis cached in the given cache by the previous launches with the same
+    /// number of validators, template and faucet key. The cache of the temporary directory is
+    /// used by default.
+    pub fn with_genesis_cache(&mut self, cache: GenesisCache) -> &mut Self {
+        self.genesis_cache = Some(cache);
+        self
+    }
+
+    /// Generates the keys and the genesis anew instead of reusing the cached ones.
+    pub fn fresh(&mut self) -> &mut Self {
+        self.genesis_cache = None;
+        self
+    }
+
+    pub fn with_num_launch_attempts(&mut self, num_launch_attempts: usize) -> &mut Self {
+        self.num_launch_attempts = num_launch_attempts;
+        self
+    }
+
+    /// Patches the config of the node of the given index (in the order of the swarm config, the
+    /// full nodes come after the validators) before it is launched. The overrides of a node are
+    /// applied in the order they're added.
+    pub fn with_node_override<F>(&mut self, index: usize, config_override: F) -> &mut Self
+    where
+        F: Fn(&mut NodeConfig) + Send + Sync + 'static,
+    {
+        self.node_overrides.push((index, Arc::new(config_override)));
+        self
+    }
+
+    /// Runs the node of the given index with the given environment variable, e.g. a different
+    /// `RUST_LOG` on a single node. The variables of a node are set in the order they're added.
+    pub fn with_node_env<K: Into<String>, V: Into<String>>(
+        &mut self,
+        index: usize,
+        name: K,
+        value: V,
+    ) -> &mut Self {
+        self.node_env
+            .entry(index)
+            .or_default()
+            .push((name.into(), value.into()));
+        self
+    }
+
+    /// Runs the node of the given index with the given command line arguments, after the ones
+    /// set by the swarm.
+    pub fn with_node_args<I, S>(&mut self, index: usize, args: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.node_args
+            .entry(index)
+            .or_default()
+            .extend(args.into_iter().map(Into::into));
+        self
+    }
+
+    /// Runs all the nodes from the given libra_node binary instead of the one built in the
+    /// workspace.
+    pub fn with_binary<P: Into<PathBuf>>(&mut self, binary: P) -> &mut Self {
+        self.binary = Some(binary.into());
+        self
+    }
+
+    /// Runs the node of the given index (in the order of the swarm config) from the given
+    /// libra_node binary, e.g. to mix the nodes of different releases in the swarm.
+    pub fn with_node_binary<P: Into<PathBuf>>(&mut self, index: usize, binary: P) -> &mut Self {
+        self.node_binaries.insert(index, binary.into());
+        self
+    }
+
+    // The binary the node of the given index runs.
+    fn node_binary(&self, index: usize) -> PathBuf {
+        if let Some(binary) = self.node_binaries.get(&index) {
+            return binary.clone();
+        }
+        match self.node_host(index) {
+            Some(host) => PathBuf::from(&host.binary),
+            None => self
+                .binary
+                .clone()
+                .unwrap_or_else(|| utils::get_bin(LIBRA_NODE_BIN)),
+        }
+    }
+
+    /// Launches the nodes on the given hosts over SSH instead of the local machine, the nodes
+    /// are assigned to the hosts round-robin in the order of the swarm config. The binaries of
+    /// `with_node_binary` are then paths on the hosts, and the DBs of the nodes are not part of
+    /// the exported artifacts.
+    pub fn with_remote_hosts(&mut self, hosts: Vec<RemoteHost>) -> &mut Self {
+        self.remote_hosts = hosts;
+        self
+    }
+
+    // The host the node of the given index runs on, None for the local machine.
+    fn node_host(&self, index: usize) -> Option<&RemoteHost> {
+        if self.remote_hosts.is_empty() {
+            None
+        } else {
+            Some(&self.remote_hosts[index % self.remote_hosts.len()])
+        }
+    }
+
+    /// Connects the nodes through TCP proxies of the swarm, such that the links between them can
+    /// be blocked with `LibraSwarm::block_link` and `LibraSwarm::partition`. Only supported for
+    /// the nodes of the local machine, on Linux.
+    pub fn with_network_proxies(&mut self) -> &mut Self {
+        self.network_proxies = true;
+        self
+    }
+
+    /// Assigns the nodes to regions and shapes the links between them as per the topology, e.g.
+    /// a low latency within "eu" and a high latency between "eu" and "us". The nodes are
+    /// connected through network proxies, see `with_network_proxies`.
+    pub fn with_regions(&mut self, topology: RegionTopology) -> &mut Self {
+        self.regions = Some(topology);
+        self.network_proxies = true;
+        self
+    }
+
+    /// Makes the nodes write their logs as one JSON object per line, such that the logs can be
+    /// queried with `LibraSwarm::logs`.
+    pub fn with_structured_logs(&mut self) -> &mut Self {
+        self.structured_logs = true;
+        self
+    }
+
+    /// Launches a faucet along with the nodes, such that the accounts are funded with
+    /// `LibraSwarm::faucet`.
+    pub fn with_faucet(&mut self) -> &mut Self {
+        self.faucet = true;
+        self
+    }
+
+    // The environment variables the node of the given index runs with.
+    fn node_env(&self, index: usize) -> Vec<(String, String)> {
+        let mut env = vec![];
+        if self.structured_logs {
+            env.push((LOG_FORMAT_ENV_VAR.to_string(), "json".to_string()));
+        }
+        env.extend(self.node_env.get(&index).cloned().unwrap_or_default());
+        env
+    }
+
+    // The extra command line arguments of the node of the given index.
+    fn node_args(&self, index: usize) -> Vec<String> {
+        self.node_args.get(&index).cloned().unwrap_or_default()
+    }
+
+    pub fn with_artifact_retention(&mut self, retention: ArtifactRetention) -> &mut Self {
+        self.artifact_retention = retention;
+        self
+    }
+
+    pub fn with_artifact_export_dir<P: Into<PathBuf>>(&mut self, export_dir: P) -> &mut Self {
+        self.artifact_export_dir = Some(export_dir.into());
+        self
+    }
+
+    /// Bundles the retained artifacts into a gzipped tarball at the given path.
+    pub fn with_artifact_bundle<P: Into<PathBuf>>(&mut self, tarball: P) -> &mut Self {
+        self.artifact_bundle = Some(tarball.into());
+        self
+    }
+
+    /// Brings the nodes up with the given concurrency, poll interval and timeouts, which also
+    /// apply to the nodes launched or restarted later.
+    pub fn with_startup_policy(&mut self, policy: StartupPolicy) -> &mut Self {
+        self.startup_policy = policy;
+        self
+    }
+
+    /// Subscribes to the lifecycle events of the swarm from its launch on, see
+    /// `LibraSwarm::events`. The events of the failed launch attempts are received as well.
+    pub fn subscribe_events(&self) -> Receiver<SwarmEvent> {
+        self.events.subscribe()
+    }
+
+    /// Launches the swarm, returns an error if the environment is not suitable for launching
+    /// the nodes, if their configs are invalid or if every launch attempt failed.
+    pub fn launch(&self) -> std::result::Result<LibraSwarm, SwarmLaunchFailure> {
+        // A broken environment or an invalid override won't be fixed by retrying.
+        LibraSwarm::preflight_check(&self.template_path).map_err(SwarmLaunchFailure::Preflight)?;
+        let num_nodes = self.num_nodes * (1 + self.num_full_nodes_per_validator);
+        if let Some(index) = self
+            .node_overrides
+            .iter()
+            .map(|(index, _)| index)
+            .chain(self.node_binaries.keys())
+            .chain(self.node_env.keys())
+            .chain(self.node_args.keys())
+            .chain(
+                self.regions
+                    .iter()
+                    .flat_map(|topology| topology.regions.keys()),
+            )
+            .find(|index| **index >= num_nodes)
+        {
+            return Err(SwarmLaunchFailure::InvalidNodeOverride(*index));
+        }
+        if self.network_proxies && !self.remote_hosts.is_empty() {
+            return Err(SwarmLaunchFailure::ProxySetup(format_err!(
+                "The links between remote nodes can't be proxied"
+            )));
+        }
+        // The binaries of the remote nodes are on their hosts.
+        if self.remote_hosts.is_empty() {
+            for binary in self.binary.iter().chain(self.node_binaries.values()) {
+                preflight::check_binary(binary).map_err(SwarmLaunchFailure::Preflight)?;
+            }
+        }
+        for i in 0..self.num_launch_attempts {
+            let swarm_config_dir = LibraSwarm::setup_config_dir(&self.config_dir);
+            info!(
+                "Launch swarm attempt: {} of {}",
+                i, self.num_launch_attempts
+            );
+            match LibraSwarm::launch_swarm_attempt(self, swarm_config_dir) {
+                Ok(swarm) => {
+                    return Ok(swarm);
+                }
+                // The configs are generated the same way by every attempt.
+                Err(SwarmLaunchFailure::Preflight(e)) => {
+                    return Err(SwarmLaunchFailure::Preflight(e));
+                }
+                Err(e) => error!("Error launching swarm: {}", e),
+            }
+        }
+        Err(SwarmLaunchFailure::MaxAttempts(self.num_launch_attempts))
+    }
+}
+
+impl LibraSwarm {
+    /// Starts building a swarm whose accounts are funded by the given faucet key, see
+    /// `LibraSwarmBuilder`.
+    pub fn builder(
+        faucet_account_keypair: KeyPair<Ed25519PrivateKey, Ed25519PublicKey>,
+    ) -> LibraSwarmBuilder {
+        LibraSwarmBuilder::new(faucet_account_keypair)
+    }
+
+    /// Shorthand for launching a swarm with `LibraSwarmBuilder`, panics if the swarm can't be
+    /// launched.
+    pub fn launch_swarm(
+        num_nodes: usize,
+        disable_logging: bool,
+        faucet_account_keypair: KeyPair<Ed25519PrivateKey, Ed25519PublicKey>,
+        config_dir: Option<String>,
+        template_path: Option<String>,
+    ) -> Self {
+        Self::builder_from_args(
+            num_nodes,
+            disable_logging,
+            faucet_account_keypair,
+            config_dir,
+            template_path,
+        )
+        .launch()
+        .unwrap_or_else(|e| panic!("Unable to launch swarm: {}", e))
+    }
+
+    /// Same as `launch_swarm`, but the validator keys and the genesis are produced by a
+    /// multi-party genesis ceremony: every validator contributes its public keys only and
+    /// verifies the assembled genesis before it's launched.
+    pub fn launch_swarm_with_genesis_ceremony(
+        num_nodes: usize,
+        disable_logging: bool,
+        faucet_account_keypair: KeyPair<Ed25519PrivateKey, Ed25519PublicKey>,
+        config_dir: Option<String>,
+        template_path: Option<String>,
+    ) -> Self {
+        Self::builder_from_args(
+            num_nodes,
+            disable_logging,
+            faucet_account_keypair,
+            config_dir,
+            template_path,
+        )
+        .with_genesis_ceremony()
+        .launch()
+        .unwrap_or_else(|e| panic!("Unable to launch swarm: {}", e))
+    }
+
+    /// Attaches to the local swarm launched into the given persistent directory by another
+    /// process (e.g. `libra_swarm -c <config_dir>`) without launching anything: the nodes are
+    /// discovered from their configs, and can be health checked, queried and their logs read.
+    /// The nodes are left running when the swarm is dropped.
+    pub fn attach<P: AsRef<Path>>(config_dir: P) -> Result<Self> {
+        let dir = config_dir.as_ref().to_path_buf();
+        let config = SwarmConfig::load(&dir)?;
+        let logs_dir_path = dir.join("logs");
+        let mut swarm = Self {
+            dir: Some(LibraSwarmDir::Persistent(dir)),
+            validator_nodes: HashMap::new(),
+            full_nodes: HashMap::new(),
+            config,
+            binaries: HashMap::new(),
+            hosts: HashMap::new(),
+            node_env: HashMap::new(),
+            node_args: HashMap::new(),
+            logs: SwarmLogs::default(),
+            proxies: None,
+            faucet: None,
+            // The artifacts belong to the swarm that launched the nodes.
+            artifact_retention: ArtifactRetention::Never,
+            artifact_export_dir: None,
+            artifact_bundle: None,
+            startup_policy: StartupPolicy::default(),
+            liveness_checker: None,
+            resource_watcher: None,
+            telemetry: None,
+            chaos: None,
+            events: EventBus::default(),
+            crash_monitor: None,
+            regions: HashMap::new(),
+        };
+        for (path, node_config) in &swarm.config.configs {
+            let node = LibraNode::attach(node_config, path, &logs_dir_path)?;
+            swarm.logs.follow(&node.peer_id, node.log.clone());
+            if node_config.is_validator() {
+                swarm.validator_nodes.insert(node.peer_id(), node);
+            } else {
+                swarm.full_nodes.insert(node.peer_id(), node);
+            }
+        }
+        info!(
+            "Attached to {} validators and {} full nodes",
+            swarm.validator_nodes.len(),
+            swarm.full_nodes.len()
+        );
+        Ok(swarm)
+    }
+
+    // The builder equivalent to the positional arguments of `launch_swarm`.
+    fn builder_from_args(
+        num_nodes: usize,
+        disable_logging: bool,
+        faucet_account_keypair: KeyPair<Ed25519PrivateKey, Ed25519PublicKey>,
+        config_dir: Option<String>,
+        template_path: Option<String>,
+    ) -> LibraSwarmBuilder {
+        let mut builder = Self::builder(faucet_account_keypair);
+        builder
+            .with_num_nodes(num_nodes)
+            .with_logging(!disable_logging);
+        if let Some(config_dir) = config_dir {
+            builder.with_config_dir(config_dir);
+        }
+        if let Some(template_path) = template_path {
+            builder.with_template(template_path);
+        }
+        builder
+    }
+
+    /// Verifies that the workspace root, the node binary and the template config (the default
+    /// one if None) are usable for launching the swarm.
+    pub fn preflight_check(
+        template_path: &Option<String>,
+    ) -> std::result::Result<(), PreflightFailure> {
+        let workspace_root = utils::workspace_root();
+        preflight::check_workspace_root(&workspace_root)?;
+        preflight::check_template(&Self::template_config_path(template_path))?;
+        preflight::check_binary(&utils::get_bin_path(LIBRA_NODE_BIN))
+    }
+
+    fn template_config_path(template_path: &Option<String>) -> PathBuf {
+        utils::workspace_root().join(
+            template_path
+                .as_ref()
+                .unwrap_or(&"config/data/configs/node.config.toml".to_string()),
+        )
+    }
+
+    /// Either create a persistent directory for swarm or return a temporary one.
+    /// If specified persistent directory already exists,
+    /// assumably due to previous launch failure, it will be removed.
+    /// The directory for the last failed attempt won't be removed.
+    fn setup_config_dir(config_dir: &Option<String>) -> LibraSwarmDir {
+        let dir = match config_dir {
+            Some(dir_str) => {
+                let path_buf = PathBuf::from_str(&dir_str).expect("unable to create config dir");
+                if path_buf.exists() {
+                    std::fs::remove_dir_all(dir_str).expect("unable to delete previous config dir");
+                }
+                std::fs::create_dir_all(dir_str).expect("unable to create config dir");
+                LibraSwarmDir::Persistent(path_buf)
+            }
+            None => {
+                let temp_dir = TempPath::new();
+                temp_dir
+                    .create_as_dir()
+                    .expect("unable to create temporary config dir");
+                LibraSwarmDir::Temporary(temp_dir)
+            }
+        };
+        println!("Base directory containing logs and configs: {:?}", &dir);
+        dir
+    }
+
+    fn launch_swarm_attempt(
+        builder: &LibraSwarmBuilder,
+        dir: LibraSwarmDir,
+    ) -> std::result::Result<Self, SwarmLaunchFailure> {
+        let logs_dir_path = dir.as_ref().join("logs");
+        std::fs::create_dir(&logs_dir_path).unwrap();
+        let base = Self::template_config_path(&builder.template_path);
         let mut config_builder = SwarmConfigBuilder::new();
 
         config_builder
             .with_ipv4()
-            .with_num_nodes(num_nodes)
+            .with_num_nodes(builder.num_nodes)
+            .with_num_full_nodes_per_validator(builder.num_full_nodes_per_validator)
+            .with_role(builder.role)
             .with_base(base)
             .with_output_dir(&dir)
-            .with_faucet_keypair(faucet_account_keypair);
-        let config = config_builder.build().unwrap();
+            .with_faucet_keypair(builder.faucet_account_keypair.clone());
+        if builder.genesis_ceremony {
+            config_builder.with_genesis_ceremony();
+        }
+        if let Some(cache) = &builder.genesis_cache {
+            config_builder.with_genesis_cache(cache.clone());
+        }
+        let mut config = config_builder.build().unwrap();
+        for (index, config_override) in &builder.node_overrides {
+            let (path, node_config) = &mut config.configs[*index];
+            config_override(node_config);
+            node_config.save_config(path);
+        }
+        // The ports of the local nodes are held until the nodes are spawned, a port taken in the
+        // meantime is replaced instead of failing the launch attempt.
+        let mut ports = PortAllocator::new();
+        if builder.remote_hosts.is_empty() {
+            let addresses = ports
+                .reserve_nodes(&mut config.configs)
+                .map_err(SwarmLaunchFailure::PortAllocation)?;
+            if !addresses.is_empty() {
+                ports::update_seed_peers(&mut config, &addresses);
+            }
+        } else {
+            Self::setup_remote_hosts(builder, &mut config, dir.as_ref())
+                .map_err(SwarmLaunchFailure::RemoteSetup)?;
+        }
+        let proxies = if builder.network_proxies {
+            Some(Self::setup_network_proxies(&mut config).map_err(SwarmLaunchFailure::ProxySetup)?)
+        } else {
+            None
+        };
+        // The configs are final, a node which can't start with its config is reported before any
+        // node is spawned.
+        preflight::validate_configs(&config).map_err(SwarmLaunchFailure::Preflight)?;
 
         let mut swarm = Self {
             dir: Some(dir),
             validator_nodes: HashMap::new(),
-            full_nodes: vec![],
+            full_nodes: HashMap::new(),
             config,
+            binaries: HashMap::new(),
+            hosts: HashMap::new(),
+            node_env: HashMap::new(),
+            node_args: HashMap::new(),
+            logs: SwarmLogs::default(),
+            proxies,
+            faucet: None,
+            artifact_retention: builder.artifact_retention,
+            artifact_export_dir: builder.artifact_export_dir.clone(),
+            artifact_bundle: builder.artifact_bundle.clone(),
+            startup_policy: builder.startup_policy.clone(),
+            liveness_checker: None,
+            resource_watcher: None,
+            telemetry: None,
+            chaos: None,
+            events: builder.events.clone(),
+            crash_monitor: None,
+            regions: HashMap::new(),
         };
-        // For each config launch a node
-        for (path, node_config) in &swarm.config.configs {
-            let node =
-                LibraNode::launch(&node_config, &path, &logs_dir_path, disable_logging).unwrap();
-            if node_config.is_validator() {
-                swarm.validator_nodes.insert(node.peer_id(), node);
-            } else {
-                swarm.full_nodes.push(node);
+        // For each config prepare a node, the nodes are then spawned concurrently
+        let mut nodes = vec![];
+        for (index, (path, node_config)) in swarm.config.configs.iter().enumerate() {
+            let binary = builder.node_binary(index);
+            let host = builder.node_host(index).cloned();
+            let env = builder.node_env(index);
+            let args = builder.node_args(index);
+            let node = LibraNode::new(
+                &node_config,
+                &path,
+                &logs_dir_path,
+                builder.disable_logging,
+                &binary,
+                host.clone(),
+                env.clone(),
+                args.clone(),
+            )
+            .unwrap();
+            node.create_logs().unwrap();
+            swarm.binaries.insert(node.peer_id(), binary);
+            swarm.node_env.insert(node.peer_id(), env);
+            swarm.node_args.insert(node.peer_id(), args);
+            swarm.logs.follow(&node.peer_id, node.log.clone());
+            if let Some(host) = host {
+                swarm.hosts.insert(node.peer_id(), host);
+            }
+            nodes.push((node, node_config.is_validator()));
+        }
+        while !nodes.is_empty() {
+            let batch_size = nodes.len().min(builder.startup_policy.concurrency.max(1));
+            let spawns: Vec<_> = nodes
+                .drain(..batch_size)
+                .map(|(mut node, is_validator)| {
+                    ports.release(&node.peer_id);
+                    thread::spawn(move || node.spawn().map(|()| (node, is_validator)))
+                })
+                .collect();
+            for spawn in spawns {
+                let (node, is_validator) =
+                    spawn.join().expect("Node launch thread panicked").unwrap();
+                if let Some(proxies) = &swarm.proxies {
+                    proxies.register_node(node.peer_id(), Arc::clone(&node.node));
+                }
+                swarm.events.watch(node.peer_id(), Arc::clone(&node.node));
+                swarm.events.emit(SwarmEvent::NodeLaunched {
+                    peer_id: node.peer_id(),
+                });
+                if is_validator {
+                    swarm.validator_nodes.insert(node.peer_id(), node);
+                } else {
+                    swarm.full_nodes.insert(node.peer_id(), node);
+                }
             }
         }
 
+        if let Some(topology) = &builder.regions {
+            swarm
+                .setup_regions(topology)
+                .map_err(SwarmLaunchFailure::ProxySetup)?;
+        }
+        if swarm.events.has_subscribers() {
+            swarm.crash_monitor = Some(CrashMonitor::start(swarm.events.clone()));
+        }
+
         swarm.wait_for_startup()?;
         swarm.wait_for_connectivity()?;
+        if builder.faucet {
+            swarm.faucet = Some(
+                swarm
+                    .launch_faucet(builder.faucet_account_keypair.clone())
+                    .map_err(SwarmLaunchFailure::FaucetSetup)?,
+            );
+        }
 
         info!("Successfully launched Swarm");
 
         Ok(swarm)
     }
 
+    // Makes the remote nodes reachable at the address of their host, both in their config and
+    // in the seed peers of the other nodes, then copies the swarm directory to every host.
+    fn setup_remote_hosts(
+        builder: &LibraSwarmBuilder,
+        config: &mut SwarmConfig,
+        dir: &Path,
+    ) -> Result<()> {
+        let mut addresses = HashMap::new();
+        for (index, (path, node_config)) in config.configs.iter_mut().enumerate() {
+            if let Some(host) = builder.node_host(index) {
+                let network = node_config.networks.get_mut(0).unwrap();
+                network.advertised_address = host
+                    .host_multiaddr(&network.advertised_address.to_string())
+                    .parse()
+                    .map_err(|_| format_err!("Invalid address of host {}", host.destination))?;
+                addresses.insert(network.peer_id.clone(), network.advertised_address.clone());
+                node_config.save_config(path);
+            }
+        }
+        ports::update_seed_peers(config, &addresses);
+
+        let mut destinations = HashSet::new();
+        for host in &builder.remote_hosts {
+            if destinations.insert(&host.destination) {
+                host.copy_dir(dir)?;
+            }
+        }
+        Ok(())
+    }
+
+    // Starts a proxy in front of every node, and makes the nodes advertise the port of their
+    // proxy instead of the one they listen on.
+    fn setup_network_proxies(config: &mut SwarmConfig) -> Result<NetworkProxies> {
+        let mut proxies = NetworkProxies::new();
+        let mut addresses = HashMap::new();
+        for (path, node_config) in config.configs.iter_mut() {
+            let network = node_config.networks.get_mut(0).unwrap();
+            let node_port = partition::tcp_port(&network.listen_address.to_string())
+                .ok_or_else(|| format_err!("No TCP port in {}", network.listen_address))?;
+            let proxy_port = proxies.start_proxy(network.peer_id.clone(), node_port)?;
+            network.advertised_address =
+                partition::with_tcp_port(&network.advertised_address.to_string(), proxy_port)
+                    .parse()
+                    .map_err(|_| {
+                        format_err!("Invalid address of the proxy of {}", network.peer_id)
+                    })?;
+            addresses.insert(network.peer_id.clone(), network.advertised_address.clone());
+            node_config.save_config(path);
+        }
+        ports::update_seed_peers(config, &addresses);
+        Ok(proxies)
+    }
+
+    // Every running validator has to be connected to the other running validators, and every
+    // running full node to its running upstream validators. The stopped or paused nodes are
+    // neither checked nor expected to be connected, and neither are the nodes of a blocked link.
     fn wait_for_connectivity(&self) -> std::result::Result<(), SwarmLaunchFailure> {
-        // Early return if we're only launching a single node
-        if self.validator_nodes.len() == 1 {
+        let is_running = |peer_id: &String| {
+            self.validator_nodes
+                .get(peer_id)
+                .map_or(false, |node| !node.is_stopped() && !node.is_paused())
+        };
+        let is_blocked = |a: &str, b: &str| {
+            self.proxies
+                .as_ref()
+                .map_or(false, |proxies| proxies.is_blocked(a, b))
+        };
+        let running_validators: Vec<_> = self
+            .validator_nodes
+            .values()
+            .filter(|node| !node.is_stopped() && !node.is_paused())
+            .collect();
+        let mut expected_peers: Vec<_> = running_validators
+            .iter()
+            .map(|node| {
+                let reachable = running_validators
+                    .iter()
+                    .filter(|peer| peer.peer_id != node.peer_id)
+                    .filter(|peer| !is_blocked(&node.peer_id, &peer.peer_id))
+                    .count();
+                (*node, reachable as i64)
+            })
+            .collect();
+        expected_peers.extend(
+            self.full_nodes
+                .values()
+                .filter(|node| !node.is_stopped() && !node.is_paused())
+                .map(|node| {
+                    let reachable_upstreams = node
+                        .upstream_peers
+                        .iter()
+                        .filter(|p| is_running(p) && !is_blocked(&node.peer_id, p))
+                        .count();
+                    (node, reachable_upstreams as i64)
+                }),
+        );
+        // Early return if no node has any peer to connect to (e.g. a single node)
+        if expected_peers.iter().all(|(_, expected)| *expected == 0) {
+            self.events.emit(SwarmEvent::ConnectivityAchieved);
             return Ok(());
         }
 
-        let num_attempts = 60;
-
-        for i in 0..num_attempts {
+        let policy = &self.startup_policy;
+        let deadline = Instant::now() + policy.connectivity_timeout;
+        for i in 0.. {
             debug!("Wait for connectivity attempt: {}", i);
 
-            if self
-                .validator_nodes
-                .values()
-                .all(|node| node.check_connectivity(self.validator_nodes.len() as i64 - 1))
-            {
+            let connected: Vec<bool> = stream::iter_ok(
+                expected_peers
+                    .iter()
+                    .map(|(node, expected)| node.check_connectivity_async(*expected)),
+            )
+            .buffered(policy.concurrency.max(1))
+            .collect()
+            .wait()
+            .unwrap_or_default();
+            if !connected.is_empty() && connected.iter().all(|connected| *connected) {
+                self.events.emit(SwarmEvent::ConnectivityAchieved);
                 return Ok(());
             }
-            // TODO check full node connectivity for full nodes
-
-            ::std::thread::sleep(::std::time::Duration::from_millis(1000));
+            if Instant::now() >= deadline {
+                break;
+            }
+            thread::sleep(policy.poll_interval);
         }
 
         Err(SwarmLaunchFailure::ConnectivityTimeout)
     }
 
     fn wait_for_startup(&mut self) -> std::result::Result<(), SwarmLaunchFailure> {
-        let num_attempts = 120;
+        let policy = self.startup_policy.clone();
+        let deadline = Instant::now() + policy.startup_timeout;
         let mut done = vec![false; self.validator_nodes.len() + self.full_nodes.len()];
-        for i in 0..num_attempts {
-            debug!("Wait for startup attempt: {} of {}", i, num_attempts);
+        for i in 0.. {
+            debug!("Wait for startup attempt: {}", i);
+            let mut pending_done = vec![];
+            let mut pending_checks = vec![];
             for (node, done) in self
                 .validator_nodes
                 .values_mut()
-                .chain(self.full_nodes.iter_mut())
+                .chain(self.full_nodes.values_mut())
                 .zip(done.iter_mut())
             {
                 if *done {
                     continue;
                 }
+                if let Some(status) = node.crash_status() {
+                    return Err(SwarmLaunchFailure::NodeCrash(node.crash_report(status)));
+                }
+                pending_done.push((node.peer_id(), done));
+                // A degraded node is up, the health of its subsystems is checked by
+                // `wait_for_healthy`.
+                pending_checks.push(node.rpc_health_check().map(|status| match status {
+                    HealthStatus::Healthy | HealthStatus::Degraded(_) => true,
+                    _ => false,
+                }));
+            }
 
-                match node.health_check() {
-                    HealthStatus::Healthy => *done = true,
-                    HealthStatus::RpcFailure(_) => continue,
-                    HealthStatus::Crashed(status) => {
-                        error!(
-                            "Libra node '{}' has crashed with status '{}'. Log output: '''{}'''",
-                            node.peer_id,
-                            status,
-                            node.get_log_contents().unwrap()
-                        );
-                        return Err(SwarmLaunchFailure::NodeCrash);
+            // Query the pending nodes concurrently, a bounded number at a time.
+            if let Ok(healthy) = stream::iter_ok::<_, Error>(pending_checks)
+                .buffered(policy.concurrency.max(1))
+                .collect()
+                .wait()
+            {
+                for ((peer_id, done), healthy) in pending_done.into_iter().zip(healthy) {
+                    *done = healthy;
+                    if healthy {
+                        self.events.emit(SwarmEvent::NodeHealthy { peer_id });
                     }
                 }
             }
@@ -384,88 +1837,397 @@ impl LibraSwarm {
             if done.iter().all(|status| *status) {
                 return Ok(());
             }
-
-            ::std::thread::sleep(::std::time::Duration::from_millis(1000));
+            if Instant::now() >= deadline {
+                break;
+            }
+            thread::sleep(policy.poll_interval);
         }
 
         Err(SwarmLaunchFailure::LaunchTimeout)
     }
 
-    /// This function first checks the last committed round of all the nodes, picks the max
-    /// value and then waits for all the nodes to catch up to that round.
-    /// Once done, we can guarantee that all the txns committed before the invocation of this
-    /// function are now available at all the nodes.
-    pub fn wait_for_all_nodes_to_catchup(&mut self) -> bool {
-        let num_attempts = 60;
-        let last_committed_round_str = "consensus{op=committed_blocks_count}";
-        let mut done = vec![false; self.validator_nodes.len()];
-
-        let mut last_committed_round = 0;
-        // First, try to retrieve the max value across all the committed rounds
-        debug!("Calculating max committed round across the validators.");
-        for node in self.validator_nodes.values() {
-            match node.get_metric(last_committed_round_str) {
-                Some(val) => {
-                    debug!("\tNode {} last committed round = {}", node.peer_id, val);
-                    last_committed_round = last_committed_round.max(val);
-                }
-                None => {
-                    debug!(
-                        "\tNode {} last committed round unknown, assuming 0.",
-                        node.peer_id
-                    );
+    /// Waits for all the validators to catch up to the target committed round. With the
+    /// default target (`CatchupTarget::MaxPlus(0)`) the function first checks the last committed
+    /// round of all the nodes, picks the max value and then waits for all the nodes to catch up
+    /// to that round. The full nodes then have to sync the highest version committed by the
+    /// validators.
+    /// Once all the nodes have caught up, we can guarantee that all the txns committed before the
+    /// invocation of this function are now available at all the nodes.
+    /// The nodes are given more time to catch up when their links are shaped with added delays.
+    pub fn wait_for_all_nodes_to_catchup(&mut self, target: CatchupTarget) -> CatchupReport {
+        let start_time = Instant::now();
+        let max_link_delay = self
+            .proxies
+            .as_ref()
+            .map_or_else(|| Duration::from_secs(0), NetworkProxies::max_link_delay);
+        let timeout = CATCHUP_TIMEOUT + max_link_delay * CATCHUP_TIMEOUT_DELAY_FACTOR;
+
+        let validators = self.get_validators_ids();
+        let start_rounds = self.get_metric_for_nodes(&validators, COMMITTED_ROUND_METRIC);
+        let target_round = match target {
+            CatchupTarget::Round(round) => round,
+            CatchupTarget::MaxPlus(rounds) => {
+                // First, try to retrieve the max value across all the committed rounds
+                debug!("Calculating max committed round across the validators.");
+                for peer_id in &validators {
+                    match start_rounds.get(peer_id) {
+                        Some(val) => debug!("\tNode {} last committed round = {}", peer_id, val),
+                        None => debug!(
+                            "\tNode {} last committed round unknown, assuming 0.",
+                            peer_id
+                        ),
+                    }
                 }
+                start_rounds.values().cloned().max().unwrap_or(0) + rounds
             }
-        }
+        };
+        let targets = validators
+            .iter()
+            .map(|peer_id| (peer_id.clone(), target_round))
+            .collect();
+        let mut report = CatchupReport::new(
+            target_round,
+            self.wait_for_nodes_to_reach(
+                &validators,
+                COMMITTED_ROUND_METRIC,
+                &start_rounds,
+                &targets,
+                start_time,
+                timeout,
+            ),
+        );
 
-        // Now wait for all the nodes to catch up to the max.
-        for i in 0..num_attempts {
-            debug!(
-                "Wait for catchup, target_commit_round = {}, attempt: {} of {}",
-                last_committed_round,
-                i + 1,
-                num_attempts
+        let full_nodes = self.get_full_nodes_ids();
+        let mut full_nodes_target = None;
+        if !full_nodes.is_empty() {
+            // The validators have caught up, the txns committed so far are all part of the
+            // highest version they report.
+            let target_version = self
+                .get_metric_for_nodes(&validators, COMMITTED_VERSION_METRIC)
+                .values()
+                .cloned()
+                .max()
+                .unwrap_or(0);
+            let start_versions = self.get_metric_for_nodes(&full_nodes, COMMITTED_VERSION_METRIC);
+            let targets = full_nodes
+                .iter()
+                .map(|peer_id| (peer_id.clone(), target_version))
+                .collect();
+            let full_nodes = self.wait_for_nodes_to_reach(
+                &full_nodes,
+                COMMITTED_VERSION_METRIC,
+                &start_versions,
+                &targets,
+                start_time,
+                timeout,
             );
-            for (node, done) in self.validator_nodes.values_mut().zip(done.iter_mut()) {
-                if *done {
-                    continue;
-                }
+            report = report.with_full_nodes(target_version, full_nodes);
+            full_nodes_target = Some(target_version);
+        }
+        if report.is_success() {
+            self.events.emit(SwarmEvent::CatchupReached {
+                round: target_round,
+                version: full_nodes_target,
+            });
+        }
+        info!("{}", report);
+        report
+    }
+
+    /// Waits for at most the given timeout for the given nodes to commit the target round, e.g.
+    /// `ProgressTarget::EachPlus(20)` for 20 more rounds of progress on every node. The report
+    /// lists the nodes that lagged behind on timeout.
+    pub fn wait_for_round(
+        &mut self,
+        target: ProgressTarget,
+        peer_ids: &[String],
+        timeout: Duration,
+    ) -> ProgressReport {
+        self.wait_for_progress(COMMITTED_ROUND_METRIC, target, peer_ids, timeout)
+    }
+
+    /// Waits for at most the given timeout for the given nodes to commit the target version to
+    /// their storage. The report lists the nodes that lagged behind on timeout.
+    pub fn wait_for_version(
+        &mut self,
+        target: ProgressTarget,
+        peer_ids: &[String],
+        timeout: Duration,
+    ) -> ProgressReport {
+        self.wait_for_progress(COMMITTED_VERSION_METRIC, target, peer_ids, timeout)
+    }
 
-                match node.get_metric(last_committed_round_str) {
-                    Some(val) => {
-                        if val >= last_committed_round {
-                            debug!(
-                                "\tNode {} is caught up with last committed round {}",
-                                node.peer_id, val
-                            );
-                            *done = true;
-                        } else {
-                            debug!(
-                                "\tNode {} is not caught up yet with last committed round {}",
-                                node.peer_id, val
-                            );
+    /// Waits for at most the given timeout for all the running nodes to report healthy
+    /// subsystems. On timeout the error lists the unhealthy subsystems of the degraded nodes,
+    /// e.g. a stuck consensus, apart from the nodes which don't answer.
+    pub fn wait_for_healthy(&mut self, timeout: Duration) -> Result<()> {
+        let deadline = Instant::now() + timeout;
+        let poll_interval = self.startup_policy.poll_interval;
+        loop {
+            let mut checks = vec![];
+            for node in self
+                .validator_nodes
+                .values_mut()
+                .chain(self.full_nodes.values_mut())
+                .filter(|node| !node.is_stopped() && !node.is_paused())
+            {
+                if let Some(status) = node.crash_status() {
+                    bail!("{}", node.crash_report(status));
+                }
+                let peer_id = node.peer_id.clone();
+                checks.push(node.rpc_health_check().map(move |status| (peer_id, status)));
+            }
+            let statuses = future::join_all(checks).wait()?;
+            let unhealthy: Vec<_> = statuses
+                .into_iter()
+                .filter(|(_, status)| match status {
+                    HealthStatus::Healthy => false,
+                    _ => true,
+                })
+                .collect();
+            if unhealthy.is_empty() {
+                return Ok(());
+            }
+            if Instant::now() >= deadline {
+                let mut reasons = vec![];
+                for (peer_id, status) in unhealthy {
+                    match status {
+                        HealthStatus::Degraded(subsystems) => {
+                            for subsystem in subsystems {
+                                reasons.push(format!("{}: {}", peer_id, subsystem));
+                            }
+                        }
+                        HealthStatus::RpcFailure(e) => {
+                            reasons.push(format!("{}: not answering: {}", peer_id, e))
+                        }
+                        HealthStatus::Crashed(status) => {
+                            reasons.push(format!("{}: crashed with {}", peer_id, status))
                         }
+                        HealthStatus::Healthy => {}
                     }
-                    None => {
+                }
+                reasons.sort();
+                bail!(
+                    "The nodes are not healthy after {:?}:\n\t{}",
+                    timeout,
+                    reasons.join("\n\t")
+                );
+            }
+            thread::sleep(poll_interval);
+        }
+    }
+
+    fn wait_for_progress(
+        &mut self,
+        metric_name: &str,
+        target: ProgressTarget,
+        peer_ids: &[String],
+        timeout: Duration,
+    ) -> ProgressReport {
+        let start_time = Instant::now();
+        let start_values = self.get_metric_for_nodes(peer_ids, metric_name);
+        let targets = target.targets(peer_ids, &start_values);
+        let nodes = self.wait_for_nodes_to_reach(
+            peer_ids,
+            metric_name,
+            &start_values,
+            &targets,
+            start_time,
+            timeout,
+        );
+        let report = ProgressReport::new(metric_name, targets, nodes);
+        info!("{}", report);
+        report
+    }
+
+    // Waits for the given nodes to report a value of the metric at least equal to their target,
+    // for at most the given timeout. A crashed node is given up on.
+    fn wait_for_nodes_to_reach(
+        &mut self,
+        peer_ids: &[String],
+        metric_name: &str,
+        start_values: &HashMap<String, i64>,
+        targets: &HashMap<String, i64>,
+        start_time: Instant,
+        timeout: Duration,
+    ) -> HashMap<String, NodeCatchup> {
+        let mut reports: HashMap<String, NodeCatchup> = peer_ids
+            .iter()
+            .map(|peer_id| {
+                let start_value = start_values.get(peer_id).cloned();
+                let report = NodeCatchup {
+                    start_round: start_value,
+                    end_round: start_value,
+                    outcome: Err(CatchupFailure::Timeout),
+                };
+                (peer_id.clone(), report)
+            })
+            .collect();
+        let mut done = HashSet::new();
+        self.poll_metric(peer_ids, metric_name, timeout, |values, crashed| {
+            for (peer_id, report) in reports.iter_mut() {
+                if done.contains(peer_id) {
+                    continue;
+                }
+                if let Some(value) = values.get(peer_id) {
+                    report.end_round = Some(*value);
+                    if targets.get(peer_id).map_or(true, |target| value >= target) {
                         debug!(
-                            "\tNode {} last committed round unknown, assuming 0.",
-                            node.peer_id
+                            "\tNode {} is caught up with {} = {}",
+                            peer_id, metric_name, value
                         );
+                        report.outcome = Ok(start_time.elapsed());
+                        done.insert(peer_id.clone());
                     }
+                } else if let Some(status) = crashed.get(peer_id) {
+                    report.outcome = Err(CatchupFailure::Crashed(*status));
+                    done.insert(peer_id.clone());
                 }
             }
+            done.len() == reports.len()
+        });
+        for report in reports.values_mut() {
+            if report.end_round.is_none() && report.outcome == Err(CatchupFailure::Timeout) {
+                report.outcome = Err(CatchupFailure::RoundUnknown);
+            }
+        }
+        reports
+    }
 
-            // Check if all the nodes have been successfully caught up
-            if done.iter().all(|status| *status) {
+    // Polls the metric of the given nodes until the given function, called with the values of the
+    // nodes reporting the metric and with the crash status of the nodes that crashed, returns
+    // true, or until the timeout. Returns whether the function returned true.
+    fn poll_metric<F>(
+        &mut self,
+        peer_ids: &[String],
+        metric_name: &str,
+        timeout: Duration,
+        mut done: F,
+    ) -> bool
+    where
+        F: FnMut(&HashMap<String, i64>, &HashMap<String, ExitStatus>) -> bool,
+    {
+        let deadline = Instant::now() + timeout;
+        for attempt in 1.. {
+            debug!("Wait for {}, attempt: {}", metric_name, attempt);
+            let values = self.get_metric_for_nodes(peer_ids, metric_name);
+            let mut crashed = HashMap::new();
+            for peer_id in peer_ids.iter().filter(|p| !values.contains_key(*p)) {
+                if let Ok(node) = self.get_node_mut(peer_id) {
+                    if let Some(status) = node.crash_status() {
+                        crashed.insert(peer_id.clone(), status);
+                    }
+                }
+            }
+            if done(&values, &crashed) {
                 return true;
             }
-
-            ::std::thread::sleep(::std::time::Duration::from_millis(1000));
+            if Instant::now() >= deadline {
+                break;
+            }
+            thread::sleep(METRIC_POLL_INTERVAL);
         }
-
         false
     }
 
+    /// The value of the given metric (e.g. `consensus{op=committed_blocks_count}`) reported by
+    /// every node, by peer id. The nodes that don't report it are left out.
+    pub fn get_metric_for_all(&self, metric_name: &str) -> HashMap<String, i64> {
+        self.get_metric_for_nodes(&self.get_nodes_ids(), metric_name)
+    }
+
+    // Queries the given metric of the given nodes concurrently.
+    fn get_metric_for_nodes(&self, peer_ids: &[String], metric_name: &str) -> HashMap<String, i64> {
+        let nodes: Vec<_> = peer_ids
+            .iter()
+            .filter_map(|peer_id| self.get_node(peer_id))
+            .collect();
+        future::join_all(nodes.iter().map(|node| node.get_metric(metric_name)))
+            .wait()
+            .unwrap_or_default()
+            .into_iter()
+            .zip(&nodes)
+            .filter_map(|(value, node)| value.map(|value| (node.peer_id.clone(), value)))
+            .collect()
+    }
+
+    /// All the metrics reported by every node, by peer id. The nodes that can't be queried are
+    /// left out.
+    pub fn get_metrics_for_all(&self) -> HashMap<String, NodeMetrics> {
+        let nodes: Vec<_> = self
+            .validator_nodes
+            .values()
+            .chain(self.full_nodes.values())
+            .collect();
+        future::join_all(nodes.iter().map(|node| node.get_metrics_async()))
+            .wait()
+            .unwrap_or_default()
+            .into_iter()
+            .zip(&nodes)
+            .filter_map(|(metrics, node)| metrics.map(|metrics| (node.peer_id.clone(), metrics)))
+            .collect()
+    }
+
+    /// The metrics of the given family (e.g. `consensus`) reported by every node, by peer id then
+    /// by labels (e.g. `op=committed_blocks_count`).
+    pub fn get_metric_family_for_all(&self, family: &str) -> HashMap<String, HashMap<String, i64>> {
+        self.get_metrics_for_all()
+            .into_iter()
+            .map(|(peer_id, metrics)| (peer_id, metrics::metric_family(&metrics, family)))
+            .collect()
+    }
+
+    /// Waits for at most the given timeout for the values of the given metric reported by the
+    /// nodes, by peer id, to satisfy the predicate. Returns the values satisfying the predicate,
+    /// or an error with the last values on timeout.
+    pub fn wait_for_metric<F>(
+        &mut self,
+        metric_name: &str,
+        mut predicate: F,
+        timeout: Duration,
+    ) -> Result<HashMap<String, i64>>
+    where
+        F: FnMut(&HashMap<String, i64>) -> bool,
+    {
+        let peer_ids = self.get_nodes_ids();
+        let mut last_values = HashMap::new();
+        let satisfied = self.poll_metric(&peer_ids, metric_name, timeout, |values, _| {
+            last_values = values.clone();
+            predicate(values)
+        });
+        ensure!(
+            satisfied,
+            "Timeout waiting for {} after {:?}, last values: {:?}",
+            metric_name,
+            timeout,
+            last_values
+        );
+        Ok(last_values)
+    }
+
+    pub fn get_fairness_report(&self) -> FairnessReport {
+        let stats = future::join_all(
+            self.validator_nodes
+                .values()
+                .map(LibraNode::proposer_stats_async),
+        )
+        .wait()
+        .unwrap_or_default();
+        FairnessReport::new(self.validator_nodes.keys().cloned().zip(stats).collect())
+    }
+
+    /// Scrapes the consensus statistics of all the validators, keyed by peer id.
+    pub fn get_consensus_stats(&self) -> HashMap<String, ConsensusStats> {
+        let stats = future::join_all(
+            self.validator_nodes
+                .values()
+                .map(LibraNode::consensus_stats_async),
+        )
+        .wait()
+        .unwrap_or_default();
+        self.validator_nodes.keys().cloned().zip(stats).collect()
+    }
+
     /// A specific public AC port of a validator or a full node.
     pub fn get_ac_port(&self, index: usize, role: RoleType) -> u16 {
         match role {
@@ -478,7 +2240,7 @@ impl LibraSwarm {
                 .unwrap(),
             RoleType::FullNode => *self
                 .full_nodes
-                .iter()
+                .values()
                 .map(|node| node.ac_port())
                 .collect::<Vec<u16>>()
                 .get(index)
@@ -486,6 +2248,17 @@ impl LibraSwarm {
         }
     }
 
+    /// A client of the admission control of a specific validator or full node, indexed as in
+    /// `get_ac_port`.
+    pub fn client_for(&self, index: usize, role: RoleType) -> Result<NodeClient> {
+        let node = match role {
+            RoleType::Validator => self.validator_nodes.values().nth(index),
+            RoleType::FullNode => self.full_nodes.values().nth(index),
+        };
+        node.ok_or_else(|| format_err!("No {:?} of index {} in the swarm", role, index))?
+            .ac_client()
+    }
+
     /// Vector with the peer ids of the validators in the swarm.
     pub fn get_validators_ids(&self) -> Vec<String> {
         self.validator_nodes.keys().cloned().collect()
@@ -496,16 +2269,444 @@ impl LibraSwarm {
         self.config
             .configs
             .iter()
+            .filter(|(_, c)| c.is_validator())
             .map(|(_, c)| c.debug_interface.admission_control_node_debug_port)
             .collect()
     }
 
+    /// Vector with the peer ids of the full nodes in the swarm.
+    pub fn get_full_nodes_ids(&self) -> Vec<String> {
+        self.full_nodes.keys().cloned().collect()
+    }
+
+    /// The peer ids of the validators and of the full nodes.
+    pub fn get_nodes_ids(&self) -> Vec<String> {
+        self.validator_nodes
+            .keys()
+            .chain(self.full_nodes.keys())
+            .cloned()
+            .collect()
+    }
+
     pub fn get_validator(&self, peer_id: &str) -> Option<&LibraNode> {
         self.validator_nodes.get(peer_id)
     }
 
+    pub fn get_full_node(&self, peer_id: &str) -> Option<&LibraNode> {
+        self.full_nodes.get(peer_id)
+    }
+
+    /// The validator or full node with the given peer id.
+    pub fn get_node(&self, peer_id: &str) -> Option<&LibraNode> {
+        self.get_validator(peer_id)
+            .or_else(|| self.get_full_node(peer_id))
+    }
+
+    /// Follows the log of the given node from its current end, see `LibraNode::tail_log`.
+    pub fn tail_log(&self, peer_id: &str) -> Result<LogTail> {
+        self.get_node(peer_id)
+            .ok_or_else(|| format_err!("No node {} in the swarm", peer_id))?
+            .tail_log()
+    }
+
+    /// Waits for a line of the log of the given node matching the pattern, see
+    /// `LibraNode::wait_for_log_pattern`.
+    pub fn wait_for_log_pattern(
+        &self,
+        peer_id: &str,
+        pattern: &Regex,
+        timeout: Duration,
+    ) -> Result<String> {
+        self.get_node(peer_id)
+            .ok_or_else(|| format_err!("No node {} in the swarm", peer_id))?
+            .wait_for_log_pattern(pattern, timeout)
+    }
+
+    /// The entries of the logs of all the nodes, collected up to now. The entries are structured
+    /// if the swarm was launched `with_structured_logs`.
+    pub fn logs(&mut self) -> &SwarmLogs {
+        if let Err(e) = self.logs.collect() {
+            warn!("Failed to collect the logs of the nodes: {}", e);
+        }
+        &self.logs
+    }
+
+    // Connects a faucet to the first validator.
+    fn launch_faucet(
+        &self,
+        keypair: KeyPair<Ed25519PrivateKey, Ed25519PublicKey>,
+    ) -> Result<Faucet> {
+        let mut validators = self.get_validators_ids();
+        validators.sort();
+        let node = validators
+            .first()
+            .and_then(|peer_id| self.get_validator(peer_id))
+            .ok_or_else(|| format_err!("No validator to mint through"))?;
+        let dir = self
+            .dir
+            .as_ref()
+            .ok_or_else(|| format_err!("The swarm has no directory"))?;
+        Faucet::new(
+            node.peer_id(),
+            &node.host_address(),
+            node.ac_port(),
+            &self.get_trusted_peers_config_path(),
+            keypair,
+            &dir.as_ref().join("faucet.mnemonic"),
+        )
+    }
+
+    /// The faucet of the swarm, e.g. `swarm.faucet().mint(address, amount)`. Panics if the swarm
+    /// was launched without `with_faucet`.
+    pub fn faucet(&mut self) -> &mut Faucet {
+        self.faucet
+            .as_mut()
+            .expect("The swarm was launched without a faucet")
+    }
+
+    fn get_node_mut(&mut self, peer_id: &str) -> Result<&mut LibraNode> {
+        match self.validator_nodes.get_mut(peer_id) {
+            Some(node) => Ok(node),
+            None => self
+                .full_nodes
+                .get_mut(peer_id)
+                .ok_or_else(|| format_err!("No node {} in the swarm", peer_id)),
+        }
+    }
+
+    /// Kills the given validator or full node and removes it from the swarm.
     pub fn kill_node(&mut self, peer_id: &str) {
-        self.validator_nodes.remove(peer_id);
+        if self.validator_nodes.remove(peer_id).is_none() {
+            self.full_nodes.remove(peer_id);
+        }
+    }
+
+    /// Stops the given validator or full node gracefully (see `LibraNode::stop`): unlike
+    /// `kill_node`, the node remains part of the swarm such that it can be restarted with
+    /// `restart_node`.
+    pub fn stop_node(&mut self, peer_id: &str) -> Result<()> {
+        self.get_node_mut(peer_id)?.stop(NODE_SHUTDOWN_GRACE_PERIOD)
+    }
+
+    /// Restarts a node stopped with `stop_node` from the same config and data directory, and
+    /// waits for it to be healthy and connected to its running peers.
+    pub fn restart_node(&mut self, peer_id: &str) -> Result<()> {
+        let policy = self.startup_policy.clone();
+        let node = self.get_node_mut(peer_id)?;
+        node.restart()?;
+        self.events.emit(SwarmEvent::NodeRestarted {
+            peer_id: peer_id.to_string(),
+        });
+        Self::wait_for_node_health(self.get_node_mut(peer_id)?, &policy)?;
+        self.events.emit(SwarmEvent::NodeHealthy {
+            peer_id: peer_id.to_string(),
+        });
+        self.wait_for_connectivity()?;
+        Ok(())
+    }
+
+    /// Suspends the process of the given node without terminating it (see `LibraNode::pause`),
+    /// e.g. to simulate a long GC pause: its peers time out on it while it keeps its connections
+    /// and its state. The paused nodes are neither checked for health nor for connectivity, and
+    /// the chaos scheduler leaves them alone while counting them as down.
+    pub fn pause_node(&mut self, peer_id: &str) -> Result<()> {
+        self.get_node_mut(peer_id)?.pause()?;
+        self.events.emit(SwarmEvent::NodePaused {
+            peer_id: peer_id.to_string(),
+        });
+        Ok(())
+    }
+
+    /// Resumes a node paused with `pause_node`, and waits for it to be healthy and connected to
+    /// its running peers. The node then catches up on the rounds it missed by itself.
+    pub fn resume_node(&mut self, peer_id: &str) -> Result<()> {
+        let policy = self.startup_policy.clone();
+        self.get_node_mut(peer_id)?.resume()?;
+        self.events.emit(SwarmEvent::NodeResumed {
+            peer_id: peer_id.to_string(),
+        });
+        Self::wait_for_node_health(self.get_node_mut(peer_id)?, &policy)?;
+        self.events.emit(SwarmEvent::NodeHealthy {
+            peer_id: peer_id.to_string(),
+        });
+        self.wait_for_connectivity()?;
+        Ok(())
+    }
+
+    /// Upgrades the nodes one at a time to the given libra_node binary: the validators first,
+    /// then the full nodes. Every running node is stopped, restarted from the new binary, and
+    /// the whole swarm has to commit a new round before the next node is upgraded, which
+    /// requires a steady load of transactions. The stopped nodes only switch to the new binary.
+    pub fn rolling_upgrade<P: AsRef<Path>>(&mut self, new_binary: P) -> Result<()> {
+        let new_binary = new_binary.as_ref();
+        // The binary of the remote nodes is on their hosts.
+        if self.hosts.is_empty() {
+            preflight::check_binary(new_binary)?;
+        }
+        let mut validators = self.get_validators_ids();
+        validators.sort();
+        let mut full_nodes = self.get_full_nodes_ids();
+        full_nodes.sort();
+        for peer_id in validators.into_iter().chain(full_nodes) {
+            self.binaries
+                .insert(peer_id.clone(), new_binary.to_path_buf());
+            let node = self.get_node_mut(&peer_id)?;
+            node.set_binary(new_binary);
+            if node.is_stopped() {
+                continue;
+            }
+            info!("Upgrading node {} to {:?}", peer_id, new_binary);
+            self.stop_node(&peer_id)?;
+            self.restart_node(&peer_id)?;
+            let report = self.wait_for_all_nodes_to_catchup(CatchupTarget::MaxPlus(1));
+            ensure!(
+                report.is_success(),
+                "The swarm stopped committing after upgrading node {}: {}",
+                peer_id,
+                report
+            );
+        }
+        Ok(())
+    }
+
+    /// Snapshots the data of every node under the given name: its storage dir (libradb and
+    /// consensusdb) and the safety rules state of a validator. The running nodes are stopped
+    /// while their data is copied, then relaunched. A snapshot of the same name is replaced.
+    pub fn snapshot(&mut self, name: &str) -> Result<()> {
+        ensure!(
+            self.hosts.is_empty(),
+            "The data of the remote nodes can't be snapshotted"
+        );
+        let snapshot_dir = self.snapshot_dir(name)?;
+        if snapshot_dir.exists() {
+            std::fs::remove_dir_all(&snapshot_dir)?;
+        }
+        let running = self.stop_running_nodes()?;
+        let copied = self
+            .get_nodes_ids()
+            .iter()
+            .try_for_each(|peer_id| -> Result<()> {
+                let (storage_dir, safety_rules_file) = self.node_data_paths(peer_id)?;
+                let node_dir = snapshot_dir.join(peer_id);
+                utils::copy_dir_all(&storage_dir, &node_dir.join(SNAPSHOT_DB_DIR))?;
+                if safety_rules_file.is_file() {
+                    std::fs::copy(
+                        &safety_rules_file,
+                        node_dir.join(SNAPSHOT_SAFETY_RULES_FILE),
+                    )?;
+                }
+                Ok(())
+            });
+        let relaunched = self.relaunch_nodes(&running);
+        copied.with_context(|_| format!("Failed to snapshot the swarm as '{}'", name))?;
+        relaunched?;
+        info!("Snapshotted the swarm as '{}'", name);
+        Ok(())
+    }
+
+    /// Restores the data of every node from the given snapshot, e.g. to start a test from a
+    /// pre-populated ledger. The running nodes are stopped while their data is replaced, then
+    /// relaunched. The snapshot must hold the data of every node of the swarm.
+    pub fn restore(&mut self, name: &str) -> Result<()> {
+        ensure!(
+            self.hosts.is_empty(),
+            "The data of the remote nodes can't be restored"
+        );
+        let snapshot_dir = self.snapshot_dir(name)?;
+        ensure!(snapshot_dir.is_dir(), "No snapshot '{}'", name);
+        let peer_ids = self.get_nodes_ids();
+        for peer_id in &peer_ids {
+            ensure!(
+                snapshot_dir.join(peer_id).is_dir(),
+                "The snapshot '{}' has no data of node {}",
+                name,
+                peer_id
+            );
+        }
+        let running = self.stop_running_nodes()?;
+        let copied = peer_ids.iter().try_for_each(|peer_id| -> Result<()> {
+            let (storage_dir, safety_rules_file) = self.node_data_paths(peer_id)?;
+            let node_dir = snapshot_dir.join(peer_id);
+            if storage_dir.exists() {
+                std::fs::remove_dir_all(&storage_dir)?;
+            }
+            utils::copy_dir_all(&node_dir.join(SNAPSHOT_DB_DIR), &storage_dir)?;
+            let snapshot_safety_rules = node_dir.join(SNAPSHOT_SAFETY_RULES_FILE);
+            if snapshot_safety_rules.is_file() {
+                std::fs::copy(&snapshot_safety_rules, &safety_rules_file)?;
+            } else if safety_rules_file.is_file() {
+                std::fs::remove_file(&safety_rules_file)?;
+            }
+            Ok(())
+        });
+        let relaunched = self.relaunch_nodes(&running);
+        copied.with_context(|_| format!("Failed to restore the snapshot '{}'", name))?;
+        relaunched?;
+        // The association account is back to the sequence number of the snapshot.
+        if let Some(faucet) = self.faucet.as_mut() {
+            faucet.sync_sequence_number()?;
+        }
+        info!("Restored the swarm from the snapshot '{}'", name);
+        Ok(())
+    }
+
+    fn snapshot_dir(&self, name: &str) -> Result<PathBuf> {
+        ensure!(
+            !name.is_empty() && !name.contains(std::path::is_separator),
+            "Invalid snapshot name '{}'",
+            name
+        );
+        let dir = self
+            .dir
+            .as_ref()
+            .ok_or_else(|| format_err!("The swarm has no directory"))?;
+        Ok(dir.as_ref().join(SNAPSHOTS_DIR).join(name))
+    }
+
+    // The storage dir and the safety rules file of the given node.
+    fn node_data_paths(&self, peer_id: &str) -> Result<(PathBuf, PathBuf)> {
+        let (_, config) = self
+            .config
+            .configs
+            .iter()
+            .find(|(_, config)| config.networks.get(0).unwrap().peer_id == peer_id)
+            .ok_or_else(|| format_err!("No config of node {}", peer_id))?;
+        Ok((
+            config.storage.dir.clone(),
+            config.consensus.safety_rules_storage_file().clone(),
+        ))
+    }
+
+    // Stops the running nodes gracefully, and returns their peer ids.
+    fn stop_running_nodes(&mut self) -> Result<Vec<String>> {
+        let mut running = vec![];
+        for peer_id in self.get_nodes_ids() {
+            let node = self.get_node_mut(&peer_id)?;
+            if !node.is_stopped() {
+                node.stop(NODE_SHUTDOWN_GRACE_PERIOD)?;
+                running.push(peer_id);
+            }
+        }
+        Ok(running)
+    }
+
+    // Restarts the given stopped nodes, and waits for them to be healthy and connected.
+    fn relaunch_nodes(&mut self, peer_ids: &[String]) -> Result<()> {
+        for peer_id in peer_ids {
+            self.get_node_mut(peer_id)?.restart()?;
+            self.events.emit(SwarmEvent::NodeRestarted {
+                peer_id: peer_id.clone(),
+            });
+        }
+        let policy = self.startup_policy.clone();
+        for peer_id in peer_ids {
+            Self::wait_for_node_health(self.get_node_mut(peer_id)?, &policy)?;
+            self.events.emit(SwarmEvent::NodeHealthy {
+                peer_id: peer_id.clone(),
+            });
+        }
+        self.wait_for_connectivity()?;
+        Ok(())
+    }
+
+    /// Removes the given validator through a reconfiguration: the faucet submits the removal
+    /// from the validator set, the remaining validators switch to the next epoch, then the
+    /// validator is stopped and removed from the swarm. Fails unless the remaining validators
+    /// keep committing rounds without it. Requires the swarm to be launched `with_faucet`,
+    /// through another validator.
+    pub fn remove_validator(&mut self, peer_id: &str) -> Result<()> {
+        ensure!(
+            self.get_validator(peer_id).is_some(),
+            "No validator {} in the swarm",
+            peer_id
+        );
+        let faucet = self
+            .faucet
+            .as_mut()
+            .ok_or_else(|| format_err!("The swarm was launched without a faucet"))?;
+        ensure!(
+            faucet.peer_id() != peer_id,
+            "The faucet submits its transactions through the validator {}",
+            peer_id
+        );
+        let address = AccountAddress::from_str(peer_id)?;
+        let mut remaining: Vec<_> = self
+            .get_validators_ids()
+            .into_iter()
+            .filter(|id| id != peer_id)
+            .collect();
+        remaining.sort();
+        let epoch = self.latest_epoch(&remaining)?;
+
+        self.faucet().remove_validator(address)?;
+        let next_epoch = self.wait_for_epoch(&remaining, epoch + 1, RECONFIGURATION_TIMEOUT)?;
+        info!(
+            "Validator {} removed from the validator set of epoch {}",
+            peer_id, next_epoch
+        );
+
+        self.stop_node(peer_id)?;
+        self.kill_node(peer_id);
+        self.events.emit(SwarmEvent::ValidatorRemoved {
+            peer_id: peer_id.to_string(),
+            epoch: next_epoch,
+        });
+
+        let report = self.wait_for_round(
+            ProgressTarget::EachPlus(RECONFIGURATION_PROGRESS_ROUNDS),
+            &remaining,
+            CATCHUP_TIMEOUT,
+        );
+        ensure!(
+            report.is_success(),
+            "The validators stopped committing without {}: {}",
+            peer_id,
+            report
+        );
+        Ok(())
+    }
+
+    // The highest epoch of the latest commit certificates of the given validators.
+    fn latest_epoch(&self, peer_ids: &[String]) -> Result<u64> {
+        let mut epoch = None;
+        for peer_id in peer_ids {
+            let node = self
+                .get_validator(peer_id)
+                .ok_or_else(|| format_err!("No validator {} in the swarm", peer_id))?;
+            if let Some(certificate) = node.latest_commit_certificate()? {
+                let node_epoch = certificate.ledger_info().epoch_num();
+                epoch = Some(epoch.map_or(node_epoch, |epoch: u64| epoch.max(node_epoch)));
+            }
+        }
+        epoch.ok_or_else(|| format_err!("No validator runs consensus"))
+    }
+
+    // Waits for every given validator to commit a block of at least the given epoch, and
+    // returns the lowest epoch they reached.
+    fn wait_for_epoch(&self, peer_ids: &[String], epoch: u64, timeout: Duration) -> Result<u64> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let mut lowest = None;
+            for peer_id in peer_ids {
+                let node_epoch = self
+                    .get_validator(peer_id)
+                    .and_then(|node| node.latest_commit_certificate().ok())
+                    .and_then(|certificate| certificate)
+                    .map_or(0, |certificate| certificate.ledger_info().epoch_num());
+                lowest = Some(lowest.map_or(node_epoch, |lowest: u64| lowest.min(node_epoch)));
+            }
+            let lowest = lowest.unwrap_or(0);
+            if lowest >= epoch {
+                return Ok(lowest);
+            }
+            ensure!(
+                Instant::now() < deadline,
+                "The validators didn't switch to epoch {} within {:?}",
+                epoch,
+                timeout
+            );
+            thread::sleep(METRIC_POLL_INTERVAL);
+        }
     }
 
     pub fn add_node(
@@ -535,15 +2736,466 @@ impl LibraSwarm {
                 )[..],
             );
         let logs_dir_path = self.dir.as_ref().map(|x| x.as_ref().join("logs")).unwrap();
-        let mut node = LibraNode::launch(config, path, &logs_dir_path, disable_logging).unwrap();
-        for _ in 0..60 {
+        let binary = self
+            .binaries
+            .get(&peer_id)
+            .cloned()
+            .unwrap_or_else(|| utils::get_bin(LIBRA_NODE_BIN));
+        let host = self.hosts.get(&peer_id).cloned();
+        let env = self.node_env.get(&peer_id).cloned().unwrap_or_default();
+        let args = self.node_args.get(&peer_id).cloned().unwrap_or_default();
+        let mut node = LibraNode::launch(
+            config,
+            path,
+            &logs_dir_path,
+            disable_logging,
+            &binary,
+            host,
+            env,
+            args,
+        )
+        .unwrap();
+        self.logs.follow(&peer_id, node.log.clone());
+        if let Some(proxies) = &self.proxies {
+            proxies.register_node(peer_id.clone(), Arc::clone(&node.node));
+        }
+        self.events.watch(peer_id.clone(), Arc::clone(&node.node));
+        self.events.emit(SwarmEvent::NodeLaunched {
+            peer_id: peer_id.clone(),
+        });
+        Self::wait_for_node_health(&mut node, &self.startup_policy)?;
+        self.events.emit(SwarmEvent::NodeHealthy {
+            peer_id: peer_id.clone(),
+        });
+        if config.is_validator() {
+            self.validator_nodes.insert(peer_id, node);
+        } else {
+            self.full_nodes.insert(peer_id, node);
+        }
+        self.wait_for_connectivity()
+    }
+
+    // Waits for a node launched after the swarm to answer the debug interface requests.
+    fn wait_for_node_health(
+        node: &mut LibraNode,
+        policy: &StartupPolicy,
+    ) -> std::result::Result<(), SwarmLaunchFailure> {
+        let deadline = Instant::now() + policy.startup_timeout;
+        loop {
+            match node.health_check() {
+                HealthStatus::Healthy | HealthStatus::Degraded(_) => return Ok(()),
+                HealthStatus::Crashed(status) => {
+                    return Err(SwarmLaunchFailure::NodeCrash(node.crash_report(status)));
+                }
+                HealthStatus::RpcFailure(_) => {}
+            }
+            if Instant::now() >= deadline {
+                return Err(SwarmLaunchFailure::LaunchTimeout);
+            }
+            thread::sleep(policy.poll_interval);
+        }
+    }
+
+    fn proxies(&self) -> Result<&NetworkProxies> {
+        self.proxies.as_ref().ok_or_else(|| {
+            format_err!("The swarm has no network proxies, see `with_network_proxies`")
+        })
+    }
+
+    /// Cuts the connections between the two given nodes and refuses the new ones, in both
+    /// directions, until the link is unblocked. The nodes keep running and keep their other
+    /// links.
+    pub fn block_link(&self, a: &str, b: &str) -> Result<()> {
+        for peer_id in &[a, b] {
+            ensure!(
+                self.get_node(peer_id).is_some(),
+                "No node {} in the swarm",
+                peer_id
+            );
+        }
+        self.proxies()?.block_link(a, b)
+    }
+
+    pub fn unblock_link(&self, a: &str, b: &str) -> Result<()> {
+        self.proxies()?.unblock_link(a, b);
+        Ok(())
+    }
+
+    /// Blocks every link between a node of one side and a node of the other side, the links
+    /// within each side are left as they are.
+    pub fn partition(&self, side_a: &[String], side_b: &[String]) -> Result<()> {
+        for a in side_a {
+            for b in side_b {
+                self.block_link(a, b)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Unblocks all the links, and waits for the nodes to reconnect.
+    pub fn heal(&self) -> Result<()> {
+        self.proxies()?.unblock_all();
+        self.wait_for_connectivity()?;
+        Ok(())
+    }
+
+    /// Delays and throttles the data exchanged between the two given nodes as per the given
+    /// profile, e.g. to emulate the latency between regions. The default profile removes the
+    /// shaping. `wait_for_all_nodes_to_catchup` waits longer as the links are slower.
+    pub fn set_link_profile(&self, a: &str, b: &str, profile: LinkProfile) -> Result<()> {
+        for peer_id in &[a, b] {
+            ensure!(
+                self.get_node(peer_id).is_some(),
+                "No node {} in the swarm",
+                peer_id
+            );
+        }
+        self.proxies()?.set_link_profile(a, b, profile)
+    }
+
+    // Assigns the nodes to their regions, and shapes the links between the nodes with a region.
+    fn setup_regions(&mut self, topology: &RegionTopology) -> Result<()> {
+        for (index, region) in &topology.regions {
+            let (_, node_config) = &self.config.configs[*index];
+            let peer_id = node_config.networks.get(0).unwrap().peer_id.clone();
+            self.regions.insert(peer_id, region.clone());
+        }
+        let proxies = self.proxies()?;
+        let mut nodes: Vec<(&String, &String)> = self.regions.iter().collect();
+        nodes.sort();
+        for (i, (a, region_a)) in nodes.iter().enumerate() {
+            for (b, region_b) in &nodes[i + 1..] {
+                let profile = topology.link_profile(region_a, region_b);
+                if profile != LinkProfile::default() {
+                    proxies.set_link_profile(a, b, profile)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The region of the given node, if it was assigned one with `with_regions`.
+    pub fn region_of(&self, peer_id: &str) -> Option<&str> {
+        self.regions.get(peer_id).map(String::as_str)
+    }
+
+    /// The peer ids of the nodes of the given region.
+    pub fn nodes_in_region(&self, region: &str) -> Vec<String> {
+        let mut peer_ids: Vec<String> = self
+            .regions
+            .iter()
+            .filter(|(_, node_region)| *node_region == region)
+            .map(|(peer_id, _)| peer_id.clone())
+            .collect();
+        peer_ids.sort();
+        peer_ids
+    }
+
+    /// Cuts the given region off the rest of the swarm, see `partition`. The region is
+    /// reconnected with `heal`.
+    pub fn partition_region(&self, region: &str) -> Result<()> {
+        let inside = self.nodes_in_region(region);
+        ensure!(!inside.is_empty(), "No node in region '{}'", region);
+        let outside: Vec<String> = self
+            .get_nodes_ids()
+            .into_iter()
+            .filter(|peer_id| !inside.contains(peer_id))
+            .collect();
+        self.partition(&inside, &outside)
+    }
+
+    /// Shapes the links between the nodes of the two regions as per the given profile, or the
+    /// links within the region if both are the same.
+    pub fn set_region_profile(&self, a: &str, b: &str, profile: LinkProfile) -> Result<()> {
+        let nodes_a = self.nodes_in_region(a);
+        let nodes_b = self.nodes_in_region(b);
+        ensure!(
+            !nodes_a.is_empty() && !nodes_b.is_empty(),
+            "No node in region '{}' or '{}'",
+            a,
+            b
+        );
+        for node_a in &nodes_a {
+            for node_b in nodes_b.iter().filter(|node_b| *node_b != node_a) {
+                self.set_link_profile(node_a, node_b, profile)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The blocked links, as pairs of peer ids.
+    pub fn blocked_links(&self) -> Vec<(String, String)> {
+        self.proxies
+            .as_ref()
+            .map_or_else(Vec::new, |proxies| proxies.blocked_links())
+    }
+
+    /// Starts a background task asserting every `interval` that no validator has crashed and
+    /// that the committed round advanced on a quorum of the validators, which requires a steady
+    /// load of transactions. On failure the validators are killed, such that the test fails
+    /// right away, and the failure is available through `liveness_failure`. Only the validators
+    /// currently in the swarm are monitored.
+    pub fn start_liveness_checker(&mut self, interval: Duration) {
+        let nodes = self
+            .validator_nodes
+            .values()
+            .map(|node| MonitoredNode {
+                peer_id: node.peer_id.clone(),
+                debug_client: node.debug_client.clone(),
+                process: Arc::clone(&node.node),
+            })
+            .collect();
+        self.liveness_checker = Some(LivenessChecker::start(nodes, interval));
+    }
+
+    /// The failure detected by the liveness checker, if any.
+    pub fn liveness_failure(&self) -> Option<LivenessFailure> {
+        self.liveness_checker
+            .as_ref()
+            .and_then(LivenessChecker::failure)
+    }
+
+    /// Panics with the context of the failure detected by the liveness checker, if any.
+    pub fn assert_live(&self) {
+        if let Some(failure) = self.liveness_failure() {
+            panic!("Swarm liveness check failed: {}", failure);
+        }
+    }
+
+    /// Starts sampling the resource usage of the nodes every `interval` in the background (see
+    /// `LibraNode::resource_stats`). When a node exceeds the budget, e.g. because its memory
+    /// leaks, the nodes are killed, such that the test fails right away, and the violation is
+    /// available through `resource_violation`. Only the nodes currently in the swarm are
+    /// watched.
+    pub fn start_resource_watcher(&mut self, budget: ResourceBudget, interval: Duration) {
+        let nodes = self
+            .validator_nodes
+            .values()
+            .chain(self.full_nodes.values())
+            .map(|node| WatchedNode {
+                peer_id: node.peer_id.clone(),
+                sampler: node.resource_sampler(),
+            })
+            .collect();
+        self.resource_watcher = Some(ResourceWatcher::start(nodes, budget, interval));
+    }
+
+    /// The latest resource usage sampled by the resource watcher, by peer id.
+    pub fn resource_stats(&self) -> HashMap<String, ResourceStats> {
+        self.resource_watcher
+            .as_ref()
+            .map_or_else(HashMap::new, ResourceWatcher::latest_stats)
+    }
+
+    /// The budget violation detected by the resource watcher, if any.
+    pub fn resource_violation(&self) -> Option<ResourceViolation> {
+        self.resource_watcher
+            .as_ref()
+            .and_then(ResourceWatcher::violation)
+    }
+
+    /// Panics with the budget violation detected by the resource watcher, if any.
+    pub fn assert_within_budget(&self) {
+        if let Some(violation) = self.resource_violation() {
+            panic!("Swarm resource check failed: {}", violation);
+        }
+    }
+
+    /// Starts collecting the current round, HQC round and committed round of the validators
+    /// every `interval` in the background. The latest view of the consensus progress is logged
+    /// and available through `round_view`. Only the validators currently in the swarm are
+    /// monitored.
+    pub fn start_round_telemetry(&mut self, interval: Duration) {
+        let nodes = self
+            .validator_nodes
+            .values()
+            .map(|node| (node.peer_id.clone(), node.debug_client.clone()))
+            .collect();
+        self.telemetry = Some(RoundTelemetry::start(nodes, interval));
+    }
+
+    /// The latest round state of the validators collected by the telemetry, if started.
+    pub fn round_view(&self) -> Option<RoundView> {
+        self.telemetry.as_ref().and_then(RoundTelemetry::view)
+    }
+
+    /// Starts killing, restarting, pausing and resuming random validators in the background,
+    /// following the given policy (see `ChaosScheduler`). Only the validators currently in the
+    /// swarm are disrupted, a scheduler already running is stopped first.
+    pub fn start_chaos(&mut self, policy: ChaosPolicy) {
+        self.stop_chaos();
+        let nodes = self
+            .validator_nodes
+            .values()
+            .map(|node| ChaosNode {
+                peer_id: node.peer_id.clone(),
+                process: Arc::clone(&node.node),
+                paused: Arc::clone(&node.paused),
+                launcher: node.launcher(),
+            })
+            .collect();
+        self.chaos = Some(ChaosScheduler::start(nodes, policy, self.events.clone()));
+    }
+
+    /// Stops the chaos scheduler once the disrupted validators are restored, and returns every
+    /// action it took.
+    pub fn stop_chaos(&mut self) -> Vec<ChaosEvent> {
+        match self.chaos.take() {
+            Some(mut chaos) => {
+                chaos.stop();
+                chaos.events()
+            }
+            None => vec![],
+        }
+    }
+
+    /// The actions taken so far by the chaos scheduler, if started.
+    pub fn chaos_events(&self) -> Vec<ChaosEvent> {
+        self.chaos
+            .as_ref()
+            .map_or_else(Vec::new, ChaosScheduler::events)
+    }
+
+    /// Subscribes to the lifecycle events of the swarm emitted from now on: the nodes launched,
+    /// healthy, crashed and restarted (also by the chaos scheduler), the connectivity achieved
+    /// and the catch-ups reached. See `LibraSwarmBuilder::subscribe_events` for the events of the
+    /// launch.
+    pub fn events(&mut self) -> Receiver<SwarmEvent> {
+        let receiver = self.events.subscribe();
+        if self.crash_monitor.is_none() {
+            self.crash_monitor = Some(CrashMonitor::start(self.events.clone()));
+        }
+        receiver
+    }
+
+    /// Sets what happens to the artifacts of the swarm when it is dropped.
+    pub fn set_artifact_retention(&mut self, retention: ArtifactRetention) {
+        self.artifact_retention = retention;
+    }
+
+    /// The retained artifacts are copied into the given directory when the swarm is dropped, in
+    /// which case a temporary swarm directory is removed anyway.
+    pub fn set_artifact_export_dir(&mut self, export_dir: Option<PathBuf>) {
+        self.artifact_export_dir = export_dir;
+    }
+
+    /// The retained artifacts are bundled into a gzipped tarball at the given path when the
+    /// swarm is dropped.
+    pub fn set_artifact_bundle(&mut self, tarball: Option<PathBuf>) {
+        self.artifact_bundle = tarball;
+    }
+
+    /// The state of every node: whether it runs, its last committed round, the last errors it
+    /// logged and its metrics. The report is written with the retained artifacts when the swarm
+    /// is dropped.
+    pub fn teardown_report(&mut self) -> TeardownReport {
+        let failed = self.has_failed();
+        if let Err(e) = self.logs.collect() {
+            warn!("Failed to collect the logs of the nodes: {}", e);
+        }
+        let mut validators = self.get_validators_ids();
+        validators.sort();
+        let mut full_nodes = self.get_full_nodes_ids();
+        full_nodes.sort();
+        let peer_ids: Vec<_> = validators.into_iter().chain(full_nodes).collect();
+        let metrics = future::join_all(
+            peer_ids
+                .iter()
+                .map(|peer_id| self.get_node(peer_id).unwrap().get_metrics_async()),
+        )
+        .wait()
+        .unwrap_or_else(|_| vec![None; peer_ids.len()]);
+        let mut nodes = vec![];
+        for (peer_id, metrics) in peer_ids.into_iter().zip(metrics) {
+            let errors: Vec<_> = self
+                .logs
+                .node_entries(&peer_id)
+                .into_iter()
+                .filter(|entry| teardown::is_error(entry))
+                .cloned()
+                .collect();
+            let errors =
+                errors[errors.len().saturating_sub(teardown::MAX_ERRORS_PER_NODE)..].to_vec();
+            let is_validator = self.validator_nodes.contains_key(&peer_id);
+            let node = self.get_node_mut(&peer_id).unwrap();
+            let state = match node.crash_status() {
+                Some(status) => NodeState::Exited(status),
+                None if node.is_stopped() => NodeState::Stopped,
+                None if node.is_paused() => NodeState::Paused,
+                None => NodeState::Running,
+            };
+            nodes.push(NodeTeardown {
+                committed_round: metrics
+                    .as_ref()
+                    .and_then(|metrics| metrics.get(COMMITTED_ROUND_METRIC).cloned()),
+                peer_id,
+                is_validator,
+                state,
+                errors,
+                metrics,
+            });
+        }
+        TeardownReport { failed, nodes }
+    }
+
+    /// Copies the artifacts of the swarm (logs, configs, DBs) into the given directory.
+    pub fn export_artifacts(&self, export_dir: &Path) -> Result<()> {
+        let dir = self
+            .dir
+            .as_ref()
+            .ok_or_else(|| format_err!("The swarm has no directory"))?;
+        utils::copy_dir_all(dir.as_ref(), export_dir)
+            .with_context(|_| format!("Failed to export the artifacts to {:?}", export_dir))?;
+        Ok(())
+    }
+
+    // A swarm has failed if the thread is panicking or any node has crashed.
+    fn has_failed(&mut self) -> bool {
+        if std::thread::panicking()
+            || self.liveness_failure().is_some()
+            || self.resource_violation().is_some()
+        {
+            return true;
+        }
+        let mut failed = false;
+        for node in self
+            .validator_nodes
+            .values_mut()
+            .chain(self.full_nodes.values_mut())
+        {
+            failed |= node.crash_status().is_some();
+        }
+        failed
+    }
+
+    // Dumps to stdout the logs of the nodes that are not healthy.
+    fn dump_unhealthy_logs(&mut self) {
+        let validators = self
+            .validator_nodes
+            .iter_mut()
+            .map(|node| ("Validator", node));
+        let full_nodes = self.full_nodes.iter_mut().map(|node| ("Full node", node));
+        for (kind, (peer_id, node)) in validators.chain(full_nodes) {
+            // Skip dumping logs for healthy nodes
             if let HealthStatus::Healthy = node.health_check() {
-                self.validator_nodes.insert(peer_id, node);
-                return self.wait_for_connectivity();
+                continue;
             }
-            ::std::thread::sleep(::std::time::Duration::from_millis(1000));
+
+            // Grab the contents of the node's logs and skip if we were unable to
+            // grab its logs
+            let log_contents = match node.get_log_contents() {
+                Ok(contents) => contents,
+                Err(_) => continue,
+            };
+
+            println!();
+            println!();
+            println!("{:=^80}", "");
+            println!("{} {}", kind, peer_id);
+            println!();
+            println!();
+            println!("{}", log_contents);
         }
-        Err(SwarmLaunchFailure::LaunchTimeout)
     }
 
     pub fn get_trusted_peers_config_path(&self) -> String {
@@ -558,40 +3210,86 @@ impl LibraSwarm {
 
 impl Drop for LibraSwarm {
     fn drop(&mut self) {
-        // If panicking, we don't want to gc the swarm directory.
-        if std::thread::panicking() {
-            if let Some(dir) = self.dir.take() {
-                if let LibraSwarmDir::Temporary(temp_dir) = dir {
-                    let log_path = temp_dir.path();
-                    println!("logs located at {:?}", log_path);
-
-                    // Dump logs for each validator to stdout when `LIBRA_DUMP_LOGS`
-                    // environment variable is set
-                    if env::var_os("LIBRA_DUMP_LOGS").is_some() {
-                        for (peer_id, node) in &mut self.validator_nodes {
-                            // Skip dumping logs for healthy nodes
-                            if let HealthStatus::Healthy = node.health_check() {
-                                continue;
-                            }
+        // The nodes killed by the teardown didn't crash.
+        self.crash_monitor.take();
+        if let Some(liveness_checker) = self.liveness_checker.as_mut() {
+            liveness_checker.stop();
+            if let Some(failure) = liveness_checker.failure() {
+                println!("Swarm liveness check failed: {}", failure);
+            }
+        }
+        if let Some(resource_watcher) = self.resource_watcher.as_mut() {
+            resource_watcher.stop();
+            if let Some(violation) = resource_watcher.violation() {
+                println!("Swarm resource check failed: {}", violation);
+            }
+        }
+        if let Some(mut telemetry) = self.telemetry.take() {
+            telemetry.stop();
+            if let Some(view) = telemetry.view() {
+                println!("{}", view);
+            }
+        }
+        let chaos_events = self.stop_chaos();
+        let failed = self.has_failed();
+        if failed && !chaos_events.is_empty() {
+            println!("Chaos actions:");
+            for event in &chaos_events {
+                println!("\t{}", event);
+            }
+        }
+        // Dump logs for each unhealthy validator to stdout when `LIBRA_DUMP_LOGS`
+        // environment variable is set
+        if failed && env::var_os("LIBRA_DUMP_LOGS").is_some() {
+            self.dump_unhealthy_logs();
+        }
+        let retain = match self.artifact_retention {
+            ArtifactRetention::Always => true,
+            ArtifactRetention::OnFailure => failed,
+            ArtifactRetention::Never => false,
+        };
+        if !retain {
+            return;
+        }
 
-                            // Grab the contents of the node's logs and skip if we were unable to
-                            // grab its logs
-                            let log_contents = match node.get_log_contents() {
-                                Ok(contents) => contents,
-                                Err(_) => continue,
-                            };
-
-                            println!();
-                            println!();
-                            println!("{:=^80}", "");
-                            println!("Validator {}", peer_id);
-                            println!();
-                            println!();
-                            println!("{}", log_contents);
-                        }
-                    }
+        // The report is taken while the nodes still answer.
+        let report = self.teardown_report();
+        if failed {
+            println!("{}", report);
+        }
+        if let Some(dir) = &self.dir {
+            let path = dir.as_ref().join(TEARDOWN_REPORT_FILE);
+            if let Err(e) = report.write(&path) {
+                println!("Failed to write the teardown report to {:?}: {}", path, e);
+            }
+        }
+
+        // Stop the nodes first, such that the logs are complete and the DBs are closed.
+        self.validator_nodes.clear();
+        self.full_nodes.clear();
+        if let (Some(dir), Some(tarball)) = (&self.dir, &self.artifact_bundle) {
+            match teardown::bundle(dir.as_ref(), tarball) {
+                Ok(()) => println!("Artifacts bundled at {:?}", tarball),
+                Err(e) => println!("{}", e),
+            }
+        }
+        if let Some(export_dir) = &self.artifact_export_dir {
+            match self.export_artifacts(export_dir) {
+                Ok(()) => {
+                    println!("Artifacts exported to {:?}", export_dir);
+                    return;
                 }
+                Err(e) => println!("{}, keeping the swarm directory", e),
+            }
+        }
+        match self.dir.take() {
+            Some(LibraSwarmDir::Temporary(temp_dir)) => {
+                println!("Artifacts located at {:?}", temp_dir.persist());
+            }
+            Some(LibraSwarmDir::Persistent(path)) => {
+                println!("Artifacts located at {:?}", path);
             }
+            None => {}
         }
     }
 }
@@ -0,0 +1,161 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! The report of the state of the nodes when a swarm is dropped, written with its artifacts.
+//!
+//! The report lists for every node whether it still runs or how it exited, its last committed
+//! round and the last errors it logged, the artifacts then also hold the metric snapshot of the
+//! nodes. The artifacts can be bundled into a single tarball, e.g. to be uploaded by CI.
+
+use crate::{logs::LogEntry, metrics::NodeMetrics};
+use failure::prelude::*;
+use std::{
+    fmt,
+    fs::File,
+    io::Write,
+    path::Path,
+    process::{Command, ExitStatus},
+};
+
+#[cfg(test)]
+#[path = "teardown_test.rs"]
+mod teardown_test;
+
+/// The report is written into the swarm directory under this name.
+pub const TEARDOWN_REPORT_FILE: &str = "teardown_report.txt";
+/// The number of the last errors of a node kept in the report.
+pub(crate) const MAX_ERRORS_PER_NODE: usize = 20;
+
+#[derive(Clone, Debug)]
+pub enum NodeState {
+    Running,
+    /// The node was stopped on purpose
+    Stopped,
+    /// The node was paused and not resumed
+    Paused,
+    Exited(ExitStatus),
+}
+
+impl fmt::Display for NodeState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NodeState::Running => write!(f, "running"),
+            NodeState::Stopped => write!(f, "stopped"),
+            NodeState::Paused => write!(f, "paused"),
+            NodeState::Exited(status) => write!(f, "exited with {}", status),
+        }
+    }
+}
+
+/// The state of a node when the swarm is dropped.
+#[derive(Clone, Debug)]
+pub struct NodeTeardown {
+    pub peer_id: String,
+    pub is_validator: bool,
+    pub state: NodeState,
+    pub committed_round: Option<i64>,
+    /// The last errors logged by the node, oldest first
+    pub errors: Vec<LogEntry>,
+    /// The metrics of the node, None if the node didn't answer
+    pub metrics: Option<NodeMetrics>,
+}
+
+/// The state of all the nodes when the swarm is dropped, validators first.
+#[derive(Clone, Debug)]
+pub struct TeardownReport {
+    /// Whether the swarm has failed, i.e. a node crashed, a check failed or the thread panicked
+    pub failed: bool,
+    pub nodes: Vec<NodeTeardown>,
+}
+
+impl TeardownReport {
+    /// Writes the report followed by the metric snapshot of the nodes into the given file.
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let mut file = File::create(path)?;
+        write!(file, "{}", self)?;
+        for node in &self.nodes {
+            if let Some(metrics) = &node.metrics {
+                writeln!(file)?;
+                writeln!(file, "Metrics of {}:", node.peer_id)?;
+                let mut metrics: Vec<_> = metrics.iter().collect();
+                metrics.sort();
+                for (name, value) in metrics {
+                    writeln!(file, "\t{} {}", name, value)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for TeardownReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "Swarm teardown ({}):",
+            if self.failed { "failed" } else { "succeeded" }
+        )?;
+        for node in &self.nodes {
+            write!(
+                f,
+                "\t{} {}: {}",
+                if node.is_validator {
+                    "Validator"
+                } else {
+                    "Full node"
+                },
+                node.peer_id,
+                node.state
+            )?;
+            match node.committed_round {
+                Some(round) => write!(f, ", committed round {}", round)?,
+                None => write!(f, ", committed round unknown")?,
+            }
+            writeln!(f, ", {} recent errors", node.errors.len())?;
+            for entry in &node.errors {
+                writeln!(
+                    f,
+                    "\t\t[{}] {}: {}",
+                    entry.level.as_ref().map_or("", String::as_str),
+                    entry.module.as_ref().map_or("", String::as_str),
+                    entry.message
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Whether the entry reports an error: an error level entry, a line of a plain log starting
+/// with the error or critical glog prefix (e.g. `E1016 12:00:00.000000`), or a panic.
+pub(crate) fn is_error(entry: &LogEntry) -> bool {
+    if entry.level.is_some() {
+        return entry.is_error();
+    }
+    let line = entry.message.as_bytes();
+    let glog_error = line.len() > 5
+        && (line[0] == b'E' || line[0] == b'C')
+        && line[1..5].iter().all(u8::is_ascii_digit)
+        && line[5] == b' ';
+    glog_error || entry.message.contains("panicked at")
+}
+
+/// Bundles the given directory into a gzipped tarball at the given path.
+pub(crate) fn bundle(dir: &Path, tarball: &Path) -> Result<()> {
+    let status = Command::new("tar")
+        .arg("-czf")
+        .arg(tarball)
+        .arg("-C")
+        .arg(dir)
+        .arg(".")
+        .status()
+        .context("Failed to run tar")?;
+    ensure!(
+        status.success(),
+        "Failed to bundle {:?} into {:?}: tar exited with {}",
+        dir,
+        tarball,
+        status
+    );
+    Ok(())
+}
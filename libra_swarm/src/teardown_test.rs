@@ -0,0 +1,106 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    logs::LogEntry,
+    teardown::{self, NodeState, NodeTeardown, TeardownReport},
+};
+use std::{collections::BTreeMap, fs, time::UNIX_EPOCH};
+use tools::tempdir::TempPath;
+
+fn entry(level: Option<&str>, message: &str) -> LogEntry {
+    LogEntry {
+        node: "validator".to_string(),
+        time: UNIX_EPOCH,
+        level: level.map(str::to_string),
+        module: level.map(|_| "consensus::chained_bft".to_string()),
+        file: None,
+        line: None,
+        message: message.to_string(),
+        fields: BTreeMap::new(),
+    }
+}
+
+#[test]
+fn test_is_error() {
+    assert!(teardown::is_error(&entry(Some("ERROR"), "failed")));
+    assert!(teardown::is_error(&entry(Some("CRITICAL"), "failed")));
+    assert!(!teardown::is_error(&entry(Some("WARN"), "E1016 12:00")));
+    // The plain lines are recognized by their glog prefix or as panics.
+    assert!(teardown::is_error(&entry(
+        None,
+        "E1016 12:00:00.000000 1 lib.rs:1] failed"
+    )));
+    assert!(teardown::is_error(&entry(
+        None,
+        "C1016 12:00:00.000000 1 lib.rs:1] failed"
+    )));
+    assert!(!teardown::is_error(&entry(
+        None,
+        "I1016 12:00:00.000000 1 lib.rs:1] started"
+    )));
+    assert!(!teardown::is_error(&entry(None, "E101 short")));
+    assert!(!teardown::is_error(&entry(None, "Error")));
+    assert!(teardown::is_error(&entry(
+        None,
+        "thread 'main' panicked at 'oops', src/main.rs:1:1"
+    )));
+}
+
+#[test]
+fn test_report() {
+    let metrics = vec![
+        ("consensus{op=committed_blocks_count}".to_string(), 10),
+        ("consensus{op=current_round}".to_string(), 12),
+    ]
+    .into_iter()
+    .collect();
+    let report = TeardownReport {
+        failed: true,
+        nodes: vec![
+            NodeTeardown {
+                peer_id: "v0".to_string(),
+                is_validator: true,
+                state: NodeState::Running,
+                committed_round: Some(10),
+                errors: vec![entry(Some("ERROR"), "vote rejected")],
+                metrics: Some(metrics),
+            },
+            NodeTeardown {
+                peer_id: "f0".to_string(),
+                is_validator: false,
+                state: NodeState::Paused,
+                committed_round: None,
+                errors: vec![],
+                metrics: None,
+            },
+        ],
+    };
+    assert_eq!(
+        report.to_string(),
+        "Swarm teardown (failed):\n\
+         \tValidator v0: running, committed round 10, 1 recent errors\n\
+         \t\t[ERROR] consensus::chained_bft: vote rejected\n\
+         \tFull node f0: paused, committed round unknown, 0 recent errors\n"
+    );
+
+    // The metrics of the nodes follow the report, sorted.
+    let path = TempPath::new();
+    report.write(path.path()).unwrap();
+    assert_eq!(
+        fs::read_to_string(path.path()).unwrap(),
+        format!(
+            "{}\nMetrics of v0:\n\
+             \tconsensus{{op=committed_blocks_count}} 10\n\
+             \tconsensus{{op=current_round}} 12\n",
+            report
+        )
+    );
+}
+
+#[test]
+fn test_node_state() {
+    assert_eq!(NodeState::Running.to_string(), "running");
+    assert_eq!(NodeState::Stopped.to_string(), "stopped");
+    assert_eq!(NodeState::Paused.to_string(), "paused");
+}
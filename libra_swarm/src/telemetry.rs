@@ -0,0 +1,180 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Opt-in collector of the round state of the validators.
+//!
+//! Every interval the collector scrapes the current round, the round of the highest quorum
+//! certificate and the last committed round of each validator from its debug interface, and
+//! aggregates them into a live view of the consensus progress across the swarm. The latest view
+//! is available at any time and is logged, such that operators and long tests can tell which
+//! validators are lagging behind.
+
+use crate::liveness::sleep_unless_stopped;
+use debug_interface::AsyncNodeDebugClient;
+use futures::{future, Future};
+use logger::prelude::*;
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+const CURRENT_ROUND_METRIC: &str = "consensus{op=current_round}";
+const HQC_ROUND_METRIC: &str = "consensus{op=highest_quorum_cert_round}";
+const COMMITTED_ROUND_METRIC: &str = "consensus{op=last_committed_round}";
+
+/// The round state reported by a validator, None if the validator didn't report it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RoundState {
+    pub current_round: Option<i64>,
+    pub hqc_round: Option<i64>,
+    pub committed_round: Option<i64>,
+}
+
+/// The round state of all the validators at a point in time, keyed by peer id.
+#[derive(Clone, Debug)]
+pub struct RoundView {
+    pub collected_at: Instant,
+    pub nodes: HashMap<String, RoundState>,
+}
+
+impl RoundView {
+    /// The highest current round across the validators.
+    pub fn max_current_round(&self) -> Option<i64> {
+        self.nodes.values().filter_map(|s| s.current_round).max()
+    }
+
+    /// The highest committed round across the validators.
+    pub fn max_committed_round(&self) -> Option<i64> {
+        self.nodes.values().filter_map(|s| s.committed_round).max()
+    }
+
+    /// The validators whose committed round is more than `rounds` behind the highest one, or
+    /// unknown.
+    pub fn lagging(&self, rounds: i64) -> Vec<String> {
+        let max_committed_round = match self.max_committed_round() {
+            Some(round) => round,
+            None => return self.nodes.keys().cloned().collect(),
+        };
+        let mut lagging: Vec<String> = self
+            .nodes
+            .iter()
+            .filter(|(_, state)| match state.committed_round {
+                Some(round) => round + rounds < max_committed_round,
+                None => true,
+            })
+            .map(|(peer_id, _)| peer_id.clone())
+            .collect();
+        lagging.sort();
+        lagging
+    }
+}
+
+impl fmt::Display for RoundView {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fn round(round: Option<i64>) -> String {
+            round.map_or_else(|| "?".to_string(), |r| r.to_string())
+        }
+        writeln!(
+            f,
+            "Round state of the validators (current / hqc / committed):"
+        )?;
+        let mut peer_ids: Vec<&String> = self.nodes.keys().collect();
+        peer_ids.sort();
+        for peer_id in peer_ids {
+            let state = &self.nodes[peer_id];
+            writeln!(
+                f,
+                "\t{}: {} / {} / {}",
+                peer_id,
+                round(state.current_round),
+                round(state.hqc_round),
+                round(state.committed_round)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Handle of the background collector, the collector is stopped when the handle is dropped.
+pub struct RoundTelemetry {
+    stop: Arc<AtomicBool>,
+    view: Arc<Mutex<Option<RoundView>>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl RoundTelemetry {
+    /// Starts collecting the round state of the given validators, given as pairs of peer id and
+    /// debug client, every `interval`.
+    pub fn start(nodes: Vec<(String, AsyncNodeDebugClient)>, interval: Duration) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let view = Arc::new(Mutex::new(None));
+        let handle = {
+            let stop = Arc::clone(&stop);
+            let view = Arc::clone(&view);
+            thread::Builder::new()
+                .name("swarm-telemetry".to_string())
+                .spawn(move || loop {
+                    let new_view = collect(&nodes);
+                    info!("{}", new_view);
+                    *view.lock().unwrap() = Some(new_view);
+                    if !sleep_unless_stopped(interval, &stop) {
+                        return;
+                    }
+                })
+                .expect("Failed to spawn the telemetry collector")
+        };
+        Self {
+            stop,
+            view,
+            handle: Some(handle),
+        }
+    }
+
+    /// The latest view collected, None until the first collection completes.
+    pub fn view(&self) -> Option<RoundView> {
+        self.view.lock().unwrap().clone()
+    }
+
+    pub fn stop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            handle.join().expect("Telemetry collector panicked");
+        }
+    }
+}
+
+impl Drop for RoundTelemetry {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+// The round state of all the nodes, queried concurrently.
+fn collect(nodes: &[(String, AsyncNodeDebugClient)]) -> RoundView {
+    let states = future::join_all(nodes.iter().map(|(_, client)| {
+        client.get_node_metrics().then(|result| {
+            let metrics = result.unwrap_or_default();
+            Ok::<_, ()>(RoundState {
+                current_round: metrics.get(CURRENT_ROUND_METRIC).cloned(),
+                hqc_round: metrics.get(HQC_ROUND_METRIC).cloned(),
+                committed_round: metrics.get(COMMITTED_ROUND_METRIC).cloned(),
+            })
+        })
+    }))
+    .wait()
+    .unwrap_or_else(|_| vec![RoundState::default(); nodes.len()]);
+    RoundView {
+        collected_at: Instant::now(),
+        nodes: nodes
+            .iter()
+            .map(|(peer_id, _)| peer_id.clone())
+            .zip(states)
+            .collect(),
+    }
+}
@@ -3,7 +3,11 @@
 
 use lazy_static::lazy_static;
 use logger::prelude::*;
-use std::{env, path::PathBuf, process::Command};
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+    process::Command,
+};
 
 const WORKSPACE_BUILD_ERROR_MSG: &str = r#"
     Unable to build all workspace binaries. Cannot continue running tests.
@@ -62,16 +66,37 @@ pub fn build_dir() -> PathBuf {
         .expect("Can't find the build directory. Cannot continue running tests")
 }
 
-// Path to a specified binary
-pub fn get_bin<S: AsRef<str>>(bin_name: S) -> PathBuf {
+// Recursively copies the content of the `src` directory into the `dst` directory, which is
+// created if it doesn't exist.
+pub fn copy_dir_all(src: &Path, dst: &Path) -> io::Result<()> {
+    fs::create_dir_all(dst)?;
+    for entry in fs::read_dir(src)? {
+        let entry = entry?;
+        let target = dst.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir_all(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), target)?;
+        }
+    }
+    Ok(())
+}
+
+// Path to a specified binary, which may not exist.
+pub fn get_bin_path<S: AsRef<str>>(bin_name: S) -> PathBuf {
     // We have to check to see if the workspace is built first to ensure that the binaries we're
     // testing are up to date.
     if !*WORKSPACE_BUILT {
         panic!(WORKSPACE_BUILD_ERROR_MSG);
     }
 
+    build_dir().join(format!("{}{}", bin_name.as_ref(), env::consts::EXE_SUFFIX))
+}
+
+// Path to a specified binary
+pub fn get_bin<S: AsRef<str>>(bin_name: S) -> PathBuf {
     let bin_name = bin_name.as_ref();
-    let bin_path = build_dir().join(format!("{}{}", bin_name, env::consts::EXE_SUFFIX));
+    let bin_path = get_bin_path(bin_name);
 
     // If the binary doesn't exist then either building them failed somehow or the supplied binary
     // name doesn't match any binaries this workspace can produce.
@@ -84,3 +109,21 @@ pub fn get_bin<S: AsRef<str>>(bin_name: S) -> PathBuf {
 
     bin_path
 }
+
+// Generates the configs of a swarm of the given number of validators in the given directory,
+// from the default template.
+#[cfg(test)]
+pub fn test_swarm_config(
+    dir: &Path,
+    num_nodes: usize,
+) -> config_builder::swarm_config::SwarmConfig {
+    let (faucet_account_keypair, _, _) = generate_keypair::load_faucet_key_or_create_default(None);
+    config_builder::swarm_config::SwarmConfigBuilder::new()
+        .with_ipv4()
+        .with_num_nodes(num_nodes)
+        .with_base(workspace_root().join("config/data/configs/node.config.toml"))
+        .with_output_dir(dir)
+        .with_faucet_keypair(faucet_account_keypair)
+        .build()
+        .unwrap()
+}
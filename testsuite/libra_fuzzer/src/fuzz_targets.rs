@@ -60,6 +60,10 @@ macro_rules! proto_fuzz_target {
 mod admission_control;
 mod compiled_module;
 mod consensus_proposal;
+mod consensus_proposal_msg;
+mod consensus_sync_info;
+mod consensus_timeout_msg;
+mod consensus_vote_msg;
 mod inner_signed_transaction;
 mod signed_transaction;
 mod vm_value;
@@ -73,6 +77,10 @@ lazy_static! {
             Box::new(inner_signed_transaction::SignedTransactionTarget::default()),
             Box::new(vm_value::ValueTarget::default()),
             Box::new(consensus_proposal::ConsensusProposal::default()),
+            Box::new(consensus_proposal_msg::ConsensusProposalMsg::default()),
+            Box::new(consensus_vote_msg::ConsensusVoteMsg::default()),
+            Box::new(consensus_timeout_msg::ConsensusTimeoutMsg::default()),
+            Box::new(consensus_sync_info::ConsensusSyncInfo::default()),
             Box::new(admission_control::AdmissionControlSubmitTransactionRequest::default()),
         ];
         targets.into_iter().map(|target| (target.name(), target)).collect()
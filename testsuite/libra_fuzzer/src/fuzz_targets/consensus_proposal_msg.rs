@@ -0,0 +1,29 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::FuzzTargetImpl;
+use consensus::chained_bft::network::network_fuzzing::{
+    fuzz_proposal_msg, generate_corpus_proposal_msg,
+};
+use proptest_helpers::ValueGenerator;
+
+#[derive(Clone, Debug, Default)]
+pub struct ConsensusProposalMsg;
+
+impl FuzzTargetImpl for ConsensusProposalMsg {
+    fn name(&self) -> &'static str {
+        module_name!()
+    }
+
+    fn description(&self) -> &'static str {
+        "Consensus ProposalMsg (protobuf, verified)"
+    }
+
+    fn generate(&self, _idx: usize, _gen: &mut ValueGenerator) -> Option<Vec<u8>> {
+        Some(generate_corpus_proposal_msg())
+    }
+
+    fn fuzz(&self, data: &[u8]) {
+        fuzz_proposal_msg(data);
+    }
+}
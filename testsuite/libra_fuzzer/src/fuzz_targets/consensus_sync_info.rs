@@ -0,0 +1,27 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::FuzzTargetImpl;
+use consensus::chained_bft::network::network_fuzzing::{fuzz_sync_info, generate_corpus_sync_info};
+use proptest_helpers::ValueGenerator;
+
+#[derive(Clone, Debug, Default)]
+pub struct ConsensusSyncInfo;
+
+impl FuzzTargetImpl for ConsensusSyncInfo {
+    fn name(&self) -> &'static str {
+        module_name!()
+    }
+
+    fn description(&self) -> &'static str {
+        "Consensus SyncInfo (protobuf, verified)"
+    }
+
+    fn generate(&self, _idx: usize, _gen: &mut ValueGenerator) -> Option<Vec<u8>> {
+        Some(generate_corpus_sync_info())
+    }
+
+    fn fuzz(&self, data: &[u8]) {
+        fuzz_sync_info(data);
+    }
+}
@@ -0,0 +1,27 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::FuzzTargetImpl;
+use consensus::chained_bft::network::network_fuzzing::{fuzz_vote_msg, generate_corpus_vote_msg};
+use proptest_helpers::ValueGenerator;
+
+#[derive(Clone, Debug, Default)]
+pub struct ConsensusVoteMsg;
+
+impl FuzzTargetImpl for ConsensusVoteMsg {
+    fn name(&self) -> &'static str {
+        module_name!()
+    }
+
+    fn description(&self) -> &'static str {
+        "Consensus VoteMsg (protobuf, verified)"
+    }
+
+    fn generate(&self, _idx: usize, _gen: &mut ValueGenerator) -> Option<Vec<u8>> {
+        Some(generate_corpus_vote_msg())
+    }
+
+    fn fuzz(&self, data: &[u8]) {
+        fuzz_vote_msg(data);
+    }
+}
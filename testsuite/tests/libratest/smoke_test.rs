@@ -6,7 +6,7 @@ use cli::{
 };
 use config::config::RoleType;
 use crypto::{ed25519::*, SigningKey};
-use libra_swarm::{swarm::LibraSwarm, utils};
+use libra_swarm::{catchup::CatchupTarget, swarm::LibraSwarm, utils};
 use num_traits::cast::FromPrimitive;
 use rust_decimal::Decimal;
 use std::str::FromStr;
@@ -16,13 +16,19 @@ fn setup_env(
     client_port_index: usize,
     template_path: Option<String>,
     role: RoleType,
+    genesis_ceremony: bool,
 ) -> (LibraSwarm, ClientProxy) {
     ::logger::init_for_e2e_testing();
 
     let (faucet_account_keypair, faucet_key_file_path, _temp_dir) =
         generate_keypair::load_faucet_key_or_create_default(None);
 
-    let swarm = LibraSwarm::launch_swarm(
+    let launch_swarm = if genesis_ceremony {
+        LibraSwarm::launch_swarm_with_genesis_ceremony
+    } else {
+        LibraSwarm::launch_swarm
+    };
+    let swarm = launch_swarm(
         num_nodes, /* num nodes */
         false,     /* disable_logging */
         faucet_account_keypair,
@@ -60,7 +66,13 @@ fn setup_swarm_and_client_proxy(
     num_nodes: usize,
     client_port_index: usize,
 ) -> (LibraSwarm, ClientProxy) {
-    setup_env(num_nodes, client_port_index, None, RoleType::Validator)
+    setup_env(
+        num_nodes,
+        client_port_index,
+        None,
+        RoleType::Validator,
+        false, /* genesis_ceremony */
+    )
 }
 
 fn test_smoke_script(mut client_proxy: ClientProxy) {
@@ -159,6 +171,18 @@ fn smoke_test_multi_node() {
     test_smoke_script(client_proxy);
 }
 
+#[test]
+fn smoke_test_genesis_ceremony() {
+    let (_swarm, mut client_proxy) = setup_env(
+        4,
+        0,
+        None,
+        RoleType::Validator,
+        true, /* genesis_ceremony */
+    );
+    test_smoke_script(client_proxy);
+}
+
 #[test]
 fn test_concurrent_transfers_single_node() {
     let (_swarm, mut client_proxy) = setup_swarm_and_client_proxy(1, 0);
@@ -197,6 +221,29 @@ fn test_basic_fault_tolerance() {
     test_smoke_script(client_proxy);
 }
 
+#[test]
+fn test_remove_validator() {
+    ::logger::init_for_e2e_testing();
+    let (faucet_account_keypair, _faucet_key_file_path, _temp_dir) =
+        generate_keypair::load_faucet_key_or_create_default(None);
+    let mut swarm = LibraSwarm::builder(faucet_account_keypair)
+        .with_num_nodes(4)
+        .with_faucet()
+        .launch()
+        .unwrap();
+    // The faucet submits through the first validator, remove the last one.
+    let mut validators = swarm.get_validators_ids();
+    validators.sort();
+    let removed = validators.pop().unwrap();
+    swarm.remove_validator(&removed).unwrap();
+    assert!(swarm.get_validator(&removed).is_none());
+    assert_eq!(swarm.get_validators_ids().len(), 3);
+
+    // The remaining validators still commit transactions.
+    let address = AccountAddress::random();
+    swarm.faucet().mint(address, 10_000_000).unwrap();
+}
+
 #[test]
 fn test_basic_restartability() {
     let (mut swarm, mut client_proxy) = setup_swarm_and_client_proxy(4, 0);
@@ -284,7 +331,8 @@ fn test_basic_state_synchronization() {
     assert!(swarm.add_node(node_to_restart.clone(), false).is_ok());
 
     // Wait for all the nodes to catch up
-    assert!(swarm.wait_for_all_nodes_to_catchup());
+    let catchup_report = swarm.wait_for_all_nodes_to_catchup(CatchupTarget::default());
+    assert!(catchup_report.is_success(), "{}", catchup_report);
 
     // Connect to the newly recovered node and verify its state
     let tmp_mnemonic_file = tools::tempdir::TempPath::new();
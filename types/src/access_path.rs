@@ -38,7 +38,7 @@
 use crate::{
     account_address::AccountAddress,
     account_config::{
-        account_resource_path, association_address, ACCOUNT_RECEIVED_EVENT_PATH,
+        account_resource_path, validator_set_address, ACCOUNT_RECEIVED_EVENT_PATH,
         ACCOUNT_SENT_EVENT_PATH,
     },
     identifier::{IdentStr, Identifier},
@@ -197,7 +197,7 @@ impl TrieKey for Accesses {
 lazy_static! {
     /// The access path where the Validator Set resource is stored.
     pub static ref VALIDATOR_SET_ACCESS_PATH: AccessPath =
-        AccessPath::new(association_address(), validator_set_path());
+        AccessPath::new(validator_set_address(), validator_set_path());
 }
 
 #[derive(
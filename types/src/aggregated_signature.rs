@@ -0,0 +1,88 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::proto::ledger_info::AggregatedSignature as ProtoAggregatedSignature;
+use crypto::traits::Signature;
+use failure::prelude::*;
+use proto_conv::{FromProto, IntoProto};
+use serde::{Deserialize, Serialize};
+
+/// A single signature aggregating the signatures of several validators over the same message,
+/// along with the bitmap of its signers: the i-th bit is set if the i-th validator, as ordered by
+/// `ValidatorVerifier::get_ordered_account_addresses`, signed. Its size doesn't depend on the
+/// number of signers, unlike a map of the individual signatures.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct AggregatedSignature<Sig> {
+    signer_bitmap: Vec<u8>,
+    signature: Sig,
+}
+
+impl<Sig> AggregatedSignature<Sig> {
+    pub fn new(signer_bitmap: Vec<u8>, signature: Sig) -> Self {
+        Self {
+            signer_bitmap,
+            signature,
+        }
+    }
+
+    pub fn signer_bitmap(&self) -> &[u8] {
+        &self.signer_bitmap
+    }
+
+    pub fn signature(&self) -> &Sig {
+        &self.signature
+    }
+
+    /// Did the validator at the given index sign?
+    pub fn is_signer(&self, index: usize) -> bool {
+        self.signer_bitmap
+            .get(index / 8)
+            .map_or(false, |byte| byte & (1 << (index % 8)) != 0)
+    }
+
+    pub fn num_of_signers(&self) -> usize {
+        self.signer_bitmap
+            .iter()
+            .map(|byte| byte.count_ones() as usize)
+            .sum()
+    }
+
+    /// Is the bitmap the one of a subset of the given number of validators?
+    pub fn is_bitmap_valid(&self, num_of_validators: usize) -> bool {
+        self.signer_bitmap.len() == bitmap_len(num_of_validators)
+            && (num_of_validators..self.signer_bitmap.len() * 8).all(|index| !self.is_signer(index))
+    }
+}
+
+/// Builds the bitmap of the signers among the given number of validators from their indices.
+pub fn signer_bitmap(num_of_validators: usize, signers: impl Iterator<Item = usize>) -> Vec<u8> {
+    let mut bitmap = vec![0; bitmap_len(num_of_validators)];
+    for index in signers {
+        bitmap[index / 8] |= 1 << (index % 8);
+    }
+    bitmap
+}
+
+fn bitmap_len(num_of_validators: usize) -> usize {
+    (num_of_validators + 7) / 8
+}
+
+impl<Sig: Signature> FromProto for AggregatedSignature<Sig> {
+    type ProtoType = ProtoAggregatedSignature;
+
+    fn from_proto(mut proto: Self::ProtoType) -> Result<Self> {
+        let signature = Sig::try_from(proto.get_signature())?;
+        Ok(Self::new(proto.take_signer_bitmap(), signature))
+    }
+}
+
+impl<Sig: Signature> IntoProto for AggregatedSignature<Sig> {
+    type ProtoType = ProtoAggregatedSignature;
+
+    fn into_proto(self) -> Self::ProtoType {
+        let mut proto = Self::ProtoType::new();
+        proto.set_signer_bitmap(self.signer_bitmap);
+        proto.set_signature(self.signature.to_bytes());
+        proto
+    }
+}
@@ -21,6 +21,7 @@
 
 use crate::{
     account_address::AccountAddress,
+    aggregated_signature::AggregatedSignature as RawAggregatedSignature,
     ledger_info::LedgerInfoWithSignatures as RawLedgerInfoWithSignatures,
     validator_change::ValidatorChangeEventWithProof as RawValidatorChangeEventWithProof,
     validator_signer::ValidatorSigner as RawValidatorSigner,
@@ -65,6 +66,58 @@ impl<Sig: RawSignature> From<Sig> for SignatureWrapper<Sig> {
     }
 }
 
+/// Identifies the signature scheme of a validator set. The identifier is carried by the
+/// consensus messages that contain signatures (e.g., votes and quorum certificates), such that a
+/// validator set can move to another scheme (e.g., multi-signatures or threshold signatures) at
+/// an epoch boundary, and the messages signed with a scheme other than the one of the validator
+/// set are rejected rather than failing the signature verification.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Hash, Clone, Copy, Debug)]
+pub enum SignatureScheme {
+    Ed25519,
+    /// BLS signatures over the BLS12-381 curve, which can be aggregated: a quorum certificate
+    /// carries a single aggregated signature and the bitmap of its signers.
+    BLS12381,
+}
+
+impl SignatureScheme {
+    /// The identifier of the scheme on the wire. Ed25519 is 0, i.e., the default value of the
+    /// messages that don't carry an identifier.
+    pub fn id(self) -> u32 {
+        match self {
+            SignatureScheme::Ed25519 => 0,
+            SignatureScheme::BLS12381 => 1,
+        }
+    }
+
+    pub fn from_id(id: u32) -> Option<Self> {
+        match id {
+            0 => Some(SignatureScheme::Ed25519),
+            1 => Some(SignatureScheme::BLS12381),
+            _ => None,
+        }
+    }
+
+    /// Whether the signatures of a quorum certificate can be aggregated into a single signature.
+    pub fn supports_aggregation(self) -> bool {
+        match self {
+            SignatureScheme::Ed25519 => false,
+            SignatureScheme::BLS12381 => true,
+        }
+    }
+}
+
+impl Default for SignatureScheme {
+    fn default() -> Self {
+        SIGNATURE_SCHEME
+    }
+}
+
+impl std::fmt::Display for SignatureScheme {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
 // This sets the types containing cryptographic materials used in the
 // consensus crate. It is intended as a one-stop shop for changing the
 // signing scheme of the consensus. If consensus uses a type that
@@ -75,14 +128,23 @@ impl<Sig: RawSignature> From<Sig> for SignatureWrapper<Sig> {
 // present anywhere in the consensus crate. Use of raw cryptographic
 // types that do not go through the instantiated polymorphic structures
 // below is banned.
+//
+// `SIGNATURE_SCHEME` must identify the scheme of the instantiations below. Switching to a scheme
+// that supports aggregation (e.g., `crypto::bls12381`) lets the quorum certificates carry an
+// `AggregatedSignature` rather than one signature per voter.
 
 use crypto::ed25519::*;
 
+/// The scheme the consensus signs and verifies with.
+pub const SIGNATURE_SCHEME: SignatureScheme = SignatureScheme::Ed25519;
+
 // used in chained_bft::consensus_types::block_test
 #[cfg(any(test, feature = "testing"))]
 pub type SecretKey = Ed25519PrivateKey;
 
+pub type PublicKey = Ed25519PublicKey;
 pub type Signature = SignatureWrapper<Ed25519Signature>;
+pub type AggregatedSignature = RawAggregatedSignature<Ed25519Signature>;
 pub type LedgerInfoWithSignatures = RawLedgerInfoWithSignatures<Ed25519Signature>;
 pub type ValidatorVerifier = RawValidatorVerifier<Ed25519PublicKey>;
 pub type ValidatorSigner = RawValidatorSigner<Ed25519PrivateKey>;
@@ -3,6 +3,7 @@
 
 use crate::{
     account_address::AccountAddress,
+    aggregated_signature::AggregatedSignature,
     transaction::Version,
     validator_set::ValidatorSet,
     validator_verifier::{ValidatorVerifier, VerifyError},
@@ -235,6 +236,10 @@ pub struct LedgerInfoWithSignatures<Sig> {
     /// The validator is identified by its account address: in order to verify a signature
     /// one needs to retrieve the public key of the validator for the given epoch.
     signatures: HashMap<AccountAddress, Sig>,
+    /// A single signature aggregating the signatures of the validators, which replaces the
+    /// individual signatures when the signature scheme supports aggregation.
+    #[serde(default)]
+    aggregated_signature: Option<AggregatedSignature<Sig>>,
 }
 
 impl<Sig> Display for LedgerInfoWithSignatures<Sig> {
@@ -248,6 +253,7 @@ impl<Sig: Signature> LedgerInfoWithSignatures<Sig> {
         LedgerInfoWithSignatures {
             ledger_info,
             signatures,
+            aggregated_signature: None,
         }
     }
 
@@ -267,6 +273,25 @@ impl<Sig: Signature> LedgerInfoWithSignatures<Sig> {
         &self.signatures
     }
 
+    pub fn aggregated_signature(&self) -> Option<&AggregatedSignature<Sig>> {
+        self.aggregated_signature.as_ref()
+    }
+
+    /// Replaces the individual signatures with their aggregation, which fails unless the
+    /// signature scheme supports aggregation.
+    pub fn aggregate_signatures(
+        &mut self,
+        validator: &ValidatorVerifier<Sig::VerifyingKeyMaterial>,
+    ) -> Result<()> {
+        ensure!(
+            self.aggregated_signature.is_none(),
+            "The signatures are already aggregated"
+        );
+        self.aggregated_signature = Some(validator.aggregate_signatures(&self.signatures)?);
+        self.signatures.clear();
+        Ok(())
+    }
+
     pub fn verify(
         &self,
         validator: &ValidatorVerifier<Sig::VerifyingKeyMaterial>,
@@ -276,7 +301,15 @@ impl<Sig: Signature> LedgerInfoWithSignatures<Sig> {
             return Ok(());
         }
         let ledger_hash = self.ledger_info().hash();
-        validator.batch_verify_aggregated_signature(ledger_hash, self.signatures())
+        match &self.aggregated_signature {
+            // The individual signatures are not expected along with the aggregated one, which
+            // prevents adding content that would go unnoticed.
+            Some(_) if !self.signatures.is_empty() => Err(VerifyError::InvalidSignature),
+            Some(aggregated_signature) => {
+                validator.verify_aggregate(ledger_hash, aggregated_signature)
+            }
+            None => validator.batch_verify_aggregated_signature(ledger_hash, self.signatures()),
+        }
     }
 }
 
@@ -301,10 +334,22 @@ impl<Sig: Signature> FromProto for LedgerInfoWithSignatures<Sig> {
             signatures.len() == num_signatures,
             "Signatures should be from different validators."
         );
+        let aggregated_signature = if proto.has_aggregated_signature() {
+            ensure!(
+                signatures.is_empty(),
+                "An aggregated signature replaces the individual signatures."
+            );
+            Some(AggregatedSignature::from_proto(
+                proto.take_aggregated_signature(),
+            )?)
+        } else {
+            None
+        };
 
         Ok(LedgerInfoWithSignatures {
             ledger_info,
             signatures,
+            aggregated_signature,
         })
     }
 }
@@ -323,6 +368,9 @@ impl<Sig: Signature> IntoProto for LedgerInfoWithSignatures<Sig> {
                 validator_signature.set_signature(signature.to_bytes().to_vec());
                 proto.mut_signatures().push(validator_signature)
             });
+        if let Some(aggregated_signature) = self.aggregated_signature {
+            proto.set_aggregated_signature(aggregated_signature.into_proto());
+        }
         proto
     }
 }
@@ -4,6 +4,7 @@
 pub mod access_path;
 pub mod account_address;
 pub mod account_config;
+pub mod aggregated_signature;
 pub mod account_state_blob;
 pub mod byte_array;
 pub mod contract_event;
@@ -0,0 +1,234 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    account_address::AccountAddress,
+    aggregated_signature::AggregatedSignature,
+    ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
+    validator_verifier::{ValidatorVerifier, VerifyError},
+};
+use crypto::{
+    bls12381::{BLS12381PrivateKey, BLS12381PublicKey, BLS12381Signature},
+    ed25519::{Ed25519PrivateKey, Ed25519PublicKey},
+    hash::{CryptoHash, ACCUMULATOR_PLACEHOLDER_HASH},
+    HashValue, SigningKey, Uniform,
+};
+use proto_conv::test_helper::assert_protobuf_encode_decode;
+use rand::{rngs::StdRng, SeedableRng};
+use std::collections::HashMap;
+
+fn bls_validators(
+    num_of_validators: usize,
+) -> (
+    Vec<(AccountAddress, BLS12381PrivateKey)>,
+    ValidatorVerifier<BLS12381PublicKey>,
+) {
+    let mut rng = StdRng::from_seed([0u8; 32]);
+    let signers: Vec<_> = (0..num_of_validators)
+        .map(|_| {
+            (
+                AccountAddress::random(),
+                BLS12381PrivateKey::generate_for_testing(&mut rng),
+            )
+        })
+        .collect();
+    let verifier = ValidatorVerifier::new(
+        signers
+            .iter()
+            .map(|(author, private_key)| (*author, private_key.into()))
+            .collect(),
+    );
+    (signers, verifier)
+}
+
+fn sign(
+    signers: &[(AccountAddress, BLS12381PrivateKey)],
+    hash: HashValue,
+) -> HashMap<AccountAddress, BLS12381Signature> {
+    signers
+        .iter()
+        .map(|(author, private_key)| (*author, private_key.sign_message(&hash)))
+        .collect()
+}
+
+#[test]
+fn test_verify_aggregate() {
+    let (signers, verifier) = bls_validators(4);
+    let hash = HashValue::random();
+
+    let aggregated_signature = verifier
+        .aggregate_signatures(&sign(&signers[..3], hash))
+        .unwrap();
+    assert_eq!(aggregated_signature.num_of_signers(), 3);
+    assert_eq!(aggregated_signature.signer_bitmap().len(), 1);
+    assert_eq!(
+        verifier.verify_aggregate(hash, &aggregated_signature),
+        Ok(())
+    );
+    assert_eq!(
+        verifier.verify_aggregate(HashValue::random(), &aggregated_signature),
+        Err(VerifyError::InvalidSignature)
+    );
+
+    // The signers must form a quorum.
+    let aggregated_signature = verifier
+        .aggregate_signatures(&sign(&signers[..2], hash))
+        .unwrap();
+    assert_eq!(
+        verifier.verify_aggregate(hash, &aggregated_signature),
+        Err(VerifyError::TooFewSignatures {
+            num_of_signatures: 2,
+            quorum_size: 3
+        })
+    );
+}
+
+#[test]
+fn test_verify_aggregate_tampered_bitmap() {
+    let (signers, verifier) = bls_validators(4);
+    let hash = HashValue::random();
+    let aggregated_signature = verifier
+        .aggregate_signatures(&sign(&signers[..3], hash))
+        .unwrap();
+
+    // Claiming all the validators signed doesn't match the aggregated signature.
+    let all_signers =
+        AggregatedSignature::new(vec![0b1111], aggregated_signature.signature().clone());
+    assert_eq!(
+        verifier.verify_aggregate(hash, &all_signers),
+        Err(VerifyError::InvalidSignature)
+    );
+
+    // The bitmap can't refer to validators past the last one.
+    let unknown_signer = AggregatedSignature::new(
+        vec![aggregated_signature.signer_bitmap()[0] | 0b1_0000],
+        aggregated_signature.signature().clone(),
+    );
+    assert_eq!(
+        verifier.verify_aggregate(hash, &unknown_signer),
+        Err(VerifyError::InvalidSignerBitmap)
+    );
+    let longer_bitmap = AggregatedSignature::new(
+        vec![aggregated_signature.signer_bitmap()[0], 0],
+        aggregated_signature.signature().clone(),
+    );
+    assert_eq!(
+        verifier.verify_aggregate(hash, &longer_bitmap),
+        Err(VerifyError::InvalidSignerBitmap)
+    );
+}
+
+#[test]
+fn test_verify_aggregate_with_rotated_keys() {
+    let (signers, verifier) = bls_validators(4);
+    let hash = HashValue::random();
+    // The keys of the first two validators are handed over at round 10 and the replaced keys
+    // are retired at round 13.
+    let mut rng = StdRng::from_seed([1u8; 32]);
+    let new_signers: Vec<_> = signers[..2]
+        .iter()
+        .map(|(author, _)| (*author, BLS12381PrivateKey::generate_for_testing(&mut rng)))
+        .collect();
+    let mut rotated = verifier;
+    for (author, private_key) in &new_signers {
+        rotated = rotated
+            .with_rotated_key(*author, private_key.into(), 10, 13)
+            .unwrap();
+    }
+
+    let aggregate = |new_keys: &[(AccountAddress, BLS12381PrivateKey)],
+                     old_keys: &[(AccountAddress, BLS12381PrivateKey)]| {
+        let mut signatures = sign(new_keys, hash);
+        signatures.extend(sign(old_keys, hash));
+        rotated.aggregate_signatures(&signatures).unwrap()
+    };
+    let old_keys = aggregate(&[], &signers[..3]);
+    let new_keys = aggregate(&new_signers, &signers[2..3]);
+    // The first validator signs under its new key while the second one still uses its old key.
+    let mixed_keys = aggregate(&new_signers[..1], &signers[1..3]);
+
+    let before_handover = rotated.at_round(9);
+    assert_eq!(before_handover.verify_aggregate(hash, &old_keys), Ok(()));
+    assert_eq!(
+        before_handover.verify_aggregate(hash, &new_keys),
+        Err(VerifyError::InvalidSignature)
+    );
+    assert_eq!(
+        before_handover.verify_aggregate(hash, &mixed_keys),
+        Err(VerifyError::InvalidSignature)
+    );
+    // Any combination of the keys is accepted until the retirement round.
+    for round in 10..13 {
+        let after_handover = rotated.at_round(round);
+        for aggregated_signature in &[&old_keys, &new_keys, &mixed_keys] {
+            assert_eq!(
+                after_handover.verify_aggregate(hash, aggregated_signature),
+                Ok(())
+            );
+        }
+        assert_eq!(
+            after_handover.verify_aggregate(HashValue::random(), &mixed_keys),
+            Err(VerifyError::InvalidSignature)
+        );
+    }
+    let retired = rotated.at_round(13);
+    assert_eq!(retired.verify_aggregate(hash, &new_keys), Ok(()));
+    assert_eq!(
+        retired.verify_aggregate(hash, &old_keys),
+        Err(VerifyError::InvalidSignature)
+    );
+    assert_eq!(
+        retired.verify_aggregate(hash, &mixed_keys),
+        Err(VerifyError::InvalidSignature)
+    );
+}
+
+#[test]
+fn test_ledger_info_with_aggregated_signature() {
+    let (signers, verifier) = bls_validators(4);
+    let ledger_info = LedgerInfo::new(
+        1,
+        *ACCUMULATOR_PLACEHOLDER_HASH,
+        HashValue::random(),
+        HashValue::random(),
+        0,
+        0,
+        None,
+    );
+    let mut ledger_info_with_signatures =
+        LedgerInfoWithSignatures::new(ledger_info.clone(), sign(&signers, ledger_info.hash()));
+    ledger_info_with_signatures
+        .aggregate_signatures(&verifier)
+        .unwrap();
+    assert!(ledger_info_with_signatures.signatures().is_empty());
+    assert_eq!(
+        ledger_info_with_signatures
+            .aggregated_signature()
+            .unwrap()
+            .num_of_signers(),
+        4
+    );
+    assert_eq!(ledger_info_with_signatures.verify(&verifier), Ok(()));
+    assert_protobuf_encode_decode(&ledger_info_with_signatures);
+
+    // Individual signatures along with the aggregated one are rejected.
+    let (author, private_key) = &signers[0];
+    ledger_info_with_signatures
+        .add_signature(*author, private_key.sign_message(&ledger_info.hash()));
+    assert_eq!(
+        ledger_info_with_signatures.verify(&verifier),
+        Err(VerifyError::InvalidSignature)
+    );
+}
+
+#[test]
+fn test_aggregation_not_supported() {
+    let mut rng = StdRng::from_seed([0u8; 32]);
+    let private_key = Ed25519PrivateKey::generate_for_testing(&mut rng);
+    let public_key: Ed25519PublicKey = (&private_key).into();
+    let author = AccountAddress::random();
+    let verifier = ValidatorVerifier::new_single(author, public_key);
+    let mut signatures = HashMap::new();
+    signatures.insert(author, private_key.sign_message(&HashValue::random()));
+    assert!(verifier.aggregate_signatures(&signatures).is_err());
+}
@@ -5,12 +5,14 @@ use crate::account_address::{AccountAddress, ADDRESS_LENGTH};
 use crypto::{test_utils::TEST_SEED, HashValue, *};
 use failure::Error;
 use rand::{rngs::StdRng, SeedableRng};
+use serde::{Deserialize, Serialize};
 use std::convert::TryFrom;
 
 /// ValidatorSigner associates an author with public and private keys with helpers for signing and
 /// validating. This struct can be used for all signing operations including block and network
-/// signing, respectively.
-#[derive(Debug)]
+/// signing, respectively. The signer is serializable such that the consensus can persist a rotated
+/// consensus key.
+#[derive(Debug, Deserialize, Serialize)]
 #[cfg_attr(any(test, feature = "testing"), derive(Clone))]
 pub struct ValidatorSigner<PrivateKey: SigningKey> {
     author: AccountAddress,
@@ -1,11 +1,15 @@
 // Copyright (c) The Libra Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::account_address::AccountAddress;
-use crypto::*;
+use crate::{
+    account_address::AccountAddress,
+    aggregated_signature::{signer_bitmap, AggregatedSignature},
+    validator_set::ValidatorSet,
+};
+use crypto::{ed25519::Ed25519PublicKey, *};
 use failure::prelude::*;
 use logger::prelude::*;
-use std::collections::HashMap;
+use std::{borrow::Cow, collections::HashMap};
 
 /// Errors possible during signature verification.
 #[derive(Debug, Fail, PartialEq)]
@@ -32,6 +36,9 @@ pub enum VerifyError {
     #[fail(display = "Signature is invalid")]
     /// The signature does not match the hash.
     InvalidSignature,
+    #[fail(display = "The signer bitmap does not match the authors")]
+    /// The bitmap of the signers of an aggregated signature refers to unknown authors.
+    InvalidSignerBitmap,
 }
 
 /// Supports validation of signatures for known authors. This struct can be used for all signature
@@ -39,9 +46,30 @@ pub enum VerifyError {
 #[derive(Clone)]
 pub struct ValidatorVerifier<P> {
     author_to_public_keys: HashMap<AccountAddress, P>,
+    // The authors missing from the map have the default voting power.
+    author_to_voting_power: HashMap<AccountAddress, u64>,
+    // The keys replaced by a key rotation whose signatures are still accepted, only set in the
+    // verifiers derived for a round (see `at_round`).
+    author_to_previous_public_keys: HashMap<AccountAddress, P>,
+    // The keys replaced by the key rotations within the epoch, ordered by handover round.
+    author_to_rotated_keys: HashMap<AccountAddress, Vec<RotatedKey<P>>>,
     quorum_size: usize,
 }
 
+/// A public key replaced by a key rotation within the epoch.
+#[derive(Clone)]
+struct RotatedKey<P> {
+    // The replaced key, which verifies the signatures of the rounds before the handover round.
+    public_key: P,
+    // The first round whose signatures are verified under the new key.
+    handover_round: u64,
+    // The first round whose signatures under the replaced key are rejected.
+    retirement_round: u64,
+}
+
+/// The voting power of the authors whose voting power is not specified.
+pub const DEFAULT_VOTING_POWER: u64 = 1;
+
 impl<PublicKey: VerifyingKey> ValidatorVerifier<PublicKey> {
     /// Initialize with a map of author to public key and set quorum size to default (`2f + 1`) or
     /// zero if `author_to_public_keys` is empty.
@@ -53,6 +81,9 @@ impl<PublicKey: VerifyingKey> ValidatorVerifier<PublicKey> {
         };
         ValidatorVerifier {
             author_to_public_keys,
+            author_to_voting_power: HashMap::new(),
+            author_to_previous_public_keys: HashMap::new(),
+            author_to_rotated_keys: HashMap::new(),
             quorum_size,
         }
     }
@@ -71,10 +102,37 @@ impl<PublicKey: VerifyingKey> ValidatorVerifier<PublicKey> {
         );
         Ok(ValidatorVerifier {
             author_to_public_keys,
+            author_to_voting_power: HashMap::new(),
+            author_to_previous_public_keys: HashMap::new(),
+            author_to_rotated_keys: HashMap::new(),
             quorum_size,
         })
     }
 
+    /// Initializes a validator verifier with the default quorum size and the given voting power
+    /// of the authors. Note that the voting power doesn't affect the quorum, which is still
+    /// counted in number of signatures.
+    pub fn new_with_voting_power(
+        author_to_public_keys: HashMap<AccountAddress, PublicKey>,
+        author_to_voting_power: HashMap<AccountAddress, u64>,
+    ) -> Result<Self> {
+        for (author, voting_power) in &author_to_voting_power {
+            ensure!(
+                author_to_public_keys.contains_key(author),
+                "Voting power of an unknown author: {}",
+                author
+            );
+            ensure!(
+                *voting_power > 0,
+                "The voting power of author {} is zero",
+                author
+            );
+        }
+        let mut validator = Self::new(author_to_public_keys);
+        validator.author_to_voting_power = author_to_voting_power;
+        Ok(validator)
+    }
+
     /// Helper method to initialize with a single author and public key.
     pub fn new_single(author: AccountAddress, public_key: PublicKey) -> Self {
         let mut author_to_public_keys = HashMap::new();
@@ -82,7 +140,86 @@ impl<PublicKey: VerifyingKey> ValidatorVerifier<PublicKey> {
         Self::new(author_to_public_keys)
     }
 
-    /// Verify the correctness of a signature of a hash by a known author.
+    /// Returns a copy of the verifier in which the public key of the given author is replaced by
+    /// the new one from the handover round on. The replaced key still verifies the signatures of
+    /// the rounds before the handover round, as well as the signatures of the rounds before the
+    /// retirement round (e.g., the messages in flight at the handover). The handover rounds of
+    /// the rotations of an author must increase.
+    /// The verifier itself verifies the signatures under the latest keys, see `at_round` for the
+    /// verifier of the signatures of a given round.
+    pub fn with_rotated_key(
+        &self,
+        author: AccountAddress,
+        new_public_key: PublicKey,
+        handover_round: u64,
+        retirement_round: u64,
+    ) -> Result<Self> {
+        ensure!(
+            handover_round <= retirement_round,
+            "The key of {} is retired at round {} before its handover at round {}",
+            author,
+            retirement_round,
+            handover_round
+        );
+        if let Some(last_rotated_key) = self
+            .author_to_rotated_keys
+            .get(&author)
+            .and_then(|rotated_keys| rotated_keys.last())
+        {
+            ensure!(
+                last_rotated_key.handover_round < handover_round,
+                "The key of {} is already rotated at round {}, after round {}",
+                author,
+                last_rotated_key.handover_round,
+                handover_round
+            );
+        }
+        let mut validator = self.clone();
+        let previous_public_key = match validator.author_to_public_keys.get_mut(&author) {
+            Some(public_key) => std::mem::replace(public_key, new_public_key),
+            None => bail!("Key rotation of an unknown author: {}", author),
+        };
+        validator
+            .author_to_rotated_keys
+            .entry(author)
+            .or_insert_with(Vec::new)
+            .push(RotatedKey {
+                public_key: previous_public_key,
+                handover_round,
+                retirement_round,
+            });
+        Ok(validator)
+    }
+
+    /// The verifier of the signatures of the given round: the key of every author is the key
+    /// valid at this round, and the key replaced by a rotation is still accepted until its
+    /// retirement round. The verifier is borrowed if no key has been rotated.
+    pub fn at_round(&self, round: u64) -> Cow<Self> {
+        if self.author_to_rotated_keys.is_empty() {
+            return Cow::Borrowed(self);
+        }
+        let mut validator = self.clone();
+        validator.author_to_rotated_keys.clear();
+        for (author, rotated_keys) in &self.author_to_rotated_keys {
+            for rotated_key in rotated_keys.iter().rev() {
+                if round >= rotated_key.handover_round {
+                    if round < rotated_key.retirement_round {
+                        validator
+                            .author_to_previous_public_keys
+                            .insert(*author, rotated_key.public_key.clone());
+                    }
+                    break;
+                }
+                validator
+                    .author_to_public_keys
+                    .insert(*author, rotated_key.public_key.clone());
+            }
+        }
+        Cow::Owned(validator)
+    }
+
+    /// Verify the correctness of a signature of a hash by a known author, under its current key
+    /// or, for a verifier derived for a round right after a key rotation, the replaced key.
     pub fn verify_signature(
         &self,
         author: AccountAddress,
@@ -93,10 +230,18 @@ impl<PublicKey: VerifyingKey> ValidatorVerifier<PublicKey> {
         match public_key {
             None => Err(VerifyError::UnknownAuthor),
             Some(public_key) => {
-                if public_key.verify_signature(&hash, signature).is_err() {
-                    Err(VerifyError::InvalidSignature)
-                } else {
-                    Ok(())
+                if public_key.verify_signature(&hash, signature).is_ok() {
+                    return Ok(());
+                }
+                match self.author_to_previous_public_keys.get(&author) {
+                    Some(previous_public_key)
+                        if previous_public_key
+                            .verify_signature(&hash, signature)
+                            .is_ok() =>
+                    {
+                        Ok(())
+                    }
+                    _ => Err(VerifyError::InvalidSignature),
                 }
             }
         }
@@ -133,32 +278,153 @@ impl<PublicKey: VerifyingKey> ValidatorVerifier<PublicKey> {
         T: Into<PublicKey::SignatureMaterial> + Clone,
     {
         self.check_num_of_signatures(aggregated_signature)?;
-        self.check_keys(aggregated_signature)?;
-        let keys_and_signatures: Vec<(PublicKey, PublicKey::SignatureMaterial)> =
-            aggregated_signature
-                .iter()
-                .flat_map(|(author, signature)| {
-                    let sig: PublicKey::SignatureMaterial = signature.clone().into();
-                    self.author_to_public_keys
-                        .get(&author)
-                        .map(|pub_key| (pub_key.clone(), sig))
-                })
-                .collect();
+        self.batch_verify(hash, aggregated_signature)
+    }
+
+    /// Verifies the signatures of known authors over the same hash in one batched operation.
+    /// Unlike `batch_verify_aggregated_signature`, the number of signatures is not checked against
+    /// the quorum size, which makes it suitable for verifying a set of individual votes.
+    pub fn batch_verify<T>(
+        &self,
+        hash: HashValue,
+        signatures: &HashMap<AccountAddress, T>,
+    ) -> std::result::Result<(), VerifyError>
+    where
+        T: Into<PublicKey::SignatureMaterial> + Clone,
+    {
+        self.check_keys(signatures)?;
+        let keys_and_signatures: Vec<(PublicKey, PublicKey::SignatureMaterial)> = signatures
+            .iter()
+            .flat_map(|(author, signature)| {
+                let sig: PublicKey::SignatureMaterial = signature.clone().into();
+                self.author_to_public_keys
+                    .get(&author)
+                    .map(|pub_key| (pub_key.clone(), sig))
+            })
+            .collect();
         // Fallback is required to identify the source of the problem if batching fails.
         if PublicKey::batch_verify_signatures(&hash, keys_and_signatures).is_err() {
-            let iterated_verification =
-                self.verify_aggregated_signature(hash, aggregated_signature);
-            match iterated_verification {
-                Ok(_) => warn!(
+            for (author, signature) in signatures {
+                self.verify_signature(*author, hash, &signature.clone().into())?;
+            }
+            // The batch only covers the current keys: a signature under a key replaced by a
+            // rotation that is not retired yet is expected to fail it.
+            if self.author_to_previous_public_keys.is_empty() {
+                warn!(
                     "Inconsistency between batch and iterative signature verification detected! \
                      Batch verification failed, while iterative passed."
-                ),
-                Err(err) => return Err(err),
+                );
             }
         }
         Ok(())
     }
 
+    /// Aggregates the signatures of known authors over the same hash into a single signature
+    /// along with the bitmap of its signers, e.g., for a quorum certificate whose size doesn't
+    /// grow with the number of signers. Fails unless the signature scheme supports aggregation.
+    pub fn aggregate_signatures<T>(
+        &self,
+        signatures: &HashMap<AccountAddress, T>,
+    ) -> Result<AggregatedSignature<PublicKey::SignatureMaterial>>
+    where
+        T: Into<PublicKey::SignatureMaterial> + Clone,
+    {
+        self.check_keys(signatures)?;
+        let authors = self.get_ordered_account_addresses();
+        let mut signer_indices = vec![];
+        let mut signers_signatures: Vec<PublicKey::SignatureMaterial> = vec![];
+        for (index, author) in authors.iter().enumerate() {
+            if let Some(signature) = signatures.get(author) {
+                signer_indices.push(index);
+                signers_signatures.push(signature.clone().into());
+            }
+        }
+        let signature =
+            PublicKey::SignatureMaterial::aggregate(signers_signatures.iter().collect())?;
+        Ok(AggregatedSignature::new(
+            signer_bitmap(authors.len(), signer_indices.into_iter()),
+            signature,
+        ))
+    }
+
+    /// This function will successfully return when the signers of the aggregated signature are
+    /// at least quorum_size known authors, and the signature is valid for the aggregation of
+    /// their public keys. As with `verify_signature`, a signer may have signed under its current
+    /// key or, for a verifier derived for a round right after a key rotation, the replaced key.
+    pub fn verify_aggregate(
+        &self,
+        hash: HashValue,
+        aggregated_signature: &AggregatedSignature<PublicKey::SignatureMaterial>,
+    ) -> std::result::Result<(), VerifyError> {
+        let authors = self.get_ordered_account_addresses();
+        if !aggregated_signature.is_bitmap_valid(authors.len()) {
+            return Err(VerifyError::InvalidSignerBitmap);
+        }
+        let num_of_signatures = aggregated_signature.num_of_signers();
+        if num_of_signatures < self.quorum_size {
+            return Err(VerifyError::TooFewSignatures {
+                num_of_signatures,
+                quorum_size: self.quorum_size,
+            });
+        }
+        // The current key of every signer, along with the replaced key still accepted if any.
+        let signer_keys: Vec<(&PublicKey, Option<&PublicKey>)> = authors
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| aggregated_signature.is_signer(*index))
+            .flat_map(|(_, author)| {
+                self.author_to_public_keys
+                    .get(author)
+                    .map(|key| (key, self.author_to_previous_public_keys.get(author)))
+            })
+            .collect();
+        // Every combination of the keys of the signers with a replaced key is tried, there are
+        // few of them as the keys are rotated one at a time.
+        let num_rotated = signer_keys
+            .iter()
+            .filter(|(_, previous_key)| previous_key.is_some())
+            .count();
+        for combination in 0..1u64 << num_rotated {
+            let mut rotated_index = 0;
+            let public_keys: Vec<&PublicKey> = signer_keys
+                .iter()
+                .map(|(key, previous_key)| match previous_key {
+                    Some(previous_key) => {
+                        let use_previous_key = (combination >> rotated_index) & 1 == 1;
+                        rotated_index += 1;
+                        if use_previous_key {
+                            *previous_key
+                        } else {
+                            *key
+                        }
+                    }
+                    None => *key,
+                })
+                .collect();
+            if Self::verify_aggregate_under(hash, aggregated_signature, public_keys) {
+                return Ok(());
+            }
+        }
+        Err(VerifyError::InvalidSignature)
+    }
+
+    // Whether the aggregated signature is valid for the aggregation of the given public keys.
+    fn verify_aggregate_under(
+        hash: HashValue,
+        aggregated_signature: &AggregatedSignature<PublicKey::SignatureMaterial>,
+        public_keys: Vec<&PublicKey>,
+    ) -> bool {
+        PublicKey::SignatureMaterial::aggregate_public_keys(public_keys).map_or(
+            false,
+            |public_key| {
+                aggregated_signature
+                    .signature()
+                    .verify(&hash, &public_key)
+                    .is_ok()
+            },
+        )
+    }
+
     /// Ensure there are at least quorum_size and not more than maximum expected signatures.
     fn check_num_of_signatures<T>(
         &self,
@@ -205,6 +471,19 @@ impl<PublicKey: VerifyingKey> ValidatorVerifier<PublicKey> {
         self.author_to_public_keys.get(&author).cloned()
     }
 
+    /// Return the voting power of this address, None for an unknown author.
+    pub fn get_voting_power(&self, author: AccountAddress) -> Option<u64> {
+        if !self.author_to_public_keys.contains_key(&author) {
+            return None;
+        }
+        Some(
+            self.author_to_voting_power
+                .get(&author)
+                .cloned()
+                .unwrap_or(DEFAULT_VOTING_POWER),
+        )
+    }
+
     /// Returns a ordered list of account addresses from smallest to largest.
     pub fn get_ordered_account_addresses(&self) -> Vec<AccountAddress> {
         let mut account_addresses: Vec<AccountAddress> =
@@ -229,14 +508,39 @@ impl<PublicKey: VerifyingKey> ValidatorVerifier<PublicKey> {
     }
 }
 
+impl ValidatorVerifier<Ed25519PublicKey> {
+    /// Returns the validator verifier of the consensus keys of the given validator set, e.g., the
+    /// validator set of a new epoch. The validator set doesn't carry the voting power: the
+    /// validators of this verifier keep their voting power, while the new validators get the
+    /// default voting power.
+    pub fn with_validator_set(&self, validator_set: &ValidatorSet) -> Self {
+        let author_to_public_keys: HashMap<_, _> = validator_set
+            .payload()
+            .iter()
+            .map(|keys| (*keys.account_address(), keys.consensus_public_key().clone()))
+            .collect();
+        let author_to_voting_power = self
+            .author_to_voting_power
+            .iter()
+            .filter(|(author, _)| author_to_public_keys.contains_key(author))
+            .map(|(author, voting_power)| (*author, *voting_power))
+            .collect();
+        let mut validator = Self::new(author_to_public_keys);
+        validator.author_to_voting_power = author_to_voting_power;
+        validator
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::{
         account_address::AccountAddress,
+        validator_public_keys::ValidatorPublicKeys,
+        validator_set::ValidatorSet,
         validator_signer::ValidatorSigner,
-        validator_verifier::{ValidatorVerifier, VerifyError},
+        validator_verifier::{ValidatorVerifier, VerifyError, DEFAULT_VOTING_POWER},
     };
-    use crypto::{ed25519::*, test_utils::TEST_SEED, HashValue};
+    use crypto::{ed25519::*, test_utils::TEST_SEED, x25519, HashValue};
     use std::collections::HashMap;
 
     #[test]
@@ -370,4 +674,267 @@ mod tests {
             Err(VerifyError::UnknownAuthor)
         );
     }
+
+    #[test]
+    fn test_batch_verify() {
+        let validator_signers: Vec<ValidatorSigner<Ed25519PrivateKey>> =
+            (0..4).map(|i| ValidatorSigner::random([i; 32])).collect();
+        let random_hash = HashValue::random();
+        let validator_verifier = ValidatorVerifier::new(
+            validator_signers
+                .iter()
+                .map(|signer| (signer.author(), signer.public_key()))
+                .collect(),
+        );
+
+        // The quorum size does not matter for batch verification.
+        let mut author_to_signature_map: HashMap<AccountAddress, Ed25519Signature> =
+            validator_signers
+                .iter()
+                .take(2)
+                .map(|signer| (signer.author(), signer.sign_message(random_hash).unwrap()))
+                .collect();
+        assert_eq!(
+            validator_verifier.batch_verify(random_hash, &author_to_signature_map),
+            Ok(())
+        );
+
+        // A signature over a different hash makes the whole batch invalid.
+        author_to_signature_map.insert(
+            validator_signers[2].author(),
+            validator_signers[2]
+                .sign_message(HashValue::random())
+                .unwrap(),
+        );
+        assert_eq!(
+            validator_verifier.batch_verify(random_hash, &author_to_signature_map),
+            Err(VerifyError::InvalidSignature)
+        );
+
+        // Unknown authors are rejected.
+        let unknown_validator_signer = ValidatorSigner::<Ed25519PrivateKey>::random([4; 32]);
+        let mut unknown_signature_map = HashMap::new();
+        unknown_signature_map.insert(
+            unknown_validator_signer.author(),
+            unknown_validator_signer.sign_message(random_hash).unwrap(),
+        );
+        assert_eq!(
+            validator_verifier.batch_verify(random_hash, &unknown_signature_map),
+            Err(VerifyError::UnknownAuthor)
+        );
+    }
+
+    #[test]
+    fn test_rotated_key() {
+        let old_signer = ValidatorSigner::<Ed25519PrivateKey>::random([0; 32]);
+        let new_signer =
+            ValidatorSigner::new(old_signer.author(), compat::generate_keypair(None).0);
+        let validator = ValidatorVerifier::new_single(old_signer.author(), old_signer.public_key());
+        let random_hash = HashValue::random();
+        let old_signature = old_signer.sign_message(random_hash).unwrap();
+        let new_signature = new_signer.sign_message(random_hash).unwrap();
+        assert_eq!(
+            validator.verify_signature(new_signer.author(), random_hash, &new_signature),
+            Err(VerifyError::InvalidSignature)
+        );
+
+        // The key is handed over at round 10 and the replaced key is retired at round 13.
+        let rotated = validator
+            .with_rotated_key(new_signer.author(), new_signer.public_key(), 10, 13)
+            .unwrap();
+        let author = old_signer.author();
+        assert_eq!(
+            rotated.get_public_key(author),
+            Some(new_signer.public_key())
+        );
+        // The verifier itself only accepts the latest key.
+        assert_eq!(
+            rotated.verify_signature(author, random_hash, &old_signature),
+            Err(VerifyError::InvalidSignature)
+        );
+        assert_eq!(
+            rotated.verify_signature(author, random_hash, &new_signature),
+            Ok(())
+        );
+        // The new key is rejected before the handover round.
+        let before_handover = rotated.at_round(9);
+        assert_eq!(
+            before_handover.get_public_key(author),
+            Some(old_signer.public_key())
+        );
+        assert_eq!(
+            before_handover.verify_signature(author, random_hash, &old_signature),
+            Ok(())
+        );
+        assert_eq!(
+            before_handover.verify_signature(author, random_hash, &new_signature),
+            Err(VerifyError::InvalidSignature)
+        );
+        // Both keys are accepted until the retirement round.
+        let mut signatures = HashMap::new();
+        signatures.insert(author, old_signature.clone());
+        for round in 10..13 {
+            let after_handover = rotated.at_round(round);
+            assert_eq!(
+                after_handover.verify_signature(author, random_hash, &old_signature),
+                Ok(())
+            );
+            assert_eq!(
+                after_handover.verify_signature(author, random_hash, &new_signature),
+                Ok(())
+            );
+            assert_eq!(
+                after_handover.batch_verify(random_hash, &signatures),
+                Ok(())
+            );
+        }
+        // The replaced key is rejected from the retirement round on.
+        let retired = rotated.at_round(13);
+        assert_eq!(
+            retired.verify_signature(author, random_hash, &old_signature),
+            Err(VerifyError::InvalidSignature)
+        );
+        assert_eq!(
+            retired.batch_verify(random_hash, &signatures),
+            Err(VerifyError::InvalidSignature)
+        );
+
+        // The keys of the successive rotations are handed over in order.
+        let newest_signer = ValidatorSigner::new(author, compat::generate_keypair(None).0);
+        let newest_signature = newest_signer.sign_message(random_hash).unwrap();
+        assert!(rotated
+            .with_rotated_key(author, newest_signer.public_key(), 10, 13)
+            .is_err());
+        let rotated_twice = rotated
+            .with_rotated_key(author, newest_signer.public_key(), 20, 23)
+            .unwrap();
+        assert_eq!(
+            rotated_twice
+                .at_round(9)
+                .verify_signature(author, random_hash, &old_signature),
+            Ok(())
+        );
+        assert_eq!(
+            rotated_twice
+                .at_round(15)
+                .verify_signature(author, random_hash, &new_signature),
+            Ok(())
+        );
+        assert_eq!(
+            rotated_twice
+                .at_round(15)
+                .verify_signature(author, random_hash, &newest_signature),
+            Err(VerifyError::InvalidSignature)
+        );
+        assert_eq!(
+            rotated_twice
+                .at_round(20)
+                .verify_signature(author, random_hash, &newest_signature),
+            Ok(())
+        );
+
+        // The rotation of an unknown author or retiring the key before the handover fails.
+        let unknown_signer = ValidatorSigner::<Ed25519PrivateKey>::random([2; 32]);
+        assert!(rotated
+            .with_rotated_key(unknown_signer.author(), unknown_signer.public_key(), 20, 23)
+            .is_err());
+        assert!(validator
+            .with_rotated_key(author, new_signer.public_key(), 10, 9)
+            .is_err());
+    }
+
+    #[test]
+    fn test_voting_power() {
+        let signers: Vec<ValidatorSigner<Ed25519PrivateKey>> =
+            (0..3).map(|i| ValidatorSigner::random([i; 32])).collect();
+        let author_to_public_key_map: HashMap<AccountAddress, Ed25519PublicKey> = signers
+            .iter()
+            .map(|signer| (signer.author(), signer.public_key()))
+            .collect();
+        let mut author_to_voting_power = HashMap::new();
+        author_to_voting_power.insert(signers[0].author(), 10);
+        let validator_verifier = ValidatorVerifier::new_with_voting_power(
+            author_to_public_key_map.clone(),
+            author_to_voting_power.clone(),
+        )
+        .unwrap();
+        assert_eq!(validator_verifier.quorum_size(), 3);
+        assert_eq!(
+            validator_verifier.get_voting_power(signers[0].author()),
+            Some(10)
+        );
+        assert_eq!(
+            validator_verifier.get_voting_power(signers[1].author()),
+            Some(DEFAULT_VOTING_POWER)
+        );
+        let unknown_author = ValidatorSigner::<Ed25519PrivateKey>::random([3; 32]).author();
+        assert_eq!(validator_verifier.get_voting_power(unknown_author), None);
+
+        author_to_voting_power.insert(signers[1].author(), 0);
+        assert!(ValidatorVerifier::new_with_voting_power(
+            author_to_public_key_map.clone(),
+            author_to_voting_power.clone()
+        )
+        .is_err());
+        author_to_voting_power.insert(signers[1].author(), 1);
+        author_to_voting_power.insert(unknown_author, 1);
+        assert!(ValidatorVerifier::new_with_voting_power(
+            author_to_public_key_map,
+            author_to_voting_power
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_with_validator_set() {
+        let signers: Vec<_> = (0..4)
+            .map(|i| ValidatorSigner::<Ed25519PrivateKey>::random([i; 32]))
+            .collect();
+        let previous_validator = ValidatorVerifier::new_with_voting_power(
+            signers
+                .iter()
+                .take(3)
+                .map(|signer| (signer.author(), signer.public_key()))
+                .collect(),
+            vec![(signers[0].author(), 5), (signers[1].author(), 3)]
+                .into_iter()
+                .collect(),
+        )
+        .unwrap();
+        // The first validator leaves, the last one joins.
+        let validator_set = ValidatorSet::new(
+            signers
+                .iter()
+                .skip(1)
+                .map(|signer| {
+                    ValidatorPublicKeys::new(
+                        signer.author(),
+                        signer.public_key(),
+                        compat::generate_keypair(None).1,
+                        x25519::compat::generate_keypair(None).1,
+                    )
+                })
+                .collect(),
+        );
+        let validator = previous_validator.with_validator_set(&validator_set);
+        assert_eq!(validator.len(), 3);
+        assert_eq!(validator.quorum_size(), 3);
+        for signer in signers.iter().skip(1) {
+            assert_eq!(
+                validator.get_public_key(signer.author()),
+                Some(signer.public_key())
+            );
+        }
+        assert_eq!(validator.get_public_key(signers[0].author()), None);
+        assert_eq!(validator.get_voting_power(signers[0].author()), None);
+        assert_eq!(validator.get_voting_power(signers[1].author()), Some(3));
+        assert_eq!(
+            validator.get_voting_power(signers[2].author()),
+            Some(DEFAULT_VOTING_POWER)
+        );
+        assert_eq!(
+            validator.get_voting_power(signers[3].author()),
+            Some(DEFAULT_VOTING_POWER)
+        );
+    }
 }